@@ -1,20 +1,35 @@
 #![allow(clippy::too_many_arguments)]
 
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
+use console::Alignment;
 use indicatif::{ProgressBar, ProgressStyle};
-use rcompare_common::{default_cache_dir, load_config, DiffStatus, Vfs};
-use rcompare_core::text_diff::{DiffChangeType, RegexRule, TextDiffConfig, WhitespaceMode};
-use rcompare_core::vfs::{SevenZVfs, TarVfs, ZipVfs};
+use rcompare_common::{
+    default_cache_dir, load_config, AppConfig, CaseCollision, DiffNode, DiffReason, DiffStatus,
+    FileEntry, ManifestHashAlgorithm, PathCollision, ProfileSettings, RCompareError,
+    SessionProfile, SimilarTo, SpecializedDiff, Vfs,
+};
+use rcompare_core::content_kind::SNIFF_LEN;
+use rcompare_core::text_diff::{
+    align_rows, AlignedLine, AlignedRow, DiffChangeType, RegexRule, TextDiffConfig,
+};
+use rcompare_core::vfs::{self, VfsCredentials};
 use rcompare_core::{
-    is_csv_file, is_excel_file, is_image_file, is_json_file, is_parquet_file, is_yaml_file,
-    ComparisonEngine, CsvDiffEngine, ExcelDiffEngine, FolderScanner, HashCache, ImageDiffEngine,
-    JsonDiffEngine, ParquetDiffEngine, TextDiffEngine,
+    add_ignored_nodes, compare_directories, compare_directory_pairs, decode_text, detect_engine,
+    detect_file_content_kind, execute_plan, expand_glob_pairs, find_case_collisions,
+    format_manifest, is_csv_file, is_excel_file, is_image_file, is_json_file, is_parquet_file,
+    is_yaml_file, read_text_file, route_engines, route_pair, summarize, CompareOptions,
+    ComparisonEngine, ComparisonMode, ComparisonSummary, CsvDiffEngine, EngineKind,
+    ExcelDiffEngine, FileOperations, FilteredScan, FolderScanner, HashCache, HashStats,
+    ImageDiffEngine, JsonDiffEngine, ParquetCompareMode, ParquetDiffEngine, PatchBuilder,
+    PatchSerializer, ScanSnapshot, SyncActionKind, SyncMode, SyncPlanner, TextDiffEngine,
+    TextDiffStats,
 };
 use serde::Serialize;
-use std::io::IsTerminal;
+use std::collections::{HashMap, HashSet};
+use std::io::{IsTerminal, Read, Write};
 use std::path::{Path, PathBuf};
-use std::time::{SystemTime, UNIX_EPOCH};
-use tracing::{error, info};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tracing::{error, info, warn};
 use tracing_subscriber::EnvFilter;
 
 #[derive(Parser)]
@@ -27,15 +42,22 @@ struct Cli {
     command: Commands,
 }
 
+// Parsed once per run, so the size of the `Scan` variant doesn't matter
+#[allow(clippy::large_enum_variant)]
 #[derive(Subcommand)]
 enum Commands {
     /// Scan and compare two directories
     Scan {
-        /// Left directory path
-        left: PathBuf,
+        /// Left directory path (defaults to the profile's)
+        left: Option<PathBuf>,
 
-        /// Right directory path
-        right: PathBuf,
+        /// Right directory path (defaults to the profile's)
+        right: Option<PathBuf>,
+
+        /// Load paths, ignore patterns and comparison settings from the saved
+        /// profile NAME; flags given on the command line add to them
+        #[arg(long, value_name = "NAME")]
+        profile: Option<String>,
 
         /// Ignore patterns (can be specified multiple times)
         #[arg(short, long)]
@@ -45,6 +67,15 @@ enum Commands {
         #[arg(short = 'L', long)]
         follow_symlinks: bool,
 
+        /// Skip dotfiles and hidden files
+        #[arg(long)]
+        exclude_hidden: bool,
+
+        /// List entries left out by hidden or ignore rules as ignored (--),
+        /// with the rule that matched, instead of dropping them
+        #[arg(long, conflicts_with = "incremental")]
+        show_ignored: bool,
+
         /// Verify file hashes for same-sized files
         #[arg(short = 'v', long)]
         verify_hashes: bool,
@@ -53,10 +84,24 @@ enum Commands {
         #[arg(long, conflicts_with = "verify_hashes")]
         no_verify_hashes: bool,
 
+        /// Read and hash every same-sized pair in full, ignoring mtimes and
+        /// cached hashes, to catch silent corruption; slow on large trees
+        #[arg(long, conflicts_with = "no_verify_hashes")]
+        force_content_check: bool,
+
         /// Cache directory for hash storage
         #[arg(short, long)]
         cache_dir: Option<PathBuf>,
 
+        /// Keep hashes in memory for this run only: the persistent hash
+        /// cache is neither read nor written
+        #[arg(long, conflicts_with_all = ["cache_dir", "incremental"])]
+        no_cache: bool,
+
+        /// Worker threads for scanning and hashing (default: one per core)
+        #[arg(long, value_name = "N", value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+        threads: Option<usize>,
+
         /// Show only differences (hide identical files)
         #[arg(short = 'd', long)]
         diff_only: bool,
@@ -89,7 +134,8 @@ enum Commands {
         #[arg(long)]
         no_color: bool,
 
-        /// Use columned diff-style output (side-by-side comparison)
+        /// Use columned diff-style output (side-by-side comparison);
+        /// with --text-diff, also show each differing text file side by side
         #[arg(long)]
         columns: bool,
 
@@ -117,12 +163,27 @@ enum Commands {
         #[arg(long)]
         parquet_diff: bool,
 
+        /// With --parquet-diff, also report codec, encoding and row-group
+        /// differences between Parquet files that hold the same data
+        #[arg(long)]
+        parquet_physical: bool,
+
         /// Enable text-specific comparison with line-by-line diff
         #[arg(long)]
         text_diff: bool,
 
+        /// Pick the specialized comparison for each differing file from its
+        /// content (magic bytes and extension) instead of the --*-diff flags
+        #[arg(long)]
+        auto: bool,
+
+        /// Summarize each differing text file as `+n -m` with a histogram
+        /// bar, like `git diff --stat`; implies --text-diff
+        #[arg(long)]
+        stat: bool,
+
         /// Ignore whitespace when comparing text files
-        /// Options: all, leading, trailing, changes
+        /// Options: all, leading, trailing, changes, tabs
         #[arg(long, value_name = "MODE")]
         ignore_whitespace: Option<String>,
 
@@ -131,10 +192,17 @@ enum Commands {
         ignore_case: bool,
 
         /// Apply regex rule to text before comparison (pattern:replacement)
-        /// Can be specified multiple times. Format: "pattern:replacement:description"
+        /// Can be specified multiple times; rules apply in the order given.
+        /// Format: "pattern:replacement:description", `$1`/`${name}` refer to
+        /// capture groups and `\:` is a literal colon
         #[arg(long, value_name = "RULE")]
         regex_rule: Vec<String>,
 
+        /// Ignore changed lines whose old and new text both match REGEX
+        /// (like `diff -I`); can be specified multiple times
+        #[arg(long, value_name = "REGEX")]
+        ignore_matching_lines: Vec<String>,
+
         /// Compare EXIF metadata when comparing images
         #[arg(long)]
         image_exif: bool,
@@ -142,13 +210,205 @@ enum Commands {
         /// Set pixel difference tolerance for image comparison (0-255)
         #[arg(long, value_name = "TOLERANCE", default_value = "1")]
         image_tolerance: u8,
+
+        /// Pair left-only and right-only files that are at least PERCENT similar
+        /// (near-duplicate detection, e.g. 90)
+        #[arg(long, value_name = "PERCENT")]
+        similarity: Option<f64>,
+
+        /// Compare only which paths exist on each side, ignoring size and content
+        #[arg(long)]
+        structure_only: bool,
+
+        /// Report only files that appeared or disappeared, without looking at
+        /// size, type or content. Local directories are still scanned with
+        /// their sizes and timestamps; archive and remote listings are used
+        /// as they are
+        #[arg(long, conflicts_with = "structure_only")]
+        presence_only: bool,
+
+        /// Flag files that are empty on one side but have content on the other
+        /// (a common sign of truncated copies)
+        #[arg(long)]
+        flag_emptied: bool,
+
+        /// Warn about every zero-byte file on either side
+        #[arg(long)]
+        warn_empty: bool,
+
+        /// Compare differing .gz, .bz2, .xz and .zst files by their
+        /// decompressed content, so recompressed copies count as the same
+        #[arg(long)]
+        decompress: bool,
+
+        /// Rewrite relative paths with a regex rule before pairing left and
+        /// right entries, e.g. '\.[0-9a-f]{8}\.js$:.js' to match hashed asset
+        /// names; same format as --regex-rule, repeatable, applied in order
+        #[arg(long, value_name = "RULE")]
+        path_rule: Vec<String>,
+
+        /// Print how long each phase took (scan per side, compare, hashing)
+        /// with bytes hashed and hash cache hits after the summary
+        #[arg(long)]
+        timing: bool,
+
+        /// Reuse directory listings from the previous scan of each side
+        /// (kept in the cache directory); only directories whose
+        /// modification time changed are read again, while the files of
+        /// reused listings are still stat-ed
+        #[arg(long)]
+        incremental: bool,
+
+        /// Write all text differences as one unified diff patch to FILE
+        /// (includes creation/deletion diffs for orphans)
+        #[arg(long, value_name = "FILE")]
+        patch: Option<PathBuf>,
+
+        /// Write progress and results to FILE as JSON Lines events
+        /// (scan_started, phase_done, entry_compared, summary); with several
+        /// pairs, each pair's events are written once all are compared
+        #[arg(long, value_name = "FILE")]
+        events: Option<PathBuf>,
+
+        /// Also compare directory LEFT against RIGHT in the same run
+        /// (repeatable); ends with a summary per pair. Per-file diffs and
+        /// alternative comparison modes need a single pair
+        #[arg(long, num_args = 2, value_names = ["LEFT", "RIGHT"])]
+        pair: Vec<PathBuf>,
+
+        /// Treat the left and right paths as glob patterns and compare each
+        /// pair of matching directories, e.g. 'projectA/*' 'projectB/*'
+        #[arg(long)]
+        glob: bool,
+    },
+
+    /// Compare two directories and synchronize them
+    Sync {
+        /// Left directory path
+        left: PathBuf,
+
+        /// Right directory path
+        right: PathBuf,
+
+        /// Sync direction and policy
+        #[arg(short, long, value_enum, default_value = "left-to-right")]
+        mode: SyncModeArg,
+
+        /// Ignore patterns (can be specified multiple times)
+        #[arg(short, long)]
+        ignore: Vec<String>,
+
+        /// Follow symbolic links
+        #[arg(short = 'L', long)]
+        follow_symlinks: bool,
+
+        /// Verify file hashes for same-sized files
+        #[arg(short = 'v', long)]
+        verify_hashes: bool,
+
+        /// Cache directory for hash storage
+        #[arg(short, long)]
+        cache_dir: Option<PathBuf>,
+
+        /// Report what would be copied or deleted without changing anything
+        #[arg(short = 'n', long)]
+        dry_run: bool,
+
+        /// Move files deleted in mirror mode to the trash
+        #[arg(long)]
+        trash: bool,
+    },
+
+    /// Write a checksum manifest of a directory or archive, one
+    /// `path<TAB>size<TAB>mtime<TAB>hash` line per file, sorted by path
+    Manifest {
+        /// Directory or archive to list
+        path: PathBuf,
+
+        /// Hash algorithm
+        #[arg(short, long, value_enum, default_value = "blake3")]
+        algorithm: ManifestAlgorithmArg,
+
+        /// Write the manifest to FILE instead of stdout
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+
+        /// Ignore patterns (can be specified multiple times)
+        #[arg(short, long)]
+        ignore: Vec<String>,
+
+        /// Follow symbolic links
+        #[arg(short = 'L', long)]
+        follow_symlinks: bool,
+
+        /// Cache directory for hash storage
+        #[arg(short, long)]
+        cache_dir: Option<PathBuf>,
+    },
+
+    /// Compare two files with the engine their content calls for; either
+    /// path may be `-` to read that side from stdin, as in
+    /// `cat a.txt | rcompare diff - b.txt`
+    Diff {
+        /// Left file, or `-` for stdin
+        left: PathBuf,
+
+        /// Right file, or `-` for stdin
+        right: PathBuf,
+
+        /// Treat stdin as a file with extension EXT (e.g. json, yaml, rs)
+        /// instead of guessing its kind from the content
+        #[arg(long = "as", visible_alias = "lang", value_name = "EXT")]
+        as_extension: Option<String>,
+
+        /// Show text differences side by side instead of as a unified diff
+        #[arg(long)]
+        columns: bool,
+
+        /// Disable ANSI colors in output
+        #[arg(long)]
+        no_color: bool,
     },
 }
 
-enum ArchiveKind {
-    Zip,
-    Tar,
-    SevenZ,
+#[derive(Clone, Copy, ValueEnum)]
+enum ManifestAlgorithmArg {
+    /// BLAKE3, verifiable by manifest comparison
+    Blake3,
+    /// SHA-256, for checking with standard tools
+    Sha256,
+}
+
+impl From<ManifestAlgorithmArg> for ManifestHashAlgorithm {
+    fn from(algorithm: ManifestAlgorithmArg) -> Self {
+        match algorithm {
+            ManifestAlgorithmArg::Blake3 => ManifestHashAlgorithm::Blake3,
+            ManifestAlgorithmArg::Sha256 => ManifestHashAlgorithm::Sha256,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum SyncModeArg {
+    /// Copy left-only and differing files to the right
+    LeftToRight,
+    /// Copy right-only and differing files to the left
+    RightToLeft,
+    /// Make the right an exact copy of the left, deleting right-only files
+    Mirror,
+    /// Copy missing files both ways; the newer side wins for differing files
+    UpdateNewer,
+}
+
+impl From<SyncModeArg> for SyncMode {
+    fn from(mode: SyncModeArg) -> Self {
+        match mode {
+            SyncModeArg::LeftToRight => SyncMode::LeftToRight,
+            SyncModeArg::RightToLeft => SyncMode::RightToLeft,
+            SyncModeArg::Mirror => SyncMode::Mirror,
+            SyncModeArg::UpdateNewer => SyncMode::UpdateNewer,
+        }
+    }
 }
 
 enum ScanSource {
@@ -187,11 +447,17 @@ fn main() {
         Commands::Scan {
             left,
             right,
-            ignore,
+            profile,
+            mut ignore,
             follow_symlinks,
+            exclude_hidden,
+            show_ignored,
             verify_hashes,
             no_verify_hashes,
+            force_content_check,
             cache_dir,
+            no_cache,
+            threads,
             diff_only,
             hide_identical,
             hide_different,
@@ -207,21 +473,184 @@ fn main() {
             json_diff,
             yaml_diff,
             parquet_diff,
+            parquet_physical,
             text_diff,
+            auto,
+            stat,
             ignore_whitespace,
             ignore_case,
             regex_rule,
+            ignore_matching_lines,
             image_exif,
             image_tolerance,
+            similarity,
+            structure_only,
+            presence_only,
+            flag_emptied,
+            warn_empty,
+            decompress,
+            path_rule,
+            timing,
+            incremental,
+            patch,
+            events,
+            pair,
+            glob,
         } => {
-            match run_scan(
-                left,
-                right,
+            let profile = match profile.as_deref().map(load_profile).transpose() {
+                Ok(profile) => profile,
+                Err(e) => {
+                    error!("Scan failed: {}", e);
+                    std::process::exit(1);
+                }
+            };
+            let settings = profile.as_ref().map(|p| &p.settings);
+            let runs = |diff| settings.is_some_and(|s| s.runs(diff));
+            let left = left.or_else(|| profile.as_ref().map(|p| p.left_path.clone()));
+            let right = right.or_else(|| profile.as_ref().map(|p| p.right_path.clone()));
+            let mut pairs = Vec::new();
+            match (left, right) {
+                (Some(left), Some(right)) if glob => {
+                    let (Some(left), Some(right)) = (left.to_str(), right.to_str()) else {
+                        error!("Scan failed: glob patterns must be valid UTF-8");
+                        std::process::exit(1);
+                    };
+                    match expand_glob_pairs(left, right) {
+                        Ok(expanded) => pairs.extend(expanded),
+                        Err(e) => {
+                            error!("Scan failed: {}", e);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                (Some(left), Some(right)) => pairs.push((left, right)),
+                _ if !pair.is_empty() => {}
+                _ => {
+                    error!(
+                        "Scan failed: left and right paths are required unless --profile or --pair is given"
+                    );
+                    std::process::exit(1);
+                }
+            }
+            pairs.extend(pair.chunks(2).map(|p| (p[0].clone(), p[1].clone())));
+            // Several pairs go through the library's multi-pair comparison,
+            // which has no per-file diffs or alternative comparison modes
+            let single_pair_flags = [
+                (show_ignored, "--show-ignored"),
+                (image_diff, "--image-diff"),
+                (csv_diff, "--csv-diff"),
+                (excel_diff, "--excel-diff"),
+                (json_diff, "--json-diff"),
+                (yaml_diff, "--yaml-diff"),
+                (parquet_diff, "--parquet-diff"),
+                (text_diff, "--text-diff"),
+                (auto, "--auto"),
+                (stat, "--stat"),
+                (similarity.is_some(), "--similarity"),
+                (structure_only, "--structure-only"),
+                (presence_only, "--presence-only"),
+                (flag_emptied, "--flag-emptied"),
+                (warn_empty, "--warn-empty"),
+                (decompress, "--decompress"),
+                (!path_rule.is_empty(), "--path-rule"),
+                (timing, "--timing"),
+                (incremental, "--incremental"),
+            ];
+            if pairs.len() > 1 {
+                if let Some((_, flag)) = single_pair_flags.iter().find(|(set, _)| *set) {
+                    error!("Scan failed: {} needs a single left/right pair", flag);
+                    std::process::exit(1);
+                }
+            }
+            if let Some(profile) = &profile {
+                ignore.extend(profile.ignore_patterns.iter().cloned());
+            }
+
+            let scan = |left: PathBuf, right: PathBuf| {
+                run_scan(
+                    left,
+                    right,
+                    ignore.clone(),
+                    settings,
+                    follow_symlinks,
+                    exclude_hidden,
+                    verify_hashes,
+                    no_verify_hashes,
+                    force_content_check,
+                    cache_dir.clone(),
+                    no_cache,
+                    threads,
+                    diff_only,
+                    hide_identical,
+                    hide_different,
+                    hide_left_only,
+                    hide_right_only,
+                    hide_unchecked,
+                    json,
+                    no_color,
+                    columns,
+                    image_diff || runs(SpecializedDiff::Image),
+                    csv_diff || runs(SpecializedDiff::Csv),
+                    excel_diff || runs(SpecializedDiff::Excel),
+                    json_diff || runs(SpecializedDiff::Json),
+                    yaml_diff || runs(SpecializedDiff::Yaml),
+                    parquet_diff || runs(SpecializedDiff::Parquet),
+                    parquet_physical,
+                    text_diff || runs(SpecializedDiff::Text),
+                    auto,
+                    stat,
+                    ignore_whitespace.clone().or_else(|| {
+                        settings
+                            .and_then(|s| s.whitespace_mode)
+                            .map(|mode| mode.name().to_string())
+                    }),
+                    ignore_case || settings.is_some_and(|s| s.ignore_case),
+                    regex_rule.clone(),
+                    ignore_matching_lines.clone(),
+                    image_exif,
+                    image_tolerance,
+                    similarity,
+                    structure_only,
+                    presence_only,
+                    flag_emptied,
+                    warn_empty,
+                    decompress,
+                    path_rule.clone(),
+                    timing,
+                    incremental,
+                    show_ignored,
+                    patch.clone(),
+                    events.clone(),
+                )
+            };
+
+            if let [(left, right)] = pairs.as_slice() {
+                match scan(left.clone(), right.clone()) {
+                    Ok(scan_result) => {
+                        // Exit with appropriate code based on scan results
+                        // 0: No differences found
+                        // 2: Differences found
+                        std::process::exit(scan_result.exit_code());
+                    }
+                    Err(e) => {
+                        error!("Scan failed: {}", e);
+                        std::process::exit(1);
+                    }
+                }
+            }
+
+            match run_pair_scan(
+                &pairs,
                 ignore,
+                settings,
                 follow_symlinks,
+                exclude_hidden,
                 verify_hashes,
                 no_verify_hashes,
+                force_content_check,
                 cache_dir,
+                no_cache,
+                threads,
                 diff_only,
                 hide_identical,
                 hide_different,
@@ -231,31 +660,374 @@ fn main() {
                 json,
                 no_color,
                 columns,
-                image_diff,
-                csv_diff,
-                excel_diff,
-                json_diff,
-                yaml_diff,
-                parquet_diff,
-                text_diff,
-                ignore_whitespace,
-                ignore_case,
-                regex_rule,
-                image_exif,
-                image_tolerance,
+                patch,
+                events,
             ) {
-                Ok(scan_result) => {
-                    // Exit with appropriate code based on scan results
-                    // 0: No differences found
-                    // 2: Differences found
-                    std::process::exit(scan_result.exit_code());
-                }
+                // 0: no differences, 2: differences, 1: a pair was skipped
+                Ok(code) => std::process::exit(code),
                 Err(e) => {
                     error!("Scan failed: {}", e);
                     std::process::exit(1);
                 }
             }
         }
+        Commands::Sync {
+            left,
+            right,
+            mode,
+            ignore,
+            follow_symlinks,
+            verify_hashes,
+            cache_dir,
+            dry_run,
+            trash,
+        } => {
+            match run_sync(
+                left,
+                right,
+                mode.into(),
+                ignore,
+                follow_symlinks,
+                verify_hashes,
+                cache_dir,
+                dry_run,
+                trash,
+            ) {
+                // 0: everything synced, 1: some operations failed
+                Ok(all_ok) => std::process::exit(if all_ok { 0 } else { 1 }),
+                Err(e) => {
+                    error!("Sync failed: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Commands::Manifest {
+            path,
+            algorithm,
+            output,
+            ignore,
+            follow_symlinks,
+            cache_dir,
+        } => {
+            if let Err(e) = run_manifest(
+                path,
+                algorithm.into(),
+                output,
+                ignore,
+                follow_symlinks,
+                cache_dir,
+            ) {
+                error!("Manifest failed: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Commands::Diff {
+            left,
+            right,
+            as_extension,
+            columns,
+            no_color,
+        } => match run_diff(&left, &right, as_extension.as_deref(), columns, no_color) {
+            // Same exit codes as `scan`: 0 identical, 2 different, 1 error
+            Ok(different) => std::process::exit(if different { 2 } else { 0 }),
+            Err(e) => {
+                error!("Diff failed: {}", e);
+                std::process::exit(1);
+            }
+        },
+    }
+}
+
+/// Look up the saved profile `name` in the config file
+fn load_profile(name: &str) -> Result<SessionProfile, Box<dyn std::error::Error>> {
+    let loaded = load_config(false)?;
+    loaded
+        .config
+        .profile(name)
+        .cloned()
+        .ok_or_else(|| format!("No saved profile named '{}'", name).into())
+}
+
+/// Compare two local directories and apply `mode`; returns whether every
+/// operation succeeded
+fn run_sync(
+    left: PathBuf,
+    right: PathBuf,
+    mode: SyncMode,
+    ignore_patterns: Vec<String>,
+    follow_symlinks: bool,
+    verify_hashes: bool,
+    cache_dir: Option<PathBuf>,
+    dry_run: bool,
+    use_trash: bool,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    let loaded = load_config(false)?;
+    let config = loaded.config;
+
+    let cache_path = match cache_dir.or(config.cache_dir) {
+        Some(path) => path,
+        None => default_cache_dir(loaded.portable, &loaded.path)?,
+    };
+
+    let mtime_tolerance = Duration::from_secs(config.mtime_tolerance_secs);
+    let options = CompareOptions::new()
+        .ignore_patterns(config.ignore_patterns)
+        .ignore_patterns(ignore_patterns)
+        .follow_symlinks(follow_symlinks || config.follow_symlinks)
+        .include_hidden(config.include_hidden)
+        .hash_verification(verify_hashes || config.use_hash_verification)
+        .mtime_tolerance(mtime_tolerance)
+        .cache_dir(cache_path);
+
+    info!("Syncing ({}):", mode.label());
+    info!("  Left:  {}", left.display());
+    info!("  Right: {}", right.display());
+
+    let diffs = compare_directories(&left, &right, &options)?;
+
+    let plan = SyncPlanner::new(&left, &right)
+        .with_mtime_tolerance(mtime_tolerance)
+        .plan(&diffs, mode);
+    let conflicts = plan
+        .iter()
+        .filter(|action| action.kind == SyncActionKind::Conflict)
+        .count();
+
+    if dry_run {
+        for action in &plan {
+            match action.kind {
+                SyncActionKind::Delete if action.is_dir => println!(
+                    "{:<9} {}/",
+                    action.kind.label(),
+                    action.relative_path.display()
+                ),
+                SyncActionKind::Conflict => println!(
+                    "{:<9} {}",
+                    action.kind.label(),
+                    action.relative_path.display()
+                ),
+                _ => println!(
+                    "{:<9} {} ({} bytes)",
+                    action.kind.label(),
+                    action.relative_path.display(),
+                    action.size
+                ),
+            }
+        }
+    }
+
+    let ops = FileOperations::new(dry_run, use_trash);
+    let summary = execute_plan(&plan, &ops);
+
+    for message in &summary.errors {
+        error!("{}", message);
+    }
+
+    if dry_run {
+        println!(
+            "Sync preview ({}): {} files would be copied, {} would be deleted, {} conflicts",
+            mode.label(),
+            summary.copied,
+            summary.deleted,
+            conflicts
+        );
+    } else {
+        println!(
+            "Sync complete ({}): {} files copied, {} deleted, {} conflicts skipped, {} errors",
+            mode.label(),
+            summary.copied,
+            summary.deleted,
+            conflicts,
+            summary.errors.len()
+        );
+    }
+
+    Ok(summary.errors.is_empty())
+}
+
+/// Hash every file under `path` and write the manifest to `output` or stdout
+fn run_manifest(
+    path: PathBuf,
+    algorithm: ManifestHashAlgorithm,
+    output: Option<PathBuf>,
+    ignore_patterns: Vec<String>,
+    follow_symlinks: bool,
+    cache_dir: Option<PathBuf>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let loaded = load_config(false)?;
+    let mut config = loaded.config;
+    config.ignore_patterns.extend(ignore_patterns);
+    config.follow_symlinks |= follow_symlinks;
+
+    let cache_path = match cache_dir.or(config.cache_dir.clone()) {
+        Some(path) => path,
+        None => default_cache_dir(loaded.portable, &loaded.path)?,
+    };
+
+    let threads = config.threads;
+    let mut scanner = FolderScanner::try_new(config)?;
+    if path.is_dir() {
+        let _ = scanner.load_gitignore(&path);
+    }
+    let source = build_scan_source(&path)?;
+    let entries = scan_source(&scanner, &source, None, false)?.entries;
+    info!("Hashing {} entries ({})", entries.len(), algorithm.name());
+
+    let mut engine = ComparisonEngine::new(HashCache::open_or_in_memory(cache_path));
+    if let Some(threads) = threads {
+        engine = engine.with_threads(threads);
+    }
+    let records = engine.generate_manifest(source.root(), entries, source.vfs(), algorithm)?;
+    engine.persist_cache()?;
+    let manifest = format_manifest(&records)?;
+
+    match output {
+        Some(file) => std::fs::write(&file, manifest)?,
+        None => std::io::stdout().write_all(manifest.as_bytes())?,
+    }
+    Ok(())
+}
+
+/// One side of `rcompare diff`: a file, or stdin read into memory
+struct DiffInput {
+    /// The path as given, `-` for stdin
+    label: String,
+    bytes: Vec<u8>,
+    /// File name for extension hints; `None` for stdin without `--as`
+    hint: Option<PathBuf>,
+}
+
+impl DiffInput {
+    fn read(path: &Path, as_extension: Option<&str>) -> std::io::Result<Self> {
+        if path != Path::new("-") {
+            return Ok(Self {
+                label: path.display().to_string(),
+                bytes: std::fs::read(path)?,
+                hint: Some(path.to_path_buf()),
+            });
+        }
+        let mut bytes = Vec::new();
+        std::io::stdin().lock().read_to_end(&mut bytes)?;
+        Ok(Self {
+            label: "-".to_string(),
+            bytes,
+            hint: as_extension
+                .map(|ext| PathBuf::from("stdin").with_extension(ext.trim_start_matches('.'))),
+        })
+    }
+
+    fn engine(&self) -> EngineKind {
+        let sample = &self.bytes[..self.bytes.len().min(SNIFF_LEN)];
+        detect_engine(sample, self.hint.as_deref())
+    }
+
+    fn text(&self) -> Result<String, RCompareError> {
+        decode_text(&self.bytes, self.hint.as_deref())
+    }
+}
+
+/// Compare two files, either of which may be stdin, and print the
+/// differences; returns whether they differ
+///
+/// Text is shown as a unified diff (or side by side with `columns`), JSON
+/// and YAML as differing paths, and anything else only as whether the
+/// bytes differ.
+fn run_diff(
+    left: &Path,
+    right: &Path,
+    as_extension: Option<&str>,
+    columns: bool,
+    no_color: bool,
+) -> Result<bool, Box<dyn std::error::Error>> {
+    if left == Path::new("-") && right == Path::new("-") {
+        return Err("Only one side can be read from stdin".into());
+    }
+    let left = DiffInput::read(left, as_extension)?;
+    let right = DiffInput::read(right, as_extension)?;
+    let use_color = !no_color && std::io::stdout().is_terminal();
+    let paint = |code: &str, text: &str| {
+        if use_color {
+            format!("\x1b[{}m{}\x1b[0m", code, text)
+        } else {
+            text.to_string()
+        }
+    };
+
+    match route_engines(left.engine(), right.engine()) {
+        engine @ (EngineKind::Json | EngineKind::Yaml) => {
+            let json_engine = JsonDiffEngine::new();
+            let (left_text, right_text) = (left.text()?, right.text()?);
+            let result = if engine == EngineKind::Json {
+                json_engine.compare_json_str(&left_text, &right_text)?
+            } else {
+                json_engine.compare_yaml_str(&left_text, &right_text)?
+            };
+            for diff in &result.path_diffs {
+                let line = match diff.diff_type {
+                    rcompare_core::json_diff::PathDiffType::ValueDifferent => {
+                        format!("{}: {} -> {}", diff.path, diff.left_value, diff.right_value)
+                    }
+                    rcompare_core::json_diff::PathDiffType::TypeDifferent => format!(
+                        "{} (type mismatch): {} -> {}",
+                        diff.path, diff.left_value, diff.right_value
+                    ),
+                    rcompare_core::json_diff::PathDiffType::LeftOnly => {
+                        paint("31", &format!("- {}: {}", diff.path, diff.left_value))
+                    }
+                    rcompare_core::json_diff::PathDiffType::RightOnly => {
+                        paint("32", &format!("+ {}: {}", diff.path, diff.right_value))
+                    }
+                };
+                println!("{}", line);
+            }
+            Ok(result.different_paths + result.left_only_paths + result.right_only_paths > 0)
+        }
+        EngineKind::Text | EngineKind::Csv => {
+            let (left_text, right_text) = (left.text()?, right.text()?);
+            if columns {
+                let syntax_hint = left
+                    .hint
+                    .as_deref()
+                    .or(right.hint.as_deref())
+                    .unwrap_or(Path::new("-"));
+                let lines =
+                    TextDiffEngine::new().compare_text(&left_text, &right_text, syntax_hint)?;
+                for row in render_side_by_side(&align_rows(&lines), terminal_width(), use_color) {
+                    println!("{}", row);
+                }
+                return Ok(lines
+                    .iter()
+                    .any(|line| line.change_type != DiffChangeType::Equal));
+            }
+
+            let patch =
+                PatchBuilder::file_patch(&left.label, &right.label, &left_text, &right_text);
+            if patch.hunks.is_empty() {
+                return Ok(false);
+            }
+            for line in PatchSerializer::serialize_file_patch(&patch).lines() {
+                let colored = if line.starts_with("---") || line.starts_with("+++") {
+                    paint("1", line)
+                } else if line.starts_with("@@") {
+                    paint("36", line)
+                } else if line.starts_with('-') {
+                    paint("31", line)
+                } else if line.starts_with('+') {
+                    paint("32", line)
+                } else {
+                    line.to_string()
+                };
+                println!("{}", colored);
+            }
+            Ok(true)
+        }
+        _ => {
+            let different = left.bytes != right.bytes;
+            if different {
+                println!("Binary files {} and {} differ", left.label, right.label);
+            }
+            Ok(different)
+        }
     }
 }
 
@@ -264,76 +1036,151 @@ fn build_text_diff_config(
     ignore_whitespace: Option<String>,
     ignore_case: bool,
     regex_rules: Vec<String>,
+    ignore_matching_lines: Vec<String>,
 ) -> Result<TextDiffConfig, Box<dyn std::error::Error>> {
-    let mut config = TextDiffConfig::new();
-
-    // Parse whitespace mode
-    if let Some(mode) = ignore_whitespace {
-        config.whitespace_mode = match mode.to_lowercase().as_str() {
-            "all" => WhitespaceMode::IgnoreAll,
-            "leading" => WhitespaceMode::IgnoreLeading,
-            "trailing" => WhitespaceMode::IgnoreTrailing,
-            "changes" => WhitespaceMode::IgnoreChanges,
-            _ => {
-                return Err(format!(
-                    "Invalid whitespace mode '{}'. Valid options: all, leading, trailing, changes",
-                    mode
-                )
-                .into())
-            }
-        };
+    Ok(TextDiffConfig::from_cli_args(
+        ignore_whitespace.as_deref(),
+        ignore_case,
+        &regex_rules,
+        &ignore_matching_lines,
+    )?)
+}
+
+/// Total compressed size of the files on one side, if its backend reports it
+/// for every one of them
+fn compressed_total<'a>(
+    source: &ScanSource,
+    entries: impl Iterator<Item = &'a FileEntry>,
+) -> Option<u64> {
+    let vfs = source.vfs()?;
+    let mut total = None;
+    for entry in entries.filter(|e| !e.is_dir) {
+        let info = vfs
+            .compression_info(&source.root().join(&entry.path))
+            .ok()??;
+        *total.get_or_insert(0) += info.compressed_size;
     }
+    total
+}
 
-    // Set case sensitivity
-    config.ignore_case = ignore_case;
+/// One line per collision: each spelling with the sides it appears on
+fn describe_case_collision(collision: &CaseCollision) -> String {
+    let spellings: Vec<String> = collision
+        .paths
+        .iter()
+        .map(|path| {
+            let sides: Vec<&str> = [("left", &collision.left), ("right", &collision.right)]
+                .into_iter()
+                .filter(|(_, paths)| paths.contains(path))
+                .map(|(side, _)| side)
+                .collect();
+            format!("{} ({})", path.display(), sides.join(", "))
+        })
+        .collect();
+    let note = if collision.within_one_side() {
+        " - clashes on case-insensitive filesystems"
+    } else {
+        ""
+    };
+    format!("{}{}", spellings.join(", "), note)
+}
 
-    // Parse regex rules
-    for rule_str in regex_rules {
-        let parts: Vec<&str> = rule_str.splitn(3, ':').collect();
-        if parts.len() < 2 {
-            return Err(format!(
-                "Invalid regex rule format '{}'. Expected 'pattern:replacement:description'",
-                rule_str
-            )
-            .into());
-        }
+/// "app.js <- left: a.1.js, a.2.js; right: a.3.js" style line for a path rule
+/// collision
+fn describe_path_collision(collision: &PathCollision) -> String {
+    let sides: Vec<String> = [("left", &collision.left), ("right", &collision.right)]
+        .into_iter()
+        .filter(|(_, paths)| !paths.is_empty())
+        .map(|(side, paths)| {
+            let paths: Vec<String> = paths.iter().map(|p| p.display().to_string()).collect();
+            format!("{}: {}", side, paths.join(", "))
+        })
+        .collect();
+    format!(
+        "{} <- {} (not paired)",
+        collision.transformed.display(),
+        sides.join("; ")
+    )
+}
 
-        let pattern = regex::Regex::new(parts[0])?;
-        let replacement = parts[1].to_string();
-        let description = parts.get(2).unwrap_or(&"").to_string();
+/// Apply `--path-rule` rewrites in order to a relative path, with `/` as the
+/// separator on every platform
+fn rewrite_path(rules: &[RegexRule], path: &Path) -> PathBuf {
+    let mut text = path.to_string_lossy().replace('\\', "/");
+    for rule in rules {
+        text = rule.apply(&text).into_owned();
+    }
+    PathBuf::from(text)
+}
 
-        config.regex_rules.push(RegexRule {
-            pattern,
-            replacement,
-            description,
-        });
+/// "right is 30% larger" style comparison of two byte counts
+fn describe_size_change(left: u64, right: u64) -> String {
+    if left == right {
+        return "same size".to_string();
+    }
+    if left == 0 {
+        return "left is empty".to_string();
+    }
+    let percent = (right as f64 - left as f64) / left as f64 * 100.0;
+    if percent >= 0.0 {
+        format!("right is {:.0}% larger", percent)
+    } else {
+        format!("right is {:.0}% smaller", -percent)
     }
+}
+
+/// Where the time of a scan went, for `--timing`
+#[derive(Debug, Default)]
+struct PhaseTimings {
+    scan_left: Duration,
+    left_entries: usize,
+    scan_right: Duration,
+    right_entries: usize,
+    /// Wall-clock time of the comparison, hashing included
+    compare: Duration,
+    hashing: HashStats,
+}
 
-    Ok(config)
+impl PhaseTimings {
+    /// The `Timing:` block printed after the summary
+    fn lines(&self) -> Vec<String> {
+        let secs = |d: Duration| format!("{:.3}s", d.as_secs_f64());
+        vec![
+            "Timing:".to_string(),
+            format!(
+                "  Scan left:       {} ({} entries)",
+                secs(self.scan_left),
+                self.left_entries
+            ),
+            format!(
+                "  Scan right:      {} ({} entries)",
+                secs(self.scan_right),
+                self.right_entries
+            ),
+            format!("  Compare:         {}", secs(self.compare)),
+            format!(
+                "  Hashing:         {} across threads, {} bytes read",
+                secs(self.hashing.hash_time),
+                self.hashing.bytes_hashed
+            ),
+            format!(
+                "  Hash cache:      {} hits, {} misses",
+                self.hashing.cache_hits, self.hashing.cache_misses
+            ),
+        ]
+    }
 }
 
 /// Result of a scan operation with diff statistics
 #[derive(Debug)]
 struct ScanResult {
-    /// Total number of entries compared
-    #[allow(dead_code)]
-    total: usize,
-    /// Number of identical files/directories
-    identical: usize,
-    /// Number of different files
-    different: usize,
-    /// Number of files only in left
-    left_only: usize,
-    /// Number of files only in right
-    right_only: usize,
-    /// Number of unchecked files (same size, different timestamp)
-    unchecked: usize,
+    summary: ComparisonSummary,
 }
 
 impl ScanResult {
     /// Returns true if any differences were found
     fn has_differences(&self) -> bool {
-        self.different > 0 || self.left_only > 0 || self.right_only > 0 || self.unchecked > 0
+        self.summary.has_differences()
     }
 
     /// Get appropriate exit code based on scan results
@@ -352,10 +1199,15 @@ fn run_scan(
     left: PathBuf,
     right: PathBuf,
     ignore_patterns: Vec<String>,
+    profile: Option<&ProfileSettings>,
     follow_symlinks: bool,
+    exclude_hidden: bool,
     verify_hashes: bool,
     no_verify_hashes: bool,
+    force_content_check: bool,
     cache_dir: Option<PathBuf>,
+    no_cache: bool,
+    threads: Option<usize>,
     diff_only: bool,
     hide_identical: bool,
     hide_different: bool,
@@ -371,12 +1223,28 @@ fn run_scan(
     json_diff: bool,
     yaml_diff: bool,
     parquet_diff: bool,
+    parquet_physical: bool,
     text_diff: bool,
+    auto: bool,
+    stat: bool,
     ignore_whitespace: Option<String>,
     ignore_case: bool,
     regex_rules: Vec<String>,
+    ignore_matching_lines: Vec<String>,
     image_exif: bool,
     image_tolerance: u8,
+    similarity: Option<f64>,
+    structure_only: bool,
+    presence_only: bool,
+    flag_emptied: bool,
+    warn_empty: bool,
+    decompress: bool,
+    path_rules: Vec<String>,
+    timing: bool,
+    incremental: bool,
+    show_ignored: bool,
+    patch: Option<PathBuf>,
+    events: Option<PathBuf>,
 ) -> Result<ScanResult, Box<dyn std::error::Error>> {
     // Validate paths
     if !left.exists() {
@@ -390,15 +1258,30 @@ fn run_scan(
     info!("  Left:  {}", left.display());
     info!("  Right: {}", right.display());
 
+    let mut event_log = events.as_deref().map(EventLog::create).transpose()?;
+    if let Some(log) = &mut event_log {
+        log.emit(&ScanEvent::ScanStarted {
+            left: left.to_string_lossy().to_string(),
+            right: right.to_string_lossy().to_string(),
+        })?;
+    }
+
     let loaded = load_config(false)?;
     let mut config = loaded.config;
-
-    if !ignore_patterns.is_empty() {
-        config.ignore_patterns.extend(ignore_patterns);
-    }
-    if follow_symlinks {
-        config.follow_symlinks = true;
+    // Profile settings apply to this run only; flags given on the command
+    // line still take precedence
+    if let Some(settings) = profile {
+        settings.apply_to(&mut config);
     }
+    apply_scan_overrides(
+        &mut config,
+        ignore_patterns,
+        follow_symlinks,
+        exclude_hidden,
+        cache_dir,
+        threads,
+    );
+
     let verify_hashes = if verify_hashes {
         true
     } else if no_verify_hashes {
@@ -407,9 +1290,10 @@ fn run_scan(
         config.use_hash_verification
     };
     config.use_hash_verification = verify_hashes;
-    if let Some(cache_dir) = cache_dir.clone() {
-        config.cache_dir = Some(cache_dir);
-    }
+
+    let mtime_tolerance = Duration::from_secs(config.mtime_tolerance_secs);
+    // From --threads or the config file; used for both scanning and hashing
+    let threads = config.threads;
 
     // Determine cache directory
     let cache_path = match config.cache_dir.clone() {
@@ -417,17 +1301,20 @@ fn run_scan(
         None => default_cache_dir(loaded.portable, &loaded.path)?,
     };
 
-    info!("Using cache directory: {}", cache_path.display());
-
-    // Initialize hash cache
-    let hash_cache = HashCache::new(cache_path)?;
+    let snapshot_dir = incremental.then(|| cache_path.clone());
+    let hash_cache = scan_hash_cache(no_cache, cache_path);
 
     // Build text diff configuration from CLI flags
-    let text_config = build_text_diff_config(ignore_whitespace, ignore_case, regex_rules)?;
+    let text_config = build_text_diff_config(
+        ignore_whitespace,
+        ignore_case,
+        regex_rules,
+        ignore_matching_lines,
+    )?;
 
-    // Create scanner
-    let mut left_scanner = FolderScanner::new(config.clone());
-    let mut right_scanner = FolderScanner::new(config);
+    // Create scanners; unreadable subdirectories are reported and skipped
+    let mut left_scanner = FolderScanner::try_new(config.clone())?;
+    let mut right_scanner = FolderScanner::try_new(config)?;
 
     // Load .gitignore if present (left side only)
     if left.is_dir() {
@@ -441,16 +1328,6 @@ fn run_scan(
     let left_source = build_scan_source(&left)?;
     let right_source = build_scan_source(&right)?;
 
-    // Auto-enable hash verification for archive comparisons
-    // Archives don't preserve timestamps reliably, so we need hash verification
-    let has_archive = matches!(left_source, ScanSource::Vfs { .. })
-        || matches!(right_source, ScanSource::Vfs { .. });
-    let verify_hashes = if has_archive && !no_verify_hashes {
-        true // Force hash verification for archives unless explicitly disabled
-    } else {
-        verify_hashes
-    };
-
     // Create progress spinner for scanning (only if not JSON output and stderr is terminal)
     let show_progress = !json && std::io::stderr().is_terminal();
 
@@ -469,7 +1346,17 @@ fn run_scan(
         None
     };
 
-    let left_entries = scan_source(&left_scanner, &left_source)?;
+    let started = Instant::now();
+    let FilteredScan {
+        entries: left_entries,
+        ignored: left_ignored,
+    } = scan_source(
+        &left_scanner,
+        &left_source,
+        snapshot_dir.as_deref(),
+        show_ignored,
+    )?;
+    let scan_left_time = started.elapsed();
 
     if let Some(pb) = &pb_left {
         pb.finish_with_message(format!(
@@ -479,6 +1366,12 @@ fn run_scan(
     } else {
         info!("Found {} entries in left source", left_entries.len());
     }
+    if let Some(log) = &mut event_log {
+        log.emit(&ScanEvent::PhaseDone {
+            phase: "scan_left",
+            count: left_entries.len(),
+        })?;
+    }
 
     let pb_right = if show_progress {
         let pb = ProgressBar::new_spinner();
@@ -495,7 +1388,17 @@ fn run_scan(
         None
     };
 
-    let right_entries = scan_source(&right_scanner, &right_source)?;
+    let started = Instant::now();
+    let FilteredScan {
+        entries: right_entries,
+        ignored: right_ignored,
+    } = scan_source(
+        &right_scanner,
+        &right_source,
+        snapshot_dir.as_deref(),
+        show_ignored,
+    )?;
+    let scan_right_time = started.elapsed();
 
     if let Some(pb) = &pb_right {
         pb.finish_with_message(format!(
@@ -505,6 +1408,12 @@ fn run_scan(
     } else {
         info!("Found {} entries in right source", right_entries.len());
     }
+    if let Some(log) = &mut event_log {
+        log.emit(&ScanEvent::PhaseDone {
+            phase: "scan_right",
+            count: right_entries.len(),
+        })?;
+    }
 
     // Compare directories
     // Calculate total items to compare
@@ -538,33 +1447,86 @@ fn run_scan(
         None
     };
 
-    let comparison_engine = ComparisonEngine::new(hash_cache).with_hash_verification(verify_hashes);
+    // Archive sides are compared by content unless hashing is explicitly
+    // disabled, since archives don't preserve timestamps reliably
+    let mut comparison_engine = ComparisonEngine::new(hash_cache)
+        .with_hash_verification(verify_hashes)
+        .with_vfs_content_verification(!no_verify_hashes)
+        .with_force_content_check(force_content_check)
+        .with_mtime_tolerance(mtime_tolerance)
+        .with_emptied_file_detection(flag_emptied)
+        .with_empty_file_warnings(warn_empty)
+        .with_decompressed_comparison(decompress);
+    if let Some(threads) = threads {
+        comparison_engine = comparison_engine.with_threads(threads);
+    }
+    if let Some(threshold) = similarity {
+        comparison_engine = comparison_engine.with_similarity_detection(threshold);
+    }
+    if structure_only {
+        comparison_engine = comparison_engine.with_mode(ComparisonMode::StructureOnly);
+    }
+    if presence_only {
+        comparison_engine = comparison_engine.with_mode(ComparisonMode::PresenceOnly);
+    }
+    if !path_rules.is_empty() {
+        let rules = path_rules
+            .iter()
+            .map(|rule| RegexRule::parse(rule))
+            .collect::<Result<Vec<_>, _>>()?;
+        comparison_engine =
+            comparison_engine.with_path_transform(move |path| rewrite_path(&rules, path));
+    }
 
-    // Use progress callback if progress bar is enabled
-    let diff_nodes = if let Some(ref pb) = pb_compare {
-        let pb_clone = pb.clone();
-        comparison_engine.compare_with_vfs_and_progress(
-            left_source.root(),
-            right_source.root(),
-            left_entries,
-            right_entries,
-            left_source.vfs(),
-            right_source.vfs(),
-            None,
-            Some(move |current, _total| {
-                pb_clone.set_position(current as u64);
-            }),
-        )?
-    } else {
-        comparison_engine.compare_with_vfs(
-            left_source.root(),
-            right_source.root(),
-            left_entries,
-            right_entries,
-            left_source.vfs(),
-            right_source.vfs(),
-        )?
-    };
+    let mut timings = timing.then(|| PhaseTimings {
+        scan_left: scan_left_time,
+        left_entries: left_entries.len(),
+        scan_right: scan_right_time,
+        right_entries: right_entries.len(),
+        ..PhaseTimings::default()
+    });
+    let started = Instant::now();
+
+    // Nodes are streamed so progress and entry_compared events keep up
+    // with the comparison instead of arriving after it. Ignored paths are
+    // reported once their nodes are merged in below.
+    let ignored_paths: HashSet<PathBuf> = left_ignored
+        .iter()
+        .chain(&right_ignored)
+        .map(|ignored| ignored.entry.path.clone())
+        .collect();
+    let mut diff_nodes = Vec::new();
+    comparison_engine.compare_each(
+        left_source.root(),
+        right_source.root(),
+        left_entries,
+        right_entries,
+        left_source.vfs(),
+        right_source.vfs(),
+        None,
+        |node| {
+            if let Some(pb) = &pb_compare {
+                pb.inc(1);
+            }
+            if let Some(log) = &mut event_log {
+                if !ignored_paths.contains(&node.relative_path) {
+                    log.emit(&ScanEvent::EntryCompared {
+                        path: node.relative_path.to_string_lossy().to_string(),
+                        status: node.status,
+                    })?;
+                }
+            }
+            diff_nodes.push(node);
+            Ok(())
+        },
+    )?;
+    comparison_engine.pair_near_duplicates(
+        &mut diff_nodes,
+        left_source.root(),
+        right_source.root(),
+        left_source.vfs(),
+        right_source.vfs(),
+    );
 
     if let Some(pb) = &pb_compare {
         pb.finish_with_message(format!(
@@ -573,8 +1535,59 @@ fn run_scan(
         ));
     }
 
+    if let Some(timings) = &mut timings {
+        timings.compare = started.elapsed();
+        timings.hashing = comparison_engine.hash_stats();
+    }
+
+    add_ignored_nodes(&mut diff_nodes, left_ignored, right_ignored);
+
     comparison_engine.persist_cache()?;
 
+    if let Some(log) = &mut event_log {
+        for node in diff_nodes
+            .iter()
+            .filter(|node| node.status == DiffStatus::Ignored)
+        {
+            log.emit(&ScanEvent::EntryCompared {
+                path: node.relative_path.to_string_lossy().to_string(),
+                status: node.status,
+            })?;
+        }
+        log.emit(&ScanEvent::PhaseDone {
+            phase: "compare",
+            count: diff_nodes.len(),
+        })?;
+    }
+
+    if let Some(patch_path) = &patch {
+        let patch_text = PatchBuilder::tree_patch(
+            left_source.root(),
+            right_source.root(),
+            &diff_nodes,
+            left_source.vfs(),
+            right_source.vfs(),
+        )?;
+        std::fs::write(patch_path, patch_text)?;
+        info!("Wrote patch to {}", patch_path.display());
+    }
+
+    // With --auto each differing file goes to the engine its content calls
+    // for, and every engine that received a file runs
+    let routes = auto.then(|| route_diff_nodes(&diff_nodes, &left_source, &right_source));
+    let routed = |engine: EngineKind| {
+        routes
+            .as_ref()
+            .is_some_and(|routes| routes.values().any(|&e| e == engine))
+    };
+    let image_diff = image_diff || routed(EngineKind::Image);
+    let csv_diff = csv_diff || routed(EngineKind::Csv);
+    let excel_diff = excel_diff || routed(EngineKind::Excel);
+    let json_diff = json_diff || routed(EngineKind::Json);
+    let yaml_diff = yaml_diff || routed(EngineKind::Yaml);
+    let parquet_diff = parquet_diff || routed(EngineKind::Parquet);
+    let text_diff = text_diff || stat || routed(EngineKind::Text);
+
     // Initialize optional result collectors for JSON mode
     let mut json_text_diffs = if json && text_diff {
         Some(Vec::new())
@@ -615,184 +1628,60 @@ fn run_scan(
     // Display results (text mode only)
     let use_color = !json && !no_color && std::io::stdout().is_terminal();
 
-    if !json {
-        let mut same_count = 0;
-        let mut different_count = 0;
-        let mut orphan_left_count = 0;
-        let mut orphan_right_count = 0;
-        let mut unchecked_count = 0;
-
-        if columns {
-            // Columned output format (side-by-side)
-            println!("\n{}", "=".repeat(120));
-            println!("Comparison Results (Side-by-Side)");
-            println!("{}", "=".repeat(120));
-            println!("{:<50} {:^8} {:<50}", "Left", "Status", "Right");
-            println!("{}", "-".repeat(120));
-
-            for node in &diff_nodes {
-                match node.status {
-                    DiffStatus::Same => same_count += 1,
-                    DiffStatus::Different => different_count += 1,
-                    DiffStatus::OrphanLeft => orphan_left_count += 1,
-                    DiffStatus::OrphanRight => orphan_right_count += 1,
-                    DiffStatus::Unchecked => unchecked_count += 1,
-                }
-
-                // Check if entry should be shown based on filters
-                if !should_show_entry(
-                    &node.status,
-                    diff_only,
-                    hide_identical,
-                    hide_different,
-                    hide_left_only,
-                    hide_right_only,
-                    hide_unchecked,
-                ) {
-                    continue;
-                }
-
-                let status_symbol = match node.status {
-                    DiffStatus::Same => "==",
-                    DiffStatus::Different => "!=",
-                    DiffStatus::OrphanLeft => "<<",
-                    DiffStatus::OrphanRight => ">>",
-                    DiffStatus::Unchecked => "??",
-                };
-
-                let (status_color, reset) = if use_color {
-                    (
-                        match node.status {
-                            DiffStatus::Same => "\x1b[32m",        // Green
-                            DiffStatus::Different => "\x1b[31m",   // Red
-                            DiffStatus::OrphanLeft => "\x1b[33m",  // Yellow
-                            DiffStatus::OrphanRight => "\x1b[34m", // Blue
-                            DiffStatus::Unchecked => "\x1b[36m",   // Cyan
-                        },
-                        "\x1b[0m",
-                    )
-                } else {
-                    ("", "")
-                };
-
-                let left_text = if node.left.is_some() {
-                    format!("{}", node.relative_path.display())
-                } else {
-                    String::from("(missing)")
-                };
+    let summary = summarize(&diff_nodes);
 
-                let right_text = if node.right.is_some() {
-                    format!("{}", node.relative_path.display())
-                } else {
-                    String::from("(missing)")
-                };
+    if !json {
+        print_entries(&diff_nodes, columns, use_color, |status| {
+            should_show_entry(
+                status,
+                diff_only,
+                hide_identical,
+                hide_different,
+                hide_left_only,
+                hide_right_only,
+                hide_unchecked,
+            )
+        });
 
-                println!(
-                    "{:<50} {}{:^8}{} {:<50}",
-                    truncate_path(&left_text, 50),
-                    status_color,
-                    status_symbol,
-                    reset,
-                    truncate_path(&right_text, 50)
-                );
+        println!("\n{}", "=".repeat(80));
+        print_summary_counts(&summary, use_color);
+        let compared = || {
+            diff_nodes
+                .iter()
+                .filter(|n| n.status != DiffStatus::Ignored)
+        };
+        let left_compressed =
+            compressed_total(&left_source, compared().filter_map(|n| n.left.as_ref()));
+        let right_compressed =
+            compressed_total(&right_source, compared().filter_map(|n| n.right.as_ref()));
+        if let (Some(left), Some(right)) = (left_compressed, right_compressed) {
+            println!(
+                "  Compressed:      {} bytes left, {} bytes right ({})",
+                left,
+                right,
+                describe_size_change(left, right)
+            );
+        }
+        let collisions = find_case_collisions(&diff_nodes);
+        if !collisions.is_empty() {
+            println!("  Case collisions: {}", collisions.len());
+            for collision in &collisions {
+                println!("    {}", describe_case_collision(collision));
             }
-            println!("{}", "=".repeat(120));
-        } else {
-            // Standard output format
-            println!("\n{}", "=".repeat(80));
-            println!("Comparison Results");
-            println!("{}", "=".repeat(80));
-
-            for node in &diff_nodes {
-                match node.status {
-                    DiffStatus::Same => same_count += 1,
-                    DiffStatus::Different => different_count += 1,
-                    DiffStatus::OrphanLeft => orphan_left_count += 1,
-                    DiffStatus::OrphanRight => orphan_right_count += 1,
-                    DiffStatus::Unchecked => unchecked_count += 1,
-                }
-
-                // Check if entry should be shown based on filters
-                if !should_show_entry(
-                    &node.status,
-                    diff_only,
-                    hide_identical,
-                    hide_different,
-                    hide_left_only,
-                    hide_right_only,
-                    hide_unchecked,
-                ) {
-                    continue;
-                }
-
-                let status_symbol = match node.status {
-                    DiffStatus::Same => "  ==  ",
-                    DiffStatus::Different => "  !=  ",
-                    DiffStatus::OrphanLeft => "  <<  ",
-                    DiffStatus::OrphanRight => "  >>  ",
-                    DiffStatus::Unchecked => "  ??  ",
-                };
-
-                let (status_color, reset) = if use_color {
-                    (
-                        match node.status {
-                            DiffStatus::Same => "\x1b[32m",        // Green
-                            DiffStatus::Different => "\x1b[31m",   // Red
-                            DiffStatus::OrphanLeft => "\x1b[33m",  // Yellow
-                            DiffStatus::OrphanRight => "\x1b[34m", // Blue
-                            DiffStatus::Unchecked => "\x1b[36m",   // Cyan
-                        },
-                        "\x1b[0m",
-                    )
-                } else {
-                    ("", "")
-                };
-
-                println!(
-                    "{}{}{} {}",
-                    status_color,
-                    status_symbol,
-                    reset,
-                    node.relative_path.display()
-                );
+        }
+        let path_collisions = comparison_engine.path_collisions();
+        if !path_collisions.is_empty() {
+            println!("  Path rule collisions: {}", path_collisions.len());
+            for collision in &path_collisions {
+                println!("    {}", describe_path_collision(collision));
+            }
+        }
+        if let Some(timings) = &timings {
+            println!();
+            for line in timings.lines() {
+                println!("{line}");
             }
-            println!("\n{}", "=".repeat(80));
         }
-
-        println!("\n{}", "=".repeat(80));
-        let same_mark = if use_color {
-            "\x1b[32m(==)\x1b[0m"
-        } else {
-            "(==)"
-        };
-        let diff_mark = if use_color {
-            "\x1b[31m(!=)\x1b[0m"
-        } else {
-            "(!=)"
-        };
-        let left_mark = if use_color {
-            "\x1b[33m(<<)\x1b[0m"
-        } else {
-            "(<<)"
-        };
-        let right_mark = if use_color {
-            "\x1b[34m(>>)\x1b[0m"
-        } else {
-            "(>>)"
-        };
-        let unchecked_mark = if use_color {
-            "\x1b[36m(??)\x1b[0m"
-        } else {
-            "(??)"
-        };
-
-        println!("Summary:");
-        println!("  Total entries:   {}", diff_nodes.len());
-        println!("  Identical:       {} {}", same_count, same_mark);
-        println!("  Different:       {} {}", different_count, diff_mark);
-        println!("  Left only:       {} {}", orphan_left_count, left_mark);
-        println!("  Right only:      {} {}", orphan_right_count, right_mark);
-        println!("  Unchecked:       {} {}", unchecked_count, unchecked_mark);
         println!("{}", "=".repeat(80));
     }
 
@@ -808,7 +1697,9 @@ fn run_scan(
             .filter(|node| matches!(node.status, DiffStatus::Different | DiffStatus::Unchecked))
             .filter(|node| {
                 if let (Some(left_entry), Some(right_entry)) = (&node.left, &node.right) {
-                    is_image_file(&left_entry.path) && is_image_file(&right_entry.path)
+                    selects(routes.as_ref(), node, EngineKind::Image, || {
+                        is_image_file(&left_entry.path) && is_image_file(&right_entry.path)
+                    })
                 } else {
                     false
                 }
@@ -839,7 +1730,9 @@ fn run_scan(
             // Only analyze images that exist on both sides and are different/unchecked
             if matches!(node.status, DiffStatus::Different | DiffStatus::Unchecked) {
                 if let (Some(left_entry), Some(right_entry)) = (&node.left, &node.right) {
-                    if is_image_file(&left_entry.path) && is_image_file(&right_entry.path) {
+                    if selects(routes.as_ref(), node, EngineKind::Image, || {
+                        is_image_file(&left_entry.path) && is_image_file(&right_entry.path)
+                    }) {
                         if let Some(pb) = &pb_images {
                             pb.inc(1);
                         }
@@ -897,6 +1790,22 @@ fn run_scan(
                                             if use_color { "\x1b[0m" } else { "" }
                                         );
                                     }
+
+                                    if result.gps_changed() {
+                                        println!(
+                                            "  {}GPS location changed{}",
+                                            if use_color { "\x1b[1;31m" } else { "" },
+                                            if use_color { "\x1b[0m" } else { "" }
+                                        );
+                                    }
+                                    for diff in &result.exif_diffs {
+                                        println!(
+                                            "  EXIF {}: {} → {}",
+                                            diff.tag_name,
+                                            diff.left_value.as_deref().unwrap_or("(none)"),
+                                            diff.right_value.as_deref().unwrap_or("(none)")
+                                        );
+                                    }
                                 }
                             }
                             Err(e) => {
@@ -944,7 +1853,9 @@ fn run_scan(
             .filter(|node| matches!(node.status, DiffStatus::Different | DiffStatus::Unchecked))
             .filter(|node| {
                 if let (Some(left_entry), Some(right_entry)) = (&node.left, &node.right) {
-                    is_csv_file(&left_entry.path) && is_csv_file(&right_entry.path)
+                    selects(routes.as_ref(), node, EngineKind::Csv, || {
+                        is_csv_file(&left_entry.path) && is_csv_file(&right_entry.path)
+                    })
                 } else {
                     false
                 }
@@ -975,7 +1886,9 @@ fn run_scan(
             // Only analyze CSVs that exist on both sides and are different/unchecked
             if matches!(node.status, DiffStatus::Different | DiffStatus::Unchecked) {
                 if let (Some(left_entry), Some(right_entry)) = (&node.left, &node.right) {
-                    if is_csv_file(&left_entry.path) && is_csv_file(&right_entry.path) {
+                    if selects(routes.as_ref(), node, EngineKind::Csv, || {
+                        is_csv_file(&left_entry.path) && is_csv_file(&right_entry.path)
+                    }) {
                         if let Some(pb) = &pb_csvs {
                             pb.inc(1);
                         }
@@ -1086,10 +1999,14 @@ fn run_scan(
                                                 }
                                             }
                                         }
-                                        if result.row_diffs.len() > 5 {
+                                        // Count from the totals: row_diffs may be capped
+                                        let row_differences = result.different_rows
+                                            + result.left_only_rows
+                                            + result.right_only_rows;
+                                        if row_differences > 5 {
                                             println!(
                                                 "    ... and {} more row differences",
-                                                result.row_diffs.len() - 5
+                                                row_differences - 5
                                             );
                                         }
                                     }
@@ -1140,7 +2057,9 @@ fn run_scan(
             .filter(|node| matches!(node.status, DiffStatus::Different | DiffStatus::Unchecked))
             .filter(|node| {
                 if let (Some(left_entry), Some(right_entry)) = (&node.left, &node.right) {
-                    is_excel_file(&left_entry.path) && is_excel_file(&right_entry.path)
+                    selects(routes.as_ref(), node, EngineKind::Excel, || {
+                        is_excel_file(&left_entry.path) && is_excel_file(&right_entry.path)
+                    })
                 } else {
                     false
                 }
@@ -1171,7 +2090,9 @@ fn run_scan(
             // Only analyze Excel files that exist on both sides and are different/unchecked
             if matches!(node.status, DiffStatus::Different | DiffStatus::Unchecked) {
                 if let (Some(left_entry), Some(right_entry)) = (&node.left, &node.right) {
-                    if is_excel_file(&left_entry.path) && is_excel_file(&right_entry.path) {
+                    if selects(routes.as_ref(), node, EngineKind::Excel, || {
+                        is_excel_file(&left_entry.path) && is_excel_file(&right_entry.path)
+                    }) {
                         if let Some(pb) = &pb_excel {
                             pb.inc(1);
                         }
@@ -1246,6 +2167,33 @@ fn run_scan(
                                         );
                                     }
 
+                                    if !result.named_range_diffs.is_empty() {
+                                        println!(
+                                            "  {}Named range differences: {}{}",
+                                            if use_color { "\x1b[33m" } else { "" },
+                                            result.named_range_diffs.len(),
+                                            if use_color { "\x1b[0m" } else { "" }
+                                        );
+                                        for named in &result.named_range_diffs {
+                                            println!(
+                                                "    {}: {} -> {}",
+                                                named.name,
+                                                named.left_refers_to.as_deref().unwrap_or("-"),
+                                                named.right_refers_to.as_deref().unwrap_or("-")
+                                            );
+                                        }
+                                    }
+
+                                    if result.charts_differ() {
+                                        println!(
+                                            "  {}Charts: {} -> {}{}",
+                                            if use_color { "\x1b[33m" } else { "" },
+                                            result.left_charts,
+                                            result.right_charts,
+                                            if use_color { "\x1b[0m" } else { "" }
+                                        );
+                                    }
+
                                     // Show sheet-level differences
                                     if !result.sheet_diffs.is_empty() {
                                         println!(
@@ -1262,6 +2210,26 @@ fn run_scan(
                                                     sheet_diff.total_cols,
                                                     sheet_diff.different_cells
                                                 );
+                                                if !sheet_diff.added_rows.is_empty()
+                                                    || !sheet_diff.removed_rows.is_empty()
+                                                    || !sheet_diff.added_cols.is_empty()
+                                                    || !sheet_diff.removed_cols.is_empty()
+                                                {
+                                                    println!(
+                                                        "      Rows: +{} -{}, columns: +{} -{}",
+                                                        sheet_diff.added_rows.len(),
+                                                        sheet_diff.removed_rows.len(),
+                                                        sheet_diff.added_cols.len(),
+                                                        sheet_diff.removed_cols.len()
+                                                    );
+                                                }
+                                                if sheet_diff.merged_ranges_differ() {
+                                                    println!(
+                                                        "      Merged ranges: +[{}] -[{}]",
+                                                        sheet_diff.added_merged_ranges.join(", "),
+                                                        sheet_diff.removed_merged_ranges.join(", ")
+                                                    );
+                                                }
 
                                                 // Show first few cell differences
                                                 if !sheet_diff.cell_diffs.is_empty() {
@@ -1354,7 +2322,9 @@ fn run_scan(
             .filter(|node| matches!(node.status, DiffStatus::Different | DiffStatus::Unchecked))
             .filter(|node| {
                 if let (Some(left_entry), Some(right_entry)) = (&node.left, &node.right) {
-                    is_json_file(&left_entry.path) && is_json_file(&right_entry.path)
+                    selects(routes.as_ref(), node, EngineKind::Json, || {
+                        is_json_file(&left_entry.path) && is_json_file(&right_entry.path)
+                    })
                 } else {
                     false
                 }
@@ -1384,7 +2354,9 @@ fn run_scan(
         for node in &diff_nodes {
             if matches!(node.status, DiffStatus::Different | DiffStatus::Unchecked) {
                 if let (Some(left_entry), Some(right_entry)) = (&node.left, &node.right) {
-                    if is_json_file(&left_entry.path) && is_json_file(&right_entry.path) {
+                    if selects(routes.as_ref(), node, EngineKind::Json, || {
+                        is_json_file(&left_entry.path) && is_json_file(&right_entry.path)
+                    }) {
                         if let Some(pb) = &pb_json {
                             pb.inc(1);
                         }
@@ -1486,10 +2458,14 @@ fn run_scan(
                                             }
                                         }
                                         }
-                                        if result.path_diffs.len() > 5 {
+                                        // Count from the totals: path_diffs may be capped
+                                        let path_differences = result.different_paths
+                                            + result.left_only_paths
+                                            + result.right_only_paths;
+                                        if path_differences > 5 {
                                             println!(
                                                 "    ... and {} more path differences",
-                                                result.path_diffs.len() - 5
+                                                path_differences - 5
                                             );
                                         }
                                     }
@@ -1540,7 +2516,9 @@ fn run_scan(
             .filter(|node| matches!(node.status, DiffStatus::Different | DiffStatus::Unchecked))
             .filter(|node| {
                 if let (Some(left_entry), Some(right_entry)) = (&node.left, &node.right) {
-                    is_yaml_file(&left_entry.path) && is_yaml_file(&right_entry.path)
+                    selects(routes.as_ref(), node, EngineKind::Yaml, || {
+                        is_yaml_file(&left_entry.path) && is_yaml_file(&right_entry.path)
+                    })
                 } else {
                     false
                 }
@@ -1570,7 +2548,9 @@ fn run_scan(
         for node in &diff_nodes {
             if matches!(node.status, DiffStatus::Different | DiffStatus::Unchecked) {
                 if let (Some(left_entry), Some(right_entry)) = (&node.left, &node.right) {
-                    if is_yaml_file(&left_entry.path) && is_yaml_file(&right_entry.path) {
+                    if selects(routes.as_ref(), node, EngineKind::Yaml, || {
+                        is_yaml_file(&left_entry.path) && is_yaml_file(&right_entry.path)
+                    }) {
                         if let Some(pb) = &pb_yaml {
                             pb.inc(1);
                         }
@@ -1672,10 +2652,14 @@ fn run_scan(
                                             }
                                         }
                                         }
-                                        if result.path_diffs.len() > 5 {
+                                        // Count from the totals: path_diffs may be capped
+                                        let path_differences = result.different_paths
+                                            + result.left_only_paths
+                                            + result.right_only_paths;
+                                        if path_differences > 5 {
                                             println!(
                                                 "    ... and {} more path differences",
-                                                result.path_diffs.len() - 5
+                                                path_differences - 5
                                             );
                                         }
                                     }
@@ -1718,7 +2702,11 @@ fn run_scan(
 
     // Parquet-specific analysis if enabled
     if parquet_diff {
-        let parquet_engine = ParquetDiffEngine::new();
+        let parquet_engine = ParquetDiffEngine::new().with_mode(if parquet_physical {
+            ParquetCompareMode::Physical
+        } else {
+            ParquetCompareMode::Logical
+        });
         let mut parquet_comparisons = 0;
 
         // Count Parquet files to analyze
@@ -1727,7 +2715,9 @@ fn run_scan(
             .filter(|node| matches!(node.status, DiffStatus::Different | DiffStatus::Unchecked))
             .filter(|node| {
                 if let (Some(left_entry), Some(right_entry)) = (&node.left, &node.right) {
-                    is_parquet_file(&left_entry.path) && is_parquet_file(&right_entry.path)
+                    selects(routes.as_ref(), node, EngineKind::Parquet, || {
+                        is_parquet_file(&left_entry.path) && is_parquet_file(&right_entry.path)
+                    })
                 } else {
                     false
                 }
@@ -1750,7 +2740,9 @@ fn run_scan(
                 }
 
                 if let (Some(left_entry), Some(right_entry)) = (&node.left, &node.right) {
-                    if !is_parquet_file(&left_entry.path) || !is_parquet_file(&right_entry.path) {
+                    if !selects(routes.as_ref(), node, EngineKind::Parquet, || {
+                        is_parquet_file(&left_entry.path) && is_parquet_file(&right_entry.path)
+                    }) {
                         continue;
                     }
 
@@ -1821,6 +2813,22 @@ fn run_scan(
                                     }
                                 }
 
+                                // Storage differences (physical mode only)
+                                if !result.physical_diffs.is_empty() {
+                                    println!(
+                                        "  {}Storage differences:{} {} difference(s)",
+                                        if use_color { "\x1b[1;33m" } else { "" },
+                                        if use_color { "\x1b[0m" } else { "" },
+                                        result.physical_diffs.len()
+                                    );
+                                    for diff in result.physical_diffs.iter().take(5) {
+                                        println!(
+                                            "    {}: {} -> {}",
+                                            diff.property, diff.left_value, diff.right_value
+                                        );
+                                    }
+                                }
+
                                 // Row statistics
                                 println!("  Total rows: {}", result.total_rows);
                                 println!(
@@ -1932,7 +2940,27 @@ fn run_scan(
     }
 
     // Text-specific analysis if enabled
-    if text_diff {
+    if text_diff && stat && !json {
+        let text_engine = TextDiffEngine::with_config(text_config);
+        let stats =
+            text_engine.diff_stats(&left, &right, &diff_nodes, |node, left_path, right_path| {
+                selects(routes.as_ref(), node, EngineKind::Text, || {
+                    is_text_file(left_path) && is_text_file(right_path)
+                })
+            });
+
+        println!("\n{}", "=".repeat(80));
+        println!("Text Diff Stat");
+        println!("{}", "=".repeat(80));
+        if stats.is_empty() {
+            println!("\nNo different text files found to analyze.");
+        } else {
+            for line in render_diff_stat(&stats, terminal_width(), use_color) {
+                println!("{}", line);
+            }
+        }
+        println!("{}", "=".repeat(80));
+    } else if text_diff {
         let text_engine = TextDiffEngine::with_config(text_config);
 
         // Count text files to analyze
@@ -1941,7 +2969,10 @@ fn run_scan(
             .filter(|node| matches!(node.status, DiffStatus::Different | DiffStatus::Unchecked))
             .filter(|node| {
                 if let (Some(left_entry), Some(right_entry)) = (&node.left, &node.right) {
-                    is_text_file(&left_entry.path) && is_text_file(&right_entry.path)
+                    selects(routes.as_ref(), node, EngineKind::Text, || {
+                        is_text_file(&left.join(&left_entry.path))
+                            && is_text_file(&right.join(&right_entry.path))
+                    })
                 } else {
                     false
                 }
@@ -1972,21 +3003,19 @@ fn run_scan(
             // Only analyze text files that exist on both sides and are different/unchecked
             if matches!(node.status, DiffStatus::Different | DiffStatus::Unchecked) {
                 if let (Some(left_entry), Some(right_entry)) = (&node.left, &node.right) {
-                    if is_text_file(&left_entry.path) && is_text_file(&right_entry.path) {
+                    let left_path = left.join(&left_entry.path);
+                    let right_path = right.join(&right_entry.path);
+                    if selects(routes.as_ref(), node, EngineKind::Text, || {
+                        is_text_file(&left_path) && is_text_file(&right_path)
+                    }) {
                         if let Some(pb) = &pb_texts {
                             pb.inc(1);
                         }
 
-                        let left_path = left.join(&left_entry.path);
-                        let right_path = right.join(&right_entry.path);
-
                         // Read file contents
-                        match (
-                            std::fs::read_to_string(&left_path),
-                            std::fs::read_to_string(&right_path),
-                        ) {
+                        match (read_text_file(&left_path), read_text_file(&right_path)) {
                             (Ok(left_content), Ok(right_content)) => {
-                                match text_engine.compare_text_patience(
+                                match text_engine.compare_text(
                                     &left_content,
                                     &right_content,
                                     &left_path,
@@ -2046,6 +3075,16 @@ fn run_scan(
                                                     deleted
                                                 );
                                             }
+                                            if columns {
+                                                println!();
+                                                for row in render_side_by_side(
+                                                    &align_rows(&diff_lines),
+                                                    terminal_width(),
+                                                    use_color,
+                                                ) {
+                                                    println!("{}", row);
+                                                }
+                                            }
                                         }
                                     }
                                     Err(e) => {
@@ -2086,117 +3125,534 @@ fn run_scan(
                     text_comparisons,
                     if text_comparisons == 1 { "" } else { "s" }
                 );
-                println!("{}", "=".repeat(80));
-            } else {
-                println!("\nNo different text files found to analyze.");
-                println!("{}", "=".repeat(80));
+                println!("{}", "=".repeat(80));
+            } else {
+                println!("\nNo different text files found to analyze.");
+                println!("{}", "=".repeat(80));
+            }
+        }
+    }
+
+    // JSON output at the end (after all diff processing)
+    if json {
+        let mut report = build_json_report(
+            &left,
+            &right,
+            &diff_nodes,
+            diff_only,
+            hide_identical,
+            hide_different,
+            hide_left_only,
+            hide_right_only,
+            hide_unchecked,
+            json_text_diffs,
+            json_image_diffs,
+            json_csv_diffs,
+            json_excel_diffs,
+            json_json_diffs,
+            json_yaml_diffs,
+            json_parquet_diffs,
+        );
+        report.timing = timings.as_ref().map(JsonTiming::from);
+        report.path_collisions = comparison_engine.path_collisions();
+        let output = serde_json::to_string_pretty(&report)?;
+        println!("{output}");
+    }
+
+    if let Some(log) = &mut event_log {
+        log.emit(&ScanEvent::Summary(JsonSummary::from(&summary)))?;
+    }
+
+    Ok(ScanResult { summary })
+}
+
+/// Compare several directory pairs with one shared hash cache, printing a
+/// report per pair and then a summary line per pair
+///
+/// A pair whose roots can't be read is skipped. Returns the exit code: 1 if
+/// a pair was skipped, otherwise 2 if any pair differs and 0 if none does.
+fn run_pair_scan(
+    pairs: &[(PathBuf, PathBuf)],
+    ignore_patterns: Vec<String>,
+    profile: Option<&ProfileSettings>,
+    follow_symlinks: bool,
+    exclude_hidden: bool,
+    verify_hashes: bool,
+    no_verify_hashes: bool,
+    force_content_check: bool,
+    cache_dir: Option<PathBuf>,
+    no_cache: bool,
+    threads: Option<usize>,
+    diff_only: bool,
+    hide_identical: bool,
+    hide_different: bool,
+    hide_left_only: bool,
+    hide_right_only: bool,
+    hide_unchecked: bool,
+    json: bool,
+    no_color: bool,
+    columns: bool,
+    patch: Option<PathBuf>,
+    events: Option<PathBuf>,
+) -> Result<i32, Box<dyn std::error::Error>> {
+    let loaded = load_config(false)?;
+    let mut config = loaded.config;
+    if let Some(settings) = profile {
+        settings.apply_to(&mut config);
+    }
+    apply_scan_overrides(
+        &mut config,
+        ignore_patterns,
+        follow_symlinks,
+        exclude_hidden,
+        cache_dir,
+        threads,
+    );
+    let verify_hashes = if verify_hashes {
+        true
+    } else if no_verify_hashes {
+        false
+    } else {
+        config.use_hash_verification
+    };
+    let cache_path = match config.cache_dir.clone() {
+        Some(path) => path,
+        None => default_cache_dir(loaded.portable, &loaded.path)?,
+    };
+
+    let mut options = CompareOptions::new()
+        .ignore_patterns(config.ignore_patterns)
+        .follow_symlinks(config.follow_symlinks)
+        .include_hidden(config.include_hidden)
+        .hash_verification(verify_hashes)
+        .force_content_check(force_content_check)
+        .mtime_tolerance(Duration::from_secs(config.mtime_tolerance_secs))
+        .cache_dir(cache_path)
+        .use_cache(!no_cache);
+    if let Some(threads) = config.threads {
+        options = options.threads(threads);
+    }
+
+    // An unreadable root would abort every pair, so those pairs are dropped
+    // up front
+    let mut readable = Vec::new();
+    let mut skipped = 0;
+    for (left, right) in pairs {
+        match std::fs::read_dir(left).and_then(|_| std::fs::read_dir(right)) {
+            Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
+                error!(
+                    "Skipping pair {} <-> {}: {}",
+                    left.display(),
+                    right.display(),
+                    e
+                );
+                skipped += 1;
             }
+            _ => readable.push((left.clone(), right.clone())),
         }
     }
 
-    // JSON output at the end (after all diff processing)
-    if json {
-        let report = build_json_report(
-            &left,
-            &right,
-            &diff_nodes,
+    let mut event_log = events.as_deref().map(EventLog::create).transpose()?;
+    let results = compare_directory_pairs(&readable, &options)?;
+
+    let use_color = !json && !no_color && std::io::stdout().is_terminal();
+    let show = |status: &DiffStatus| {
+        should_show_entry(
+            status,
             diff_only,
             hide_identical,
             hide_different,
             hide_left_only,
             hide_right_only,
             hide_unchecked,
-            json_text_diffs,
-            json_image_diffs,
-            json_csv_diffs,
-            json_excel_diffs,
-            json_json_diffs,
-            json_yaml_diffs,
-            json_parquet_diffs,
-        );
-        let output = serde_json::to_string_pretty(&report)?;
-        println!("{output}");
+        )
+    };
+    let mut reports = Vec::new();
+    let mut patch_text = String::new();
+    let mut summaries = Vec::new();
+    for result in &results {
+        let summary = summarize(&result.diffs);
+
+        // Pairs are compared in one call, so each pair's events follow
+        // its comparison rather than streaming during it
+        if let Some(log) = &mut event_log {
+            log.emit(&ScanEvent::ScanStarted {
+                left: result.left.to_string_lossy().to_string(),
+                right: result.right.to_string_lossy().to_string(),
+            })?;
+            for node in &result.diffs {
+                log.emit(&ScanEvent::EntryCompared {
+                    path: node.relative_path.to_string_lossy().to_string(),
+                    status: node.status,
+                })?;
+            }
+            log.emit(&ScanEvent::PhaseDone {
+                phase: "compare",
+                count: result.diffs.len(),
+            })?;
+            log.emit(&ScanEvent::Summary(JsonSummary::from(&summary)))?;
+        }
+
+        // Each pair's files are placed under its left directory's name so
+        // the patches don't collide
+        if patch.is_some() {
+            let prefix = result
+                .left
+                .file_name()
+                .map(PathBuf::from)
+                .unwrap_or_default();
+            patch_text.push_str(&PatchBuilder::tree_patch_under(
+                &prefix,
+                &result.left,
+                &result.right,
+                &result.diffs,
+                None,
+                None,
+            )?);
+        }
+
+        if json {
+            reports.push(build_json_report(
+                &result.left,
+                &result.right,
+                &result.diffs,
+                diff_only,
+                hide_identical,
+                hide_different,
+                hide_left_only,
+                hide_right_only,
+                hide_unchecked,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+            ));
+        } else {
+            println!(
+                "=== {} <-> {} ===",
+                result.left.display(),
+                result.right.display()
+            );
+            print_entries(&result.diffs, columns, use_color, show);
+            println!("\n{}", "=".repeat(80));
+            print_summary_counts(&summary, use_color);
+            println!("{}", "=".repeat(80));
+            println!();
+        }
+        summaries.push(summary);
     }
 
-    // Calculate final statistics for exit code
-    let mut scan_result = ScanResult {
-        total: diff_nodes.len(),
-        identical: 0,
-        different: 0,
-        left_only: 0,
-        right_only: 0,
-        unchecked: 0,
-    };
+    if let Some(patch_path) = &patch {
+        std::fs::write(patch_path, patch_text)?;
+        info!("Wrote patch to {}", patch_path.display());
+    }
+
+    if json {
+        let report = JsonPairsReport {
+            schema_version: "1.1.0".to_string(),
+            pairs: reports,
+        };
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        println!("Pair summary:");
+        for (result, summary) in results.iter().zip(&summaries) {
+            println!(
+                "  {} <-> {}: {} identical, {} different, {} left-only, {} right-only, {} unchecked",
+                result.left.display(),
+                result.right.display(),
+                summary.statuses.same,
+                summary.changed(),
+                summary.statuses.orphan_left,
+                summary.statuses.orphan_right,
+                summary.statuses.unchecked
+            );
+        }
+        if skipped > 0 {
+            println!("  {} pair(s) skipped", skipped);
+        }
+    }
+
+    Ok(if skipped > 0 {
+        1
+    } else if summaries.iter().any(|summary| summary.has_differences()) {
+        2
+    } else {
+        0
+    })
+}
+
+/// Print one line per node that `show` keeps, side by side with `columns`
+fn print_entries(
+    diff_nodes: &[DiffNode],
+    columns: bool,
+    use_color: bool,
+    show: impl Fn(&DiffStatus) -> bool,
+) {
+    if columns {
+        // Columned output format (side-by-side)
+        println!("\n{}", "=".repeat(120));
+        println!("Comparison Results (Side-by-Side)");
+        println!("{}", "=".repeat(120));
+        println!("{:<50} {:^8} {:<50}", "Left", "Status", "Right");
+        println!("{}", "-".repeat(120));
+
+        for node in diff_nodes {
+            // Check if entry should be shown based on filters
+            if !show(&node.status) {
+                continue;
+            }
+
+            let status_symbol = match node.status {
+                DiffStatus::Same => "==",
+                DiffStatus::Different => "!=",
+                DiffStatus::OrphanLeft => "<<",
+                DiffStatus::OrphanRight => ">>",
+                DiffStatus::Unchecked => "??",
+                DiffStatus::TypeConflict => "<>",
+                DiffStatus::Ignored => "--",
+            };
+
+            let (status_color, reset) = if use_color {
+                (
+                    match node.status {
+                        DiffStatus::Same => "\x1b[32m",         // Green
+                        DiffStatus::Different => "\x1b[31m",    // Red
+                        DiffStatus::OrphanLeft => "\x1b[33m",   // Yellow
+                        DiffStatus::OrphanRight => "\x1b[34m",  // Blue
+                        DiffStatus::Unchecked => "\x1b[36m",    // Cyan
+                        DiffStatus::TypeConflict => "\x1b[35m", // Magenta
+                        DiffStatus::Ignored => "\x1b[90m",      // Gray
+                    },
+                    "\x1b[0m",
+                )
+            } else {
+                ("", "")
+            };
+
+            let left_text = if node.left.is_some() {
+                format!("{}", node.relative_path.display())
+            } else {
+                String::from("(missing)")
+            };
 
-    for node in &diff_nodes {
-        match node.status {
-            DiffStatus::Same => scan_result.identical += 1,
-            DiffStatus::Different => scan_result.different += 1,
-            DiffStatus::OrphanLeft => scan_result.left_only += 1,
-            DiffStatus::OrphanRight => scan_result.right_only += 1,
-            DiffStatus::Unchecked => scan_result.unchecked += 1,
+            let right_text = if node.right.is_some() {
+                format!("{}", node.relative_path.display())
+            } else {
+                String::from("(missing)")
+            };
+
+            println!(
+                "{:<50} {}{:^8}{} {:<50}",
+                truncate_path(&left_text, 50),
+                status_color,
+                status_symbol,
+                reset,
+                truncate_path(&right_text, 50)
+            );
+        }
+        println!("{}", "=".repeat(120));
+    } else {
+        // Standard output format
+        println!("\n{}", "=".repeat(80));
+        println!("Comparison Results");
+        println!("{}", "=".repeat(80));
+
+        for node in diff_nodes {
+            // Check if entry should be shown based on filters
+            if !show(&node.status) {
+                continue;
+            }
+
+            let status_symbol = match node.status {
+                DiffStatus::Same => "  ==  ",
+                DiffStatus::Different => "  !=  ",
+                DiffStatus::OrphanLeft => "  <<  ",
+                DiffStatus::OrphanRight => "  >>  ",
+                DiffStatus::Unchecked => "  ??  ",
+                DiffStatus::TypeConflict => "  <>  ",
+                DiffStatus::Ignored => "  --  ",
+            };
+
+            let (status_color, reset) = if use_color {
+                (
+                    match node.status {
+                        DiffStatus::Same => "\x1b[32m",         // Green
+                        DiffStatus::Different => "\x1b[31m",    // Red
+                        DiffStatus::OrphanLeft => "\x1b[33m",   // Yellow
+                        DiffStatus::OrphanRight => "\x1b[34m",  // Blue
+                        DiffStatus::Unchecked => "\x1b[36m",    // Cyan
+                        DiffStatus::TypeConflict => "\x1b[35m", // Magenta
+                        DiffStatus::Ignored => "\x1b[90m",      // Gray
+                    },
+                    "\x1b[0m",
+                )
+            } else {
+                ("", "")
+            };
+
+            let similar_note = node
+                .similar_to
+                .as_ref()
+                .map(|similar| {
+                    format!(
+                        "  (~{:.0}% similar to {})",
+                        similar.similarity,
+                        similar.path.display()
+                    )
+                })
+                .unwrap_or_default();
+            let reason_note = match node.reason {
+                Some(DiffReason::EmptiedFile) => "  (emptied on one side)",
+                Some(DiffReason::ChangedDuringScan) => "  (changed during scan)",
+                Some(DiffReason::DecompressedMatch) => "  (same when decompressed)",
+                Some(DiffReason::Renamed) => "  (renamed)",
+                None => "",
+            };
+            let ignored_note = node
+                .ignored_by
+                .as_ref()
+                .map(|rule| format!("  (ignored by {})", rule))
+                .unwrap_or_default();
+
+            println!(
+                "{}{}{} {}{}{}{}",
+                status_color,
+                status_symbol,
+                reset,
+                node.relative_path.display(),
+                similar_note,
+                reason_note,
+                ignored_note
+            );
         }
+        println!("\n{}", "=".repeat(80));
+    }
+}
+
+/// Print the per-status counts of a comparison summary
+fn print_summary_counts(summary: &ComparisonSummary, use_color: bool) {
+    let same_mark = if use_color {
+        "\x1b[32m(==)\x1b[0m"
+    } else {
+        "(==)"
+    };
+    let diff_mark = if use_color {
+        "\x1b[31m(!=)\x1b[0m"
+    } else {
+        "(!=)"
+    };
+    let left_mark = if use_color {
+        "\x1b[33m(<<)\x1b[0m"
+    } else {
+        "(<<)"
+    };
+    let right_mark = if use_color {
+        "\x1b[34m(>>)\x1b[0m"
+    } else {
+        "(>>)"
+    };
+    let unchecked_mark = if use_color {
+        "\x1b[36m(??)\x1b[0m"
+    } else {
+        "(??)"
+    };
+
+    println!("Summary:");
+    println!("  Total entries:   {}", summary.total);
+    println!("  Identical:       {} {}", summary.statuses.same, same_mark);
+    println!("  Different:       {} {}", summary.changed(), diff_mark);
+    println!(
+        "  Left only:       {} {}",
+        summary.statuses.orphan_left, left_mark
+    );
+    println!(
+        "  Right only:      {} {}",
+        summary.statuses.orphan_right, right_mark
+    );
+    println!(
+        "  Unchecked:       {} {}",
+        summary.statuses.unchecked, unchecked_mark
+    );
+    if summary.statuses.ignored > 0 {
+        println!("  Ignored:         {}", summary.statuses.ignored);
+    }
+    if summary.changed_during_scan > 0 {
+        println!("  Changed in scan: {}", summary.changed_during_scan);
+    }
+}
+
+/// One line of the `--events` JSON Lines log
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum ScanEvent {
+    ScanStarted { left: String, right: String },
+    PhaseDone { phase: &'static str, count: usize },
+    EntryCompared { path: String, status: DiffStatus },
+    Summary(JsonSummary),
+}
+
+/// Writer for the `--events` log, flushed after every event so the file can
+/// be tailed while a long scan runs
+struct EventLog {
+    writer: std::io::BufWriter<std::fs::File>,
+}
+
+impl EventLog {
+    fn create(path: &Path) -> std::io::Result<Self> {
+        Ok(Self {
+            writer: std::io::BufWriter::new(std::fs::File::create(path)?),
+        })
     }
 
-    Ok(scan_result)
+    fn emit(&mut self, event: &ScanEvent) -> std::io::Result<()> {
+        serde_json::to_writer(&mut self.writer, event)?;
+        self.writer.write_all(b"\n")?;
+        self.writer.flush()
+    }
 }
 
-/// Check if a file is likely a text file based on extension
+/// Check if a file holds text, sniffing its content and encoding
 fn is_text_file(path: &Path) -> bool {
-    path.extension()
-        .and_then(|ext| ext.to_str())
-        .map(|ext| {
-            matches!(
-                ext.to_lowercase().as_str(),
-                "txt"
-                    | "md"
-                    | "markdown"
-                    | "rst"
-                    | "log"
-                    | "rs"
-                    | "toml"
-                    | "yaml"
-                    | "yml"
-                    | "json"
-                    | "xml"
-                    | "html"
-                    | "htm"
-                    | "css"
-                    | "js"
-                    | "ts"
-                    | "tsx"
-                    | "jsx"
-                    | "c"
-                    | "cpp"
-                    | "cc"
-                    | "cxx"
-                    | "h"
-                    | "hpp"
-                    | "hxx"
-                    | "cs"
-                    | "java"
-                    | "py"
-                    | "rb"
-                    | "go"
-                    | "php"
-                    | "pl"
-                    | "sh"
-                    | "bash"
-                    | "zsh"
-                    | "fish"
-                    | "sql"
-                    | "conf"
-                    | "cfg"
-                    | "ini"
-                    | "properties"
-                    | "cmake"
-                    | "make"
-                    | "dockerfile"
-                    | "gitignore"
-                    | "gitattributes"
+    detect_file_content_kind(path).is_ok_and(|kind| kind.is_text())
+}
+
+/// Engine for each differing file present on both sides, keyed by relative
+/// path; files that cannot be read are left out
+fn route_diff_nodes(
+    diff_nodes: &[DiffNode],
+    left_source: &ScanSource,
+    right_source: &ScanSource,
+) -> HashMap<PathBuf, EngineKind> {
+    diff_nodes
+        .iter()
+        .filter(|node| matches!(node.status, DiffStatus::Different | DiffStatus::Unchecked))
+        .filter_map(|node| {
+            let (left_entry, right_entry) = (node.left.as_ref()?, node.right.as_ref()?);
+            let engine = route_pair(
+                &left_source.root().join(&left_entry.path),
+                &right_source.root().join(&right_entry.path),
             )
+            .ok()?;
+            Some((node.relative_path.clone(), engine))
         })
-        .unwrap_or(false)
+        .collect()
+}
+
+/// Whether `node` goes to `engine`: by its sniffed route under --auto,
+/// otherwise by `by_name`
+fn selects(
+    routes: Option<&HashMap<PathBuf, EngineKind>>,
+    node: &DiffNode,
+    engine: EngineKind,
+    by_name: impl FnOnce() -> bool,
+) -> bool {
+    match routes {
+        Some(routes) => routes.get(&node.relative_path) == Some(&engine),
+        None => by_name(),
+    }
 }
 
 #[derive(Serialize)]
@@ -2223,6 +3679,17 @@ struct JsonReport {
     yaml_diffs: Option<Vec<JsonJsonDiffReport>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     parquet_diffs: Option<Vec<JsonParquetDiffReport>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timing: Option<JsonTiming>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    path_collisions: Vec<PathCollision>,
+}
+
+/// `--json` report of a multi-pair scan: one report per pair, in order
+#[derive(Serialize)]
+struct JsonPairsReport {
+    schema_version: String,
+    pairs: Vec<JsonReport>,
 }
 
 #[derive(Serialize)]
@@ -2233,6 +3700,47 @@ struct JsonSummary {
     orphan_left: usize,
     orphan_right: usize,
     unchecked: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ignored: Option<usize>,
+}
+
+impl From<&ComparisonSummary> for JsonSummary {
+    fn from(summary: &ComparisonSummary) -> Self {
+        Self {
+            total: summary.total,
+            same: summary.statuses.same,
+            different: summary.changed(),
+            orphan_left: summary.statuses.orphan_left,
+            orphan_right: summary.statuses.orphan_right,
+            unchecked: summary.statuses.unchecked,
+            ignored: (summary.statuses.ignored > 0).then_some(summary.statuses.ignored),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct JsonTiming {
+    scan_left_ms: u128,
+    scan_right_ms: u128,
+    compare_ms: u128,
+    hash_ms: u128,
+    bytes_hashed: u64,
+    cache_hits: u64,
+    cache_misses: u64,
+}
+
+impl From<&PhaseTimings> for JsonTiming {
+    fn from(timings: &PhaseTimings) -> Self {
+        Self {
+            scan_left_ms: timings.scan_left.as_millis(),
+            scan_right_ms: timings.scan_right.as_millis(),
+            compare_ms: timings.compare.as_millis(),
+            hash_ms: timings.hashing.hash_time.as_millis(),
+            bytes_hashed: timings.hashing.bytes_hashed,
+            cache_hits: timings.hashing.cache_hits,
+            cache_misses: timings.hashing.cache_misses,
+        }
+    }
 }
 
 #[derive(Serialize)]
@@ -2241,6 +3749,12 @@ struct JsonEntry {
     status: DiffStatus,
     left: Option<JsonFileSide>,
     right: Option<JsonFileSide>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    similar_to: Option<SimilarTo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<DiffReason>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ignored_by: Option<String>,
 }
 
 #[derive(Serialize)]
@@ -2308,26 +3822,10 @@ fn build_json_report(
     yaml_diffs: Option<Vec<JsonJsonDiffReport>>,
     parquet_diffs: Option<Vec<JsonParquetDiffReport>>,
 ) -> JsonReport {
-    let mut summary = JsonSummary {
-        total: diff_nodes.len(),
-        same: 0,
-        different: 0,
-        orphan_left: 0,
-        orphan_right: 0,
-        unchecked: 0,
-    };
-
+    let summary = JsonSummary::from(&summarize(diff_nodes));
     let mut entries = Vec::new();
 
     for node in diff_nodes {
-        match node.status {
-            DiffStatus::Same => summary.same += 1,
-            DiffStatus::Different => summary.different += 1,
-            DiffStatus::OrphanLeft => summary.orphan_left += 1,
-            DiffStatus::OrphanRight => summary.orphan_right += 1,
-            DiffStatus::Unchecked => summary.unchecked += 1,
-        }
-
         if !should_show_entry(
             &node.status,
             diff_only,
@@ -2345,6 +3843,9 @@ fn build_json_report(
             status: node.status,
             left: node.left.as_ref().map(json_side),
             right: node.right.as_ref().map(json_side),
+            similar_to: node.similar_to.clone(),
+            reason: node.reason,
+            ignored_by: node.ignored_by.clone(),
         });
     }
 
@@ -2361,6 +3862,8 @@ fn build_json_report(
         json_diffs,
         yaml_diffs,
         parquet_diffs,
+        timing: None,
+        path_collisions: Vec::new(),
     }
 }
 
@@ -2376,6 +3879,144 @@ fn system_time_to_unix(time: SystemTime) -> Option<u64> {
     time.duration_since(UNIX_EPOCH).ok().map(|d| d.as_secs())
 }
 
+/// Width of the terminal on stdout, or 120 columns when not a terminal
+fn terminal_width() -> usize {
+    console::Term::stdout()
+        .size_checked()
+        .map_or(120, |(_, columns)| usize::from(columns))
+}
+
+/// Render per-file text diff stats as a `git diff --stat` table fitting
+/// in `width`
+///
+/// Each row shows the path, the number of changed lines and a bar of `+`
+/// and `-`, scaled down when the largest change does not fit. A totals line
+/// follows the rows.
+fn render_diff_stat(stats: &[TextDiffStats], width: usize, use_color: bool) -> Vec<String> {
+    let names: Vec<String> = stats.iter().map(|s| s.path.display().to_string()).collect();
+    let name_width = names.iter().map(|n| n.chars().count()).max().unwrap_or(0);
+    let max_changes = stats.iter().map(TextDiffStats::changes).max().unwrap_or(0);
+    let count_width = max_changes.to_string().len();
+    let bar_width = width.saturating_sub(name_width + count_width + 5).max(10);
+    // Like git, every non-zero count keeps at least one bar character
+    let scale = |n: usize| {
+        if n == 0 || max_changes <= bar_width {
+            n
+        } else {
+            1 + n * (bar_width - 1) / max_changes
+        }
+    };
+    let (green, red, reset) = if use_color {
+        ("\x1b[32m", "\x1b[31m", "\x1b[0m")
+    } else {
+        ("", "", "")
+    };
+
+    let mut lines: Vec<String> = stats
+        .iter()
+        .zip(&names)
+        .map(|(stat, name)| {
+            let plus = scale(stat.insertions);
+            let minus = scale(stat.changes()) - plus;
+            let mut bar = String::new();
+            if plus > 0 {
+                bar.push_str(&format!("{}{}{}", green, "+".repeat(plus), reset));
+            }
+            if minus > 0 {
+                bar.push_str(&format!("{}{}{}", red, "-".repeat(minus), reset));
+            }
+            format!(
+                " {:<name_width$} | {:>count_width$} {}",
+                name,
+                stat.changes(),
+                bar
+            )
+            .trim_end()
+            .to_string()
+        })
+        .collect();
+
+    let insertions: usize = stats.iter().map(|s| s.insertions).sum();
+    let deletions: usize = stats.iter().map(|s| s.deletions).sum();
+    let plural = |n: usize| if n == 1 { "" } else { "s" };
+    let mut totals = format!(" {} file{} changed", stats.len(), plural(stats.len()));
+    if insertions > 0 {
+        totals.push_str(&format!(
+            ", {} insertion{}(+)",
+            insertions,
+            plural(insertions)
+        ));
+    }
+    if deletions > 0 {
+        totals.push_str(&format!(", {} deletion{}(-)", deletions, plural(deletions)));
+    }
+    lines.push(totals);
+    lines
+}
+
+/// Render aligned text diff rows as two columns fitting in `width`
+///
+/// Each side shows the line number, a `-`/`+` marker on changed rows and
+/// the line text, truncated with `…` when it doesn't fit. A line with no
+/// counterpart faces a blank filler on the other side.
+fn render_side_by_side(rows: &[AlignedRow], width: usize, use_color: bool) -> Vec<String> {
+    let number_width = rows
+        .iter()
+        .flat_map(|row| row.left.iter().chain(&row.right))
+        .map(|line| line.number)
+        .max()
+        .unwrap_or(0)
+        .to_string()
+        .len();
+    // "<number> <marker> <text>" per side, " | " between the sides
+    let text_width = (width.saturating_sub(3) / 2)
+        .saturating_sub(number_width + 3)
+        .max(8);
+    let (dim, reset) = if use_color {
+        ("\x1b[90m", "\x1b[0m")
+    } else {
+        ("", "")
+    };
+
+    let cell = |line: Option<&AlignedLine>, marker: char, color: &str| match line {
+        Some(line) => {
+            let text = line.text.replace('\t', "    ");
+            format!(
+                "{}{:>width$}{} {}{} {}{}",
+                dim,
+                line.number,
+                reset,
+                color,
+                marker,
+                console::pad_str(&text, text_width, Alignment::Left, Some("…")),
+                reset,
+                width = number_width
+            )
+        }
+        None => " ".repeat(number_width + 3 + text_width),
+    };
+
+    rows.iter()
+        .map(|row| {
+            let (left_marker, right_marker) = if row.changed { ('-', '+') } else { (' ', ' ') };
+            let (left_color, right_color) = if row.changed && use_color {
+                ("\x1b[31m", "\x1b[32m")
+            } else {
+                ("", "")
+            };
+            format!(
+                "{} {}|{} {}",
+                cell(row.left.as_ref(), left_marker, left_color),
+                dim,
+                reset,
+                cell(row.right.as_ref(), right_marker, right_color)
+            )
+            .trim_end()
+            .to_string()
+        })
+        .collect()
+}
+
 fn truncate_path(path: &str, max_len: usize) -> String {
     if path.chars().count() <= max_len {
         return path.to_string();
@@ -2409,7 +4050,7 @@ fn should_show_entry(
     // Check individual hide flags
     match status {
         DiffStatus::Same if hide_identical => false,
-        DiffStatus::Different if hide_different => false,
+        DiffStatus::Different | DiffStatus::TypeConflict if hide_different => false,
         DiffStatus::OrphanLeft if hide_left_only => false,
         DiffStatus::OrphanRight if hide_right_only => false,
         DiffStatus::Unchecked if hide_unchecked => false,
@@ -2417,14 +4058,81 @@ fn should_show_entry(
     }
 }
 
+/// Apply the scan command's flags on top of the loaded configuration
+fn apply_scan_overrides(
+    config: &mut AppConfig,
+    ignore_patterns: Vec<String>,
+    follow_symlinks: bool,
+    exclude_hidden: bool,
+    cache_dir: Option<PathBuf>,
+    threads: Option<usize>,
+) {
+    config.ignore_patterns.extend(ignore_patterns);
+    if follow_symlinks {
+        config.follow_symlinks = true;
+    }
+    if exclude_hidden {
+        config.include_hidden = false;
+    }
+    if let Some(cache_dir) = cache_dir {
+        config.cache_dir = Some(cache_dir);
+    }
+    if let Some(threads) = threads {
+        config.threads = Some(threads);
+    }
+}
+
+/// Hash cache of a scan; with `no_cache`, hashes live in memory only and
+/// nothing under `cache_path` is read or written
+fn scan_hash_cache(no_cache: bool, cache_path: PathBuf) -> HashCache {
+    if no_cache {
+        info!("Hash cache disabled for this run");
+        return HashCache::in_memory();
+    }
+    info!("Using cache directory: {}", cache_path.display());
+    HashCache::open_or_in_memory(cache_path)
+}
+
+/// Scan one side; with `snapshot_dir`, local folders are rescanned
+/// incrementally against the snapshot kept there, and with `show_ignored`
+/// the entries left out by the filters are returned too
 fn scan_source(
     scanner: &FolderScanner,
     source: &ScanSource,
-) -> Result<Vec<rcompare_common::FileEntry>, rcompare_common::RCompareError> {
-    match source {
-        ScanSource::Local { root } => scanner.scan(root),
-        ScanSource::Vfs { vfs, root } => scanner.scan_vfs(vfs.as_ref(), root),
+    snapshot_dir: Option<&Path>,
+    show_ignored: bool,
+) -> Result<FilteredScan, rcompare_common::RCompareError> {
+    if show_ignored {
+        return match source {
+            ScanSource::Local { root } => scanner.scan_with_ignored(root, None),
+            ScanSource::Vfs { vfs, root } => {
+                scanner.scan_vfs_with_ignored(vfs.as_ref(), root, None)
+            }
+        };
     }
+
+    let entries = match (source, snapshot_dir) {
+        (ScanSource::Local { root }, Some(dir)) => {
+            let snapshot_path = ScanSnapshot::path_in(dir, root);
+            let previous = ScanSnapshot::load(&snapshot_path);
+            let scan = scanner.scan_incremental(root, previous.as_ref())?;
+            info!(
+                "Incremental scan of {}: {} directories re-read",
+                root.display(),
+                scan.rescanned_dirs.len()
+            );
+            if let Err(e) = scan.snapshot.save(&snapshot_path) {
+                warn!("Failed to save scan snapshot: {}", e);
+            }
+            scan.entries
+        }
+        (ScanSource::Local { root }, None) => scanner.scan(root)?,
+        (ScanSource::Vfs { vfs, root }, _) => scanner.scan_vfs(vfs.as_ref(), root)?,
+    };
+    Ok(FilteredScan {
+        entries,
+        ignored: Vec::new(),
+    })
 }
 
 fn build_scan_source(path: &std::path::Path) -> Result<ScanSource, Box<dyn std::error::Error>> {
@@ -2435,105 +4143,21 @@ fn build_scan_source(path: &std::path::Path) -> Result<ScanSource, Box<dyn std::
     }
 
     if path.is_file() {
-        return match detect_archive_kind(path) {
-            Some(ArchiveKind::Zip) => Ok(ScanSource::Vfs {
-                vfs: Box::new(ZipVfs::new(path.to_path_buf())?),
-                root: PathBuf::new(),
-            }),
-            Some(ArchiveKind::Tar) => Ok(ScanSource::Vfs {
-                vfs: Box::new(TarVfs::new(path.to_path_buf())?),
-                root: PathBuf::new(),
-            }),
-            Some(ArchiveKind::SevenZ) => Ok(ScanSource::Vfs {
-                vfs: Box::new(SevenZVfs::new(path.to_path_buf())?),
-                root: PathBuf::new(),
-            }),
-            None => Err(format!(
-                "Path is not a directory or supported archive (.zip, .tar, .tar.gz, .tgz, .7z): {}",
-                path.display()
-            )
-            .into()),
-        };
+        return Ok(ScanSource::Vfs {
+            vfs: vfs::open(path, &VfsCredentials::default())?,
+            root: PathBuf::new(),
+        });
     }
 
     Err(format!("Path does not exist: {}", path.display()).into())
 }
 
-fn detect_archive_kind(path: &std::path::Path) -> Option<ArchiveKind> {
-    let name = path.file_name()?.to_string_lossy().to_lowercase();
-    if name.ends_with(".zip") {
-        Some(ArchiveKind::Zip)
-    } else if name.ends_with(".tar") || name.ends_with(".tar.gz") || name.ends_with(".tgz") {
-        Some(ArchiveKind::Tar)
-    } else if name.ends_with(".7z") {
-        Some(ArchiveKind::SevenZ)
-    } else {
-        None
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::path::Path;
     use std::time::{Duration, UNIX_EPOCH};
 
-    #[test]
-    fn test_detect_archive_kind_zip() {
-        assert!(matches!(
-            detect_archive_kind(Path::new("file.zip")),
-            Some(ArchiveKind::Zip)
-        ));
-        assert!(matches!(
-            detect_archive_kind(Path::new("FILE.ZIP")),
-            Some(ArchiveKind::Zip)
-        ));
-        assert!(matches!(
-            detect_archive_kind(Path::new("/path/to/archive.ZIP")),
-            Some(ArchiveKind::Zip)
-        ));
-    }
-
-    #[test]
-    fn test_detect_archive_kind_tar() {
-        assert!(matches!(
-            detect_archive_kind(Path::new("file.tar")),
-            Some(ArchiveKind::Tar)
-        ));
-        assert!(matches!(
-            detect_archive_kind(Path::new("file.tar.gz")),
-            Some(ArchiveKind::Tar)
-        ));
-        assert!(matches!(
-            detect_archive_kind(Path::new("file.tgz")),
-            Some(ArchiveKind::Tar)
-        ));
-        assert!(matches!(
-            detect_archive_kind(Path::new("FILE.TAR.GZ")),
-            Some(ArchiveKind::Tar)
-        ));
-    }
-
-    #[test]
-    fn test_detect_archive_kind_7z() {
-        assert!(matches!(
-            detect_archive_kind(Path::new("file.7z")),
-            Some(ArchiveKind::SevenZ)
-        ));
-        assert!(matches!(
-            detect_archive_kind(Path::new("FILE.7Z")),
-            Some(ArchiveKind::SevenZ)
-        ));
-    }
-
-    #[test]
-    fn test_detect_archive_kind_none() {
-        assert!(detect_archive_kind(Path::new("file.txt")).is_none());
-        assert!(detect_archive_kind(Path::new("file.rar")).is_none());
-        assert!(detect_archive_kind(Path::new("file")).is_none());
-        assert!(detect_archive_kind(Path::new("")).is_none());
-    }
-
     #[test]
     fn test_system_time_to_unix() {
         let time = UNIX_EPOCH + Duration::from_secs(1700000000);
@@ -2549,6 +4173,7 @@ mod tests {
             size: 1024,
             modified: UNIX_EPOCH + Duration::from_secs(1700000000),
             is_dir: false,
+            file_type: rcompare_common::FileType::Regular,
         };
 
         let side = json_side(&entry);
@@ -2564,6 +4189,7 @@ mod tests {
             size: 4096,
             modified: UNIX_EPOCH + Duration::from_secs(1600000000),
             is_dir: true,
+            file_type: rcompare_common::FileType::Directory,
         };
 
         let side = json_side(&entry);
@@ -2581,33 +4207,43 @@ mod tests {
             rcompare_common::DiffNode {
                 relative_path: PathBuf::from("same.txt"),
                 status: DiffStatus::Same,
+                similar_to: None,
+                reason: None,
+                ignored_by: None,
                 left: Some(rcompare_common::FileEntry {
                     path: PathBuf::from("same.txt"),
                     size: 100,
                     modified: UNIX_EPOCH,
                     is_dir: false,
+                    file_type: rcompare_common::FileType::Regular,
                 }),
                 right: Some(rcompare_common::FileEntry {
                     path: PathBuf::from("same.txt"),
                     size: 100,
                     modified: UNIX_EPOCH,
                     is_dir: false,
+                    file_type: rcompare_common::FileType::Regular,
                 }),
             },
             rcompare_common::DiffNode {
                 relative_path: PathBuf::from("diff.txt"),
                 status: DiffStatus::Different,
+                similar_to: None,
+                reason: None,
+                ignored_by: None,
                 left: Some(rcompare_common::FileEntry {
                     path: PathBuf::from("diff.txt"),
                     size: 100,
                     modified: UNIX_EPOCH,
                     is_dir: false,
+                    file_type: rcompare_common::FileType::Regular,
                 }),
                 right: Some(rcompare_common::FileEntry {
                     path: PathBuf::from("diff.txt"),
                     size: 200,
                     modified: UNIX_EPOCH,
                     is_dir: false,
+                    file_type: rcompare_common::FileType::Regular,
                 }),
             },
         ];
@@ -2648,27 +4284,36 @@ mod tests {
             rcompare_common::DiffNode {
                 relative_path: PathBuf::from("same.txt"),
                 status: DiffStatus::Same,
+                similar_to: None,
+                reason: None,
+                ignored_by: None,
                 left: Some(rcompare_common::FileEntry {
                     path: PathBuf::from("same.txt"),
                     size: 100,
                     modified: UNIX_EPOCH,
                     is_dir: false,
+                    file_type: rcompare_common::FileType::Regular,
                 }),
                 right: Some(rcompare_common::FileEntry {
                     path: PathBuf::from("same.txt"),
                     size: 100,
                     modified: UNIX_EPOCH,
                     is_dir: false,
+                    file_type: rcompare_common::FileType::Regular,
                 }),
             },
             rcompare_common::DiffNode {
                 relative_path: PathBuf::from("orphan.txt"),
                 status: DiffStatus::OrphanLeft,
+                similar_to: None,
+                reason: None,
+                ignored_by: None,
                 left: Some(rcompare_common::FileEntry {
                     path: PathBuf::from("orphan.txt"),
                     size: 100,
                     modified: UNIX_EPOCH,
                     is_dir: false,
+                    file_type: rcompare_common::FileType::Regular,
                 }),
                 right: None,
             },
@@ -2716,4 +4361,149 @@ mod tests {
         };
         assert!(source.vfs().is_none());
     }
+
+    fn row(left: Option<(usize, &str)>, right: Option<(usize, &str)>, changed: bool) -> AlignedRow {
+        let line = |(number, text): (usize, &str)| AlignedLine {
+            number,
+            text: text.to_string(),
+        };
+        AlignedRow {
+            left: left.map(line),
+            right: right.map(line),
+            changed,
+        }
+    }
+
+    #[test]
+    fn test_render_side_by_side_aligns_columns() {
+        let rows = vec![
+            row(Some((1, "fn main() {")), Some((1, "fn main() {")), false),
+            row(None, Some((2, "    init();")), true),
+            row(Some((2, "    run(1);")), Some((3, "    run(2);")), true),
+            row(Some((3, "    cleanup();")), None, true),
+            row(Some((4, "}")), Some((4, "}")), false),
+        ];
+        let lines = render_side_by_side(&rows, 41, false);
+
+        assert_eq!(
+            lines,
+            vec![
+                "1   fn main() {     | 1   fn main() {",
+                "                    | 2 +     init();",
+                "2 -     run(1);     | 3 +     run(2);",
+                "3 -     cleanup();  |",
+                "4   }               | 4   }",
+            ]
+        );
+        // The separator sits in the same column on every row
+        assert!(lines.iter().all(|line| line.find('|') == Some(20)));
+        assert!(lines.iter().all(|line| !line.contains('\x1b')));
+    }
+
+    #[test]
+    fn test_render_side_by_side_truncates_to_width() {
+        let long = "x".repeat(200);
+        let rows = vec![row(Some((9, &long)), Some((10, "short")), true)];
+
+        let plain = render_side_by_side(&rows, 60, false);
+        assert!(plain[0].chars().count() <= 60, "{}", plain[0]);
+        assert!(plain[0].contains("x…"));
+        assert!(plain[0].ends_with("10 + short"));
+
+        let colored = render_side_by_side(&rows, 60, true);
+        assert!(colored[0].contains("\x1b[31m- "));
+        assert!(colored[0].contains("\x1b[32m+ short"));
+    }
+
+    fn diff_stat(path: &str, insertions: usize, deletions: usize) -> TextDiffStats {
+        TextDiffStats {
+            path: PathBuf::from(path),
+            insertions,
+            deletions,
+        }
+    }
+
+    #[test]
+    fn test_render_diff_stat_counts_and_totals() {
+        let stats = vec![diff_stat("src/lib.rs", 3, 1), diff_stat("README.md", 0, 2)];
+
+        assert_eq!(
+            render_diff_stat(&stats, 80, false),
+            vec![
+                " src/lib.rs | 4 +++-",
+                " README.md  | 2 --",
+                " 2 files changed, 3 insertions(+), 3 deletions(-)",
+            ]
+        );
+
+        let colored = render_diff_stat(&stats, 80, true);
+        assert!(colored[0].ends_with("\x1b[32m+++\x1b[0m\x1b[31m-\x1b[0m"));
+
+        let single = render_diff_stat(&[diff_stat("a.txt", 1, 0)], 80, false);
+        assert_eq!(single[1], " 1 file changed, 1 insertion(+)");
+    }
+
+    #[test]
+    fn test_render_diff_stat_scales_bars_to_width() {
+        let stats = vec![diff_stat("big.txt", 100, 50), diff_stat("small.txt", 1, 0)];
+        let lines = render_diff_stat(&stats, 40, false);
+
+        assert_eq!(
+            lines[0],
+            format!(" big.txt   | 150 {}{}", "+".repeat(15), "-".repeat(8))
+        );
+        assert_eq!(lines[1], " small.txt |   1 +");
+        assert!(lines[0].chars().count() <= 40, "{}", lines[0]);
+        assert_eq!(
+            lines[2],
+            " 2 files changed, 101 insertions(+), 50 deletions(-)"
+        );
+    }
+
+    #[test]
+    fn test_scan_threads_and_no_cache_flags() {
+        let cli =
+            Cli::try_parse_from(["rcompare", "scan", "a", "b", "--threads", "4", "--no-cache"])
+                .unwrap();
+        let Commands::Scan {
+            threads, no_cache, ..
+        } = cli.command
+        else {
+            panic!("expected the scan command");
+        };
+        assert_eq!(threads, Some(4));
+        assert!(no_cache);
+
+        assert!(Cli::try_parse_from(["rcompare", "scan", "a", "b", "--threads", "0"]).is_err());
+        assert!(
+            Cli::try_parse_from(["rcompare", "scan", "a", "b", "--no-cache", "--incremental"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_apply_scan_overrides_sets_threads() {
+        let mut config = AppConfig::default();
+        apply_scan_overrides(&mut config, vec!["*.o".into()], false, true, None, Some(3));
+        assert_eq!(config.threads, Some(3));
+        assert_eq!(config.ignore_patterns, ["*.o"]);
+        assert!(!config.include_hidden);
+
+        let mut config = AppConfig::default();
+        apply_scan_overrides(&mut config, Vec::new(), false, false, None, None);
+        assert_eq!(config.threads, None);
+    }
+
+    #[test]
+    fn test_no_cache_uses_in_memory_hash_cache() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let cache_path = temp.path().join("cache");
+
+        let cache = scan_hash_cache(true, cache_path.clone());
+        assert!(!cache.is_persistent());
+        cache.persist().unwrap();
+        assert!(!cache_path.exists());
+
+        assert!(scan_hash_cache(false, cache_path).is_persistent());
+    }
 }
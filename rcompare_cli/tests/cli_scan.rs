@@ -167,6 +167,235 @@ fn scan_json_basic_statuses() {
     );
 }
 
+#[test]
+fn scan_events_log_ends_with_matching_summary() {
+    let left = TempDir::new().expect("left dir");
+    let right = TempDir::new().expect("right dir");
+    let out = TempDir::new().expect("output dir");
+
+    fs::write(left.path().join("diff.txt"), "abc").unwrap();
+    fs::write(right.path().join("diff.txt"), "abcd").unwrap();
+    fs::write(left.path().join("left_only.txt"), "left").unwrap();
+    fs::write(right.path().join("right_only.txt"), "right").unwrap();
+
+    let events_path = out.path().join("events.jsonl");
+    let report = run_cli_json(&[
+        "scan",
+        left.path().to_str().unwrap(),
+        right.path().to_str().unwrap(),
+        "--json",
+        "--events",
+        events_path.to_str().unwrap(),
+    ]);
+
+    let events: Vec<Value> = fs::read_to_string(&events_path)
+        .expect("events file")
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("invalid event line"))
+        .collect();
+    let kinds: Vec<&str> = events
+        .iter()
+        .map(|e| e["event"].as_str().unwrap())
+        .collect();
+    assert_eq!(kinds.first(), Some(&"scan_started"));
+    assert_eq!(kinds.last(), Some(&"summary"));
+    assert_eq!(
+        kinds.iter().filter(|k| **k == "entry_compared").count(),
+        report["entries"].as_array().unwrap().len()
+    );
+    assert!(events.iter().any(|e| e["event"] == "entry_compared"
+        && e["path"] == "left_only.txt"
+        && e["status"] == "OrphanLeft"));
+
+    let summary = events.last().unwrap();
+    for key in [
+        "total",
+        "same",
+        "different",
+        "orphan_left",
+        "orphan_right",
+        "unchecked",
+    ] {
+        assert_eq!(summary[key], report["summary"][key], "{key}");
+    }
+}
+
+#[test]
+fn scan_events_report_each_path_once_with_its_final_status() {
+    let left = TempDir::new().expect("left dir");
+    let right = TempDir::new().expect("right dir");
+    let out = TempDir::new().expect("output dir");
+
+    // Ignored on the left only, so the right side's orphan node is taken
+    // over as ignored after the comparison
+    fs::write(left.path().join(".gitignore"), "hidden.txt\n").unwrap();
+    fs::write(left.path().join("hidden.txt"), "left").unwrap();
+    fs::write(right.path().join("hidden.txt"), "right").unwrap();
+    fs::write(left.path().join("same.txt"), "same").unwrap();
+    fs::write(right.path().join("same.txt"), "same").unwrap();
+
+    let events_path = out.path().join("events.jsonl");
+    run_cli_json(&[
+        "scan",
+        left.path().to_str().unwrap(),
+        right.path().to_str().unwrap(),
+        "--json",
+        "--show-ignored",
+        "--events",
+        events_path.to_str().unwrap(),
+    ]);
+
+    let compared: Vec<(String, String)> = fs::read_to_string(&events_path)
+        .expect("events file")
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).expect("invalid event line"))
+        .filter(|e| e["event"] == "entry_compared")
+        .map(|e| {
+            (
+                e["path"].as_str().unwrap().to_string(),
+                e["status"].as_str().unwrap().to_string(),
+            )
+        })
+        .collect();
+    let hidden: Vec<&str> = compared
+        .iter()
+        .filter(|(path, _)| path == "hidden.txt")
+        .map(|(_, status)| status.as_str())
+        .collect();
+    assert_eq!(hidden, vec!["Ignored"]);
+    assert!(compared.contains(&("same.txt".to_string(), "Same".to_string())));
+}
+
+#[test]
+fn scan_glob_pairs_prints_summary_per_pair() {
+    let temp = TempDir::new().expect("temp dir");
+    for (side, module, name, contents) in [
+        ("projectA", "api", "lib.rs", "fn a() {}"),
+        ("projectB", "api", "lib.rs", "fn a() {}"),
+        ("projectA", "web", "index.html", "<p>left</p>"),
+        ("projectB", "web", "index.html", "<p>right!</p>"),
+    ] {
+        let dir = temp.path().join(side).join(module);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(name), contents).unwrap();
+    }
+    fs::write(temp.path().join("projectB/web/extra.css"), "p {}").unwrap();
+
+    let exe = env!("CARGO_BIN_EXE_rcompare_cli");
+    let output = Command::new(exe)
+        .args([
+            "scan",
+            "--glob",
+            "--verify-hashes",
+            "--no-color",
+            temp.path().join("projectA/*").to_str().unwrap(),
+            temp.path().join("projectB/*").to_str().unwrap(),
+        ])
+        .env("XDG_CONFIG_HOME", temp.path())
+        .env("XDG_CACHE_HOME", temp.path())
+        .env("HOME", temp.path())
+        .output()
+        .expect("failed to run rcompare_cli");
+
+    assert_eq!(output.status.code(), Some(2));
+    let stdout = String::from_utf8(output.stdout).expect("stdout not utf-8");
+    let summary = stdout
+        .split("Pair summary:")
+        .nth(1)
+        .expect("missing pair summary");
+    let lines: Vec<&str> = summary.lines().filter(|l| !l.is_empty()).collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].contains("projectA/api <-> "));
+    assert!(lines[0].contains(": 1 identical, 0 different, 0 left-only, 0 right-only"));
+    assert!(lines[1].contains("projectA/web <-> "));
+    assert!(lines[1].contains(": 0 identical, 1 different, 0 left-only, 1 right-only"));
+}
+
+#[test]
+fn scan_pairs_support_json_patch_and_events() {
+    let temp = TempDir::new().expect("temp dir");
+    for (side, module, name, contents) in [
+        ("projectA", "api", "lib.rs", "fn a() {}\n"),
+        ("projectB", "api", "lib.rs", "fn b() {}\n"),
+        ("projectA", "web", "index.html", "<p>same</p>\n"),
+        ("projectB", "web", "index.html", "<p>same</p>\n"),
+    ] {
+        let dir = temp.path().join(side).join(module);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(name), contents).unwrap();
+    }
+    fs::write(temp.path().join("projectB/web/extra.css"), "p {}\n").unwrap();
+    let patch_path = temp.path().join("pairs.patch");
+    let events_path = temp.path().join("events.jsonl");
+
+    let report = run_cli_json(&[
+        "scan",
+        "--glob",
+        "--verify-hashes",
+        "--json",
+        temp.path().join("projectA/*").to_str().unwrap(),
+        temp.path().join("projectB/*").to_str().unwrap(),
+        "--patch",
+        patch_path.to_str().unwrap(),
+        "--events",
+        events_path.to_str().unwrap(),
+    ]);
+
+    let pairs = report["pairs"].as_array().expect("one report per pair");
+    assert_eq!(pairs.len(), 2);
+    assert!(pairs[0]["left"].as_str().unwrap().ends_with("api"));
+    assert_eq!(pairs[0]["summary"]["different"], 1);
+    assert!(pairs[1]["left"].as_str().unwrap().ends_with("web"));
+    assert_eq!(pairs[1]["summary"]["same"], 1);
+    assert_eq!(pairs[1]["summary"]["orphan_right"], 1);
+
+    let patch = fs::read_to_string(&patch_path).expect("patch file");
+    assert!(patch.contains("--- a/api/lib.rs\n+++ b/api/lib.rs\n"));
+    assert!(patch.contains("--- /dev/null\n+++ b/web/extra.css\n"));
+
+    let events = fs::read_to_string(&events_path).expect("events file");
+    let kinds: Vec<String> = events
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).expect("invalid event line"))
+        .map(|e| e["event"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(kinds.iter().filter(|k| *k == "scan_started").count(), 2);
+    assert_eq!(kinds.iter().filter(|k| *k == "summary").count(), 2);
+}
+
+#[test]
+fn scan_pairs_reject_per_file_diffs() {
+    let temp = TempDir::new().expect("temp dir");
+    for dir in ["a", "b", "c", "d"] {
+        fs::create_dir_all(temp.path().join(dir)).unwrap();
+    }
+    let path = |dir: &str| temp.path().join(dir).to_str().unwrap().to_string();
+
+    let exe = env!("CARGO_BIN_EXE_rcompare_cli");
+    let output = Command::new(exe)
+        .args([
+            "scan",
+            &path("a"),
+            &path("b"),
+            "--pair",
+            &path("c"),
+            &path("d"),
+        ])
+        .arg("--text-diff")
+        .env("XDG_CONFIG_HOME", temp.path())
+        .env("XDG_CACHE_HOME", temp.path())
+        .env("HOME", temp.path())
+        .output()
+        .expect("failed to run rcompare_cli");
+
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("--text-diff needs a single left/right pair"),
+        "{stderr}"
+    );
+}
+
 #[test]
 fn scan_json_diff_only_filters_same() {
     let left = TempDir::new().expect("left dir");
@@ -242,6 +471,75 @@ fn scan_json_verify_hashes_detects_same_size_changes() {
     );
 }
 
+/// A profile's settings apply to the run without being saved as defaults
+#[cfg(target_os = "linux")]
+#[test]
+fn scan_profile_applies_mtime_tolerance_for_the_run_only() {
+    let temp = TempDir::new().expect("temp dir");
+    let left = temp.path().join("left");
+    let right = temp.path().join("right");
+    fs::create_dir_all(&left).unwrap();
+    fs::create_dir_all(&right).unwrap();
+    fs::write(left.join("a.txt"), "same").unwrap();
+    fs::write(right.join("a.txt"), "same").unwrap();
+    set_file_mtime(
+        left.join("a.txt"),
+        FileTime::from_unix_time(1_700_000_000, 0),
+    )
+    .unwrap();
+    set_file_mtime(
+        right.join("a.txt"),
+        FileTime::from_unix_time(1_700_000_003, 0),
+    )
+    .unwrap();
+
+    let config_dir = temp.path().join("config");
+    let config_file = config_dir.join("rcompare").join("rcompare.toml");
+    fs::create_dir_all(config_file.parent().unwrap()).unwrap();
+    let config = format!(
+        r#"
+[[profiles]]
+name = "tolerant"
+left_path = "{}"
+right_path = "{}"
+ignore_patterns = []
+last_used = 0
+
+[profiles.settings]
+mtime_tolerance_secs = 5
+"#,
+        left.display(),
+        right.display()
+    );
+    fs::write(&config_file, &config).unwrap();
+
+    let scan = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_rcompare_cli"))
+            .args(args)
+            .env("XDG_CONFIG_HOME", &config_dir)
+            .env("XDG_CACHE_HOME", temp.path().join("cache"))
+            .env("HOME", temp.path())
+            .output()
+            .expect("failed to run rcompare_cli");
+        let report: Value = serde_json::from_slice(&output.stdout).expect("invalid json output");
+        entries_by_path(&report).get("a.txt").cloned()
+    };
+
+    let with_profile = scan(&[
+        "scan",
+        "--profile",
+        "tolerant",
+        "--no-verify-hashes",
+        "--json",
+    ]);
+    assert_eq!(with_profile.as_deref(), Some("Same"));
+
+    let (left, right) = (left.to_str().unwrap(), right.to_str().unwrap());
+    let without = scan(&["scan", left, right, "--no-verify-hashes", "--json"]);
+    assert_ne!(without.as_deref(), Some("Same"));
+    assert_eq!(fs::read_to_string(&config_file).unwrap(), config);
+}
+
 #[test]
 fn scan_json_zip_archives() {
     let temp = TempDir::new().expect("temp dir");
@@ -285,6 +583,201 @@ fn scan_json_zip_archives() {
     );
 }
 
+#[test]
+fn scan_zip_archives_reports_compressed_sizes() {
+    let temp = TempDir::new().expect("temp dir");
+    let left_zip = temp.path().join("left.zip");
+    let right_zip = temp.path().join("right.zip");
+    let random: String = (0..2000u32)
+        .map(|i| char::from(b'a' + (i.wrapping_mul(2654435761) >> 13) as u8 % 26))
+        .collect();
+
+    create_zip_archive(&left_zip, &[("data.txt", &"a".repeat(2000))]);
+    create_zip_archive(&right_zip, &[("data.txt", &random)]);
+
+    let exe = env!("CARGO_BIN_EXE_rcompare_cli");
+    let output = Command::new(exe)
+        .args([
+            "scan",
+            "--no-color",
+            left_zip.to_str().unwrap(),
+            right_zip.to_str().unwrap(),
+        ])
+        .env("XDG_CONFIG_HOME", temp.path())
+        .env("XDG_CACHE_HOME", temp.path())
+        .env("HOME", temp.path())
+        .output()
+        .expect("failed to run rcompare_cli");
+
+    let stdout = String::from_utf8(output.stdout).expect("stdout not utf-8");
+    let line = stdout
+        .lines()
+        .find(|l| l.trim_start().starts_with("Compressed:"))
+        .expect("missing compressed size line");
+    assert!(line.contains("right is"), "{line}");
+    assert!(line.contains("% larger"), "{line}");
+}
+
+#[test]
+fn scan_reports_case_collisions() {
+    let temp = TempDir::new().expect("temp dir");
+    let left = temp.path().join("left");
+    let right = temp.path().join("right");
+    fs::create_dir_all(&left).unwrap();
+    fs::create_dir_all(&right).unwrap();
+    fs::write(left.join("README"), "upper").unwrap();
+    fs::write(left.join("readme"), "lower").unwrap();
+    fs::write(right.join("README"), "upper").unwrap();
+
+    let exe = env!("CARGO_BIN_EXE_rcompare_cli");
+    let output = Command::new(exe)
+        .args([
+            "scan",
+            "--no-color",
+            left.to_str().unwrap(),
+            right.to_str().unwrap(),
+        ])
+        .env("XDG_CONFIG_HOME", temp.path())
+        .env("XDG_CACHE_HOME", temp.path())
+        .env("HOME", temp.path())
+        .output()
+        .expect("failed to run rcompare_cli");
+
+    let stdout = String::from_utf8(output.stdout).expect("stdout not utf-8");
+    assert!(stdout.contains("Case collisions: 1"), "{stdout}");
+    assert!(stdout
+        .contains("README (left, right), readme (left) - clashes on case-insensitive filesystems"));
+}
+
+#[test]
+fn scan_text_diff_ignores_matching_lines() {
+    let temp = TempDir::new().expect("temp dir");
+    let left = temp.path().join("left");
+    let right = temp.path().join("right");
+    fs::create_dir_all(&left).unwrap();
+    fs::create_dir_all(&right).unwrap();
+    fs::write(
+        left.join("app.conf"),
+        "# built 2024-01-01\nname = app\nport = 80\n",
+    )
+    .unwrap();
+    fs::write(
+        right.join("app.conf"),
+        "# built 2025-06-30\nname = app\nport = 8080\n",
+    )
+    .unwrap();
+
+    let report = run_cli_json(&[
+        "scan",
+        left.to_str().unwrap(),
+        right.to_str().unwrap(),
+        "--json",
+        "--text-diff",
+        "--ignore-matching-lines",
+        "^#",
+    ]);
+
+    let diffs = report["text_diffs"].as_array().expect("text_diffs array");
+    assert_eq!(diffs.len(), 1);
+    let diff = &diffs[0];
+    assert_eq!(diff["path"], "app.conf");
+    assert_eq!(diff["equal_lines"], 2);
+    assert_eq!(diff["deleted_lines"], 1);
+    assert_eq!(diff["inserted_lines"], 1);
+}
+
+#[test]
+fn scan_text_diff_columns_shows_files_side_by_side() {
+    let temp = TempDir::new().expect("temp dir");
+    let left = temp.path().join("left");
+    let right = temp.path().join("right");
+    fs::create_dir_all(&left).unwrap();
+    fs::create_dir_all(&right).unwrap();
+    fs::write(left.join("notes.txt"), "alpha\nold line\n").unwrap();
+    fs::write(right.join("notes.txt"), "alpha\nnew line\nextra\n").unwrap();
+
+    let exe = env!("CARGO_BIN_EXE_rcompare_cli");
+    let output = Command::new(exe)
+        .args([
+            "scan",
+            "--no-color",
+            "--columns",
+            "--text-diff",
+            left.to_str().unwrap(),
+            right.to_str().unwrap(),
+        ])
+        .env("XDG_CONFIG_HOME", temp.path())
+        .env("XDG_CACHE_HOME", temp.path())
+        .env("HOME", temp.path())
+        .output()
+        .expect("failed to run rcompare_cli");
+
+    let stdout = String::from_utf8(output.stdout).expect("stdout not utf-8");
+    assert!(!stdout.contains('\x1b'), "{stdout}");
+    let rows: Vec<&str> = stdout.lines().filter(|l| l.contains(" | ")).collect();
+    assert_eq!(rows.len(), 3, "{stdout}");
+    assert!(rows[0].starts_with("1   alpha") && rows[0].ends_with("| 1   alpha"));
+    assert!(rows[1].starts_with("2 - old line") && rows[1].ends_with("| 2 + new line"));
+    assert_eq!(rows[2].trim_start(), "| 3 + extra");
+}
+
+/// 1x1 RGB images, one red and one blue
+const RED_PNG: &[u8] = &[
+    0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, 0x00, 0x00, 0x00, 0x0D, 0x49, 0x48, 0x44, 0x52,
+    0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x08, 0x02, 0x00, 0x00, 0x00, 0x90, 0x77, 0x53,
+    0xDE, 0x00, 0x00, 0x00, 0x0C, 0x49, 0x44, 0x41, 0x54, 0x78, 0x9C, 0x63, 0xF8, 0xCF, 0xC0, 0x00,
+    0x00, 0x03, 0x01, 0x01, 0x00, 0xC9, 0xFE, 0x92, 0xEF, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45, 0x4E,
+    0x44, 0xAE, 0x42, 0x60, 0x82,
+];
+const BLUE_PNG: &[u8] = &[
+    0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, 0x00, 0x00, 0x00, 0x0D, 0x49, 0x48, 0x44, 0x52,
+    0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x01, 0x08, 0x02, 0x00, 0x00, 0x00, 0x90, 0x77, 0x53,
+    0xDE, 0x00, 0x00, 0x00, 0x0C, 0x49, 0x44, 0x41, 0x54, 0x78, 0x9C, 0x63, 0x60, 0x60, 0xF8, 0x0F,
+    0x00, 0x01, 0x03, 0x01, 0x00, 0x08, 0x89, 0xC2, 0xEC, 0x00, 0x00, 0x00, 0x00, 0x49, 0x45, 0x4E,
+    0x44, 0xAE, 0x42, 0x60, 0x82,
+];
+
+#[test]
+fn scan_auto_routes_each_file_to_its_engine() {
+    let temp = TempDir::new().expect("temp dir");
+    let left = temp.path().join("left");
+    let right = temp.path().join("right");
+    fs::create_dir_all(&left).unwrap();
+    fs::create_dir_all(&right).unwrap();
+    fs::write(left.join("config.json"), r#"{"port": 80}"#).unwrap();
+    fs::write(right.join("config.json"), r#"{"port": 8080}"#).unwrap();
+    fs::write(left.join("logo.png"), RED_PNG).unwrap();
+    fs::write(right.join("logo.png"), BLUE_PNG).unwrap();
+    fs::write(left.join("people.csv"), "name,age\nAlice,30\n").unwrap();
+    fs::write(right.join("people.csv"), "name,age\nAlice,31\n").unwrap();
+    fs::write(left.join("notes.txt"), "old\n").unwrap();
+    fs::write(right.join("notes.txt"), "new\n").unwrap();
+
+    let report = run_cli_json(&[
+        "scan",
+        left.to_str().unwrap(),
+        right.to_str().unwrap(),
+        "--json",
+        "--verify-hashes",
+        "--auto",
+    ]);
+
+    let paths = |key: &str| -> Vec<String> {
+        report[key]
+            .as_array()
+            .unwrap_or_else(|| panic!("{key} array in {report}"))
+            .iter()
+            .map(|diff| diff["path"].as_str().unwrap().to_string())
+            .collect()
+    };
+    assert_eq!(paths("json_diffs"), ["config.json"]);
+    assert_eq!(paths("image_diffs"), ["logo.png"]);
+    assert_eq!(paths("csv_diffs"), ["people.csv"]);
+    assert_eq!(paths("text_diffs"), ["notes.txt"]);
+    assert!(report.get("yaml_diffs").is_none());
+    assert!(report.get("excel_diffs").is_none());
+}
+
 #[test]
 fn scan_json_tar_gz_archive_vs_directory() {
     let temp = TempDir::new().expect("temp dir");
@@ -410,6 +410,25 @@ fn test_ignore_patterns() {
     assert!(!result_text.contains("test.log"));
 }
 
+#[test]
+fn test_invalid_ignore_pattern_is_an_error() {
+    let fixture = TestFixture::new();
+    fixture.create_left_file("file.txt", "Text");
+    fixture.create_right_file("file.txt", "Text");
+
+    let output = run_cli(&[
+        "scan",
+        fixture.left().to_str().unwrap(),
+        fixture.right().to_str().unwrap(),
+        "--ignore",
+        "{*.log",
+    ]);
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("ignore pattern '{*.log' is not a valid glob"));
+}
+
 #[test]
 fn test_multiple_ignore_patterns() {
     let fixture = TestFixture::new();
@@ -906,3 +925,359 @@ fn test_right_gitignore_ignored() {
     // Should show only 1 right-only file (.gitignore)
     assert!(stdout.contains("Right only:") && stdout.contains("1"));
 }
+
+#[test]
+fn test_flag_emptied_files() {
+    let fixture = TestFixture::new();
+
+    fixture.create_left_file("truncated.log", "important data");
+    fixture.create_right_file("truncated.log", "");
+    fixture.create_left_file("empty.txt", "");
+    fixture.create_right_file("empty.txt", "");
+
+    let output = run_cli_success(&[
+        "scan",
+        fixture.left().to_str().unwrap(),
+        fixture.right().to_str().unwrap(),
+        "--json",
+        "--flag-emptied",
+    ]);
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let json: serde_json::Value =
+        serde_json::from_str(&stdout).expect("Output should be valid JSON");
+    let entries = json.get("entries").unwrap().as_array().unwrap();
+    let entry = |path: &str| entries.iter().find(|e| e["path"] == path).unwrap();
+
+    assert_eq!(entry("truncated.log")["status"], "Different");
+    assert_eq!(entry("truncated.log")["reason"], "EmptiedFile");
+    assert_eq!(entry("empty.txt")["status"], "Same");
+    assert!(entry("empty.txt").get("reason").is_none());
+}
+
+#[test]
+fn test_incremental_scan_sees_new_files() {
+    let fixture = TestFixture::new();
+    let cache = fixture.left().parent().unwrap().join("cache");
+
+    fixture.create_left_file("sub/a.txt", "A");
+    fixture.create_right_file("sub/a.txt", "A");
+
+    let scan = || {
+        let output = run_cli_success(&[
+            "scan",
+            fixture.left().to_str().unwrap(),
+            fixture.right().to_str().unwrap(),
+            "--incremental",
+            "--cache-dir",
+            cache.to_str().unwrap(),
+        ]);
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    let first = scan();
+    assert!(first.contains("a.txt"));
+    assert!(cache.join("scan_snapshots").is_dir());
+
+    fixture.create_left_file("sub/b.txt", "B");
+    let second = scan();
+    assert!(second.contains("b.txt"));
+    assert!(second.contains("Left only:") && second.contains("1"));
+}
+
+#[test]
+fn test_timing_reports_cache_hits_on_rerun() {
+    let fixture = TestFixture::new();
+    let cache = fixture.left().parent().unwrap().join("cache");
+
+    fixture.create_left_file("same.txt", "identical");
+    fixture.create_right_file("same.txt", "identical");
+
+    let scan = || {
+        let output = run_cli_success(&[
+            "scan",
+            fixture.left().to_str().unwrap(),
+            fixture.right().to_str().unwrap(),
+            "--json",
+            "--timing",
+            "--verify-hashes",
+            "--cache-dir",
+            cache.to_str().unwrap(),
+        ]);
+        let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+        json["timing"].clone()
+    };
+
+    let first = scan();
+    assert_eq!(first["cache_hits"], 0);
+    assert_eq!(first["cache_misses"], 2);
+    assert!(first["bytes_hashed"].as_u64().unwrap() >= 18);
+    assert!(first["scan_left_ms"].is_u64() && first["compare_ms"].is_u64());
+
+    let second = scan();
+    assert_eq!(second["cache_hits"], 2);
+    assert_eq!(second["cache_misses"], 0);
+}
+
+#[test]
+fn test_timing_block_only_with_flag() {
+    let fixture = TestFixture::new();
+    fixture.create_left_file("a.txt", "A");
+    fixture.create_right_file("a.txt", "A");
+    let left = fixture.left().to_str().unwrap();
+    let right = fixture.right().to_str().unwrap();
+
+    let output = run_cli_success(&["scan", left, right, "--timing"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Timing:"));
+    assert!(stdout.contains("Scan left:") && stdout.contains("Hash cache:"));
+
+    let output = run_cli_success(&["scan", left, right]);
+    assert!(!String::from_utf8_lossy(&output.stdout).contains("Timing:"));
+}
+
+#[test]
+fn test_show_ignored_lists_filtered_entries_with_rule() {
+    let fixture = TestFixture::new();
+    fixture.create_left_file("same.txt", "identical");
+    fixture.create_right_file("same.txt", "identical");
+    fixture.create_left_file("debug.log", "left only, but ignored");
+    let left = fixture.left().to_str().unwrap();
+    let right = fixture.right().to_str().unwrap();
+
+    let scan = |extra: &[&str]| {
+        let mut args = vec!["scan", left, right, "--json", "-i", "*.log"];
+        args.extend_from_slice(extra);
+        let output = run_cli_success(&args);
+        serde_json::from_slice::<serde_json::Value>(&output.stdout).unwrap()
+    };
+
+    // Dropped by default
+    let json = scan(&[]);
+    assert_eq!(json["entries"].as_array().unwrap().len(), 1);
+    assert!(json["summary"].get("ignored").is_none());
+
+    let json = scan(&["--show-ignored"]);
+    let entries = json["entries"].as_array().unwrap();
+    let log = entries.iter().find(|e| e["path"] == "debug.log").unwrap();
+    assert_eq!(log["status"], "Ignored");
+    assert_eq!(log["ignored_by"], "*.log");
+    assert!(log["left"].is_object() && log["right"].is_null());
+    assert_eq!(json["summary"]["ignored"], 1);
+
+    let output = run_cli_success(&["scan", left, right, "-i", "*.log", "--show-ignored"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("debug.log  (ignored by *.log)"));
+    // Ignored entries don't count as differences
+    assert_eq!(output.status.code(), Some(0));
+}
+
+#[test]
+fn test_path_rule_pairs_hashed_asset_names() {
+    let fixture = TestFixture::new();
+    fixture.create_left_file("assets/app.abc12345.js", "console.log(1);");
+    fixture.create_right_file("assets/app.def67890.js", "console.log(1);");
+    fixture.create_left_file("assets/chunk.11111111.js", "a");
+    fixture.create_left_file("assets/chunk.22222222.js", "b");
+
+    let output = run_cli_success(&[
+        "scan",
+        fixture.left().to_str().unwrap(),
+        fixture.right().to_str().unwrap(),
+        "--json",
+        "--verify-hashes",
+        "--path-rule",
+        r"\.[0-9a-f]{8}\.js$:.js",
+    ]);
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+
+    let entries = json["entries"].as_array().unwrap();
+    let app = entries
+        .iter()
+        .find(|e| e["path"] == "assets/app.abc12345.js")
+        .unwrap();
+    assert_eq!(app["status"], "Same");
+    assert!(app["right"].is_object());
+    assert_eq!(json["summary"]["orphan_right"], 0);
+
+    let collisions = json["path_collisions"].as_array().unwrap();
+    assert_eq!(collisions.len(), 1);
+    assert_eq!(collisions[0]["transformed"], "assets/chunk.js");
+    assert_eq!(collisions[0]["left"].as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn test_path_rule_patch_reads_each_side_under_its_own_name() {
+    let fixture = TestFixture::new();
+    fixture.create_left_file("assets/app.abc12345.js", "console.log(1);\n");
+    fixture.create_right_file("assets/app.def67890.js", "console.log(2);\n");
+    let patch = fixture.left().parent().unwrap().join("out.diff");
+
+    let output = run_cli(&[
+        "scan",
+        fixture.left().to_str().unwrap(),
+        fixture.right().to_str().unwrap(),
+        "--verify-hashes",
+        "--path-rule",
+        r"\.[0-9a-f]{8}\.js$:.js",
+        "--patch",
+        patch.to_str().unwrap(),
+    ]);
+    assert_eq!(output.status.code(), Some(2), "{:?}", output);
+
+    let patch = fs::read_to_string(patch).unwrap();
+    assert!(patch.contains("--- a/assets/app.abc12345.js"), "{}", patch);
+    assert!(patch.contains("+++ b/assets/app.def67890.js"), "{}", patch);
+    assert!(patch.contains("-console.log(1);"), "{}", patch);
+    assert!(patch.contains("+console.log(2);"), "{}", patch);
+}
+
+#[test]
+fn test_sync_mirror_dry_run_then_apply() {
+    let fixture = TestFixture::new();
+
+    fixture.create_left_file("keep.txt", "left");
+    fixture.create_left_file("new/added.txt", "added");
+    fixture.create_right_file("keep.txt", "stale right");
+    fixture.create_right_file("obsolete.txt", "remove me");
+
+    let left = fixture.left().to_str().unwrap();
+    let right = fixture.right().to_str().unwrap();
+
+    let output = run_cli(&["sync", left, right, "--mode", "mirror", "--dry-run"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("2 files would be copied, 1 would be deleted"),
+        "{}",
+        stdout
+    );
+    // Each planned action is listed with its size
+    assert!(
+        stdout.contains("copy L→R  keep.txt (4 bytes)"),
+        "{}",
+        stdout
+    );
+    assert!(
+        stdout.contains("delete    obsolete.txt (9 bytes)"),
+        "{}",
+        stdout
+    );
+    assert!(fixture.right().join("obsolete.txt").exists());
+    assert!(!fixture.right().join("new/added.txt").exists());
+
+    let output = run_cli(&["sync", left, right, "--mode", "mirror"]);
+    assert!(output.status.success());
+    assert!(!fixture.right().join("obsolete.txt").exists());
+    assert_eq!(
+        fs::read_to_string(fixture.right().join("new/added.txt")).unwrap(),
+        "added"
+    );
+    assert_eq!(
+        fs::read_to_string(fixture.right().join("keep.txt")).unwrap(),
+        "left"
+    );
+}
+
+#[test]
+fn test_manifest_is_sorted_and_deterministic() {
+    let fixture = TestFixture::new();
+    fixture.create_left_file("z.txt", "last");
+    fixture.create_left_file("bin/app", "release build");
+    fixture.create_left_file("a.txt", "first");
+    let left = fixture.left().to_str().unwrap();
+
+    let output = run_cli(&["manifest", left, "--algorithm", "sha256"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    let paths: Vec<&str> = stdout
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .map(|line| line.split('\t').next().unwrap())
+        .collect();
+    assert_eq!(paths, ["a.txt", "bin/app", "z.txt"]);
+    assert!(stdout
+        .contains("\tsha256:a7937b64b8caa58f03721bb6bacf5c78cb235febe0e70b1b84cd99541461a08e\n"));
+
+    let again = run_cli(&["manifest", left, "--algorithm", "sha256"]);
+    assert_eq!(String::from_utf8_lossy(&again.stdout), stdout);
+
+    let file = fixture.right().join("release.manifest");
+    let output = run_cli(&["manifest", left, "--output", file.to_str().unwrap()]);
+    assert!(output.status.success());
+    let written = fs::read_to_string(&file).unwrap();
+    assert_eq!(written.lines().count(), 4);
+    assert!(written.contains(&format!(
+        "a.txt\t5\t{}",
+        fs::metadata(fixture.left().join("a.txt"))
+            .unwrap()
+            .modified()
+            .unwrap()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+    )));
+}
+
+/// Run the CLI with `stdin` piped to it
+fn run_cli_with_stdin(args: &[&str], stdin: &str) -> std::process::Output {
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let exe = env!("CARGO_BIN_EXE_rcompare_cli");
+    let config_dir = TempDir::new().expect("Failed to create config dir");
+    let mut child = Command::new(exe)
+        .args(args)
+        .env("XDG_CONFIG_HOME", config_dir.path())
+        .env("HOME", config_dir.path())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to execute command");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child
+        .wait_with_output()
+        .expect("Failed to wait for command")
+}
+
+#[test]
+fn test_diff_stdin_against_file() {
+    let fixture = TestFixture::new();
+    let right = fixture.create_right_file("b.txt", "one\ntwo\nthree\n");
+    let right = right.to_str().unwrap();
+
+    let output = run_cli_with_stdin(&["diff", "-", right], "one\n2\nthree\n");
+    assert_eq!(output.status.code(), Some(2));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains(&format!("--- -\n+++ {}\n", right)));
+    assert!(stdout.contains("@@ -1,3 +1,3 @@\n one\n-2\n+two\n three\n"));
+
+    let output = run_cli_with_stdin(&["diff", right, "-"], "one\ntwo\nthree\n");
+    assert_eq!(output.status.code(), Some(0));
+    assert!(output.stdout.is_empty());
+
+    // Stdin without an extension is routed by content, or by --as
+    let json = fixture.create_right_file("b.conf", "{\"name\": \"b\", \"port\": 80}");
+    let json = json.to_str().unwrap();
+    let output = run_cli_with_stdin(&["diff", "-", json], "{\"name\": \"a\", \"port\": 80}");
+    assert_eq!(output.status.code(), Some(2));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("name: \"a\" -> \"b\""), "{}", stdout);
+
+    let yaml = fixture.create_right_file("c.yaml", "name: b\n");
+    let output = run_cli_with_stdin(
+        &["diff", "--as", "yaml", "-", yaml.to_str().unwrap()],
+        "name: a\n",
+    );
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("name: \"a\" -> \"b\""));
+
+    let output = run_cli_with_stdin(&["diff", "-", "-"], "");
+    assert_eq!(output.status.code(), Some(1));
+}
@@ -1,5 +1,6 @@
 use crate::{AppConfig, RCompareError};
 use directories::ProjectDirs;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
 use std::fs;
 use std::path::{Path, PathBuf};
 
@@ -18,8 +19,7 @@ pub fn load_config(prefer_portable: bool) -> Result<LoadedConfig, RCompareError>
     let exists = path.exists();
 
     let mut config = if exists {
-        let data = fs::read_to_string(&path)?;
-        toml::from_str(&data).map_err(|e| RCompareError::Serialization(e.to_string()))?
+        read_config(&path)?
     } else {
         AppConfig::default()
     };
@@ -42,6 +42,53 @@ pub fn ensure_config(prefer_portable: bool) -> Result<LoadedConfig, RCompareErro
     Ok(loaded)
 }
 
+/// Read the configuration stored at `path`
+///
+/// Ignore patterns, including those of every profile, are compiled so that
+/// an invalid glob is reported here rather than skipped during a scan.
+pub fn read_config(path: &Path) -> Result<AppConfig, RCompareError> {
+    let data = fs::read_to_string(path)?;
+    let config: AppConfig =
+        toml::from_str(&data).map_err(|e| RCompareError::Serialization(e.to_string()))?;
+
+    let located = |context: String, e: RCompareError| match e {
+        RCompareError::Config(msg) => {
+            RCompareError::Config(format!("{}: {}{}", path.display(), context, msg))
+        }
+        e => e,
+    };
+    compile_ignore_patterns(&config.ignore_patterns).map_err(|e| located(String::new(), e))?;
+    for profile in &config.profiles {
+        compile_ignore_patterns(&profile.ignore_patterns)
+            .map_err(|e| located(format!("profile '{}': ", profile.name), e))?;
+    }
+    Ok(config)
+}
+
+/// Compile gitignore-style patterns into one matcher
+///
+/// Returns `None` for an empty list and an error naming the first invalid
+/// pattern.
+pub fn compile_ignore_patterns(patterns: &[String]) -> Result<Option<Gitignore>, RCompareError> {
+    if patterns.is_empty() {
+        return Ok(None);
+    }
+
+    let mut builder = GitignoreBuilder::new("");
+    for pattern in patterns {
+        builder.add_line(None, pattern).map_err(|e| {
+            RCompareError::Config(format!(
+                "ignore pattern '{}' is not a valid glob: {}",
+                pattern, e
+            ))
+        })?;
+    }
+    builder
+        .build()
+        .map(Some)
+        .map_err(|e| RCompareError::Config(format!("ignore patterns failed to compile: {}", e)))
+}
+
 pub fn save_config(path: &Path, config: &AppConfig) -> Result<(), RCompareError> {
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent)?;
@@ -84,3 +131,118 @@ fn portable_config_path() -> Option<PathBuf> {
         .ok()
         .and_then(|path| path.parent().map(|dir| dir.join(CONFIG_FILE_NAME)))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ProfileSettings, SessionProfile, SpecializedDiff, WhitespaceSetting};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_profile_settings_round_trip() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join(CONFIG_FILE_NAME);
+
+        let mut config = AppConfig::default();
+        config.profiles.push(SessionProfile {
+            name: "screenshots".to_string(),
+            left_path: PathBuf::from("/left"),
+            right_path: PathBuf::from("/right"),
+            ignore_patterns: vec!["*.tmp".to_string()],
+            last_used: 1_700_000_000,
+            settings: ProfileSettings {
+                follow_symlinks: true,
+                include_hidden: false,
+                use_hash_verification: true,
+                mtime_tolerance_secs: 2,
+                whitespace_mode: Some(WhitespaceSetting::Trailing),
+                ignore_case: true,
+                specialized_diffs: vec![SpecializedDiff::Image, SpecializedDiff::Csv],
+            },
+        });
+        save_config(&path, &config).unwrap();
+
+        let loaded = read_config(&path).unwrap();
+        let profile = loaded.profile("screenshots").unwrap();
+        assert_eq!(profile.settings, config.profiles[0].settings);
+        assert_eq!(profile.ignore_patterns, ["*.tmp"]);
+        assert!(profile.settings.runs(SpecializedDiff::Csv));
+        assert!(!profile.settings.runs(SpecializedDiff::Json));
+
+        let mut applied = AppConfig::default();
+        profile.settings.apply_to(&mut applied);
+        assert!(applied.use_hash_verification && !applied.include_hidden);
+        assert_eq!(applied.mtime_tolerance_secs, 2);
+    }
+
+    #[test]
+    fn test_profile_without_settings_loads_defaults() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join(CONFIG_FILE_NAME);
+        fs::write(
+            &path,
+            r#"
+[[profiles]]
+name = "old"
+left_path = "/left"
+right_path = "/right"
+ignore_patterns = []
+last_used = 0
+"#,
+        )
+        .unwrap();
+
+        let loaded = read_config(&path).unwrap();
+        assert_eq!(
+            loaded.profile("old").unwrap().settings,
+            ProfileSettings::default()
+        );
+    }
+
+    #[test]
+    fn test_invalid_ignore_pattern_fails_to_load() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join(CONFIG_FILE_NAME);
+        fs::write(&path, "ignore_patterns = [\"*.tmp\", \"{a,b\"]\n").unwrap();
+
+        let err = read_config(&path).unwrap_err().to_string();
+        assert!(
+            err.contains("ignore pattern '{a,b' is not a valid glob"),
+            "{err}"
+        );
+        assert!(err.contains("unclosed alternate group"), "{err}");
+        assert!(err.contains(&path.display().to_string()), "{err}");
+
+        fs::write(
+            &path,
+            r#"
+[[profiles]]
+name = "broken"
+left_path = "/left"
+right_path = "/right"
+ignore_patterns = ["[z-a]"]
+last_used = 0
+"#,
+        )
+        .unwrap();
+        let err = read_config(&path).unwrap_err().to_string();
+        assert!(
+            err.contains("profile 'broken': ignore pattern '[z-a]'"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_compiled_ignore_patterns_match() {
+        let matcher = compile_ignore_patterns(&["*.o".to_string(), "target/".to_string()])
+            .unwrap()
+            .expect("patterns given");
+
+        assert!(matcher.matched("main.o", false).is_ignore());
+        assert!(matcher.matched("src/lib.o", false).is_ignore());
+        assert!(matcher.matched("target", true).is_ignore());
+        assert!(!matcher.matched("target", false).is_ignore());
+        assert!(!matcher.matched("main.rs", false).is_ignore());
+        assert!(compile_ignore_patterns(&[]).unwrap().is_none());
+    }
+}
@@ -1,12 +1,80 @@
+//! Error types shared by all RCompare crates.
+//!
+//! Callers that need to react to a failure rather than just report it
+//! (retry, skip an entry, abort the run) should branch on
+//! [`RCompareError::kind`] or [`VfsError::kind`] instead of matching
+//! variants or error messages. The [`ErrorKind`] of a failure is stable:
+//! new variants may be added to the error enums, but the kind an existing
+//! failure maps to does not change.
+//!
+//! Whether a failure is worth retrying is a separate question, answered by
+//! [`VfsError::is_transient`] and [`RCompareError::is_transient`].
+
 use thiserror::Error;
 
+/// Stable classification of an [`RCompareError`] or [`VfsError`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+    /// A file or directory does not exist
+    NotFound,
+    /// A file or directory exists but may not be read or written
+    PermissionDenied,
+    /// A path, setting or argument given by the caller is not usable
+    InvalidInput,
+    /// Data read from a file (patch, cache, manifest, ...) is malformed
+    Parse,
+    /// The operation is not supported by the backend or platform
+    Unsupported,
+    /// A remote host could not be authenticated
+    Untrusted,
+    /// Any other I/O failure
+    Io,
+    /// Failures that fit none of the above
+    Other,
+}
+
+/// I/O failures caused by the connection or a timeout rather than by the
+/// request itself
+fn is_transient_io(error: &std::io::Error) -> bool {
+    matches!(
+        error.kind(),
+        std::io::ErrorKind::TimedOut
+            | std::io::ErrorKind::Interrupted
+            | std::io::ErrorKind::WouldBlock
+            | std::io::ErrorKind::ConnectionReset
+            | std::io::ErrorKind::ConnectionAborted
+            | std::io::ErrorKind::NotConnected
+            | std::io::ErrorKind::BrokenPipe
+            | std::io::ErrorKind::UnexpectedEof
+    )
+}
+
+impl ErrorKind {
+    fn from_io(error: &std::io::Error) -> Self {
+        match error.kind() {
+            std::io::ErrorKind::NotFound => Self::NotFound,
+            std::io::ErrorKind::PermissionDenied => Self::PermissionDenied,
+            std::io::ErrorKind::InvalidData => Self::Parse,
+            std::io::ErrorKind::InvalidInput => Self::InvalidInput,
+            std::io::ErrorKind::Unsupported => Self::Unsupported,
+            _ => Self::Io,
+        }
+    }
+}
+
 #[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum RCompareError {
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
-    #[error("VFS error: {0}")]
-    Vfs(String),
+    #[error("VFS error: {context}: {source}")]
+    Vfs {
+        context: String,
+        #[source]
+        source: VfsError,
+    },
 
     #[error("Path error: {0}")]
     Path(String),
@@ -25,11 +93,89 @@ pub enum RCompareError {
 
     #[error("Patch parse error: {0}")]
     PatchParse(String),
+
+    #[error("Malformed patch at line {line}: {reason}")]
+    MalformedPatch { line: usize, reason: String },
+
+    #[error("Patch apply error: {0}")]
+    PatchApply(String),
+
+    #[error("Trash error: {0}")]
+    Trash(String),
+
+    #[error("Image error: {0}")]
+    Image(#[from] ImageError),
+}
+
+impl RCompareError {
+    /// Wrap a VFS failure, keeping it as the error source
+    pub fn vfs(context: impl Into<String>, source: VfsError) -> Self {
+        Self::Vfs {
+            context: context.into(),
+            source,
+        }
+    }
+
+    /// Whether the same operation may succeed if retried, see
+    /// [`VfsError::is_transient`]
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::Io(e) => is_transient_io(e),
+            Self::Vfs { source, .. } => source.is_transient(),
+            _ => false,
+        }
+    }
+
+    /// Stable classification of this error
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Io(e) => ErrorKind::from_io(e),
+            Self::Vfs { source, .. } => source.kind(),
+            Self::Path(_) | Self::Config(_) => ErrorKind::InvalidInput,
+            Self::Serialization(_) | Self::PatchParse(_) | Self::MalformedPatch { .. } => {
+                ErrorKind::Parse
+            }
+            Self::Cache(_) | Self::Comparison(_) | Self::PatchApply(_) | Self::Trash(_) => {
+                ErrorKind::Other
+            }
+            Self::Image(e) => e.kind(),
+        }
+    }
 }
 
 pub type Result<T> = std::result::Result<T, RCompareError>;
 
+/// Failures loading an image for comparison
 #[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum ImageError {
+    /// No decoder built into this binary reads the format, named by its
+    /// usual abbreviation (`HEIC`, `AVIF`) or, when it is not recognised at
+    /// all, by the file extension; classified as [`ErrorKind::Unsupported`]
+    #[error("Unsupported image format: {0}")]
+    UnsupportedFormat(String),
+
+    /// The format is supported but the file could not be decoded
+    #[error("Failed to decode {path}: {reason}")]
+    Decode { path: String, reason: String },
+
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl ImageError {
+    /// Stable classification of this error
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::UnsupportedFormat(_) => ErrorKind::Unsupported,
+            Self::Decode { .. } => ErrorKind::Parse,
+            Self::Io(e) => ErrorKind::from_io(e),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+#[non_exhaustive]
 pub enum VfsError {
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
@@ -48,4 +194,93 @@ pub enum VfsError {
 
     #[error("Unsupported operation: {0}")]
     Unsupported(String),
+
+    #[error("Host key mismatch for {host}: server presented {fingerprint}")]
+    HostKeyMismatch { host: String, fingerprint: String },
+
+    #[error("Unknown host key for {host} ({fingerprint}); add it to known_hosts or pin it")]
+    UnknownHostKey { host: String, fingerprint: String },
+
+    /// A remote backend reported a temporary condition (timeout, throttling,
+    /// server error); classified as [`ErrorKind::Io`]
+    #[error("Transient error: {0}")]
+    Transient(String),
+}
+
+impl VfsError {
+    /// Stable classification of this error
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Self::Io(e) => ErrorKind::from_io(e),
+            Self::NotFound(_) => ErrorKind::NotFound,
+            Self::PermissionDenied(_) => ErrorKind::PermissionDenied,
+            Self::NotADirectory(_) | Self::NotAFile(_) => ErrorKind::InvalidInput,
+            Self::Unsupported(_) => ErrorKind::Unsupported,
+            Self::HostKeyMismatch { .. } | Self::UnknownHostKey { .. } => ErrorKind::Untrusted,
+            Self::Transient(_) => ErrorKind::Io,
+        }
+    }
+
+    /// Whether the same operation may succeed if retried
+    ///
+    /// True for [`VfsError::Transient`] and for I/O errors caused by the
+    /// connection (timeouts, resets, a peer hanging up). Missing paths,
+    /// permission problems and untrusted hosts are permanent.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::Io(e) => is_transient_io(e),
+            Self::Transient(_) => true,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error as _;
+
+    #[test]
+    fn test_io_errors_keep_their_kind() {
+        let missing = std::fs::read("/nonexistent/rcompare/file.txt").unwrap_err();
+        assert_eq!(RCompareError::from(missing).kind(), ErrorKind::NotFound);
+
+        let denied = std::io::Error::from(std::io::ErrorKind::PermissionDenied);
+        assert_eq!(
+            RCompareError::from(denied).kind(),
+            ErrorKind::PermissionDenied
+        );
+
+        let malformed = RCompareError::MalformedPatch {
+            line: 3,
+            reason: "bad hunk header".to_string(),
+        };
+        assert_eq!(malformed.kind(), ErrorKind::Parse);
+    }
+
+    #[test]
+    fn test_transient_errors() {
+        let timeout = VfsError::Io(std::io::Error::from(std::io::ErrorKind::TimedOut));
+        assert!(timeout.is_transient());
+        assert_eq!(timeout.kind(), ErrorKind::Io);
+        assert!(VfsError::Transient("503 Service Unavailable".to_string()).is_transient());
+        assert!(RCompareError::vfs("listing", timeout).is_transient());
+
+        assert!(!VfsError::NotFound("a.txt".to_string()).is_transient());
+        assert!(!VfsError::Io(std::io::Error::other("bad request")).is_transient());
+        assert!(!RCompareError::Config("bad".to_string()).is_transient());
+    }
+
+    #[test]
+    fn test_vfs_error_is_the_source() {
+        let error = RCompareError::vfs(
+            "reading left",
+            VfsError::Io(std::io::Error::from(std::io::ErrorKind::PermissionDenied)),
+        );
+        assert_eq!(error.kind(), ErrorKind::PermissionDenied);
+
+        let source = error.source().unwrap();
+        assert!(source.downcast_ref::<VfsError>().is_some());
+        assert!(source.source().unwrap().is::<std::io::Error>());
+    }
 }
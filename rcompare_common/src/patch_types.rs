@@ -21,6 +21,7 @@ pub enum DiffGenerator {
     CvsDiff = 2,
     Perforce = 3,
     SubVersion = 4,
+    Git = 5,
 }
 
 /// Type of a single difference block
@@ -61,6 +62,25 @@ impl PatchSet {
             generator: DiffGenerator::Unknown,
         }
     }
+
+    /// Find the file patch for `path`
+    ///
+    /// Matches the destination first, then the source, either exactly or
+    /// after dropping a git-style `a/`/`b/` prefix, so `src/lib.rs` finds
+    /// the pair `a/src/lib.rs` → `b/src/lib.rs`.
+    pub fn file_by_path(&self, path: &str) -> Option<&FilePatch> {
+        let matches = |header: &str| header == path || strip_git_prefix(header) == path;
+        self.files
+            .iter()
+            .find(|fp| matches(&fp.destination))
+            .or_else(|| self.files.iter().find(|fp| matches(&fp.source)))
+    }
+}
+
+fn strip_git_prefix(path: &str) -> &str {
+    path.strip_prefix("a/")
+        .or_else(|| path.strip_prefix("b/"))
+        .unwrap_or(path)
 }
 
 impl Default for PatchSet {
@@ -90,6 +110,11 @@ pub struct FilePatch {
     pub applied_count: usize,
     /// Whether the original file has been blended into this model
     pub blended: bool,
+    /// Whether the file and hunk header lines were terminated with CRLF
+    pub crlf_headers: bool,
+    /// `diff --git` header and extended header lines, for git diffs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git: Option<GitHeader>,
 }
 
 impl FilePatch {
@@ -104,6 +129,8 @@ impl FilePatch {
             hunks: Vec::new(),
             applied_count: 0,
             blended: false,
+            crlf_headers: false,
+            git: None,
         }
     }
 
@@ -129,6 +156,40 @@ impl FilePatch {
         indices
     }
 
+    /// Path of the file the patch is about, without a git-style `a/`/`b/`
+    /// prefix: the destination, or the source for a deletion
+    pub fn path(&self) -> &str {
+        let header = if self.destination == "/dev/null" {
+            &self.source
+        } else {
+            &self.destination
+        };
+        strip_git_prefix(header)
+    }
+
+    /// Differences flagged as conflicting with the file, with their
+    /// `(hunk_idx, diff_idx)`
+    pub fn conflicts(&self) -> impl Iterator<Item = (usize, usize, &PatchDifference)> + '_ {
+        self.differences().filter(|(_, _, d)| d.conflict)
+    }
+
+    /// Differences not applied yet, with their `(hunk_idx, diff_idx)`
+    ///
+    /// Conflicting differences are never applied, so they are included here.
+    pub fn unapplied(&self) -> impl Iterator<Item = (usize, usize, &PatchDifference)> + '_ {
+        self.differences().filter(|(_, _, d)| !d.applied)
+    }
+
+    fn differences(&self) -> impl Iterator<Item = (usize, usize, &PatchDifference)> + '_ {
+        self.hunks.iter().enumerate().flat_map(|(hi, hunk)| {
+            hunk.differences
+                .iter()
+                .enumerate()
+                .filter(|(_, d)| d.diff_type != DifferenceType::Unchanged)
+                .map(move |(di, d)| (hi, di, d))
+        })
+    }
+
     /// Check if there are any unsaved changes
     pub fn has_unsaved_changes(&self) -> bool {
         self.hunks
@@ -144,6 +205,75 @@ impl Default for FilePatch {
     }
 }
 
+/// How two versions of a patch differ, as computed by
+/// `rcompare_core::patch_compare`
+///
+/// Only files and hunks that differ are listed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PatchSetDiff {
+    /// Changed files, ordered by path
+    pub files: Vec<FilePatchDiff>,
+}
+
+impl PatchSetDiff {
+    /// Whether both patches make the same changes
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+}
+
+/// One file whose changes differ between two versions of a patch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FilePatchDiff {
+    /// Path as returned by [`FilePatch::path`]
+    pub path: String,
+    /// `Added`/`Removed` when only one version touches the file
+    pub change: PatchChange,
+    /// Hunks that differ; every hunk of an added or removed file
+    pub hunks: Vec<HunkDiff>,
+}
+
+/// One hunk that differs between two versions of a patch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HunkDiff {
+    pub change: PatchChange,
+    /// Hunk in the old patch; `None` when added
+    pub old: Option<Hunk>,
+    /// Hunk in the new patch; `None` when removed
+    pub new: Option<Hunk>,
+}
+
+/// Change between two versions of a patch
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PatchChange {
+    /// Only in the new patch
+    Added,
+    /// Only in the old patch
+    Removed,
+    /// In both, with different content
+    Modified,
+}
+
+/// Git extended header of one file pair
+///
+/// Paths keep their `a/`/`b/` prefixes as written on the `diff --git` line.
+/// Modes are octal strings such as `100644`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GitHeader {
+    pub old_path: String,
+    pub new_path: String,
+    pub old_mode: Option<String>,
+    pub new_mode: Option<String>,
+    pub new_file_mode: Option<String>,
+    pub deleted_file_mode: Option<String>,
+    /// Similarity of a rename, e.g. `90%`
+    pub similarity_index: Option<String>,
+    pub rename_from: Option<String>,
+    pub rename_to: Option<String>,
+    /// Blob range and mode of the `index` line, e.g. `83db48f..bf269f4 100644`
+    pub index: Option<String>,
+}
+
 /// A hunk groups related differences with surrounding context
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Hunk {
@@ -220,6 +350,13 @@ pub struct PatchDifference {
     pub conflict: bool,
     /// Whether this difference has been modified since last save
     pub unsaved: bool,
+    /// Exact original diff lines (without `\n`) this difference was parsed from
+    ///
+    /// Set by parsers for formats the serializer cannot regenerate. A parser
+    /// that cannot attribute lines to single differences stores the whole
+    /// hunk text on the first one and `Some(vec![])` on the rest.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw: Option<Vec<String>>,
 }
 
 impl PatchDifference {
@@ -238,6 +375,7 @@ impl PatchDifference {
             applied: false,
             conflict: false,
             unsaved: false,
+            raw: None,
         }
     }
 
@@ -276,6 +414,67 @@ mod tests {
         assert_eq!(fp.difference_count(), 2); // Change + Insert, not Unchanged
     }
 
+    #[test]
+    fn test_file_by_path() {
+        let mut ps = PatchSet::new();
+        for (source, destination) in [
+            ("a/src/lib.rs", "b/src/lib.rs"),
+            ("a/gone.txt", "/dev/null"),
+            ("old/name.txt", "new/name.txt"),
+        ] {
+            let mut fp = FilePatch::new();
+            fp.source = source.to_string();
+            fp.destination = destination.to_string();
+            ps.files.push(fp);
+        }
+
+        assert_eq!(
+            ps.file_by_path("src/lib.rs").unwrap().source,
+            "a/src/lib.rs"
+        );
+        assert_eq!(
+            ps.file_by_path("b/src/lib.rs").unwrap().source,
+            "a/src/lib.rs"
+        );
+        assert_eq!(ps.file_by_path("gone.txt").unwrap().source, "a/gone.txt");
+        assert_eq!(
+            ps.file_by_path("old/name.txt").unwrap().destination,
+            "new/name.txt"
+        );
+        assert!(ps.file_by_path("name.txt").is_none());
+        assert!(ps.file_by_path("missing.rs").is_none());
+    }
+
+    #[test]
+    fn test_conflicts_and_unapplied() {
+        let mut fp = FilePatch::new();
+        let mut first = Hunk::new(1, 1);
+        first
+            .differences
+            .push(PatchDifference::new(DifferenceType::Unchanged, 1, 1));
+        let mut applied = PatchDifference::new(DifferenceType::Change, 2, 2);
+        applied.applied = true;
+        first.differences.push(applied);
+        let mut second = Hunk::new(10, 10);
+        let mut conflicting = PatchDifference::new(DifferenceType::Change, 10, 10);
+        conflicting.conflict = true;
+        second.differences.push(conflicting);
+        second
+            .differences
+            .push(PatchDifference::new(DifferenceType::Unchanged, 11, 11));
+        second
+            .differences
+            .push(PatchDifference::new(DifferenceType::Insert, 12, 12));
+        fp.hunks.push(first);
+        fp.hunks.push(second);
+
+        let conflicts: Vec<(usize, usize)> = fp.conflicts().map(|(hi, di, _)| (hi, di)).collect();
+        assert_eq!(conflicts, [(1, 0)]);
+        let unapplied: Vec<(usize, usize)> = fp.unapplied().map(|(hi, di, _)| (hi, di)).collect();
+        assert_eq!(unapplied, [(1, 0), (1, 2)]);
+        assert!(fp.unapplied().all(|(_, _, d)| !d.applied));
+    }
+
     #[test]
     fn test_hunk_recompute_counts() {
         let mut hunk = Hunk::new(1, 1);
@@ -1,3 +1,4 @@
+use crate::HashAlgorithm;
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::time::SystemTime;
@@ -10,6 +11,78 @@ pub struct FileEntry {
     pub size: u64,
     pub modified: SystemTime,
     pub is_dir: bool,
+    /// What kind of filesystem object the entry is; a symlink keeps
+    /// `is_dir` set when it points to a directory
+    #[serde(default)]
+    pub file_type: FileType,
+}
+
+impl FileEntry {
+    /// Whether the entry is a FIFO, socket or device node
+    pub fn is_special(&self) -> bool {
+        self.file_type.is_special()
+    }
+}
+
+/// Kind of filesystem object behind a [`FileEntry`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FileType {
+    #[default]
+    Regular,
+    Directory,
+    Symlink,
+    /// Named pipe
+    Fifo,
+    Socket,
+    BlockDevice,
+    CharDevice,
+}
+
+impl FileType {
+    /// `Directory` or `Regular`, for sources that know no finer types
+    /// (archives, object stores, remote listings)
+    pub fn from_is_dir(is_dir: bool) -> Self {
+        if is_dir {
+            Self::Directory
+        } else {
+            Self::Regular
+        }
+    }
+
+    /// Classify a local file type, as returned by `symlink_metadata` when
+    /// links should be reported as `Symlink`
+    pub fn from_std(file_type: std::fs::FileType) -> Self {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::FileTypeExt;
+            if file_type.is_fifo() {
+                return Self::Fifo;
+            }
+            if file_type.is_socket() {
+                return Self::Socket;
+            }
+            if file_type.is_block_device() {
+                return Self::BlockDevice;
+            }
+            if file_type.is_char_device() {
+                return Self::CharDevice;
+            }
+        }
+        if file_type.is_symlink() {
+            Self::Symlink
+        } else {
+            Self::from_is_dir(file_type.is_dir())
+        }
+    }
+
+    /// FIFOs, sockets and device nodes: their size means nothing and
+    /// reading them can block or never end, so they are compared by type only
+    pub fn is_special(self) -> bool {
+        matches!(
+            self,
+            Self::Fifo | Self::Socket | Self::BlockDevice | Self::CharDevice
+        )
+    }
 }
 
 /// Metadata for a file or directory
@@ -21,6 +94,28 @@ pub struct FileMetadata {
     pub is_symlink: bool,
 }
 
+/// How an archive entry is stored inside its archive
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompressionInfo {
+    /// Bytes the entry occupies in the archive
+    pub compressed_size: u64,
+    /// Compression method as named by the archive format, e.g. `"deflated"`
+    pub method: String,
+}
+
+impl CompressionInfo {
+    /// Compressed size as a fraction of `size` (the uncompressed size)
+    ///
+    /// Values below 1.0 mean the entry shrank; an empty entry gives 1.0.
+    pub fn ratio(&self, size: u64) -> f64 {
+        if size == 0 {
+            1.0
+        } else {
+            self.compressed_size as f64 / size as f64
+        }
+    }
+}
+
 /// Status of a file comparison
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum DiffStatus {
@@ -34,6 +129,12 @@ pub enum DiffStatus {
     OrphanRight,
     /// Files have the same size but haven't been fully compared yet
     Unchecked,
+    /// The two sides are different kinds of object at the same path: a
+    /// directory and a file, or a FIFO, socket or device and anything else
+    TypeConflict,
+    /// Left out of the comparison by a hidden or ignore rule; only reported
+    /// when the scan was asked to keep such entries
+    Ignored,
 }
 
 /// Represents a node in the diff tree, aligning files from left and right
@@ -43,6 +144,155 @@ pub struct DiffNode {
     pub left: Option<FileEntry>,
     pub right: Option<FileEntry>,
     pub status: DiffStatus,
+    /// Likely near-duplicate on the other side (orphans only, opt-in)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub similar_to: Option<SimilarTo>,
+    /// Why the node deserves attention beyond its status (opt-in checks)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<DiffReason>,
+    /// Rule that excluded an [`DiffStatus::Ignored`] node, e.g. `*.log` or
+    /// `.gitignore: target/`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ignored_by: Option<String>,
+}
+
+/// Extra classification attached to a [`DiffNode`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DiffReason {
+    /// One side is a zero-byte file while the other has content, which
+    /// often points to a truncated copy
+    EmptiedFile,
+    /// A file was modified or removed between listing and comparison and
+    /// did not settle on a re-read, so its status is only provisional
+    ChangedDuringScan,
+    /// Compressed files whose bytes differ but whose decompressed content
+    /// is identical, reported `Same`
+    DecompressedMatch,
+    /// An orphan paired as a rename with the orphan in `similar_to`, which
+    /// also holds the similarity score
+    Renamed,
+}
+
+/// Pairing of an orphan with a near-duplicate orphan on the other side
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SimilarTo {
+    /// Relative path of the matching entry on the other side
+    pub path: PathBuf,
+    /// Estimated content similarity (0.0 - 100.0)
+    pub similarity: f64,
+}
+
+/// Files within one tree whose content is byte-identical
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DuplicateGroup {
+    /// Content hash shared by every file in the group
+    pub hash: Blake3Hash,
+    /// Size of each file in bytes
+    pub size: u64,
+    /// Relative paths of the duplicates, sorted
+    pub paths: Vec<PathBuf>,
+}
+
+impl DuplicateGroup {
+    /// Bytes that deduplicating the group (keeping one copy) would free
+    pub fn wasted_bytes(&self) -> u64 {
+        self.size * (self.paths.len().saturating_sub(1) as u64)
+    }
+}
+
+/// Sibling paths whose names differ only by letter case
+///
+/// Such entries can't coexist on a case-insensitive filesystem, so a tree
+/// holding more than one spelling breaks when checked out there.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CaseCollision {
+    /// Every spelling found on either side, sorted
+    pub paths: Vec<PathBuf>,
+    /// Spellings present on the left side
+    pub left: Vec<PathBuf>,
+    /// Spellings present on the right side
+    pub right: Vec<PathBuf>,
+}
+
+impl CaseCollision {
+    /// Whether a single side holds several spellings, rather than each side
+    /// spelling the name its own way
+    pub fn within_one_side(&self) -> bool {
+        self.left.len() > 1 || self.right.len() > 1
+    }
+}
+
+/// Paths that a path transform maps to the same name
+///
+/// Colliding entries are left unpaired under their own paths rather than
+/// matched arbitrarily.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PathCollision {
+    /// The name they all transform to
+    pub transformed: PathBuf,
+    /// Left paths transforming to it, sorted
+    pub left: Vec<PathBuf>,
+    /// Right paths transforming to it, sorted
+    pub right: Vec<PathBuf>,
+}
+
+/// One file listed in a precomputed manifest of a tree
+///
+/// Used as the left side of `ComparisonEngine::compare_against_manifest` in
+/// place of a scan. Without either hash only the size is known.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// Path relative to the tree root
+    pub path: PathBuf,
+    /// Size in bytes
+    pub size: u64,
+    /// BLAKE3 content hash, if the manifest records one
+    pub hash: Option<Blake3Hash>,
+    /// SHA-256 content hash, if the manifest records one instead
+    pub sha256: Option<[u8; 32]>,
+}
+
+/// Hash written to a generated manifest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ManifestHashAlgorithm {
+    /// BLAKE3, the hash used for comparison; served from the hash cache
+    #[default]
+    Blake3,
+    /// SHA-256, for checking releases with standard tools; always read in
+    /// full, both when generating and when comparing
+    Sha256,
+}
+
+impl ManifestHashAlgorithm {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ManifestHashAlgorithm::Blake3 => "blake3",
+            ManifestHashAlgorithm::Sha256 => "sha256",
+        }
+    }
+}
+
+impl From<ManifestHashAlgorithm> for HashAlgorithm {
+    fn from(algorithm: ManifestHashAlgorithm) -> Self {
+        match algorithm {
+            ManifestHashAlgorithm::Blake3 => HashAlgorithm::Blake3,
+            ManifestHashAlgorithm::Sha256 => HashAlgorithm::Sha256,
+        }
+    }
+}
+
+/// One file of a generated manifest, see
+/// `ComparisonEngine::generate_manifest`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestRecord {
+    /// Path relative to the tree root
+    pub path: PathBuf,
+    /// Size in bytes
+    pub size: u64,
+    /// Last modification time
+    pub modified: SystemTime,
+    /// Hex digest; SHA-256 digests carry a `sha256:` prefix
+    pub hash: String,
 }
 
 /// Status of a three-way file comparison
@@ -173,10 +423,123 @@ pub struct SessionProfile {
     pub ignore_patterns: Vec<String>,
     /// Last time this profile was used (Unix timestamp)
     pub last_used: u64,
+    /// Analysis settings restored along with the paths
+    #[serde(default)]
+    pub settings: ProfileSettings,
+}
+
+/// Comparison settings stored with a [`SessionProfile`]
+///
+/// Missing fields take their defaults, so profiles saved before a setting
+/// existed still load.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProfileSettings {
+    /// Whether to follow symbolic links
+    pub follow_symlinks: bool,
+    /// Whether to include dotfiles and other hidden entries
+    pub include_hidden: bool,
+    /// Whether to hash same-sized files instead of trusting timestamps
+    pub use_hash_verification: bool,
+    /// Modification times at most this many seconds apart count as equal
+    pub mtime_tolerance_secs: u64,
+    /// Text whitespace handling (`None` compares whitespace exactly)
+    pub whitespace_mode: Option<WhitespaceSetting>,
+    /// Whether text comparison ignores case
+    pub ignore_case: bool,
+    /// Specialized comparisons to run
+    pub specialized_diffs: Vec<SpecializedDiff>,
+}
+
+/// Whitespace handling stored in a [`ProfileSettings`]
+///
+/// Serialized under the name of the matching `--ignore-whitespace` value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WhitespaceSetting {
+    /// Ignore all whitespace
+    All,
+    /// Ignore leading whitespace
+    Leading,
+    /// Ignore trailing whitespace
+    Trailing,
+    /// Ignore changes in the amount of whitespace
+    Changes,
+    /// Expand tabs before comparing
+    Tabs,
+}
+
+impl WhitespaceSetting {
+    /// The `--ignore-whitespace` value for this setting
+    pub fn name(&self) -> &'static str {
+        match self {
+            WhitespaceSetting::All => "all",
+            WhitespaceSetting::Leading => "leading",
+            WhitespaceSetting::Trailing => "trailing",
+            WhitespaceSetting::Changes => "changes",
+            WhitespaceSetting::Tabs => "tabs",
+        }
+    }
+}
+
+/// A specialized comparison a [`ProfileSettings`] can turn on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SpecializedDiff {
+    Text,
+    Image,
+    Csv,
+    Excel,
+    Json,
+    Yaml,
+    Parquet,
+}
+
+impl Default for ProfileSettings {
+    fn default() -> Self {
+        Self {
+            follow_symlinks: false,
+            include_hidden: default_include_hidden(),
+            use_hash_verification: false,
+            mtime_tolerance_secs: 0,
+            whitespace_mode: None,
+            ignore_case: false,
+            specialized_diffs: Vec::new(),
+        }
+    }
+}
+
+impl ProfileSettings {
+    /// Snapshot the scan and comparison settings of `config`
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self {
+            follow_symlinks: config.follow_symlinks,
+            include_hidden: config.include_hidden,
+            use_hash_verification: config.use_hash_verification,
+            mtime_tolerance_secs: config.mtime_tolerance_secs,
+            ..Default::default()
+        }
+    }
+
+    /// Write the scan and comparison settings into `config`
+    ///
+    /// Meant for the in-memory config of a single run; saving the result
+    /// would make the profile's settings the global defaults.
+    pub fn apply_to(&self, config: &mut AppConfig) {
+        config.follow_symlinks = self.follow_symlinks;
+        config.include_hidden = self.include_hidden;
+        config.use_hash_verification = self.use_hash_verification;
+        config.mtime_tolerance_secs = self.mtime_tolerance_secs;
+    }
+
+    /// Whether the specialized comparison `diff` is enabled
+    pub fn runs(&self, diff: SpecializedDiff) -> bool {
+        self.specialized_diffs.contains(&diff)
+    }
 }
 
 /// Application configuration
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AppConfig {
     /// Ignore patterns (e.g., "*.o", "node_modules/")
     #[serde(default)]
@@ -186,14 +549,28 @@ pub struct AppConfig {
     #[serde(default)]
     pub follow_symlinks: bool,
 
+    /// Whether to include hidden entries: names starting with `.` on every
+    /// backend, plus files with the hidden attribute on Windows
+    #[serde(default = "default_include_hidden")]
+    pub include_hidden: bool,
+
     /// Whether to use hash verification
     #[serde(default)]
     pub use_hash_verification: bool,
 
+    /// Modification times at most this many seconds apart count as equal
+    /// (e.g. 2 when comparing against FAT/exFAT)
+    #[serde(default)]
+    pub mtime_tolerance_secs: u64,
+
     /// Cache directory
     #[serde(default)]
     pub cache_dir: Option<PathBuf>,
 
+    /// Worker threads for scanning and hashing (`None` uses one per core)
+    #[serde(default)]
+    pub threads: Option<usize>,
+
     /// Enable portable mode (config alongside binary)
     #[serde(default)]
     pub portable_mode: bool,
@@ -203,6 +580,33 @@ pub struct AppConfig {
     pub profiles: Vec<SessionProfile>,
 }
 
+impl AppConfig {
+    /// The saved profile called `name`, if any
+    pub fn profile(&self, name: &str) -> Option<&SessionProfile> {
+        self.profiles.iter().find(|p| p.name == name)
+    }
+}
+
+fn default_include_hidden() -> bool {
+    true
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            ignore_patterns: Vec::new(),
+            follow_symlinks: false,
+            include_hidden: default_include_hidden(),
+            use_hash_verification: false,
+            mtime_tolerance_secs: 0,
+            cache_dir: None,
+            threads: None,
+            portable_mode: false,
+            profiles: Vec::new(),
+        }
+    }
+}
+
 /// Session identifier for a comparison
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct SessionId(pub Uuid);
@@ -231,6 +635,13 @@ impl Blake3Hash {
     pub fn to_hex(&self) -> String {
         hex::encode(self.0)
     }
+
+    /// Parse a 64-character hex string, as produced by [`Self::to_hex`]
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let mut bytes = [0u8; 32];
+        hex::decode_to_slice(hex, &mut bytes).ok()?;
+        Some(Self(bytes))
+    }
 }
 
 impl From<blake3::Hash> for Blake3Hash {
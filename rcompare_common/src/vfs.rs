@@ -1,6 +1,6 @@
-use crate::{FileEntry, FileMetadata, VfsError};
+use crate::{CompressionInfo, FileEntry, FileMetadata, VfsError};
 use std::io::{Read, Write};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
 /// Virtual File System trait for abstracting filesystem operations
@@ -11,9 +11,26 @@ pub trait Vfs: Send + Sync {
     /// Uniquely identifies the VFS instance (e.g., "local", "zip:archive.zip")
     fn instance_id(&self) -> &str;
 
-    /// Returns the metadata for a specific path
+    /// Returns the metadata for a specific path, following symlinks
     fn metadata(&self, path: &Path) -> Result<FileMetadata, VfsError>;
 
+    /// Returns the metadata for a path without following a final symlink
+    ///
+    /// `is_symlink` is only ever set here. Backends without symlinks return
+    /// the same as [`metadata`](Self::metadata).
+    fn symlink_metadata(&self, path: &Path) -> Result<FileMetadata, VfsError> {
+        self.metadata(path)
+    }
+
+    /// Reads the target of a symlink as stored, which may be relative to
+    /// the link's directory
+    fn read_link(&self, _path: &Path) -> Result<PathBuf, VfsError> {
+        Err(VfsError::Unsupported(format!(
+            "{} does not support symlinks",
+            self.instance_id()
+        )))
+    }
+
     /// Lists the contents of a directory
     fn read_dir(&self, path: &Path) -> Result<Vec<FileEntry>, VfsError>;
 
@@ -41,6 +58,48 @@ pub trait Vfs: Send + Sync {
         VfsCapabilities::default()
     }
 
+    /// Cheap content identifier for a file, if the backend has one
+    ///
+    /// Returns something like an S3 ETag that changes whenever the content
+    /// changes, without reading the file. Values are only meaningful when
+    /// compared against fingerprints from the same kind of backend, so
+    /// implementations should prefix them with a scheme (e.g. `"s3:"`).
+    fn content_fingerprint(&self, _path: &Path) -> Result<Option<String>, VfsError> {
+        Ok(None)
+    }
+
+    /// Digest of a file's content, computed or looked up by the backend
+    ///
+    /// Returns the raw `algorithm` digest, or `None` to make the caller read
+    /// the file through [`open_file`](Self::open_file) and hash it itself.
+    /// Backends that can hash while streaming, without buffering the whole
+    /// file first, or that store a digest next to the content, implement
+    /// this so hashing costs a single pass.
+    fn hash(&self, _path: &Path, _algorithm: HashAlgorithm) -> Result<Option<Vec<u8>>, VfsError> {
+        Ok(None)
+    }
+
+    /// Compressed size and method of an archive entry
+    ///
+    /// Returns `None` for backends that don't compress individual entries
+    /// (plain filesystems, remote stores, TAR streams) and for directories.
+    fn compression_info(&self, _path: &Path) -> Result<Option<CompressionInfo>, VfsError> {
+        Ok(None)
+    }
+
+    /// Fail early with a clear error if `op` is not supported by this VFS
+    fn ensure_supported(&self, op: VfsOperation) -> Result<(), VfsError> {
+        if self.capabilities().supports(op) {
+            Ok(())
+        } else {
+            Err(VfsError::Unsupported(format!(
+                "{} does not support {}",
+                self.instance_id(),
+                op
+            )))
+        }
+    }
+
     /// Create a new file and return a writer
     /// Returns Unsupported error if not writable
     fn create_file(&self, _path: &Path) -> Result<Box<dyn Write + Send>, VfsError> {
@@ -99,6 +158,9 @@ pub struct VfsCapabilities {
     pub rename: bool,
     pub create_dir: bool,
     pub set_mtime: bool,
+    /// Reading file content is slow or billed (remote backends), so callers
+    /// should prefer metadata and fingerprints over hashing
+    pub expensive_reads: bool,
 }
 
 impl VfsCapabilities {
@@ -111,6 +173,7 @@ impl VfsCapabilities {
             rename: true,
             create_dir: true,
             set_mtime: true,
+            expensive_reads: false,
         }
     }
 
@@ -123,6 +186,51 @@ impl VfsCapabilities {
             rename: false,
             create_dir: false,
             set_mtime: false,
+            expensive_reads: false,
+        }
+    }
+
+    /// Whether `op` can be performed on a VFS with these capabilities
+    pub fn supports(&self, op: VfsOperation) -> bool {
+        match op {
+            VfsOperation::Read => self.read,
+            VfsOperation::Write => self.write,
+            VfsOperation::Delete => self.delete,
+            VfsOperation::Rename => self.rename,
+            VfsOperation::CreateDir => self.create_dir,
+            VfsOperation::SetMtime => self.set_mtime,
         }
     }
 }
+
+/// Operations that can be checked against [`VfsCapabilities`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VfsOperation {
+    Read,
+    Write,
+    Delete,
+    Rename,
+    CreateDir,
+    SetMtime,
+}
+
+impl std::fmt::Display for VfsOperation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            VfsOperation::Read => "reading files",
+            VfsOperation::Write => "writing files",
+            VfsOperation::Delete => "deleting files",
+            VfsOperation::Rename => "renaming files",
+            VfsOperation::CreateDir => "creating directories",
+            VfsOperation::SetMtime => "setting modification times",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Digest requested from [`Vfs::hash`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Blake3,
+    Sha256,
+}
@@ -1,5 +1,5 @@
 use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
-use rcompare_common::{AppConfig, FileEntry};
+use rcompare_common::{AppConfig, FileEntry, FileType};
 use rcompare_core::{ComparisonEngine, FolderScanner, HashCache};
 use std::fs;
 use std::io::Write as IoWrite;
@@ -37,6 +37,7 @@ fn create_file_entries(count: usize) -> Vec<FileEntry> {
             size: 1024,
             modified: SystemTime::now(),
             is_dir: false,
+            file_type: FileType::Regular,
         })
         .collect()
 }
@@ -174,6 +175,7 @@ fn bench_comparison_all_different(c: &mut Criterion) {
                     size: 2048,
                     modified: SystemTime::now(),
                     is_dir: false,
+                    file_type: FileType::Regular,
                 })
                 .collect();
 
@@ -0,0 +1,225 @@
+//! Benchmarks for the tuning knobs of the core engines
+//!
+//! Each group varies one parameter around its default so the effect on a
+//! given machine can be measured before changing it:
+//!
+//! - `scan_large_tree`: walker threads (`FolderScanner::with_threads`)
+//! - `hashing`: hash algorithm and read buffer size
+//!   (`ComparisonEngine::with_hash_buffer_size`)
+//! - `streaming_compare`: chunk size of the streaming comparison
+//!   (`ComparisonEngine::with_stream_chunk_size`)
+//! - `binary_diff`: chunk size of `BinaryDiffEngine::new`
+//! - `text_diff`: each line diff algorithm on a large file with scattered edits
+//!
+//! Run with `cargo bench -p rcompare_core --bench tuning_benchmarks`.
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rcompare_common::{AppConfig, ManifestHashAlgorithm};
+use rcompare_core::text_diff::{DiffAlgorithm, TextDiffConfig};
+use rcompare_core::{BinaryDiffEngine, ComparisonEngine, FolderScanner, HashCache, TextDiffEngine};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+/// Size of the files hashed and compared byte for byte
+const FILE_SIZE: usize = 8 * 1024 * 1024;
+
+// Wide synthetic tree: `width` directories per level, `files` files in each
+fn create_wide_tree(root: &Path, depth: usize, width: usize, files: usize) {
+    for i in 0..files {
+        fs::write(root.join(format!("file_{i}.dat")), [i as u8; 256]).unwrap();
+    }
+    if depth > 0 {
+        for i in 0..width {
+            let dir = root.join(format!("dir_{i}"));
+            fs::create_dir(&dir).unwrap();
+            create_wide_tree(&dir, depth - 1, width, files);
+        }
+    }
+}
+
+// Deterministic pseudo-random bytes, so compression-like shortcuts don't apply
+fn pseudo_random_bytes(len: usize, seed: u64) -> Vec<u8> {
+    let mut state = seed;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as u8
+        })
+        .collect()
+}
+
+fn bench_scan_large_tree(c: &mut Criterion) {
+    // 1 + 6 + 36 + 216 directories with 8 files each: ~2300 entries
+    let temp = TempDir::new().unwrap();
+    create_wide_tree(temp.path(), 3, 6, 8);
+
+    let mut group = c.benchmark_group("scan_large_tree");
+    group.sample_size(20);
+
+    group.bench_function("default_pool", |b| {
+        let scanner = FolderScanner::new(AppConfig::default());
+        b.iter(|| black_box(scanner.scan(black_box(temp.path())).unwrap()));
+    });
+    for threads in [1, 2, 4, 8] {
+        group.bench_with_input(BenchmarkId::new("threads", threads), &threads, |b, &t| {
+            let scanner = FolderScanner::new(AppConfig::default()).with_threads(t);
+            b.iter(|| black_box(scanner.scan(black_box(temp.path())).unwrap()));
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_hashing(c: &mut Criterion) {
+    let temp = TempDir::new().unwrap();
+    let path = temp.path().join("data.bin");
+    fs::write(&path, pseudo_random_bytes(FILE_SIZE, 1)).unwrap();
+    let entries = FolderScanner::new(AppConfig::default())
+        .scan(temp.path())
+        .unwrap();
+
+    let mut group = c.benchmark_group("hashing");
+    group.sample_size(20);
+    group.throughput(Throughput::Bytes(FILE_SIZE as u64));
+
+    // Forcing content checks keeps the hash cache from answering
+    for algorithm in [ManifestHashAlgorithm::Blake3, ManifestHashAlgorithm::Sha256] {
+        let engine = ComparisonEngine::new(HashCache::in_memory()).with_force_content_check(true);
+        group.bench_function(BenchmarkId::new("algorithm", algorithm.name()), |b| {
+            b.iter(|| {
+                black_box(
+                    engine
+                        .generate_manifest(temp.path(), entries.clone(), None, algorithm)
+                        .unwrap(),
+                )
+            });
+        });
+    }
+
+    for buffer in [4 * 1024, 64 * 1024, 1024 * 1024] {
+        let engine = ComparisonEngine::new(HashCache::in_memory())
+            .with_force_content_check(true)
+            .with_hash_buffer_size(buffer);
+        group.bench_with_input(
+            BenchmarkId::new("blake3_buffer", buffer),
+            &path,
+            |b, path| {
+                b.iter(|| black_box(engine.hash_file(path).unwrap()));
+            },
+        );
+    }
+
+    group.finish();
+}
+
+fn bench_streaming_compare(c: &mut Criterion) {
+    let temp = TempDir::new().unwrap();
+    let left = temp.path().join("left");
+    let right = temp.path().join("right");
+    fs::create_dir(&left).unwrap();
+    fs::create_dir(&right).unwrap();
+    let content = pseudo_random_bytes(FILE_SIZE, 2);
+    fs::write(left.join("data.bin"), &content).unwrap();
+    fs::write(right.join("data.bin"), &content).unwrap();
+
+    let scanner = FolderScanner::new(AppConfig::default());
+    let left_entries = scanner.scan(&left).unwrap();
+    let right_entries = scanner.scan(&right).unwrap();
+
+    let mut group = c.benchmark_group("streaming_compare");
+    group.sample_size(20);
+    group.throughput(Throughput::Bytes(2 * FILE_SIZE as u64));
+
+    for chunk in [16 * 1024, 256 * 1024, 1024 * 1024, 4 * 1024 * 1024] {
+        let engine = ComparisonEngine::new(HashCache::in_memory())
+            .with_hash_verification(true)
+            .with_streaming_threshold(0)
+            .with_stream_chunk_size(chunk);
+        group.bench_with_input(BenchmarkId::new("chunk", chunk), &chunk, |b, _| {
+            b.iter(|| {
+                black_box(
+                    engine
+                        .compare(&left, &right, left_entries.clone(), right_entries.clone())
+                        .unwrap(),
+                )
+            });
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_binary_diff(c: &mut Criterion) {
+    let temp = TempDir::new().unwrap();
+    let left = temp.path().join("left.bin");
+    let right = temp.path().join("right.bin");
+    let mut content = pseudo_random_bytes(1024 * 1024, 3);
+    fs::write(&left, &content).unwrap();
+    for i in (0..content.len()).step_by(4096) {
+        content[i] = content[i].wrapping_add(1);
+    }
+    fs::write(&right, &content).unwrap();
+
+    let mut group = c.benchmark_group("binary_diff");
+    group.sample_size(20);
+    group.throughput(Throughput::Bytes(content.len() as u64));
+
+    for chunk in [256, 4096, 65536] {
+        let engine = BinaryDiffEngine::new(chunk);
+        group.bench_with_input(BenchmarkId::new("chunk", chunk), &chunk, |b, _| {
+            b.iter(|| black_box(engine.compare_files(&left, &right).unwrap()));
+        });
+    }
+
+    group.finish();
+}
+
+fn bench_text_diff(c: &mut Criterion) {
+    let left: String = (0..5000)
+        .map(|i| format!("line {i}: the quick brown fox jumps over the lazy dog\n"))
+        .collect();
+    let right: String = (0..5000)
+        .map(|i| {
+            if i % 97 == 0 {
+                format!("line {i}: the quick brown cat jumps over the lazy dog\n")
+            } else {
+                format!("line {i}: the quick brown fox jumps over the lazy dog\n")
+            }
+        })
+        .collect();
+    let path = Path::new("bench.txt");
+
+    let mut group = c.benchmark_group("text_diff");
+    group.sample_size(20);
+    group.throughput(Throughput::Bytes(left.len() as u64));
+
+    for algorithm in [
+        DiffAlgorithm::Myers,
+        DiffAlgorithm::Patience,
+        DiffAlgorithm::Histogram,
+    ] {
+        let engine = TextDiffEngine::with_config(TextDiffConfig {
+            algorithm,
+            ..TextDiffConfig::new()
+        });
+        group.bench_function(format!("{algorithm:?}").to_lowercase(), |b| {
+            b.iter(|| black_box(engine.compare_text(&left, &right, path).unwrap()));
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(
+    tuning_benches,
+    bench_scan_large_tree,
+    bench_hashing,
+    bench_streaming_compare,
+    bench_binary_diff,
+    bench_text_diff
+);
+
+criterion_main!(tuning_benches);
@@ -11,6 +11,84 @@ pub struct HexChunk {
     pub left_data: Vec<u8>,
     pub right_data: Vec<u8>,
     pub differences: Vec<usize>, // Indices where bytes differ
+    /// Display hint for `left_data`
+    pub left_kind: ChunkKind,
+    /// Display hint for `right_data`
+    pub right_kind: ChunkKind,
+}
+
+/// Rough classification of a chunk's bytes, as a rendering hint
+///
+/// Viewers can collapse long runs of `Zero` chunks, show `Text` inline and
+/// dim `HighEntropy` (compressed or encrypted) data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ChunkKind {
+    /// Printable UTF-8 text, including tabs and line breaks
+    Text,
+    /// Anything else
+    Binary,
+    /// Only zero bytes (or no bytes at all)
+    Zero,
+    /// Close to uniformly distributed bytes
+    HighEntropy,
+}
+
+impl ChunkKind {
+    /// Chunks shorter than this are too small to judge entropy
+    const MIN_ENTROPY_SAMPLE: usize = 64;
+
+    /// Fraction of the maximum possible entropy for a chunk of this length
+    /// above which it counts as high-entropy
+    const HIGH_ENTROPY_RATIO: f64 = 0.85;
+
+    /// Classify a chunk in a single pass over its bytes
+    pub fn classify(data: &[u8]) -> Self {
+        if data.iter().all(|&b| b == 0) {
+            return ChunkKind::Zero;
+        }
+        if is_printable_text(data) {
+            return ChunkKind::Text;
+        }
+        if data.len() >= Self::MIN_ENTROPY_SAMPLE {
+            let max_entropy = (data.len().min(256) as f64).log2();
+            if shannon_entropy(data) >= max_entropy * Self::HIGH_ENTROPY_RATIO {
+                return ChunkKind::HighEntropy;
+            }
+        }
+        ChunkKind::Binary
+    }
+}
+
+/// Valid UTF-8 without control characters other than whitespace; a
+/// multi-byte character cut off at the end of the chunk is tolerated
+fn is_printable_text(data: &[u8]) -> bool {
+    let text = match std::str::from_utf8(data) {
+        Ok(text) => text,
+        Err(e) if e.error_len().is_none() => {
+            // A character cut off at the chunk boundary; judge the rest
+            std::str::from_utf8(&data[..e.valid_up_to()]).unwrap_or_default()
+        }
+        Err(_) => return false,
+    };
+    text.chars()
+        .all(|c| !c.is_control() || matches!(c, '\t' | '\n' | '\r' | '\x0c'))
+}
+
+/// Shannon entropy in bits per byte
+fn shannon_entropy(data: &[u8]) -> f64 {
+    let mut counts = [0usize; 256];
+    for &b in data {
+        counts[b as usize] += 1;
+    }
+    let len = data.len() as f64;
+    counts
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / len;
+            -p * p.log2()
+        })
+        .sum()
 }
 
 /// Binary comparison engine
@@ -83,6 +161,8 @@ impl BinaryDiffEngine {
 
         Ok(HexChunk {
             offset,
+            left_kind: ChunkKind::classify(&left_data),
+            right_kind: ChunkKind::classify(&right_data),
             left_data,
             right_data,
             differences,
@@ -246,4 +326,46 @@ mod tests {
         assert!(!diffs.is_empty());
         assert!(diffs.contains(&6)); // 'W' vs 'R'
     }
+
+    #[test]
+    fn test_chunk_kind_classification() {
+        assert_eq!(ChunkKind::classify(&[0u8; 256]), ChunkKind::Zero);
+        assert_eq!(
+            ChunkKind::classify(b"fn main() {\n\tprintln!(\"hi\");\n}\n"),
+            ChunkKind::Text
+        );
+        assert_eq!(
+            ChunkKind::classify("Größe: 5 €".as_bytes()),
+            ChunkKind::Text
+        );
+
+        // xorshift output stands in for compressed or encrypted data
+        let mut state = 0x2545_F491_4F6C_DD1Du64;
+        let random: Vec<u8> = (0..256)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state >> 24) as u8
+            })
+            .collect();
+        assert_eq!(ChunkKind::classify(&random), ChunkKind::HighEntropy);
+
+        let png_header = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\0\0\0\x10\0\0\0\x10\x08\x06\0\0\0";
+        assert_eq!(ChunkKind::classify(png_header), ChunkKind::Binary);
+    }
+
+    #[test]
+    fn test_compare_files_tags_chunk_kinds() {
+        let mut left = NamedTempFile::new().unwrap();
+        let mut right = NamedTempFile::new().unwrap();
+        left.write_all(&[0u8; 16]).unwrap();
+        right.write_all(b"sixteen bytes!!\n").unwrap();
+
+        let chunks = BinaryDiffEngine::new(16)
+            .compare_files(left.path(), right.path())
+            .unwrap();
+        assert_eq!(chunks[0].left_kind, ChunkKind::Zero);
+        assert_eq!(chunks[0].right_kind, ChunkKind::Text);
+    }
 }
@@ -0,0 +1,39 @@
+//! Cancellation and progress hooks for the specialized engines.
+//!
+//! The folder comparison takes a cancellation flag and a progress callback
+//! directly; the CSV, Excel, Parquet and image engines thread both through
+//! their loops as one [`Monitor`] so that the plain `compare_*` methods can
+//! pass [`Monitor::default`] and pay nothing but a null check.
+
+use rcompare_common::RCompareError;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Cancellation flag and `(done, total)` progress callback of one comparison
+#[derive(Clone, Copy, Default)]
+pub(crate) struct Monitor<'a> {
+    cancel: Option<&'a AtomicBool>,
+    progress: Option<&'a dyn Fn(usize, usize)>,
+}
+
+impl<'a> Monitor<'a> {
+    pub(crate) fn new(cancel: &'a AtomicBool, progress: Option<&'a dyn Fn(usize, usize)>) -> Self {
+        Self {
+            cancel: Some(cancel),
+            progress,
+        }
+    }
+
+    /// Fail with "`what` cancelled" once the flag is set
+    pub(crate) fn check(&self, what: &str) -> Result<(), RCompareError> {
+        if self.cancel.is_some_and(|flag| flag.load(Ordering::Relaxed)) {
+            return Err(RCompareError::Comparison(format!("{} cancelled", what)));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn report(&self, done: usize, total: usize) {
+        if let Some(progress) = self.progress {
+            progress(done, total);
+        }
+    }
+}
@@ -13,6 +13,7 @@
 //! - **Hash verification**: Optional re-hashing to verify cache integrity
 //! - **VFS support**: Works with both filesystem and virtual file systems
 //! - **Cancellation**: Supports cancelling long-running comparisons
+//! - **Spilling**: Results of huge comparisons can be written to disk as they are produced
 //!
 //! # Comparison Logic
 //!
@@ -85,16 +86,89 @@
 
 #![allow(clippy::too_many_arguments)]
 
+use crate::diff_spill::{DiffSpillWriter, SpilledDiff};
 use crate::hash_cache::HashCache;
+use crate::scanner::{FolderScanner, IgnoredEntry};
+use crate::similarity::{similarity_upper_bound, ChunkFingerprint};
+#[cfg(feature = "archives")]
+use crate::vfs::CompressionType;
 use rcompare_common::{
-    Blake3Hash, CacheKey, DiffNode, DiffStatus, FileEntry, RCompareError, ThreeWayDiffNode,
-    ThreeWayDiffStatus, Vfs,
+    Blake3Hash, CacheKey, CaseCollision, DiffNode, DiffReason, DiffStatus, DuplicateGroup,
+    FileEntry, FileType, HashAlgorithm, ManifestEntry, ManifestHashAlgorithm, ManifestRecord,
+    PathCollision, RCompareError, SimilarTo, ThreeWayDiffNode, ThreeWayDiffStatus, Vfs,
 };
-use std::collections::HashMap;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, HashSet};
+#[cfg(feature = "archives")]
+use std::io::BufRead;
 use std::io::{Read, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
-use std::sync::atomic::{AtomicBool, Ordering};
-use tracing::{debug, info};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use tracing::{debug, debug_span, info, instrument, warn, Span};
+
+/// What a two-way comparison inspects for paths present on both sides
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ComparisonMode {
+    /// Compare sizes, timestamps and content hashes (default)
+    #[default]
+    Content,
+    /// Classify on presence alone: a path on both sides is `Same` unless it
+    /// is a file on one side and a directory on the other. Never reads file
+    /// content, so it is a fast "were all files copied?" check.
+    StructureOnly,
+    /// Report only which paths appeared or disappeared: every path on both
+    /// sides is `Same`, whatever its size or type. The comparison needs
+    /// nothing beyond the directory listings, so it never touches file
+    /// metadata or content. Scanning is separate: [`FolderScanner`] still
+    /// stats every entry of a local tree to fill in its `FileEntry`, while
+    /// a VFS scan uses only what `read_dir` returns.
+    ///
+    /// [`FolderScanner`]: crate::FolderScanner
+    PresenceOnly,
+}
+
+/// Hashing work done by a [`ComparisonEngine`], from
+/// [`ComparisonEngine::hash_stats`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HashStats {
+    /// Full hashes of local files answered by the hash cache
+    pub cache_hits: u64,
+    /// Full hashes of local files that had to read the file
+    pub cache_misses: u64,
+    /// Bytes read for hashing, partial and VFS hashes included
+    pub bytes_hashed: u64,
+    /// Time spent hashing, summed over worker threads
+    pub hash_time: Duration,
+}
+
+/// Running totals behind [`HashStats`], shared by the hashing threads
+#[derive(Debug, Default)]
+struct HashCounters {
+    cache_hits: AtomicU64,
+    cache_misses: AtomicU64,
+    bytes_hashed: AtomicU64,
+    hash_nanos: AtomicU64,
+}
+
+impl HashCounters {
+    /// Count `bytes` read for a hash that started at `started`
+    fn record(&self, bytes: u64, started: Instant) {
+        let nanos = u64::try_from(started.elapsed().as_nanos()).unwrap_or(u64::MAX);
+        self.bytes_hashed.fetch_add(bytes, Ordering::Relaxed);
+        self.hash_nanos.fetch_add(nanos, Ordering::Relaxed);
+    }
+
+    fn snapshot(&self) -> HashStats {
+        HashStats {
+            cache_hits: self.cache_hits.load(Ordering::Relaxed),
+            cache_misses: self.cache_misses.load(Ordering::Relaxed),
+            bytes_hashed: self.bytes_hashed.load(Ordering::Relaxed),
+            hash_time: Duration::from_nanos(self.hash_nanos.load(Ordering::Relaxed)),
+        }
+    }
+}
 
 /// Comparison engine for comparing file trees with BLAKE3 hashing and persistent caching.
 ///
@@ -102,6 +176,11 @@ use tracing::{debug, info};
 /// hash comparisons. It supports both two-way and three-way comparisons, with optional
 /// hash verification for cache integrity.
 ///
+/// Comparisons emit `tracing` spans (`compare` with `classify` and
+/// `similarity` phases, plus a debug-level span per hash computation carrying
+/// the path and byte count), so an embedder can attach a subscriber for a
+/// timing breakdown. Without a subscriber they cost next to nothing.
+///
 /// # Examples
 ///
 /// ```no_run
@@ -119,28 +198,149 @@ use tracing::{debug, info};
 pub struct ComparisonEngine {
     cache: HashCache,
     verify_hashes: bool,
+    /// Read every same-sized pair in full, trusting no metadata or cache
+    force_content_check: bool,
+    /// Compare content of files read through a VFS even without hash
+    /// verification, since archives do not preserve timestamps reliably
+    verify_vfs_content: bool,
+    /// Hash VFS content even when cheap fingerprints (e.g. ZIP CRC32) match
+    strict_fingerprints: bool,
     /// Threshold in bytes for using streaming comparison (default: 100MB)
     /// Files larger than this will be compared in chunks to avoid loading entirely into memory
     streaming_threshold: u64,
+    /// Read buffer for hashing; `None` picks 64KB, or 1MB above 10MB
+    hash_buffer_size: Option<usize>,
+    /// Chunk size of the streaming comparison of large files (default: 1MB)
+    stream_chunk_size: usize,
+    /// Minimum similarity (percent) for pairing orphans as near-duplicates;
+    /// `None` disables the similarity pass
+    similarity_threshold: Option<f64>,
+    /// Minimum similarity (percent) for marking a pair of orphans as a
+    /// rename; `None` disables rename detection
+    rename_threshold: Option<f64>,
+    /// Upper bound on orphan pairs examined by the similarity pass
+    max_similarity_pairs: usize,
+    /// Mark subtrees with matching directory signatures `Same` without
+    /// comparing their files
+    use_directory_signatures: bool,
+    mode: ComparisonMode,
+    /// Largest mtime difference still treated as the same timestamp
+    mtime_tolerance: Duration,
+    /// Tag pairs where only one side is zero-byte with
+    /// [`DiffReason::EmptiedFile`]
+    flag_emptied_files: bool,
+    /// Log a warning for every zero-byte file on either side
+    warn_on_empty_files: bool,
+    /// Pool for parallel hashing; `None` uses the global rayon pool
+    pool: Option<Arc<rayon::ThreadPool>>,
+    /// Compare differing compressed files by their decompressed content
+    #[cfg(feature = "archives")]
+    compare_decompressed: bool,
+    /// Rewrites relative paths before left and right entries are paired
+    path_transform: Option<PathTransform>,
+    /// Collisions of `path_transform` in the last comparison
+    path_collisions: Mutex<Vec<PathCollision>>,
+    counters: HashCounters,
 }
 
+/// Relative path rewrite of [`ComparisonEngine::with_path_transform`]
+type PathTransform = Arc<dyn Fn(&Path) -> PathBuf + Send + Sync>;
+
+/// Pairing key with the left and right entry found under it
+type EntryPair = (PathBuf, Option<FileEntry>, Option<FileEntry>);
+
 impl ComparisonEngine {
     /// Default streaming threshold: 100MB
     const DEFAULT_STREAMING_THRESHOLD: u64 = 100 * 1024 * 1024;
 
+    /// Default chunk size of the streaming comparison: 1MB
+    const DEFAULT_STREAM_CHUNK_SIZE: usize = 1024 * 1024;
+
+    /// Default cap on orphan pairs examined for near-duplicates
+    const DEFAULT_MAX_SIMILARITY_PAIRS: usize = 10_000;
+
+    /// Files larger than this are not fingerprinted for near-duplicate detection
+    const MAX_SIMILARITY_FILE_SIZE: u64 = 16 * 1024 * 1024;
+
     pub fn new(cache: HashCache) -> Self {
         Self {
             cache,
             verify_hashes: false,
+            force_content_check: false,
+            verify_vfs_content: true,
+            strict_fingerprints: false,
             streaming_threshold: Self::DEFAULT_STREAMING_THRESHOLD,
+            hash_buffer_size: None,
+            stream_chunk_size: Self::DEFAULT_STREAM_CHUNK_SIZE,
+            similarity_threshold: None,
+            rename_threshold: None,
+            max_similarity_pairs: Self::DEFAULT_MAX_SIMILARITY_PAIRS,
+            use_directory_signatures: false,
+            mode: ComparisonMode::Content,
+            mtime_tolerance: Duration::ZERO,
+            flag_emptied_files: false,
+            warn_on_empty_files: false,
+            pool: None,
+            #[cfg(feature = "archives")]
+            compare_decompressed: false,
+            path_transform: None,
+            path_collisions: Mutex::new(Vec::new()),
+            counters: HashCounters::default(),
         }
     }
 
+    /// Compare same-sized files by hashing their content (default off)
+    ///
+    /// Files on backends with expensive reads are still read when this is
+    /// on; without it they are decided by fingerprints and mtimes.
     pub fn with_hash_verification(mut self, enabled: bool) -> Self {
         self.verify_hashes = enabled;
         self
     }
 
+    /// Compare the content of every same-sized pair, trusting nothing else
+    /// (default off)
+    ///
+    /// Hash verification alone still takes shortcuts that rest on metadata:
+    /// local hashes come from the hash cache when path, size and mtime are
+    /// unchanged, content fingerprints such as ZIP CRC32s are trusted and
+    /// matching directory signatures skip whole subtrees. Silent corruption (bit rot) keeps
+    /// size and mtime intact, so it slips through all of them. With this
+    /// on, both files of every same-sized pair are read and hashed afresh.
+    ///
+    /// This costs a full read of both trees' common files on every run,
+    /// including downloads from remote backends, so it is meant for
+    /// periodic integrity checks rather than routine comparisons.
+    pub fn with_force_content_check(mut self, enabled: bool) -> Self {
+        self.force_content_check = enabled;
+        self
+    }
+
+    /// Compare content whenever a side is read through a VFS (default on)
+    ///
+    /// Archive entries rarely keep the timestamps of the files they were
+    /// made from, so a size-and-mtime check against an archive would report
+    /// identical files as unchecked. With this on, pairs involving a VFS
+    /// with cheap reads are hashed as if hash verification were enabled;
+    /// backends with expensive reads still use fingerprints and mtimes.
+    pub fn with_vfs_content_verification(mut self, enabled: bool) -> Self {
+        self.verify_vfs_content = enabled;
+        self
+    }
+
+    /// Confirm matching content fingerprints by hashing (default off)
+    ///
+    /// Before reading two VFS files, their fingerprints (such as the CRC32
+    /// and size a ZIP central directory stores) are compared: different
+    /// fingerprints of the same scheme always mean different content, and
+    /// equal ones are trusted as the same. A CRC32 collision between files
+    /// of equal size is astronomically unlikely; with this on, equal
+    /// fingerprints are still confirmed by a full read.
+    pub fn with_strict_fingerprints(mut self, enabled: bool) -> Self {
+        self.strict_fingerprints = enabled;
+        self
+    }
+
     /// Set the threshold for streaming comparison (in bytes)
     /// Files larger than this will be compared using chunk-by-chunk streaming
     /// to avoid loading them entirely into memory. Default is 100MB.
@@ -149,6 +349,185 @@ impl ComparisonEngine {
         self
     }
 
+    /// Use a fixed read buffer of `bytes` (at least 1) when hashing files
+    ///
+    /// By default local files are read in 64KB blocks, or 1MB blocks above
+    /// 10MB, and VFS readers in 64KB blocks. Backends that hash files
+    /// themselves through [`Vfs::hash`] use their own buffers and ignore this
+    /// setting. The `hashing` benchmarks show the effect of other sizes on a
+    /// given machine.
+    pub fn with_hash_buffer_size(mut self, bytes: usize) -> Self {
+        self.hash_buffer_size = Some(bytes.max(1));
+        self
+    }
+
+    /// Set the chunk size (at least 1 byte) of the streaming comparison
+    /// used above the streaming threshold. Default is 1MB.
+    pub fn with_stream_chunk_size(mut self, bytes: usize) -> Self {
+        self.stream_chunk_size = bytes.max(1);
+        self
+    }
+
+    /// Pair left-only and right-only files that are at least `threshold`
+    /// percent similar, recording the match in [`DiffNode::similar_to`]
+    ///
+    /// Off by default. Similarity is estimated from content-defined chunk
+    /// fingerprints (see [`crate::similarity`]), so it tolerates inserted or
+    /// removed lines. Skipped for backends with expensive reads.
+    pub fn with_similarity_detection(mut self, threshold: f64) -> Self {
+        self.similarity_threshold = Some(threshold.clamp(0.0, 100.0));
+        self
+    }
+
+    /// Match a moved-and-edited file as a rename when it is at least
+    /// `threshold` (0.0 - 1.0, clamped) similar to a left-only file
+    ///
+    /// Both orphans of a rename are marked [`DiffReason::Renamed`] and get
+    /// the other path and the score, as a percentage, in
+    /// [`DiffNode::similar_to`]. Renames are found by the same pass as
+    /// [`with_similarity_detection`](Self::with_similarity_detection); with
+    /// both on, pairs below the rename threshold stay near-duplicates.
+    pub fn with_rename_similarity_threshold(mut self, threshold: f64) -> Self {
+        self.rename_threshold = Some(threshold.clamp(0.0, 1.0) * 100.0);
+        self
+    }
+
+    /// Limit the number of orphan pairs the similarity pass examines
+    /// (default 10,000), bounding the otherwise quadratic pairing
+    pub fn with_max_similarity_pairs(mut self, max_pairs: usize) -> Self {
+        self.max_similarity_pairs = max_pairs;
+        self
+    }
+
+    /// Skip per-file work inside directories whose signatures match
+    ///
+    /// Off by default. Signatures (see [`FolderScanner::directory_signatures`])
+    /// cover names, sizes and modification times, so a matching subtree is
+    /// marked `Same` on metadata alone, even with hash verification enabled.
+    ///
+    /// [`FolderScanner::directory_signatures`]: crate::FolderScanner::directory_signatures
+    pub fn with_directory_signatures(mut self, enabled: bool) -> Self {
+        self.use_directory_signatures = enabled;
+        self
+    }
+
+    /// Choose what to inspect for paths present on both sides
+    ///
+    /// [`ComparisonMode::StructureOnly`] and [`ComparisonMode::PresenceOnly`]
+    /// also disable the similarity pass, since that reads file content.
+    pub fn with_mode(mut self, mode: ComparisonMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Treat modification times at most `tolerance` apart as equal
+    ///
+    /// Exact by default. FAT/exFAT store mtimes at 2-second granularity and
+    /// some cloud backends round to the second, so copies across them need
+    /// a tolerance to be recognized as unchanged without hashing.
+    pub fn with_mtime_tolerance(mut self, tolerance: Duration) -> Self {
+        self.mtime_tolerance = tolerance;
+        self
+    }
+
+    /// Tag files that are empty on one side only (default off)
+    ///
+    /// A zero-byte file opposite one with content is usually a truncated
+    /// copy rather than a real edit. Such pairs keep their status and get
+    /// [`DiffReason::EmptiedFile`] in [`DiffNode::reason`]; files empty on
+    /// both sides are still `Same`.
+    pub fn with_emptied_file_detection(mut self, enabled: bool) -> Self {
+        self.flag_emptied_files = enabled;
+        self
+    }
+
+    /// Log a warning for every zero-byte file found on either side
+    /// (default off)
+    pub fn with_empty_file_warnings(mut self, enabled: bool) -> Self {
+        self.warn_on_empty_files = enabled;
+        self
+    }
+
+    /// Compare compressed files by their decompressed content (default off)
+    ///
+    /// Recompressing a file at another level, or with another tool,
+    /// changes every byte of it. With this on, a pair of single-file
+    /// compressed files (.gz, .bz2, .xz, .zst, recognized by extension or
+    /// magic bytes) whose bytes differ is decompressed and compared again;
+    /// matching payloads are reported `Same` with
+    /// [`DiffReason::DecompressedMatch`]. Corrupt streams keep their status.
+    #[cfg(feature = "archives")]
+    pub fn with_decompressed_comparison(mut self, enabled: bool) -> Self {
+        self.compare_decompressed = enabled;
+        self
+    }
+
+    /// Pair left and right entries by their relative paths after `transform`
+    ///
+    /// Lets names that differ systematically between the trees match, such
+    /// as hashed assets `app.abc123.js` and `app.def456.js` once the hash is
+    /// stripped. A node keeps the left path (or the right one for a right
+    /// orphan), and nodes come in the order of the transformed paths. Entries
+    /// of one side that transform to the same path are not paired; they stay
+    /// under their own paths and are reported by
+    /// [`path_collisions`](Self::path_collisions). Directory signatures are
+    /// not used while a transform is set.
+    pub fn with_path_transform(
+        mut self,
+        transform: impl Fn(&Path) -> PathBuf + Send + Sync + 'static,
+    ) -> Self {
+        self.path_transform = Some(Arc::new(transform));
+        self
+    }
+
+    /// Hash files in parallel on `threads` worker threads instead of the
+    /// global rayon pool
+    ///
+    /// Falls back to the global pool, with a warning, if the threads can't
+    /// be spawned.
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        match rayon::ThreadPoolBuilder::new()
+            .num_threads(threads.max(1))
+            .build()
+        {
+            Ok(pool) => self.pool = Some(Arc::new(pool)),
+            Err(e) => warn!("Failed to build thread pool, using the global one: {}", e),
+        }
+        self
+    }
+
+    /// Worker threads used for parallel hashing
+    pub fn threads(&self) -> usize {
+        self.pool
+            .as_ref()
+            .map_or_else(rayon::current_num_threads, |pool| {
+                pool.current_num_threads()
+            })
+    }
+
+    /// Run `op` on the engine's thread pool
+    fn install<R: Send>(&self, op: impl FnOnce() -> R + Send) -> R {
+        match &self.pool {
+            Some(pool) => pool.install(op),
+            None => op(),
+        }
+    }
+
+    /// Hashing done by this engine so far, across all comparisons
+    pub fn hash_stats(&self) -> HashStats {
+        self.counters.snapshot()
+    }
+
+    /// Paths the [path transform](Self::with_path_transform) mapped to the
+    /// same name in the last comparison
+    pub fn path_collisions(&self) -> Vec<PathCollision> {
+        self.path_collisions
+            .lock()
+            .map(|collisions| collisions.clone())
+            .unwrap_or_default()
+    }
+
+    /// Write the hash cache to disk; a no-op for [`HashCache::in_memory`]
     pub fn persist_cache(&self) -> Result<(), RCompareError> {
         self.cache.persist()
     }
@@ -219,6 +598,15 @@ impl ComparisonEngine {
     /// # Arguments
     ///
     /// * `progress_fn` - Optional callback function that receives (current, total) progress updates
+    #[instrument(
+        name = "compare",
+        skip_all,
+        fields(
+            left_entries = left_entries.len(),
+            right_entries = right_entries.len(),
+            nodes = tracing::field::Empty
+        )
+    )]
     pub fn compare_with_vfs_and_progress<F>(
         &self,
         left_root: &Path,
@@ -232,6 +620,161 @@ impl ComparisonEngine {
     ) -> Result<Vec<DiffNode>, RCompareError>
     where
         F: Fn(usize, usize),
+    {
+        let mut diff_nodes = Vec::new();
+        self.classify_each(
+            left_root,
+            right_root,
+            left_entries,
+            right_entries,
+            left_vfs,
+            right_vfs,
+            cancel,
+            progress_fn,
+            |node| {
+                diff_nodes.push(node);
+                Ok(())
+            },
+        )?;
+
+        self.pair_near_duplicates(&mut diff_nodes, left_root, right_root, left_vfs, right_vfs);
+
+        debug!("Generated {} diff nodes", diff_nodes.len());
+        Span::current().record("nodes", diff_nodes.len());
+        Ok(diff_nodes)
+    }
+
+    /// Fill in `similar_to` for orphans with a near-duplicate on the other
+    /// side, when [`with_similarity_detection`](Self::with_similarity_detection)
+    /// or [`with_rename_similarity_threshold`](Self::with_rename_similarity_threshold)
+    /// is on, and mark renames
+    ///
+    /// [`compare_with_vfs`](Self::compare_with_vfs) does this itself; call it
+    /// on the nodes collected from [`compare_each`](Self::compare_each) to
+    /// get the same result. Statuses are left unchanged.
+    pub fn pair_near_duplicates(
+        &self,
+        diff_nodes: &mut [DiffNode],
+        left_root: &Path,
+        right_root: &Path,
+        left_vfs: Option<&dyn Vfs>,
+        right_vfs: Option<&dyn Vfs>,
+    ) {
+        let threshold = match (self.similarity_threshold, self.rename_threshold) {
+            (Some(similar), Some(rename)) => similar.min(rename),
+            (Some(threshold), None) | (None, Some(threshold)) => threshold,
+            (None, None) => return,
+        };
+        if self.mode != ComparisonMode::Content {
+            debug!("Skipping similarity pass ({:?} comparison)", self.mode);
+        } else if reads_are_expensive(left_vfs, right_vfs) {
+            debug!("Skipping similarity pass (backend reads are expensive)");
+        } else {
+            self.pair_similar_orphans(
+                diff_nodes, left_root, right_root, left_vfs, right_vfs, threshold,
+            );
+        }
+    }
+
+    /// Compare directory entries, handing each node to `on_node` as soon as
+    /// it is classified instead of collecting them
+    ///
+    /// Nodes arrive in the same order as from
+    /// [`compare_with_vfs`](Self::compare_with_vfs). Near-duplicate pairing
+    /// needs every orphan at once, so it is skipped and `similar_to` is
+    /// always `None`; run [`pair_near_duplicates`](Self::pair_near_duplicates)
+    /// over the collected nodes to add it. An error from `on_node` stops the
+    /// comparison.
+    #[instrument(
+        name = "compare",
+        skip_all,
+        fields(
+            left_entries = left_entries.len(),
+            right_entries = right_entries.len(),
+            nodes = tracing::field::Empty
+        )
+    )]
+    pub fn compare_each<S>(
+        &self,
+        left_root: &Path,
+        right_root: &Path,
+        left_entries: Vec<FileEntry>,
+        right_entries: Vec<FileEntry>,
+        left_vfs: Option<&dyn Vfs>,
+        right_vfs: Option<&dyn Vfs>,
+        cancel: Option<&AtomicBool>,
+        mut on_node: S,
+    ) -> Result<(), RCompareError>
+    where
+        S: FnMut(DiffNode) -> Result<(), RCompareError>,
+    {
+        let mut nodes = 0;
+        self.classify_each::<fn(usize, usize), _>(
+            left_root,
+            right_root,
+            left_entries,
+            right_entries,
+            left_vfs,
+            right_vfs,
+            cancel,
+            None,
+            |node| {
+                nodes += 1;
+                on_node(node)
+            },
+        )?;
+        Span::current().record("nodes", nodes);
+        Ok(())
+    }
+
+    /// Compare directory entries, spilling the result to a temp file
+    ///
+    /// The finished nodes are not kept in memory; read them back with
+    /// [`SpilledDiff::iter`]. The entry lists and the path index built to
+    /// pair them still are, so peak memory grows with the number of entries,
+    /// just without a second copy of everything as result nodes. Behaves
+    /// like [`compare_each`](Self::compare_each), so near-duplicates are not
+    /// paired.
+    pub fn compare_spilled(
+        &self,
+        left_root: &Path,
+        right_root: &Path,
+        left_entries: Vec<FileEntry>,
+        right_entries: Vec<FileEntry>,
+        left_vfs: Option<&dyn Vfs>,
+        right_vfs: Option<&dyn Vfs>,
+        cancel: Option<&AtomicBool>,
+    ) -> Result<SpilledDiff, RCompareError> {
+        let mut spill = DiffSpillWriter::new()?;
+        self.compare_each(
+            left_root,
+            right_root,
+            left_entries,
+            right_entries,
+            left_vfs,
+            right_vfs,
+            cancel,
+            |node| spill.push(node),
+        )?;
+        spill.finish()
+    }
+
+    /// Classify every path of both entry lists in path order
+    fn classify_each<F, S>(
+        &self,
+        left_root: &Path,
+        right_root: &Path,
+        left_entries: Vec<FileEntry>,
+        right_entries: Vec<FileEntry>,
+        left_vfs: Option<&dyn Vfs>,
+        right_vfs: Option<&dyn Vfs>,
+        cancel: Option<&AtomicBool>,
+        progress_fn: Option<F>,
+        mut sink: S,
+    ) -> Result<(), RCompareError>
+    where
+        F: Fn(usize, usize),
+        S: FnMut(DiffNode) -> Result<(), RCompareError>,
     {
         info!(
             "Comparing {} left entries with {} right entries",
@@ -239,27 +782,24 @@ impl ComparisonEngine {
             right_entries.len()
         );
 
-        let mut left_map: HashMap<PathBuf, FileEntry> = left_entries
-            .into_iter()
-            .map(|e| (e.path.clone(), e))
-            .collect();
-
-        let mut right_map: HashMap<PathBuf, FileEntry> = right_entries
-            .into_iter()
-            .map(|e| (e.path.clone(), e))
-            .collect();
-
-        let mut diff_nodes = Vec::new();
+        let identical_dirs = if self.use_directory_signatures
+            && !self.force_content_check
+            && self.path_transform.is_none()
+        {
+            matching_directories(&left_entries, &right_entries)
+        } else {
+            HashSet::new()
+        };
 
-        // Find all unique paths
-        let mut all_paths: Vec<PathBuf> =
-            left_map.keys().chain(right_map.keys()).cloned().collect();
-        all_paths.sort();
-        all_paths.dedup();
+        let pairs = match &self.path_transform {
+            Some(transform) => self.pair_transformed(transform, left_entries, right_entries),
+            None => pair_by_path(left_entries, right_entries),
+        };
 
-        let total = all_paths.len();
+        let total = pairs.len();
+        let classify_span = debug_span!("classify", paths = total).entered();
 
-        for (idx, path) in all_paths.into_iter().enumerate() {
+        for (idx, (path, mut left, mut right)) in pairs.into_iter().enumerate() {
             if cancel.is_some_and(|flag| flag.load(Ordering::Relaxed)) {
                 return Err(RCompareError::Comparison(
                     "Comparison cancelled".to_string(),
@@ -271,15 +811,28 @@ impl ComparisonEngine {
                 progress(idx + 1, total);
             }
 
-            let left = left_map.remove(&path);
-            let right = right_map.remove(&path);
+            let mut changed_during_scan = false;
+            let mut refreshed = None;
 
-            let status = match (&left, &right) {
+            let mut status = match (&left, &right) {
+                (Some(_), Some(_)) if in_identical_dir(&path, &identical_dirs) => DiffStatus::Same,
+                (Some(_), Some(_)) if self.mode == ComparisonMode::PresenceOnly => DiffStatus::Same,
                 (Some(l), Some(r)) => {
-                    if l.is_dir && r.is_dir {
+                    if is_type_conflict(l, r) {
+                        // The directory side's children are still scanned and
+                        // show up as orphans under this path
+                        DiffStatus::TypeConflict
+                    } else if l.is_dir
+                        || l.is_special()
+                        || self.mode == ComparisonMode::StructureOnly
+                    {
+                        // FIFOs, sockets and devices are never read
                         DiffStatus::Same
-                    } else if l.is_dir || r.is_dir {
-                        DiffStatus::Different
+                    } else if left_vfs.is_none() && right_vfs.is_none() {
+                        let checked = self.compare_listed_files(left_root, right_root, l, r)?;
+                        changed_during_scan = checked.changed_during_scan;
+                        refreshed = checked.refreshed;
+                        checked.status
                     } else {
                         self.compare_files(left_root, right_root, left_vfs, right_vfs, l, r)?
                     }
@@ -289,61 +842,403 @@ impl ComparisonEngine {
                 (None, None) => continue,
             };
 
-            diff_nodes.push(DiffNode {
-                relative_path: path,
+            if let Some((l, r)) = refreshed {
+                left = Some(l);
+                right = Some(r);
+            }
+            #[cfg(feature = "archives")]
+            let decompressed_match = self.compare_decompressed
+                && !changed_during_scan
+                && matches!(status, DiffStatus::Different | DiffStatus::Unchecked)
+                && match (&left, &right) {
+                    (Some(l), Some(r)) => {
+                        self.decompressed_same(left_root, right_root, left_vfs, right_vfs, l, r)
+                    }
+                    _ => false,
+                };
+            #[cfg(not(feature = "archives"))]
+            let decompressed_match = false;
+            if decompressed_match {
+                status = DiffStatus::Same;
+            }
+
+            let reason = if self.mode == ComparisonMode::PresenceOnly {
+                None
+            } else {
+                self.empty_file_reason(&path, left.as_ref(), right.as_ref())
+            };
+            let reason = if changed_during_scan {
+                Some(DiffReason::ChangedDuringScan)
+            } else if decompressed_match {
+                Some(DiffReason::DecompressedMatch)
+            } else {
+                reason
+            };
+            let relative_path = match (&self.path_transform, left.as_ref().or(right.as_ref())) {
+                (Some(_), Some(entry)) => entry.path.clone(),
+                _ => path,
+            };
+            sink(DiffNode {
+                relative_path,
                 left,
                 right,
                 status,
-            });
+                similar_to: None,
+                reason,
+                ignored_by: None,
+            })?;
         }
+        classify_span.exit();
+        Ok(())
+    }
 
-        debug!("Generated {} diff nodes", diff_nodes.len());
-        Ok(diff_nodes)
+    /// Pair both sides' entries by transformed path, recording collisions
+    ///
+    /// Entries of a collision stay unpaired, listed under their own paths.
+    fn pair_transformed(
+        &self,
+        transform: &PathTransform,
+        left_entries: Vec<FileEntry>,
+        right_entries: Vec<FileEntry>,
+    ) -> Vec<EntryPair> {
+        let mut groups: BTreeMap<PathBuf, (Vec<FileEntry>, Vec<FileEntry>)> = BTreeMap::new();
+        for entry in left_entries {
+            let (left, _) = groups.entry(transform(&entry.path)).or_default();
+            left.push(entry);
+        }
+        for entry in right_entries {
+            let (_, right) = groups.entry(transform(&entry.path)).or_default();
+            right.push(entry);
+        }
+
+        let mut pairs = Vec::new();
+        let mut collisions = Vec::new();
+        for (transformed, (mut left, mut right)) in groups {
+            if left.len() <= 1 && right.len() <= 1 {
+                pairs.push((transformed, left.pop(), right.pop()));
+                continue;
+            }
+
+            let sorted_paths = |entries: &[FileEntry]| {
+                let mut paths: Vec<PathBuf> = entries.iter().map(|e| e.path.clone()).collect();
+                paths.sort();
+                paths
+            };
+            let collision = PathCollision {
+                transformed,
+                left: sorted_paths(&left),
+                right: sorted_paths(&right),
+            };
+            warn!(
+                "{} left and {} right paths transform to {}, leaving them unpaired",
+                collision.left.len(),
+                collision.right.len(),
+                collision.transformed.display()
+            );
+            collisions.push(collision);
+            pairs.extend(left.into_iter().map(|e| (e.path.clone(), Some(e), None)));
+            pairs.extend(right.into_iter().map(|e| (e.path.clone(), None, Some(e))));
+        }
+
+        if let Ok(mut recorded) = self.path_collisions.lock() {
+            *recorded = collisions;
+        }
+        // Unpaired entries may sort anywhere among the transformed paths
+        pairs.sort_by(|a, b| a.0.cmp(&b.0));
+        pairs
     }
 
-    /// Compare two individual files
-    fn compare_files(
+    /// Compare a local pair, guarding against files that change mid-scan
+    ///
+    /// The listing may be stale by the time a pair is read. If either file no
+    /// longer matches its listed size and mtime after its content was read,
+    /// both are re-stat'd and compared once more with the fresh metadata. A
+    /// pair that is still moving, or has vanished, comes back `Unchecked` and
+    /// flagged instead of being reported from content that may be outdated.
+    fn compare_listed_files(
         &self,
         left_root: &Path,
         right_root: &Path,
-        left_vfs: Option<&dyn Vfs>,
-        right_vfs: Option<&dyn Vfs>,
         left: &FileEntry,
         right: &FileEntry,
-    ) -> Result<DiffStatus, RCompareError> {
-        // Safety check: ensure neither entry is a directory
-        // (catches cases where is_dir flag might be incorrect due to symlinks)
-        if left.is_dir || right.is_dir {
-            return Ok(DiffStatus::Different);
+    ) -> Result<ListedComparison, RCompareError> {
+        let status = self.compare_files(left_root, right_root, None, None, left, right)?;
+        // Decisions made from the listing alone are consistent with it
+        let reads_content = self.verifies_content(None, None)
+            && !left.is_dir
+            && !right.is_dir
+            && !left.is_special()
+            && left.size == right.size
+            && left.size > 0;
+        if !reads_content {
+            return Ok(ListedComparison::settled(status));
+        }
+        if !changed_since_listing(left_root, left) && !changed_since_listing(right_root, right) {
+            return Ok(ListedComparison::settled(status));
         }
 
-        // Quick size check
-        if left.size != right.size {
-            return Ok(DiffStatus::Different);
+        debug!(
+            "{} changed during the scan, comparing again",
+            left.path.display()
+        );
+        let (Some(left), Some(right)) = (restat(left_root, left), restat(right_root, right)) else {
+            return Ok(ListedComparison::volatile());
+        };
+        let status = self.compare_files(left_root, right_root, None, None, &left, &right)?;
+        if changed_since_listing(left_root, &left) || changed_since_listing(right_root, &right) {
+            warn!("{} keeps changing during the scan", left.path.display());
+            return Ok(ListedComparison::volatile());
         }
+        Ok(ListedComparison {
+            refreshed: Some((left, right)),
+            ..ListedComparison::settled(status)
+        })
+    }
 
-        if !self.verify_hashes {
-            // If sizes match and timestamps match, assume same
-            if left.modified == right.modified {
-                return Ok(DiffStatus::Same);
+    /// Apply the opt-in zero-byte checks to one path
+    fn empty_file_reason(
+        &self,
+        path: &Path,
+        left: Option<&FileEntry>,
+        right: Option<&FileEntry>,
+    ) -> Option<DiffReason> {
+        let is_file = |e: &FileEntry| !e.is_dir && !e.is_special();
+        let is_empty = |entry: Option<&FileEntry>| entry.is_some_and(|e| is_file(e) && e.size == 0);
+        let has_content =
+            |entry: Option<&FileEntry>| entry.is_some_and(|e| is_file(e) && e.size > 0);
+
+        if self.warn_on_empty_files {
+            for (side, entry) in [("left", left), ("right", right)] {
+                if is_empty(entry) {
+                    warn!("Zero-byte file on {} side: {}", side, path.display());
+                }
             }
-
-            // Otherwise, we'd need to compare content/hashes
-            return Ok(DiffStatus::Unchecked);
         }
 
-        let left_path = left_root.join(&left.path);
-        let right_path = right_root.join(&right.path);
+        let emptied =
+            (is_empty(left) && has_content(right)) || (has_content(left) && is_empty(right));
+        (self.flag_emptied_files && emptied).then_some(DiffReason::EmptiedFile)
+    }
 
-        if left_vfs.is_none() && right_vfs.is_none() {
-            // Try to hash files, but handle broken symlinks gracefully
-            let left_partial = match self.partial_hash_file(&left_path) {
-                Ok(hash) => hash,
-                Err(RCompareError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
-                    debug!("Skipping broken symlink: {}", left_path.display());
-                    return Ok(DiffStatus::Different);
-                }
-                Err(e) => return Err(e),
+    /// Pair orphan files with near-duplicates on the other side
+    ///
+    /// Candidate pairs whose sizes already rule out reaching `threshold` are
+    /// skipped without reading: right orphans are sorted by size, so each
+    /// left orphan only visits the window of sizes close enough to its own.
+    /// At most `max_similarity_pairs` are examined.
+    /// Matches are assigned greedily from the most similar pair down, so each
+    /// file is paired at most once.
+    #[instrument(name = "similarity", level = "debug", skip_all, fields(threshold = threshold))]
+    fn pair_similar_orphans(
+        &self,
+        diff_nodes: &mut [DiffNode],
+        left_root: &Path,
+        right_root: &Path,
+        left_vfs: Option<&dyn Vfs>,
+        right_vfs: Option<&dyn Vfs>,
+        threshold: f64,
+    ) {
+        use rayon::prelude::*;
+
+        let orphans = |status: DiffStatus| -> Vec<(usize, u64)> {
+            diff_nodes
+                .iter()
+                .enumerate()
+                .filter(|(_, node)| node.status == status)
+                .filter_map(|(idx, node)| {
+                    let entry = node.left.as_ref().or(node.right.as_ref())?;
+                    let fits = !entry.is_dir
+                        && !entry.is_special()
+                        && entry.size > 0
+                        && entry.size <= Self::MAX_SIMILARITY_FILE_SIZE;
+                    fits.then_some((idx, entry.size))
+                })
+                .collect()
+        };
+        let left_orphans = orphans(DiffStatus::OrphanLeft);
+        let mut right_orphans = orphans(DiffStatus::OrphanRight);
+        right_orphans.sort_unstable_by_key(|&(idx, size)| (size, idx));
+
+        let mut candidates = Vec::new();
+        'outer: for &(left_idx, left_size) in &left_orphans {
+            // The bound grows with the right size up to the left size and
+            // shrinks after it, so the reachable sizes are one window
+            let start = right_orphans.partition_point(|&(_, right_size)| {
+                right_size < left_size && similarity_upper_bound(left_size, right_size) < threshold
+            });
+            for &(right_idx, right_size) in &right_orphans[start..] {
+                if similarity_upper_bound(left_size, right_size) < threshold {
+                    break;
+                }
+                if candidates.len() >= self.max_similarity_pairs {
+                    debug!(
+                        "Similarity pass stopped after {} candidate pairs",
+                        self.max_similarity_pairs
+                    );
+                    break 'outer;
+                }
+                candidates.push((left_idx, right_idx));
+            }
+        }
+        if candidates.is_empty() {
+            return;
+        }
+
+        let mut needed: Vec<usize> = candidates.iter().flat_map(|&(l, r)| [l, r]).collect();
+        needed.sort_unstable();
+        needed.dedup();
+
+        let fingerprints: HashMap<usize, ChunkFingerprint> = self.install(|| {
+            needed
+                .par_iter()
+                .filter_map(|&idx| {
+                    let node = &diff_nodes[idx];
+                    let (root, vfs) = if node.status == DiffStatus::OrphanLeft {
+                        (left_root, left_vfs)
+                    } else {
+                        (right_root, right_vfs)
+                    };
+                    let path = root.join(&node.relative_path);
+                    let mut data = Vec::new();
+                    let read = self.open_reader(&path, vfs).and_then(|reader| {
+                        reader
+                            .take(Self::MAX_SIMILARITY_FILE_SIZE)
+                            .read_to_end(&mut data)
+                            .map_err(RCompareError::from)
+                    });
+                    match read {
+                        Ok(_) => Some((idx, ChunkFingerprint::from_bytes(&data))),
+                        Err(e) => {
+                            debug!("Skipping {} in similarity pass: {}", path.display(), e);
+                            None
+                        }
+                    }
+                })
+                .collect()
+        });
+
+        let mut scored: Vec<(f64, usize, usize)> = candidates
+            .into_iter()
+            .filter_map(|(l, r)| {
+                let similarity = fingerprints.get(&l)?.similarity(fingerprints.get(&r)?);
+                (similarity >= threshold).then_some((similarity, l, r))
+            })
+            .collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2)));
+
+        let mut paired = std::collections::HashSet::new();
+        for (similarity, l, r) in scored {
+            if paired.contains(&l) || paired.contains(&r) {
+                continue;
+            }
+            paired.insert(l);
+            paired.insert(r);
+
+            let left_path = diff_nodes[l].relative_path.clone();
+            let right_path = diff_nodes[r].relative_path.clone();
+            debug!(
+                "Near-duplicate ({:.1}%): {} ~ {}",
+                similarity,
+                left_path.display(),
+                right_path.display()
+            );
+            if matches!(self.rename_threshold, Some(rename) if similarity >= rename) {
+                diff_nodes[l].reason = Some(DiffReason::Renamed);
+                diff_nodes[r].reason = Some(DiffReason::Renamed);
+            }
+            diff_nodes[l].similar_to = Some(SimilarTo {
+                path: right_path,
+                similarity,
+            });
+            diff_nodes[r].similar_to = Some(SimilarTo {
+                path: left_path,
+                similarity,
+            });
+        }
+    }
+
+    /// Compare two individual files
+    fn compare_files(
+        &self,
+        left_root: &Path,
+        right_root: &Path,
+        left_vfs: Option<&dyn Vfs>,
+        right_vfs: Option<&dyn Vfs>,
+        left: &FileEntry,
+        right: &FileEntry,
+    ) -> Result<DiffStatus, RCompareError> {
+        // Safety check: ensure neither entry is a directory
+        // (catches cases where is_dir flag might be incorrect due to symlinks)
+        if left.is_dir || right.is_dir {
+            return Ok(DiffStatus::Different);
+        }
+        // Reading a FIFO or device could block or never end
+        if is_type_conflict(left, right) {
+            return Ok(DiffStatus::TypeConflict);
+        }
+        if left.is_special() {
+            return Ok(DiffStatus::Same);
+        }
+
+        // Quick size check
+        if left.size != right.size {
+            return Ok(DiffStatus::Different);
+        }
+
+        // VFS listings describe a symlink itself, not the file it points to
+        if left_vfs.is_some() || right_vfs.is_some() {
+            let targets = symlink_targets(
+                &left_root.join(&left.path),
+                &right_root.join(&right.path),
+                left_vfs,
+                right_vfs,
+            );
+            if let Some((left_target, right_target)) = targets {
+                return Ok(if left_target == right_target {
+                    DiffStatus::Same
+                } else {
+                    DiffStatus::Different
+                });
+            }
+        }
+
+        // Zero-byte files have no content to differ in
+        if left.size == 0 {
+            return Ok(DiffStatus::Same);
+        }
+
+        if !self.verifies_content(left_vfs, right_vfs) {
+            // If sizes match and timestamps match, assume same
+            if mtimes_match(left.modified, right.modified, self.mtime_tolerance) {
+                return Ok(DiffStatus::Same);
+            }
+
+            // Otherwise, we'd need to compare content/hashes
+            return Ok(DiffStatus::Unchecked);
+        }
+
+        let left_path = left_root.join(&left.path);
+        let right_path = right_root.join(&right.path);
+
+        if reads_are_expensive(left_vfs, right_vfs) && !self.reads_expensive_content() {
+            let same =
+                self.cheap_files_same(&left_path, &right_path, left_vfs, right_vfs, left, right);
+            return Ok(match same {
+                Some(true) => DiffStatus::Same,
+                Some(false) => DiffStatus::Different,
+                None => DiffStatus::Unchecked,
+            });
+        }
+
+        if left_vfs.is_none() && right_vfs.is_none() {
+            // Try to hash files, but handle broken symlinks gracefully
+            let left_partial = match self.partial_hash_file(&left_path) {
+                Ok(hash) => hash,
+                Err(RCompareError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+                    debug!("Skipping broken symlink: {}", left_path.display());
+                    return Ok(DiffStatus::Different);
+                }
+                Err(e) => return Err(e),
             };
 
             let right_partial = match self.partial_hash_file(&right_path) {
@@ -394,8 +1289,16 @@ impl ComparisonEngine {
             });
         }
 
-        let left_reader = match self.open_reader(&left_path, left_vfs) {
-            Ok(reader) => reader,
+        if let Some(same) = self.fingerprints_decide(&left_path, &right_path, left_vfs, right_vfs) {
+            return Ok(if same {
+                DiffStatus::Same
+            } else {
+                DiffStatus::Different
+            });
+        }
+
+        let left_hash = match self.read_hash(&left_path, left.size, left_vfs) {
+            Ok(hash) => hash,
             Err(RCompareError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
                 debug!("Skipping broken symlink: {}", left_path.display());
                 return Ok(DiffStatus::Different);
@@ -403,8 +1306,8 @@ impl ComparisonEngine {
             Err(e) => return Err(e),
         };
 
-        let right_reader = match self.open_reader(&right_path, right_vfs) {
-            Ok(reader) => reader,
+        let right_hash = match self.read_hash(&right_path, right.size, right_vfs) {
+            Ok(hash) => hash,
             Err(RCompareError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
                 debug!("Skipping broken symlink: {}", right_path.display());
                 return Ok(DiffStatus::Different);
@@ -412,9 +1315,6 @@ impl ComparisonEngine {
             Err(e) => return Err(e),
         };
 
-        let left_hash = self.hash_reader(left_reader)?;
-        let right_hash = self.hash_reader(right_reader)?;
-
         Ok(if left_hash == right_hash {
             DiffStatus::Same
         } else {
@@ -422,6 +1322,221 @@ impl ComparisonEngine {
         })
     }
 
+    /// Find files with identical content within a single tree
+    ///
+    /// Files are grouped by size first, and only sizes shared by two or more
+    /// files are hashed (through the hash cache for local files), so unique
+    /// sizes are never read. Directories and empty files are skipped, as are
+    /// files that vanished or are broken symlinks. Groups are ordered by
+    /// wasted bytes, largest first.
+    #[instrument(skip_all, fields(root = %root.display(), entries = entries.len()))]
+    pub fn find_duplicates(
+        &self,
+        root: &Path,
+        entries: &[FileEntry],
+        vfs: Option<&dyn Vfs>,
+    ) -> Result<Vec<DuplicateGroup>, RCompareError> {
+        let mut by_size: HashMap<u64, Vec<&FileEntry>> = HashMap::new();
+        for entry in entries
+            .iter()
+            .filter(|e| !e.is_dir && !e.is_special() && e.size > 0)
+        {
+            by_size.entry(entry.size).or_default().push(entry);
+        }
+
+        let mut groups = Vec::new();
+        for (size, candidates) in by_size.into_iter().filter(|(_, c)| c.len() > 1) {
+            let paths: Vec<PathBuf> = candidates.iter().map(|e| root.join(&e.path)).collect();
+            let hashes: Vec<Result<Blake3Hash, RCompareError>> = match vfs {
+                None => self
+                    .hash_files_parallel(paths.iter().map(PathBuf::as_path))
+                    .into_iter()
+                    .map(|(_, hash)| hash)
+                    .collect(),
+                Some(vfs) => paths
+                    .iter()
+                    .map(|path| self.read_hash(path, size, Some(vfs)))
+                    .collect(),
+            };
+
+            let mut by_hash: HashMap<Blake3Hash, Vec<PathBuf>> = HashMap::new();
+            for (entry, hash) in candidates.iter().zip(hashes) {
+                match hash {
+                    Ok(hash) => by_hash.entry(hash).or_default().push(entry.path.clone()),
+                    Err(RCompareError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => {
+                        debug!("Skipping missing file: {}", entry.path.display());
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+            for (hash, mut paths) in by_hash.into_iter().filter(|(_, p)| p.len() > 1) {
+                paths.sort();
+                groups.push(DuplicateGroup { hash, size, paths });
+            }
+        }
+
+        groups.sort_by(|a, b| {
+            b.wasted_bytes()
+                .cmp(&a.wasted_bytes())
+                .then_with(|| a.paths.cmp(&b.paths))
+        });
+        info!("Found {} duplicate groups", groups.len());
+        Ok(groups)
+    }
+
+    /// Compare a scanned tree against a precomputed manifest of the left side
+    ///
+    /// The manifest stands in for the left scan, e.g. a release manifest
+    /// checked against deployed artifacts. Manifests list files only, so
+    /// directories in `right_entries` are ignored unless the manifest has a
+    /// file at the same path (a `TypeConflict`, as is a FIFO, socket or
+    /// device node there). A right file is hashed only
+    /// when its size matches; an entry without a hash can't be verified and
+    /// is reported `Unchecked` when the size matches. Manifest-side entries
+    /// carry the Unix epoch as their modification time.
+    #[instrument(skip_all, fields(manifest = manifest.len(), right_entries = right_entries.len()))]
+    pub fn compare_against_manifest(
+        &self,
+        manifest: &[ManifestEntry],
+        right_root: &Path,
+        right_entries: Vec<FileEntry>,
+        right_vfs: Option<&dyn Vfs>,
+    ) -> Result<Vec<DiffNode>, RCompareError> {
+        let mut right_map: HashMap<PathBuf, FileEntry> = right_entries
+            .into_iter()
+            .map(|e| (e.path.clone(), e))
+            .collect();
+
+        let mut diff_nodes = Vec::new();
+        for item in manifest {
+            let left = FileEntry {
+                path: item.path.clone(),
+                size: item.size,
+                modified: SystemTime::UNIX_EPOCH,
+                is_dir: false,
+                file_type: FileType::Regular,
+            };
+            let right = right_map.remove(&item.path);
+            let status = match &right {
+                None => DiffStatus::OrphanLeft,
+                Some(_) if self.mode == ComparisonMode::PresenceOnly => DiffStatus::Same,
+                Some(r) if r.is_dir || r.is_special() => DiffStatus::TypeConflict,
+                Some(_) if self.mode == ComparisonMode::StructureOnly => DiffStatus::Same,
+                Some(r) if r.size != item.size => DiffStatus::Different,
+                Some(r) => {
+                    let path = right_root.join(&r.path);
+                    let same = if let Some(expected) = item.hash {
+                        let actual = match right_vfs {
+                            Some(vfs) => self.read_hash(&path, r.size, Some(vfs))?,
+                            None => self.hash_file(&path)?,
+                        };
+                        Some(actual == expected)
+                    } else if let Some(expected) = item.sha256 {
+                        Some(self.sha256_digest(&path, right_vfs)? == expected)
+                    } else {
+                        None
+                    };
+                    match same {
+                        Some(true) => DiffStatus::Same,
+                        Some(false) => DiffStatus::Different,
+                        None => DiffStatus::Unchecked,
+                    }
+                }
+            };
+            let reason = self.empty_file_reason(&item.path, Some(&left), right.as_ref());
+            diff_nodes.push(DiffNode {
+                relative_path: item.path.clone(),
+                left: Some(left),
+                right,
+                status,
+                similar_to: None,
+                reason,
+                ignored_by: None,
+            });
+        }
+
+        diff_nodes.extend(
+            right_map
+                .into_values()
+                .filter(|e| !e.is_dir)
+                .map(|e| DiffNode {
+                    relative_path: e.path.clone(),
+                    left: None,
+                    reason: self.empty_file_reason(&e.path, None, Some(&e)),
+                    right: Some(e),
+                    status: DiffStatus::OrphanRight,
+                    similar_to: None,
+                    ignored_by: None,
+                }),
+        );
+        diff_nodes.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+
+        debug!("Generated {} diff nodes", diff_nodes.len());
+        Ok(diff_nodes)
+    }
+
+    /// List the files of a scanned tree with their hashes, sorted by path
+    ///
+    /// The producer side of [`compare_against_manifest`](Self::compare_against_manifest):
+    /// write the result with [`format_manifest`]. BLAKE3 hashes of local files
+    /// go through the hash cache; SHA-256 and VFS entries are read in full.
+    #[instrument(skip_all, fields(entries = entries.len(), algorithm = algorithm.name()))]
+    pub fn generate_manifest(
+        &self,
+        root: &Path,
+        entries: Vec<FileEntry>,
+        vfs: Option<&dyn Vfs>,
+        algorithm: ManifestHashAlgorithm,
+    ) -> Result<Vec<ManifestRecord>, RCompareError> {
+        let mut files: Vec<FileEntry> = entries
+            .into_iter()
+            .filter(|e| !e.is_dir && !e.is_special())
+            .collect();
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+
+        files
+            .into_iter()
+            .map(|entry| {
+                let path = root.join(&entry.path);
+                let hash = match (algorithm, vfs) {
+                    (ManifestHashAlgorithm::Blake3, None) => self.hash_file(&path)?.to_hex(),
+                    (ManifestHashAlgorithm::Blake3, Some(vfs)) => {
+                        self.read_hash(&path, entry.size, Some(vfs))?.to_hex()
+                    }
+                    (ManifestHashAlgorithm::Sha256, vfs) => {
+                        format!("sha256:{}", hex::encode(self.sha256_digest(&path, vfs)?))
+                    }
+                };
+                Ok(ManifestRecord {
+                    path: entry.path,
+                    size: entry.size,
+                    modified: entry.modified,
+                    hash,
+                })
+            })
+            .collect()
+    }
+
+    /// SHA-256 of a file, from the backend when it can provide one
+    fn sha256_digest(&self, path: &Path, vfs: Option<&dyn Vfs>) -> Result<[u8; 32], RCompareError> {
+        let digest = match vfs {
+            Some(vfs) => backend_hash(path, vfs, HashAlgorithm::Sha256)?,
+            None => None,
+        };
+        if let Some(digest) = digest {
+            return digest.try_into().map_err(|_| {
+                RCompareError::Comparison(format!(
+                    "Backend returned a malformed SHA-256 for {}",
+                    path.display()
+                ))
+            });
+        }
+        let mut reader = self.open_reader(path, vfs)?;
+        let mut hasher = Sha256::new();
+        std::io::copy(&mut reader, &mut hasher)?;
+        Ok(hasher.finalize().into())
+    }
+
     /// Compute hashes for multiple files in parallel using rayon
     ///
     /// This method processes a batch of files concurrently, utilizing multiple CPU cores
@@ -477,15 +1592,33 @@ impl ComparisonEngine {
     {
         use rayon::prelude::*;
 
-        paths
-            .into_iter()
-            .collect::<Vec<_>>()
-            .par_iter()
-            .map(|path| (*path, self.hash_file(path)))
-            .collect()
+        let paths: Vec<&Path> = paths.into_iter().collect();
+        self.install(|| {
+            paths
+                .par_iter()
+                .map(|path| (*path, self.hash_file(path)))
+                .collect()
+        })
+    }
+
+    /// Read buffer used to hash a local file of `file_size` bytes
+    fn hash_buffer_size_for(&self, file_size: u64) -> usize {
+        // Larger files (>10MB) get a bigger buffer; BLAKE3 uses SIMD
+        // internally either way
+        self.hash_buffer_size
+            .unwrap_or(if file_size > 10 * 1024 * 1024 {
+                1024 * 1024
+            } else {
+                64 * 1024
+            })
     }
 
     /// Compute hash for a file
+    #[instrument(
+        level = "debug",
+        skip_all,
+        fields(path = %path.display(), bytes = tracing::field::Empty, cached)
+    )]
     pub fn hash_file(&self, path: &Path) -> Result<Blake3Hash, RCompareError> {
         // Check for broken symlinks first (use symlink_metadata which doesn't follow symlinks)
         let symlink_meta = std::fs::symlink_metadata(path).map_err(|e| {
@@ -539,11 +1672,23 @@ impl ComparisonEngine {
                 .unwrap_or(std::time::SystemTime::UNIX_EPOCH),
         };
 
-        // Check cache first
-        if let Some(cached_hash) = self.cache.get(&cache_key) {
+        Span::current().record("bytes", metadata.len());
+
+        // Check cache first, unless content must be read afresh
+        let cached = if self.force_content_check {
+            None
+        } else {
+            self.cache.get(&cache_key)
+        };
+        if let Some(cached_hash) = cached {
             debug!("Cache hit for {:?}", path);
+            Span::current().record("cached", true);
+            self.counters.cache_hits.fetch_add(1, Ordering::Relaxed);
             return Ok(cached_hash);
         }
+        Span::current().record("cached", false);
+        self.counters.cache_misses.fetch_add(1, Ordering::Relaxed);
+        let started = Instant::now();
 
         // Compute hash - use larger buffer for better performance
         let mut file = std::fs::File::open(path).map_err(|e| {
@@ -553,17 +1698,9 @@ impl ComparisonEngine {
             ))
         })?;
 
-        // For large files (>10MB), use a bigger buffer and parallel hashing
-        // BLAKE3 automatically uses SIMD and can parallelize internally
-        let file_size = metadata.len();
-        let buffer_size = if file_size > 10 * 1024 * 1024 {
-            1024 * 1024 // 1MB buffer for large files
-        } else {
-            64 * 1024 // 64KB buffer for small files
-        };
-
         let mut hasher = blake3::Hasher::new();
-        let mut buffer = vec![0; buffer_size];
+        let mut buffer = vec![0; self.hash_buffer_size_for(metadata.len())];
+        let mut bytes = 0u64;
 
         loop {
             let n = file.read(&mut buffer)?;
@@ -571,19 +1708,39 @@ impl ComparisonEngine {
                 break;
             }
             hasher.update(&buffer[..n]);
+            bytes += n as u64;
         }
 
         let hash: Blake3Hash = hasher.finalize().into();
-
-        // Store in cache
-        self.cache.put(cache_key, hash);
+        self.counters.record(bytes, started);
+
+        // A file written to while it was read has no stable hash to cache
+        let unchanged = std::fs::metadata(path).is_ok_and(|after| {
+            after.len() == cache_key.size && after.modified().ok() == Some(cache_key.modified)
+        });
+        if unchanged {
+            self.cache.put(cache_key, hash);
+        } else {
+            debug!("{} changed while hashing, not caching", path.display());
+        }
 
         Ok(hash)
     }
 
-    fn hash_reader(&self, mut reader: Box<dyn Read + Send>) -> Result<Blake3Hash, RCompareError> {
+    #[instrument(
+        level = "debug",
+        skip_all,
+        fields(path = %path.display(), bytes = tracing::field::Empty)
+    )]
+    fn hash_reader(
+        &self,
+        path: &Path,
+        mut reader: Box<dyn Read + Send>,
+    ) -> Result<Blake3Hash, RCompareError> {
+        let started = Instant::now();
         let mut hasher = blake3::Hasher::new();
-        let mut buffer = vec![0; 64 * 1024]; // 64KB buffer
+        let mut buffer = vec![0; self.hash_buffer_size.unwrap_or(64 * 1024)];
+        let mut bytes = 0u64;
 
         loop {
             let n = reader.read(&mut buffer)?;
@@ -591,20 +1748,41 @@ impl ComparisonEngine {
                 break;
             }
             hasher.update(&buffer[..n]);
+            bytes += n as u64;
         }
 
+        Span::current().record("bytes", bytes);
+        self.counters.record(bytes, started);
         Ok(hasher.finalize().into())
     }
 
+    /// BLAKE3 of a `size`-byte file, from the backend's [`Vfs::hash`] when it
+    /// provides one and otherwise read through [`open_reader`](Self::open_reader)
+    fn read_hash(
+        &self,
+        path: &Path,
+        size: u64,
+        vfs: Option<&dyn Vfs>,
+    ) -> Result<Blake3Hash, RCompareError> {
+        if let Some(vfs) = vfs {
+            let started = Instant::now();
+            let digest = backend_hash(path, vfs, HashAlgorithm::Blake3)?;
+            if let Some(hash) = digest.and_then(|d| <[u8; 32]>::try_from(d).ok()) {
+                self.counters.record(size, started);
+                return Ok(Blake3Hash(hash));
+            }
+        }
+        self.hash_reader(path, self.open_reader(path, vfs)?)
+    }
+
     fn open_reader(
         &self,
         path: &Path,
         vfs: Option<&dyn Vfs>,
     ) -> Result<Box<dyn Read + Send>, RCompareError> {
         if let Some(vfs) = vfs {
-            vfs.open_file(path).map_err(|e| {
-                RCompareError::Vfs(format!("Failed to open {} from VFS: {}", path.display(), e))
-            })
+            vfs.open_file(path)
+                .map_err(|e| RCompareError::vfs(format!("Failed to open {}", path.display()), e))
         } else {
             std::fs::File::open(path)
                 .map(|f| Box::new(f) as Box<dyn Read + Send>)
@@ -617,6 +1795,11 @@ impl ComparisonEngine {
         }
     }
 
+    #[instrument(
+        level = "debug",
+        skip_all,
+        fields(path = %path.display(), bytes = tracing::field::Empty)
+    )]
     fn partial_hash_file(&self, path: &Path) -> Result<Blake3Hash, RCompareError> {
         const CHUNK_SIZE: usize = 16 * 1024;
 
@@ -663,13 +1846,16 @@ impl ComparisonEngine {
         }
 
         let len = metadata.len();
+        Span::current().record("bytes", len);
 
+        let started = Instant::now();
         let mut hasher = blake3::Hasher::new();
 
         if len <= (CHUNK_SIZE as u64) * 3 {
             let mut buffer = Vec::with_capacity(len as usize);
             file.read_to_end(&mut buffer)?;
             hasher.update(&buffer);
+            self.counters.record(buffer.len() as u64, started);
         } else {
             let mut buffer = vec![0u8; CHUNK_SIZE];
 
@@ -685,6 +1871,7 @@ impl ComparisonEngine {
             file.seek(SeekFrom::Start(last_offset))?;
             file.read_exact(&mut buffer)?;
             hasher.update(&buffer);
+            self.counters.record(3 * CHUNK_SIZE as u64, started);
         }
 
         Ok(hasher.finalize().into())
@@ -701,6 +1888,11 @@ impl ComparisonEngine {
     /// This avoids loading the entire files into memory, making it suitable for very large files (>100MB).
     /// Returns true if files are identical, false if different.
     /// Exits early on first chunk mismatch for better performance.
+    #[instrument(
+        level = "debug",
+        skip_all,
+        fields(left = %left_path.display(), right = %right_path.display())
+    )]
     fn compare_files_streaming(&self, left_path: &Path, right_path: &Path) -> Result<bool, RCompareError> {
         use std::fs::File;
         use std::io::Read;
@@ -720,10 +1912,8 @@ impl ComparisonEngine {
             ))
         })?;
 
-        // Use 1MB chunks for streaming comparison
-        const STREAM_CHUNK_SIZE: usize = 1024 * 1024;
-        let mut left_buffer = vec![0u8; STREAM_CHUNK_SIZE];
-        let mut right_buffer = vec![0u8; STREAM_CHUNK_SIZE];
+        let mut left_buffer = vec![0u8; self.stream_chunk_size];
+        let mut right_buffer = vec![0u8; self.stream_chunk_size];
 
         loop {
             let left_read = left_file.read(&mut left_buffer)?;
@@ -879,7 +2069,6 @@ impl ComparisonEngine {
                     // Mixed dir/file - treat as both changed
                     return Ok(ThreeWayDiffStatus::BothChanged);
                 }
-
                 // Compare hashes/content
                 let base_same_as_left =
                     self.files_same(base_root, left_root, base_vfs, left_vfs, b, l)?;
@@ -939,14 +2128,19 @@ impl ComparisonEngine {
         entry1: &FileEntry,
         entry2: &FileEntry,
     ) -> Result<bool, RCompareError> {
+        // FIFOs, sockets and devices are compared by type, never read
+        if entry1.is_special() || entry2.is_special() {
+            return Ok(entry1.file_type == entry2.file_type);
+        }
+
         // Quick size check
         if entry1.size != entry2.size {
             return Ok(false);
         }
 
-        if !self.verify_hashes {
+        if !self.verifies_content(vfs1, vfs2) {
             // If sizes match and timestamps match, assume same
-            if entry1.modified == entry2.modified {
+            if mtimes_match(entry1.modified, entry2.modified, self.mtime_tolerance) {
                 return Ok(true);
             }
             // Can't determine without hash verification
@@ -956,232 +2150,2836 @@ impl ComparisonEngine {
         let path1 = root1.join(&entry1.path);
         let path2 = root2.join(&entry2.path);
 
+        if reads_are_expensive(vfs1, vfs2) && !self.reads_expensive_content() {
+            return Ok(self
+                .cheap_files_same(&path1, &path2, vfs1, vfs2, entry1, entry2)
+                .unwrap_or(false));
+        }
+
         if vfs1.is_none() && vfs2.is_none() {
             return self.verify_files(&path1, &path2);
         }
 
-        let reader1 = self.open_reader(&path1, vfs1)?;
-        let reader2 = self.open_reader(&path2, vfs2)?;
-        let hash1 = self.hash_reader(reader1)?;
-        let hash2 = self.hash_reader(reader2)?;
+        if let Some(same) = self.fingerprints_decide(&path1, &path2, vfs1, vfs2) {
+            return Ok(same);
+        }
+
+        let hash1 = self.read_hash(&path1, entry1.size, vfs1)?;
+        let hash2 = self.read_hash(&path2, entry2.size, vfs2)?;
 
         Ok(hash1 == hash2)
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::fs::File;
-    use std::io::Write;
-    use std::path::Path;
-    use std::time::SystemTime;
-    use tempfile::TempDir;
+    /// Whether both files are compressed and decompress to the same bytes
+    ///
+    /// Files that aren't compressed, or whose streams fail to decode, count
+    /// as not the same.
+    #[cfg(feature = "archives")]
+    fn decompressed_same(
+        &self,
+        left_root: &Path,
+        right_root: &Path,
+        left_vfs: Option<&dyn Vfs>,
+        right_vfs: Option<&dyn Vfs>,
+        left: &FileEntry,
+        right: &FileEntry,
+    ) -> bool {
+        if left.is_dir || right.is_dir || left.is_special() || right.is_special() {
+            return false;
+        }
+        let left_path = left_root.join(&left.path);
+        let right_path = right_root.join(&right.path);
+        let readers = self
+            .decompressing_reader(&left_path, left_vfs)
+            .and_then(|l| Ok(l.zip(self.decompressing_reader(&right_path, right_vfs)?)));
+        let result = match readers {
+            Ok(Some((left_reader, right_reader))) => {
+                streams_equal(left_reader, right_reader, self.stream_chunk_size)
+                    .map_err(RCompareError::from)
+            }
+            Ok(None) => Ok(false),
+            Err(e) => Err(e),
+        };
+        result.unwrap_or_else(|e| {
+            debug!(
+                "Could not compare {} decompressed: {}",
+                left.path.display(),
+                e
+            );
+            false
+        })
+    }
 
-    #[test]
-    fn test_comparison_basic() {
-        let temp = TempDir::new().unwrap();
-        let cache = HashCache::new(temp.path().to_path_buf()).unwrap();
-        let engine = ComparisonEngine::new(cache);
+    /// Reader over the decompressed content of `path`, or `None` if it is
+    /// not a compressed file
+    #[cfg(feature = "archives")]
+    fn decompressing_reader(
+        &self,
+        path: &Path,
+        vfs: Option<&dyn Vfs>,
+    ) -> Result<Option<Box<dyn Read + Send>>, RCompareError> {
+        let mut reader = std::io::BufReader::new(self.open_reader(path, vfs)?);
+        let compression = match CompressionType::from_path(path) {
+            Some(compression) => compression,
+            None => match CompressionType::from_magic(reader.fill_buf()?) {
+                Some(compression) => compression,
+                None => return Ok(None),
+            },
+        };
+        Ok(Some(compression.decoder(reader)?))
+    }
 
-        let left = vec![FileEntry {
-            path: PathBuf::from("file1.txt"),
-            size: 100,
-            modified: SystemTime::now(),
-            is_dir: false,
-        }];
+    /// Whether same-sized files on these backends are checked beyond their
+    /// size and mtime
+    ///
+    /// Unless hash verification or content checks were asked for, backends
+    /// with expensive reads are then checked by fingerprints only.
+    fn verifies_content(&self, vfs1: Option<&dyn Vfs>, vfs2: Option<&dyn Vfs>) -> bool {
+        self.verify_hashes
+            || self.force_content_check
+            || (self.verify_vfs_content && (vfs1.is_some() || vfs2.is_some()))
+    }
 
-        let right = vec![FileEntry {
-            path: PathBuf::from("file2.txt"),
-            size: 200,
-            modified: SystemTime::now(),
-            is_dir: false,
-        }];
+    /// Whether content on backends with expensive reads is read anyway,
+    /// because hash verification or content checks were asked for
+    fn reads_expensive_content(&self) -> bool {
+        self.verify_hashes || self.force_content_check
+    }
 
-        let diff = engine
-            .compare(Path::new("left"), Path::new("right"), left, right)
-            .unwrap();
-        assert_eq!(diff.len(), 2);
+    /// Decide equality of two same-sized files from content fingerprints
+    ///
+    /// `None` when either side has no fingerprint, the schemes differ, or
+    /// the fingerprints match but strict fingerprints are enabled.
+    fn fingerprints_decide(
+        &self,
+        path1: &Path,
+        path2: &Path,
+        vfs1: Option<&dyn Vfs>,
+        vfs2: Option<&dyn Vfs>,
+    ) -> Option<bool> {
+        let fp1 = content_fingerprint(path1, vfs1)?;
+        let fp2 = content_fingerprint(path2, vfs2)?;
+        if fingerprint_scheme(&fp1) != fingerprint_scheme(&fp2) {
+            None
+        } else if fp1 != fp2 {
+            debug!("Fingerprints differ for {}", path1.display());
+            Some(false)
+        } else if self.strict_fingerprints || self.force_content_check {
+            None
+        } else {
+            debug!("Fingerprints match for {}", path1.display());
+            Some(true)
+        }
     }
 
-    #[test]
-    fn test_parallel_hashing() {
-        let temp = TempDir::new().unwrap();
-        let cache = HashCache::new(temp.path().join("cache")).unwrap();
-        let engine = ComparisonEngine::new(cache);
+    /// Decide equality of two same-sized files without reading their content
+    ///
+    /// Used when a backend reports expensive reads. Matching fingerprints
+    /// mean the files are the same and differing fingerprints of the same
+    /// scheme mean they differ; otherwise timestamps within the mtime
+    /// tolerance are trusted and anything else is left undecided (`None`).
+    fn cheap_files_same(
+        &self,
+        path1: &Path,
+        path2: &Path,
+        vfs1: Option<&dyn Vfs>,
+        vfs2: Option<&dyn Vfs>,
+        entry1: &FileEntry,
+        entry2: &FileEntry,
+    ) -> Option<bool> {
+        debug!(
+            "Skipping content hashing of {} (backend reads are expensive)",
+            path1.display()
+        );
+
+        if let (Some(fp1), Some(fp2)) = (
+            content_fingerprint(path1, vfs1),
+            content_fingerprint(path2, vfs2),
+        ) {
+            if fp1 == fp2 {
+                return Some(true);
+            }
+            if fingerprint_scheme(&fp1) == fingerprint_scheme(&fp2) {
+                return Some(false);
+            }
+        }
+
+        if mtimes_match(entry1.modified, entry2.modified, self.mtime_tolerance) {
+            Some(true)
+        } else {
+            None
+        }
+    }
+}
+
+/// Parse a manifest for [`ComparisonEngine::compare_against_manifest`]
+///
+/// Each line is `<hash> <size> <path>`, separated by runs of whitespace, where
+/// the hash is BLAKE3 in hex, SHA-256 in hex with a `sha256:` prefix, or `-`
+/// when unknown and the path is the rest of the line, taken as is (spaces and
+/// a leading `*` included), relative to the tree root. Lines containing a tab
+/// are read in the
+/// `<path>\t<size>\t<mtime>\t<hash>` form written by [`format_manifest`];
+/// their modification time is not used. Blank lines and lines starting with
+/// `#` are skipped. Later duplicates of a path replace earlier ones.
+pub fn parse_manifest(text: &str) -> Result<Vec<ManifestEntry>, RCompareError> {
+    let mut entries: Vec<ManifestEntry> = Vec::new();
+    let mut index: HashMap<PathBuf, usize> = HashMap::new();
+
+    for (line_no, line) in text.lines().enumerate() {
+        let line = line.trim_start();
+        if line.trim_end().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let invalid = |reason: &str| {
+            RCompareError::Config(format!("Manifest line {}: {}", line_no + 1, reason))
+        };
+
+        let (hash, size, path) = if line.contains('\t') {
+            let fields: Vec<&str> = line.split('\t').collect();
+            let [path, size, mtime, hash] = fields[..] else {
+                return Err(invalid("expected <path>\\t<size>\\t<mtime>\\t<hash>"));
+            };
+            mtime.parse::<i64>().map_err(|_| invalid("invalid mtime"))?;
+            (hash, size, path)
+        } else {
+            let Some((hash, size, path)) = split_field(line)
+                .and_then(|(hash, rest)| split_field(rest).map(|(size, path)| (hash, size, path)))
+            else {
+                return Err(invalid("expected <hash> <size> <path>"));
+            };
+            (hash, size, path)
+        };
+        let (hash, sha256) = match hash {
+            "-" => (None, None),
+            hash => match hash.strip_prefix("sha256:") {
+                Some(digest) => {
+                    let mut bytes = [0u8; 32];
+                    hex::decode_to_slice(digest, &mut bytes)
+                        .map_err(|_| invalid("invalid hash"))?;
+                    (None, Some(bytes))
+                }
+                None => (
+                    Some(Blake3Hash::from_hex(hash).ok_or_else(|| invalid("invalid hash"))?),
+                    None,
+                ),
+            },
+        };
+        let size = size.parse().map_err(|_| invalid("invalid size"))?;
+        let path = PathBuf::from(path);
+
+        let entry = ManifestEntry {
+            path,
+            size,
+            hash,
+            sha256,
+        };
+        match index.get(&entry.path) {
+            Some(&idx) => entries[idx] = entry,
+            None => {
+                index.insert(entry.path.clone(), entries.len());
+                entries.push(entry);
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Split a manifest line at its first run of whitespace, keeping the rest of
+/// the line as is; `None` if nothing follows
+fn split_field(line: &str) -> Option<(&str, &str)> {
+    let (field, rest) = line.split_at(line.find(char::is_whitespace)?);
+    let rest = rest.trim_start();
+    (!rest.is_empty()).then_some((field, rest))
+}
+
+/// Write records from [`ComparisonEngine::generate_manifest`] as
+/// `<path>\t<size>\t<mtime>\t<hash>` lines, readable by [`parse_manifest`]
+///
+/// The modification time is in whole seconds since the Unix epoch, negative
+/// for earlier times, and path components are joined with `/`. The first line
+/// is a `#` header naming the columns. Paths containing a tab or a line break
+/// can't be represented and are an error.
+pub fn format_manifest(records: &[ManifestRecord]) -> Result<String, RCompareError> {
+    let mut out = String::from("# path\tsize\tmtime\thash\n");
+    for record in records {
+        let path = record
+            .path
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        if path.contains(['\t', '\n', '\r']) {
+            return Err(RCompareError::Path(format!(
+                "{} can't be written to a manifest: it contains a tab or line break",
+                record.path.display()
+            )));
+        }
+        // Round down, so a time before the epoch lands on the earlier second
+        let mtime = match record.modified.duration_since(SystemTime::UNIX_EPOCH) {
+            Ok(after) => after.as_secs() as i64,
+            Err(e) => {
+                let before = e.duration();
+                -(before.as_secs() as i64) - i64::from(before.subsec_nanos() > 0)
+            }
+        };
+        out.push_str(&format!(
+            "{}\t{}\t{}\t{}\n",
+            path, record.size, mtime, record.hash
+        ));
+    }
+    Ok(out)
+}
+
+/// Read and parse a manifest file, see [`parse_manifest`]
+pub fn load_manifest(path: &Path) -> Result<Vec<ManifestEntry>, RCompareError> {
+    parse_manifest(&std::fs::read_to_string(path)?)
+}
+
+/// Find compared paths that differ only in the letter case of their name
+///
+/// Only siblings are grouped, so a directory spelled differently is reported
+/// once rather than once per file below it. A group is returned whenever the
+/// two sides together hold more than one spelling; use
+/// [`CaseCollision::within_one_side`] to single out trees that can't be
+/// checked out on a case-insensitive filesystem at all.
+pub fn find_case_collisions(nodes: &[DiffNode]) -> Vec<CaseCollision> {
+    let mut siblings: BTreeMap<(&Path, String), Vec<&DiffNode>> = BTreeMap::new();
+    for node in nodes {
+        let Some(name) = node.relative_path.file_name() else {
+            continue;
+        };
+        let parent = node.relative_path.parent().unwrap_or(Path::new(""));
+        siblings
+            .entry((parent, name.to_string_lossy().to_lowercase()))
+            .or_default()
+            .push(node);
+    }
+
+    siblings
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .map(|group| {
+            let spellings = |side: fn(&DiffNode) -> bool| {
+                let mut paths: Vec<PathBuf> = group
+                    .iter()
+                    .filter(|node| side(node))
+                    .map(|node| node.relative_path.clone())
+                    .collect();
+                paths.sort();
+                paths
+            };
+            CaseCollision {
+                paths: spellings(|_| true),
+                left: spellings(|node| node.left.is_some()),
+                right: spellings(|node| node.right.is_some()),
+            }
+        })
+        .collect()
+}
+
+/// Add the entries a scan left out to a comparison's nodes as
+/// [`DiffStatus::Ignored`], keeping the nodes sorted by path
+///
+/// `left` and `right` come from [`FolderScanner::scan_with_ignored`] (or its
+/// VFS counterpart) of each side. A path ignored on one side only takes over
+/// the orphan node of the other side, so every path still appears once; a
+/// path ignored on both sides keeps the left side's rule.
+///
+/// A path that exists on both sides but is ignored on one of them (say a
+/// `.gitignore` that only one tree has) is therefore reported as `Ignored`,
+/// not as an orphan: the rule decides, and `ignored_by` names it. Its
+/// contents are never compared.
+pub fn add_ignored_nodes(
+    nodes: &mut Vec<DiffNode>,
+    left: Vec<IgnoredEntry>,
+    right: Vec<IgnoredEntry>,
+) {
+    if left.is_empty() && right.is_empty() {
+        return;
+    }
+
+    let mut index: HashMap<PathBuf, usize> = nodes
+        .iter()
+        .enumerate()
+        .map(|(i, node)| (node.relative_path.clone(), i))
+        .collect();
+    let tagged = left
+        .into_iter()
+        .map(|ignored| (true, ignored))
+        .chain(right.into_iter().map(|ignored| (false, ignored)));
+    for (is_left, IgnoredEntry { entry, rule }) in tagged {
+        let i = *index.entry(entry.path.clone()).or_insert_with(|| {
+            nodes.push(DiffNode {
+                relative_path: entry.path.clone(),
+                left: None,
+                right: None,
+                status: DiffStatus::Ignored,
+                similar_to: None,
+                reason: None,
+                ignored_by: None,
+            });
+            nodes.len() - 1
+        });
+        let node = &mut nodes[i];
+        if is_left {
+            node.left = Some(entry);
+        } else {
+            node.right = Some(entry);
+        }
+        node.status = DiffStatus::Ignored;
+        node.ignored_by.get_or_insert(rule);
+    }
+
+    nodes.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+}
+
+/// Result of [`ComparisonEngine::compare_listed_files`]
+struct ListedComparison {
+    status: DiffStatus,
+    /// Fresh metadata of both sides when the pair was compared again
+    refreshed: Option<(FileEntry, FileEntry)>,
+    changed_during_scan: bool,
+}
+
+impl ListedComparison {
+    fn settled(status: DiffStatus) -> Self {
+        Self {
+            status,
+            refreshed: None,
+            changed_during_scan: false,
+        }
+    }
+
+    fn volatile() -> Self {
+        Self {
+            status: DiffStatus::Unchecked,
+            refreshed: None,
+            changed_during_scan: true,
+        }
+    }
+}
+
+/// Current metadata of a listed local entry, stat'd the way it was listed
+fn current_metadata(root: &Path, entry: &FileEntry) -> Option<std::fs::Metadata> {
+    let path = root.join(&entry.path);
+    if entry.file_type == FileType::Symlink {
+        std::fs::symlink_metadata(path).ok()
+    } else {
+        std::fs::metadata(path).ok()
+    }
+}
+
+/// Whether a local file's size or mtime no longer matches its listing
+fn changed_since_listing(root: &Path, entry: &FileEntry) -> bool {
+    current_metadata(root, entry).is_none_or(|metadata| {
+        metadata.len() != entry.size || metadata.modified().ok() != Some(entry.modified)
+    })
+}
+
+/// The entry with its size and mtime read afresh, or `None` once it is gone
+fn restat(root: &Path, entry: &FileEntry) -> Option<FileEntry> {
+    let metadata = current_metadata(root, entry)?;
+    Some(FileEntry {
+        size: metadata.len(),
+        modified: metadata.modified().ok()?,
+        ..entry.clone()
+    })
+}
+
+/// Whether two readers yield the same bytes, compared chunk by chunk
+#[cfg(feature = "archives")]
+fn streams_equal(
+    mut left: impl Read,
+    mut right: impl Read,
+    chunk_size: usize,
+) -> std::io::Result<bool> {
+    let mut left_buffer = vec![0u8; chunk_size];
+    let mut right_buffer = vec![0u8; chunk_size];
+    loop {
+        let left_read = read_chunk(&mut left, &mut left_buffer)?;
+        let right_read = read_chunk(&mut right, &mut right_buffer)?;
+        if left_buffer[..left_read] != right_buffer[..right_read] {
+            return Ok(false);
+        }
+        if left_read < chunk_size {
+            return Ok(true);
+        }
+    }
+}
+
+/// Fill `buffer` from `reader`, stopping short only at the end of input;
+/// decoders often return fewer bytes per read than asked for
+#[cfg(feature = "archives")]
+fn read_chunk(reader: &mut impl Read, buffer: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(filled)
+}
+
+/// Whether two modification times are at most `tolerance` apart
+pub fn mtimes_match(a: SystemTime, b: SystemTime, tolerance: Duration) -> bool {
+    let delta = match a.duration_since(b) {
+        Ok(delta) => delta,
+        Err(e) => e.duration(),
+    };
+    delta <= tolerance
+}
+
+/// Whether either side lives on a backend where full reads are costly
+fn reads_are_expensive(vfs1: Option<&dyn Vfs>, vfs2: Option<&dyn Vfs>) -> bool {
+    [vfs1, vfs2]
+        .into_iter()
+        .flatten()
+        .any(|vfs| vfs.capabilities().expensive_reads)
+}
+
+/// Targets of two files that are both symlinks
+fn symlink_targets(
+    left: &Path,
+    right: &Path,
+    left_vfs: Option<&dyn Vfs>,
+    right_vfs: Option<&dyn Vfs>,
+) -> Option<(PathBuf, PathBuf)> {
+    let target = |path: &Path, vfs: Option<&dyn Vfs>| match vfs {
+        Some(vfs) => vfs.read_link(path).ok(),
+        None => std::fs::read_link(path).ok(),
+    };
+    Some((target(left, left_vfs)?, target(right, right_vfs)?))
+}
+
+/// Pair both sides' entries by relative path, in path order
+fn pair_by_path(left: Vec<FileEntry>, right: Vec<FileEntry>) -> Vec<EntryPair> {
+    let mut right_map: HashMap<PathBuf, FileEntry> =
+        right.into_iter().map(|e| (e.path.clone(), e)).collect();
+    let mut pairs: Vec<EntryPair> = left
+        .into_iter()
+        .map(|e| {
+            let right = right_map.remove(&e.path);
+            (e.path.clone(), Some(e), right)
+        })
+        .collect();
+    pairs.extend(
+        right_map
+            .into_values()
+            .map(|e| (e.path.clone(), None, Some(e))),
+    );
+    pairs.sort_by(|a, b| a.0.cmp(&b.0));
+    pairs
+}
+
+/// Directories (including the root, keyed by the empty path) whose
+/// signatures are equal on both sides
+fn matching_directories(left: &[FileEntry], right: &[FileEntry]) -> HashSet<PathBuf> {
+    let left_signatures = FolderScanner::directory_signatures(left);
+    let right_signatures = FolderScanner::directory_signatures(right);
+    let matching: HashSet<PathBuf> = left_signatures
+        .into_iter()
+        .filter(|(dir, signature)| right_signatures.get(dir) == Some(signature))
+        .map(|(dir, _)| dir)
+        .collect();
+    debug!("{} directories match by signature", matching.len());
+    matching
+}
+
+/// Whether two entries at one path are different kinds of object: a
+/// directory and a file, or a FIFO, socket or device and anything else
+fn is_type_conflict(left: &FileEntry, right: &FileEntry) -> bool {
+    left.is_dir != right.is_dir
+        || ((left.is_special() || right.is_special()) && left.file_type != right.file_type)
+}
+
+/// Whether `path` is, or lies under, a directory with matching signatures
+fn in_identical_dir(path: &Path, identical_dirs: &HashSet<PathBuf>) -> bool {
+    !identical_dirs.is_empty() && path.ancestors().any(|dir| identical_dirs.contains(dir))
+}
+
+fn content_fingerprint(path: &Path, vfs: Option<&dyn Vfs>) -> Option<String> {
+    match vfs?.content_fingerprint(path) {
+        Ok(fingerprint) => fingerprint,
+        Err(e) => {
+            debug!("No content fingerprint for {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+fn backend_hash(
+    path: &Path,
+    vfs: &dyn Vfs,
+    algorithm: HashAlgorithm,
+) -> Result<Option<Vec<u8>>, RCompareError> {
+    vfs.hash(path, algorithm)
+        .map_err(|e| RCompareError::vfs(format!("Failed to hash {}", path.display()), e))
+}
+
+fn fingerprint_scheme(fingerprint: &str) -> Option<&str> {
+    fingerprint.split_once(':').map(|(scheme, _)| scheme)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::result_views::{summarize, StatusCounts};
+    use rcompare_common::{AppConfig, VfsCapabilities, VfsError, VfsOperation};
+    use std::fs::{self, File};
+    use std::io::Write;
+    use std::path::Path;
+    use std::time::SystemTime;
+    use tempfile::TempDir;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    #[test]
+    fn test_comparison_basic() {
+        let temp = TempDir::new().unwrap();
+        let cache = HashCache::new(temp.path().to_path_buf()).unwrap();
+        let engine = ComparisonEngine::new(cache);
+
+        let left = vec![FileEntry {
+            path: PathBuf::from("file1.txt"),
+            size: 100,
+            modified: SystemTime::now(),
+            is_dir: false,
+            file_type: FileType::Regular,
+        }];
+
+        let right = vec![FileEntry {
+            path: PathBuf::from("file2.txt"),
+            size: 200,
+            modified: SystemTime::now(),
+            is_dir: false,
+            file_type: FileType::Regular,
+        }];
+
+        let diff = engine
+            .compare(Path::new("left"), Path::new("right"), left, right)
+            .unwrap();
+        assert_eq!(diff.len(), 2);
+    }
+
+    #[test]
+    fn test_file_vs_directory_type_conflict() {
+        let temp = TempDir::new().unwrap();
+        let cache = HashCache::new(temp.path().join("cache")).unwrap();
+        let engine = ComparisonEngine::new(cache);
+
+        let now = SystemTime::now();
+        let left = vec![FileEntry {
+            path: PathBuf::from("config"),
+            size: 10,
+            modified: now,
+            is_dir: false,
+            file_type: FileType::Regular,
+        }];
+        let right = vec![
+            FileEntry {
+                path: PathBuf::from("config"),
+                size: 0,
+                modified: now,
+                is_dir: true,
+                file_type: FileType::Directory,
+            },
+            FileEntry {
+                path: PathBuf::from("config/settings.toml"),
+                size: 42,
+                modified: now,
+                is_dir: false,
+                file_type: FileType::Regular,
+            },
+        ];
+
+        let diff = engine
+            .compare(Path::new("left"), Path::new("right"), left, right)
+            .unwrap();
+
+        assert_eq!(diff.len(), 2);
+        assert_eq!(diff[0].relative_path, PathBuf::from("config"));
+        assert_eq!(diff[0].status, DiffStatus::TypeConflict);
+        assert!(!diff[0].left.as_ref().unwrap().is_dir);
+        assert!(diff[0].right.as_ref().unwrap().is_dir);
+        assert_eq!(diff[1].relative_path, PathBuf::from("config/settings.toml"));
+        assert_eq!(diff[1].status, DiffStatus::OrphanRight);
+    }
+
+    /// Layer recording each span's name and fields as `name=value` text
+    #[derive(Clone, Default)]
+    struct SpanCapture(std::sync::Arc<std::sync::Mutex<Vec<(String, String)>>>);
+
+    struct FieldText<'a>(&'a mut String);
+
+    impl tracing::field::Visit for FieldText<'_> {
+        fn record_debug(&mut self, field: &tracing::field::Field, value: &dyn std::fmt::Debug) {
+            self.0.push_str(&format!("{}={:?} ", field.name(), value));
+        }
+    }
+
+    impl<S> tracing_subscriber::Layer<S> for SpanCapture
+    where
+        S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+    {
+        fn on_new_span(
+            &self,
+            attrs: &tracing::span::Attributes<'_>,
+            id: &tracing::span::Id,
+            ctx: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            let mut fields = String::new();
+            attrs.record(&mut FieldText(&mut fields));
+            let mut spans = self.0.lock().unwrap();
+            ctx.span(id).unwrap().extensions_mut().insert(spans.len());
+            spans.push((attrs.metadata().name().to_string(), fields));
+        }
+
+        fn on_record(
+            &self,
+            id: &tracing::span::Id,
+            values: &tracing::span::Record<'_>,
+            ctx: tracing_subscriber::layer::Context<'_, S>,
+        ) {
+            let idx = *ctx.span(id).unwrap().extensions().get::<usize>().unwrap();
+            values.record(&mut FieldText(&mut self.0.lock().unwrap()[idx].1));
+        }
+    }
+
+    #[test]
+    fn test_comparison_emits_tracing_spans() {
+        let temp = TempDir::new().unwrap();
+        let left_root = temp.path().join("left");
+        let right_root = temp.path().join("right");
+        fs::create_dir_all(&left_root).unwrap();
+        fs::create_dir_all(&right_root).unwrap();
+        fs::write(left_root.join("same.txt"), "identical").unwrap();
+        fs::write(right_root.join("same.txt"), "identical").unwrap();
+
+        let capture = SpanCapture::default();
+        let subscriber = tracing_subscriber::registry().with(capture.clone());
+        tracing::subscriber::with_default(subscriber, || {
+            let scanner = FolderScanner::new(AppConfig::default());
+            let left = scanner.scan(&left_root).unwrap();
+            let right = scanner.scan(&right_root).unwrap();
+            let cache = HashCache::new(temp.path().join("cache")).unwrap();
+            let engine = ComparisonEngine::new(cache).with_hash_verification(true);
+            engine
+                .compare(&left_root, &right_root, left, right)
+                .unwrap();
+            engine.persist_cache().unwrap();
+        });
+
+        let spans = capture.0.lock().unwrap();
+        let fields = |name: &str| -> Vec<&str> {
+            spans
+                .iter()
+                .filter(|(n, _)| n == name)
+                .map(|(_, f)| f.as_str())
+                .collect()
+        };
+        assert_eq!(fields("scan").len(), 2);
+        assert!(fields("scan").iter().all(|f| f.contains("entries=1")));
+        assert_eq!(fields("load_cache").len(), 1);
+        assert_eq!(
+            fields("compare"),
+            ["left_entries=1 right_entries=1 nodes=1 "]
+        );
+        assert_eq!(fields("classify"), ["paths=1 "]);
+        assert_eq!(fields("partial_hash_file").len(), 2);
+
+        let hashes = fields("hash_file");
+        assert_eq!(hashes.len(), 2);
+        assert!(hashes[0].contains(&format!("path={}", left_root.join("same.txt").display())));
+        assert!(hashes.iter().all(|f| f.contains("bytes=9")));
+        assert!(fields("persist_cache")[0].contains("entries=2"));
+    }
+
+    #[test]
+    fn test_hash_stats_count_cache_hits_across_runs() {
+        let temp = TempDir::new().unwrap();
+        let left_root = temp.path().join("left");
+        let right_root = temp.path().join("right");
+        fs::create_dir_all(&left_root).unwrap();
+        fs::create_dir_all(&right_root).unwrap();
+        fs::write(left_root.join("same.txt"), "identical").unwrap();
+        fs::write(right_root.join("same.txt"), "identical").unwrap();
+        // Partial hashes differ, so these are never fully hashed
+        fs::write(left_root.join("diff.txt"), "aaaa").unwrap();
+        fs::write(right_root.join("diff.txt"), "bbbb").unwrap();
+
+        let scanner = FolderScanner::new(AppConfig::default());
+        let run = |engine: &ComparisonEngine| {
+            engine
+                .compare(
+                    &left_root,
+                    &right_root,
+                    scanner.scan(&left_root).unwrap(),
+                    scanner.scan(&right_root).unwrap(),
+                )
+                .unwrap();
+        };
+        let counts = |stats: HashStats| (stats.cache_hits, stats.cache_misses, stats.bytes_hashed);
+
+        let cache = HashCache::new(temp.path().join("cache")).unwrap();
+        let engine = ComparisonEngine::new(cache).with_hash_verification(true);
+        assert_eq!(engine.hash_stats(), HashStats::default());
+
+        // Partial hashes of all four files (26 bytes), then full hashes of
+        // the matching pair (18 bytes)
+        run(&engine);
+        assert_eq!(counts(engine.hash_stats()), (0, 2, 44));
+
+        // The totals accumulate; the second run reads only partial hashes
+        run(&engine);
+        assert_eq!(counts(engine.hash_stats()), (2, 2, 70));
+        engine.persist_cache().unwrap();
+
+        // A new engine over the persisted cache starts from zero
+        let cache = HashCache::new(temp.path().join("cache")).unwrap();
+        let engine = ComparisonEngine::new(cache).with_hash_verification(true);
+        run(&engine);
+        assert_eq!(counts(engine.hash_stats()), (2, 0, 26));
+
+        // Forced content checks bypass the cache
+        let cache = HashCache::new(temp.path().join("cache")).unwrap();
+        let engine = ComparisonEngine::new(cache).with_force_content_check(true);
+        run(&engine);
+        assert_eq!(counts(engine.hash_stats()), (0, 2, 44));
+    }
+
+    #[test]
+    fn test_structure_only_ignores_content_and_size() {
+        let temp = TempDir::new().unwrap();
+        let left_root = temp.path().join("left");
+        let right_root = temp.path().join("right");
+        fs::create_dir_all(&left_root).unwrap();
+        fs::create_dir_all(&right_root).unwrap();
+        fs::write(left_root.join("data.txt"), "original contents").unwrap();
+        fs::write(right_root.join("data.txt"), "changed").unwrap();
+        fs::write(left_root.join("only_left.txt"), "x").unwrap();
+        fs::create_dir(left_root.join("mixed")).unwrap();
+        fs::write(right_root.join("mixed"), "file").unwrap();
+
+        let scanner = FolderScanner::new(AppConfig::default());
+        let left = scanner.scan(&left_root).unwrap();
+        let right = scanner.scan(&right_root).unwrap();
+
+        let cache = HashCache::new(temp.path().join("cache")).unwrap();
+        let engine = ComparisonEngine::new(cache)
+            .with_hash_verification(true)
+            .with_mode(ComparisonMode::StructureOnly);
+        let diff = engine
+            .compare(&left_root, &right_root, left, right)
+            .unwrap();
+
+        let status = |name: &str| {
+            diff.iter()
+                .find(|n| n.relative_path == Path::new(name))
+                .unwrap()
+                .status
+        };
+        assert_eq!(status("data.txt"), DiffStatus::Same);
+        assert_eq!(status("only_left.txt"), DiffStatus::OrphanLeft);
+        assert_eq!(status("mixed"), DiffStatus::TypeConflict);
+        assert!(diff.iter().all(|n| n.status != DiffStatus::Different));
+    }
+
+    #[test]
+    fn test_presence_only_reads_nothing_but_listings() {
+        let file = |path: &str, size: u64| FileEntry {
+            path: PathBuf::from(path),
+            size,
+            modified: SystemTime::UNIX_EPOCH,
+            is_dir: false,
+            file_type: FileType::Regular,
+        };
+        let dir = |path: &str| FileEntry {
+            path: PathBuf::from(path),
+            size: 0,
+            modified: SystemTime::UNIX_EPOCH,
+            is_dir: true,
+            file_type: FileType::Directory,
+        };
+        let left_vfs = FakeVfs::new(VfsCapabilities::read_only())
+            .with_dir(
+                "/l",
+                vec![file("/l/data.txt", 17), dir("/l/docs"), file("/l/mixed", 4)],
+            )
+            .with_dir(
+                "/l/docs",
+                vec![file("/l/docs/old.txt", 3), file("/l/docs/empty", 0)],
+            );
+        let right_vfs = FakeVfs::new(VfsCapabilities::read_only())
+            .with_dir(
+                "/r",
+                vec![file("/r/data.txt", 7), dir("/r/docs"), dir("/r/mixed")],
+            )
+            .with_dir(
+                "/r/docs",
+                vec![file("/r/docs/new.txt", 3), file("/r/docs/empty", 9)],
+            )
+            .with_dir("/r/mixed", Vec::new());
+
+        let scanner = FolderScanner::new(AppConfig::default());
+        let (left_root, right_root) = (Path::new("/l"), Path::new("/r"));
+        let left = scanner.scan_vfs(&left_vfs, left_root).unwrap();
+        let right = scanner.scan_vfs(&right_vfs, right_root).unwrap();
+
+        let temp = TempDir::new().unwrap();
+        let engine = ComparisonEngine::new(HashCache::new(temp.path().join("cache")).unwrap())
+            .with_mode(ComparisonMode::PresenceOnly)
+            .with_similarity_detection(50.0)
+            .with_emptied_file_detection(true);
+        let diff = engine
+            .compare_with_vfs(
+                left_root,
+                right_root,
+                left,
+                right,
+                Some(&left_vfs),
+                Some(&right_vfs),
+            )
+            .unwrap();
+
+        let statuses: Vec<(&str, DiffStatus)> = diff
+            .iter()
+            .map(|n| (n.relative_path.to_str().unwrap(), n.status))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ("data.txt", DiffStatus::Same),
+                ("docs", DiffStatus::Same),
+                ("docs/empty", DiffStatus::Same),
+                ("docs/new.txt", DiffStatus::OrphanRight),
+                ("docs/old.txt", DiffStatus::OrphanLeft),
+                ("mixed", DiffStatus::Same),
+            ]
+        );
+        assert!(diff
+            .iter()
+            .all(|n| n.reason.is_none() && n.similar_to.is_none()));
+        for vfs in [&left_vfs, &right_vfs] {
+            assert_eq!(vfs.metadata_calls(), 0);
+            assert_eq!(vfs.reads(), 0);
+        }
+    }
+
+    /// Synthetic trees of `files` files spread over directories of 100:
+    /// every 50th file exists only on the left, every 13th other file
+    /// changed size, and every 70th index adds a right-only file
+    fn synthetic_trees(files: usize) -> (Vec<FileEntry>, Vec<FileEntry>) {
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let entry = |path: String, size: u64, is_dir: bool| FileEntry {
+            path: PathBuf::from(path),
+            size,
+            modified,
+            is_dir,
+            file_type: FileType::from_is_dir(is_dir),
+        };
+        let (mut left, mut right) = (Vec::new(), Vec::new());
+        for i in 0..files {
+            if i % 100 == 0 {
+                let dir = format!("d{:05}", i / 100);
+                left.push(entry(dir.clone(), 0, true));
+                right.push(entry(dir, 0, true));
+            }
+            let path = format!("d{:05}/f{i}.dat", i / 100);
+            let size = (i % 7) as u64 + 1;
+            left.push(entry(path.clone(), size, false));
+            if i % 50 != 0 {
+                let size = if i % 13 == 0 { size + 1 } else { size };
+                right.push(entry(path, size, false));
+            }
+            if i % 70 == 0 {
+                right.push(entry(format!("d{:05}/new{i}.dat", i / 100), 1, false));
+            }
+        }
+        (left, right)
+    }
+
+    #[test]
+    fn test_spilled_result_matches_in_memory() {
+        let temp = TempDir::new().unwrap();
+        let engine = ComparisonEngine::new(HashCache::new(temp.path().join("cache")).unwrap());
+        let (left, right) = synthetic_trees(2_000);
+
+        let in_memory = engine
+            .compare(
+                Path::new("left"),
+                Path::new("right"),
+                left.clone(),
+                right.clone(),
+            )
+            .unwrap();
+        let spilled = engine
+            .compare_spilled(
+                Path::new("left"),
+                Path::new("right"),
+                left,
+                right,
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+
+        assert_eq!(spilled.len(), in_memory.len());
+        let read_back = spilled.to_vec().unwrap();
+        assert_eq!(format!("{read_back:?}"), format!("{in_memory:?}"));
+    }
+
+    #[test]
+    fn test_spilled_large_tree_counts() {
+        let temp = TempDir::new().unwrap();
+        let engine = ComparisonEngine::new(HashCache::new(temp.path().join("cache")).unwrap());
+        let files = 200_000;
+        let (left, right) = synthetic_trees(files);
+
+        let spilled = engine
+            .compare_spilled(
+                Path::new("left"),
+                Path::new("right"),
+                left,
+                right,
+                None,
+                None,
+                None,
+            )
+            .unwrap();
+
+        let counts =
+            StatusCounts::from_statuses(spilled.iter().unwrap().map(|node| node.unwrap().status));
+        let multiples = |n: usize| (0..files).filter(|i| i % n == 0).count();
+        let orphan_left = multiples(50);
+        let different = (0..files).filter(|i| i % 13 == 0 && i % 50 != 0).count();
+        let dirs = multiples(100);
+        assert_eq!(counts.orphan_left, orphan_left);
+        assert_eq!(counts.orphan_right, multiples(70));
+        assert_eq!(counts.different, different);
+        assert_eq!(counts.same, files - orphan_left - different + dirs);
+        assert_eq!(counts.unchecked + counts.type_conflict, 0);
+        assert_eq!(spilled.len(), files + dirs + multiples(70));
+    }
+
+    #[test]
+    fn test_compare_each_stops_on_sink_error() {
+        let temp = TempDir::new().unwrap();
+        let engine = ComparisonEngine::new(HashCache::new(temp.path().join("cache")).unwrap());
+        let (left, right) = synthetic_trees(500);
+
+        let mut seen = 0;
+        let result = engine.compare_each(
+            Path::new("left"),
+            Path::new("right"),
+            left,
+            right,
+            None,
+            None,
+            None,
+            |_| {
+                seen += 1;
+                if seen == 10 {
+                    Err(RCompareError::Comparison("enough".to_string()))
+                } else {
+                    Ok(())
+                }
+            },
+        );
+
+        assert!(matches!(result, Err(RCompareError::Comparison(msg)) if msg == "enough"));
+        assert_eq!(seen, 10);
+    }
+
+    #[test]
+    fn test_find_case_collisions() {
+        let entry = |path: &str, is_dir: bool| FileEntry {
+            path: PathBuf::from(path),
+            size: if is_dir { 0 } else { 6 },
+            modified: SystemTime::UNIX_EPOCH,
+            is_dir,
+            file_type: FileType::from_is_dir(is_dir),
+        };
+        let left = vec![
+            entry("README", false),
+            entry("readme", false),
+            entry("Src", true),
+            entry("Src/main.rs", false),
+        ];
+        let right = vec![
+            entry("README", false),
+            entry("src", true),
+            entry("src/main.rs", false),
+        ];
+
+        let temp = TempDir::new().unwrap();
+        let engine = ComparisonEngine::new(HashCache::new(temp.path().to_path_buf()).unwrap());
+        let diff = engine
+            .compare(Path::new("left"), Path::new("right"), left, right)
+            .unwrap();
+        let collisions = find_case_collisions(&diff);
+
+        assert_eq!(collisions.len(), 2);
+        let readme = &collisions[0];
+        assert_eq!(
+            readme.paths,
+            [PathBuf::from("README"), PathBuf::from("readme")]
+        );
+        assert_eq!(readme.left, readme.paths);
+        assert_eq!(readme.right, [PathBuf::from("README")]);
+        assert!(readme.within_one_side());
+
+        // The directory is reported once, not again for its contents
+        let src = &collisions[1];
+        assert_eq!(src.left, [PathBuf::from("Src")]);
+        assert_eq!(src.right, [PathBuf::from("src")]);
+        assert!(!src.within_one_side());
+    }
+
+    #[test]
+    fn test_find_duplicates() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().join("tree");
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::write(root.join("a.txt"), "same content").unwrap();
+        fs::write(root.join("sub/b.txt"), "same content").unwrap();
+        // Same size, different content: hashed but not grouped
+        fs::write(root.join("c.txt"), "diff content").unwrap();
+
+        let entries = FolderScanner::new(AppConfig::default())
+            .scan(&root)
+            .unwrap();
+        let cache = HashCache::new(temp.path().join("cache")).unwrap();
+        let engine = ComparisonEngine::new(cache);
+        let groups = engine.find_duplicates(&root, &entries, None).unwrap();
+
+        assert_eq!(groups.len(), 1);
+        assert_eq!(
+            groups[0].paths,
+            [PathBuf::from("a.txt"), PathBuf::from("sub/b.txt")]
+        );
+        assert_eq!(groups[0].size, 12);
+        assert_eq!(groups[0].wasted_bytes(), 12);
+        assert_eq!(
+            groups[0].hash,
+            engine.hash_file(&root.join("a.txt")).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_compare_against_manifest() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().join("deploy");
+        fs::create_dir_all(root.join("bin")).unwrap();
+        fs::write(root.join("bin/app"), "release build").unwrap();
+        fs::write(root.join("config.toml"), "tampered").unwrap();
+        fs::write(root.join("notes.txt"), "no hash").unwrap();
+        fs::write(root.join("extra.log"), "not released").unwrap();
+
+        let hash = |content: &str| blake3::hash(content.as_bytes()).to_hex().to_string();
+        let text = format!(
+            "# release 1.2.0\n{} 13 bin/app\n{} 8 config.toml\n- 7 notes.txt\n{} 7 missing.dat\n",
+            hash("release build"),
+            hash("original"),
+            hash("missing"),
+        );
+        let manifest = parse_manifest(&text).unwrap();
+        assert_eq!(manifest.len(), 4);
+        assert_eq!(manifest[2].hash, None);
+
+        let entries = FolderScanner::new(AppConfig::default())
+            .scan(&root)
+            .unwrap();
+        let cache = HashCache::new(temp.path().join("cache")).unwrap();
+        let engine = ComparisonEngine::new(cache);
+        let diff = engine
+            .compare_against_manifest(&manifest, &root, entries, None)
+            .unwrap();
+
+        let statuses: Vec<(&Path, DiffStatus)> = diff
+            .iter()
+            .map(|n| (n.relative_path.as_path(), n.status))
+            .collect();
+        assert_eq!(
+            statuses,
+            [
+                (Path::new("bin/app"), DiffStatus::Same),
+                (Path::new("config.toml"), DiffStatus::Different),
+                (Path::new("extra.log"), DiffStatus::OrphanRight),
+                (Path::new("missing.dat"), DiffStatus::OrphanLeft),
+                (Path::new("notes.txt"), DiffStatus::Unchecked),
+            ]
+        );
+
+        assert!(parse_manifest("abc 1 file").is_err());
+        assert!(parse_manifest("- big file").is_err());
+
+        // Runs of whitespace separate the fields, and the rest of the line,
+        // binary marker and inner spaces included, is the name
+        let manifest = parse_manifest(&format!(
+            "{}   13   *release notes.txt \n  -  7 a  b",
+            hash("release build")
+        ))
+        .unwrap();
+        let entries: Vec<_> = manifest
+            .iter()
+            .map(|e| (e.path.as_path(), e.size))
+            .collect();
+        assert_eq!(
+            entries,
+            [
+                (Path::new("*release notes.txt "), 13),
+                (Path::new("a  b"), 7)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_generate_manifest_is_sorted_and_round_trips() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().join("release");
+        fs::create_dir_all(root.join("bin")).unwrap();
+        fs::write(root.join("z.txt"), "last").unwrap();
+        fs::write(root.join("bin/app"), "release build").unwrap();
+        fs::write(root.join("a.txt"), "first").unwrap();
+        for name in ["z.txt", "bin/app", "a.txt"] {
+            filetime::set_file_mtime(
+                root.join(name),
+                filetime::FileTime::from_unix_time(1_700_000_000, 0),
+            )
+            .unwrap();
+        }
+
+        let cache = HashCache::new(temp.path().join("cache")).unwrap();
+        let engine = ComparisonEngine::new(cache);
+        let generate = |algorithm| {
+            let entries = FolderScanner::new(AppConfig::default())
+                .scan(&root)
+                .unwrap();
+            let records = engine
+                .generate_manifest(&root, entries, None, algorithm)
+                .unwrap();
+            format_manifest(&records).unwrap()
+        };
+
+        let text = generate(ManifestHashAlgorithm::Blake3);
+        let hash = |content: &str| blake3::hash(content.as_bytes()).to_hex().to_string();
+        assert_eq!(
+            text,
+            format!(
+                "# path\tsize\tmtime\thash\n\
+                 a.txt\t5\t1700000000\t{}\n\
+                 bin/app\t13\t1700000000\t{}\n\
+                 z.txt\t4\t1700000000\t{}\n",
+                hash("first"),
+                hash("release build"),
+                hash("last"),
+            )
+        );
+        assert_eq!(generate(ManifestHashAlgorithm::Blake3), text);
+
+        // The manifest verifies the tree it was generated from
+        let manifest = parse_manifest(&text).unwrap();
+        let entries = FolderScanner::new(AppConfig::default())
+            .scan(&root)
+            .unwrap();
+        let diff = engine
+            .compare_against_manifest(&manifest, &root, entries, None)
+            .unwrap();
+        assert!(
+            diff.iter().all(|n| n.status == DiffStatus::Same),
+            "{diff:?}"
+        );
+
+        let sha = generate(ManifestHashAlgorithm::Sha256);
+        assert!(sha.contains(
+            "a.txt\t5\t1700000000\tsha256:\
+             a7937b64b8caa58f03721bb6bacf5c78cb235febe0e70b1b84cd99541461a08e\n"
+        ));
+        let manifest = parse_manifest(&sha).unwrap();
+        assert!(manifest
+            .iter()
+            .all(|e| e.hash.is_none() && e.sha256.is_some()));
+        assert_eq!(manifest[1].size, 13);
+
+        // SHA-256 entries are verified too, not just sized
+        fs::write(root.join("z.txt"), "LAST").unwrap();
+        let entries = FolderScanner::new(AppConfig::default())
+            .scan(&root)
+            .unwrap();
+        let diff = engine
+            .compare_against_manifest(&manifest, &root, entries, None)
+            .unwrap();
+        let statuses: Vec<_> = diff.iter().map(|n| n.status).collect();
+        assert_eq!(
+            statuses,
+            [DiffStatus::Same, DiffStatus::Same, DiffStatus::Different]
+        );
+    }
+
+    #[test]
+    fn test_format_manifest_keeps_names_and_early_mtimes() {
+        let record = |path: &str, modified| ManifestRecord {
+            path: PathBuf::from(path),
+            size: 1,
+            modified,
+            hash: "-".to_string(),
+        };
+        let text = format_manifest(&[
+            record("dir/a.txt", SystemTime::UNIX_EPOCH + Duration::from_secs(5)),
+            record(
+                "back\\slash.txt",
+                SystemTime::UNIX_EPOCH - Duration::from_millis(1_500),
+            ),
+        ])
+        .unwrap();
+
+        // A backslash is part of a Unix file name, and a time before the
+        // epoch is kept rather than clamped
+        #[cfg(unix)]
+        assert!(text.contains("back\\slash.txt\t1\t-2\t-\n"), "{text}");
+        assert!(text.contains("dir/a.txt\t1\t5\t-\n"), "{text}");
+        assert_eq!(parse_manifest(&text).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_parallel_hashing() {
+        let temp = TempDir::new().unwrap();
+        let cache = HashCache::new(temp.path().join("cache")).unwrap();
+        let engine = ComparisonEngine::new(cache);
+
+        // Create test files
+        let file1 = temp.path().join("file1.txt");
+        let file2 = temp.path().join("file2.txt");
+        let file3 = temp.path().join("file3.txt");
+
+        File::create(&file1)
+            .unwrap()
+            .write_all(b"content1")
+            .unwrap();
+        File::create(&file2)
+            .unwrap()
+            .write_all(b"content2")
+            .unwrap();
+        File::create(&file3)
+            .unwrap()
+            .write_all(b"content1")
+            .unwrap(); // Same as file1
+
+        let paths = [file1.as_path(), file2.as_path(), file3.as_path()];
+
+        // Hash in parallel
+        let results = engine.hash_files_parallel(paths.iter().copied());
+
+        assert_eq!(results.len(), 3);
+
+        // All should succeed
+        assert!(results.iter().all(|(_, r)| r.is_ok()));
+
+        // file1 and file3 should have same hash (same content)
+        let hash1 = results[0].1.as_ref().unwrap();
+        let hash2 = results[1].1.as_ref().unwrap();
+        let hash3 = results[2].1.as_ref().unwrap();
+
+        assert_eq!(hash1, hash3, "Files with same content should have same hash");
+        assert_ne!(hash1, hash2, "Files with different content should have different hash");
+    }
+
+    #[test]
+    fn test_with_threads_hashes_on_own_pool() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("file.txt");
+        fs::write(&path, "content").unwrap();
+
+        let engine = ComparisonEngine::new(HashCache::in_memory()).with_threads(2);
+        assert_eq!(engine.threads(), 2);
+        assert_eq!(engine.install(rayon::current_num_threads), 2);
+        let results = engine.hash_files_parallel([path.as_path()]);
+        assert!(results[0].1.is_ok());
+    }
+
+    #[test]
+    fn test_parallel_hashing_with_errors() {
+        let temp = TempDir::new().unwrap();
+        let cache = HashCache::new(temp.path().join("cache")).unwrap();
+        let engine = ComparisonEngine::new(cache);
+
+        // Create one valid file and reference non-existent files
+        let file1 = temp.path().join("file1.txt");
+        let file2 = temp.path().join("nonexistent.txt");
+
+        std::fs::File::create(&file1)
+            .unwrap()
+            .write_all(b"content")
+            .unwrap();
+
+        let paths = [file1.as_path(), file2.as_path()];
+
+        // Hash in parallel
+        let results = engine.hash_files_parallel(paths.iter().copied());
+
+        assert_eq!(results.len(), 2);
+        assert!(results[0].1.is_ok(), "First file should hash successfully");
+        assert!(results[1].1.is_err(), "Second file should error (not found)");
+    }
+
+    #[test]
+    fn test_streaming_comparison_identical_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let cache = HashCache::new(temp.path().join("cache")).unwrap();
+        let engine = ComparisonEngine::new(cache);
+
+        let file1 = temp.path().join("file1.dat");
+        let file2 = temp.path().join("file2.dat");
+
+        // Create identical large content (2MB)
+        let content = vec![42u8; 2 * 1024 * 1024];
+        std::fs::write(&file1, &content).unwrap();
+        std::fs::write(&file2, &content).unwrap();
+
+        let result = engine.compare_files_streaming(&file1, &file2).unwrap();
+        assert!(result, "Identical files should return true");
+    }
+
+    #[test]
+    fn test_streaming_comparison_different_files() {
+        let temp = tempfile::tempdir().unwrap();
+        let cache = HashCache::new(temp.path().join("cache")).unwrap();
+        let engine = ComparisonEngine::new(cache);
+
+        let file1 = temp.path().join("file1.dat");
+        let file2 = temp.path().join("file2.dat");
+
+        // Create different large content (2MB each)
+        let content1 = vec![42u8; 2 * 1024 * 1024];
+        let content2 = vec![43u8; 2 * 1024 * 1024];
+        std::fs::write(&file1, &content1).unwrap();
+        std::fs::write(&file2, &content2).unwrap();
+
+        let result = engine.compare_files_streaming(&file1, &file2).unwrap();
+        assert!(!result, "Different files should return false");
+    }
+
+    #[test]
+    fn test_streaming_comparison_different_sizes() {
+        let temp = tempfile::tempdir().unwrap();
+        let cache = HashCache::new(temp.path().join("cache")).unwrap();
+        let engine = ComparisonEngine::new(cache);
+
+        let file1 = temp.path().join("file1.dat");
+        let file2 = temp.path().join("file2.dat");
+
+        // Create files of different sizes
+        let content1 = vec![42u8; 2 * 1024 * 1024];
+        let content2 = vec![42u8; 1024 * 1024]; // Half the size
+        std::fs::write(&file1, &content1).unwrap();
+        std::fs::write(&file2, &content2).unwrap();
+
+        let result = engine.compare_files_streaming(&file1, &file2).unwrap();
+        assert!(!result, "Files of different sizes should return false");
+    }
+
+    #[test]
+    fn test_streaming_threshold_configuration() {
+        let temp = tempfile::tempdir().unwrap();
+        let cache = HashCache::new(temp.path().join("cache")).unwrap();
+
+        // Create engine with 1MB threshold
+        let engine = ComparisonEngine::new(cache)
+            .with_streaming_threshold(1024 * 1024);
+
+        assert_eq!(engine.streaming_threshold, 1024 * 1024);
+    }
+
+    #[test]
+    fn test_tuning_parameters_are_honored() {
+        use std::sync::{Arc, Mutex};
+
+        /// Reader recording the largest buffer it was asked to fill
+        struct RecordingReader {
+            data: std::io::Cursor<Vec<u8>>,
+            largest: Arc<Mutex<usize>>,
+        }
+
+        impl Read for RecordingReader {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                let mut largest = self.largest.lock().unwrap();
+                *largest = (*largest).max(buf.len());
+                self.data.read(buf)
+            }
+        }
+
+        let read_with = |engine: &ComparisonEngine| {
+            let largest = Arc::new(Mutex::new(0));
+            let reader = RecordingReader {
+                data: std::io::Cursor::new(vec![7u8; 10_000]),
+                largest: largest.clone(),
+            };
+            let hash = engine
+                .hash_reader(Path::new("data.bin"), Box::new(reader))
+                .unwrap();
+            let largest = *largest.lock().unwrap();
+            (hash, largest)
+        };
+
+        // Defaults are unchanged
+        let default = ComparisonEngine::new(HashCache::in_memory());
+        assert_eq!(default.hash_buffer_size_for(1024), 64 * 1024);
+        assert_eq!(default.hash_buffer_size_for(20 * 1024 * 1024), 1024 * 1024);
+        assert_eq!(default.stream_chunk_size, 1024 * 1024);
+        let (default_hash, default_buffer) = read_with(&default);
+        assert_eq!(default_buffer, 64 * 1024);
+
+        let tuned = ComparisonEngine::new(HashCache::in_memory())
+            .with_hash_buffer_size(4096)
+            .with_stream_chunk_size(512);
+        assert_eq!(tuned.hash_buffer_size_for(20 * 1024 * 1024), 4096);
+        let (tuned_hash, tuned_buffer) = read_with(&tuned);
+        assert_eq!(tuned_buffer, 4096);
+        assert_eq!(tuned_hash, default_hash);
+
+        // Zero is clamped so the read loops still make progress
+        let clamped = ComparisonEngine::new(HashCache::in_memory())
+            .with_hash_buffer_size(0)
+            .with_stream_chunk_size(0);
+        assert_eq!(clamped.hash_buffer_size_for(1024), 1);
+        assert_eq!(clamped.stream_chunk_size, 1);
+
+        // Streaming works chunk by chunk, catching a difference in the last one
+        let temp = tempfile::tempdir().unwrap();
+        let left = temp.path().join("left.bin");
+        let right = temp.path().join("right.bin");
+        let mut content = vec![1u8; 5000];
+        std::fs::write(&left, &content).unwrap();
+        assert_eq!(
+            tuned.hash_file(&left).unwrap(),
+            default.hash_file(&left).unwrap()
+        );
+        content[4999] = 2;
+        std::fs::write(&right, &content).unwrap();
+        assert!(!tuned.compare_files_streaming(&left, &right).unwrap());
+        assert!(tuned.compare_files_streaming(&left, &left).unwrap());
+    }
+
+    #[test]
+    fn test_compare_files_uses_streaming_for_large_files() {
+        use rcompare_common::{FileEntry, FileType};
+        use std::time::SystemTime;
+
+        let temp = tempfile::tempdir().unwrap();
+        let cache = HashCache::new(temp.path().join("cache")).unwrap();
+
+        // Set threshold to 1MB, enable verification
+        let engine = ComparisonEngine::new(cache)
+            .with_streaming_threshold(1024 * 1024)
+            .with_hash_verification(true);
+
+        let file1 = temp.path().join("file1.dat");
+        let file2 = temp.path().join("file2.dat");
+
+        // Create identical 2MB files (above threshold)
+        let content = vec![42u8; 2 * 1024 * 1024];
+        std::fs::write(&file1, &content).unwrap();
+        std::fs::write(&file2, &content).unwrap();
+
+        let metadata1 = std::fs::metadata(&file1).unwrap();
+        let metadata2 = std::fs::metadata(&file2).unwrap();
+
+        let entry1 = FileEntry {
+            path: "file1.dat".into(),
+            size: metadata1.len(),
+            modified: metadata1.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+            is_dir: false,
+            file_type: FileType::Regular,
+        };
+
+        let entry2 = FileEntry {
+            path: "file2.dat".into(),
+            size: metadata2.len(),
+            modified: metadata2.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+            is_dir: false,
+            file_type: FileType::Regular,
+        };
+
+        let status = engine
+            .compare_files(temp.path(), temp.path(), None, None, &entry1, &entry2)
+            .unwrap();
+
+        assert_eq!(status, DiffStatus::Same, "Large identical files should be detected as same using streaming");
+    }
+
+    /// In-memory VFS with configurable capabilities that counts content reads
+    struct FakeVfs {
+        capabilities: VfsCapabilities,
+        files: HashMap<PathBuf, (Vec<u8>, Option<String>)>,
+        /// Listings returned by `read_dir`; other directories are empty
+        dirs: HashMap<PathBuf, Vec<FileEntry>>,
+        metadata_calls: std::sync::atomic::AtomicUsize,
+        reads: std::sync::atomic::AtomicUsize,
+        /// Answer `Vfs::hash` with a digest instead of `None`
+        serves_hashes: bool,
+        hashes: std::sync::atomic::AtomicUsize,
+    }
+
+    impl FakeVfs {
+        fn new(capabilities: VfsCapabilities) -> Self {
+            Self {
+                capabilities,
+                files: HashMap::new(),
+                dirs: HashMap::new(),
+                metadata_calls: std::sync::atomic::AtomicUsize::new(0),
+                reads: std::sync::atomic::AtomicUsize::new(0),
+                serves_hashes: false,
+                hashes: std::sync::atomic::AtomicUsize::new(0),
+            }
+        }
+
+        fn serving_hashes(mut self) -> Self {
+            self.serves_hashes = true;
+            self
+        }
+
+        fn with_file(mut self, path: &str, content: &[u8], fingerprint: Option<&str>) -> Self {
+            self.files.insert(
+                PathBuf::from(path),
+                (content.to_vec(), fingerprint.map(str::to_string)),
+            );
+            self
+        }
+
+        fn with_dir(mut self, path: &str, entries: Vec<FileEntry>) -> Self {
+            self.dirs.insert(PathBuf::from(path), entries);
+            self
+        }
+
+        fn reads(&self) -> usize {
+            self.reads.load(Ordering::SeqCst)
+        }
+
+        fn metadata_calls(&self) -> usize {
+            self.metadata_calls.load(Ordering::SeqCst)
+        }
+
+        fn hashes(&self) -> usize {
+            self.hashes.load(Ordering::SeqCst)
+        }
+    }
+
+    impl Vfs for FakeVfs {
+        fn instance_id(&self) -> &str {
+            "fake:remote"
+        }
+
+        fn metadata(&self, path: &Path) -> Result<rcompare_common::FileMetadata, VfsError> {
+            self.metadata_calls.fetch_add(1, Ordering::SeqCst);
+            Err(VfsError::NotFound(path.display().to_string()))
+        }
+
+        fn read_dir(&self, path: &Path) -> Result<Vec<FileEntry>, VfsError> {
+            Ok(self.dirs.get(path).cloned().unwrap_or_default())
+        }
+
+        fn open_file(&self, path: &Path) -> Result<Box<dyn Read + Send>, VfsError> {
+            self.reads.fetch_add(1, Ordering::SeqCst);
+            let (content, _) = self
+                .files
+                .get(path)
+                .ok_or_else(|| VfsError::NotFound(path.display().to_string()))?;
+            Ok(Box::new(std::io::Cursor::new(content.clone())))
+        }
+
+        fn remove_file(&self, _path: &Path) -> Result<(), VfsError> {
+            self.ensure_supported(VfsOperation::Delete)
+        }
+
+        fn copy_file(&self, _src: &Path, _dest: &Path) -> Result<(), VfsError> {
+            self.ensure_supported(VfsOperation::Write)
+        }
+
+        fn capabilities(&self) -> VfsCapabilities {
+            self.capabilities
+        }
+
+        fn content_fingerprint(&self, path: &Path) -> Result<Option<String>, VfsError> {
+            Ok(self.files.get(path).and_then(|(_, fp)| fp.clone()))
+        }
+
+        fn hash(&self, path: &Path, algorithm: HashAlgorithm) -> Result<Option<Vec<u8>>, VfsError> {
+            self.hashes.fetch_add(1, Ordering::SeqCst);
+            if !self.serves_hashes {
+                return Ok(None);
+            }
+            let (content, _) = self
+                .files
+                .get(path)
+                .ok_or_else(|| VfsError::NotFound(path.display().to_string()))?;
+            Ok(Some(match algorithm {
+                HashAlgorithm::Blake3 => blake3::hash(content).as_bytes().to_vec(),
+                HashAlgorithm::Sha256 => Sha256::digest(content).to_vec(),
+            }))
+        }
+    }
+
+    fn remote_capabilities() -> VfsCapabilities {
+        VfsCapabilities {
+            expensive_reads: true,
+            ..VfsCapabilities::read_only()
+        }
+    }
+
+    fn entry(path: &str, size: u64, modified: SystemTime) -> FileEntry {
+        FileEntry {
+            path: PathBuf::from(path),
+            size,
+            modified,
+            is_dir: false,
+            file_type: FileType::Regular,
+        }
+    }
+
+    #[test]
+    fn test_expensive_reads_use_fingerprints_and_mtime() {
+        let temp = TempDir::new().unwrap();
+        let cache = HashCache::new(temp.path().to_path_buf()).unwrap();
+        let engine = ComparisonEngine::new(cache);
+
+        let vfs = FakeVfs::new(remote_capabilities())
+            .with_file("a/same.txt", b"content", Some("fake:\"1\""))
+            .with_file("b/same.txt", b"content", Some("fake:\"1\""))
+            .with_file("a/changed.txt", b"left!!", Some("fake:\"2\""))
+            .with_file("b/changed.txt", b"right!", Some("fake:\"3\""))
+            .with_file("a/touched.txt", b"content", None)
+            .with_file("b/touched.txt", b"content", None)
+            .with_file("a/untouched.txt", b"content", None)
+            .with_file("b/untouched.txt", b"content", None);
+
+        let t0 = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_000);
+        let t1 = t0 + std::time::Duration::from_secs(60);
+        let left = vec![
+            entry("same.txt", 7, t0),
+            entry("changed.txt", 6, t0),
+            entry("touched.txt", 7, t0),
+            entry("untouched.txt", 7, t0),
+        ];
+        let right = vec![
+            entry("same.txt", 7, t1),
+            entry("changed.txt", 6, t1),
+            entry("touched.txt", 7, t1),
+            entry("untouched.txt", 7, t0),
+        ];
+
+        let compare = |engine: &ComparisonEngine| {
+            engine
+                .compare_with_vfs(
+                    Path::new("a"),
+                    Path::new("b"),
+                    left.clone(),
+                    right.clone(),
+                    Some(&vfs),
+                    Some(&vfs),
+                )
+                .unwrap()
+        };
+        let status = |diffs: &[DiffNode], name: &str| {
+            diffs
+                .iter()
+                .find(|d| d.relative_path == Path::new(name))
+                .unwrap()
+                .status
+        };
+
+        let diffs = compare(&engine);
+        assert_eq!(status(&diffs, "same.txt"), DiffStatus::Same);
+        assert_eq!(status(&diffs, "changed.txt"), DiffStatus::Different);
+        assert_eq!(status(&diffs, "touched.txt"), DiffStatus::Unchecked);
+        assert_eq!(status(&diffs, "untouched.txt"), DiffStatus::Same);
+        assert_eq!(
+            vfs.reads(),
+            0,
+            "no content should be read from an expensive backend"
+        );
+
+        // Asking for hash verification reads the files fingerprints can't
+        // decide, instead of trusting their mtimes
+        let diffs = compare(&engine.with_hash_verification(true));
+        assert_eq!(status(&diffs, "same.txt"), DiffStatus::Same);
+        assert_eq!(status(&diffs, "changed.txt"), DiffStatus::Different);
+        assert_eq!(status(&diffs, "touched.txt"), DiffStatus::Same);
+        assert_eq!(status(&diffs, "untouched.txt"), DiffStatus::Same);
+        assert_eq!(vfs.reads(), 4);
+    }
+
+    #[test]
+    fn test_cheap_reads_still_hash_content() {
+        let temp = TempDir::new().unwrap();
+        let cache = HashCache::new(temp.path().to_path_buf()).unwrap();
+        let engine = ComparisonEngine::new(cache).with_hash_verification(true);
+
+        let vfs = FakeVfs::new(VfsCapabilities::read_only())
+            .with_file("a/file.txt", b"content", None)
+            .with_file("b/file.txt", b"content", None);
+
+        let t0 = SystemTime::UNIX_EPOCH;
+        let t1 = t0 + std::time::Duration::from_secs(60);
+        let status = engine
+            .compare_files(
+                Path::new("a"),
+                Path::new("b"),
+                Some(&vfs),
+                Some(&vfs),
+                &entry("file.txt", 7, t0),
+                &entry("file.txt", 7, t1),
+            )
+            .unwrap();
+
+        assert_eq!(status, DiffStatus::Same);
+        assert_eq!(vfs.reads(), 2);
+        // Asked first, then fell back to reading
+        assert_eq!(vfs.hashes(), 2);
+    }
+
+    #[test]
+    fn test_backend_hashes_avoid_reads() {
+        let temp = TempDir::new().unwrap();
+        let cache = HashCache::new(temp.path().to_path_buf()).unwrap();
+        let engine = ComparisonEngine::new(cache).with_hash_verification(true);
+
+        let vfs = FakeVfs::new(VfsCapabilities::read_only())
+            .serving_hashes()
+            .with_file("a/same.txt", b"content", None)
+            .with_file("b/same.txt", b"content", None)
+            .with_file("a/diff.txt", b"content", None)
+            .with_file("b/diff.txt", b"CONTENT", None);
+
+        let t0 = SystemTime::UNIX_EPOCH;
+        let t1 = t0 + std::time::Duration::from_secs(60);
+        let status = |name: &str| {
+            engine
+                .compare_files(
+                    Path::new("a"),
+                    Path::new("b"),
+                    Some(&vfs),
+                    Some(&vfs),
+                    &entry(name, 7, t0),
+                    &entry(name, 7, t1),
+                )
+                .unwrap()
+        };
+
+        assert_eq!(status("same.txt"), DiffStatus::Same);
+        assert_eq!(status("diff.txt"), DiffStatus::Different);
+        assert_eq!(vfs.hashes(), 4);
+        assert_eq!(vfs.reads(), 0);
+        assert_eq!(engine.hash_stats().bytes_hashed, 28);
+
+        let records = engine
+            .generate_manifest(
+                Path::new("a"),
+                vec![entry("same.txt", 7, t0)],
+                Some(&vfs),
+                ManifestHashAlgorithm::Sha256,
+            )
+            .unwrap();
+        assert_eq!(
+            records[0].hash,
+            format!("sha256:{}", hex::encode(Sha256::digest(b"content")))
+        );
+        assert_eq!(vfs.reads(), 0);
+    }
+
+    #[test]
+    fn test_matching_fingerprints_skip_reads() {
+        let temp = TempDir::new().unwrap();
+        let vfs = FakeVfs::new(VfsCapabilities::read_only())
+            .with_file("a/same.txt", b"content", Some("zip:1a2b3c4d:7"))
+            .with_file("b/same.txt", b"content", Some("zip:1a2b3c4d:7"))
+            .with_file("a/diff.txt", b"content", Some("zip:1a2b3c4d:7"))
+            .with_file("b/diff.txt", b"CONTENT", Some("zip:99999999:7"));
+
+        let t0 = SystemTime::UNIX_EPOCH;
+        let compare = |engine: &ComparisonEngine, name: &str| {
+            engine
+                .compare_files(
+                    Path::new("a"),
+                    Path::new("b"),
+                    Some(&vfs),
+                    Some(&vfs),
+                    &entry(name, 7, t0),
+                    &entry(name, 7, t0),
+                )
+                .unwrap()
+        };
+
+        let engine = ComparisonEngine::new(HashCache::new(temp.path().to_path_buf()).unwrap());
+        assert_eq!(compare(&engine, "same.txt"), DiffStatus::Same);
+        assert_eq!(compare(&engine, "diff.txt"), DiffStatus::Different);
+        assert_eq!(vfs.reads(), 0);
+
+        // Strict mode confirms a match by reading both sides
+        let strict = ComparisonEngine::new(HashCache::new(temp.path().to_path_buf()).unwrap())
+            .with_strict_fingerprints(true);
+        assert_eq!(compare(&strict, "same.txt"), DiffStatus::Same);
+        assert_eq!(vfs.reads(), 2);
+        assert_eq!(compare(&strict, "diff.txt"), DiffStatus::Different);
+        assert_eq!(vfs.reads(), 2);
+    }
+
+    #[cfg(feature = "archives")]
+    #[test]
+    fn test_recompressed_files_are_same_when_decompressed() {
+        use flate2::write::GzEncoder;
+        use flate2::Compression;
+
+        fn gzip(payload: &[u8], level: u32) -> Vec<u8> {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::new(level));
+            encoder.write_all(payload).unwrap();
+            encoder.finish().unwrap()
+        }
+
+        let temp = TempDir::new().unwrap();
+        let left = temp.path().join("left");
+        let right = temp.path().join("right");
+        fs::create_dir_all(&left).unwrap();
+        fs::create_dir_all(&right).unwrap();
+
+        let payload = "the same payload, line after line\n".repeat(500);
+        let fast = gzip(payload.as_bytes(), 1);
+        let best = gzip(payload.as_bytes(), 9);
+        assert_ne!(fast, best);
+        fs::write(left.join("data.gz"), &fast).unwrap();
+        fs::write(right.join("data.gz"), &best).unwrap();
+        // Recognized by magic bytes, without a compression extension
+        fs::write(left.join("blob"), &fast).unwrap();
+        fs::write(right.join("blob"), &best).unwrap();
+        fs::write(left.join("other.gz"), gzip(b"left payload", 1)).unwrap();
+        fs::write(right.join("other.gz"), gzip(b"right payload", 9)).unwrap();
+        let zst = |level| zstd::encode_all(payload.as_bytes(), level).unwrap();
+        fs::write(left.join("data.zst"), zst(1)).unwrap();
+        fs::write(right.join("data.zst"), zst(19)).unwrap();
+        // Not compressed at all: the flag leaves such pairs alone
+        fs::write(left.join("plain.txt"), "left").unwrap();
+        fs::write(right.join("plain.txt"), "right!").unwrap();
+
+        let scanner = FolderScanner::new(AppConfig::default());
+        let compare = |decompress: bool| {
+            let cache = HashCache::new(temp.path().join("cache")).unwrap();
+            ComparisonEngine::new(cache)
+                .with_decompressed_comparison(decompress)
+                .compare(
+                    &left,
+                    &right,
+                    scanner.scan(&left).unwrap(),
+                    scanner.scan(&right).unwrap(),
+                )
+                .unwrap()
+        };
+        let node = |nodes: &[DiffNode], name: &str| {
+            let node = nodes
+                .iter()
+                .find(|n| n.relative_path == Path::new(name))
+                .unwrap();
+            (node.status, node.reason)
+        };
+
+        let raw = compare(false);
+        assert_eq!(node(&raw, "data.gz"), (DiffStatus::Different, None));
+        assert_eq!(node(&raw, "data.zst"), (DiffStatus::Different, None));
+
+        let decompressed = compare(true);
+        let matched = (DiffStatus::Same, Some(DiffReason::DecompressedMatch));
+        assert_eq!(node(&decompressed, "data.gz"), matched);
+        assert_eq!(node(&decompressed, "blob"), matched);
+        assert_eq!(node(&decompressed, "data.zst"), matched);
+        assert_eq!(
+            node(&decompressed, "other.gz"),
+            (DiffStatus::Different, None)
+        );
+        assert_eq!(
+            node(&decompressed, "plain.txt"),
+            (DiffStatus::Different, None)
+        );
+    }
+
+    #[test]
+    fn test_path_transform_pairs_hashed_asset_names() {
+        let temp = TempDir::new().unwrap();
+        let left = temp.path().join("left");
+        let right = temp.path().join("right");
+        for (root, files) in [
+            (
+                &left,
+                [
+                    ("app.abc123.js", "app"),
+                    ("vendor.111aaa.js", "vendor 1"),
+                    ("chunk.aaa111.js", "chunk"),
+                    ("chunk.bbb222.js", "chunk"),
+                ],
+            ),
+            (
+                &right,
+                [
+                    ("app.def456.js", "app"),
+                    ("vendor.222bbb.js", "vendor 2"),
+                    ("chunk.ccc333.js", "chunk"),
+                    ("app.css", "css"),
+                ],
+            ),
+        ] {
+            fs::create_dir_all(root.join("static")).unwrap();
+            for (name, content) in files {
+                fs::write(root.join("static").join(name), content).unwrap();
+            }
+        }
+        fs::write(left.join("static/app.css"), "css").unwrap();
+
+        let hash = regex::Regex::new(r"\.[0-9a-f]{6}\.js$").unwrap();
+        let scanner = FolderScanner::new(AppConfig::default());
+        let cache = HashCache::new(temp.path().join("cache")).unwrap();
+        let engine = ComparisonEngine::new(cache)
+            .with_hash_verification(true)
+            .with_path_transform(move |path| {
+                PathBuf::from(hash.replace(&path.to_string_lossy(), ".js").as_ref())
+            });
+        let nodes = engine
+            .compare(
+                &left,
+                &right,
+                scanner.scan(&left).unwrap(),
+                scanner.scan(&right).unwrap(),
+            )
+            .unwrap();
 
-        // Create test files
-        let file1 = temp.path().join("file1.txt");
-        let file2 = temp.path().join("file2.txt");
-        let file3 = temp.path().join("file3.txt");
+        let sides = |n: &DiffNode| {
+            let path = |e: &Option<FileEntry>| e.as_ref().map(|e| e.path.clone());
+            (n.status, path(&n.left), path(&n.right))
+        };
+        let node = |name: &str| {
+            nodes
+                .iter()
+                .find(|n| n.relative_path == Path::new("static").join(name))
+                .map(sides)
+                .unwrap()
+        };
+        let at = |name: &str| Some(Path::new("static").join(name));
+        assert_eq!(
+            node("app.abc123.js"),
+            (DiffStatus::Same, at("app.abc123.js"), at("app.def456.js"))
+        );
+        assert_eq!(
+            node("vendor.111aaa.js"),
+            (
+                DiffStatus::Different,
+                at("vendor.111aaa.js"),
+                at("vendor.222bbb.js")
+            )
+        );
+        assert_eq!(node("app.css").0, DiffStatus::Same);
+
+        // Both left chunks strip to the same name: reported, not paired
+        assert_eq!(node("chunk.aaa111.js").0, DiffStatus::OrphanLeft);
+        assert_eq!(node("chunk.bbb222.js").0, DiffStatus::OrphanLeft);
+        assert_eq!(node("chunk.ccc333.js").0, DiffStatus::OrphanRight);
+        assert_eq!(
+            engine.path_collisions(),
+            [PathCollision {
+                transformed: PathBuf::from("static/chunk.js"),
+                left: vec![
+                    at("chunk.aaa111.js").unwrap(),
+                    at("chunk.bbb222.js").unwrap()
+                ],
+                right: vec![at("chunk.ccc333.js").unwrap()],
+            }]
+        );
+        assert_eq!(nodes.len(), 7);
+    }
 
-        File::create(&file1)
-            .unwrap()
-            .write_all(b"content1")
+    #[test]
+    fn test_path_transform_collisions_never_drop_entries() {
+        let temp = TempDir::new().unwrap();
+        let t0 = SystemTime::UNIX_EPOCH;
+        // `a` and `c` collide on `b`, while `e` transforms to `a`
+        let engine = ComparisonEngine::new(HashCache::new(temp.path().join("cache")).unwrap())
+            .with_path_transform(|path| {
+                PathBuf::from(match path.to_str().unwrap() {
+                    "a" | "c" => "b",
+                    "e" => "a",
+                    other => other,
+                })
+            });
+        let nodes = engine
+            .compare(
+                temp.path(),
+                temp.path(),
+                vec![entry("a", 1, t0), entry("c", 1, t0), entry("e", 1, t0)],
+                Vec::new(),
+            )
             .unwrap();
-        File::create(&file2)
-            .unwrap()
-            .write_all(b"content2")
+
+        let mut paths: Vec<&Path> = nodes.iter().map(|n| n.relative_path.as_path()).collect();
+        paths.sort();
+        assert_eq!(paths, [Path::new("a"), Path::new("c"), Path::new("e")]);
+        assert!(nodes.iter().all(|n| n.status == DiffStatus::OrphanLeft));
+    }
+
+    #[test]
+    fn test_ignored_entries_are_kept_with_their_rule() {
+        let temp = TempDir::new().unwrap();
+        let left = temp.path().join("left");
+        let right = temp.path().join("right");
+        for root in [&left, &right] {
+            fs::create_dir_all(root.join("cache")).unwrap();
+            fs::write(root.join("cache/blob"), "cached").unwrap();
+            fs::write(root.join("same.txt"), "same").unwrap();
+            fs::write(root.join("notes"), "notes").unwrap();
+        }
+        fs::write(left.join("debug.log"), "left log").unwrap();
+
+        let scanner = |patterns: &[&str]| {
+            FolderScanner::new(AppConfig {
+                ignore_patterns: patterns.iter().map(|p| p.to_string()).collect(),
+                ..Default::default()
+            })
+        };
+        let left_scan = scanner(&["*.log", "cache/"])
+            .scan_with_ignored(&left, None)
+            .unwrap();
+        let right_scan = scanner(&["cache/", "notes"])
+            .scan_with_ignored(&right, None)
             .unwrap();
-        File::create(&file3)
-            .unwrap()
-            .write_all(b"content1")
-            .unwrap(); // Same as file1
 
-        let paths = vec![file1.as_path(), file2.as_path(), file3.as_path()];
+        let cache = HashCache::new(temp.path().join("cache")).unwrap();
+        let mut nodes = ComparisonEngine::new(cache)
+            .compare(&left, &right, left_scan.entries, right_scan.entries)
+            .unwrap();
+        add_ignored_nodes(&mut nodes, left_scan.ignored, right_scan.ignored);
+
+        let summary: Vec<(&str, DiffStatus, Option<&str>, bool, bool)> = nodes
+            .iter()
+            .map(|n| {
+                (
+                    n.relative_path.to_str().unwrap(),
+                    n.status,
+                    n.ignored_by.as_deref(),
+                    n.left.is_some(),
+                    n.right.is_some(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("cache", DiffStatus::Ignored, Some("cache/"), true, true),
+                ("debug.log", DiffStatus::Ignored, Some("*.log"), true, false),
+                // Compared as a left orphan, then ignored on the right
+                ("notes", DiffStatus::Ignored, Some("notes"), true, true),
+                ("same.txt", DiffStatus::Same, None, true, true),
+            ]
+        );
+    }
 
-        // Hash in parallel
-        let results = engine.hash_files_parallel(paths.iter().copied());
+    #[cfg(feature = "archives")]
+    #[test]
+    fn test_directory_against_zip_compares_content() {
+        use crate::vfs::{WritableZipVfs, ZipVfs};
 
-        assert_eq!(results.len(), 3);
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path().join("dir");
+        fs::create_dir_all(&dir).unwrap();
+        let zip_path = temp.path().join("same.zip");
+        let zip = WritableZipVfs::create(zip_path.clone()).unwrap();
+        // Local files are older than the archive entries
+        let old = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_000_000_000);
+        for (name, content) in [("a.txt", "alpha"), ("b.txt", "beta")] {
+            fs::write(dir.join(name), content).unwrap();
+            File::options()
+                .write(true)
+                .open(dir.join(name))
+                .unwrap()
+                .set_modified(old)
+                .unwrap();
+            zip.write_file(Path::new(name), content.as_bytes()).unwrap();
+        }
+        zip.flush().unwrap();
+        let zip = ZipVfs::new(zip_path).unwrap();
+
+        let scanner = FolderScanner::new(AppConfig::default());
+        let compare = |engine: &ComparisonEngine| {
+            engine
+                .compare_with_vfs(
+                    &dir,
+                    Path::new(""),
+                    scanner.scan(&dir).unwrap(),
+                    scanner.scan_vfs(&zip, Path::new("")).unwrap(),
+                    None,
+                    Some(&zip as &dyn Vfs),
+                )
+                .unwrap()
+        };
+        let status = |nodes: &[DiffNode], name: &str| {
+            nodes
+                .iter()
+                .find(|n| n.relative_path == Path::new(name))
+                .unwrap()
+                .status
+        };
 
-        // All should succeed
-        assert!(results.iter().all(|(_, r)| r.is_ok()));
+        // No hash verification requested: the engine still compares content
+        let engine = ComparisonEngine::new(HashCache::new(temp.path().join("cache")).unwrap());
+        let nodes = compare(&engine);
+        assert_eq!(status(&nodes, "a.txt"), DiffStatus::Same);
+        assert_eq!(status(&nodes, "b.txt"), DiffStatus::Same);
 
-        // file1 and file3 should have same hash (same content)
-        let hash1 = results[0].1.as_ref().unwrap();
-        let hash2 = results[1].1.as_ref().unwrap();
-        let hash3 = results[2].1.as_ref().unwrap();
+        let engine = engine.with_vfs_content_verification(false);
+        assert_eq!(status(&compare(&engine), "a.txt"), DiffStatus::Unchecked);
+    }
 
-        assert_eq!(hash1, hash3, "Files with same content should have same hash");
-        assert_ne!(hash1, hash2, "Files with different content should have different hash");
+    #[test]
+    fn test_three_way_expensive_reads_skip_hashing() {
+        let temp = TempDir::new().unwrap();
+        let cache = HashCache::new(temp.path().to_path_buf()).unwrap();
+        let engine = ComparisonEngine::new(cache).with_hash_verification(true);
+
+        let vfs = FakeVfs::new(remote_capabilities())
+            .with_file("a/file.txt", b"content", Some("fake:1"))
+            .with_file("b/file.txt", b"content", Some("fake:1"));
+
+        let t0 = SystemTime::UNIX_EPOCH;
+        let t1 = t0 + std::time::Duration::from_secs(60);
+        let same = engine
+            .files_same(
+                Path::new("a"),
+                Path::new("b"),
+                Some(&vfs),
+                Some(&vfs),
+                &entry("file.txt", 7, t0),
+                &entry("file.txt", 7, t1),
+            )
+            .unwrap();
+
+        assert!(same);
+        assert_eq!(vfs.reads(), 0);
     }
 
     #[test]
-    fn test_parallel_hashing_with_errors() {
+    fn test_ensure_supported_rejects_writes_on_read_only_vfs() {
+        let vfs = FakeVfs::new(VfsCapabilities::read_only());
+
+        assert!(vfs.ensure_supported(VfsOperation::Read).is_ok());
+        match vfs.ensure_supported(VfsOperation::SetMtime) {
+            Err(VfsError::Unsupported(msg)) => {
+                assert!(msg.contains("fake:remote"), "{}", msg);
+                assert!(msg.contains("modification times"), "{}", msg);
+            }
+            other => panic!("expected Unsupported, got {:?}", other),
+        }
+        assert!(matches!(
+            vfs.remove_file(Path::new("file.txt")),
+            Err(VfsError::Unsupported(_))
+        ));
+    }
+
+    #[cfg(feature = "archives")]
+    #[test]
+    fn test_tar_symlinks_compare_by_target() {
         let temp = TempDir::new().unwrap();
-        let cache = HashCache::new(temp.path().join("cache")).unwrap();
-        let engine = ComparisonEngine::new(cache);
+        let tar_with_link = |name: &str, target: &str| {
+            let path = temp.path().join(name);
+            let mut builder = tar::Builder::new(File::create(&path).unwrap());
+            for file in ["a.txt", "b.txt"] {
+                let mut header = tar::Header::new_gnu();
+                header.set_size(4);
+                header.set_cksum();
+                builder
+                    .append_data(&mut header, file, &b"same"[..])
+                    .unwrap();
+            }
+            let mut header = tar::Header::new_gnu();
+            header.set_entry_type(tar::EntryType::Symlink);
+            header.set_size(0);
+            builder.append_link(&mut header, "link", target).unwrap();
+            builder.finish().unwrap();
+            crate::vfs::TarVfs::new(path).unwrap()
+        };
+        let left = tar_with_link("left.tar", "a.txt");
+        let same = tar_with_link("same.tar", "a.txt");
+        let moved = tar_with_link("moved.tar", "b.txt");
+
+        let engine = ComparisonEngine::new(HashCache::new(temp.path().join("cache")).unwrap());
+        let scanner = FolderScanner::new(AppConfig::default());
+        let link_status = |right: &crate::vfs::TarVfs| {
+            engine
+                .compare_with_vfs(
+                    Path::new(""),
+                    Path::new(""),
+                    scanner.scan_vfs(&left, Path::new("")).unwrap(),
+                    scanner.scan_vfs(right, Path::new("")).unwrap(),
+                    Some(&left),
+                    Some(right),
+                )
+                .unwrap()
+                .into_iter()
+                .find(|d| d.relative_path == Path::new("link"))
+                .unwrap()
+                .status
+        };
 
-        // Create one valid file and reference non-existent files
-        let file1 = temp.path().join("file1.txt");
-        let file2 = temp.path().join("nonexistent.txt");
+        // Both links are empty entries; only their targets tell them apart
+        assert_eq!(link_status(&same), DiffStatus::Same);
+        assert_eq!(link_status(&moved), DiffStatus::Different);
+    }
 
-        std::fs::File::create(&file1)
-            .unwrap()
-            .write_all(b"content")
-            .unwrap();
+    fn write_orphan_pair(temp: &TempDir) -> (PathBuf, PathBuf, Vec<FileEntry>, Vec<FileEntry>) {
+        let left = temp.path().join("left");
+        let right = temp.path().join("right");
+        std::fs::create_dir_all(&left).unwrap();
+        std::fs::create_dir_all(&right).unwrap();
 
-        let paths = vec![file1.as_path(), file2.as_path()];
+        let original: String = (0..100)
+            .map(|i| format!("line {:03}: some configuration value = {}\n", i, i * 3))
+            .collect();
+        let edited = original.replace(
+            "line 050: some configuration value = 150",
+            "line 050: edited",
+        );
+        let unrelated: String = (0..40).map(|i| format!("{} unrelated\n", i)).collect();
 
-        // Hash in parallel
-        let results = engine.hash_files_parallel(paths.iter().copied());
+        std::fs::write(left.join("a.txt"), &original).unwrap();
+        std::fs::write(right.join("b.txt"), &edited).unwrap();
+        std::fs::write(right.join("c.txt"), &unrelated).unwrap();
 
-        assert_eq!(results.len(), 2);
-        assert!(results[0].1.is_ok(), "First file should hash successfully");
-        assert!(results[1].1.is_err(), "Second file should error (not found)");
+        let entry = |root: &Path, name: &str| FileEntry {
+            path: PathBuf::from(name),
+            size: std::fs::metadata(root.join(name)).unwrap().len(),
+            modified: SystemTime::now(),
+            is_dir: false,
+            file_type: FileType::Regular,
+        };
+        let left_entries = vec![entry(&left, "a.txt")];
+        let right_entries = vec![entry(&right, "b.txt"), entry(&right, "c.txt")];
+        (left, right, left_entries, right_entries)
     }
 
     #[test]
-    fn test_streaming_comparison_identical_files() {
-        let temp = tempfile::tempdir().unwrap();
+    fn test_similarity_pass_pairs_near_duplicate_orphans() {
+        let temp = TempDir::new().unwrap();
         let cache = HashCache::new(temp.path().join("cache")).unwrap();
-        let engine = ComparisonEngine::new(cache);
+        let engine = ComparisonEngine::new(cache).with_similarity_detection(80.0);
+        let (left, right, left_entries, right_entries) = write_orphan_pair(&temp);
 
-        let file1 = temp.path().join("file1.dat");
-        let file2 = temp.path().join("file2.dat");
+        let diff = engine
+            .compare(&left, &right, left_entries, right_entries)
+            .unwrap();
 
-        // Create identical large content (2MB)
-        let content = vec![42u8; 2 * 1024 * 1024];
-        std::fs::write(&file1, &content).unwrap();
-        std::fs::write(&file2, &content).unwrap();
+        let node = |name: &str| {
+            diff.iter()
+                .find(|d| d.relative_path == Path::new(name))
+                .unwrap()
+        };
+        let a = node("a.txt")
+            .similar_to
+            .as_ref()
+            .expect("a.txt should be paired");
+        assert_eq!(a.path, PathBuf::from("b.txt"));
+        assert!(a.similarity > 85.0, "{}", a.similarity);
+        let b = node("b.txt")
+            .similar_to
+            .as_ref()
+            .expect("b.txt should be paired");
+        assert_eq!(b.path, PathBuf::from("a.txt"));
+        assert!(node("c.txt").similar_to.is_none());
+    }
 
-        let result = engine.compare_files_streaming(&file1, &file2).unwrap();
-        assert!(result, "Identical files should return true");
+    #[test]
+    fn test_rename_threshold_decides_between_rename_and_orphans() {
+        let temp = TempDir::new().unwrap();
+        let left = temp.path().join("left");
+        let right = temp.path().join("right");
+        std::fs::create_dir_all(left.join("docs")).unwrap();
+        std::fs::create_dir_all(right.join("archive")).unwrap();
+
+        // 20 of 400 lines rewritten after the move
+        let original: String = (0..400)
+            .map(|i| format!("{:03} the quick brown fox jumps over the lazy dog\n", i))
+            .collect();
+        let edited: String = original
+            .lines()
+            .enumerate()
+            .map(|(i, line)| match i {
+                200..=219 => format!("{:03} rewritten after the move\n", i),
+                _ => format!("{}\n", line),
+            })
+            .collect();
+        std::fs::write(left.join("docs/guide.txt"), &original).unwrap();
+        std::fs::write(right.join("archive/guide.txt"), &edited).unwrap();
+
+        let scanner = FolderScanner::new(AppConfig::default());
+        let compare = |threshold: f64| {
+            let cache = HashCache::new(temp.path().join("cache")).unwrap();
+            ComparisonEngine::new(cache)
+                .with_rename_similarity_threshold(threshold)
+                .compare(
+                    &left,
+                    &right,
+                    scanner.scan(&left).unwrap(),
+                    scanner.scan(&right).unwrap(),
+                )
+                .unwrap()
+        };
+        let node = |diff: &[DiffNode], name: &str| {
+            diff.iter()
+                .find(|d| d.relative_path == Path::new(name))
+                .unwrap()
+                .clone()
+        };
+
+        let diff = compare(0.9);
+        let moved = node(&diff, "docs/guide.txt");
+        assert_eq!(moved.status, DiffStatus::OrphanLeft);
+        assert_eq!(moved.reason, Some(DiffReason::Renamed));
+        let rename = moved.similar_to.expect("moved file should be a rename");
+        assert_eq!(rename.path, PathBuf::from("archive/guide.txt"));
+        assert!(
+            (90.0..99.0).contains(&rename.similarity),
+            "{}",
+            rename.similarity
+        );
+        let target = node(&diff, "archive/guide.txt");
+        assert_eq!(target.reason, Some(DiffReason::Renamed));
+        assert_eq!(target.similar_to.unwrap().similarity, rename.similarity);
+        assert_eq!(summarize(&diff).renamed, 1);
+
+        let diff = compare(0.99);
+        for name in ["docs/guide.txt", "archive/guide.txt"] {
+            let orphan = node(&diff, name);
+            assert!(orphan.similar_to.is_none());
+            assert_eq!(orphan.reason, None);
+        }
+        assert_eq!(summarize(&diff).renamed, 0);
+
+        // Near-duplicates below the rename threshold are paired but not
+        // renames, and out-of-range thresholds are clamped
+        let cache = HashCache::new(temp.path().join("cache")).unwrap();
+        let diff = ComparisonEngine::new(cache)
+            .with_similarity_detection(50.0)
+            .with_rename_similarity_threshold(1.5)
+            .compare(
+                &left,
+                &right,
+                scanner.scan(&left).unwrap(),
+                scanner.scan(&right).unwrap(),
+            )
+            .unwrap();
+        let moved = node(&diff, "docs/guide.txt");
+        assert!(moved.similar_to.is_some());
+        assert_eq!(moved.reason, None);
     }
 
     #[test]
-    fn test_streaming_comparison_different_files() {
-        let temp = tempfile::tempdir().unwrap();
+    fn test_similarity_pass_is_opt_in_and_bounded() {
+        let temp = TempDir::new().unwrap();
+        let (left, right, left_entries, right_entries) = write_orphan_pair(&temp);
+
         let cache = HashCache::new(temp.path().join("cache")).unwrap();
-        let engine = ComparisonEngine::new(cache);
+        let diff = ComparisonEngine::new(cache)
+            .compare(&left, &right, left_entries.clone(), right_entries.clone())
+            .unwrap();
+        assert!(diff.iter().all(|d| d.similar_to.is_none()));
 
-        let file1 = temp.path().join("file1.dat");
-        let file2 = temp.path().join("file2.dat");
+        let cache = HashCache::new(temp.path().join("cache2")).unwrap();
+        let diff = ComparisonEngine::new(cache)
+            .with_similarity_detection(80.0)
+            .with_max_similarity_pairs(0)
+            .compare(&left, &right, left_entries, right_entries)
+            .unwrap();
+        assert!(diff.iter().all(|d| d.similar_to.is_none()));
+    }
 
-        // Create different large content (2MB each)
-        let content1 = vec![42u8; 2 * 1024 * 1024];
-        let content2 = vec![43u8; 2 * 1024 * 1024];
-        std::fs::write(&file1, &content1).unwrap();
-        std::fs::write(&file2, &content2).unwrap();
+    #[test]
+    fn test_similarity_pass_only_reads_orphans_of_close_sizes() {
+        let original: String = (0..200).map(|i| format!("line {i}\n")).collect();
+        let edited = original.replace("line 100\n", "line one hundred\n");
+        let mut vfs = FakeVfs::new(VfsCapabilities::full())
+            .with_file("a/report.txt", original.as_bytes(), None)
+            .with_file("b/report-v2.txt", edited.as_bytes(), None);
+        let t0 = SystemTime::UNIX_EPOCH;
+        let mut right = vec![entry("report-v2.txt", edited.len() as u64, t0)];
+        // Far too small or too large to reach the threshold
+        for (i, size) in [1usize, 40, 200, 20_000].into_iter().enumerate() {
+            let name = format!("other{i}.txt");
+            vfs = vfs.with_file(&format!("b/{name}"), "x".repeat(size).as_bytes(), None);
+            right.insert(0, entry(&name, size as u64, t0));
+        }
 
-        let result = engine.compare_files_streaming(&file1, &file2).unwrap();
-        assert!(!result, "Different files should return false");
+        let temp = TempDir::new().unwrap();
+        let diffs = ComparisonEngine::new(HashCache::new(temp.path().to_path_buf()).unwrap())
+            .with_similarity_detection(80.0)
+            .with_max_similarity_pairs(1)
+            .compare_with_vfs(
+                Path::new("a"),
+                Path::new("b"),
+                vec![entry("report.txt", original.len() as u64, t0)],
+                right,
+                Some(&vfs),
+                Some(&vfs),
+            )
+            .unwrap();
+
+        let report = diffs
+            .iter()
+            .find(|d| d.relative_path == Path::new("report.txt"))
+            .unwrap();
+        assert_eq!(
+            report.similar_to.as_ref().map(|s| s.path.as_path()),
+            Some(Path::new("report-v2.txt"))
+        );
+        assert_eq!(vfs.reads(), 2);
     }
 
     #[test]
-    fn test_streaming_comparison_different_sizes() {
-        let temp = tempfile::tempdir().unwrap();
-        let cache = HashCache::new(temp.path().join("cache")).unwrap();
-        let engine = ComparisonEngine::new(cache);
+    fn test_directory_signatures_skip_identical_subtrees() {
+        let t0 = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_000);
+        let dir = |path: &str| FileEntry {
+            path: PathBuf::from(path),
+            size: 0,
+            modified: t0,
+            is_dir: true,
+            file_type: FileType::Directory,
+        };
+        let entries = |other_modified: SystemTime| {
+            vec![
+                dir("same"),
+                entry("same/one.txt", 3, t0),
+                dir("same/nested"),
+                entry("same/nested/two.txt", 3, t0),
+                dir("other"),
+                entry("other/three.txt", 3, other_modified),
+            ]
+        };
+        let vfs = || {
+            FakeVfs::new(VfsCapabilities::full())
+                .with_file("a/same/one.txt", b"one", None)
+                .with_file("b/same/one.txt", b"one", None)
+                .with_file("a/same/nested/two.txt", b"two", None)
+                .with_file("b/same/nested/two.txt", b"two", None)
+                .with_file("a/other/three.txt", b"abc", None)
+                .with_file("b/other/three.txt", b"xyz", None)
+        };
 
-        let file1 = temp.path().join("file1.dat");
-        let file2 = temp.path().join("file2.dat");
+        let run = |signatures: bool, vfs: &FakeVfs| {
+            let temp = TempDir::new().unwrap();
+            let cache = HashCache::new(temp.path().to_path_buf()).unwrap();
+            ComparisonEngine::new(cache)
+                .with_hash_verification(true)
+                .with_directory_signatures(signatures)
+                .compare_with_vfs(
+                    Path::new("a"),
+                    Path::new("b"),
+                    entries(t0),
+                    entries(t0 + std::time::Duration::from_secs(60)),
+                    Some(vfs),
+                    Some(vfs),
+                )
+                .unwrap()
+        };
 
-        // Create files of different sizes
-        let content1 = vec![42u8; 2 * 1024 * 1024];
-        let content2 = vec![42u8; 1024 * 1024]; // Half the size
-        std::fs::write(&file1, &content1).unwrap();
-        std::fs::write(&file2, &content2).unwrap();
+        let baseline_vfs = vfs();
+        let baseline = run(false, &baseline_vfs);
+        assert_eq!(baseline_vfs.reads(), 6);
 
-        let result = engine.compare_files_streaming(&file1, &file2).unwrap();
-        assert!(!result, "Files of different sizes should return false");
+        let signature_vfs = vfs();
+        let diffs = run(true, &signature_vfs);
+        // Only the differing subtree is read
+        assert_eq!(signature_vfs.reads(), 2);
+
+        let statuses = |diffs: &[DiffNode]| {
+            diffs
+                .iter()
+                .map(|d| (d.relative_path.clone(), d.status))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(statuses(&diffs), statuses(&baseline));
+        let status = |path: &str| {
+            diffs
+                .iter()
+                .find(|d| d.relative_path == Path::new(path))
+                .unwrap()
+                .status
+        };
+        assert_eq!(status("same/nested/two.txt"), DiffStatus::Same);
+        assert_eq!(status("other/three.txt"), DiffStatus::Different);
     }
 
     #[test]
-    fn test_streaming_threshold_configuration() {
-        let temp = tempfile::tempdir().unwrap();
-        let cache = HashCache::new(temp.path().join("cache")).unwrap();
+    fn test_mtime_tolerance() {
+        let t0 = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+        let t1 = t0 + Duration::from_secs(1);
+        assert!(mtimes_match(t0, t1, Duration::from_secs(2)));
+        assert!(mtimes_match(t1, t0, Duration::from_secs(1)));
+        assert!(!mtimes_match(t0, t1, Duration::ZERO));
+
+        let status = |tolerance: Duration| {
+            let temp = TempDir::new().unwrap();
+            let cache = HashCache::new(temp.path().to_path_buf()).unwrap();
+            ComparisonEngine::new(cache)
+                .with_mtime_tolerance(tolerance)
+                .compare(
+                    Path::new("a"),
+                    Path::new("b"),
+                    vec![entry("f.txt", 3, t0)],
+                    vec![entry("f.txt", 3, t1)],
+                )
+                .unwrap()[0]
+                .status
+        };
+        assert_eq!(status(Duration::from_secs(2)), DiffStatus::Same);
+        assert_eq!(status(Duration::ZERO), DiffStatus::Unchecked);
+    }
 
-        // Create engine with 1MB threshold
-        let engine = ComparisonEngine::new(cache)
-            .with_streaming_threshold(1024 * 1024);
+    #[test]
+    fn test_force_content_check_catches_silent_corruption() {
+        let temp = TempDir::new().unwrap();
+        let left = temp.path().join("left");
+        let right = temp.path().join("right");
+        fs::create_dir_all(&left).unwrap();
+        fs::create_dir_all(&right).unwrap();
+
+        let mut content = vec![0u8; 200 * 1024];
+        let mtime = filetime::FileTime::from_unix_time(1_700_000_000, 0);
+        for root in [&left, &right] {
+            fs::write(root.join("data.bin"), &content).unwrap();
+            filetime::set_file_mtime(root.join("data.bin"), mtime).unwrap();
+        }
 
-        assert_eq!(engine.streaming_threshold, 1024 * 1024);
+        let scanner = FolderScanner::new(AppConfig::default());
+        let status = |engine: &ComparisonEngine| {
+            let left_entries = scanner.scan(&left).unwrap();
+            let right_entries = scanner.scan(&right).unwrap();
+            engine
+                .compare(&left, &right, left_entries, right_entries)
+                .unwrap()[0]
+                .status
+        };
+
+        let engine = ComparisonEngine::new(HashCache::in_memory()).with_hash_verification(true);
+        assert_eq!(status(&engine), DiffStatus::Same);
+
+        // Flip a byte outside the sampled chunks, keeping size and mtime
+        content[30_000] = 1;
+        fs::write(right.join("data.bin"), &content).unwrap();
+        filetime::set_file_mtime(right.join("data.bin"), mtime).unwrap();
+
+        // Metadata matches, and hash verification reuses the cached hashes
+        let metadata_only = ComparisonEngine::new(HashCache::in_memory());
+        assert_eq!(status(&metadata_only), DiffStatus::Same);
+        assert_eq!(status(&engine), DiffStatus::Same);
+
+        let engine = engine.with_force_content_check(true);
+        assert_eq!(status(&engine), DiffStatus::Different);
+        let forced = ComparisonEngine::new(HashCache::in_memory()).with_force_content_check(true);
+        assert_eq!(status(&forced), DiffStatus::Different);
+    }
+
+    #[cfg(unix)]
+    fn mkfifo(path: &Path) {
+        let status = std::process::Command::new("mkfifo")
+            .arg(path)
+            .status()
+            .unwrap();
+        assert!(status.success());
     }
 
+    #[cfg(unix)]
     #[test]
-    fn test_compare_files_uses_streaming_for_large_files() {
-        use rcompare_common::FileEntry;
-        use std::time::SystemTime;
+    fn test_fifos_compare_by_type_without_reading() {
+        use std::os::unix::net::UnixListener;
 
-        let temp = tempfile::tempdir().unwrap();
-        let cache = HashCache::new(temp.path().join("cache")).unwrap();
+        let temp = TempDir::new().unwrap();
+        let left = temp.path().join("left");
+        let right = temp.path().join("right");
+        fs::create_dir_all(&left).unwrap();
+        fs::create_dir_all(&right).unwrap();
+        mkfifo(&left.join("pipe"));
+        mkfifo(&right.join("pipe"));
+        mkfifo(&left.join("swapped"));
+        fs::write(right.join("swapped"), "").unwrap();
+        let _left_socket = UnixListener::bind(left.join("sock")).unwrap();
+        let _right_socket = UnixListener::bind(right.join("sock")).unwrap();
+        mkfifo(&left.join("orphan"));
+
+        let scanner = FolderScanner::new(AppConfig::default());
+        let left_entries = scanner.scan(&left).unwrap();
+        let right_entries = scanner.scan(&right).unwrap();
+        let file_type = |name: &str| {
+            left_entries
+                .iter()
+                .find(|e| e.path == Path::new(name))
+                .unwrap()
+                .file_type
+        };
+        assert_eq!(file_type("pipe"), FileType::Fifo);
+        assert_eq!(file_type("sock"), FileType::Socket);
 
-        // Set threshold to 1MB, enable verification
+        // Reading any of the FIFOs would block: nothing writes to them
+        let engine = ComparisonEngine::new(HashCache::in_memory())
+            .with_force_content_check(true)
+            .with_emptied_file_detection(true);
+        let diff = engine
+            .compare(&left, &right, left_entries.clone(), right_entries.clone())
+            .unwrap();
+        let status = |name: &str| {
+            diff.iter()
+                .find(|node| node.relative_path == Path::new(name))
+                .unwrap()
+                .status
+        };
+        assert_eq!(status("pipe"), DiffStatus::Same);
+        assert_eq!(status("sock"), DiffStatus::Same);
+        assert_eq!(status("swapped"), DiffStatus::TypeConflict);
+        assert_eq!(status("orphan"), DiffStatus::OrphanLeft);
+        assert!(diff.iter().all(|node| node.reason.is_none()));
+
+        let duplicates = engine.find_duplicates(&left, &left_entries, None).unwrap();
+        assert!(duplicates.is_empty());
+        let three_way = engine
+            .compare_three_way(
+                &left,
+                &left,
+                &right,
+                left_entries.clone(),
+                left_entries,
+                right_entries,
+            )
+            .unwrap();
+        let swapped = three_way
+            .iter()
+            .find(|node| node.relative_path == Path::new("swapped"))
+            .unwrap();
+        assert_eq!(swapped.status, ThreeWayDiffStatus::RightChanged);
+    }
+
+    fn write_empty_file_trees(temp: &TempDir) -> (PathBuf, PathBuf) {
+        let left = temp.path().join("left");
+        let right = temp.path().join("right");
+        fs::create_dir_all(&left).unwrap();
+        fs::create_dir_all(&right).unwrap();
+        fs::write(left.join("emptied.log"), "log line\n").unwrap();
+        fs::write(right.join("emptied.log"), "").unwrap();
+        fs::write(left.join("empty.txt"), "").unwrap();
+        fs::write(right.join("empty.txt"), "").unwrap();
+        fs::write(left.join("data.txt"), "same").unwrap();
+        fs::write(right.join("data.txt"), "same").unwrap();
+        (left, right)
+    }
+
+    #[test]
+    fn test_emptied_file_is_flagged() {
+        let temp = TempDir::new().unwrap();
+        let (left, right) = write_empty_file_trees(&temp);
+        let scanner = FolderScanner::new(AppConfig::default());
+        let cache = HashCache::new(temp.path().join("cache")).unwrap();
         let engine = ComparisonEngine::new(cache)
-            .with_streaming_threshold(1024 * 1024)
-            .with_hash_verification(true);
+            .with_emptied_file_detection(true)
+            .with_empty_file_warnings(true);
 
-        let file1 = temp.path().join("file1.dat");
-        let file2 = temp.path().join("file2.dat");
+        let diffs = engine
+            .compare(
+                &left,
+                &right,
+                scanner.scan(&left).unwrap(),
+                scanner.scan(&right).unwrap(),
+            )
+            .unwrap();
+        let node = |name: &str| {
+            diffs
+                .iter()
+                .find(|d| d.relative_path == Path::new(name))
+                .unwrap()
+        };
 
-        // Create identical 2MB files (above threshold)
-        let content = vec![42u8; 2 * 1024 * 1024];
-        std::fs::write(&file1, &content).unwrap();
-        std::fs::write(&file2, &content).unwrap();
+        assert_eq!(node("emptied.log").status, DiffStatus::Different);
+        assert_eq!(node("emptied.log").reason, Some(DiffReason::EmptiedFile));
+        assert_eq!(node("empty.txt").status, DiffStatus::Same);
+        assert_eq!(node("empty.txt").reason, None);
+        assert_eq!(node("data.txt").reason, None);
+    }
 
-        let metadata1 = std::fs::metadata(&file1).unwrap();
-        let metadata2 = std::fs::metadata(&file2).unwrap();
+    #[test]
+    fn test_emptied_file_detection_is_opt_in() {
+        let temp = TempDir::new().unwrap();
+        let (left, right) = write_empty_file_trees(&temp);
+        let scanner = FolderScanner::new(AppConfig::default());
+        let cache = HashCache::new(temp.path().join("cache")).unwrap();
 
-        let entry1 = FileEntry {
-            path: "file1.dat".into(),
-            size: metadata1.len(),
-            modified: metadata1.modified().unwrap_or(SystemTime::UNIX_EPOCH),
-            is_dir: false,
-        };
+        let diffs = ComparisonEngine::new(cache)
+            .compare(
+                &left,
+                &right,
+                scanner.scan(&left).unwrap(),
+                scanner.scan(&right).unwrap(),
+            )
+            .unwrap();
+        assert!(diffs.iter().all(|d| d.reason.is_none()));
+    }
 
-        let entry2 = FileEntry {
-            path: "file2.dat".into(),
-            size: metadata2.len(),
-            modified: metadata2.modified().unwrap_or(SystemTime::UNIX_EPOCH),
-            is_dir: false,
-        };
+    #[test]
+    fn test_file_modified_after_listing_is_compared_again() {
+        let temp = TempDir::new().unwrap();
+        let left = temp.path().join("left");
+        let right = temp.path().join("right");
+        fs::create_dir_all(&left).unwrap();
+        fs::create_dir_all(&right).unwrap();
+        fs::write(left.join("live.log"), "version A\n").unwrap();
+        fs::write(right.join("live.log"), "version B\n").unwrap();
+
+        let scanner = FolderScanner::new(AppConfig::default());
+        let left_entries = scanner.scan(&left).unwrap();
+        let right_entries = scanner.scan(&right).unwrap();
+
+        // The left file is rewritten after it was listed
+        let listed = left_entries[0].modified;
+        fs::write(left.join("live.log"), "version B\n").unwrap();
+        let rewritten = listed + Duration::from_secs(1);
+        filetime::set_file_mtime(
+            left.join("live.log"),
+            filetime::FileTime::from_system_time(rewritten),
+        )
+        .unwrap();
 
-        let status = engine
-            .compare_files(temp.path(), temp.path(), None, None, &entry1, &entry2)
+        let engine = ComparisonEngine::new(HashCache::in_memory()).with_hash_verification(true);
+        let diff = engine
+            .compare(&left, &right, left_entries, right_entries)
             .unwrap();
+        let node = &diff[0];
+        assert_eq!(node.status, DiffStatus::Same);
+        assert_eq!(node.reason, None);
+        assert_eq!(node.left.as_ref().unwrap().modified, rewritten);
+    }
 
-        assert_eq!(status, DiffStatus::Same, "Large identical files should be detected as same using streaming");
+    #[test]
+    fn test_file_removed_after_listing_is_flagged() {
+        let temp = TempDir::new().unwrap();
+        let left = temp.path().join("left");
+        let right = temp.path().join("right");
+        fs::create_dir_all(&left).unwrap();
+        fs::create_dir_all(&right).unwrap();
+        fs::write(left.join("rotated.log"), "entries").unwrap();
+        fs::write(right.join("rotated.log"), "entries").unwrap();
+
+        let scanner = FolderScanner::new(AppConfig::default());
+        let left_entries = scanner.scan(&left).unwrap();
+        let right_entries = scanner.scan(&right).unwrap();
+
+        fs::remove_file(left.join("rotated.log")).unwrap();
+
+        let engine = ComparisonEngine::new(HashCache::in_memory()).with_hash_verification(true);
+        let diff = engine
+            .compare(&left, &right, left_entries, right_entries)
+            .unwrap();
+        assert_eq!(diff[0].status, DiffStatus::Unchecked);
+        assert_eq!(diff[0].reason, Some(DiffReason::ChangedDuringScan));
     }
 }
@@ -0,0 +1,647 @@
+//! Text vs binary content detection.
+//!
+//! [`detect_content_kind`] is the single place where RCompare decides whether
+//! something should be shown as text or as bytes. It combines, in order:
+//!
+//! 1. A byte order mark (UTF-8, UTF-16 LE/BE)
+//! 2. Extension hints for formats that are binary even when their first
+//!    kilobytes happen to be ASCII (PDF, archives, images, ...)
+//! 3. UTF-16 sniffing for BOM-less files, recognised by the pattern of zero
+//!    bytes that ASCII characters leave in every other position
+//! 4. Null-byte detection and UTF-8 validation
+//! 5. A Latin-1 fallback for legacy 8-bit text without control characters
+//!
+//! [`read_text_file`] decodes a file with the detected encoding, so callers
+//! that previously used `fs::read_to_string` also handle UTF-16.
+//!
+//! [`detect_engine`] goes one step further and picks the comparison engine
+//! for a file from its magic bytes and extension; [`route_pair`] does the
+//! same for a left/right pair. Where the signals are ambiguous the structural
+//! engines (JSON, YAML, CSV) win over a plain text diff.
+
+use rcompare_common::RCompareError;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+/// Number of leading bytes inspected when sniffing a file
+pub const SNIFF_LEN: usize = 8192;
+
+/// Character encoding of a text file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextEncoding {
+    Utf8,
+    /// UTF-8 preceded by the `EF BB BF` byte order mark
+    Utf8Bom,
+    Utf16Le,
+    Utf16Be,
+    /// Any 8-bit encoding that is not valid UTF-8, decoded byte-for-byte
+    Latin1,
+}
+
+/// Result of [`detect_content_kind`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentKind {
+    Text(TextEncoding),
+    Binary,
+}
+
+impl ContentKind {
+    pub fn is_text(&self) -> bool {
+        matches!(self, ContentKind::Text(_))
+    }
+}
+
+/// Comparison engine chosen for a file by [`detect_engine`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EngineKind {
+    Image,
+    Csv,
+    Excel,
+    Json,
+    Yaml,
+    Parquet,
+    /// Line-based text diff
+    Text,
+    /// Byte comparison only
+    Binary,
+}
+
+impl EngineKind {
+    /// Name of the engine as used by the `--*-diff` flags and session profiles
+    pub fn name(&self) -> &'static str {
+        match self {
+            EngineKind::Image => "image",
+            EngineKind::Csv => "csv",
+            EngineKind::Excel => "excel",
+            EngineKind::Json => "json",
+            EngineKind::Yaml => "yaml",
+            EngineKind::Parquet => "parquet",
+            EngineKind::Text => "text",
+            EngineKind::Binary => "binary",
+        }
+    }
+
+    /// Whether the engine compares structure (records, cells, keys) rather
+    /// than lines or bytes
+    pub fn is_structural(&self) -> bool {
+        !matches!(self, EngineKind::Text | EngineKind::Binary)
+    }
+}
+
+impl TextEncoding {
+    /// Decode `bytes` (including any BOM) into a string
+    ///
+    /// Fails with [`io::ErrorKind::InvalidData`] if the bytes are not valid
+    /// in this encoding, mirroring `fs::read_to_string`.
+    pub fn decode(&self, bytes: &[u8]) -> io::Result<String> {
+        match self {
+            TextEncoding::Utf8 | TextEncoding::Utf8Bom => {
+                let bytes = bytes.strip_prefix(&UTF8_BOM[..]).unwrap_or(bytes);
+                String::from_utf8(bytes.to_vec())
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+            }
+            TextEncoding::Utf16Le | TextEncoding::Utf16Be => {
+                let little_endian = *self == TextEncoding::Utf16Le;
+                let bom = if little_endian {
+                    UTF16LE_BOM
+                } else {
+                    UTF16BE_BOM
+                };
+                let bytes = bytes.strip_prefix(&bom[..]).unwrap_or(bytes);
+                if bytes.len() % 2 != 0 {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "UTF-16 data has an odd number of bytes",
+                    ));
+                }
+                char::decode_utf16(utf16_units(bytes, little_endian))
+                    .collect::<Result<String, _>>()
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+            }
+            TextEncoding::Latin1 => Ok(bytes.iter().map(|&b| b as char).collect()),
+        }
+    }
+}
+
+const UTF8_BOM: [u8; 3] = [0xEF, 0xBB, 0xBF];
+const UTF16LE_BOM: [u8; 2] = [0xFF, 0xFE];
+const UTF16BE_BOM: [u8; 2] = [0xFE, 0xFF];
+
+/// Extensions that are treated as binary whatever their leading bytes look like
+const BINARY_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "gif", "bmp", "ico", "tif", "tiff", "webp", "heic", "heif", "avif",
+    "pdf", "zip", "gz", "tgz", "bz2", "xz", "zst", "7z", "rar", "tar", "jar", "exe", "dll", "so",
+    "dylib", "o", "a", "lib", "class", "wasm", "bin", "dat", "db", "sqlite", "parquet", "xlsx",
+    "xls", "docx", "pptx", "mp3", "mp4", "mkv", "avi", "mov", "wav", "flac", "ogg", "ttf", "otf",
+    "woff", "woff2",
+];
+
+/// Extensions whose non-UTF-8 content is still decoded as Latin-1 text
+const TEXT_EXTENSIONS: &[&str] = &[
+    "txt",
+    "md",
+    "markdown",
+    "rst",
+    "log",
+    "rs",
+    "toml",
+    "yaml",
+    "yml",
+    "json",
+    "xml",
+    "html",
+    "htm",
+    "css",
+    "js",
+    "ts",
+    "tsx",
+    "jsx",
+    "c",
+    "cpp",
+    "cc",
+    "cxx",
+    "h",
+    "hpp",
+    "hxx",
+    "cs",
+    "java",
+    "py",
+    "rb",
+    "go",
+    "php",
+    "pl",
+    "sh",
+    "bash",
+    "zsh",
+    "fish",
+    "sql",
+    "conf",
+    "cfg",
+    "ini",
+    "properties",
+    "cmake",
+    "make",
+    "dockerfile",
+    "gitignore",
+    "gitattributes",
+    "csv",
+    "tsv",
+];
+
+/// Classify `bytes` (typically the first [`SNIFF_LEN`] bytes of a file)
+///
+/// `path_hint` supplies the file name for extension hints; pass `None` when
+/// classifying in-memory data.
+pub fn detect_content_kind(bytes: &[u8], path_hint: Option<&Path>) -> ContentKind {
+    if bytes.starts_with(&UTF8_BOM) {
+        return ContentKind::Text(TextEncoding::Utf8Bom);
+    }
+    if bytes.starts_with(&UTF16LE_BOM) {
+        return ContentKind::Text(TextEncoding::Utf16Le);
+    }
+    if bytes.starts_with(&UTF16BE_BOM) {
+        return ContentKind::Text(TextEncoding::Utf16Be);
+    }
+
+    let extension = path_hint
+        .and_then(|path| path.extension())
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase());
+    let has_extension = |list: &[&str]| extension.as_deref().is_some_and(|ext| list.contains(&ext));
+
+    if has_extension(BINARY_EXTENSIONS) {
+        return ContentKind::Binary;
+    }
+
+    if let Some(encoding) = sniff_utf16(bytes) {
+        return ContentKind::Text(encoding);
+    }
+
+    if bytes.contains(&0) {
+        return ContentKind::Binary;
+    }
+
+    match std::str::from_utf8(bytes) {
+        Ok(_) => return ContentKind::Text(TextEncoding::Utf8),
+        // The sample may end in the middle of a multi-byte character
+        Err(e) if e.error_len().is_none() => return ContentKind::Text(TextEncoding::Utf8),
+        Err(_) => {}
+    }
+
+    if has_extension(TEXT_EXTENSIONS) || control_byte_ratio(bytes) < 0.01 {
+        ContentKind::Text(TextEncoding::Latin1)
+    } else {
+        ContentKind::Binary
+    }
+}
+
+/// Classify a file by sniffing its first [`SNIFF_LEN`] bytes
+pub fn detect_file_content_kind(path: &Path) -> io::Result<ContentKind> {
+    let mut file = File::open(path)?;
+    let mut buffer = Vec::with_capacity(SNIFF_LEN);
+    file.by_ref()
+        .take(SNIFF_LEN as u64)
+        .read_to_end(&mut buffer)?;
+    Ok(detect_content_kind(&buffer, Some(path)))
+}
+
+/// Pick the comparison engine for `bytes` (typically the first
+/// [`SNIFF_LEN`] bytes of a file)
+///
+/// Magic bytes decide first, so a PNG named `.json` is still an image.
+/// Container formats shared with other documents (ZIP, OLE) only count as
+/// Excel when the extension says so. Text is routed by extension, and text
+/// with an unknown extension goes to the JSON or YAML engine when it looks
+/// like a complete document of that kind.
+pub fn detect_engine(bytes: &[u8], path_hint: Option<&Path>) -> EngineKind {
+    let extension = path_hint
+        .and_then(|path| path.extension())
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_ascii_lowercase());
+    let extension = extension.as_deref().unwrap_or("");
+
+    if let Some(engine) = sniff_magic(bytes, extension) {
+        return engine;
+    }
+    if !detect_content_kind(bytes, path_hint).is_text() {
+        return EngineKind::Binary;
+    }
+
+    match extension {
+        "json" | "jsonc" | "json5" => EngineKind::Json,
+        "yaml" | "yml" => EngineKind::Yaml,
+        "csv" | "tsv" => EngineKind::Csv,
+        _ => sniff_structured_text(bytes).unwrap_or(EngineKind::Text),
+    }
+}
+
+/// Pick the comparison engine for a file by sniffing its first
+/// [`SNIFF_LEN`] bytes
+pub fn detect_file_engine(path: &Path) -> io::Result<EngineKind> {
+    let mut file = File::open(path)?;
+    let mut buffer = Vec::with_capacity(SNIFF_LEN);
+    file.by_ref()
+        .take(SNIFF_LEN as u64)
+        .read_to_end(&mut buffer)?;
+    Ok(detect_engine(&buffer, Some(path)))
+}
+
+/// Pick one comparison engine for a left/right pair of files
+///
+/// When the two sides disagree, a structural engine is preferred over a
+/// text diff (a `.json` compared with a `.txt` holding JSON is still a JSON
+/// comparison) and YAML is used for JSON against YAML, since YAML reads
+/// both. Any other disagreement falls back to a text diff when both sides
+/// are text, and to a byte comparison otherwise.
+pub fn route_pair(left: &Path, right: &Path) -> io::Result<EngineKind> {
+    Ok(route_engines(
+        detect_file_engine(left)?,
+        detect_file_engine(right)?,
+    ))
+}
+
+/// Pick one comparison engine for a pair whose sides were classified
+/// separately, by the rules of [`route_pair`]
+///
+/// Use this when a side is not a file, e.g. data read from stdin and
+/// classified with [`detect_engine`].
+pub fn route_engines(left: EngineKind, right: EngineKind) -> EngineKind {
+    match (left, right) {
+        _ if left == right => left,
+        (EngineKind::Json, EngineKind::Yaml) | (EngineKind::Yaml, EngineKind::Json) => {
+            EngineKind::Yaml
+        }
+        (engine, EngineKind::Text) | (EngineKind::Text, engine) if is_text_based(engine) => engine,
+        _ if is_text_based(left) && is_text_based(right) => EngineKind::Text,
+        _ => EngineKind::Binary,
+    }
+}
+
+/// Engines whose input is text, so they can stand in for each other
+fn is_text_based(engine: EngineKind) -> bool {
+    matches!(
+        engine,
+        EngineKind::Text | EngineKind::Json | EngineKind::Yaml | EngineKind::Csv
+    )
+}
+
+/// Recognise binary formats by their leading bytes
+fn sniff_magic(bytes: &[u8], extension: &str) -> Option<EngineKind> {
+    const IMAGE_MAGIC: &[&[u8]] = &[
+        b"\x89PNG\r\n\x1a\n",
+        b"\xFF\xD8\xFF",
+        b"GIF87a",
+        b"GIF89a",
+        b"II*\0",
+        b"MM\0*",
+        b"\0\0\x01\0",
+    ];
+    const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+    const OLE_MAGIC: &[u8] = b"\xD0\xCF\x11\xE0\xA1\xB1\x1A\xE1";
+
+    let is_excel = matches!(extension, "xlsx" | "xls" | "xlsm" | "xlsb");
+    if IMAGE_MAGIC.iter().any(|magic| bytes.starts_with(magic))
+        || (bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(&b"WEBP"[..]))
+        || (bytes.get(4..8) == Some(&b"ftyp"[..])
+            && matches!(
+                bytes.get(8..12),
+                Some(b"heic" | b"heix" | b"mif1" | b"avif" | b"avis")
+            ))
+        || (bytes.starts_with(b"BM") && extension == "bmp")
+    {
+        Some(EngineKind::Image)
+    } else if bytes.starts_with(b"PAR1") {
+        Some(EngineKind::Parquet)
+    } else if bytes.starts_with(ZIP_MAGIC) || bytes.starts_with(OLE_MAGIC) {
+        Some(if is_excel {
+            EngineKind::Excel
+        } else {
+            EngineKind::Binary
+        })
+    } else {
+        None
+    }
+}
+
+/// Recognise JSON and YAML documents in text without a telling extension
+fn sniff_structured_text(bytes: &[u8]) -> Option<EngineKind> {
+    let text = std::str::from_utf8(bytes).ok()?.trim();
+    if text.starts_with("---") || text.starts_with("%YAML") {
+        return Some(EngineKind::Yaml);
+    }
+    // A sample cut at SNIFF_LEN never parses, so only whole files qualify
+    let is_document = (text.starts_with('{') && text.ends_with('}'))
+        || (text.starts_with('[') && text.ends_with(']'));
+    (is_document && serde_json::from_str::<serde_json::Value>(text).is_ok())
+        .then_some(EngineKind::Json)
+}
+
+/// Read a whole text file, decoding it with the detected encoding
+///
+/// Binary files are rejected with [`io::ErrorKind::InvalidData`], like
+/// `fs::read_to_string` does for non-UTF-8 input.
+pub fn read_text_file(path: &Path) -> Result<String, RCompareError> {
+    let bytes = std::fs::read(path)?;
+    decode_text(&bytes, Some(path))
+}
+
+/// Decode in-memory data as text with the detected encoding
+///
+/// `path_hint` supplies the file name for extension hints, as in
+/// [`detect_content_kind`]. Binary data is rejected like in
+/// [`read_text_file`].
+pub fn decode_text(bytes: &[u8], path_hint: Option<&Path>) -> Result<String, RCompareError> {
+    let sample = &bytes[..bytes.len().min(SNIFF_LEN)];
+    match detect_content_kind(sample, path_hint) {
+        ContentKind::Text(encoding) => Ok(encoding.decode(bytes)?),
+        ContentKind::Binary => Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            match path_hint {
+                Some(path) => format!("{} does not look like a text file", path.display()),
+                None => "Input does not look like text".to_string(),
+            },
+        )
+        .into()),
+    }
+}
+
+fn utf16_units(bytes: &[u8], little_endian: bool) -> impl Iterator<Item = u16> + '_ {
+    bytes.chunks_exact(2).map(move |pair| {
+        if little_endian {
+            u16::from_le_bytes([pair[0], pair[1]])
+        } else {
+            u16::from_be_bytes([pair[0], pair[1]])
+        }
+    })
+}
+
+/// Recognise BOM-less UTF-16 by its zero bytes: ASCII text leaves a zero in
+/// every high byte, so nearly all zeros sit on one parity of offsets
+fn sniff_utf16(bytes: &[u8]) -> Option<TextEncoding> {
+    // Ignore a trailing odd byte cut off by the sample length
+    let bytes = &bytes[..bytes.len() & !1];
+    if bytes.len() < 4 {
+        return None;
+    }
+
+    let pairs = bytes.len() / 2;
+    let zeros_even = bytes.iter().step_by(2).filter(|&&b| b == 0).count();
+    let zeros_odd = bytes.iter().skip(1).step_by(2).filter(|&&b| b == 0).count();
+
+    let little_endian = if zeros_odd * 2 >= pairs && zeros_even * 20 <= pairs {
+        true
+    } else if zeros_even * 2 >= pairs && zeros_odd * 20 <= pairs {
+        false
+    } else {
+        return None;
+    };
+
+    // The sample may end between the two halves of a surrogate pair
+    let mut decoded = char::decode_utf16(utf16_units(bytes, little_endian)).peekable();
+    while let Some(unit) = decoded.next() {
+        match unit {
+            Ok(c) if c.is_control() && !c.is_whitespace() => return None,
+            Ok(_) => {}
+            Err(_) if decoded.peek().is_none() => {}
+            Err(_) => return None,
+        }
+    }
+
+    Some(if little_endian {
+        TextEncoding::Utf16Le
+    } else {
+        TextEncoding::Utf16Be
+    })
+}
+
+/// Fraction of bytes that are control characters not found in ordinary text
+fn control_byte_ratio(bytes: &[u8]) -> f64 {
+    if bytes.is_empty() {
+        return 0.0;
+    }
+    let controls = bytes
+        .iter()
+        .filter(|&&b| (b < 0x20 && !matches!(b, b'\t' | b'\n' | b'\r' | 0x0C | 0x1B)) || b == 0x7F)
+        .count();
+    controls as f64 / bytes.len() as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const PNG_HEADER: &[u8] = &[
+        0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, 0x00, 0x00, 0x00, 0x0D, 0x49, 0x48, 0x44,
+        0x52, 0x00, 0x00, 0x00, 0x10, 0x00, 0x00, 0x00, 0x10, 0x08, 0x06, 0x00, 0x00, 0x00, 0x1F,
+        0xF3, 0xFF, 0x61,
+    ];
+
+    fn utf16(text: &str, little_endian: bool, bom: bool) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        if bom {
+            bytes.extend_from_slice(if little_endian {
+                &UTF16LE_BOM
+            } else {
+                &UTF16BE_BOM
+            });
+        }
+        for unit in text.encode_utf16() {
+            bytes.extend_from_slice(&if little_endian {
+                unit.to_le_bytes()
+            } else {
+                unit.to_be_bytes()
+            });
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_utf8_text() {
+        assert_eq!(
+            detect_content_kind("héllo wörld\n".as_bytes(), None),
+            ContentKind::Text(TextEncoding::Utf8)
+        );
+        assert_eq!(
+            detect_content_kind(b"\xEF\xBB\xBFbom\n", None),
+            ContentKind::Text(TextEncoding::Utf8Bom)
+        );
+        assert_eq!(
+            detect_content_kind(b"", None),
+            ContentKind::Text(TextEncoding::Utf8)
+        );
+    }
+
+    #[test]
+    fn test_utf8_sample_cut_mid_character() {
+        let text = "ä".repeat(10);
+        let cut = &text.as_bytes()[..text.len() - 1];
+        assert_eq!(
+            detect_content_kind(cut, None),
+            ContentKind::Text(TextEncoding::Utf8)
+        );
+    }
+
+    #[test]
+    fn test_utf16_with_and_without_bom() {
+        let text = "fn main() {\n    println!(\"hi\");\n}\n";
+
+        assert_eq!(
+            detect_content_kind(&utf16(text, true, true), None),
+            ContentKind::Text(TextEncoding::Utf16Le)
+        );
+        assert_eq!(
+            detect_content_kind(&utf16(text, false, true), None),
+            ContentKind::Text(TextEncoding::Utf16Be)
+        );
+        // Full of null bytes, but still text
+        assert_eq!(
+            detect_content_kind(&utf16(text, true, false), None),
+            ContentKind::Text(TextEncoding::Utf16Le)
+        );
+        assert_eq!(
+            detect_content_kind(&utf16(text, false, false), None),
+            ContentKind::Text(TextEncoding::Utf16Be)
+        );
+    }
+
+    #[test]
+    fn test_png_is_binary() {
+        assert_eq!(detect_content_kind(PNG_HEADER, None), ContentKind::Binary);
+        assert_eq!(
+            detect_content_kind(PNG_HEADER, Some(Path::new("image.png"))),
+            ContentKind::Binary
+        );
+    }
+
+    #[test]
+    fn test_extension_hints() {
+        // PDFs often start with plenty of plain ASCII
+        assert_eq!(
+            detect_content_kind(b"%PDF-1.7\n%comment\n", Some(Path::new("doc.pdf"))),
+            ContentKind::Binary
+        );
+        // Latin-1 with a few control bytes is still text when named like text
+        let latin1 = b"caf\xE9\x01\x02 cr\xE8me\n";
+        assert_eq!(detect_content_kind(latin1, None), ContentKind::Binary);
+        assert_eq!(
+            detect_content_kind(latin1, Some(Path::new("menu.txt"))),
+            ContentKind::Text(TextEncoding::Latin1)
+        );
+        assert_eq!(
+            detect_content_kind(b"caf\xE9 cr\xE8me\n", None),
+            ContentKind::Text(TextEncoding::Latin1)
+        );
+    }
+
+    #[test]
+    fn test_read_text_file_decodes_utf16() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("notes.txt");
+        std::fs::write(&path, utf16("line one\nline two\n", true, true)).unwrap();
+
+        assert_eq!(
+            detect_file_content_kind(&path).unwrap(),
+            ContentKind::Text(TextEncoding::Utf16Le)
+        );
+        assert_eq!(read_text_file(&path).unwrap(), "line one\nline two\n");
+
+        let png = temp.path().join("image.png");
+        std::fs::write(&png, PNG_HEADER).unwrap();
+        assert!(read_text_file(&png).is_err());
+    }
+
+    #[test]
+    fn test_detect_engine_uses_magic_then_extension() {
+        let engine = |bytes: &[u8], name: &str| detect_engine(bytes, Some(Path::new(name)));
+
+        assert_eq!(engine(PNG_HEADER, "logo.png"), EngineKind::Image);
+        // Magic bytes beat a misleading extension
+        assert_eq!(engine(PNG_HEADER, "logo.json"), EngineKind::Image);
+        assert_eq!(engine(b"PAR1\x15\x04", "table"), EngineKind::Parquet);
+        assert_eq!(engine(b"PK\x03\x04rest", "book.xlsx"), EngineKind::Excel);
+        assert_eq!(engine(b"PK\x03\x04rest", "bundle.zip"), EngineKind::Binary);
+
+        assert_eq!(engine(b"a,b\n1,2\n", "data.csv"), EngineKind::Csv);
+        assert_eq!(engine(b"key: value\n", "config.yml"), EngineKind::Yaml);
+        assert_eq!(engine(b"{\"a\": 1}", "data.json"), EngineKind::Json);
+        // Structure wins over text when the extension says nothing
+        assert_eq!(engine(b"{\"a\": 1}\n", "data.txt"), EngineKind::Json);
+        assert_eq!(engine(b"---\nkey: value\n", "config"), EngineKind::Yaml);
+        assert_eq!(engine(b"{ not json }", "notes.txt"), EngineKind::Text);
+        assert_eq!(engine(b"plain words\n", "notes.txt"), EngineKind::Text);
+        assert_eq!(engine(b"\x00\x01\x02\x03", "blob"), EngineKind::Binary);
+    }
+
+    #[test]
+    fn test_route_pair_prefers_structural_engines() {
+        let temp = TempDir::new().unwrap();
+        let write = |name: &str, bytes: &[u8]| {
+            let path = temp.path().join(name);
+            std::fs::write(&path, bytes).unwrap();
+            path
+        };
+        let json = write("a.json", b"{\"a\": 1}");
+        let text = write("a.txt", b"{\"a\": 2");
+        let yaml = write("a.yaml", b"a: 1\n");
+        let csv = write("a.csv", b"a\n1\n");
+        let png = write("a.png", PNG_HEADER);
+
+        assert_eq!(route_pair(&json, &json).unwrap(), EngineKind::Json);
+        assert_eq!(route_pair(&json, &text).unwrap(), EngineKind::Json);
+        assert_eq!(route_pair(&text, &json).unwrap(), EngineKind::Json);
+        assert_eq!(route_pair(&json, &yaml).unwrap(), EngineKind::Yaml);
+        assert_eq!(route_pair(&json, &csv).unwrap(), EngineKind::Text);
+        assert_eq!(route_pair(&png, &json).unwrap(), EngineKind::Binary);
+        assert!(route_pair(&json, &temp.path().join("missing")).is_err());
+
+        // In-memory data without a name is classified by content alone
+        let stdin = detect_engine(b"{\"a\": 3}", None);
+        assert_eq!(stdin, EngineKind::Json);
+        assert_eq!(
+            route_engines(stdin, detect_file_engine(&yaml).unwrap()),
+            EngineKind::Yaml
+        );
+    }
+}
@@ -1,8 +1,14 @@
-use csv::{Reader, StringRecord};
+use crate::cancel::Monitor;
+use csv::{Reader, StringRecord, StringRecordsIntoIter, Writer};
 use rcompare_common::RCompareError;
 use serde::Serialize;
-use std::collections::HashMap;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap};
+use std::fs::File;
+use std::io::{Seek, SeekFrom};
 use std::path::Path;
+use std::sync::atomic::AtomicBool;
+use tracing::debug;
 
 /// Result of a CSV comparison
 #[derive(Debug, Clone, Serialize)]
@@ -23,8 +29,11 @@ pub struct CsvDiffResult {
     pub left_headers: Vec<String>,
     /// Right headers
     pub right_headers: Vec<String>,
-    /// Detailed row differences (limited to first 100)
+    /// Detailed row differences, at most
+    /// [`with_max_detail`](CsvDiffEngine::with_max_detail) of them
     pub row_diffs: Vec<RowDiff>,
+    /// `row_diffs` stopped at the cap; the row counts still cover every row
+    pub truncated: bool,
 }
 
 /// Represents a difference in a specific row
@@ -71,18 +80,34 @@ pub enum CsvCompareMode {
 }
 
 /// Engine for comparing CSV files
+///
+/// [`compare_files`](Self::compare_files) reads both files into memory.
+/// For files too large for that, [`compare_files_streaming`](Self::compare_files_streaming)
+/// produces the same result with memory bounded by
+/// [`with_sort_buffer_rows`](Self::with_sort_buffer_rows). Both have a
+/// `_with_cancel` variant that stops between rows once a flag is set and
+/// reports how many bytes of the two inputs have been read.
 pub struct CsvDiffEngine {
     mode: CsvCompareMode,
     key_columns: Vec<String>,
-    max_row_diffs: usize,
+    max_detail: usize,
+    sort_buffer_rows: usize,
 }
 
+/// Maximum number of sorted runs merged at once; more runs are first merged
+/// in groups, keeping the number of open temp files bounded
+const MAX_MERGE_FAN_IN: usize = 64;
+
 impl CsvDiffEngine {
+    /// Default number of rows sorted in memory before spilling to disk
+    const DEFAULT_SORT_BUFFER_ROWS: usize = 100_000;
+
     pub fn new() -> Self {
         Self {
             mode: CsvCompareMode::default(),
             key_columns: vec![],
-            max_row_diffs: 100,
+            max_detail: 100,
+            sort_buffer_rows: Self::DEFAULT_SORT_BUFFER_ROWS,
         }
     }
 
@@ -99,52 +124,55 @@ impl CsvDiffEngine {
         self
     }
 
-    pub fn with_max_row_diffs(mut self, max: usize) -> Self {
-        self.max_row_diffs = max;
+    /// Keep details for at most `max` differing rows (default 100)
+    ///
+    /// Rows past the cap are still counted, so memory stays bounded on
+    /// files with millions of differences.
+    pub fn with_max_detail(mut self, max: usize) -> Self {
+        self.max_detail = max;
+        self
+    }
+
+    #[deprecated(note = "renamed to `with_max_detail`")]
+    pub fn with_max_row_diffs(self, max: usize) -> Self {
+        self.with_max_detail(max)
+    }
+
+    /// Set how many rows per file the streaming keyed comparison sorts in
+    /// memory before spilling a sorted run to a temp file (default 100,000)
+    pub fn with_sort_buffer_rows(mut self, rows: usize) -> Self {
+        self.sort_buffer_rows = rows.max(1);
         self
     }
 
     /// Compare two CSV files
     pub fn compare_files(&self, left: &Path, right: &Path) -> Result<CsvDiffResult, RCompareError> {
-        let mut left_reader = Reader::from_path(left).map_err(|e| {
-            RCompareError::Io(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                format!("Failed to open left CSV file: {}", e),
-            ))
-        })?;
-
-        let mut right_reader = Reader::from_path(right).map_err(|e| {
-            RCompareError::Io(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                format!("Failed to open right CSV file: {}", e),
-            ))
-        })?;
+        self.compare(left, right, Monitor::default())
+    }
 
-        let left_headers = left_reader
-            .headers()
-            .map_err(|e| {
-                RCompareError::Io(std::io::Error::new(
-                    std::io::ErrorKind::InvalidData,
-                    format!("Failed to read left CSV headers: {}", e),
-                ))
-            })?
-            .iter()
-            .map(|s| s.to_string())
-            .collect::<Vec<_>>();
-
-        let right_headers = right_reader
-            .headers()
-            .map_err(|e| {
-                RCompareError::Io(std::io::Error::new(
-                    std::io::ErrorKind::InvalidData,
-                    format!("Failed to read right CSV headers: {}", e),
-                ))
-            })?
-            .iter()
-            .map(|s| s.to_string())
-            .collect::<Vec<_>>();
+    /// Compare two CSV files, stopping with an error once `cancel` is set
+    ///
+    /// `progress` receives `(bytes read, combined size of both files)`.
+    pub fn compare_files_with_cancel(
+        &self,
+        left: &Path,
+        right: &Path,
+        cancel: &AtomicBool,
+        progress: Option<&dyn Fn(usize, usize)>,
+    ) -> Result<CsvDiffResult, RCompareError> {
+        self.compare(left, right, Monitor::new(cancel, progress))
+    }
 
+    fn compare(
+        &self,
+        left: &Path,
+        right: &Path,
+        monitor: Monitor,
+    ) -> Result<CsvDiffResult, RCompareError> {
+        let (left_reader, left_headers) = open_csv(left, "left")?;
+        let (right_reader, right_headers) = open_csv(right, "right")?;
         let headers_match = left_headers == right_headers;
+        let mut input = InputProgress::new(monitor, left, right);
 
         match self.mode {
             CsvCompareMode::RowByRow => self.compare_row_by_row(
@@ -153,6 +181,7 @@ impl CsvDiffEngine {
                 left_reader,
                 right_reader,
                 headers_match,
+                &mut input,
             ),
             CsvCompareMode::ByKey => self.compare_by_key(
                 &left_headers,
@@ -160,118 +189,215 @@ impl CsvDiffEngine {
                 left_reader,
                 right_reader,
                 headers_match,
+                &mut input,
             ),
         }
     }
 
-    fn compare_row_by_row(
+    /// Compare two CSV files without loading them into memory
+    ///
+    /// Produces the same [`CsvDiffResult`] as [`compare_files`](Self::compare_files).
+    /// Row-by-row mode reads both files in lockstep. Keyed mode external-sorts
+    /// each file by its key columns, spilling sorted runs of at most
+    /// `sort_buffer_rows` rows to temp files, then merge-joins the two sorted
+    /// streams. As in the in-memory path, the last row wins for duplicate keys.
+    pub fn compare_files_streaming(
         &self,
-        left_headers: &[String],
-        right_headers: &[String],
-        mut left_reader: Reader<std::fs::File>,
-        mut right_reader: Reader<std::fs::File>,
-        headers_match: bool,
+        left: &Path,
+        right: &Path,
     ) -> Result<CsvDiffResult, RCompareError> {
-        let mut different_rows = 0;
-        let mut left_only_rows = 0;
-        let mut right_only_rows = 0;
-        let mut identical_rows = 0;
-        let mut row_diffs = Vec::new();
-
-        let left_records: Vec<StringRecord> = left_reader
-            .records()
-            .collect::<Result<Vec<_>, _>>()
-            .map_err(|e| {
-                RCompareError::Io(std::io::Error::new(
-                    std::io::ErrorKind::InvalidData,
-                    format!("Failed to read left CSV records: {}", e),
-                ))
-            })?;
-
-        let right_records: Vec<StringRecord> = right_reader
-            .records()
-            .collect::<Result<Vec<_>, _>>()
-            .map_err(|e| {
-                RCompareError::Io(std::io::Error::new(
-                    std::io::ErrorKind::InvalidData,
-                    format!("Failed to read right CSV records: {}", e),
-                ))
-            })?;
-
-        let max_rows = left_records.len().max(right_records.len());
-        let total_rows = max_rows;
-
-        for i in 0..max_rows {
-            let row_num = i + 1;
-            let left_row = left_records.get(i);
-            let right_row = right_records.get(i);
-
-            match (left_row, right_row) {
-                (Some(left), Some(right)) => {
-                    if left == right {
-                        identical_rows += 1;
-                    } else {
-                        different_rows += 1;
-                        if row_diffs.len() < self.max_row_diffs {
-                            let column_diffs = self.find_column_diffs(left_headers, left, right);
-                            row_diffs.push(RowDiff {
-                                row_num,
-                                diff_type: RowDiffType::Modified,
-                                column_diffs,
-                            });
-                        }
-                    }
-                }
-                (Some(_), None) => {
-                    left_only_rows += 1;
-                    if row_diffs.len() < self.max_row_diffs {
-                        row_diffs.push(RowDiff {
-                            row_num,
-                            diff_type: RowDiffType::LeftOnly,
-                            column_diffs: vec![],
-                        });
+        self.compare_streaming(left, right, Monitor::default())
+    }
+
+    /// [`compare_files_streaming`](Self::compare_files_streaming), stopping
+    /// with an error once `cancel` is set
+    ///
+    /// `progress` receives `(bytes read, combined size of both files)`; in
+    /// keyed mode it reaches the total once both files are sorted, before the
+    /// merge-join.
+    pub fn compare_files_streaming_with_cancel(
+        &self,
+        left: &Path,
+        right: &Path,
+        cancel: &AtomicBool,
+        progress: Option<&dyn Fn(usize, usize)>,
+    ) -> Result<CsvDiffResult, RCompareError> {
+        self.compare_streaming(left, right, Monitor::new(cancel, progress))
+    }
+
+    fn compare_streaming(
+        &self,
+        left: &Path,
+        right: &Path,
+        monitor: Monitor,
+    ) -> Result<CsvDiffResult, RCompareError> {
+        let (mut left_reader, left_headers) = open_csv(left, "left")?;
+        let (mut right_reader, right_headers) = open_csv(right, "right")?;
+        let headers_match = left_headers == right_headers;
+        let mut input = InputProgress::new(monitor, left, right);
+        let mut tally = DiffTally::default();
+
+        let total_rows = match self.mode {
+            CsvCompareMode::RowByRow => {
+                let mut left_records = left_reader.records();
+                let mut right_records = right_reader.records();
+                let mut row_num = 0;
+                loop {
+                    let left_row = next_record(&mut left_records, "left")?;
+                    let right_row = next_record(&mut right_records, "right")?;
+                    if left_row.is_none() && right_row.is_none() {
+                        break;
                     }
+                    input.advance("left", left_records.reader())?;
+                    input.advance("right", right_records.reader())?;
+                    row_num += 1;
+                    self.tally_row(
+                        &mut tally,
+                        &left_headers,
+                        row_num,
+                        left_row.as_ref(),
+                        right_row.as_ref(),
+                    );
                 }
-                (None, Some(_)) => {
-                    right_only_rows += 1;
-                    if row_diffs.len() < self.max_row_diffs {
-                        row_diffs.push(RowDiff {
-                            row_num,
-                            diff_type: RowDiffType::RightOnly,
-                            column_diffs: vec![],
-                        });
+                row_num
+            }
+            CsvCompareMode::ByKey => {
+                let key_indices = self.key_indices(&left_headers)?;
+                let left_runs =
+                    self.spill_sorted_runs(&mut left_reader, &key_indices, "left", &mut input)?;
+                let right_runs =
+                    self.spill_sorted_runs(&mut right_reader, &key_indices, "right", &mut input)?;
+                let mut left_rows = UniqueRows::new(RunMerger::new(left_runs)?);
+                let mut right_rows = UniqueRows::new(RunMerger::new(right_runs)?);
+
+                let mut left_row = left_rows.next()?;
+                let mut right_row = right_rows.next()?;
+                let mut row_num = 0;
+                loop {
+                    let ordering = match (&left_row, &right_row) {
+                        (Some((left_key, _)), Some((right_key, _))) => left_key.cmp(right_key),
+                        (Some(_), None) => Ordering::Less,
+                        (None, Some(_)) => Ordering::Greater,
+                        (None, None) => break,
+                    };
+                    input.check()?;
+                    row_num += 1;
+                    let left_record = left_row.as_ref().map(|(_, record)| record);
+                    let right_record = right_row.as_ref().map(|(_, record)| record);
+                    match ordering {
+                        Ordering::Less => {
+                            self.tally_row(&mut tally, &left_headers, row_num, left_record, None);
+                            left_row = left_rows.next()?;
+                        }
+                        Ordering::Greater => {
+                            self.tally_row(&mut tally, &left_headers, row_num, None, right_record);
+                            right_row = right_rows.next()?;
+                        }
+                        Ordering::Equal => {
+                            self.tally_row(
+                                &mut tally,
+                                &left_headers,
+                                row_num,
+                                left_record,
+                                right_record,
+                            );
+                            left_row = left_rows.next()?;
+                            right_row = right_rows.next()?;
+                        }
                     }
                 }
-                (None, None) => unreachable!(),
+                left_rows.count.max(right_rows.count)
             }
+        };
+
+        Ok(tally.finish(total_rows, headers_match, &left_headers, &right_headers))
+    }
+
+    fn compare_row_by_row(
+        &self,
+        left_headers: &[String],
+        right_headers: &[String],
+        mut left_reader: Reader<File>,
+        mut right_reader: Reader<File>,
+        headers_match: bool,
+        input: &mut InputProgress,
+    ) -> Result<CsvDiffResult, RCompareError> {
+        let mut tally = DiffTally::default();
+
+        let mut left_records = Vec::new();
+        input.read_all(&mut left_reader, "left", |record| left_records.push(record))?;
+
+        let mut right_records = Vec::new();
+        input.read_all(&mut right_reader, "right", |record| {
+            right_records.push(record)
+        })?;
+
+        let total_rows = left_records.len().max(right_records.len());
+
+        for i in 0..total_rows {
+            input.check()?;
+            self.tally_row(
+                &mut tally,
+                left_headers,
+                i + 1,
+                left_records.get(i),
+                right_records.get(i),
+            );
         }
 
-        Ok(CsvDiffResult {
-            total_rows,
-            different_rows,
-            left_only_rows,
-            right_only_rows,
-            identical_rows,
-            headers_match,
-            left_headers: left_headers.to_vec(),
-            right_headers: right_headers.to_vec(),
-            row_diffs,
-        })
+        Ok(tally.finish(total_rows, headers_match, left_headers, right_headers))
     }
 
     fn compare_by_key(
         &self,
         left_headers: &[String],
         right_headers: &[String],
-        mut left_reader: Reader<std::fs::File>,
-        mut right_reader: Reader<std::fs::File>,
+        mut left_reader: Reader<File>,
+        mut right_reader: Reader<File>,
         headers_match: bool,
+        input: &mut InputProgress,
     ) -> Result<CsvDiffResult, RCompareError> {
-        // Get key column indices
+        let key_indices = self.key_indices(left_headers)?;
+
+        // Build hash maps keyed by the key column(s)
+        let mut left_map: HashMap<String, StringRecord> = HashMap::new();
+        input.read_all(&mut left_reader, "left", |record| {
+            left_map.insert(self.build_key(&record, &key_indices), record);
+        })?;
+
+        let mut right_map: HashMap<String, StringRecord> = HashMap::new();
+        input.read_all(&mut right_reader, "right", |record| {
+            right_map.insert(self.build_key(&record, &key_indices), record);
+        })?;
+
+        let total_rows = left_map.len().max(right_map.len());
+        let mut tally = DiffTally::default();
+
+        // Collect all unique keys
+        let mut all_keys: Vec<String> = left_map.keys().chain(right_map.keys()).cloned().collect();
+        all_keys.sort();
+        all_keys.dedup();
+
+        for (idx, key) in all_keys.iter().enumerate() {
+            input.check()?;
+            self.tally_row(
+                &mut tally,
+                left_headers,
+                idx + 1,
+                left_map.get(key),
+                right_map.get(key),
+            );
+        }
+
+        Ok(tally.finish(total_rows, headers_match, left_headers, right_headers))
+    }
+
+    /// Indices of the configured key columns in `headers`
+    fn key_indices(&self, headers: &[String]) -> Result<Vec<usize>, RCompareError> {
         let key_indices: Vec<usize> = self
             .key_columns
             .iter()
-            .filter_map(|col| left_headers.iter().position(|h| h == col))
+            .filter_map(|col| headers.iter().position(|h| h == col))
             .collect();
 
         if key_indices.is_empty() {
@@ -279,100 +405,99 @@ impl CsvDiffEngine {
                 "No valid key columns found in CSV headers".to_string(),
             ));
         }
+        Ok(key_indices)
+    }
 
-        // Build hash maps keyed by the key column(s)
-        let mut left_map: HashMap<String, StringRecord> = HashMap::new();
-        for result in left_reader.records() {
-            let record = result.map_err(|e| {
-                RCompareError::Io(std::io::Error::new(
-                    std::io::ErrorKind::InvalidData,
-                    format!("Failed to read left CSV record: {}", e),
-                ))
-            })?;
-            let key = self.build_key(&record, &key_indices);
-            left_map.insert(key, record);
-        }
+    /// Classify one aligned row pair and add it to `tally`
+    fn tally_row(
+        &self,
+        tally: &mut DiffTally,
+        headers: &[String],
+        row_num: usize,
+        left: Option<&StringRecord>,
+        right: Option<&StringRecord>,
+    ) {
+        let diff_type = match (left, right) {
+            (Some(left), Some(right)) if left == right => {
+                tally.identical_rows += 1;
+                return;
+            }
+            (Some(_), Some(_)) => {
+                tally.different_rows += 1;
+                RowDiffType::Modified
+            }
+            (Some(_), None) => {
+                tally.left_only_rows += 1;
+                RowDiffType::LeftOnly
+            }
+            (None, Some(_)) => {
+                tally.right_only_rows += 1;
+                RowDiffType::RightOnly
+            }
+            (None, None) => return,
+        };
 
-        let mut right_map: HashMap<String, StringRecord> = HashMap::new();
-        for result in right_reader.records() {
-            let record = result.map_err(|e| {
-                RCompareError::Io(std::io::Error::new(
-                    std::io::ErrorKind::InvalidData,
-                    format!("Failed to read right CSV record: {}", e),
-                ))
-            })?;
-            let key = self.build_key(&record, &key_indices);
-            right_map.insert(key, record);
+        if tally.row_diffs.len() < self.max_detail {
+            let column_diffs = match (left, right) {
+                (Some(left), Some(right)) => self.find_column_diffs(headers, left, right),
+                _ => vec![],
+            };
+            tally.row_diffs.push(RowDiff {
+                row_num,
+                diff_type,
+                column_diffs,
+            });
         }
+    }
 
-        let total_rows = left_map.len().max(right_map.len());
-        let mut different_rows = 0;
-        let mut left_only_rows = 0;
-        let mut right_only_rows = 0;
-        let mut identical_rows = 0;
-        let mut row_diffs = Vec::new();
+    /// Read all records, spilling runs of at most `sort_buffer_rows` rows,
+    /// each stably sorted by key, to anonymous temp files
+    fn spill_sorted_runs(
+        &self,
+        reader: &mut Reader<File>,
+        key_indices: &[usize],
+        side: &str,
+        input: &mut InputProgress,
+    ) -> Result<Vec<SortedRun>, RCompareError> {
+        let mut runs = Vec::new();
+        let mut buffer: Vec<(String, StringRecord)> = Vec::new();
 
-        // Collect all unique keys
-        let mut all_keys: Vec<String> = left_map.keys().chain(right_map.keys()).cloned().collect();
-        all_keys.sort();
-        all_keys.dedup();
+        let mut records = reader.records();
+        while let Some(record) = next_record(&mut records, side)? {
+            input.advance(side, records.reader())?;
+            buffer.push((self.build_key(&record, key_indices), record));
+            if buffer.len() >= self.sort_buffer_rows {
+                runs.push(SortedRun::from_buffer(&mut buffer)?);
+            }
+        }
+        if !buffer.is_empty() {
+            runs.push(SortedRun::from_buffer(&mut buffer)?);
+        }
+        debug!(
+            "Spilled {} {} CSV rows into {} sorted runs",
+            runs.iter().map(|run| run.rows).sum::<usize>(),
+            side,
+            runs.len()
+        );
 
-        for (idx, key) in all_keys.iter().enumerate() {
-            let row_num = idx + 1;
-            let left_row = left_map.get(key);
-            let right_row = right_map.get(key);
-
-            match (left_row, right_row) {
-                (Some(left), Some(right)) => {
-                    if left == right {
-                        identical_rows += 1;
-                    } else {
-                        different_rows += 1;
-                        if row_diffs.len() < self.max_row_diffs {
-                            let column_diffs = self.find_column_diffs(left_headers, left, right);
-                            row_diffs.push(RowDiff {
-                                row_num,
-                                diff_type: RowDiffType::Modified,
-                                column_diffs,
-                            });
-                        }
-                    }
-                }
-                (Some(_), None) => {
-                    left_only_rows += 1;
-                    if row_diffs.len() < self.max_row_diffs {
-                        row_diffs.push(RowDiff {
-                            row_num,
-                            diff_type: RowDiffType::LeftOnly,
-                            column_diffs: vec![],
-                        });
-                    }
+        // Merge consecutive groups so the final merge has few open files;
+        // keeping groups in order preserves the original order of equal keys
+        while runs.len() > MAX_MERGE_FAN_IN {
+            let mut merged = Vec::new();
+            let mut remaining = runs.into_iter().peekable();
+            while remaining.peek().is_some() {
+                let group: Vec<SortedRun> = remaining.by_ref().take(MAX_MERGE_FAN_IN).collect();
+                let mut merger = RunMerger::new(group)?;
+                let mut writer = RunWriter::new()?;
+                while let Some((key, record)) = merger.next_row()? {
+                    writer.write(&key, &record)?;
                 }
-                (None, Some(_)) => {
-                    right_only_rows += 1;
-                    if row_diffs.len() < self.max_row_diffs {
-                        row_diffs.push(RowDiff {
-                            row_num,
-                            diff_type: RowDiffType::RightOnly,
-                            column_diffs: vec![],
-                        });
-                    }
-                }
-                (None, None) => unreachable!(),
+                merged.push(writer.finish()?);
             }
+            runs = merged;
         }
 
-        Ok(CsvDiffResult {
-            total_rows,
-            different_rows,
-            left_only_rows,
-            right_only_rows,
-            identical_rows,
-            headers_match,
-            left_headers: left_headers.to_vec(),
-            right_headers: right_headers.to_vec(),
-            row_diffs,
-        })
+        Ok(runs)
     }
 
     fn build_key(&self, record: &StringRecord, key_indices: &[usize]) -> String {
@@ -415,6 +540,262 @@ impl Default for CsvDiffEngine {
     }
 }
 
+/// Running counts and capped row details for one comparison
+#[derive(Default)]
+struct DiffTally {
+    different_rows: usize,
+    left_only_rows: usize,
+    right_only_rows: usize,
+    identical_rows: usize,
+    row_diffs: Vec<RowDiff>,
+}
+
+impl DiffTally {
+    fn finish(
+        self,
+        total_rows: usize,
+        headers_match: bool,
+        left_headers: &[String],
+        right_headers: &[String],
+    ) -> CsvDiffResult {
+        let truncated =
+            self.different_rows + self.left_only_rows + self.right_only_rows > self.row_diffs.len();
+        CsvDiffResult {
+            total_rows,
+            different_rows: self.different_rows,
+            left_only_rows: self.left_only_rows,
+            right_only_rows: self.right_only_rows,
+            identical_rows: self.identical_rows,
+            headers_match,
+            left_headers: left_headers.to_vec(),
+            right_headers: right_headers.to_vec(),
+            row_diffs: self.row_diffs,
+            truncated,
+        }
+    }
+}
+
+/// Cancellation checks and byte-based progress over both inputs
+struct InputProgress<'a> {
+    monitor: Monitor<'a>,
+    /// Combined size of both files
+    total: usize,
+    left_read: usize,
+    right_read: usize,
+}
+
+impl<'a> InputProgress<'a> {
+    fn new(monitor: Monitor<'a>, left: &Path, right: &Path) -> Self {
+        let size = |path: &Path| std::fs::metadata(path).map_or(0, |m| m.len() as usize);
+        Self {
+            monitor,
+            total: size(left) + size(right),
+            left_read: 0,
+            right_read: 0,
+        }
+    }
+
+    fn check(&self) -> Result<(), RCompareError> {
+        self.monitor.check("CSV comparison")
+    }
+
+    /// Record how far `reader` has got into the `side` file
+    fn advance(&mut self, side: &str, reader: &Reader<File>) -> Result<(), RCompareError> {
+        self.check()?;
+        let position = reader.position().byte() as usize;
+        if side == "left" {
+            self.left_read = position;
+        } else {
+            self.right_read = position;
+        }
+        self.monitor
+            .report(self.left_read + self.right_read, self.total);
+        Ok(())
+    }
+
+    /// Pass every remaining record of the `side` file to `f`
+    fn read_all(
+        &mut self,
+        reader: &mut Reader<File>,
+        side: &str,
+        mut f: impl FnMut(StringRecord),
+    ) -> Result<(), RCompareError> {
+        let mut records = reader.records();
+        while let Some(record) = next_record(&mut records, side)? {
+            self.advance(side, records.reader())?;
+            f(record);
+        }
+        Ok(())
+    }
+}
+
+/// A run of `(key, record)` rows sorted by key, stored in a temp file
+struct SortedRun {
+    file: File,
+    rows: usize,
+}
+
+impl SortedRun {
+    /// Stably sort and drain `buffer` into a new run
+    fn from_buffer(buffer: &mut Vec<(String, StringRecord)>) -> Result<Self, RCompareError> {
+        buffer.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut writer = RunWriter::new()?;
+        for (key, record) in buffer.drain(..) {
+            writer.write(&key, &record)?;
+        }
+        writer.finish()
+    }
+}
+
+/// Writes rows to a run file as CSV records with the key as first field
+struct RunWriter {
+    writer: Writer<File>,
+    rows: usize,
+}
+
+impl RunWriter {
+    fn new() -> Result<Self, RCompareError> {
+        let writer = csv::WriterBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .from_writer(tempfile::tempfile()?);
+        Ok(Self { writer, rows: 0 })
+    }
+
+    fn write(&mut self, key: &str, record: &StringRecord) -> Result<(), RCompareError> {
+        self.writer
+            .write_record(std::iter::once(key).chain(record.iter()))
+            .map_err(|e| invalid_data(format!("Failed to write CSV sort run: {}", e)))?;
+        self.rows += 1;
+        Ok(())
+    }
+
+    fn finish(self) -> Result<SortedRun, RCompareError> {
+        let mut file = self
+            .writer
+            .into_inner()
+            .map_err(|e| invalid_data(format!("Failed to flush CSV sort run: {}", e)))?;
+        file.seek(SeekFrom::Start(0))?;
+        Ok(SortedRun {
+            file,
+            rows: self.rows,
+        })
+    }
+}
+
+/// K-way merge of sorted runs; equal keys come out in run order
+struct RunMerger {
+    runs: Vec<StringRecordsIntoIter<File>>,
+    heads: Vec<Option<StringRecord>>,
+    heap: BinaryHeap<Reverse<(String, usize)>>,
+}
+
+impl RunMerger {
+    fn new(runs: Vec<SortedRun>) -> Result<Self, RCompareError> {
+        let mut merger = Self {
+            runs: Vec::with_capacity(runs.len()),
+            heads: vec![None; runs.len()],
+            heap: BinaryHeap::with_capacity(runs.len()),
+        };
+        for run in runs {
+            let reader = csv::ReaderBuilder::new()
+                .has_headers(false)
+                .flexible(true)
+                .from_reader(run.file);
+            merger.runs.push(reader.into_records());
+            merger.advance(merger.runs.len() - 1)?;
+        }
+        Ok(merger)
+    }
+
+    /// Load the next row of run `idx` into the heap
+    fn advance(&mut self, idx: usize) -> Result<(), RCompareError> {
+        if let Some(row) = next_record(&mut self.runs[idx], "sort run")? {
+            let key = row.get(0).unwrap_or_default().to_string();
+            self.heads[idx] = Some(row.iter().skip(1).collect());
+            self.heap.push(Reverse((key, idx)));
+        }
+        Ok(())
+    }
+
+    fn next_row(&mut self) -> Result<Option<(String, StringRecord)>, RCompareError> {
+        let Some(Reverse((key, idx))) = self.heap.pop() else {
+            return Ok(None);
+        };
+        let record = self.heads[idx].take().unwrap_or_default();
+        self.advance(idx)?;
+        Ok(Some((key, record)))
+    }
+}
+
+/// Merged rows with duplicate keys collapsed to the last occurrence
+struct UniqueRows {
+    merger: RunMerger,
+    pending: Option<(String, StringRecord)>,
+    /// Number of distinct keys returned so far
+    count: usize,
+}
+
+impl UniqueRows {
+    fn new(merger: RunMerger) -> Self {
+        Self {
+            merger,
+            pending: None,
+            count: 0,
+        }
+    }
+
+    fn next(&mut self) -> Result<Option<(String, StringRecord)>, RCompareError> {
+        let mut current = match self.pending.take() {
+            Some(row) => row,
+            None => match self.merger.next_row()? {
+                Some(row) => row,
+                None => return Ok(None),
+            },
+        };
+        while let Some(row) = self.merger.next_row()? {
+            if row.0 == current.0 {
+                current = row;
+            } else {
+                self.pending = Some(row);
+                break;
+            }
+        }
+        self.count += 1;
+        Ok(Some(current))
+    }
+}
+
+/// Open a CSV file and read its header row
+fn open_csv(path: &Path, side: &str) -> Result<(Reader<File>, Vec<String>), RCompareError> {
+    let mut reader = Reader::from_path(path)
+        .map_err(|e| invalid_data(format!("Failed to open {} CSV file: {}", side, e)))?;
+    let headers = reader
+        .headers()
+        .map_err(|e| invalid_data(format!("Failed to read {} CSV headers: {}", side, e)))?
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    Ok((reader, headers))
+}
+
+fn next_record<I>(records: &mut I, side: &str) -> Result<Option<StringRecord>, RCompareError>
+where
+    I: Iterator<Item = csv::Result<StringRecord>>,
+{
+    records
+        .next()
+        .transpose()
+        .map_err(|e| invalid_data(format!("Failed to read {} CSV record: {}", side, e)))
+}
+
+fn invalid_data(message: String) -> RCompareError {
+    RCompareError::Io(std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        message,
+    ))
+}
+
 /// Check if a file path appears to be a CSV based on extension
 pub fn is_csv_file(path: &Path) -> bool {
     if let Some(ext) = path.extension() {
@@ -453,6 +834,36 @@ mod tests {
         assert!(result.headers_match);
     }
 
+    #[test]
+    fn test_max_detail_caps_rows_but_keeps_totals() {
+        let rows = |value: &str| {
+            let mut content = String::from("id,value\n");
+            for i in 0..50 {
+                content.push_str(&format!("{},{}\n", i, value));
+            }
+            content
+        };
+        let left = create_temp_csv(&rows("a"));
+        let mut right_rows = rows("b");
+        right_rows.push_str("50,b\n");
+        let right = create_temp_csv(&right_rows);
+
+        let engine = CsvDiffEngine::new().with_max_detail(10);
+        let result = engine.compare_files(left.path(), right.path()).unwrap();
+        assert_eq!(result.total_rows, 51);
+        assert_eq!(result.different_rows, 50);
+        assert_eq!(result.right_only_rows, 1);
+        assert_eq!(result.row_diffs.len(), 10);
+        assert!(result.truncated);
+
+        let result = CsvDiffEngine::new()
+            .with_max_detail(51)
+            .compare_files(left.path(), right.path())
+            .unwrap();
+        assert_eq!(result.row_diffs.len(), 51);
+        assert!(!result.truncated);
+    }
+
     #[test]
     fn test_different_values() {
         let left = create_temp_csv("name,age,city\nAlice,30,NYC\nBob,25,LA\n");
@@ -481,6 +892,144 @@ mod tests {
         assert_eq!(result.right_only_rows, 0);
     }
 
+    /// Synthetic inputs with shuffled keys, edits, deletions, additions
+    /// and a duplicated key on the left
+    fn large_csv_pair(rows: usize) -> (NamedTempFile, NamedTempFile) {
+        let mut left = String::from("id,name,value\n");
+        let mut right = String::from("id,name,value\n");
+        for i in 0..rows {
+            let id = (i * 7919) % rows;
+            left.push_str(&format!("{},item{},{}\n", id, id, id * 3));
+            match id % 10 {
+                0 => {}
+                1 => right.push_str(&format!("{},item{},{}\n", id, id, id * 3 + 1)),
+                _ => right.push_str(&format!("{},item{},{}\n", id, id, id * 3)),
+            }
+        }
+        left.push_str("7,duplicate,0\n");
+        for id in rows..rows + 25 {
+            right.push_str(&format!("{},new{},0\n", id, id));
+        }
+        (create_temp_csv(&left), create_temp_csv(&right))
+    }
+
+    fn as_json(result: &CsvDiffResult) -> serde_json::Value {
+        serde_json::to_value(result).unwrap()
+    }
+
+    #[test]
+    fn test_streaming_matches_in_memory() {
+        let (left, right) = large_csv_pair(5_000);
+
+        let row_engine = CsvDiffEngine::new().with_max_detail(1_000);
+        let expected = row_engine.compare_files(left.path(), right.path()).unwrap();
+        let streamed = row_engine
+            .compare_files_streaming(left.path(), right.path())
+            .unwrap();
+        assert_eq!(as_json(&streamed), as_json(&expected));
+
+        // A tiny sort buffer forces 500 runs and a cascaded merge
+        let key_engine = CsvDiffEngine::new()
+            .with_key_columns(vec!["id".to_string()])
+            .with_max_detail(1_000)
+            .with_sort_buffer_rows(10);
+        let expected = key_engine.compare_files(left.path(), right.path()).unwrap();
+        let streamed = key_engine
+            .compare_files_streaming(left.path(), right.path())
+            .unwrap();
+        assert_eq!(as_json(&streamed), as_json(&expected));
+        assert_eq!(streamed.total_rows, 5_000);
+        assert_eq!(streamed.left_only_rows, 500);
+        assert_eq!(streamed.right_only_rows, 25);
+        assert_eq!(streamed.different_rows, 501);
+    }
+
+    #[test]
+    fn test_streaming_sort_memory_is_bounded() {
+        let (left, _right) = large_csv_pair(5_000);
+        let engine = CsvDiffEngine::new()
+            .with_key_columns(vec!["id".to_string()])
+            .with_sort_buffer_rows(100);
+
+        let (mut reader, headers) = open_csv(left.path(), "left").unwrap();
+        let key_indices = engine.key_indices(&headers).unwrap();
+        let mut input = InputProgress::new(Monitor::default(), left.path(), left.path());
+        let runs = engine
+            .spill_sorted_runs(&mut reader, &key_indices, "left", &mut input)
+            .unwrap();
+
+        // Never more than `sort_buffer_rows` rows were held at once
+        assert_eq!(runs.len(), 51);
+        assert!(runs.iter().all(|run| run.rows <= 100));
+        assert_eq!(runs.iter().map(|run| run.rows).sum::<usize>(), 5_001);
+    }
+
+    #[test]
+    fn test_cancel_stops_large_comparison() {
+        use std::sync::atomic::Ordering;
+
+        let (left, right) = large_csv_pair(200_000);
+        let cancel = AtomicBool::new(false);
+        let reports = std::cell::Cell::new(0);
+        let progress = |_done: usize, _total: usize| {
+            reports.set(reports.get() + 1);
+            if reports.get() == 10 {
+                cancel.store(true, Ordering::Relaxed);
+            }
+        };
+        let engines = [
+            CsvDiffEngine::new(),
+            CsvDiffEngine::new().with_key_columns(vec!["id".to_string()]),
+        ];
+
+        for engine in &engines {
+            for streaming in [false, true] {
+                cancel.store(false, Ordering::Relaxed);
+                reports.set(0);
+                let result = if streaming {
+                    engine.compare_files_streaming_with_cancel(
+                        left.path(),
+                        right.path(),
+                        &cancel,
+                        Some(&progress),
+                    )
+                } else {
+                    engine.compare_files_with_cancel(
+                        left.path(),
+                        right.path(),
+                        &cancel,
+                        Some(&progress),
+                    )
+                };
+                let err = result.unwrap_err();
+                assert!(err.to_string().contains("CSV comparison cancelled"));
+                // Stopped at the next row, not at the end of the file
+                assert_eq!(reports.get(), 10);
+            }
+        }
+    }
+
+    #[test]
+    fn test_progress_reaches_combined_size() {
+        let (left, right) = large_csv_pair(1_000);
+        let total = std::fs::metadata(left.path()).unwrap().len()
+            + std::fs::metadata(right.path()).unwrap().len();
+        let last = std::cell::Cell::new((0, 0));
+        let progress = |done: usize, total: usize| last.set((done, total));
+
+        let result = CsvDiffEngine::new()
+            .compare_files_with_cancel(
+                left.path(),
+                right.path(),
+                &AtomicBool::new(false),
+                Some(&progress),
+            )
+            .unwrap();
+
+        assert_eq!(result.total_rows, 1_001);
+        assert_eq!(last.get(), (total as usize, total as usize));
+    }
+
     #[test]
     fn test_is_csv_file() {
         assert!(is_csv_file(Path::new("data.csv")));
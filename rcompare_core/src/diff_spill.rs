@@ -0,0 +1,319 @@
+//! On-disk store for comparison results.
+//!
+//! [`ComparisonEngine::compare_spilled`](crate::ComparisonEngine::compare_spilled)
+//! appends each [`DiffNode`] to a temp file as soon as it is classified, so
+//! the result nodes are never all held in memory at once. The nodes are read
+//! back one at a time through [`SpilledDiff::iter`].
+//!
+//! # Format
+//!
+//! The file is a plain append log: each record is a little-endian `u32` byte
+//! length followed by the bincode encoding of the node. The temp file is
+//! deleted once the [`SpilledDiff`] is dropped.
+
+use rcompare_common::{DiffNode, DiffReason, DiffStatus, FileEntry, RCompareError, SimilarTo};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, ErrorKind, Read, Write};
+use std::marker::PhantomData;
+use std::path::PathBuf;
+use tempfile::NamedTempFile;
+
+/// Spilled form of a [`DiffNode`]
+///
+/// `DiffNode` skips `similar_to` and `reason` when they are `None`, which
+/// suits self-describing formats like JSON but not bincode, so records use
+/// this mirror with every field always present.
+#[derive(Serialize, Deserialize)]
+struct SpillRecord {
+    relative_path: PathBuf,
+    left: Option<FileEntry>,
+    right: Option<FileEntry>,
+    status: DiffStatus,
+    similar_to: Option<SimilarTo>,
+    reason: Option<DiffReason>,
+    ignored_by: Option<String>,
+}
+
+impl From<DiffNode> for SpillRecord {
+    fn from(node: DiffNode) -> Self {
+        Self {
+            relative_path: node.relative_path,
+            left: node.left,
+            right: node.right,
+            status: node.status,
+            similar_to: node.similar_to,
+            reason: node.reason,
+            ignored_by: node.ignored_by,
+        }
+    }
+}
+
+impl From<SpillRecord> for DiffNode {
+    fn from(record: SpillRecord) -> Self {
+        Self {
+            relative_path: record.relative_path,
+            left: record.left,
+            right: record.right,
+            status: record.status,
+            similar_to: record.similar_to,
+            reason: record.reason,
+            ignored_by: record.ignored_by,
+        }
+    }
+}
+
+/// Appends nodes to a temp file; [`finish`](Self::finish) seals it into a
+/// [`SpilledDiff`]
+pub struct DiffSpillWriter {
+    writer: BufWriter<NamedTempFile>,
+    len: usize,
+    buffer: Vec<u8>,
+}
+
+impl DiffSpillWriter {
+    pub fn new() -> Result<Self, RCompareError> {
+        Ok(Self {
+            writer: BufWriter::new(NamedTempFile::new()?),
+            len: 0,
+            buffer: Vec::new(),
+        })
+    }
+
+    /// Append one node to the log
+    pub fn push(&mut self, node: DiffNode) -> Result<(), RCompareError> {
+        self.buffer.clear();
+        bincode::serialize_into(&mut self.buffer, &SpillRecord::from(node))
+            .map_err(|e| RCompareError::Serialization(e.to_string()))?;
+        let len = u32::try_from(self.buffer.len()).map_err(|_| {
+            RCompareError::Serialization("diff node too large to spill".to_string())
+        })?;
+        self.writer.write_all(&len.to_le_bytes())?;
+        self.writer.write_all(&self.buffer)?;
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Number of nodes written so far
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Flush the log and make it readable
+    pub fn finish(self) -> Result<SpilledDiff, RCompareError> {
+        let file = self
+            .writer
+            .into_inner()
+            .map_err(|e| RCompareError::Io(e.into_error()))?;
+        Ok(SpilledDiff {
+            file,
+            len: self.len,
+        })
+    }
+}
+
+/// Comparison result stored on disk
+pub struct SpilledDiff {
+    /// Kept named so every iterator can open its own read cursor
+    file: NamedTempFile,
+    len: usize,
+}
+
+impl SpilledDiff {
+    /// Number of stored nodes
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Read the nodes back in the order they were written
+    ///
+    /// Each call starts again from the first node with its own file handle,
+    /// so several iterators can be advanced independently; nodes are decoded
+    /// one at a time as the iterator advances.
+    pub fn iter(&self) -> Result<SpilledNodes<'_>, RCompareError> {
+        Ok(SpilledNodes {
+            reader: BufReader::new(self.file.reopen()?),
+            remaining: self.len,
+            buffer: Vec::new(),
+            _diff: PhantomData,
+        })
+    }
+
+    /// Load every node into memory
+    pub fn to_vec(&self) -> Result<Vec<DiffNode>, RCompareError> {
+        self.iter()?.collect()
+    }
+}
+
+/// Iterator over the nodes of a [`SpilledDiff`]
+pub struct SpilledNodes<'a> {
+    reader: BufReader<File>,
+    remaining: usize,
+    buffer: Vec<u8>,
+    _diff: PhantomData<&'a SpilledDiff>,
+}
+
+impl SpilledNodes<'_> {
+    fn read_node(&mut self) -> Result<DiffNode, RCompareError> {
+        let mut len = [0u8; 4];
+        self.reader.read_exact(&mut len).map_err(truncated)?;
+        self.buffer.resize(u32::from_le_bytes(len) as usize, 0);
+        self.reader
+            .read_exact(&mut self.buffer)
+            .map_err(truncated)?;
+        let record: SpillRecord = bincode::deserialize(&self.buffer)
+            .map_err(|e| RCompareError::Serialization(e.to_string()))?;
+        Ok(record.into())
+    }
+}
+
+impl Iterator for SpilledNodes<'_> {
+    type Item = Result<DiffNode, RCompareError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        let node = self.read_node();
+        if node.is_err() {
+            // The log can't be resynchronised after a bad record
+            self.remaining = 0;
+        }
+        Some(node)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+fn truncated(e: std::io::Error) -> RCompareError {
+    if e.kind() == ErrorKind::UnexpectedEof {
+        RCompareError::Serialization("spilled diff log is truncated".to_string())
+    } else {
+        RCompareError::Io(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rcompare_common::FileType;
+    use std::time::{Duration, SystemTime};
+
+    fn entry(path: &str, size: u64) -> FileEntry {
+        FileEntry {
+            path: PathBuf::from(path),
+            size,
+            modified: SystemTime::UNIX_EPOCH + Duration::from_nanos(1_700_000_000_123_456_789),
+            is_dir: false,
+            file_type: FileType::Regular,
+        }
+    }
+
+    #[test]
+    fn test_round_trip_preserves_nodes() {
+        let nodes = vec![
+            DiffNode {
+                relative_path: PathBuf::from("a.txt"),
+                left: Some(entry("a.txt", 3)),
+                right: Some(entry("a.txt", 4)),
+                status: DiffStatus::Different,
+                similar_to: None,
+                reason: None,
+                ignored_by: None,
+            },
+            DiffNode {
+                relative_path: PathBuf::from("old/b.txt"),
+                left: Some(entry("old/b.txt", 10)),
+                right: None,
+                status: DiffStatus::OrphanLeft,
+                similar_to: Some(SimilarTo {
+                    path: PathBuf::from("new/b.txt"),
+                    similarity: 87.5,
+                }),
+                reason: None,
+                ignored_by: None,
+            },
+        ];
+
+        let mut writer = DiffSpillWriter::new().unwrap();
+        for node in nodes.clone() {
+            writer.push(node).unwrap();
+        }
+        let spilled = writer.finish().unwrap();
+        assert_eq!(spilled.len(), 2);
+
+        // Iterating twice reads from the start each time
+        for _ in 0..2 {
+            let read = spilled.to_vec().unwrap();
+            assert_eq!(format!("{read:?}"), format!("{nodes:?}"));
+        }
+    }
+
+    #[test]
+    fn test_round_trip_preserves_reason() {
+        let node = DiffNode {
+            relative_path: PathBuf::from("a.txt"),
+            left: Some(entry("a.txt", 0)),
+            right: Some(entry("a.txt", 4)),
+            status: DiffStatus::Different,
+            similar_to: None,
+            reason: Some(DiffReason::EmptiedFile),
+            ignored_by: None,
+        };
+
+        let mut writer = DiffSpillWriter::new().unwrap();
+        writer.push(node.clone()).unwrap();
+        let read = writer.finish().unwrap().to_vec().unwrap();
+        assert_eq!(read.len(), 1);
+        assert_eq!(read[0].reason, Some(DiffReason::EmptiedFile));
+        assert_eq!(format!("{:?}", read[0]), format!("{node:?}"));
+    }
+
+    #[test]
+    fn test_iterators_advance_independently() {
+        let mut writer = DiffSpillWriter::new().unwrap();
+        for i in 0..3 {
+            let path = format!("{i}.txt");
+            writer
+                .push(DiffNode {
+                    relative_path: PathBuf::from(&path),
+                    left: Some(entry(&path, i)),
+                    right: None,
+                    status: DiffStatus::OrphanLeft,
+                    similar_to: None,
+                    reason: None,
+                    ignored_by: None,
+                })
+                .unwrap();
+        }
+        let spilled = writer.finish().unwrap();
+
+        let mut outer = spilled.iter().unwrap();
+        let first = outer.next().unwrap().unwrap();
+        let inner: Vec<_> = spilled.iter().unwrap().map(|n| n.unwrap()).collect();
+        let rest: Vec<_> = outer.map(|n| n.unwrap()).collect();
+
+        assert_eq!(first.relative_path, PathBuf::from("0.txt"));
+        assert_eq!(inner.len(), 3);
+        let rest: Vec<_> = rest.iter().map(|n| n.relative_path.clone()).collect();
+        assert_eq!(rest, [PathBuf::from("1.txt"), PathBuf::from("2.txt")]);
+    }
+
+    #[test]
+    fn test_empty_spill() {
+        let spilled = DiffSpillWriter::new().unwrap().finish().unwrap();
+        assert!(spilled.is_empty());
+        assert_eq!(spilled.iter().unwrap().count(), 0);
+    }
+}
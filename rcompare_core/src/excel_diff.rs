@@ -1,8 +1,13 @@
-use calamine::{open_workbook_auto, Data, DataType, Range, Reader};
+use crate::cancel::Monitor;
+use calamine::{open_workbook_auto, Data, DataType, Dimensions, Range, Reader, SheetType, Sheets};
 use rcompare_common::RCompareError;
 use serde::Serialize;
-use std::collections::HashMap;
+use similar::{capture_diff_slices, Algorithm, DiffOp};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::File;
+use std::io::{Read, Seek};
 use std::path::Path;
+use std::sync::atomic::AtomicBool;
 
 /// Result of an Excel workbook comparison
 #[derive(Debug, Clone, Serialize)]
@@ -25,6 +30,26 @@ pub struct ExcelDiffResult {
     pub right_sheet_names: Vec<String>,
     /// Detailed sheet differences (limited)
     pub sheet_diffs: Vec<SheetDiff>,
+    /// Defined names (named ranges) that were added, removed or redefined
+    pub named_range_diffs: Vec<NamedRangeDiff>,
+    /// Charts in the left workbook (embedded charts and chart sheets)
+    pub left_charts: usize,
+    /// Charts in the right workbook
+    pub right_charts: usize,
+}
+
+impl ExcelDiffResult {
+    /// Whether the chart counts differ between the two workbooks
+    pub fn charts_differ(&self) -> bool {
+        self.left_charts != self.right_charts
+    }
+
+    /// Whether any sheet, named range or chart count differs
+    pub fn has_differences(&self) -> bool {
+        self.different_sheets + self.left_only_sheets + self.right_only_sheets > 0
+            || !self.named_range_diffs.is_empty()
+            || self.charts_differ()
+    }
 }
 
 /// Represents a difference in a specific sheet
@@ -42,6 +67,42 @@ pub struct SheetDiff {
     pub different_cells: usize,
     /// Cell differences (limited to first 20)
     pub cell_diffs: Vec<CellDiff>,
+    /// Rows present only in the right sheet (right row indices, 0-indexed)
+    pub added_rows: Vec<usize>,
+    /// Rows present only in the left sheet (left row indices, 0-indexed)
+    pub removed_rows: Vec<usize>,
+    /// Columns present only in the right sheet (right column indices)
+    pub added_cols: Vec<usize>,
+    /// Columns present only in the left sheet (left column indices)
+    pub removed_cols: Vec<usize>,
+    /// Merged ranges present only in the right sheet, in A1 notation
+    pub added_merged_ranges: Vec<String>,
+    /// Merged ranges present only in the left sheet, in A1 notation
+    pub removed_merged_ranges: Vec<String>,
+}
+
+impl SheetDiff {
+    fn new(sheet_name: &str, diff_type: SheetDiffType) -> Self {
+        Self {
+            sheet_name: sheet_name.to_string(),
+            diff_type,
+            total_rows: 0,
+            total_cols: 0,
+            different_cells: 0,
+            cell_diffs: Vec::new(),
+            added_rows: Vec::new(),
+            removed_rows: Vec::new(),
+            added_cols: Vec::new(),
+            removed_cols: Vec::new(),
+            added_merged_ranges: Vec::new(),
+            removed_merged_ranges: Vec::new(),
+        }
+    }
+
+    /// Whether the merged ranges differ between the two sheets
+    pub fn merged_ranges_differ(&self) -> bool {
+        !self.added_merged_ranges.is_empty() || !self.removed_merged_ranges.is_empty()
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
@@ -54,12 +115,39 @@ pub enum SheetDiffType {
     RightOnly,
 }
 
+/// A defined name whose reference differs between the workbooks
+#[derive(Debug, Clone, Serialize)]
+pub struct NamedRangeDiff {
+    /// Defined name
+    pub name: String,
+    /// Type of difference
+    pub diff_type: NamedRangeDiffType,
+    /// What the name refers to in the left workbook
+    pub left_refers_to: Option<String>,
+    /// What the name refers to in the right workbook
+    pub right_refers_to: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum NamedRangeDiffType {
+    /// Name exists in both but refers to something else
+    Modified,
+    /// Name only exists in left
+    LeftOnly,
+    /// Name only exists in right
+    RightOnly,
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct CellDiff {
-    /// Row index (0-indexed)
+    /// Row index in the left sheet (0-indexed)
     pub row: usize,
-    /// Column index (0-indexed)
+    /// Column index in the left sheet (0-indexed)
     pub col: usize,
+    /// Row index in the right sheet; differs from `row` after insertions
+    pub right_row: usize,
+    /// Column index in the right sheet; differs from `col` after insertions
+    pub right_col: usize,
     /// Left value
     pub left_value: String,
     /// Right value
@@ -67,9 +155,16 @@ pub struct CellDiff {
 }
 
 /// Engine for comparing Excel files
+///
+/// By default rows and columns are aligned before cells are compared:
+/// columns by their header cells (the first row) and rows by their content,
+/// so an inserted row or column is reported in `added_rows`/`added_cols`
+/// instead of shifting every later cell. Disable with
+/// [`with_alignment`](Self::with_alignment) for a purely positional diff.
 pub struct ExcelDiffEngine {
     max_sheet_diffs: usize,
     max_cell_diffs_per_sheet: usize,
+    align: bool,
 }
 
 impl ExcelDiffEngine {
@@ -77,9 +172,17 @@ impl ExcelDiffEngine {
         Self {
             max_sheet_diffs: 10,
             max_cell_diffs_per_sheet: 20,
+            align: true,
         }
     }
 
+    /// Align inserted/deleted rows and columns before comparing cells
+    /// (default on)
+    pub fn with_alignment(mut self, enabled: bool) -> Self {
+        self.align = enabled;
+        self
+    }
+
     pub fn with_max_sheet_diffs(mut self, max: usize) -> Self {
         self.max_sheet_diffs = max;
         self
@@ -95,6 +198,29 @@ impl ExcelDiffEngine {
         &self,
         left: &Path,
         right: &Path,
+    ) -> Result<ExcelDiffResult, RCompareError> {
+        self.compare(left, right, Monitor::default())
+    }
+
+    /// Compare two Excel files, stopping with an error once `cancel` is set
+    ///
+    /// The flag is checked before each sheet is loaded and compared;
+    /// `progress` receives `(sheets compared, total sheets)`.
+    pub fn compare_files_with_cancel(
+        &self,
+        left: &Path,
+        right: &Path,
+        cancel: &AtomicBool,
+        progress: Option<&dyn Fn(usize, usize)>,
+    ) -> Result<ExcelDiffResult, RCompareError> {
+        self.compare(left, right, Monitor::new(cancel, progress))
+    }
+
+    fn compare(
+        &self,
+        left: &Path,
+        right: &Path,
+        monitor: Monitor,
     ) -> Result<ExcelDiffResult, RCompareError> {
         // Open both workbooks
         let mut left_workbook = open_workbook_auto(left).map_err(|e| {
@@ -120,6 +246,7 @@ impl ExcelDiffEngine {
         // Build maps of sheet data
         let mut left_sheets: HashMap<String, Range<Data>> = HashMap::new();
         for sheet_name in &left_sheet_names {
+            monitor.check("Excel comparison")?;
             if let Ok(range) = left_workbook.worksheet_range(sheet_name) {
                 left_sheets.insert(sheet_name.clone(), range);
             }
@@ -127,6 +254,7 @@ impl ExcelDiffEngine {
 
         let mut right_sheets: HashMap<String, Range<Data>> = HashMap::new();
         for sheet_name in &right_sheet_names {
+            monitor.check("Excel comparison")?;
             if let Ok(range) = right_workbook.worksheet_range(sheet_name) {
                 right_sheets.insert(sheet_name.clone(), range);
             }
@@ -148,18 +276,26 @@ impl ExcelDiffEngine {
         let mut identical_sheets = 0;
         let mut sheet_diffs = Vec::new();
 
-        for sheet_name in &all_sheet_names {
+        for (done, sheet_name) in all_sheet_names.iter().enumerate() {
+            monitor.check("Excel comparison")?;
+            monitor.report(done, total_sheets);
             let left_range = left_sheets.get(sheet_name);
             let right_range = right_sheets.get(sheet_name);
 
             match (left_range, right_range) {
                 (Some(left), Some(right)) => {
-                    if self.ranges_equal(left, right) {
+                    let left_merged = merged_ranges(&mut left_workbook, sheet_name);
+                    let right_merged = merged_ranges(&mut right_workbook, sheet_name);
+                    if self.ranges_equal(left, right) && left_merged == right_merged {
                         identical_sheets += 1;
                     } else {
                         different_sheets += 1;
                         if sheet_diffs.len() < self.max_sheet_diffs {
-                            let diff = self.compare_ranges(sheet_name, left, right);
+                            let mut diff = self.compare_ranges(sheet_name, left, right);
+                            diff.removed_merged_ranges =
+                                left_merged.difference(&right_merged).cloned().collect();
+                            diff.added_merged_ranges =
+                                right_merged.difference(&left_merged).cloned().collect();
                             sheet_diffs.push(diff);
                         }
                     }
@@ -167,32 +303,24 @@ impl ExcelDiffEngine {
                 (Some(_), None) => {
                     left_only_sheets += 1;
                     if sheet_diffs.len() < self.max_sheet_diffs {
-                        sheet_diffs.push(SheetDiff {
-                            sheet_name: sheet_name.clone(),
-                            diff_type: SheetDiffType::LeftOnly,
-                            total_rows: 0,
-                            total_cols: 0,
-                            different_cells: 0,
-                            cell_diffs: vec![],
-                        });
+                        sheet_diffs.push(SheetDiff::new(sheet_name, SheetDiffType::LeftOnly));
                     }
                 }
                 (None, Some(_)) => {
                     right_only_sheets += 1;
                     if sheet_diffs.len() < self.max_sheet_diffs {
-                        sheet_diffs.push(SheetDiff {
-                            sheet_name: sheet_name.clone(),
-                            diff_type: SheetDiffType::RightOnly,
-                            total_rows: 0,
-                            total_cols: 0,
-                            different_cells: 0,
-                            cell_diffs: vec![],
-                        });
+                        sheet_diffs.push(SheetDiff::new(sheet_name, SheetDiffType::RightOnly));
                     }
                 }
                 (None, None) => unreachable!(),
             }
         }
+        monitor.report(total_sheets, total_sheets);
+
+        let named_range_diffs = compare_defined_names(
+            left_workbook.defined_names(),
+            right_workbook.defined_names(),
+        );
 
         Ok(ExcelDiffResult {
             total_sheets,
@@ -204,6 +332,9 @@ impl ExcelDiffEngine {
             left_sheet_names,
             right_sheet_names,
             sheet_diffs,
+            named_range_diffs,
+            left_charts: count_charts(left, &left_workbook),
+            right_charts: count_charts(right, &right_workbook),
         })
     }
 
@@ -234,33 +365,65 @@ impl ExcelDiffEngine {
     ) -> SheetDiff {
         let left_size = left.get_size();
         let right_size = right.get_size();
-
-        let total_rows = left_size.0.max(right_size.0);
-        let total_cols = left_size.1.max(right_size.1);
-
-        let mut different_cells = 0;
-        let mut cell_diffs = Vec::new();
-
-        for row in 0..total_rows {
-            for col in 0..total_cols {
-                let left_cell = if row < left_size.0 && col < left_size.1 {
-                    left.get_value((row as u32, col as u32))
-                } else {
-                    None
-                };
-
-                let right_cell = if row < right_size.0 && col < right_size.1 {
-                    right.get_value((row as u32, col as u32))
-                } else {
-                    None
-                };
+        let mut diff = SheetDiff {
+            total_rows: left_size.0.max(right_size.0),
+            total_cols: left_size.1.max(right_size.1),
+            ..SheetDiff::new(sheet_name, SheetDiffType::Modified)
+        };
+
+        let (col_pairs, row_pairs) = if self.align {
+            // Columns by header cells, then rows by their matched-column cells
+            // so an added column doesn't make every row look different
+            let header = |range: &Range<Data>, size: (usize, usize)| -> Vec<String> {
+                (0..size.1)
+                    .map(|col| self.format_cell(cell_at(range, size, 0, col)))
+                    .collect()
+            };
+            let col_pairs = align(
+                &header(left, left_size),
+                &header(right, right_size),
+                &mut diff.removed_cols,
+                &mut diff.added_cols,
+            );
+            let signatures = |range: &Range<Data>, size: (usize, usize), left_side: bool| {
+                (0..size.0)
+                    .map(|row| {
+                        col_pairs
+                            .iter()
+                            .map(|&(l, r)| {
+                                let col = if left_side { l } else { r };
+                                self.format_cell(cell_at(range, size, row, col))
+                            })
+                            .collect::<Vec<_>>()
+                            .join("\u{1f}")
+                    })
+                    .collect::<Vec<_>>()
+            };
+            let row_pairs = align(
+                &signatures(left, left_size, true),
+                &signatures(right, right_size, false),
+                &mut diff.removed_rows,
+                &mut diff.added_rows,
+            );
+            (col_pairs, row_pairs)
+        } else {
+            let positional = |n: usize| (0..n).map(|i| (i, i)).collect::<Vec<_>>();
+            (positional(diff.total_cols), positional(diff.total_rows))
+        };
+
+        for &(row, right_row) in &row_pairs {
+            for &(col, right_col) in &col_pairs {
+                let left_cell = cell_at(left, left_size, row, col);
+                let right_cell = cell_at(right, right_size, right_row, right_col);
 
                 if left_cell != right_cell {
-                    different_cells += 1;
-                    if cell_diffs.len() < self.max_cell_diffs_per_sheet {
-                        cell_diffs.push(CellDiff {
+                    diff.different_cells += 1;
+                    if diff.cell_diffs.len() < self.max_cell_diffs_per_sheet {
+                        diff.cell_diffs.push(CellDiff {
                             row,
                             col,
+                            right_row,
+                            right_col,
                             left_value: self.format_cell(left_cell),
                             right_value: self.format_cell(right_cell),
                         });
@@ -269,14 +432,7 @@ impl ExcelDiffEngine {
             }
         }
 
-        SheetDiff {
-            sheet_name: sheet_name.to_string(),
-            diff_type: SheetDiffType::Modified,
-            total_rows,
-            total_cols,
-            different_cells,
-            cell_diffs,
-        }
+        diff
     }
 
     fn format_cell(&self, cell: Option<&Data>) -> String {
@@ -311,6 +467,138 @@ impl Default for ExcelDiffEngine {
     }
 }
 
+/// Cell at `(row, col)`, or `None` outside a range of the given size
+fn cell_at(
+    range: &Range<Data>,
+    (rows, cols): (usize, usize),
+    row: usize,
+    col: usize,
+) -> Option<&Data> {
+    if row < rows && col < cols {
+        range.get_value((row as u32, col as u32))
+    } else {
+        None
+    }
+}
+
+/// Merged ranges of a sheet in A1 notation
+///
+/// Only XLSX and XLS expose merged cells; other formats yield an empty set.
+fn merged_ranges<RS: Read + Seek>(workbook: &mut Sheets<RS>, sheet_name: &str) -> BTreeSet<String> {
+    let regions = match workbook {
+        Sheets::Xlsx(xlsx) => xlsx
+            .worksheet_merge_cells(sheet_name)
+            .and_then(Result::ok)
+            .unwrap_or_default(),
+        Sheets::Xls(xls) => xls.worksheet_merge_cells(sheet_name).unwrap_or_default(),
+        Sheets::Xlsb(_) | Sheets::Ods(_) => Vec::new(),
+    };
+    regions.iter().map(a1_range).collect()
+}
+
+/// Format a region as an A1-style range, e.g. `A1:C2`
+fn a1_range(region: &Dimensions) -> String {
+    let cell = |(row, col): (u32, u32)| {
+        let mut letters = Vec::new();
+        let mut n = col + 1;
+        while n > 0 {
+            n -= 1;
+            letters.push(b'A' + (n % 26) as u8);
+            n /= 26;
+        }
+        letters.reverse();
+        format!("{}{}", String::from_utf8_lossy(&letters), row + 1)
+    };
+    format!("{}:{}", cell(region.start), cell(region.end))
+}
+
+/// Diff two lists of `(name, refers_to)` defined names
+fn compare_defined_names(
+    left: &[(String, String)],
+    right: &[(String, String)],
+) -> Vec<NamedRangeDiff> {
+    let left: BTreeMap<&str, &str> = left.iter().map(|(n, r)| (n.as_str(), r.as_str())).collect();
+    let right: BTreeMap<&str, &str> = right
+        .iter()
+        .map(|(n, r)| (n.as_str(), r.as_str()))
+        .collect();
+    let names: BTreeSet<&str> = left.keys().chain(right.keys()).copied().collect();
+
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let (l, r) = (left.get(name).copied(), right.get(name).copied());
+            let diff_type = match (l, r) {
+                (Some(l), Some(r)) if l == r => return None,
+                (Some(_), Some(_)) => NamedRangeDiffType::Modified,
+                (Some(_), None) => NamedRangeDiffType::LeftOnly,
+                _ => NamedRangeDiffType::RightOnly,
+            };
+            Some(NamedRangeDiff {
+                name: name.to_string(),
+                diff_type,
+                left_refers_to: l.map(str::to_string),
+                right_refers_to: r.map(str::to_string),
+            })
+        })
+        .collect()
+}
+
+/// Number of charts in a workbook
+///
+/// For zip-based workbooks (XLSX/XLSM) every chart part under `xl/charts/`
+/// is counted, which covers embedded charts as well as chart sheets. Other
+/// formats only report chart sheets.
+fn count_charts<RS: Read + Seek>(path: &Path, workbook: &Sheets<RS>) -> usize {
+    if let Sheets::Xlsx(_) = workbook {
+        if let Some(count) = File::open(path)
+            .ok()
+            .and_then(|file| zip::ZipArchive::new(file).ok())
+            .map(|archive| {
+                archive
+                    .file_names()
+                    .filter(|name| name.starts_with("xl/charts/chart") && name.ends_with(".xml"))
+                    .count()
+            })
+        {
+            return count;
+        }
+    }
+    workbook
+        .sheets_metadata()
+        .iter()
+        .filter(|sheet| sheet.typ == SheetType::ChartSheet)
+        .count()
+}
+
+/// Align two sequences by longest common subsequence
+///
+/// Returns the index pairs to compare: matching items, plus items of a
+/// replaced run paired up positionally. Unpaired indices go to `removed`
+/// (left) and `added` (right).
+fn align(
+    left: &[String],
+    right: &[String],
+    removed: &mut Vec<usize>,
+    added: &mut Vec<usize>,
+) -> Vec<(usize, usize)> {
+    let mut pairs = Vec::new();
+    for op in capture_diff_slices(Algorithm::Myers, left, right) {
+        let (old, new) = (op.old_range(), op.new_range());
+        match op {
+            DiffOp::Equal { .. } | DiffOp::Replace { .. } => {
+                pairs.extend(old.clone().zip(new.clone()));
+                let paired = old.len().min(new.len());
+                removed.extend(old.skip(paired));
+                added.extend(new.skip(paired));
+            }
+            DiffOp::Delete { .. } => removed.extend(old),
+            DiffOp::Insert { .. } => added.extend(new),
+        }
+    }
+    pairs
+}
+
 /// Check if a file path appears to be an Excel file based on extension
 pub fn is_excel_file(path: &Path) -> bool {
     if let Some(ext) = path.extension() {
@@ -335,4 +623,216 @@ mod tests {
         assert!(!is_excel_file(Path::new("data.txt")));
         assert!(!is_excel_file(Path::new("data.csv")));
     }
+
+    fn sheet(rows: &[&[&str]]) -> Range<Data> {
+        let cols = rows.iter().map(|r| r.len()).max().unwrap_or(1);
+        let mut range = Range::new((0, 0), (rows.len() as u32 - 1, cols as u32 - 1));
+        for (r, row) in rows.iter().enumerate() {
+            for (c, value) in row.iter().enumerate() {
+                range.set_value((r as u32, c as u32), Data::String(value.to_string()));
+            }
+        }
+        range
+    }
+
+    #[test]
+    fn test_inserted_row_is_single_addition() {
+        let left = sheet(&[
+            &["id", "name"],
+            &["1", "alpha"],
+            &["2", "beta"],
+            &["3", "gamma"],
+            &["4", "delta"],
+        ]);
+        let right = sheet(&[
+            &["id", "name"],
+            &["1", "alpha"],
+            &["2", "beta"],
+            &["9", "inserted"],
+            &["3", "gamma"],
+            &["4", "DELTA"],
+        ]);
+
+        let diff = ExcelDiffEngine::new().compare_ranges("Sheet1", &left, &right);
+        assert_eq!(diff.added_rows, [3]);
+        assert!(diff.removed_rows.is_empty());
+        assert_eq!(diff.different_cells, 1);
+        let cell = &diff.cell_diffs[0];
+        assert_eq!((cell.row, cell.col, cell.right_row), (4, 1, 5));
+        assert_eq!(cell.right_value, "DELTA");
+
+        let positional = ExcelDiffEngine::new()
+            .with_alignment(false)
+            .compare_ranges("Sheet1", &left, &right);
+        assert!(positional.added_rows.is_empty());
+        assert_eq!(positional.different_cells, 6);
+    }
+
+    #[test]
+    fn test_inserted_column_is_aligned_by_header() {
+        let left = sheet(&[&["id", "name"], &["1", "alpha"], &["2", "beta"]]);
+        let right = sheet(&[
+            &["id", "score", "name"],
+            &["1", "10", "alpha"],
+            &["2", "20", "beta"],
+        ]);
+
+        let diff = ExcelDiffEngine::new().compare_ranges("Sheet1", &left, &right);
+        assert_eq!(diff.added_cols, [1]);
+        assert!(diff.added_rows.is_empty());
+        assert_eq!(diff.different_cells, 0);
+    }
+
+    /// Write a minimal one-sheet XLSX with optional merged range, defined
+    /// name and chart part
+    fn write_xlsx(
+        path: &Path,
+        merge: Option<&str>,
+        defined_name: Option<(&str, &str)>,
+        chart: bool,
+    ) {
+        use std::io::Write;
+
+        let names = defined_name
+            .map(|(name, refers_to)| {
+                format!(
+                    r#"<definedNames><definedName name="{}">{}</definedName></definedNames>"#,
+                    name, refers_to
+                )
+            })
+            .unwrap_or_default();
+        let merge = merge
+            .map(|range| {
+                format!(
+                    r#"<mergeCells count="1"><mergeCell ref="{}"/></mergeCells>"#,
+                    range
+                )
+            })
+            .unwrap_or_default();
+        let ns = r#"xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships""#;
+        let mut parts = vec![
+            (
+                "[Content_Types].xml".to_string(),
+                r#"<?xml version="1.0" encoding="UTF-8"?><Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="xml" ContentType="application/xml"/><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/><Override PartName="/xl/worksheets/sheet1.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/></Types>"#.to_string(),
+            ),
+            (
+                "_rels/.rels".to_string(),
+                r#"<?xml version="1.0" encoding="UTF-8"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/></Relationships>"#.to_string(),
+            ),
+            (
+                "xl/workbook.xml".to_string(),
+                format!(
+                    r#"<?xml version="1.0" encoding="UTF-8"?><workbook {}><sheets><sheet name="Sheet1" sheetId="1" r:id="rId1"/></sheets>{}</workbook>"#,
+                    ns, names
+                ),
+            ),
+            (
+                "xl/_rels/workbook.xml.rels".to_string(),
+                r#"<?xml version="1.0" encoding="UTF-8"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet1.xml"/></Relationships>"#.to_string(),
+            ),
+            (
+                "xl/worksheets/sheet1.xml".to_string(),
+                format!(
+                    r#"<?xml version="1.0" encoding="UTF-8"?><worksheet {}><sheetData><row r="1"><c r="A1"><v>1</v></c><c r="B1"><v>2</v></c></row><row r="2"><c r="A2"><v>3</v></c><c r="B2"><v>4</v></c></row></sheetData>{}</worksheet>"#,
+                    ns, merge
+                ),
+            ),
+        ];
+        if chart {
+            parts.push((
+                "xl/charts/chart1.xml".to_string(),
+                r#"<?xml version="1.0" encoding="UTF-8"?><c:chartSpace xmlns:c="http://schemas.openxmlformats.org/drawingml/2006/chart"/>"#.to_string(),
+            ));
+        }
+
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(path).unwrap());
+        for (name, content) in parts {
+            zip.start_file(name, zip::write::FileOptions::default())
+                .unwrap();
+            zip.write_all(content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn test_cancel_and_progress() {
+        let dir = tempfile::tempdir().unwrap();
+        let (left, right) = (dir.path().join("left.xlsx"), dir.path().join("right.xlsx"));
+        write_xlsx(&left, None, None, false);
+        write_xlsx(&right, None, None, false);
+        let engine = ExcelDiffEngine::new();
+
+        let reports = std::cell::RefCell::new(Vec::new());
+        let progress = |done: usize, total: usize| reports.borrow_mut().push((done, total));
+        let result = engine
+            .compare_files_with_cancel(&left, &right, &AtomicBool::new(false), Some(&progress))
+            .unwrap();
+        assert_eq!(result.identical_sheets, 1);
+        assert_eq!(*reports.borrow(), [(0, 1), (1, 1)]);
+
+        let err = engine
+            .compare_files_with_cancel(&left, &right, &AtomicBool::new(true), None)
+            .unwrap_err();
+        assert!(err.to_string().contains("Excel comparison cancelled"));
+    }
+
+    #[test]
+    fn test_merged_range_difference() {
+        let dir = tempfile::tempdir().unwrap();
+        let (left, right) = (dir.path().join("left.xlsx"), dir.path().join("right.xlsx"));
+        write_xlsx(&left, None, None, false);
+        write_xlsx(&right, Some("A1:B1"), None, false);
+
+        let same = ExcelDiffEngine::new().compare_files(&left, &left).unwrap();
+        assert!(!same.has_differences());
+
+        let result = ExcelDiffEngine::new().compare_files(&left, &right).unwrap();
+        assert_eq!(result.different_sheets, 1);
+        assert!(result.named_range_diffs.is_empty());
+        let sheet = &result.sheet_diffs[0];
+        assert_eq!(sheet.different_cells, 0);
+        assert!(sheet.merged_ranges_differ());
+        assert_eq!(sheet.added_merged_ranges, ["A1:B1"]);
+        assert!(sheet.removed_merged_ranges.is_empty());
+    }
+
+    #[test]
+    fn test_named_range_difference() {
+        let dir = tempfile::tempdir().unwrap();
+        let (left, right) = (dir.path().join("left.xlsx"), dir.path().join("right.xlsx"));
+        write_xlsx(&left, None, Some(("Totals", "Sheet1!$A$1:$A$2")), false);
+        write_xlsx(&right, None, Some(("Totals", "Sheet1!$B$1:$B$2")), false);
+
+        let result = ExcelDiffEngine::new().compare_files(&left, &right).unwrap();
+        assert_eq!(result.identical_sheets, 1);
+        assert!(result.has_differences());
+        let named = &result.named_range_diffs;
+        assert_eq!(named.len(), 1);
+        assert_eq!(named[0].name, "Totals");
+        assert_eq!(named[0].diff_type, NamedRangeDiffType::Modified);
+        assert_eq!(named[0].left_refers_to.as_deref(), Some("Sheet1!$A$1:$A$2"));
+        assert_eq!(
+            named[0].right_refers_to.as_deref(),
+            Some("Sheet1!$B$1:$B$2")
+        );
+    }
+
+    #[test]
+    fn test_chart_presence_is_flagged() {
+        let dir = tempfile::tempdir().unwrap();
+        let (left, right) = (dir.path().join("left.xlsx"), dir.path().join("right.xlsx"));
+        write_xlsx(&left, None, None, false);
+        write_xlsx(&right, None, None, true);
+
+        let result = ExcelDiffEngine::new().compare_files(&left, &right).unwrap();
+        assert_eq!((result.left_charts, result.right_charts), (0, 1));
+        assert!(result.charts_differ());
+        assert_eq!(result.identical_sheets, 1);
+    }
+
+    #[test]
+    fn test_a1_range() {
+        assert_eq!(a1_range(&Dimensions::new((0, 0), (1, 2))), "A1:C2");
+        assert_eq!(a1_range(&Dimensions::new((9, 25), (9, 27))), "Z10:AB10");
+    }
 }
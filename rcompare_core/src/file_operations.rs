@@ -304,6 +304,17 @@ impl FileOperations {
 
     /// Delete a file (with optional trash support)
     pub fn delete_file(&self, path: &Path) -> Result<OperationResult, RCompareError> {
+        self.delete(path)
+    }
+
+    /// Delete a file or directory.
+    ///
+    /// When trash support is enabled the path is moved to the platform trash
+    /// (the XDG trash on Linux, the Recycle Bin on Windows, `~/.Trash` on
+    /// macOS). If the platform refuses, for example on a network filesystem
+    /// without a trash directory, a [`RCompareError::Trash`] error is returned
+    /// and the path is left untouched rather than being deleted permanently.
+    pub fn delete(&self, path: &Path) -> Result<OperationResult, RCompareError> {
         if self.dry_run {
             info!("DRY RUN: Would delete {}", path.display());
             return Ok(OperationResult {
@@ -320,13 +331,23 @@ impl FileOperations {
             });
         }
 
-        let metadata = fs::metadata(path)?;
-        let bytes = metadata.len();
+        let metadata = fs::symlink_metadata(path)?;
+        let bytes = if metadata.is_dir() { 0 } else { metadata.len() };
 
         if self.use_trash {
             debug!("Moving {} to trash", path.display());
-            trash::delete(path).map_err(|e| RCompareError::Io(io::Error::other(e.to_string())))?;
+            trash::delete(path).map_err(|e| {
+                RCompareError::Trash(format!(
+                    "Could not move {} to trash, it was left in place: {}",
+                    path.display(),
+                    e
+                ))
+            })?;
             info!("Moved {} to trash", path.display());
+        } else if metadata.is_dir() {
+            debug!("Permanently deleting directory {}", path.display());
+            fs::remove_dir_all(path)?;
+            info!("Deleted {}", path.display());
         } else {
             debug!("Permanently deleting {}", path.display());
             fs::remove_file(path)?;
@@ -608,4 +629,73 @@ mod tests {
         // Different content should produce different hash
         assert_ne!(hash1, hash3);
     }
+
+    #[test]
+    fn test_delete_permanently() {
+        let temp = TempDir::new().unwrap();
+        let file = temp.path().join("file.txt");
+        let dir = temp.path().join("dir");
+        fs::write(&file, b"12345").unwrap();
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        fs::write(dir.join("nested/inner.txt"), b"inner").unwrap();
+
+        let ops = FileOperations::new(false, false);
+        let result = ops.delete(&file).unwrap();
+        assert!(result.success);
+        assert_eq!(result.bytes_processed, 5);
+        assert!(!file.exists());
+
+        let result = ops.delete(&dir).unwrap();
+        assert!(result.success);
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn test_delete_dry_run_keeps_file() {
+        let temp = TempDir::new().unwrap();
+        let file = temp.path().join("file.txt");
+        fs::write(&file, b"keep").unwrap();
+
+        let ops = FileOperations::new(true, true);
+        let result = ops.delete(&file).unwrap();
+
+        assert!(result.success);
+        assert!(file.exists());
+    }
+
+    #[test]
+    fn test_delete_missing_path_with_trash() {
+        let temp = TempDir::new().unwrap();
+        let missing = temp.path().join("missing.txt");
+
+        let ops = FileOperations::new(false, true);
+        let result = ops.delete(&missing);
+
+        assert!(matches!(result, Err(RCompareError::Io(_))));
+    }
+
+    // Note: This test moves a file into the current user's trash, so it is
+    // ignored by default and should be run manually on a desktop session
+    #[cfg(any(target_os = "linux", target_os = "windows"))]
+    #[test]
+    #[ignore]
+    fn test_delete_moves_to_trash() {
+        let temp = TempDir::new().unwrap();
+        let file = temp.path().join("rcompare-trash-test.txt");
+        fs::write(&file, b"trash me").unwrap();
+        let original = fs::canonicalize(&file).unwrap();
+
+        let ops = FileOperations::new(false, true);
+        let result = ops.delete(&file).unwrap();
+        assert!(result.success);
+        assert!(!file.exists());
+
+        let trashed: Vec<_> = trash::os_limited::list()
+            .unwrap()
+            .into_iter()
+            .filter(|item| item.original_path() == original)
+            .collect();
+        assert_eq!(trashed.len(), 1);
+        trash::os_limited::purge_all(trashed).unwrap();
+    }
 }
@@ -23,6 +23,29 @@
 //! This ensures that cached hashes are automatically invalidated when files
 //! are modified, preventing stale data.
 //!
+//! # On-Disk Format and Versioning
+//!
+//! The cache file starts with the magic bytes `RCHC` and a little-endian
+//! `u32` format version ([`CACHE_FORMAT_VERSION`]), followed by the bincode
+//! payload. When loading:
+//!
+//! - the current version is read as-is
+//! - a known older version is migrated in memory and rewritten in the
+//!   current format on the next [`persist()`](HashCache::persist); this
+//!   includes version 1, the original header-less bincode map
+//! - an unknown newer version (written by a later release) or an unreadable
+//!   file is ignored and the cache starts empty
+//!
+//! A cache is only an optimization, so the worst outcome of any of these is
+//! rehashing files, never wrong hashes or a failed startup.
+//!
+//! # In-Memory Mode
+//!
+//! [`HashCache::in_memory`] keeps hashes for the lifetime of the process
+//! only. [`HashCache::open_or_in_memory`] falls back to it when the cache
+//! directory cannot be created or written, as in read-only containers, so a
+//! comparison still runs there, just without persistence.
+//!
 //! # Examples
 //!
 //! Basic usage:
@@ -59,9 +82,18 @@
 use rcompare_common::{Blake3Hash, CacheKey, RCompareError};
 use std::collections::HashMap;
 use std::fs;
+use std::io::Write;
 use std::path::PathBuf;
 use std::sync::{Arc, RwLock};
-use tracing::{debug, warn};
+use tracing::{debug, instrument, warn, Span};
+
+/// Version of the on-disk cache format written by this release
+pub const CACHE_FORMAT_VERSION: u32 = 2;
+
+/// Magic bytes at the start of versioned cache files
+const CACHE_MAGIC: &[u8; 4] = b"RCHC";
+
+type CacheMap = HashMap<CacheKey, Blake3Hash>;
 
 /// Thread-safe in-memory and disk-backed BLAKE3 hash cache.
 ///
@@ -89,42 +121,125 @@ use tracing::{debug, warn};
 /// # }
 /// ```
 pub struct HashCache {
-    cache_dir: PathBuf,
+    /// `None` for an in-memory cache
+    cache_dir: Option<PathBuf>,
     memory_cache: Arc<RwLock<HashMap<CacheKey, Blake3Hash>>>,
 }
 
 impl HashCache {
+    #[instrument(
+        name = "load_cache",
+        skip_all,
+        fields(dir = %cache_dir.display(), entries = tracing::field::Empty)
+    )]
     pub fn new(cache_dir: PathBuf) -> Result<Self, RCompareError> {
         if !cache_dir.exists() {
             fs::create_dir_all(&cache_dir)?;
         }
 
-        let mut memory_cache = HashMap::new();
-
         // Load existing cache from disk
         let cache_file = cache_dir.join("hash_cache.bin");
-        if cache_file.exists() {
+        let memory_cache = if cache_file.exists() {
             match fs::read(&cache_file) {
-                Ok(data) => {
-                    if let Ok(cached_data) =
-                        bincode::deserialize::<HashMap<CacheKey, Blake3Hash>>(&data)
-                    {
-                        memory_cache = cached_data;
-                        debug!("Loaded {} entries from cache", memory_cache.len());
-                    }
-                }
+                Ok(data) => Self::decode(&data),
                 Err(e) => {
                     warn!("Failed to load cache file: {}", e);
+                    HashMap::new()
                 }
             }
-        }
+        } else {
+            HashMap::new()
+        };
+        Span::current().record("entries", memory_cache.len());
 
         Ok(Self {
-            cache_dir,
+            cache_dir: Some(cache_dir),
             memory_cache: Arc::new(RwLock::new(memory_cache)),
         })
     }
 
+    /// Create a cache that is never read from or written to disk
+    ///
+    /// [`persist()`](Self::persist) is a no-op for such a cache.
+    pub fn in_memory() -> Self {
+        Self {
+            cache_dir: None,
+            memory_cache: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Load the cache in `cache_dir`, or fall back to an in-memory cache
+    ///
+    /// The fallback, logged as a warning, is used when the directory cannot
+    /// be created or a file cannot be written to it.
+    pub fn open_or_in_memory(cache_dir: PathBuf) -> Self {
+        let opened = Self::new(cache_dir.clone()).and_then(|cache| {
+            // Probe with a temp file like the one persist() writes, so a
+            // read-only directory is caught now rather than after the comparison
+            tempfile::NamedTempFile::new_in(&cache_dir)?;
+            Ok(cache)
+        });
+        match opened {
+            Ok(cache) => cache,
+            Err(e) => {
+                warn!(
+                    "Cache directory {} is not writable ({}); hashes will not be persisted",
+                    cache_dir.display(),
+                    e
+                );
+                Self::in_memory()
+            }
+        }
+    }
+
+    /// Whether [`persist()`](Self::persist) writes to disk
+    pub fn is_persistent(&self) -> bool {
+        self.cache_dir.is_some()
+    }
+
+    /// Decode cache file contents, migrating older formats
+    fn decode(data: &[u8]) -> CacheMap {
+        let Some(rest) = data.strip_prefix(CACHE_MAGIC) else {
+            // Version 1: a bare bincode map without header
+            return match bincode::deserialize::<CacheMap>(data) {
+                Ok(entries) => {
+                    debug!("Migrated {} entries from cache format v1", entries.len());
+                    entries
+                }
+                Err(e) => {
+                    warn!("Discarding unreadable cache file: {}", e);
+                    HashMap::new()
+                }
+            };
+        };
+
+        let version = match rest.get(..4) {
+            Some(bytes) => u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+            None => {
+                warn!("Discarding truncated cache file");
+                return HashMap::new();
+            }
+        };
+        if version != CACHE_FORMAT_VERSION {
+            warn!(
+                "Ignoring cache format v{} (this build uses v{}); starting fresh",
+                version, CACHE_FORMAT_VERSION
+            );
+            return HashMap::new();
+        }
+
+        match bincode::deserialize::<CacheMap>(&rest[4..]) {
+            Ok(entries) => {
+                debug!("Loaded {} entries from cache", entries.len());
+                entries
+            }
+            Err(e) => {
+                warn!("Discarding unreadable cache file: {}", e);
+                HashMap::new()
+            }
+        }
+    }
+
     /// Get cached hash for a file
     pub fn get(&self, key: &CacheKey) -> Option<Blake3Hash> {
         self.memory_cache.read().ok()?.get(key).copied()
@@ -137,25 +252,41 @@ impl HashCache {
         }
     }
 
-    /// Persist cache to disk atomically
+    /// Persist cache to disk atomically; does nothing for an in-memory cache
+    #[instrument(
+        name = "persist_cache",
+        skip_all,
+        fields(entries = tracing::field::Empty, bytes = tracing::field::Empty)
+    )]
     pub fn persist(&self) -> Result<(), RCompareError> {
-        let cache_file = self.cache_dir.join("hash_cache.bin");
-        let temp_file = self.cache_dir.join("hash_cache.bin.tmp");
+        let Some(cache_dir) = &self.cache_dir else {
+            return Ok(());
+        };
+        let cache_file = cache_dir.join("hash_cache.bin");
 
         let cache = self
             .memory_cache
             .read()
             .map_err(|e| RCompareError::Cache(format!("Lock error: {}", e)))?;
 
-        let data =
-            bincode::serialize(&*cache).map_err(|e| RCompareError::Serialization(e.to_string()))?;
+        let mut data = CACHE_MAGIC.to_vec();
+        data.extend_from_slice(&CACHE_FORMAT_VERSION.to_le_bytes());
+        bincode::serialize_into(&mut data, &*cache)
+            .map_err(|e| RCompareError::Serialization(e.to_string()))?;
 
-        // Write to temporary file first
-        fs::write(&temp_file, data)?;
+        Span::current().record("entries", cache.len());
+        Span::current().record("bytes", data.len());
+
+        // Write to a uniquely named temporary file first, so concurrent
+        // processes sharing the cache directory never write the same file
+        let mut temp_file = tempfile::NamedTempFile::new_in(cache_dir)?;
+        temp_file.write_all(&data)?;
 
         // Atomically rename temporary file to final cache file
         // This ensures the cache file is never corrupted even if the process crashes
-        fs::rename(&temp_file, &cache_file)?;
+        temp_file
+            .persist(&cache_file)
+            .map_err(|e| RCompareError::Io(e.error))?;
 
         debug!("Persisted {} cache entries to disk (atomic)", cache.len());
 
@@ -227,4 +358,90 @@ mod tests {
             assert_eq!(cache.get(&key), Some(hash));
         }
     }
+
+    #[test]
+    fn test_unwritable_cache_dir_falls_back_to_memory() {
+        let temp = TempDir::new().unwrap();
+        // A regular file where the directory should be can never be created,
+        // even by root
+        let blocker = temp.path().join("blocker");
+        fs::write(&blocker, b"").unwrap();
+        let cache_dir = blocker.join("cache");
+        assert!(HashCache::new(cache_dir.clone()).is_err());
+
+        let cache = HashCache::open_or_in_memory(cache_dir.clone());
+        assert!(!cache.is_persistent());
+
+        let key = CacheKey {
+            path: PathBuf::from("test.txt"),
+            modified: SystemTime::now(),
+            size: 100,
+        };
+        cache.put(key.clone(), Blake3Hash([3; 32]));
+        assert_eq!(cache.get(&key), Some(Blake3Hash([3; 32])));
+        cache.persist().unwrap();
+        assert!(!cache_dir.exists());
+
+        let writable = HashCache::open_or_in_memory(temp.path().join("cache"));
+        assert!(writable.is_persistent());
+        // The probe leaves nothing behind
+        assert_eq!(fs::read_dir(temp.path().join("cache")).unwrap().count(), 0);
+    }
+
+    fn sample_entries() -> CacheMap {
+        (0..3u8)
+            .map(|i| {
+                let key = CacheKey {
+                    path: PathBuf::from(format!("file{}.txt", i)),
+                    modified: SystemTime::UNIX_EPOCH,
+                    size: i as u64,
+                };
+                (key, Blake3Hash([i; 32]))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_hash_cache_migrates_v1_file() {
+        let temp = TempDir::new().unwrap();
+        let cache_file = temp.path().join("hash_cache.bin");
+
+        // Version 1 wrote the bare bincode map
+        let entries = sample_entries();
+        fs::write(&cache_file, bincode::serialize(&entries).unwrap()).unwrap();
+
+        let cache = HashCache::new(temp.path().to_path_buf()).unwrap();
+        assert_eq!(cache.len(), entries.len());
+        for (key, hash) in &entries {
+            assert_eq!(cache.get(key), Some(*hash));
+        }
+
+        // Persisting rewrites the file in the current format
+        cache.persist().unwrap();
+        let data = fs::read(&cache_file).unwrap();
+        assert!(data.starts_with(CACHE_MAGIC));
+        assert_eq!(data[4..8], CACHE_FORMAT_VERSION.to_le_bytes());
+        assert_eq!(HashCache::new(temp.path().to_path_buf()).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_hash_cache_resets_on_newer_or_corrupt_file() {
+        let temp = TempDir::new().unwrap();
+        let cache_file = temp.path().join("hash_cache.bin");
+
+        let mut newer = CACHE_MAGIC.to_vec();
+        newer.extend_from_slice(&(CACHE_FORMAT_VERSION + 1).to_le_bytes());
+        newer.extend_from_slice(&bincode::serialize(&sample_entries()).unwrap());
+        fs::write(&cache_file, newer).unwrap();
+        assert!(HashCache::new(temp.path().to_path_buf())
+            .unwrap()
+            .is_empty());
+
+        for garbage in [&b"RCHC\x02"[..], b"not a cache", b""] {
+            fs::write(&cache_file, garbage).unwrap();
+            assert!(HashCache::new(temp.path().to_path_buf())
+                .unwrap()
+                .is_empty());
+        }
+    }
 }
@@ -1,9 +1,13 @@
+use crate::cancel::Monitor;
 use exif as kamadak_exif;
-use image::{DynamicImage, GenericImageView, Rgba, RgbaImage};
-use rcompare_common::RCompareError;
+use image::{DynamicImage, GenericImageView, GrayImage, ImageReader, Rgba, RgbaImage};
+use rcompare_common::{ImageError, RCompareError};
 use serde::Serialize;
 use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
 use std::path::Path;
+use std::sync::atomic::AtomicBool;
 
 /// EXIF metadata for an image
 #[derive(Debug, Clone, Default, Serialize)]
@@ -22,18 +26,46 @@ pub struct ExifMetadata {
     pub other_tags: HashMap<String, String>,
 }
 
-/// Difference in EXIF metadata between two images
+/// Category of an EXIF tag, used to group changes for display
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ExifField {
+    /// Camera make and model
+    CameraModel,
+    /// Capture timestamp
+    Timestamp,
+    /// Orientation flag (1-8), which changes how viewers rotate the image
+    Orientation,
+    /// GPS location
+    Gps,
+    /// Exposure settings (exposure time, aperture, ISO, focal length)
+    Exposure,
+    /// Software that last wrote the file
+    Software,
+    /// EXIF block present on one side only
+    Presence,
+}
+
+/// A single EXIF tag that differs between two images
 #[derive(Debug, Clone, Serialize)]
-pub struct ExifDifference {
+pub struct ExifFieldDiff {
+    pub field: ExifField,
     pub tag_name: String,
     pub left_value: Option<String>,
     pub right_value: Option<String>,
 }
 
+impl ExifFieldDiff {
+    /// Whether this change touches location data and should be surfaced
+    /// prominently
+    pub fn is_gps(&self) -> bool {
+        self.field == ExifField::Gps
+    }
+}
+
 /// Result of an image comparison
 #[derive(Debug, Clone, Serialize)]
 pub struct ImageDiffResult {
-    /// Total number of pixels
+    /// Number of pixels compared (masked-out pixels are not counted)
     pub total_pixels: u64,
     /// Number of different pixels
     pub different_pixels: u64,
@@ -51,8 +83,15 @@ pub struct ImageDiffResult {
     pub left_exif: Option<ExifMetadata>,
     /// EXIF metadata from right image
     pub right_exif: Option<ExifMetadata>,
-    /// Differences in EXIF metadata
-    pub exif_differences: Vec<ExifDifference>,
+    /// EXIF tags that differ, reported even when the pixels match
+    pub exif_diffs: Vec<ExifFieldDiff>,
+}
+
+impl ImageDiffResult {
+    /// Whether the GPS location differs between the two images
+    pub fn gps_changed(&self) -> bool {
+        self.exif_diffs.iter().any(ExifFieldDiff::is_gps)
+    }
 }
 
 /// Comparison mode for images
@@ -72,6 +111,37 @@ impl Default for ImageCompareMode {
     }
 }
 
+/// Rectangle of pixels, `x`/`y` being its top-left corner
+///
+/// Rectangles may extend past the image; they are clamped to its bounds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ImageRegion {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl ImageRegion {
+    pub fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Column and row ranges covered inside a `width` x `height` image
+    fn clamped(&self, width: u32, height: u32) -> (std::ops::Range<u32>, std::ops::Range<u32>) {
+        let x0 = self.x.min(width);
+        let y0 = self.y.min(height);
+        let x1 = self.x.saturating_add(self.width).min(width);
+        let y1 = self.y.saturating_add(self.height).min(height);
+        (x0..x1, y0..y1)
+    }
+}
+
 /// Engine for comparing images
 pub struct ImageDiffEngine {
     mode: ImageCompareMode,
@@ -79,6 +149,12 @@ pub struct ImageDiffEngine {
     compare_exif: bool,
     /// Pixel difference tolerance (0-255)
     tolerance: u8,
+    /// Regions excluded from the comparison
+    ignore_regions: Vec<ImageRegion>,
+    /// Mask image whose non-black pixels are excluded from the comparison
+    ignore_mask: Option<GrayImage>,
+    /// When set, only pixels inside this region are compared
+    region_of_interest: Option<ImageRegion>,
 }
 
 impl ImageDiffEngine {
@@ -87,6 +163,9 @@ impl ImageDiffEngine {
             mode: ImageCompareMode::default(),
             compare_exif: false,
             tolerance: 1,
+            ignore_regions: Vec::new(),
+            ignore_mask: None,
+            region_of_interest: None,
         }
     }
 
@@ -113,6 +192,70 @@ impl ImageDiffEngine {
         self.tolerance
     }
 
+    /// Exclude pixels inside any of `regions` from the difference metrics,
+    /// e.g. a clock widget in a screenshot
+    pub fn with_ignore_regions(mut self, regions: Vec<ImageRegion>) -> Self {
+        self.ignore_regions = regions;
+        self
+    }
+
+    /// Exclude pixels where `mask` is non-black
+    ///
+    /// The mask is aligned with the top-left corner of the images; pixels
+    /// outside it are compared as usual.
+    pub fn with_ignore_mask(mut self, mask: GrayImage) -> Self {
+        self.ignore_mask = Some(mask);
+        self
+    }
+
+    /// Compare only the pixels inside `region`, minus any ignored ones
+    pub fn with_region_of_interest(mut self, region: ImageRegion) -> Self {
+        self.region_of_interest = Some(region);
+        self
+    }
+
+    /// Which pixels of a `width` x `height` image take part in the
+    /// comparison, row-major; `None` when all of them do
+    fn comparison_mask(&self, width: u32, height: u32) -> Option<Vec<bool>> {
+        if self.ignore_regions.is_empty()
+            && self.ignore_mask.is_none()
+            && self.region_of_interest.is_none()
+        {
+            return None;
+        }
+
+        let index = |x: u32, y: u32| y as usize * width as usize + x as usize;
+        let mut mask = match self.region_of_interest {
+            Some(roi) => {
+                let mut mask = vec![false; width as usize * height as usize];
+                let (xs, ys) = roi.clamped(width, height);
+                for y in ys {
+                    for x in xs.clone() {
+                        mask[index(x, y)] = true;
+                    }
+                }
+                mask
+            }
+            None => vec![true; width as usize * height as usize],
+        };
+        for region in &self.ignore_regions {
+            let (xs, ys) = region.clamped(width, height);
+            for y in ys {
+                for x in xs.clone() {
+                    mask[index(x, y)] = false;
+                }
+            }
+        }
+        if let Some(ignore) = &self.ignore_mask {
+            for (x, y, pixel) in ignore.enumerate_pixels() {
+                if x < width && y < height && pixel[0] != 0 {
+                    mask[index(x, y)] = false;
+                }
+            }
+        }
+        Some(mask)
+    }
+
     /// Extract EXIF metadata from an image file
     fn extract_exif(&self, path: &Path) -> Option<ExifMetadata> {
         if !self.compare_exif {
@@ -175,7 +318,11 @@ impl ImageDiffEngine {
         if let Some(orient_field) =
             exif_data.get_field(kamadak_exif::Tag::Orientation, kamadak_exif::In::PRIMARY)
         {
-            metadata.orientation = Some(orient_field.display_value().to_string());
+            // Keep the raw 1-8 code so changes read as "1 -> 6"
+            metadata.orientation = Some(match orient_field.value.get_uint(0) {
+                Some(code) => code.to_string(),
+                None => orient_field.display_value().to_string(),
+            });
         }
         if let Some(sw_field) =
             exif_data.get_field(kamadak_exif::Tag::Software, kamadak_exif::In::PRIMARY)
@@ -198,108 +345,47 @@ impl ImageDiffEngine {
         &self,
         left: &Option<ExifMetadata>,
         right: &Option<ExifMetadata>,
-    ) -> Vec<ExifDifference> {
-        let mut differences = Vec::new();
+    ) -> Vec<ExifFieldDiff> {
+        type Getter = fn(&ExifMetadata) -> &Option<String>;
+        const FIELDS: &[(ExifField, &str, Getter)] = &[
+            (ExifField::CameraModel, "Make", |m| &m.make),
+            (ExifField::CameraModel, "Model", |m| &m.model),
+            (ExifField::Timestamp, "DateTime", |m| &m.datetime),
+            (ExifField::Exposure, "ExposureTime", |m| &m.exposure_time),
+            (ExifField::Exposure, "FNumber", |m| &m.f_number),
+            (ExifField::Exposure, "ISO", |m| &m.iso),
+            (ExifField::Exposure, "FocalLength", |m| &m.focal_length),
+            (ExifField::Gps, "GPSLatitude", |m| &m.gps_latitude),
+            (ExifField::Gps, "GPSLongitude", |m| &m.gps_longitude),
+            (ExifField::Orientation, "Orientation", |m| &m.orientation),
+            (ExifField::Software, "Software", |m| &m.software),
+        ];
 
         match (left, right) {
-            (Some(l), Some(r)) => {
-                // Compare common fields
-                if l.make != r.make {
-                    differences.push(ExifDifference {
-                        tag_name: "Make".to_string(),
-                        left_value: l.make.clone(),
-                        right_value: r.make.clone(),
-                    });
-                }
-                if l.model != r.model {
-                    differences.push(ExifDifference {
-                        tag_name: "Model".to_string(),
-                        left_value: l.model.clone(),
-                        right_value: r.model.clone(),
-                    });
-                }
-                if l.datetime != r.datetime {
-                    differences.push(ExifDifference {
-                        tag_name: "DateTime".to_string(),
-                        left_value: l.datetime.clone(),
-                        right_value: r.datetime.clone(),
-                    });
-                }
-                if l.exposure_time != r.exposure_time {
-                    differences.push(ExifDifference {
-                        tag_name: "ExposureTime".to_string(),
-                        left_value: l.exposure_time.clone(),
-                        right_value: r.exposure_time.clone(),
-                    });
-                }
-                if l.f_number != r.f_number {
-                    differences.push(ExifDifference {
-                        tag_name: "FNumber".to_string(),
-                        left_value: l.f_number.clone(),
-                        right_value: r.f_number.clone(),
-                    });
-                }
-                if l.iso != r.iso {
-                    differences.push(ExifDifference {
-                        tag_name: "ISO".to_string(),
-                        left_value: l.iso.clone(),
-                        right_value: r.iso.clone(),
-                    });
-                }
-                if l.focal_length != r.focal_length {
-                    differences.push(ExifDifference {
-                        tag_name: "FocalLength".to_string(),
-                        left_value: l.focal_length.clone(),
-                        right_value: r.focal_length.clone(),
-                    });
-                }
-                if l.gps_latitude != r.gps_latitude {
-                    differences.push(ExifDifference {
-                        tag_name: "GPSLatitude".to_string(),
-                        left_value: l.gps_latitude.clone(),
-                        right_value: r.gps_latitude.clone(),
-                    });
-                }
-                if l.gps_longitude != r.gps_longitude {
-                    differences.push(ExifDifference {
-                        tag_name: "GPSLongitude".to_string(),
-                        left_value: l.gps_longitude.clone(),
-                        right_value: r.gps_longitude.clone(),
-                    });
-                }
-                if l.orientation != r.orientation {
-                    differences.push(ExifDifference {
-                        tag_name: "Orientation".to_string(),
-                        left_value: l.orientation.clone(),
-                        right_value: r.orientation.clone(),
-                    });
-                }
-                if l.software != r.software {
-                    differences.push(ExifDifference {
-                        tag_name: "Software".to_string(),
-                        left_value: l.software.clone(),
-                        right_value: r.software.clone(),
-                    });
-                }
-            }
-            (Some(_), None) => {
-                differences.push(ExifDifference {
-                    tag_name: "EXIF Data".to_string(),
-                    left_value: Some("Present".to_string()),
-                    right_value: None,
-                });
-            }
-            (None, Some(_)) => {
-                differences.push(ExifDifference {
-                    tag_name: "EXIF Data".to_string(),
-                    left_value: None,
-                    right_value: Some("Present".to_string()),
-                });
-            }
-            (None, None) => {}
+            (Some(l), Some(r)) => FIELDS
+                .iter()
+                .filter(|(_, _, get)| get(l) != get(r))
+                .map(|(field, tag_name, get)| ExifFieldDiff {
+                    field: *field,
+                    tag_name: tag_name.to_string(),
+                    left_value: get(l).clone(),
+                    right_value: get(r).clone(),
+                })
+                .collect(),
+            (Some(_), None) => vec![ExifFieldDiff {
+                field: ExifField::Presence,
+                tag_name: "EXIF Data".to_string(),
+                left_value: Some("Present".to_string()),
+                right_value: None,
+            }],
+            (None, Some(_)) => vec![ExifFieldDiff {
+                field: ExifField::Presence,
+                tag_name: "EXIF Data".to_string(),
+                left_value: None,
+                right_value: Some("Present".to_string()),
+            }],
+            (None, None) => Vec::new(),
         }
-
-        differences
     }
 
     /// Compare two image files
@@ -307,26 +393,40 @@ impl ImageDiffEngine {
         &self,
         left: &Path,
         right: &Path,
+    ) -> Result<ImageDiffResult, RCompareError> {
+        self.compare(left, right, Monitor::default())
+    }
+
+    /// Compare two image files, stopping with an error once `cancel` is set
+    ///
+    /// The flag is checked before each image is decoded and then between
+    /// pixel rows; `progress` receives `(rows compared, image height)`.
+    pub fn compare_files_with_cancel(
+        &self,
+        left: &Path,
+        right: &Path,
+        cancel: &AtomicBool,
+        progress: Option<&dyn Fn(usize, usize)>,
+    ) -> Result<ImageDiffResult, RCompareError> {
+        self.compare(left, right, Monitor::new(cancel, progress))
+    }
+
+    fn compare(
+        &self,
+        left: &Path,
+        right: &Path,
+        monitor: Monitor,
     ) -> Result<ImageDiffResult, RCompareError> {
         // Extract EXIF metadata if enabled
         let left_exif = self.extract_exif(left);
         let right_exif = self.extract_exif(right);
 
-        let left_img = image::open(left).map_err(|e| {
-            RCompareError::Io(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                format!("Failed to open left image: {}", e),
-            ))
-        })?;
-
-        let right_img = image::open(right).map_err(|e| {
-            RCompareError::Io(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                format!("Failed to open right image: {}", e),
-            ))
-        })?;
+        monitor.check("Image comparison")?;
+        let left_img = load_image(left)?;
+        monitor.check("Image comparison")?;
+        let right_img = load_image(right)?;
 
-        self.compare_images_with_exif(&left_img, &right_img, left_exif, right_exif)
+        self.compare_pixels(&left_img, &right_img, left_exif, right_exif, monitor)
     }
 
     /// Compare two images (without EXIF)
@@ -345,12 +445,23 @@ impl ImageDiffEngine {
         right: &DynamicImage,
         left_exif: Option<ExifMetadata>,
         right_exif: Option<ExifMetadata>,
+    ) -> Result<ImageDiffResult, RCompareError> {
+        self.compare_pixels(left, right, left_exif, right_exif, Monitor::default())
+    }
+
+    fn compare_pixels(
+        &self,
+        left: &DynamicImage,
+        right: &DynamicImage,
+        left_exif: Option<ExifMetadata>,
+        right_exif: Option<ExifMetadata>,
+        monitor: Monitor,
     ) -> Result<ImageDiffResult, RCompareError> {
         let left_dims = left.dimensions();
         let right_dims = right.dimensions();
         let same_dimensions = left_dims == right_dims;
 
-        let exif_differences = self.compare_exif_metadata(&left_exif, &right_exif);
+        let exif_diffs = self.compare_exif_metadata(&left_exif, &right_exif);
 
         if !same_dimensions {
             // Images have different dimensions - consider fully different
@@ -366,7 +477,7 @@ impl ImageDiffEngine {
                 right_dimensions: right_dims,
                 left_exif,
                 right_exif,
-                exif_differences,
+                exif_diffs,
             });
         }
 
@@ -374,12 +485,24 @@ impl ImageDiffEngine {
         let right_rgba = right.to_rgba8();
 
         let (width, height) = left_dims;
-        let total_pixels = (width as u64) * (height as u64);
+        let mask = self.comparison_mask(width, height);
+        let total_pixels = match &mask {
+            Some(mask) => mask.iter().filter(|&&included| included).count() as u64,
+            None => (width as u64) * (height as u64),
+        };
         let mut different_pixels = 0u64;
         let mut total_diff = 0u64;
 
         for y in 0..height {
+            monitor.check("Image comparison")?;
+            monitor.report(y as usize, height as usize);
             for x in 0..width {
+                if let Some(mask) = &mask {
+                    if !mask[y as usize * width as usize + x as usize] {
+                        continue;
+                    }
+                }
+
                 let left_pixel = left_rgba.get_pixel(x, y);
                 let right_pixel = right_rgba.get_pixel(x, y);
 
@@ -393,8 +516,17 @@ impl ImageDiffEngine {
             }
         }
 
-        let difference_percentage = (different_pixels as f64 / total_pixels as f64) * 100.0;
-        let mean_diff = total_diff as f64 / (total_pixels as f64 * 4.0); // 4 channels
+        monitor.report(height as usize, height as usize);
+
+        // Everything may be masked out; that compares as identical
+        let (difference_percentage, mean_diff) = if total_pixels == 0 {
+            (0.0, 0.0)
+        } else {
+            (
+                (different_pixels as f64 / total_pixels as f64) * 100.0,
+                total_diff as f64 / (total_pixels as f64 * 4.0), // 4 channels
+            )
+        };
 
         Ok(ImageDiffResult {
             total_pixels,
@@ -406,11 +538,13 @@ impl ImageDiffEngine {
             right_dimensions: right_dims,
             left_exif,
             right_exif,
-            exif_differences,
+            exif_diffs,
         })
     }
 
     /// Create a difference visualization image
+    ///
+    /// Masked-out pixels are never highlighted.
     pub fn create_diff_image(
         &self,
         left: &DynamicImage,
@@ -429,14 +563,18 @@ impl ImageDiffEngine {
         let right_rgba = right.to_rgba8();
 
         let (width, height) = left_dims;
+        let mask = self.comparison_mask(width, height);
         let mut diff_image = RgbaImage::new(width, height);
 
         for y in 0..height {
             for x in 0..width {
                 let left_pixel = left_rgba.get_pixel(x, y);
                 let right_pixel = right_rgba.get_pixel(x, y);
+                let included = mask
+                    .as_ref()
+                    .is_none_or(|mask| mask[y as usize * width as usize + x as usize]);
 
-                let diff_pixel = if self.pixels_differ(left_pixel, right_pixel) {
+                let diff_pixel = if included && self.pixels_differ(left_pixel, right_pixel) {
                     // Highlight difference in red
                     Rgba([255, 0, 0, 255])
                 } else {
@@ -584,6 +722,106 @@ impl Default for ImageDiffEngine {
     }
 }
 
+/// Load an image for comparison
+///
+/// Every format of the `image` crate (PNG, JPEG, GIF, BMP, TIFF, WebP, ...)
+/// is always available. HEIC/HEIF and AVIF need the `image-heif` feature,
+/// which decodes them with the system libheif; without it they fail with
+/// [`ImageError::UnsupportedFormat`], as does any format no decoder
+/// recognises.
+pub fn load_image(path: &Path) -> Result<DynamicImage, ImageError> {
+    let mut header = Vec::with_capacity(64);
+    File::open(path)?.take(64).read_to_end(&mut header)?;
+    if let Some(format) = heif_format(&header) {
+        return load_heif(path, format);
+    }
+
+    let reader = ImageReader::open(path)?.with_guessed_format()?;
+    let Some(format) = reader.format() else {
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_uppercase())
+            .unwrap_or_else(|| "unknown".to_string());
+        return Err(ImageError::UnsupportedFormat(extension));
+    };
+    reader.decode().map_err(|e| match e {
+        image::ImageError::Unsupported(_) => {
+            ImageError::UnsupportedFormat(format!("{:?}", format).to_uppercase())
+        }
+        e => ImageError::Decode {
+            path: path.display().to_string(),
+            reason: e.to_string(),
+        },
+    })
+}
+
+/// Recognise HEIF containers holding HEIC or AVIF images by the brands of
+/// their leading `ftyp` box
+fn heif_format(header: &[u8]) -> Option<&'static str> {
+    if header.get(4..8) != Some(&b"ftyp"[..]) {
+        return None;
+    }
+    let box_len = u32::from_be_bytes(header[..4].try_into().ok()?) as usize;
+    // The major brand, then the minor version, then compatible brands
+    let major = header.get(8..12)?;
+    let compatible = header.get(16..box_len.min(header.len())).unwrap_or(&[]);
+    let brands: Vec<&[u8]> = std::iter::once(major)
+        .chain(compatible.chunks_exact(4))
+        .collect();
+    let any_of = |wanted: &[&[u8]]| brands.iter().any(|brand| wanted.contains(brand));
+
+    if any_of(&[b"avif", b"avis"]) {
+        Some("AVIF")
+    } else if any_of(&[
+        b"heic", b"heix", b"hevc", b"hevx", b"heim", b"heis", b"mif1",
+    ]) {
+        Some("HEIC")
+    } else {
+        None
+    }
+}
+
+/// Decode a HEIC or AVIF file with libheif
+#[cfg(feature = "image-heif")]
+fn load_heif(path: &Path, format: &str) -> Result<DynamicImage, ImageError> {
+    use libheif_rs::{ColorSpace, HeifContext, LibHeif, RgbChroma};
+
+    let decode_error = |reason: String| ImageError::Decode {
+        path: path.display().to_string(),
+        reason: format!("{}: {}", format, reason),
+    };
+    let context = HeifContext::read_from_file(&path.to_string_lossy())
+        .map_err(|e| decode_error(e.to_string()))?;
+    let handle = context
+        .primary_image_handle()
+        .map_err(|e| decode_error(e.to_string()))?;
+    let image = LibHeif::new()
+        .decode(&handle, ColorSpace::Rgb(RgbChroma::Rgba), None)
+        .map_err(|e| decode_error(e.to_string()))?;
+    let plane = image
+        .planes()
+        .interleaved
+        .ok_or_else(|| decode_error("no interleaved RGBA plane".to_string()))?;
+
+    // Rows may be padded beyond width * 4 bytes
+    let row_len = plane.width as usize * 4;
+    let pixels = plane
+        .data
+        .chunks(plane.stride)
+        .take(plane.height as usize)
+        .flat_map(|row| &row[..row_len])
+        .copied()
+        .collect();
+    RgbaImage::from_raw(plane.width, plane.height, pixels)
+        .map(DynamicImage::ImageRgba8)
+        .ok_or_else(|| decode_error("truncated pixel data".to_string()))
+}
+
+#[cfg(not(feature = "image-heif"))]
+fn load_heif(_path: &Path, format: &str) -> Result<DynamicImage, ImageError> {
+    Err(ImageError::UnsupportedFormat(format.to_string()))
+}
+
 /// Check if a file path appears to be an image based on extension
 pub fn is_image_file(path: &Path) -> bool {
     if let Some(ext) = path.extension() {
@@ -599,6 +837,9 @@ pub fn is_image_file(path: &Path) -> bool {
                 | "tiff"
                 | "tif"
                 | "webp"
+                | "heic"
+                | "heif"
+                | "avif"
                 | "pnm"
                 | "pbm"
                 | "pgm"
@@ -679,15 +920,187 @@ mod tests {
         assert_eq!(result.difference_percentage, 100.0);
     }
 
+    /// 10x10 grey images whose pixels differ only in the 3x2 block at (6, 1)
+    fn images_with_clock_region() -> (DynamicImage, DynamicImage) {
+        let left = RgbaImage::from_pixel(10, 10, Rgba([100, 100, 100, 255]));
+        let mut right = left.clone();
+        for y in 1..3 {
+            for x in 6..9 {
+                right.put_pixel(x, y, Rgba([255, 0, 0, 255]));
+            }
+        }
+        (
+            DynamicImage::ImageRgba8(left),
+            DynamicImage::ImageRgba8(right),
+        )
+    }
+
+    #[test]
+    fn test_ignore_regions_and_mask() {
+        let (left, right) = images_with_clock_region();
+
+        let result = ImageDiffEngine::new()
+            .compare_images(&left, &right)
+            .unwrap();
+        assert_eq!(result.different_pixels, 6);
+
+        let engine = ImageDiffEngine::new().with_ignore_regions(vec![ImageRegion::new(6, 1, 3, 2)]);
+        let result = engine.compare_images(&left, &right).unwrap();
+        assert_eq!(result.different_pixels, 0);
+        assert_eq!(result.total_pixels, 94);
+        let diff_image = engine.create_diff_image(&left, &right).unwrap();
+        assert!(diff_image.pixels().all(|p| p[1] == p[0]));
+
+        // Partly off-image rectangles are clamped instead of panicking
+        let engine =
+            ImageDiffEngine::new().with_ignore_regions(vec![ImageRegion::new(5, 0, u32::MAX, 3)]);
+        assert_eq!(
+            engine
+                .compare_images(&left, &right)
+                .unwrap()
+                .different_pixels,
+            0
+        );
+
+        let mut mask = GrayImage::new(9, 3);
+        for y in 1..3 {
+            for x in 6..9 {
+                mask.put_pixel(x, y, image::Luma([255]));
+            }
+        }
+        let engine = ImageDiffEngine::new().with_ignore_mask(mask);
+        let result = engine.compare_images(&left, &right).unwrap();
+        assert_eq!(result.different_pixels, 0);
+        assert_eq!(result.difference_percentage, 0.0);
+    }
+
+    #[test]
+    fn test_region_of_interest() {
+        let (left, right) = images_with_clock_region();
+
+        let engine = ImageDiffEngine::new().with_region_of_interest(ImageRegion::new(0, 4, 10, 6));
+        let result = engine.compare_images(&left, &right).unwrap();
+        assert_eq!(result.total_pixels, 60);
+        assert_eq!(result.different_pixels, 0);
+
+        let engine = ImageDiffEngine::new().with_region_of_interest(ImageRegion::new(7, 2, 50, 50));
+        let result = engine.compare_images(&left, &right).unwrap();
+        assert_eq!(result.total_pixels, 24);
+        assert_eq!(result.different_pixels, 2);
+
+        // A region entirely outside the image compares nothing
+        let engine = ImageDiffEngine::new().with_region_of_interest(ImageRegion::new(20, 20, 5, 5));
+        let result = engine.compare_images(&left, &right).unwrap();
+        assert_eq!(result.total_pixels, 0);
+        assert_eq!(result.difference_percentage, 0.0);
+    }
+
     #[test]
     fn test_is_image_file() {
         assert!(is_image_file(Path::new("test.png")));
         assert!(is_image_file(Path::new("test.JPG")));
         assert!(is_image_file(Path::new("test.jpeg")));
+        assert!(is_image_file(Path::new("IMG_0001.HEIC")));
+        assert!(is_image_file(Path::new("photo.avif")));
         assert!(!is_image_file(Path::new("test.txt")));
         assert!(!is_image_file(Path::new("test.rs")));
     }
 
+    #[test]
+    fn test_compare_tiff_files() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let left = temp.path().join("left.tiff");
+        let right = temp.path().join("right.tif");
+        let mut image = RgbaImage::from_pixel(8, 8, Rgba([10, 20, 30, 255]));
+        image.save(&left).unwrap();
+        for x in 0..8 {
+            image.put_pixel(x, 0, Rgba([250, 20, 30, 255]));
+        }
+        image.save(&right).unwrap();
+
+        let result = ImageDiffEngine::new().compare_files(&left, &right).unwrap();
+        assert_eq!(result.left_dimensions, (8, 8));
+        assert_eq!(result.different_pixels, 8);
+    }
+
+    #[test]
+    fn test_cancel_between_pixel_rows() {
+        use std::sync::atomic::Ordering;
+
+        let temp = tempfile::TempDir::new().unwrap();
+        let path = temp.path().join("image.png");
+        RgbaImage::new(16, 64).save(&path).unwrap();
+        let engine = ImageDiffEngine::new();
+
+        let cancel = AtomicBool::new(false);
+        let rows = std::cell::Cell::new(0);
+        let progress = |done: usize, total: usize| {
+            assert_eq!(total, 64);
+            rows.set(done);
+            if done == 8 {
+                cancel.store(true, Ordering::Relaxed);
+            }
+        };
+        let err = engine
+            .compare_files_with_cancel(&path, &path, &cancel, Some(&progress))
+            .unwrap_err();
+        assert!(err.to_string().contains("Image comparison cancelled"));
+        assert_eq!(rows.get(), 8);
+
+        let result = engine
+            .compare_files_with_cancel(&path, &path, &AtomicBool::new(false), Some(&|_, _| {}))
+            .unwrap();
+        assert_eq!(result.different_pixels, 0);
+    }
+
+    #[test]
+    fn test_unknown_format_is_reported_as_unsupported() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let png = temp.path().join("ok.png");
+        RgbaImage::new(2, 2).save(&png).unwrap();
+        let unknown = temp.path().join("scan.xyz");
+        std::fs::write(&unknown, b"not an image at all").unwrap();
+
+        let error = ImageDiffEngine::new()
+            .compare_files(&png, &unknown)
+            .unwrap_err();
+        assert!(
+            matches!(&error, RCompareError::Image(ImageError::UnsupportedFormat(f)) if f == "XYZ"),
+            "{error}"
+        );
+        assert_eq!(error.kind(), rcompare_common::ErrorKind::Unsupported);
+
+        // A corrupt file in a known format is a decode failure instead
+        let corrupt = temp.path().join("corrupt.png");
+        std::fs::write(&corrupt, b"\x89PNG\r\n\x1a\ntruncated").unwrap();
+        assert!(matches!(
+            load_image(&corrupt),
+            Err(ImageError::Decode { .. })
+        ));
+    }
+
+    #[cfg(not(feature = "image-heif"))]
+    #[test]
+    fn test_heic_and_avif_need_the_heif_feature() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let ftyp = |brand: &[u8; 4]| {
+            let mut bytes = vec![0, 0, 0, 24];
+            bytes.extend_from_slice(b"ftyp");
+            bytes.extend_from_slice(brand);
+            bytes.extend_from_slice(&[0, 0, 0, 0]);
+            bytes.extend_from_slice(b"mif1");
+            bytes.extend_from_slice(brand);
+            bytes
+        };
+        for (brand, name) in [(b"heic", "HEIC"), (b"avif", "AVIF")] {
+            let path = temp.path().join("photo");
+            std::fs::write(&path, ftyp(brand)).unwrap();
+            assert!(
+                matches!(load_image(&path), Err(ImageError::UnsupportedFormat(f)) if f == name)
+            );
+        }
+    }
+
     #[test]
     fn test_tolerance_adjustment() {
         // Create images with slight differences
@@ -759,4 +1172,82 @@ mod tests {
         assert_eq!(diffs[0].left_value, Some("Present".to_string()));
         assert_eq!(diffs[0].right_value, None);
     }
+
+    /// Encode a small JPEG and splice in an APP1 segment holding a minimal
+    /// little-endian EXIF block with just the Orientation tag
+    fn jpeg_with_orientation(orientation: u16) -> Vec<u8> {
+        let mut img = RgbaImage::new(8, 8);
+        for (x, y, pixel) in img.enumerate_pixels_mut() {
+            *pixel = Rgba([(x * 30) as u8, (y * 30) as u8, 128, 255]);
+        }
+        let mut jpeg = Vec::new();
+        DynamicImage::ImageRgba8(img)
+            .to_rgb8()
+            .write_to(
+                &mut std::io::Cursor::new(&mut jpeg),
+                image::ImageFormat::Jpeg,
+            )
+            .unwrap();
+
+        let mut tiff = Vec::new();
+        tiff.extend_from_slice(b"II*\0");
+        tiff.extend_from_slice(&8u32.to_le_bytes()); // IFD0 offset
+        tiff.extend_from_slice(&1u16.to_le_bytes()); // one entry
+        tiff.extend_from_slice(&0x0112u16.to_le_bytes()); // Orientation
+        tiff.extend_from_slice(&3u16.to_le_bytes()); // SHORT
+        tiff.extend_from_slice(&1u32.to_le_bytes()); // count
+        tiff.extend_from_slice(&orientation.to_le_bytes());
+        tiff.extend_from_slice(&[0, 0]); // value padding
+        tiff.extend_from_slice(&0u32.to_le_bytes()); // no next IFD
+
+        let mut app1 = vec![0xFF, 0xE1];
+        app1.extend_from_slice(&((2 + 6 + tiff.len()) as u16).to_be_bytes());
+        app1.extend_from_slice(b"Exif\0\0");
+        app1.extend_from_slice(&tiff);
+
+        // Insert right after the SOI marker
+        jpeg.splice(2..2, app1);
+        jpeg
+    }
+
+    #[test]
+    fn test_orientation_change_reported_when_pixels_match() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let left = temp.path().join("left.jpg");
+        let right = temp.path().join("right.jpg");
+        std::fs::write(&left, jpeg_with_orientation(1)).unwrap();
+        std::fs::write(&right, jpeg_with_orientation(6)).unwrap();
+
+        let engine = ImageDiffEngine::new().with_exif_compare(true);
+        let result = engine.compare_files(&left, &right).unwrap();
+
+        assert_eq!(result.different_pixels, 0);
+        assert_eq!(result.exif_diffs.len(), 1);
+        let diff = &result.exif_diffs[0];
+        assert_eq!(diff.field, ExifField::Orientation);
+        assert_eq!(diff.left_value.as_deref(), Some("1"));
+        assert_eq!(diff.right_value.as_deref(), Some("6"));
+        assert!(!result.gps_changed());
+    }
+
+    #[test]
+    fn test_gps_change_is_flagged() {
+        let left_exif = ExifMetadata {
+            gps_latitude: Some("48 deg 51 min 29 sec".to_string()),
+            model: Some("Pixel 8".to_string()),
+            ..Default::default()
+        };
+        let right_exif = ExifMetadata {
+            gps_latitude: Some("40 deg 41 min 21 sec".to_string()),
+            model: Some("Pixel 8".to_string()),
+            ..Default::default()
+        };
+
+        let engine = ImageDiffEngine::new().with_exif_compare(true);
+        let diffs = engine.compare_exif_metadata(&Some(left_exif), &Some(right_exif));
+
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(diffs[0].field, ExifField::Gps);
+        assert!(diffs[0].is_gps());
+    }
 }
@@ -4,6 +4,7 @@ use serde_json::Value as JsonValue;
 use serde_yml::Value as YamlValue;
 use std::collections::HashMap;
 use std::path::Path;
+use std::time::Duration;
 
 /// Result of a JSON/YAML comparison
 #[derive(Debug, Clone, Serialize)]
@@ -18,8 +19,11 @@ pub struct JsonDiffResult {
     pub right_only_paths: usize,
     /// Number of identical paths
     pub identical_paths: usize,
-    /// Detailed path differences (limited to first 100)
+    /// Detailed path differences, at most
+    /// [`with_max_detail`](JsonDiffEngine::with_max_detail) of them
     pub path_diffs: Vec<PathDiff>,
+    /// `path_diffs` stopped at the cap; the path counts still cover every path
+    pub truncated: bool,
 }
 
 /// Represents a difference in a specific path
@@ -47,23 +51,298 @@ pub enum PathDiffType {
     RightOnly,
 }
 
+/// Result of an NDJSON (JSON Lines) comparison
+#[derive(Debug, Clone, Serialize)]
+pub struct NdjsonDiffResult {
+    /// Total number of distinct records compared
+    pub total_records: usize,
+    /// Number of records present on both sides with identical content
+    pub identical_records: usize,
+    /// Number of records present on both sides with different content
+    pub changed_records: usize,
+    /// Number of records only in left
+    pub left_only_records: usize,
+    /// Number of records only in right
+    pub right_only_records: usize,
+    /// Detailed record differences, at most
+    /// [`with_max_detail`](JsonDiffEngine::with_max_detail) of them
+    pub record_diffs: Vec<RecordDiff>,
+    /// `record_diffs` stopped at the cap; the record counts still cover
+    /// every record
+    pub truncated: bool,
+}
+
+/// Represents a difference in a single NDJSON record
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordDiff {
+    /// Record key: the id field value as JSON, so `"7"` and `7` differ, or
+    /// `record N` for the Nth non-blank line when matching by position
+    pub key: String,
+    /// Type of difference
+    pub diff_type: RecordDiffType,
+    /// 1-based line number in the left file (if present)
+    pub left_line: Option<usize>,
+    /// 1-based line number in the right file (if present)
+    pub right_line: Option<usize>,
+    /// Path-level differences within the record (only for changed records)
+    pub path_diffs: Vec<PathDiff>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum RecordDiffType {
+    /// Record exists in both but its content differs
+    Changed,
+    /// Record only exists in left
+    LeftOnly,
+    /// Record only exists in right
+    RightOnly,
+}
+
+/// A parsed NDJSON record with its matching key and source line
+struct NdjsonRecord {
+    key: String,
+    line: usize,
+    value: JsonValue,
+}
+
 /// Engine for comparing JSON/YAML files
 pub struct JsonDiffEngine {
-    max_path_diffs: usize,
+    max_detail: usize,
+    /// Field used to match NDJSON records (None matches records by line)
+    ndjson_id_field: Option<String>,
+    /// Report an integer and a float as different even when their values
+    /// match (`1` != `1.0`)
+    strict_number_types: bool,
+    /// Largest absolute difference at which two floats are still equal
+    float_tolerance: f64,
+    /// Parse ISO-8601 strings as timestamps and treat timestamps at most
+    /// this far apart as equal (None compares them as plain strings)
+    date_tolerance: Option<Duration>,
 }
 
 impl JsonDiffEngine {
     pub fn new() -> Self {
         Self {
-            max_path_diffs: 100,
+            max_detail: 100,
+            ndjson_id_field: None,
+            strict_number_types: false,
+            float_tolerance: f64::EPSILON,
+            date_tolerance: None,
         }
     }
 
-    pub fn with_max_path_diffs(mut self, max: usize) -> Self {
-        self.max_path_diffs = max;
+    /// Keep details for at most `max` differing paths, or NDJSON records
+    /// (default 100)
+    ///
+    /// Differences past the cap are still counted, so memory stays bounded
+    /// on documents with millions of differences.
+    pub fn with_max_detail(mut self, max: usize) -> Self {
+        self.max_detail = max;
+        self
+    }
+
+    #[deprecated(note = "renamed to `with_max_detail`")]
+    pub fn with_max_path_diffs(self, max: usize) -> Self {
+        self.with_max_detail(max)
+    }
+
+    /// Match NDJSON records by the value of a top-level field instead of by
+    /// position.
+    ///
+    /// Records missing the field fall back to matching by record number.
+    pub fn with_ndjson_id_field(mut self, field: impl Into<String>) -> Self {
+        self.ndjson_id_field = Some(field.into());
+        self
+    }
+
+    /// Report `1` and `1.0` as different, for data where a serializer
+    /// switching between integer and float signals a type change.
+    ///
+    /// Off by default: numbers are compared by value regardless of
+    /// representation.
+    pub fn with_strict_number_types(mut self, enabled: bool) -> Self {
+        self.strict_number_types = enabled;
+        self
+    }
+
+    /// Treat floats at most `epsilon` apart as equal (default
+    /// [`f64::EPSILON`]).
+    ///
+    /// Also applies to integer/float pairs unless strict number types are
+    /// on.
+    pub fn with_float_tolerance(mut self, epsilon: f64) -> Self {
+        self.float_tolerance = epsilon.abs();
+        self
+    }
+
+    /// Parse strings that are ISO-8601 timestamps (RFC 3339, or without an
+    /// offset, taken as UTC) and treat two timestamps at most `tolerance`
+    /// apart as equal, so `2024-01-01T00:00:00Z` matches
+    /// `2024-01-01T00:00:00.000+00:00`. Off by default.
+    pub fn with_date_tolerance(mut self, tolerance: Duration) -> Self {
+        self.date_tolerance = Some(tolerance);
         self
     }
 
+    /// Compare two NDJSON (JSON Lines) files record by record
+    pub fn compare_ndjson_files(
+        &self,
+        left: &Path,
+        right: &Path,
+    ) -> Result<NdjsonDiffResult, RCompareError> {
+        let left_content = std::fs::read_to_string(left).map_err(|e| {
+            RCompareError::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Failed to read left NDJSON file: {}", e),
+            ))
+        })?;
+
+        let right_content = std::fs::read_to_string(right).map_err(|e| {
+            RCompareError::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("Failed to read right NDJSON file: {}", e),
+            ))
+        })?;
+
+        self.compare_ndjson_str(&left_content, &right_content)
+    }
+
+    /// Compare two NDJSON documents held in memory
+    pub fn compare_ndjson_str(
+        &self,
+        left: &str,
+        right: &str,
+    ) -> Result<NdjsonDiffResult, RCompareError> {
+        let left_records = self.parse_ndjson(left, "left")?;
+        let right_records = self.parse_ndjson(right, "right")?;
+
+        let mut right_by_key: HashMap<&str, &NdjsonRecord> =
+            right_records.iter().map(|r| (r.key.as_str(), r)).collect();
+
+        let mut identical_records = 0;
+        let mut changed_records = 0;
+        let mut left_only_records = 0;
+        let mut record_diffs = Vec::new();
+
+        for left_record in &left_records {
+            match right_by_key.remove(left_record.key.as_str()) {
+                Some(right_record) => {
+                    let diff = self.compare_json_values(&left_record.value, &right_record.value)?;
+                    if diff.different_paths == 0
+                        && diff.left_only_paths == 0
+                        && diff.right_only_paths == 0
+                    {
+                        identical_records += 1;
+                    } else {
+                        changed_records += 1;
+                        if record_diffs.len() < self.max_detail {
+                            record_diffs.push(RecordDiff {
+                                key: left_record.key.clone(),
+                                diff_type: RecordDiffType::Changed,
+                                left_line: Some(left_record.line),
+                                right_line: Some(right_record.line),
+                                path_diffs: diff.path_diffs,
+                            });
+                        }
+                    }
+                }
+                None => {
+                    left_only_records += 1;
+                    if record_diffs.len() < self.max_detail {
+                        record_diffs.push(RecordDiff {
+                            key: left_record.key.clone(),
+                            diff_type: RecordDiffType::LeftOnly,
+                            left_line: Some(left_record.line),
+                            right_line: None,
+                            path_diffs: Vec::new(),
+                        });
+                    }
+                }
+            }
+        }
+
+        // Remaining right records, reported in right-file order
+        let mut right_only_records = 0;
+        for right_record in &right_records {
+            if right_by_key.contains_key(right_record.key.as_str()) {
+                right_only_records += 1;
+                if record_diffs.len() < self.max_detail {
+                    record_diffs.push(RecordDiff {
+                        key: right_record.key.clone(),
+                        diff_type: RecordDiffType::RightOnly,
+                        left_line: None,
+                        right_line: Some(right_record.line),
+                        path_diffs: Vec::new(),
+                    });
+                }
+            }
+        }
+
+        let truncated =
+            changed_records + left_only_records + right_only_records > record_diffs.len();
+        Ok(NdjsonDiffResult {
+            total_records: identical_records
+                + changed_records
+                + left_only_records
+                + right_only_records,
+            identical_records,
+            changed_records,
+            left_only_records,
+            right_only_records,
+            record_diffs,
+            truncated,
+        })
+    }
+
+    /// Parse NDJSON text into keyed records, skipping blank lines
+    ///
+    /// Records without an id are keyed by their position among the records,
+    /// so blank lines don't shift the keys of the records after them. Ids are
+    /// rendered as JSON and the fallback `record N` is never valid JSON, so
+    /// the two can't collide.
+    fn parse_ndjson(&self, content: &str, side: &str) -> Result<Vec<NdjsonRecord>, RCompareError> {
+        let mut records = Vec::new();
+        let mut seen_keys: HashMap<String, usize> = HashMap::new();
+
+        for (idx, line) in content.lines().enumerate() {
+            let line_no = idx + 1;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let value: JsonValue = serde_json::from_str(line).map_err(|e| {
+                RCompareError::Serialization(format!(
+                    "Failed to parse {} NDJSON line {}: {}",
+                    side, line_no, e
+                ))
+            })?;
+
+            let base_key = self
+                .ndjson_id_field
+                .as_ref()
+                .and_then(|field| value.get(field))
+                .map(JsonValue::to_string)
+                .unwrap_or_else(|| format!("record {}", records.len() + 1));
+
+            // Disambiguate duplicate ids so every record keeps a unique key
+            let occurrence = seen_keys.entry(base_key.clone()).or_insert(0);
+            *occurrence += 1;
+            let key = if *occurrence == 1 {
+                base_key
+            } else {
+                format!("{}#{}", base_key, occurrence)
+            };
+
+            records.push(NdjsonRecord {
+                key,
+                line: line_no,
+                value,
+            });
+        }
+
+        Ok(records)
+    }
+
     /// Compare two JSON files
     pub fn compare_json_files(
         &self,
@@ -84,14 +363,23 @@ impl JsonDiffEngine {
             ))
         })?;
 
-        let left_json: JsonValue = serde_json::from_str(&left_content).map_err(|e| {
+        self.compare_json_str(&left_content, &right_content)
+    }
+
+    /// Compare two JSON documents held in memory
+    pub fn compare_json_str(
+        &self,
+        left_content: &str,
+        right_content: &str,
+    ) -> Result<JsonDiffResult, RCompareError> {
+        let left_json: JsonValue = serde_json::from_str(left_content).map_err(|e| {
             RCompareError::Io(std::io::Error::new(
                 std::io::ErrorKind::InvalidData,
                 format!("Failed to parse left JSON: {}", e),
             ))
         })?;
 
-        let right_json: JsonValue = serde_json::from_str(&right_content).map_err(|e| {
+        let right_json: JsonValue = serde_json::from_str(right_content).map_err(|e| {
             RCompareError::Io(std::io::Error::new(
                 std::io::ErrorKind::InvalidData,
                 format!("Failed to parse right JSON: {}", e),
@@ -121,14 +409,23 @@ impl JsonDiffEngine {
             ))
         })?;
 
-        let left_yaml: YamlValue = serde_yml::from_str(&left_content).map_err(|e| {
+        self.compare_yaml_str(&left_content, &right_content)
+    }
+
+    /// Compare two YAML documents held in memory
+    pub fn compare_yaml_str(
+        &self,
+        left_content: &str,
+        right_content: &str,
+    ) -> Result<JsonDiffResult, RCompareError> {
+        let left_yaml: YamlValue = serde_yml::from_str(left_content).map_err(|e| {
             RCompareError::Io(std::io::Error::new(
                 std::io::ErrorKind::InvalidData,
                 format!("Failed to parse left YAML: {}", e),
             ))
         })?;
 
-        let right_yaml: YamlValue = serde_yml::from_str(&right_content).map_err(|e| {
+        let right_yaml: YamlValue = serde_yml::from_str(right_content).map_err(|e| {
             RCompareError::Io(std::io::Error::new(
                 std::io::ErrorKind::InvalidData,
                 format!("Failed to parse right YAML: {}", e),
@@ -177,11 +474,11 @@ impl JsonDiffEngine {
 
             match (left_val, right_val) {
                 (Some(left), Some(right)) => {
-                    if values_equal(left, right) {
+                    if self.values_equal(left, right) {
                         identical_paths += 1;
                     } else {
                         different_paths += 1;
-                        if path_diffs.len() < self.max_path_diffs {
+                        if path_diffs.len() < self.max_detail {
                             let diff_type =
                                 if std::mem::discriminant(left) != std::mem::discriminant(right) {
                                     PathDiffType::TypeDifferent
@@ -200,7 +497,7 @@ impl JsonDiffEngine {
                 }
                 (Some(left), None) => {
                     left_only_paths += 1;
-                    if path_diffs.len() < self.max_path_diffs {
+                    if path_diffs.len() < self.max_detail {
                         path_diffs.push(PathDiff {
                             path: path.clone(),
                             diff_type: PathDiffType::LeftOnly,
@@ -211,7 +508,7 @@ impl JsonDiffEngine {
                 }
                 (None, Some(right)) => {
                     right_only_paths += 1;
-                    if path_diffs.len() < self.max_path_diffs {
+                    if path_diffs.len() < self.max_detail {
                         path_diffs.push(PathDiff {
                             path: path.clone(),
                             diff_type: PathDiffType::RightOnly,
@@ -224,6 +521,7 @@ impl JsonDiffEngine {
             }
         }
 
+        let truncated = different_paths + left_only_paths + right_only_paths > path_diffs.len();
         Ok(JsonDiffResult {
             total_paths,
             different_paths,
@@ -231,8 +529,116 @@ impl JsonDiffEngine {
             right_only_paths,
             identical_paths,
             path_diffs,
+            truncated,
         })
     }
+
+    /// The differences between two documents as an RFC 6902 JSON Patch
+    ///
+    /// Applying the patch to `left` yields `right`. Objects are matched by
+    /// key and arrays by index, as in [`JsonDiffResult::path_diffs`], so an
+    /// array that grew or shrank gets `add` or `remove` operations at its
+    /// end, and values equal under the engine's tolerances are left alone.
+    /// The patch is built on demand and never truncated; unlike the path
+    /// counts it also covers empty arrays and objects.
+    pub fn json_patch(&self, left: &JsonValue, right: &JsonValue) -> JsonValue {
+        let mut ops = Vec::new();
+        self.patch_ops("", left, right, &mut ops);
+        JsonValue::Array(ops)
+    }
+
+    /// Append the JSON Patch operations turning `left` into `right`, located
+    /// at JSON Pointer `pointer`, to `ops`
+    fn patch_ops(
+        &self,
+        pointer: &str,
+        left: &JsonValue,
+        right: &JsonValue,
+        ops: &mut Vec<JsonValue>,
+    ) {
+        match (left, right) {
+            (JsonValue::Object(left_map), JsonValue::Object(right_map)) => {
+                for (key, left_value) in left_map {
+                    let child = format!("{}/{}", pointer, escape_pointer_token(key));
+                    match right_map.get(key) {
+                        Some(right_value) => self.patch_ops(&child, left_value, right_value, ops),
+                        None => ops.push(serde_json::json!({ "op": "remove", "path": child })),
+                    }
+                }
+                for (key, right_value) in right_map {
+                    if !left_map.contains_key(key) {
+                        let child = format!("{}/{}", pointer, escape_pointer_token(key));
+                        ops.push(serde_json::json!({
+                            "op": "add",
+                            "path": child,
+                            "value": right_value,
+                        }));
+                    }
+                }
+            }
+            (JsonValue::Array(left_items), JsonValue::Array(right_items)) => {
+                for (i, (left_value, right_value)) in left_items.iter().zip(right_items).enumerate()
+                {
+                    self.patch_ops(&format!("{}/{}", pointer, i), left_value, right_value, ops);
+                }
+                // Remove from the end so earlier indices stay valid
+                for i in (right_items.len()..left_items.len()).rev() {
+                    ops.push(serde_json::json!({
+                        "op": "remove",
+                        "path": format!("{}/{}", pointer, i),
+                    }));
+                }
+                for (i, right_value) in right_items.iter().enumerate().skip(left_items.len()) {
+                    ops.push(serde_json::json!({
+                        "op": "add",
+                        "path": format!("{}/{}", pointer, i),
+                        "value": right_value,
+                    }));
+                }
+            }
+            _ if self.values_equal(left, right) => {}
+            _ => ops.push(serde_json::json!({
+                "op": "replace",
+                "path": pointer,
+                "value": right,
+            })),
+        }
+    }
+
+    /// Check if two leaf JSON values are equal under the configured tolerances
+    fn values_equal(&self, left: &JsonValue, right: &JsonValue) -> bool {
+        match (left, right) {
+            (JsonValue::Null, JsonValue::Null) => true,
+            (JsonValue::Bool(a), JsonValue::Bool(b)) => a == b,
+            (JsonValue::Number(a), JsonValue::Number(b)) => {
+                if let (Some(a_i), Some(b_i)) = (a.as_i64(), b.as_i64()) {
+                    a_i == b_i
+                } else if let (Some(a_u), Some(b_u)) = (a.as_u64(), b.as_u64()) {
+                    a_u == b_u
+                } else if a.is_f64() != b.is_f64() && self.strict_number_types {
+                    false
+                } else {
+                    match (a.as_f64(), b.as_f64()) {
+                        (Some(a_f), Some(b_f)) => (a_f - b_f).abs() <= self.float_tolerance,
+                        _ => false,
+                    }
+                }
+            }
+            (JsonValue::String(a), JsonValue::String(b)) => {
+                a == b
+                    || self.date_tolerance.is_some_and(|tolerance| {
+                        match (parse_timestamp(a), parse_timestamp(b)) {
+                            (Some(a_t), Some(b_t)) => (a_t - b_t)
+                                .abs()
+                                .to_std()
+                                .is_ok_and(|delta| delta <= tolerance),
+                            _ => false,
+                        }
+                    })
+            }
+            _ => false,
+        }
+    }
 }
 
 impl Default for JsonDiffEngine {
@@ -262,26 +668,19 @@ fn flatten_json(prefix: &str, value: &JsonValue, output: &mut HashMap<String, Js
     }
 }
 
-/// Check if two JSON values are equal
-fn values_equal(left: &JsonValue, right: &JsonValue) -> bool {
-    match (left, right) {
-        (JsonValue::Null, JsonValue::Null) => true,
-        (JsonValue::Bool(a), JsonValue::Bool(b)) => a == b,
-        (JsonValue::Number(a), JsonValue::Number(b)) => {
-            // Compare numbers with some tolerance for floating point
-            if let (Some(a_f), Some(b_f)) = (a.as_f64(), b.as_f64()) {
-                (a_f - b_f).abs() < f64::EPSILON
-            } else if let (Some(a_i), Some(b_i)) = (a.as_i64(), b.as_i64()) {
-                a_i == b_i
-            } else if let (Some(a_u), Some(b_u)) = (a.as_u64(), b.as_u64()) {
-                a_u == b_u
-            } else {
-                false
-            }
-        }
-        (JsonValue::String(a), JsonValue::String(b)) => a == b,
-        _ => false,
+/// Escape an object key for use as a JSON Pointer token (RFC 6901)
+fn escape_pointer_token(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// Parse an ISO-8601 timestamp, taking one without an offset as UTC
+fn parse_timestamp(value: &str) -> Option<chrono::DateTime<chrono::Utc>> {
+    if let Ok(timestamp) = chrono::DateTime::parse_from_rfc3339(value) {
+        return Some(timestamp.to_utc());
     }
+    chrono::NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f")
+        .ok()
+        .map(|naive| naive.and_utc())
 }
 
 /// Format a JSON value as a string for display
@@ -297,7 +696,7 @@ fn format_json_value(value: &JsonValue) -> String {
 }
 
 /// Convert YAML value to JSON value
-fn yaml_to_json(yaml: YamlValue) -> JsonValue {
+pub(crate) fn yaml_to_json(yaml: YamlValue) -> JsonValue {
     match yaml {
         YamlValue::Null => JsonValue::Null,
         YamlValue::Bool(b) => JsonValue::Bool(b),
@@ -343,6 +742,16 @@ pub fn is_json_file(path: &Path) -> bool {
     }
 }
 
+/// Check if a file path appears to be NDJSON / JSON Lines based on extension
+pub fn is_ndjson_file(path: &Path) -> bool {
+    if let Some(ext) = path.extension() {
+        let ext = ext.to_string_lossy().to_lowercase();
+        matches!(ext.as_str(), "ndjson" | "jsonl")
+    } else {
+        false
+    }
+}
+
 /// Check if a file path appears to be YAML based on extension
 pub fn is_yaml_file(path: &Path) -> bool {
     if let Some(ext) = path.extension() {
@@ -398,6 +807,40 @@ mod tests {
         assert_eq!(result.path_diffs.len(), 1);
     }
 
+    #[test]
+    fn test_max_detail_caps_paths_but_keeps_totals() {
+        let document = |value: u32| {
+            let fields: Vec<String> = (0..30).map(|i| format!("\"k{}\": {}", i, value)).collect();
+            format!("{{{}}}", fields.join(", "))
+        };
+        let left = create_temp_json(&document(1));
+        let right = create_temp_json(&document(2));
+
+        let engine = JsonDiffEngine::new().with_max_detail(5);
+        let result = engine
+            .compare_json_files(left.path(), right.path())
+            .unwrap();
+        assert_eq!(result.different_paths, 30);
+        assert_eq!(result.path_diffs.len(), 5);
+        assert!(result.truncated);
+
+        let records = |value: u32| -> String {
+            (0..8)
+                .map(|i| format!("{{\"id\": {}, \"v\": {}}}\n", i, value))
+                .collect()
+        };
+        let result = engine.compare_ndjson_str(&records(1), &records(2)).unwrap();
+        assert_eq!(result.changed_records, 8);
+        assert_eq!(result.record_diffs.len(), 5);
+        assert!(result.truncated);
+
+        let result = JsonDiffEngine::new()
+            .compare_json_files(left.path(), right.path())
+            .unwrap();
+        assert_eq!(result.path_diffs.len(), 30);
+        assert!(!result.truncated);
+    }
+
     #[test]
     fn test_missing_keys() {
         let left = create_temp_json(r#"{"name": "test", "count": 42, "extra": "left"}"#);
@@ -427,6 +870,58 @@ mod tests {
         assert_eq!(result.different_paths, 1); // user.age
     }
 
+    #[test]
+    fn test_number_comparison() {
+        let left = create_temp_json(r#"{"count": 1, "ratio": 0.1, "big": 9007199254740993}"#);
+        let right =
+            create_temp_json(r#"{"count": 1.0, "ratio": 0.1000001, "big": 9007199254740992}"#);
+
+        // By value by default, with floats equal only within f64::EPSILON
+        let result = JsonDiffEngine::new()
+            .compare_json_files(left.path(), right.path())
+            .unwrap();
+        assert_eq!(result.identical_paths, 1); // count
+        assert_eq!(result.different_paths, 2);
+
+        let result = JsonDiffEngine::new()
+            .with_strict_number_types(true)
+            .compare_json_files(left.path(), right.path())
+            .unwrap();
+        assert_eq!(result.different_paths, 3);
+
+        let result = JsonDiffEngine::new()
+            .with_float_tolerance(1e-3)
+            .compare_json_files(left.path(), right.path())
+            .unwrap();
+        assert_eq!(result.identical_paths, 2); // count, ratio
+        assert_eq!(result.different_paths, 1);
+        // Large integers are compared exactly, never through f64
+        assert_eq!(result.path_diffs[0].path, "root.big");
+    }
+
+    #[test]
+    fn test_date_tolerance() {
+        let left = create_temp_json(
+            r#"{"created": "2024-05-01T12:00:00Z", "updated": "2024-05-01T12:00:00", "name": "a"}"#,
+        );
+        let right = create_temp_json(
+            r#"{"created": "2024-05-01T14:00:01.500+02:00", "updated": "2024-05-01T12:10:00", "name": "b"}"#,
+        );
+
+        let result = JsonDiffEngine::new()
+            .compare_json_files(left.path(), right.path())
+            .unwrap();
+        assert_eq!(result.different_paths, 3);
+
+        let result = JsonDiffEngine::new()
+            .with_date_tolerance(Duration::from_secs(2))
+            .compare_json_files(left.path(), right.path())
+            .unwrap();
+        assert_eq!(result.identical_paths, 1); // created, within 1.5s
+        let paths: Vec<&str> = result.path_diffs.iter().map(|d| d.path.as_str()).collect();
+        assert_eq!(paths, ["root.name", "root.updated"]);
+    }
+
     #[test]
     fn test_is_json_file() {
         assert!(is_json_file(Path::new("data.json")));
@@ -435,6 +930,163 @@ mod tests {
         assert!(!is_json_file(Path::new("data.txt")));
     }
 
+    #[test]
+    fn test_is_ndjson_file() {
+        assert!(is_ndjson_file(Path::new("events.ndjson")));
+        assert!(is_ndjson_file(Path::new("events.JSONL")));
+        assert!(!is_ndjson_file(Path::new("data.json")));
+    }
+
+    #[test]
+    fn test_ndjson_by_id_field() {
+        let left = create_temp_json(
+            "{\"id\": 1, \"event\": \"start\"}\n{\"id\": 2, \"event\": \"tick\"}\n{\"id\": 3, \"event\": \"stop\"}\n",
+        );
+        // Record 2 changed, record 4 inserted before 3, trailing blank lines and no final newline
+        let right = create_temp_json(
+            "{\"id\": 1, \"event\": \"start\"}\n{\"id\": 2, \"event\": \"tock\"}\n{\"id\": 4, \"event\": \"pause\"}\n\n{\"id\": 3, \"event\": \"stop\"}",
+        );
+
+        let engine = JsonDiffEngine::new().with_ndjson_id_field("id");
+        let result = engine
+            .compare_ndjson_files(left.path(), right.path())
+            .unwrap();
+
+        assert_eq!(result.total_records, 4);
+        assert_eq!(result.identical_records, 2);
+        assert_eq!(result.changed_records, 1);
+        assert_eq!(result.left_only_records, 0);
+        assert_eq!(result.right_only_records, 1);
+
+        let changed = &result.record_diffs[0];
+        assert_eq!(changed.key, "2");
+        assert_eq!(changed.diff_type, RecordDiffType::Changed);
+        assert_eq!(changed.path_diffs[0].path, "root.event");
+
+        let inserted = &result.record_diffs[1];
+        assert_eq!(inserted.key, "4");
+        assert_eq!(inserted.diff_type, RecordDiffType::RightOnly);
+        assert_eq!(inserted.right_line, Some(3));
+    }
+
+    #[test]
+    fn test_ndjson_by_line() {
+        let engine = JsonDiffEngine::new();
+        let result = engine
+            .compare_ndjson_str(
+                "{\"a\": 1}\n{\"a\": 2}\n\n\n",
+                "{\"a\": 1}\n{\"a\": 3}\n{\"a\": 4}",
+            )
+            .unwrap();
+
+        assert_eq!(result.identical_records, 1);
+        assert_eq!(result.changed_records, 1);
+        assert_eq!(result.right_only_records, 1);
+        assert_eq!(result.record_diffs[0].key, "record 2");
+        assert_eq!(result.record_diffs[1].key, "record 3");
+    }
+
+    #[test]
+    fn test_ndjson_blank_lines_do_not_shift_record_keys() {
+        let engine = JsonDiffEngine::new();
+        let result = engine
+            .compare_ndjson_str(
+                "{\"a\": 1}\n{\"a\": 2}\n{\"a\": 3}\n",
+                "{\"a\": 1}\n\n{\"a\": 2}\n{\"a\": 3}\n",
+            )
+            .unwrap();
+        assert_eq!(result.identical_records, 3);
+        assert!(result.record_diffs.is_empty());
+    }
+
+    #[test]
+    fn test_ndjson_id_keys_keep_types_apart() {
+        let engine = JsonDiffEngine::new().with_ndjson_id_field("id");
+        let result = engine
+            .compare_ndjson_str(
+                "{\"id\": 7}\n{\"id\": \"record 2\"}\n{\"x\": 1}\n",
+                "{\"id\": \"7\"}\n{\"id\": \"record 2\"}\n{\"x\": 1}\n",
+            )
+            .unwrap();
+
+        // The string "7" is not the number 7, and the id "record 2" is not
+        // the fallback key of the second record
+        assert_eq!(result.identical_records, 2);
+        let keys: Vec<(&str, RecordDiffType)> = result
+            .record_diffs
+            .iter()
+            .map(|d| (d.key.as_str(), d.diff_type))
+            .collect();
+        assert_eq!(
+            keys,
+            [
+                ("7", RecordDiffType::LeftOnly),
+                ("\"7\"", RecordDiffType::RightOnly)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ndjson_invalid_line_reports_line_number() {
+        let engine = JsonDiffEngine::new();
+        let err = engine
+            .compare_ndjson_str("{\"a\": 1}\nnot json\n", "{\"a\": 1}\n")
+            .unwrap_err();
+        assert!(matches!(err, RCompareError::Serialization(_)));
+        assert!(err.to_string().contains("line 2"));
+    }
+
+    #[test]
+    fn test_json_patch_turns_left_into_right() {
+        let cases = [
+            (
+                r#"{"name": "a", "tags": [1, 2, 3], "meta": {"x": 1, "old": true}, "a/b": 1, "c~d": []}"#,
+                r#"{"name": "b", "tags": [1, 5], "meta": {"x": 1, "new": null}, "a/b": 2, "c~d": [{"k": 1}]}"#,
+            ),
+            (
+                r#"{"list": [1], "value": 3, "empty": {}}"#,
+                r#"{"list": [1, [2], {"y": 3}], "value": {"nested": [true]}}"#,
+            ),
+            (r#"[1, {"a": 2}, 3, 4]"#, r#"[0, {"a": 2, "b": 1}]"#),
+            (r#"{"same": 1}"#, r#"{"same": 1}"#),
+        ];
+
+        let engine = JsonDiffEngine::new();
+        for (left_text, right_text) in cases {
+            let left: JsonValue = serde_json::from_str(left_text).unwrap();
+            let right: JsonValue = serde_json::from_str(right_text).unwrap();
+            let result = engine.compare_json_str(left_text, right_text).unwrap();
+
+            let patch = engine.json_patch(&left, &right);
+            let ops = patch.as_array().unwrap();
+            assert_eq!(
+                ops.is_empty(),
+                result.path_diffs.is_empty(),
+                "{}",
+                left_text
+            );
+
+            let mut document = left;
+            let patch: json_patch::Patch = serde_json::from_value(patch).unwrap();
+            json_patch::patch(&mut document, &patch).unwrap();
+            assert_eq!(document, right, "{}", left_text);
+        }
+    }
+
+    #[test]
+    fn test_empty_containers_have_no_paths() {
+        let engine = JsonDiffEngine::new();
+        let result = engine
+            .compare_json_str(r#"{"a": [], "b": {}}"#, "{}")
+            .unwrap();
+        assert_eq!(result.total_paths, 0);
+        assert!(result.path_diffs.is_empty());
+
+        let left = serde_json::json!({"a": []});
+        let patch = engine.json_patch(&left, &serde_json::json!({}));
+        assert_eq!(patch, serde_json::json!([{"op": "remove", "path": "/a"}]));
+    }
+
     #[test]
     fn test_is_yaml_file() {
         assert!(is_yaml_file(Path::new("config.yaml")));
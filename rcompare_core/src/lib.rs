@@ -9,9 +9,11 @@
 //! RCompare follows the Czkawka architectural pattern with strict separation between
 //! core logic and presentation layers. This crate (`rcompare_core`) contains:
 //!
+//! - **One-call API**: [`compare_directories`] for the common two-directory case
 //! - **File tree comparison**: [`ComparisonEngine`] for two-way and three-way diffs
 //! - **Directory scanning**: [`FolderScanner`] with gitignore support
 //! - **Hash caching**: [`HashCache`] for persistent BLAKE3 hashes
+//! - **Synchronization**: [`execute_sync`] to copy (and mirror-delete) based on a comparison
 //! - **Specialized comparisons**: Text, binary, image, CSV, JSON, Excel, Parquet
 //! - **VFS abstraction**: Support for archives (ZIP, TAR, 7Z) and cloud storage (S3, SSH)
 //! - **Patch operations**: Parsing, applying, and serializing unified/context diffs
@@ -51,6 +53,19 @@
 //! # }
 //! ```
 //!
+//! The same comparison in a single call:
+//!
+//! ```no_run
+//! use rcompare_core::{compare_directories, CompareOptions};
+//! use std::path::Path;
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let options = CompareOptions::new().hash_verification(true);
+//! let diffs = compare_directories(Path::new("/left"), Path::new("/right"), &options)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
 //! # Specialized File Comparisons
 //!
 //! RCompare provides specialized diff engines for various file types:
@@ -114,15 +129,18 @@
 //!
 //! This crate supports optional features to reduce binary size and compile times:
 //!
-//! - **`default`**: Enables `cloud`, `archives`, and `specialized` features
-//! - **`cloud`**: Cloud storage support (S3, SSH/SFTP, WebDAV)
-//! - **`archives`**: Archive format support (ZIP, TAR, 7Z, RAR)
+//! - **`default`**: Enables `fs`, `cloud`, `archives`, and `specialized` features
+//! - **`fs`**: Scanning, folder comparison, hash cache, sync and the VFS layer
+//! - **`cloud`**: Cloud storage support (S3, SSH/SFTP, WebDAV); implies `fs`
+//! - **`archives`**: Archive format support (ZIP, TAR, 7Z, RAR); implies `fs`
 //! - **`specialized`**: All specialized file format comparisons (enables all `*-diff` features)
 //! - **`csv-diff`**: CSV file comparison
 //! - **`excel-diff`**: Excel workbook comparison
 //! - **`json-diff`**: JSON/YAML structural comparison
 //! - **`parquet-diff`**: Parquet DataFrame comparison
 //! - **`image-diff`**: Image pixel-level comparison with EXIF
+//! - **`image-heif`**: HEIC/HEIF and AVIF decoding for `image-diff` through
+//!   the system libheif; not part of `specialized`
 //!
 //! ## Example
 //!
@@ -133,6 +151,14 @@
 //! rcompare_core = { version = "0.1", default-features = false }
 //! ```
 //!
+//! Without `fs` the crate keeps the patch parser, serializer and engine and
+//! the text, binary and merge engines that work on in-memory content, and
+//! compiles for `wasm32-unknown-unknown`:
+//!
+//! ```sh
+//! cargo check -p rcompare_core --no-default-features --target wasm32-unknown-unknown
+//! ```
+//!
 //! To enable specific features:
 //!
 //! ```toml
@@ -140,18 +166,39 @@
 //! rcompare_core = { version = "0.1", default-features = false, features = ["archives", "csv-diff"] }
 //! ```
 
-// Core modules (always available)
+// Core modules (always available, including on wasm32)
 pub mod binary_diff;
-pub mod comparison;
-pub mod file_operations;
-pub mod hash_cache;
+pub mod content_kind;
 pub mod merge_engine;
+pub mod patch_builder;
+pub mod patch_compare;
 pub mod patch_engine;
 pub mod patch_parser;
 pub mod patch_serializer;
+pub mod result_views;
+pub mod similarity;
+pub mod text_diff;
+
+// Filesystem modules (feature-gated)
+#[cfg(feature = "fs")]
+pub mod comparison;
+#[cfg(feature = "fs")]
+pub mod diff_spill;
+#[cfg(feature = "fs")]
+pub mod file_operations;
+#[cfg(feature = "fs")]
+pub mod hash_cache;
+#[cfg(feature = "fs")]
+pub mod quick_compare;
+#[cfg(feature = "fs")]
 pub mod resumable_copy;
+#[cfg(feature = "fs")]
+pub mod scan_snapshot;
+#[cfg(feature = "fs")]
 pub mod scanner;
-pub mod text_diff;
+#[cfg(feature = "fs")]
+pub mod sync;
+#[cfg(feature = "fs")]
 pub mod vfs;
 
 // Specialized comparison modules (feature-gated)
@@ -170,19 +217,65 @@ pub mod json_diff;
 #[cfg(feature = "parquet-diff")]
 pub mod parquet_diff;
 
+#[cfg(any(
+    feature = "csv-diff",
+    feature = "excel-diff",
+    feature = "image-diff",
+    feature = "parquet-diff"
+))]
+mod cancel;
+
 // Core exports (always available)
-pub use binary_diff::BinaryDiffEngine;
-pub use comparison::ComparisonEngine;
+pub use binary_diff::{BinaryDiffEngine, ChunkKind};
+pub use content_kind::{
+    decode_text, detect_content_kind, detect_engine, detect_file_content_kind, detect_file_engine,
+    read_text_file, route_engines, route_pair, ContentKind, EngineKind, TextEncoding,
+};
+pub use merge_engine::{
+    render_merge_regions, IndentStyle, LineEnding, MergeEngine, MergeOptions, MergeRegion,
+    TextStyle,
+};
+pub use patch_builder::PatchBuilder;
+pub use patch_compare::{compare_patch_sets, compare_patches};
+pub use patch_engine::{ApplyMode, PatchEngine};
+pub use patch_parser::PatchParser;
+pub use patch_serializer::{PatchSerializer, TimestampPolicy};
+pub use result_views::{
+    filter_by_status, group_by_top_dir, next_with_status, previous_with_status, sort_by_size_delta,
+    summarize, summarize_three_way, three_way_dir_statuses, ComparisonSummary, StatusCounts,
+    ThreeWaySummary,
+};
+pub use text_diff::{TextDiffEngine, TextDiffStats};
+
+// Filesystem exports
+#[cfg(feature = "fs")]
+pub use comparison::{
+    add_ignored_nodes, find_case_collisions, format_manifest, load_manifest, parse_manifest,
+    ComparisonEngine, ComparisonMode, HashStats,
+};
+#[cfg(feature = "fs")]
+pub use diff_spill::{SpilledDiff, SpilledNodes};
+#[cfg(feature = "fs")]
 pub use file_operations::FileOperations;
+#[cfg(feature = "fs")]
 pub use hash_cache::HashCache;
-pub use merge_engine::MergeEngine;
-pub use patch_engine::PatchEngine;
-pub use patch_parser::PatchParser;
-pub use patch_serializer::PatchSerializer;
+#[cfg(feature = "fs")]
+pub use quick_compare::{
+    compare_directories, compare_directory_pairs, expand_glob_pairs, CompareOptions, PairComparison,
+};
+#[cfg(feature = "fs")]
 pub use resumable_copy::{CopyCheckpoint, ResumableCopy, ResumableResult};
-pub use scanner::FolderScanner;
-pub use text_diff::TextDiffEngine;
-pub use vfs::LocalVfs;
+#[cfg(feature = "fs")]
+pub use scan_snapshot::ScanSnapshot;
+#[cfg(feature = "fs")]
+pub use scanner::{FilteredScan, FolderScanner, IgnoredEntry, IncrementalScan};
+#[cfg(feature = "fs")]
+pub use sync::{
+    execute_plan, execute_plan_with_vfs, execute_sync, execute_sync_with_mtime_tolerance,
+    sync_archives, SyncAction, SyncActionKind, SyncMode, SyncPlanner, SyncSummary,
+};
+#[cfg(feature = "fs")]
+pub use vfs::{LocalVfs, MemVfs};
 
 // Feature-gated exports
 #[cfg(feature = "csv-diff")]
@@ -192,10 +285,19 @@ pub use csv_diff::{is_csv_file, CsvCompareMode, CsvDiffEngine, CsvDiffResult};
 pub use excel_diff::{is_excel_file, ExcelDiffEngine, ExcelDiffResult};
 
 #[cfg(feature = "image-diff")]
-pub use image_diff::{is_image_file, ImageCompareMode, ImageDiffEngine, ImageDiffResult};
+pub use image_diff::{
+    is_image_file, load_image, ImageCompareMode, ImageDiffEngine, ImageDiffResult, ImageRegion,
+};
+
+#[cfg(feature = "json-diff")]
+pub use json_diff::{
+    is_json_file, is_ndjson_file, is_yaml_file, JsonDiffEngine, JsonDiffResult, NdjsonDiffResult,
+};
 
 #[cfg(feature = "json-diff")]
-pub use json_diff::{is_json_file, is_yaml_file, JsonDiffEngine, JsonDiffResult};
+pub use merge_engine::StructuredMerge;
 
 #[cfg(feature = "parquet-diff")]
-pub use parquet_diff::{is_parquet_file, ParquetDiffEngine, ParquetDiffResult};
+pub use parquet_diff::{
+    is_parquet_file, ParquetCompareMode, ParquetDiffEngine, ParquetDiffResult, PhysicalDiff,
+};
@@ -2,28 +2,279 @@ use rcompare_common::error::RCompareError;
 use rcompare_common::types::{
     ConflictType, FileEntry, MergeConflict, MergeResolution, MergeResult, MergeSource,
 };
+use similar::{capture_diff_slices, Algorithm};
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 
+use crate::text_diff::{diff3_chunks, Diff3Chunk, Diff3Kind};
+
+#[cfg(feature = "json-diff")]
+use crate::json_diff::{is_json_file, is_yaml_file, yaml_to_json};
+#[cfg(feature = "json-diff")]
+use serde_json::Value as JsonValue;
+
+/// Result of merging the contents of a JSON or YAML document
+#[cfg(feature = "json-diff")]
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructuredMerge {
+    /// Merged document in canonical form (sorted keys, pretty-printed)
+    pub merged: String,
+    /// Paths (e.g. `root.server.port`) changed differently on both sides;
+    /// the merged document keeps the left value there
+    pub conflicts: Vec<String>,
+}
+
+#[cfg(feature = "json-diff")]
+impl StructuredMerge {
+    /// Whether every change merged without conflict
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+/// One stretch of a line-based three-way merge
+///
+/// Lines keep their line endings, so concatenating the lines of a merge
+/// without conflicts gives the merged text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MergeRegion {
+    /// Lines unchanged on both sides, or changed on one side or identically
+    /// on both
+    Resolved(Vec<String>),
+    /// Lines changed differently on both sides
+    Conflict {
+        base: Vec<String>,
+        left: Vec<String>,
+        right: Vec<String>,
+    },
+}
+
+impl MergeRegion {
+    pub fn is_conflict(&self) -> bool {
+        matches!(self, MergeRegion::Conflict { .. })
+    }
+}
+
+/// Render merge regions as text, with git-style markers around conflicts
+///
+/// This is the output of [`MergeEngine::merge_three_way`]; embedders that
+/// resolve conflicts themselves can render the regions after replacing
+/// conflicts with [`MergeRegion::Resolved`] ones.
+pub fn render_merge_regions(regions: &[MergeRegion]) -> String {
+    render_regions(regions, LineEnding::Lf)
+}
+
+/// Render merge regions with conflict marker lines ended by `eol`
+fn render_regions(regions: &[MergeRegion], eol: LineEnding) -> String {
+    let eol = eol.as_str();
+    let mut out = String::new();
+    for region in regions {
+        match region {
+            MergeRegion::Resolved(lines) => out.extend(lines.iter().map(String::as_str)),
+            MergeRegion::Conflict { left, right, .. } => {
+                for (marker, lines) in [("<<<<<<< left", left), ("=======", right)] {
+                    end_line(&mut out, eol);
+                    out.push_str(marker);
+                    out.push_str(eol);
+                    out.extend(lines.iter().map(String::as_str));
+                }
+                end_line(&mut out, eol);
+                out.push_str(">>>>>>> right");
+                out.push_str(eol);
+            }
+        }
+    }
+    out
+}
+
+/// Terminate the last line of `out` so a marker starts on a line of its own
+fn end_line(out: &mut String, eol: &str) {
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push_str(eol);
+    }
+}
+
+/// Line terminator of a text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+}
+
+impl LineEnding {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
+        }
+    }
+}
+
+/// Leading whitespace of one indentation level
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndentStyle {
+    Tabs,
+    /// The given number of spaces
+    Spaces(usize),
+}
+
+/// Dominant line ending and indentation of a text, from [`TextStyle::detect`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TextStyle {
+    /// `None` when the text has no line breaks
+    pub line_ending: Option<LineEnding>,
+    /// `None` when no line is indented
+    pub indent: Option<IndentStyle>,
+}
+
+impl TextStyle {
+    /// Detect the line ending and indentation most lines of `text` use
+    ///
+    /// Ties go to LF and to spaces. The width of a space indent is the most
+    /// common step between the indentation of consecutive lines.
+    pub fn detect(text: &str) -> Self {
+        let (mut crlf, mut lf) = (0, 0);
+        let (mut tabbed, mut spaced) = (0, 0);
+        let mut steps: HashMap<usize, usize> = HashMap::new();
+        let mut previous = 0;
+        for line in text.split_inclusive('\n') {
+            if line.ends_with("\r\n") {
+                crlf += 1;
+            } else if line.ends_with('\n') {
+                lf += 1;
+            }
+
+            let content = line.trim_end_matches(['\r', '\n']);
+            if content.trim().is_empty() {
+                continue;
+            }
+            if content.starts_with('\t') {
+                tabbed += 1;
+                continue;
+            }
+            let spaces = content.len() - content.trim_start_matches(' ').len();
+            if spaces > 0 {
+                spaced += 1;
+            }
+            if spaces > previous {
+                *steps.entry(spaces - previous).or_default() += 1;
+            }
+            previous = spaces;
+        }
+
+        let line_ending = match (crlf, lf) {
+            (0, 0) => None,
+            (crlf, lf) if crlf > lf => Some(LineEnding::Crlf),
+            _ => Some(LineEnding::Lf),
+        };
+        let indent = match (tabbed, spaced) {
+            (0, 0) => None,
+            (tabbed, spaced) if tabbed > spaced => Some(IndentStyle::Tabs),
+            _ => {
+                let width = steps
+                    .into_iter()
+                    .max_by_key(|&(width, count)| (count, std::cmp::Reverse(width)))
+                    .map_or(4, |(width, _)| width);
+                Some(IndentStyle::Spaces(width))
+            }
+        };
+        Self {
+            line_ending,
+            indent,
+        }
+    }
+
+    /// Rewrite `text` in this style
+    ///
+    /// Every line break becomes this line ending. Leading tabs become spaces
+    /// or leading spaces become tabs, level for level, when the indentation
+    /// of `text` differs from this one; a space indent of another width is
+    /// left alone.
+    pub fn apply(&self, text: &str) -> String {
+        let from = Self::detect(text).indent;
+        let mut out = String::with_capacity(text.len());
+        for line in text.split_inclusive('\n') {
+            let (content, eol) = if let Some(content) = line.strip_suffix("\r\n") {
+                (content, "\r\n")
+            } else if let Some(content) = line.strip_suffix('\n') {
+                (content, "\n")
+            } else {
+                (line, "")
+            };
+            let body = content.trim_start_matches([' ', '\t']);
+            let indent = &content[..content.len() - body.len()];
+
+            out.push_str(&reindent(indent, from, self.indent));
+            out.push_str(body);
+            if !eol.is_empty() {
+                out.push_str(self.line_ending.map_or(eol, LineEnding::as_str));
+            }
+        }
+        out
+    }
+}
+
+/// Convert leading whitespace between indentation styles
+fn reindent(indent: &str, from: Option<IndentStyle>, to: Option<IndentStyle>) -> Cow<'_, str> {
+    let tabs = indent.matches('\t').count();
+    let spaces = indent.len() - tabs;
+    match (from, to) {
+        (Some(from), Some(to)) if from == to => Cow::Borrowed(indent),
+        (Some(IndentStyle::Spaces(width)), Some(IndentStyle::Tabs)) => Cow::Owned(format!(
+            "{}{}",
+            "\t".repeat(tabs + spaces / width),
+            " ".repeat(spaces % width)
+        )),
+        (_, Some(IndentStyle::Spaces(width))) if tabs > 0 => {
+            Cow::Owned(" ".repeat(tabs * width + spaces))
+        }
+        _ => Cow::Borrowed(indent),
+    }
+}
+
+/// Settings of a [`MergeEngine`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MergeOptions {
+    /// Whether to automatically resolve trivial conflicts
+    pub auto_resolve: bool,
+    /// Whether text merges rewrite the base and right sides in the line
+    /// ending and indentation of left, the file merged into, so hunks taken
+    /// from right don't mix LF with CRLF or tabs with spaces
+    pub normalize_style: bool,
+}
+
+impl Default for MergeOptions {
+    fn default() -> Self {
+        Self {
+            auto_resolve: true,
+            normalize_style: false,
+        }
+    }
+}
+
 /// Engine for three-way merge operations
 pub struct MergeEngine {
-    /// Whether to automatically resolve trivial conflicts
-    auto_resolve: bool,
+    options: MergeOptions,
 }
 
 impl MergeEngine {
     /// Create a new merge engine with default settings
     pub fn new() -> Self {
-        Self {
-            auto_resolve: true,
-        }
+        Self::with_options(MergeOptions::default())
     }
 
     /// Create a merge engine with auto-resolution disabled
     pub fn without_auto_resolve() -> Self {
-        Self {
+        Self::with_options(MergeOptions {
             auto_resolve: false,
-        }
+            ..MergeOptions::default()
+        })
+    }
+
+    /// Create a merge engine with the given settings
+    pub fn with_options(options: MergeOptions) -> Self {
+        Self { options }
     }
 
     /// Perform a three-way merge between base, left, and right file trees
@@ -165,7 +416,7 @@ impl MergeEngine {
             // Only left modified
             (true, false) => Ok(MergeResult {
                 path: path.to_path_buf(),
-                resolution: if self.auto_resolve {
+                resolution: if self.options.auto_resolve {
                     MergeResolution::UseLeft
                 } else {
                     MergeResolution::AutoMerged
@@ -177,7 +428,7 @@ impl MergeEngine {
             // Only right modified
             (false, true) => Ok(MergeResult {
                 path: path.to_path_buf(),
-                resolution: if self.auto_resolve {
+                resolution: if self.options.auto_resolve {
                     MergeResolution::UseRight
                 } else {
                     MergeResolution::AutoMerged
@@ -281,7 +532,7 @@ impl MergeEngine {
             // File wasn't actually modified, just use the deletion
             Ok(MergeResult {
                 path: path.to_path_buf(),
-                resolution: if self.auto_resolve {
+                resolution: if self.options.auto_resolve {
                     if is_left_modified {
                         MergeResolution::UseRight
                     } else {
@@ -318,6 +569,179 @@ impl MergeEngine {
         }
     }
 
+    /// Three-way merge the contents of a JSON or YAML file
+    ///
+    /// The format is chosen from the file extension; other files return
+    /// `None` and should go through line-based merging.
+    #[cfg(feature = "json-diff")]
+    pub fn merge_structured(
+        &self,
+        path: &Path,
+        base: &str,
+        left: &str,
+        right: &str,
+    ) -> Result<Option<StructuredMerge>, RCompareError> {
+        if is_json_file(path) {
+            self.merge_json(base, left, right).map(Some)
+        } else if is_yaml_file(path) {
+            self.merge_yaml(base, left, right).map(Some)
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Three-way merge JSON documents at the value-tree level
+    ///
+    /// Object keys are merged independently, so additions of different keys
+    /// on each side combine cleanly, while the same key changed to different
+    /// values on both sides is a conflict. Arrays and scalars are merged as
+    /// whole values.
+    #[cfg(feature = "json-diff")]
+    pub fn merge_json(
+        &self,
+        base: &str,
+        left: &str,
+        right: &str,
+    ) -> Result<StructuredMerge, RCompareError> {
+        let parse = |content: &str, side: &str| {
+            serde_json::from_str::<JsonValue>(content).map_err(|e| {
+                RCompareError::Io(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("Failed to parse {} JSON: {}", side, e),
+                ))
+            })
+        };
+        let (merged, conflicts) = merge_documents(
+            &parse(base, "base")?,
+            &parse(left, "left")?,
+            &parse(right, "right")?,
+        );
+
+        let mut merged = serde_json::to_string_pretty(&merged)
+            .map_err(|e| RCompareError::Serialization(e.to_string()))?;
+        merged.push('\n');
+        Ok(StructuredMerge { merged, conflicts })
+    }
+
+    /// Three-way merge YAML documents at the value-tree level
+    ///
+    /// Documents are converted to the JSON value model used by
+    /// [`crate::JsonDiffEngine`] and merged like [`MergeEngine::merge_json`].
+    /// Comments, anchors and tags are not preserved.
+    #[cfg(feature = "json-diff")]
+    pub fn merge_yaml(
+        &self,
+        base: &str,
+        left: &str,
+        right: &str,
+    ) -> Result<StructuredMerge, RCompareError> {
+        let parse = |content: &str, side: &str| {
+            serde_yml::from_str::<serde_yml::Value>(content)
+                .map(yaml_to_json)
+                .map_err(|e| {
+                    RCompareError::Io(std::io::Error::new(
+                        std::io::ErrorKind::InvalidData,
+                        format!("Failed to parse {} YAML: {}", side, e),
+                    ))
+                })
+        };
+        let (merged, conflicts) = merge_documents(
+            &parse(base, "base")?,
+            &parse(left, "left")?,
+            &parse(right, "right")?,
+        );
+
+        let merged = serde_yml::to_string(&merged)
+            .map_err(|e| RCompareError::Serialization(e.to_string()))?;
+        Ok(StructuredMerge { merged, conflicts })
+    }
+
+    /// Three-way merge text line by line, marking conflicts git-style
+    ///
+    /// Conflicting lines are written between `<<<<<<< left`, `=======` and
+    /// `>>>>>>> right` markers. See [`merge_regions`](Self::merge_regions)
+    /// for the same merge without markers. With
+    /// [`MergeOptions::normalize_style`] the markers end with the line
+    /// ending of `left`.
+    pub fn merge_three_way(&self, base: &str, left: &str, right: &str) -> String {
+        let eol = self
+            .target_style(left)
+            .and_then(|style| style.line_ending)
+            .unwrap_or(LineEnding::Lf);
+        render_regions(&self.merge_regions(base, left, right), eol)
+    }
+
+    /// Three-way merge text line by line into resolved and conflicting
+    /// regions
+    ///
+    /// Each side is diffed against `base`. Base lines kept by both sides
+    /// anchor the merge; between anchors, a change made on only one side
+    /// (or identically on both) is taken, and different changes on both
+    /// sides become a [`MergeRegion::Conflict`]. Adjacent resolved lines are
+    /// combined into one region.
+    ///
+    /// With [`MergeOptions::normalize_style`], `base` and `right` are first
+    /// rewritten in the [`TextStyle`] of `left`, so a side that only differs
+    /// in line endings or indentation doesn't change every line.
+    pub fn merge_regions(&self, base: &str, left: &str, right: &str) -> Vec<MergeRegion> {
+        let (base, right) = match self.target_style(left) {
+            Some(style) => (
+                Cow::Owned(style.apply(base)),
+                Cow::Owned(style.apply(right)),
+            ),
+            None => (Cow::Borrowed(base), Cow::Borrowed(right)),
+        };
+        let base: Vec<&str> = base.split_inclusive('\n').collect();
+        let left: Vec<&str> = left.split_inclusive('\n').collect();
+        let right: Vec<&str> = right.split_inclusive('\n').collect();
+        let chunks = diff3_chunks(
+            &base,
+            &left,
+            &right,
+            &capture_diff_slices(Algorithm::Myers, &base, &left),
+            &capture_diff_slices(Algorithm::Myers, &base, &right),
+        );
+
+        let mut regions = Vec::new();
+        let mut resolved: Vec<String> = Vec::new();
+        let chunk = |lines: &[&str]| lines.iter().map(|line| line.to_string()).collect();
+        for Diff3Chunk {
+            kind,
+            base: b,
+            left: l,
+            right: r,
+        } in chunks
+        {
+            match kind {
+                Diff3Kind::Shared | Diff3Kind::RightOnly | Diff3Kind::BaseOnly => {
+                    resolved.extend(chunk(&right[r]))
+                }
+                Diff3Kind::LeftOnly => resolved.extend(chunk(&left[l])),
+                Diff3Kind::Conflict => {
+                    if !resolved.is_empty() {
+                        regions.push(MergeRegion::Resolved(std::mem::take(&mut resolved)));
+                    }
+                    regions.push(MergeRegion::Conflict {
+                        base: chunk(&base[b]),
+                        left: chunk(&left[l]),
+                        right: chunk(&right[r]),
+                    });
+                }
+            }
+        }
+        if !resolved.is_empty() {
+            regions.push(MergeRegion::Resolved(resolved));
+        }
+        regions
+    }
+
+    /// Style to normalize merged-in text to, if enabled
+    fn target_style(&self, left: &str) -> Option<TextStyle> {
+        self.options
+            .normalize_style
+            .then(|| TextStyle::detect(left))
+    }
+
     /// Check if an entry was modified compared to base
     fn is_modified(&self, base: &FileEntry, other: &FileEntry) -> bool {
         // Compare size first (quick check)
@@ -346,6 +770,79 @@ impl MergeEngine {
     }
 }
 
+/// Merge three documents, returning the canonical result and conflict paths
+#[cfg(feature = "json-diff")]
+fn merge_documents(
+    base: &JsonValue,
+    left: &JsonValue,
+    right: &JsonValue,
+) -> (JsonValue, Vec<String>) {
+    let mut conflicts = Vec::new();
+    let merged = merge_values("root", Some(base), Some(left), Some(right), &mut conflicts)
+        .unwrap_or(JsonValue::Null);
+    (canonicalize(merged), conflicts)
+}
+
+/// Merge one value; `None` means the value is absent (deleted or never added)
+#[cfg(feature = "json-diff")]
+fn merge_values(
+    path: &str,
+    base: Option<&JsonValue>,
+    left: Option<&JsonValue>,
+    right: Option<&JsonValue>,
+    conflicts: &mut Vec<String>,
+) -> Option<JsonValue> {
+    if left == right || right == base {
+        return left.cloned();
+    }
+    if left == base {
+        return right.cloned();
+    }
+
+    // Both sides changed: descend into objects so distinct keys combine
+    if let (Some(JsonValue::Object(l)), Some(JsonValue::Object(r))) = (left, right) {
+        let empty = serde_json::Map::new();
+        let b = match base {
+            Some(JsonValue::Object(b)) => b,
+            _ => &empty,
+        };
+        let keys: std::collections::BTreeSet<&String> =
+            b.keys().chain(l.keys()).chain(r.keys()).collect();
+
+        let mut merged = serde_json::Map::new();
+        for key in keys {
+            let child = format!("{}.{}", path, key);
+            if let Some(value) = merge_values(&child, b.get(key), l.get(key), r.get(key), conflicts)
+            {
+                merged.insert(key.clone(), value);
+            }
+        }
+        return Some(JsonValue::Object(merged));
+    }
+
+    conflicts.push(path.to_string());
+    left.cloned()
+}
+
+/// Rebuild objects with sorted keys so output does not depend on map order
+#[cfg(feature = "json-diff")]
+fn canonicalize(value: JsonValue) -> JsonValue {
+    match value {
+        JsonValue::Object(map) => {
+            let mut entries: Vec<(String, JsonValue)> = map.into_iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            JsonValue::Object(
+                entries
+                    .into_iter()
+                    .map(|(k, v)| (k, canonicalize(v)))
+                    .collect(),
+            )
+        }
+        JsonValue::Array(items) => JsonValue::Array(items.into_iter().map(canonicalize).collect()),
+        other => other,
+    }
+}
+
 impl Default for MergeEngine {
     fn default() -> Self {
         Self::new()
@@ -355,6 +852,7 @@ impl Default for MergeEngine {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rcompare_common::types::FileType;
     use std::time::{Duration, SystemTime};
 
     fn create_file_entry(size: u64, modified: SystemTime) -> FileEntry {
@@ -363,6 +861,7 @@ mod tests {
             size,
             modified,
             is_dir: false,
+            file_type: FileType::Regular,
         }
     }
 
@@ -372,6 +871,7 @@ mod tests {
             size: 0,
             modified: SystemTime::now(),
             is_dir: true,
+            file_type: FileType::Directory,
         }
     }
 
@@ -680,4 +1180,179 @@ mod tests {
         assert_eq!(results[0].resolution, MergeResolution::UseBase);
         assert!(results[0].conflict.is_none());
     }
+
+    #[cfg(feature = "json-diff")]
+    #[test]
+    fn test_structured_merge_combines_distinct_key_additions() {
+        let engine = MergeEngine::new();
+        let base = r#"{"name": "app", "version": 1}"#;
+        let left = r#"{"name": "app", "version": 1, "a": true}"#;
+        let right = r#"{"b": [1, 2], "version": 1, "name": "app"}"#;
+
+        let result = engine
+            .merge_structured(Path::new("config.json"), base, left, right)
+            .unwrap()
+            .unwrap();
+        assert!(result.is_clean());
+        assert_eq!(
+            result.merged,
+            "{\n  \"a\": true,\n  \"b\": [\n    1,\n    2\n  ],\n  \"name\": \"app\",\n  \"version\": 1\n}\n"
+        );
+
+        // Output is canonical regardless of which side is passed first
+        let swapped = engine.merge_json(base, right, left).unwrap();
+        assert_eq!(swapped.merged, result.merged);
+    }
+
+    #[cfg(feature = "json-diff")]
+    #[test]
+    fn test_structured_merge_conflicts_on_same_key() {
+        let engine = MergeEngine::new();
+        let base = r#"{"server": {"port": 80, "host": "a"}, "debug": false}"#;
+        let left = r#"{"server": {"port": 8080, "host": "a"}, "debug": true}"#;
+        let right = r#"{"server": {"port": 9090, "host": "b"}, "debug": true}"#;
+
+        let result = engine.merge_json(base, left, right).unwrap();
+        assert_eq!(result.conflicts, ["root.server.port"]);
+
+        // Non-conflicting nested edits and identical edits still merge
+        let merged: JsonValue = serde_json::from_str(&result.merged).unwrap();
+        assert_eq!(merged["server"]["host"], "b");
+        assert_eq!(merged["server"]["port"], 8080);
+        assert_eq!(merged["debug"], true);
+    }
+
+    #[cfg(feature = "json-diff")]
+    #[test]
+    fn test_structured_merge_yaml_and_deletions() {
+        let engine = MergeEngine::new();
+        let base = "keep: 1\ndrop: 2\n";
+        let left = "keep: 1\n";
+        let right = "keep: 1\ndrop: 2\nadded: x\n";
+
+        let result = engine
+            .merge_structured(Path::new("values.yaml"), base, left, right)
+            .unwrap()
+            .unwrap();
+        assert!(result.is_clean());
+        assert_eq!(result.merged, "added: x\nkeep: 1\n");
+
+        // Deleting a key the other side edited is a conflict
+        let edited = "keep: 1\ndrop: 3\n";
+        let result = engine.merge_yaml(base, left, edited).unwrap();
+        assert_eq!(result.conflicts, ["root.drop"]);
+
+        assert!(engine
+            .merge_structured(Path::new("notes.txt"), "", "", "")
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn test_merge_regions_mixed_clean_and_conflict() {
+        let engine = MergeEngine::new();
+        let base = "a\nb\nc\nd\ne\n";
+        let left = "a\nB\nc\nd-left\ne\n";
+        let right = "a\nb\nc\nd-right\ne\nf\n";
+
+        let lines = |lines: &[&str]| lines.iter().map(|l| l.to_string()).collect::<Vec<_>>();
+        let regions = engine.merge_regions(base, left, right);
+        assert_eq!(
+            regions,
+            vec![
+                MergeRegion::Resolved(lines(&["a\n", "B\n", "c\n"])),
+                MergeRegion::Conflict {
+                    base: lines(&["d\n"]),
+                    left: lines(&["d-left\n"]),
+                    right: lines(&["d-right\n"]),
+                },
+                MergeRegion::Resolved(lines(&["e\n", "f\n"])),
+            ]
+        );
+
+        let marked = engine.merge_three_way(base, left, right);
+        assert_eq!(
+            marked,
+            "a\nB\nc\n<<<<<<< left\nd-left\n=======\nd-right\n>>>>>>> right\ne\nf\n"
+        );
+        assert_eq!(render_merge_regions(&regions), marked);
+    }
+
+    #[test]
+    fn test_merge_regions_clean_merge_has_no_conflicts() {
+        let engine = MergeEngine::new();
+        let base = "one\ntwo\nthree";
+        let left = "zero\none\ntwo\nthree";
+        let right = "one\ntwo\nthree!";
+
+        let regions = engine.merge_regions(base, left, right);
+        assert!(!regions.iter().any(MergeRegion::is_conflict));
+        assert_eq!(
+            engine.merge_three_way(base, left, right),
+            "zero\none\ntwo\nthree!"
+        );
+
+        // A conflict on an unterminated last line still gets markers on
+        // lines of their own
+        let marked = engine.merge_three_way(base, "one\ntwo\nthree?", right);
+        assert_eq!(
+            marked,
+            "one\ntwo\n<<<<<<< left\nthree?\n=======\nthree!\n>>>>>>> right\n"
+        );
+    }
+
+    #[test]
+    fn test_detect_text_style() {
+        assert_eq!(
+            TextStyle::detect("fn a() {\r\n\tb();\r\n\tif c {\r\n\t\td();\r\n\t}\n}\r\n"),
+            TextStyle {
+                line_ending: Some(LineEnding::Crlf),
+                indent: Some(IndentStyle::Tabs),
+            }
+        );
+        assert_eq!(
+            TextStyle::detect("a:\n  b:\n    c: 1\n  d: 2\n"),
+            TextStyle {
+                line_ending: Some(LineEnding::Lf),
+                indent: Some(IndentStyle::Spaces(2)),
+            }
+        );
+        assert_eq!(TextStyle::detect("flat"), TextStyle::default());
+    }
+
+    #[test]
+    fn test_normalize_style_merges_crlf_tabs_into_lf_spaces() {
+        let base = "fn main() {\n    a();\n}\n";
+        let left = "fn main() {\n    a();\n}\n\nfn left() {}\n";
+        // Right adds a line but was saved with CRLF and tab indentation
+        let right = "fn main() {\r\n\ta();\r\n\tb();\r\n}\r\n";
+
+        // Without normalization every right line differs from base
+        let raw = MergeEngine::new().merge_three_way(base, left, right);
+        assert!(raw.contains("<<<<<<< left"));
+
+        let engine = MergeEngine::with_options(MergeOptions {
+            normalize_style: true,
+            ..MergeOptions::default()
+        });
+        let merged = engine.merge_three_way(base, left, right);
+        assert_eq!(
+            merged,
+            "fn main() {\n    a();\n    b();\n}\n\nfn left() {}\n"
+        );
+        assert!(!merged.contains('\r') && !merged.contains('\t'));
+    }
+
+    #[test]
+    fn test_normalize_style_markers_follow_target_line_ending() {
+        let engine = MergeEngine::with_options(MergeOptions {
+            normalize_style: true,
+            ..MergeOptions::default()
+        });
+        let merged = engine.merge_three_way("a\nb\n", "a\r\nleft\r\n", "a\nright\n");
+        assert_eq!(
+            merged,
+            "a\r\n<<<<<<< left\r\nleft\r\n=======\r\nright\r\n>>>>>>> right\r\n"
+        );
+    }
 }
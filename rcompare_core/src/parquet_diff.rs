@@ -1,8 +1,10 @@
+use crate::cancel::Monitor;
 use polars::prelude::*;
 use rcompare_common::RCompareError;
 use serde::Serialize;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::path::Path;
+use std::sync::atomic::AtomicBool;
 
 /// Result of a Parquet/DataFrame comparison
 #[derive(Debug, Clone, Serialize)]
@@ -19,10 +21,16 @@ pub struct ParquetDiffResult {
     pub identical_rows: usize,
     /// Column names
     pub columns: Vec<String>,
-    /// Detailed row differences (limited to first N)
+    /// Detailed row differences, at most
+    /// [`with_max_detail`](ParquetDiffEngine::with_max_detail) of them
     pub row_diffs: Vec<RowDiff>,
+    /// `row_diffs` stopped at the cap; the row counts still cover every row
+    pub truncated: bool,
     /// Schema differences
     pub schema_diffs: Vec<SchemaDiff>,
+    /// Storage layout differences; only filled in
+    /// [`ParquetCompareMode::Physical`]
+    pub physical_diffs: Vec<PhysicalDiff>,
 }
 
 /// Represents a difference in a specific row
@@ -51,7 +59,9 @@ pub enum RowDiffType {
 /// Represents a difference in a specific column value
 #[derive(Debug, Clone, Serialize)]
 pub struct ColumnDiff {
-    /// Column name
+    /// Column name, followed by the path to the differing value for nested
+    /// columns: `.field` for struct fields, `[2]` for list elements and
+    /// `["key"]` for map entries (e.g. `address.city`, `tags[2]`)
     pub column: String,
     /// Left value (as string)
     pub left_value: String,
@@ -82,9 +92,43 @@ pub enum SchemaDiffType {
     TypeDifferent,
 }
 
+/// What two Parquet files have to share to count as equal
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub enum ParquetCompareMode {
+    /// Schema and values only; compression codecs, page encodings and
+    /// row-group boundaries are ignored
+    #[default]
+    Logical,
+    /// Also report how the files are laid out on disk
+    Physical,
+}
+
+/// Represents a difference in how the data is stored, such as the codec of a
+/// column chunk or the row-group boundaries
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PhysicalDiff {
+    /// What differs: `row_groups`, or `<column>: compression` and
+    /// `<column>: encodings` for a leaf column
+    pub property: String,
+    /// Left value
+    pub left_value: String,
+    /// Right value
+    pub right_value: String,
+}
+
+/// Storage properties of one Parquet file, gathered from its footer
+#[derive(Debug, Default)]
+struct StorageLayout {
+    /// Row count of every row group, in file order
+    row_groups: Vec<usize>,
+    /// Codecs and encodings used by each leaf column across all row groups
+    columns: BTreeMap<String, (BTreeSet<String>, BTreeSet<String>)>,
+}
+
 /// Engine for comparing Parquet files using Polars
 pub struct ParquetDiffEngine {
-    max_row_diffs: usize,
+    max_detail: usize,
+    mode: ParquetCompareMode,
     /// Columns to use as keys for row matching (if empty, use row index)
     key_columns: Vec<String>,
 }
@@ -92,13 +136,33 @@ pub struct ParquetDiffEngine {
 impl ParquetDiffEngine {
     pub fn new() -> Self {
         Self {
-            max_row_diffs: 100,
+            max_detail: 100,
+            mode: ParquetCompareMode::Logical,
             key_columns: Vec::new(),
         }
     }
 
-    pub fn with_max_row_diffs(mut self, max: usize) -> Self {
-        self.max_row_diffs = max;
+    /// Keep details for at most `max` differing rows (default 100)
+    ///
+    /// Rows past the cap are still counted, so memory stays bounded on
+    /// frames with millions of differences.
+    pub fn with_max_detail(mut self, max: usize) -> Self {
+        self.max_detail = max;
+        self
+    }
+
+    #[deprecated(note = "renamed to `with_max_detail`")]
+    pub fn with_max_row_diffs(self, max: usize) -> Self {
+        self.with_max_detail(max)
+    }
+
+    /// Choose between a logical (default) and a physical comparison
+    ///
+    /// Files holding the same data written with another codec, encoding or
+    /// row-group size are equal in logical mode; physical mode lists those
+    /// differences in [`ParquetDiffResult::physical_diffs`].
+    pub fn with_mode(mut self, mode: ParquetCompareMode) -> Self {
+        self.mode = mode;
         self
     }
 
@@ -112,8 +176,33 @@ impl ParquetDiffEngine {
         &self,
         left: &Path,
         right: &Path,
+    ) -> Result<ParquetDiffResult, RCompareError> {
+        self.compare_files(left, right, Monitor::default())
+    }
+
+    /// Compare two Parquet files, stopping with an error once `cancel` is set
+    ///
+    /// Each file is loaded in one go, so the flag is checked before each
+    /// load and then between rows; `progress` receives
+    /// `(rows compared, total rows)`.
+    pub fn compare_parquet_files_with_cancel(
+        &self,
+        left: &Path,
+        right: &Path,
+        cancel: &AtomicBool,
+        progress: Option<&dyn Fn(usize, usize)>,
+    ) -> Result<ParquetDiffResult, RCompareError> {
+        self.compare_files(left, right, Monitor::new(cancel, progress))
+    }
+
+    fn compare_files(
+        &self,
+        left: &Path,
+        right: &Path,
+        monitor: Monitor,
     ) -> Result<ParquetDiffResult, RCompareError> {
         // Read Parquet files using Polars
+        monitor.check("Parquet comparison")?;
         let left_df = LazyFrame::scan_parquet(left, ScanArgsParquet::default())
             .map_err(|e| {
                 RCompareError::Io(std::io::Error::new(
@@ -129,6 +218,7 @@ impl ParquetDiffEngine {
                 ))
             })?;
 
+        monitor.check("Parquet comparison")?;
         let right_df = LazyFrame::scan_parquet(right, ScanArgsParquet::default())
             .map_err(|e| {
                 RCompareError::Io(std::io::Error::new(
@@ -144,7 +234,14 @@ impl ParquetDiffEngine {
                 ))
             })?;
 
-        self.compare_dataframes(&left_df, &right_df)
+        let mut result = self.compare_frames(&left_df, &right_df, monitor)?;
+        if self.mode == ParquetCompareMode::Physical {
+            result.physical_diffs = compare_layouts(
+                &storage_layout(left, "left")?,
+                &storage_layout(right, "right")?,
+            );
+        }
+        Ok(result)
     }
 
     /// Compare two Polars DataFrames
@@ -152,6 +249,27 @@ impl ParquetDiffEngine {
         &self,
         left: &DataFrame,
         right: &DataFrame,
+    ) -> Result<ParquetDiffResult, RCompareError> {
+        self.compare_frames(left, right, Monitor::default())
+    }
+
+    /// Compare two Polars DataFrames, stopping with an error once `cancel`
+    /// is set; `progress` receives `(rows compared, total rows)`
+    pub fn compare_dataframes_with_cancel(
+        &self,
+        left: &DataFrame,
+        right: &DataFrame,
+        cancel: &AtomicBool,
+        progress: Option<&dyn Fn(usize, usize)>,
+    ) -> Result<ParquetDiffResult, RCompareError> {
+        self.compare_frames(left, right, Monitor::new(cancel, progress))
+    }
+
+    fn compare_frames(
+        &self,
+        left: &DataFrame,
+        right: &DataFrame,
+        monitor: Monitor,
     ) -> Result<ParquetDiffResult, RCompareError> {
         // Compare schemas
         let schema_diffs = self.compare_schemas(left.schema(), right.schema());
@@ -164,10 +282,10 @@ impl ParquetDiffEngine {
 
         // If key columns are specified, use them for matching
         if !self.key_columns.is_empty() {
-            self.compare_with_keys(left, right, columns, schema_diffs)
+            self.compare_with_keys(left, right, columns, schema_diffs, monitor)
         } else {
             // Compare row by row using index
-            self.compare_by_index(left, right, columns, schema_diffs)
+            self.compare_by_index(left, right, columns, schema_diffs, monitor)
         }
     }
 
@@ -224,6 +342,7 @@ impl ParquetDiffEngine {
         right: &DataFrame,
         columns: Vec<String>,
         schema_diffs: Vec<SchemaDiff>,
+        monitor: Monitor,
     ) -> Result<ParquetDiffResult, RCompareError> {
         let left_rows = left.height();
         let right_rows = right.height();
@@ -243,10 +362,12 @@ impl ParquetDiffEngine {
             .collect();
 
         for i in 0..max_rows {
+            monitor.check("Parquet comparison")?;
+            monitor.report(i, max_rows);
             if i >= left_rows {
                 // Row only in right
                 right_only_rows += 1;
-                if row_diffs.len() < self.max_row_diffs {
+                if row_diffs.len() < self.max_detail {
                     row_diffs.push(RowDiff {
                         left_row: None,
                         right_row: Some(i),
@@ -257,7 +378,7 @@ impl ParquetDiffEngine {
             } else if i >= right_rows {
                 // Row only in left
                 left_only_rows += 1;
-                if row_diffs.len() < self.max_row_diffs {
+                if row_diffs.len() < self.max_detail {
                     row_diffs.push(RowDiff {
                         left_row: Some(i),
                         right_row: None,
@@ -267,25 +388,13 @@ impl ParquetDiffEngine {
                 }
             } else {
                 // Compare rows
-                let mut col_diffs = Vec::new();
-                for col in &common_cols {
-                    let left_val = self.get_cell_value(left, col, i)?;
-                    let right_val = self.get_cell_value(right, col, i)?;
-
-                    if left_val != right_val {
-                        col_diffs.push(ColumnDiff {
-                            column: col.clone(),
-                            left_value: left_val,
-                            right_value: right_val,
-                        });
-                    }
-                }
+                let col_diffs = self.compare_row(left, right, &common_cols, i, i)?;
 
                 if col_diffs.is_empty() {
                     identical_rows += 1;
                 } else {
                     different_rows += 1;
-                    if row_diffs.len() < self.max_row_diffs {
+                    if row_diffs.len() < self.max_detail {
                         row_diffs.push(RowDiff {
                             left_row: Some(i),
                             right_row: Some(i),
@@ -297,6 +406,9 @@ impl ParquetDiffEngine {
             }
         }
 
+        monitor.report(max_rows, max_rows);
+
+        let truncated = different_rows + left_only_rows + right_only_rows > row_diffs.len();
         Ok(ParquetDiffResult {
             total_rows: max_rows,
             different_rows,
@@ -306,6 +418,8 @@ impl ParquetDiffEngine {
             columns,
             row_diffs,
             schema_diffs,
+            physical_diffs: Vec::new(),
+            truncated,
         })
     }
 
@@ -315,6 +429,7 @@ impl ParquetDiffEngine {
         right: &DataFrame,
         columns: Vec<String>,
         schema_diffs: Vec<SchemaDiff>,
+        monitor: Monitor,
     ) -> Result<ParquetDiffResult, RCompareError> {
         // Create key -> row index maps
         let left_keys = self.build_key_map(left)?;
@@ -337,32 +452,22 @@ impl ParquetDiffEngine {
             .cloned()
             .collect();
 
-        for key in &all_keys {
+        for (done, key) in all_keys.iter().enumerate() {
+            monitor.check("Parquet comparison")?;
+            monitor.report(done, all_keys.len());
             let left_idx = left_keys.get(key);
             let right_idx = right_keys.get(key);
 
             match (left_idx, right_idx) {
                 (Some(&li), Some(&ri)) => {
                     // Compare rows
-                    let mut col_diffs = Vec::new();
-                    for col in &common_cols {
-                        let left_val = self.get_cell_value(left, col, li)?;
-                        let right_val = self.get_cell_value(right, col, ri)?;
-
-                        if left_val != right_val {
-                            col_diffs.push(ColumnDiff {
-                                column: col.clone(),
-                                left_value: left_val,
-                                right_value: right_val,
-                            });
-                        }
-                    }
+                    let col_diffs = self.compare_row(left, right, &common_cols, li, ri)?;
 
                     if col_diffs.is_empty() {
                         identical_rows += 1;
                     } else {
                         different_rows += 1;
-                        if row_diffs.len() < self.max_row_diffs {
+                        if row_diffs.len() < self.max_detail {
                             row_diffs.push(RowDiff {
                                 left_row: Some(li),
                                 right_row: Some(ri),
@@ -374,7 +479,7 @@ impl ParquetDiffEngine {
                 }
                 (Some(&li), None) => {
                     left_only_rows += 1;
-                    if row_diffs.len() < self.max_row_diffs {
+                    if row_diffs.len() < self.max_detail {
                         row_diffs.push(RowDiff {
                             left_row: Some(li),
                             right_row: None,
@@ -385,7 +490,7 @@ impl ParquetDiffEngine {
                 }
                 (None, Some(&ri)) => {
                     right_only_rows += 1;
-                    if row_diffs.len() < self.max_row_diffs {
+                    if row_diffs.len() < self.max_detail {
                         row_diffs.push(RowDiff {
                             left_row: None,
                             right_row: Some(ri),
@@ -398,6 +503,9 @@ impl ParquetDiffEngine {
             }
         }
 
+        monitor.report(all_keys.len(), all_keys.len());
+
+        let truncated = different_rows + left_only_rows + right_only_rows > row_diffs.len();
         Ok(ParquetDiffResult {
             total_rows: all_keys.len(),
             different_rows,
@@ -407,6 +515,8 @@ impl ParquetDiffEngine {
             columns,
             row_diffs,
             schema_diffs,
+            physical_diffs: Vec::new(),
+            truncated,
         })
     }
 
@@ -426,12 +536,39 @@ impl ParquetDiffEngine {
         Ok(map)
     }
 
+    /// Differences between one row of each frame over `columns`
+    fn compare_row(
+        &self,
+        left: &DataFrame,
+        right: &DataFrame,
+        columns: &[String],
+        left_row: usize,
+        right_row: usize,
+    ) -> Result<Vec<ColumnDiff>, RCompareError> {
+        let mut diffs = Vec::new();
+        for col in columns {
+            let left_val = self.get_cell(left, col, left_row)?;
+            let right_val = self.get_cell(right, col, right_row)?;
+            diff_values(col.clone(), left_val, right_val, &mut diffs);
+        }
+        Ok(diffs)
+    }
+
     fn get_cell_value(
         &self,
         df: &DataFrame,
         column: &str,
         row: usize,
     ) -> Result<String, RCompareError> {
+        Ok(format!("{}", self.get_cell(df, column, row)?))
+    }
+
+    fn get_cell<'a>(
+        &self,
+        df: &'a DataFrame,
+        column: &str,
+        row: usize,
+    ) -> Result<AnyValue<'a>, RCompareError> {
         let series = df.column(column).map_err(|e| {
             RCompareError::Io(std::io::Error::new(
                 std::io::ErrorKind::InvalidData,
@@ -446,10 +583,217 @@ impl ParquetDiffEngine {
             ))
         })?;
 
-        Ok(format!("{}", val))
+        Ok(val)
+    }
+}
+
+/// Placeholder value for a struct field, list element or map entry that
+/// only exists on one side
+const MISSING: &str = "<missing>";
+
+/// Compare two values, descending into structs, lists and maps
+///
+/// Every differing leaf is reported at its own path below `path`. A null
+/// on either side is compared as a whole: a null struct against a
+/// non-null one is a single difference, not one per field.
+fn diff_values(path: String, left: AnyValue, right: AnyValue, out: &mut Vec<ColumnDiff>) {
+    if left.is_null() || right.is_null() {
+        if left.is_null() != right.is_null() {
+            out.push(ColumnDiff {
+                column: path,
+                left_value: left.to_string(),
+                right_value: right.to_string(),
+            });
+        }
+        return;
+    }
+
+    match (left.into_static(), right.into_static()) {
+        (AnyValue::StructOwned(left), AnyValue::StructOwned(right)) => {
+            let (left_values, left_fields) = *left;
+            let (right_values, right_fields) = *right;
+            let mut right_fields: Vec<_> = right_fields
+                .iter()
+                .map(|field| field.name().to_string())
+                .zip(right_values.into_iter().map(Some))
+                .collect();
+
+            for (field, left_value) in left_fields.iter().zip(left_values) {
+                let name = field.name().to_string();
+                let child = format!("{}.{}", path, name);
+                match right_fields.iter_mut().find(|(n, _)| *n == name) {
+                    Some((_, right_value)) => {
+                        let right_value = right_value.take().unwrap_or(AnyValue::Null);
+                        diff_values(child, left_value, right_value, out);
+                    }
+                    None => out.push(ColumnDiff {
+                        column: child,
+                        left_value: left_value.to_string(),
+                        right_value: MISSING.to_string(),
+                    }),
+                }
+            }
+            // Fields matched above were taken; the rest are right-only
+            for (name, right_value) in right_fields {
+                if let Some(right_value) = right_value {
+                    out.push(ColumnDiff {
+                        column: format!("{}.{}", path, name),
+                        left_value: MISSING.to_string(),
+                        right_value: right_value.to_string(),
+                    });
+                }
+            }
+        }
+        (
+            AnyValue::List(left) | AnyValue::Array(left, _),
+            AnyValue::List(right) | AnyValue::Array(right, _),
+        ) => {
+            if let (Some(left_map), Some(right_map)) = (map_entries(&left), map_entries(&right)) {
+                diff_maps(&path, left_map, right_map, out);
+                return;
+            }
+
+            for idx in 0..left.len().max(right.len()) {
+                let child = format!("{}[{}]", path, idx);
+                match (left.get(idx).ok(), right.get(idx).ok()) {
+                    (Some(l), Some(r)) => diff_values(child, l, r, out),
+                    (l, r) => out.push(ColumnDiff {
+                        column: child,
+                        left_value: l.map_or(MISSING.to_string(), |v| v.to_string()),
+                        right_value: r.map_or(MISSING.to_string(), |v| v.to_string()),
+                    }),
+                }
+            }
+        }
+        (left, right) => {
+            let (left_value, right_value) = (left.to_string(), right.to_string());
+            if left_value != right_value {
+                out.push(ColumnDiff {
+                    column: path,
+                    left_value,
+                    right_value,
+                });
+            }
+        }
     }
 }
 
+/// Entries of a Parquet map, which Polars reads as a list of
+/// `{key, value}` structs
+fn map_entries(list: &Series) -> Option<Vec<(String, AnyValue<'static>)>> {
+    let DataType::Struct(fields) = list.dtype() else {
+        return None;
+    };
+    let names: Vec<&str> = fields.iter().map(|f| f.name().as_str()).collect();
+    if names != ["key", "value"] {
+        return None;
+    }
+
+    let mut entries = Vec::with_capacity(list.len());
+    for idx in 0..list.len() {
+        match list.get(idx).ok()?.into_static() {
+            AnyValue::StructOwned(entry) => {
+                let mut values = entry.0.into_iter();
+                let key = values.next()?.to_string();
+                entries.push((key, values.next()?));
+            }
+            _ => return None,
+        }
+    }
+    Some(entries)
+}
+
+fn diff_maps(
+    path: &str,
+    left: Vec<(String, AnyValue<'static>)>,
+    mut right: Vec<(String, AnyValue<'static>)>,
+    out: &mut Vec<ColumnDiff>,
+) {
+    for (key, left_value) in left {
+        let child = format!("{}[{}]", path, key);
+        match right.iter().position(|(k, _)| *k == key) {
+            Some(pos) => diff_values(child, left_value, right.remove(pos).1, out),
+            None => out.push(ColumnDiff {
+                column: child,
+                left_value: left_value.to_string(),
+                right_value: MISSING.to_string(),
+            }),
+        }
+    }
+    for (key, right_value) in right {
+        out.push(ColumnDiff {
+            column: format!("{}[{}]", path, key),
+            left_value: MISSING.to_string(),
+            right_value: right_value.to_string(),
+        });
+    }
+}
+
+/// Read the footer of a Parquet file without decoding any pages
+fn storage_layout(path: &Path, side: &str) -> Result<StorageLayout, RCompareError> {
+    let invalid = |e: PolarsError| {
+        RCompareError::Io(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Failed to read {} Parquet metadata: {}", side, e),
+        ))
+    };
+    let file = std::fs::File::open(path)?;
+    let mut reader = ParquetReader::new(file);
+    let metadata = reader.get_metadata().map_err(invalid)?;
+
+    let mut layout = StorageLayout::default();
+    for group in &metadata.row_groups {
+        layout.row_groups.push(group.num_rows());
+        for field in metadata.schema().fields() {
+            let Some(chunks) = group.columns_under_root_iter(field.name()) else {
+                continue;
+            };
+            for chunk in chunks {
+                let name = chunk.descriptor().path_in_schema.join(".");
+                let (codecs, encodings) = layout.columns.entry(name).or_default();
+                codecs.insert(format!("{:?}", chunk.compression()));
+                encodings.extend(chunk.column_encoding().iter().map(|e| format!("{:?}", e)));
+            }
+        }
+    }
+    Ok(layout)
+}
+
+/// List the storage properties that differ; columns missing on one side are
+/// left to the schema comparison
+fn compare_layouts(left: &StorageLayout, right: &StorageLayout) -> Vec<PhysicalDiff> {
+    let join = |set: &BTreeSet<String>| set.iter().cloned().collect::<Vec<_>>().join(", ");
+    let mut diffs = Vec::new();
+
+    if left.row_groups != right.row_groups {
+        diffs.push(PhysicalDiff {
+            property: "row_groups".to_string(),
+            left_value: format!("{:?}", left.row_groups),
+            right_value: format!("{:?}", right.row_groups),
+        });
+    }
+    for (column, (left_codecs, left_encodings)) in &left.columns {
+        let Some((right_codecs, right_encodings)) = right.columns.get(column) else {
+            continue;
+        };
+        if left_codecs != right_codecs {
+            diffs.push(PhysicalDiff {
+                property: format!("{}: compression", column),
+                left_value: join(left_codecs),
+                right_value: join(right_codecs),
+            });
+        }
+        if left_encodings != right_encodings {
+            diffs.push(PhysicalDiff {
+                property: format!("{}: encodings", column),
+                left_value: join(left_encodings),
+                right_value: join(right_encodings),
+            });
+        }
+    }
+    diffs
+}
+
 impl Default for ParquetDiffEngine {
     fn default() -> Self {
         Self::new()
@@ -534,6 +878,259 @@ mod tests {
         assert_eq!(result.left_only_rows, 1);
     }
 
+    #[test]
+    fn test_cancel_between_rows() {
+        use std::sync::atomic::Ordering;
+
+        let ids: Vec<i64> = (0..10_000).collect();
+        let left = df! { "id" => &ids }.unwrap();
+        let cancel = AtomicBool::new(false);
+        let rows = std::cell::Cell::new(0);
+        let progress = |done: usize, total: usize| {
+            assert_eq!(total, 10_000);
+            rows.set(done);
+            if done == 100 {
+                cancel.store(true, Ordering::Relaxed);
+            }
+        };
+
+        let err = ParquetDiffEngine::new()
+            .compare_dataframes_with_cancel(&left, &left, &cancel, Some(&progress))
+            .unwrap_err();
+        assert!(err.to_string().contains("Parquet comparison cancelled"));
+        assert_eq!(rows.get(), 100);
+    }
+
+    #[test]
+    fn test_max_detail_caps_rows_but_keeps_totals() {
+        let ids: Vec<i32> = (0..20).collect();
+        let df1 = df! { "id" => &ids, "value" => vec![1; 20] }.unwrap();
+        let df2 = df! { "id" => &ids[..15], "value" => vec![2; 15] }.unwrap();
+
+        let engine = ParquetDiffEngine::new().with_max_detail(4);
+        let result = engine.compare_dataframes(&df1, &df2).unwrap();
+        assert_eq!(result.different_rows, 15);
+        assert_eq!(result.left_only_rows, 5);
+        assert_eq!(result.row_diffs.len(), 4);
+        assert!(result.truncated);
+
+        let engine = ParquetDiffEngine::new().with_key_columns(vec!["id".to_string()]);
+        let result = engine.compare_dataframes(&df1, &df2).unwrap();
+        assert_eq!(result.row_diffs.len(), 20);
+        assert!(!result.truncated);
+    }
+
+    fn struct_column(name: &str, fields: &[Series]) -> Column {
+        StructChunked::from_series(name.into(), fields[0].len(), fields.iter())
+            .unwrap()
+            .into_series()
+            .into_column()
+    }
+
+    fn address_frame(cities: &[&str], zips: &[Option<&str>]) -> DataFrame {
+        let address = struct_column(
+            "address",
+            &[
+                Series::new("city".into(), cities),
+                Series::new("zip".into(), zips),
+            ],
+        );
+        DataFrame::new(vec![Column::new("id".into(), &[1, 2, 3]), address]).unwrap()
+    }
+
+    fn nested_diffs(result: &ParquetDiffResult) -> Vec<(usize, String, String, String)> {
+        result
+            .row_diffs
+            .iter()
+            .flat_map(|row| {
+                row.column_diffs.iter().map(|diff| {
+                    (
+                        row.left_row.unwrap(),
+                        diff.column.clone(),
+                        diff.left_value.clone(),
+                        diff.right_value.clone(),
+                    )
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_struct_field_change_in_parquet_file() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let frames = [
+            address_frame(
+                &["Paris", "Lyon", "Nice"],
+                &[Some("75001"), Some("69001"), None],
+            ),
+            address_frame(
+                &["Paris", "Lille", "Nice"],
+                &[Some("75001"), Some("69001"), None],
+            ),
+        ];
+        let mut paths = Vec::new();
+        for (i, mut df) in frames.into_iter().enumerate() {
+            let path = temp.path().join(format!("{i}.parquet"));
+            ParquetWriter::new(std::fs::File::create(&path).unwrap())
+                .finish(&mut df)
+                .unwrap();
+            paths.push(path);
+        }
+
+        let result = ParquetDiffEngine::new()
+            .compare_parquet_files(&paths[0], &paths[1])
+            .unwrap();
+
+        assert_eq!(result.identical_rows, 2);
+        assert_eq!(
+            nested_diffs(&result),
+            [(
+                1,
+                "address.city".to_string(),
+                "\"Lyon\"".to_string(),
+                "\"Lille\"".to_string()
+            )]
+        );
+    }
+
+    fn write_parquet(path: &Path, compression: ParquetCompression, row_group_size: Option<usize>) {
+        let mut df = create_test_dataframe();
+        ParquetWriter::new(std::fs::File::create(path).unwrap())
+            .with_compression(compression)
+            .with_row_group_size(row_group_size)
+            .finish(&mut df)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_codec_and_row_groups_ignored_in_logical_mode() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let left = temp.path().join("snappy.parquet");
+        let right = temp.path().join("zstd.parquet");
+        let plain = temp.path().join("plain.parquet");
+        write_parquet(&left, ParquetCompression::Snappy, None);
+        write_parquet(&right, ParquetCompression::Zstd(None), Some(1));
+        write_parquet(&plain, ParquetCompression::Uncompressed, None);
+
+        let engine = ParquetDiffEngine::new();
+        for other in [&right, &plain] {
+            let result = engine.compare_parquet_files(&left, other).unwrap();
+            assert_eq!(result.identical_rows, 3);
+            assert_eq!(result.different_rows, 0);
+            assert!(result.schema_diffs.is_empty());
+            assert!(result.physical_diffs.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_physical_mode_reports_storage_differences() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let left = temp.path().join("snappy.parquet");
+        let right = temp.path().join("zstd.parquet");
+        write_parquet(&left, ParquetCompression::Snappy, None);
+        write_parquet(&right, ParquetCompression::Zstd(None), Some(1));
+
+        let engine = ParquetDiffEngine::new().with_mode(ParquetCompareMode::Physical);
+        let result = engine.compare_parquet_files(&left, &right).unwrap();
+        assert_eq!(result.identical_rows, 3);
+
+        let row_groups = result
+            .physical_diffs
+            .iter()
+            .find(|d| d.property == "row_groups")
+            .unwrap();
+        assert_eq!(row_groups.left_value, "[3]");
+        assert_eq!(row_groups.right_value, "[1, 1, 1]");
+        let codec = result
+            .physical_diffs
+            .iter()
+            .find(|d| d.property == "name: compression")
+            .unwrap();
+        assert_eq!(codec.left_value, "Snappy");
+        assert!(codec.right_value.starts_with("Zstd"));
+
+        let same = engine.compare_parquet_files(&left, &left).unwrap();
+        assert!(same.physical_diffs.is_empty());
+    }
+
+    #[test]
+    fn test_nested_nulls() {
+        let left = address_frame(&["Paris", "Lyon", "Nice"], &[Some("75001"), None, None]);
+        let right = address_frame(&["Paris", "Lyon", "Nice"], &[None, None, Some("06000")]);
+
+        let result = ParquetDiffEngine::new()
+            .compare_dataframes(&left, &right)
+            .unwrap();
+
+        // Null on both sides is equal; null on one side is a difference
+        assert_eq!(result.identical_rows, 1);
+        assert_eq!(
+            nested_diffs(&result),
+            [
+                (
+                    0,
+                    "address.zip".to_string(),
+                    "\"75001\"".to_string(),
+                    "null".to_string()
+                ),
+                (
+                    2,
+                    "address.zip".to_string(),
+                    "null".to_string(),
+                    "\"06000\"".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_list_and_map_elements() {
+        let tags = |rows: &[&[&str]]| {
+            let rows: Vec<Series> = rows
+                .iter()
+                .map(|row| Series::new("".into(), *row))
+                .collect();
+            Column::new("tags".into(), rows)
+        };
+        let attrs = |keys: &[&str], values: &[i32]| {
+            let entry = StructChunked::from_series(
+                "".into(),
+                keys.len(),
+                [
+                    Series::new("key".into(), keys),
+                    Series::new("value".into(), values),
+                ]
+                .iter(),
+            )
+            .unwrap()
+            .into_series();
+            Column::new("attrs".into(), [entry])
+        };
+
+        let left =
+            DataFrame::new(vec![tags(&[&["a", "b", "c"]]), attrs(&["x", "y"], &[1, 2])]).unwrap();
+        let right =
+            DataFrame::new(vec![tags(&[&["a", "B"]]), attrs(&["y", "z"], &[2, 3])]).unwrap();
+
+        let result = ParquetDiffEngine::new()
+            .compare_dataframes(&left, &right)
+            .unwrap();
+
+        let diffs: Vec<_> = nested_diffs(&result)
+            .into_iter()
+            .map(|(_, column, left, right)| format!("{column}: {left} -> {right}"))
+            .collect();
+        assert_eq!(
+            diffs,
+            [
+                "tags[1]: \"b\" -> \"B\"",
+                "tags[2]: \"c\" -> <missing>",
+                "attrs[\"x\"]: 1 -> <missing>",
+                "attrs[\"z\"]: <missing> -> 3",
+            ]
+        );
+    }
+
     #[test]
     fn test_is_parquet_file() {
         assert!(is_parquet_file(Path::new("data.parquet")));
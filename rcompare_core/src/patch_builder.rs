@@ -0,0 +1,353 @@
+use crate::content_kind::{detect_content_kind, ContentKind};
+use crate::patch_serializer::PatchSerializer;
+use rcompare_common::{
+    DiffNode, DiffStatus, DifferenceType, FileEntry, FilePatch, Hunk, PatchDifference,
+    RCompareError, Vfs,
+};
+use similar::{DiffOp, TextDiff};
+use std::io::Read;
+use std::path::Path;
+
+/// Lines of context around each change, as in `diff -u`
+const CONTEXT_LINES: usize = 3;
+
+/// Builder that turns file contents and comparison results into patch models.
+///
+/// This is the counterpart of the parser: instead of reading diff text it
+/// diffs two texts and produces the `FilePatch` that [`PatchSerializer`]
+/// turns into unified diff text.
+pub struct PatchBuilder;
+
+impl PatchBuilder {
+    /// Build a unified-diff `FilePatch` transforming `old` into `new`.
+    ///
+    /// `source` and `destination` are the header paths verbatim (e.g.
+    /// `a/file.txt`, or `/dev/null` for a created or deleted file). Identical
+    /// inputs produce a patch without hunks.
+    pub fn file_patch(source: &str, destination: &str, old: &str, new: &str) -> FilePatch {
+        let mut fp = FilePatch::new();
+        fp.source = source.to_string();
+        fp.destination = destination.to_string();
+
+        let diff = TextDiff::from_lines(old, new);
+        let old_lines = diff.old_slices();
+        let new_lines = diff.new_slices();
+
+        for group in diff.grouped_ops(CONTEXT_LINES) {
+            let (Some(first), Some(last)) = (group.first(), group.last()) else {
+                continue;
+            };
+            let old_range = first.old_range().start..last.old_range().end;
+            let new_range = first.new_range().start..last.new_range().end;
+            // An empty side is anchored at the line before it, as diff does
+            let start = |range: &std::ops::Range<usize>| {
+                if range.is_empty() {
+                    range.start
+                } else {
+                    range.start + 1
+                }
+            };
+
+            let mut hunk = Hunk::new(start(&old_range), start(&new_range));
+            for op in &group {
+                let diff_type = match op {
+                    DiffOp::Equal { .. } => DifferenceType::Unchanged,
+                    DiffOp::Delete { .. } => DifferenceType::Delete,
+                    DiffOp::Insert { .. } => DifferenceType::Insert,
+                    DiffOp::Replace { .. } => DifferenceType::Change,
+                };
+                let mut difference = PatchDifference::new(
+                    diff_type,
+                    op.old_range().start + 1,
+                    op.new_range().start + 1,
+                );
+                if diff_type != DifferenceType::Insert {
+                    difference.source_lines = old_lines[op.old_range()]
+                        .iter()
+                        .map(|l| l.to_string())
+                        .collect();
+                }
+                if diff_type != DifferenceType::Delete {
+                    difference.dest_lines = new_lines[op.new_range()]
+                        .iter()
+                        .map(|l| l.to_string())
+                        .collect();
+                }
+                hunk.differences.push(difference);
+            }
+            hunk.recompute_counts();
+            fp.hunks.push(hunk);
+        }
+
+        fp
+    }
+
+    /// Build one multi-file unified diff covering every difference in a tree.
+    ///
+    /// Differing text files get a regular `a/`, `b/` diff, left-only files a
+    /// deletion against `/dev/null` and right-only files a creation. Pairs
+    /// where either side is binary get a `diff --git` line and a
+    /// `Binary files ... differ` line, as git writes them without `--binary`.
+    /// Nodes are visited in order, so the output follows the comparison.
+    pub fn tree_patch(
+        left_root: &Path,
+        right_root: &Path,
+        nodes: &[DiffNode],
+        left_vfs: Option<&dyn Vfs>,
+        right_vfs: Option<&dyn Vfs>,
+    ) -> Result<String, RCompareError> {
+        Self::tree_patch_under(
+            Path::new(""),
+            left_root,
+            right_root,
+            nodes,
+            left_vfs,
+            right_vfs,
+        )
+    }
+
+    /// Like [`tree_patch`](Self::tree_patch), with every path shown below
+    /// `prefix` (`a/<prefix>/...`), so the patches of several directory
+    /// pairs can be concatenated into one
+    pub fn tree_patch_under(
+        prefix: &Path,
+        left_root: &Path,
+        right_root: &Path,
+        nodes: &[DiffNode],
+        left_vfs: Option<&dyn Vfs>,
+        right_vfs: Option<&dyn Vfs>,
+    ) -> Result<String, RCompareError> {
+        let mut output = String::new();
+
+        for node in nodes {
+            let is_file = |entry: &Option<FileEntry>| entry.as_ref().is_some_and(|e| !e.is_dir);
+            let (has_left, has_right) = match node.status {
+                DiffStatus::Different | DiffStatus::Unchecked => (true, true),
+                DiffStatus::OrphanLeft => (true, false),
+                DiffStatus::OrphanRight => (false, true),
+                DiffStatus::Same | DiffStatus::TypeConflict | DiffStatus::Ignored => continue,
+            };
+            if (has_left && !is_file(&node.left)) || (has_right && !is_file(&node.right)) {
+                continue;
+            }
+
+            // A path transform may pair entries whose paths differ
+            let left_path = node.left.as_ref().map_or(&node.relative_path, |e| &e.path);
+            let right_path = node.right.as_ref().map_or(&node.relative_path, |e| &e.path);
+            let header = |side: &str, path: &Path| {
+                let path = prefix.join(path);
+                format!("{}/{}", side, path.to_string_lossy().replace('\\', "/"))
+            };
+            let source = if has_left {
+                header("a", left_path)
+            } else {
+                "/dev/null".to_string()
+            };
+            let destination = if has_right {
+                header("b", right_path)
+            } else {
+                "/dev/null".to_string()
+            };
+
+            let old = if has_left {
+                Some(read_bytes(&left_root.join(left_path), left_vfs)?)
+            } else {
+                None
+            };
+            let new = if has_right {
+                Some(read_bytes(&right_root.join(right_path), right_vfs)?)
+            } else {
+                None
+            };
+            if old.is_some() && old == new {
+                continue;
+            }
+
+            let old_text = old.as_deref().map(|b| decode(b, &node.relative_path));
+            let new_text = new.as_deref().map(|b| decode(b, &node.relative_path));
+            match (old_text, new_text) {
+                (Some(None), _) | (_, Some(None)) => {
+                    output.push_str(&format!(
+                        "diff --git {} {}\n",
+                        header("a", left_path),
+                        header("b", right_path)
+                    ));
+                    output.push_str(&format!(
+                        "Binary files {} and {} differ\n",
+                        source, destination
+                    ));
+                }
+                (old_text, new_text) => {
+                    let old_text = old_text.flatten().unwrap_or_default();
+                    let new_text = new_text.flatten().unwrap_or_default();
+                    let fp = Self::file_patch(&source, &destination, &old_text, &new_text);
+                    output.push_str(&PatchSerializer::serialize_file_patch(&fp));
+                }
+            }
+        }
+
+        Ok(output)
+    }
+}
+
+fn read_bytes(path: &Path, vfs: Option<&dyn Vfs>) -> Result<Vec<u8>, RCompareError> {
+    match vfs {
+        Some(vfs) => {
+            let mut reader = vfs
+                .open_file(path)
+                .map_err(|e| RCompareError::vfs(path.display().to_string(), e))?;
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes)?;
+            Ok(bytes)
+        }
+        None => Ok(std::fs::read(path)?),
+    }
+}
+
+/// Decode text content, or `None` if it is binary
+fn decode(bytes: &[u8], path: &Path) -> Option<String> {
+    match detect_content_kind(bytes, Some(path)) {
+        ContentKind::Text(encoding) => encoding.decode(bytes).ok(),
+        ContentKind::Binary => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::patch_engine::PatchEngine;
+    use crate::patch_parser::PatchParser;
+    use rcompare_common::FileType;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::time::SystemTime;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_file_patch_round_trips_through_serializer() {
+        let old = "one\ntwo\nthree\nfour\nfive\nsix\nseven\neight\nnine\nten\n";
+        let new = "one\ntwo\nTHREE\nfour\nfive\nsix\nseven\neight\nnine\nten\neleven\n";
+        let fp = PatchBuilder::file_patch("a/f.txt", "b/f.txt", old, new);
+        let text = PatchSerializer::serialize_file_patch(&fp);
+        assert!(text.starts_with("--- a/f.txt\n+++ b/f.txt\n@@ -1,6 +1,6 @@\n"));
+        assert!(text.contains("\n@@ -8,3 +8,4 @@\n"));
+
+        let mut parsed = PatchParser::new().parse_string(&text).unwrap();
+        PatchEngine::blend_file(&mut parsed.files[0], old).unwrap();
+        assert_eq!(
+            PatchEngine::reconstruct_destination(&parsed.files[0]).unwrap(),
+            new
+        );
+    }
+
+    #[test]
+    fn test_file_patch_marks_missing_final_newline() {
+        let fp = PatchBuilder::file_patch("a/f.txt", "b/f.txt", "one\ntwo", "one\nTWO");
+        assert_eq!(
+            PatchSerializer::serialize_file_patch(&fp),
+            "--- a/f.txt\n+++ b/f.txt\n@@ -1,2 +1,2 @@\n one\n\
+             -two\n\\ No newline at end of file\n\
+             +TWO\n\\ No newline at end of file\n"
+        );
+
+        // Only the side without the final newline is marked
+        let fp = PatchBuilder::file_patch("a/f.txt", "b/f.txt", "one\n", "one\ntwo");
+        assert_eq!(
+            PatchSerializer::serialize_file_patch(&fp),
+            "--- a/f.txt\n+++ b/f.txt\n@@ -1,1 +1,2 @@\n one\n+two\n\\ No newline at end of file\n"
+        );
+    }
+
+    #[test]
+    fn test_tree_patch_parses_back_and_applies() {
+        let temp = TempDir::new().unwrap();
+        let left = temp.path().join("left");
+        let right = temp.path().join("right");
+        fs::create_dir_all(left.join("src")).unwrap();
+        fs::create_dir_all(right.join("src")).unwrap();
+
+        let files: [(&str, Option<&[u8]>, Option<&[u8]>); 5] = [
+            ("gone.txt", Some(b"old\nfile\n"), None),
+            ("image.bin", Some(b"\x00\x01\x02"), Some(b"\x00\x01\x03")),
+            ("new.txt", None, Some(b"brand\nnew\n")),
+            ("same.txt", Some(b"same\n"), Some(b"same\n")),
+            (
+                "src/main.rs",
+                Some(b"fn main() {\n    old();\n}\n"),
+                Some(b"fn main() {\n    new();\n}\n"),
+            ),
+        ];
+        let mut nodes = Vec::new();
+        for (name, l, r) in files {
+            let entry = |root: &Path, content: Option<&[u8]>| {
+                content.map(|content| {
+                    fs::write(root.join(name), content).unwrap();
+                    FileEntry {
+                        path: PathBuf::from(name),
+                        size: content.len() as u64,
+                        modified: SystemTime::now(),
+                        is_dir: false,
+                        file_type: FileType::Regular,
+                    }
+                })
+            };
+            let status = match (l, r) {
+                (Some(l), Some(r)) if l == r => DiffStatus::Same,
+                (Some(_), Some(_)) => DiffStatus::Different,
+                (Some(_), None) => DiffStatus::OrphanLeft,
+                _ => DiffStatus::OrphanRight,
+            };
+            nodes.push(DiffNode {
+                relative_path: PathBuf::from(name),
+                left: entry(&left, l),
+                right: entry(&right, r),
+                status,
+                similar_to: None,
+                reason: None,
+                ignored_by: None,
+            });
+        }
+
+        let under =
+            PatchBuilder::tree_patch_under(Path::new("pkg"), &left, &right, &nodes, None, None)
+                .unwrap();
+        assert!(under.contains("--- a/pkg/src/main.rs\n+++ b/pkg/src/main.rs\n"));
+        assert!(under.contains("diff --git a/pkg/image.bin b/pkg/image.bin\n"));
+
+        let patch = PatchBuilder::tree_patch(&left, &right, &nodes, None, None).unwrap();
+        assert!(patch.contains(
+            "diff --git a/image.bin b/image.bin\n\
+             Binary files a/image.bin and b/image.bin differ\n"
+        ));
+        assert!(!patch.contains("same.txt"));
+
+        let parsed = PatchParser::new().parse_string(&patch).unwrap();
+        let headers: Vec<(&str, &str)> = parsed
+            .files
+            .iter()
+            .map(|f| (f.source.as_str(), f.destination.as_str()))
+            .collect();
+        assert_eq!(
+            headers,
+            [
+                ("a/gone.txt", "/dev/null"),
+                ("a/image.bin", "b/image.bin"),
+                ("/dev/null", "b/new.txt"),
+                ("a/src/main.rs", "b/src/main.rs"),
+            ]
+        );
+
+        // The binary file has a git header but nothing to apply
+        for mut fp in parsed.files.into_iter().filter(|fp| !fp.hunks.is_empty()) {
+            let name = |p: &str| (p != "/dev/null").then(|| p[2..].to_string());
+            let original = name(&fp.source)
+                .map(|n| fs::read_to_string(left.join(n)).unwrap())
+                .unwrap_or_default();
+            let expected = name(&fp.destination)
+                .map(|n| fs::read_to_string(right.join(n)).unwrap())
+                .unwrap_or_default();
+            PatchEngine::blend_file(&mut fp, &original).unwrap();
+            assert_eq!(PatchEngine::reconstruct_destination(&fp).unwrap(), expected);
+        }
+    }
+}
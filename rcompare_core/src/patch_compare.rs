@@ -0,0 +1,281 @@
+//! Semantic comparison of two patches (a diff of diffs).
+//!
+//! Both patches are parsed and matched file by file and hunk by hunk, so the
+//! result says which changes were added, dropped or reworked rather than
+//! which lines of the patch text moved. Files are matched by
+//! [`FilePatch::path`], so their order in the patch doesn't matter. Hunks are
+//! matched by content first, ignoring their line numbers and function names:
+//! a hunk that only shifted because an earlier hunk grew or shrank counts as
+//! unchanged. Leftover hunks whose source ranges overlap are reported as
+//! modified, the rest as added or removed. Hunks added by blending are
+//! ignored.
+
+use crate::patch_parser::PatchParser;
+use rcompare_common::{
+    FilePatch, FilePatchDiff, Hunk, HunkDiff, HunkType, PatchChange, PatchSet, PatchSetDiff,
+    RCompareError,
+};
+use std::collections::BTreeMap;
+
+/// Parse two patch texts and compare them with [`compare_patch_sets`]
+pub fn compare_patches(old: &str, new: &str) -> Result<PatchSetDiff, RCompareError> {
+    let parser = PatchParser::new();
+    Ok(compare_patch_sets(
+        &parser.parse_string(old)?,
+        &parser.parse_string(new)?,
+    ))
+}
+
+/// Compare two parsed patches file by file and hunk by hunk
+pub fn compare_patch_sets(old: &PatchSet, new: &PatchSet) -> PatchSetDiff {
+    let old_files = hunks_by_path(old);
+    let mut new_files = hunks_by_path(new);
+
+    let mut files = Vec::new();
+    for (path, old_hunks) in old_files {
+        let (change, hunks) = match new_files.remove(path) {
+            Some(new_hunks) => (PatchChange::Modified, compare_hunks(&old_hunks, &new_hunks)),
+            None => (
+                PatchChange::Removed,
+                whole(&old_hunks, PatchChange::Removed),
+            ),
+        };
+        if !hunks.is_empty() || change == PatchChange::Removed {
+            files.push(FilePatchDiff {
+                path: path.to_string(),
+                change,
+                hunks,
+            });
+        }
+    }
+    for (path, new_hunks) in new_files {
+        files.push(FilePatchDiff {
+            path: path.to_string(),
+            change: PatchChange::Added,
+            hunks: whole(&new_hunks, PatchChange::Added),
+        });
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+
+    PatchSetDiff { files }
+}
+
+/// Original hunks of every file, keyed by path; a file that appears more
+/// than once keeps the hunks of every occurrence
+fn hunks_by_path(patch_set: &PatchSet) -> BTreeMap<&str, Vec<&Hunk>> {
+    let mut files: BTreeMap<&str, Vec<&Hunk>> = BTreeMap::new();
+    for file in &patch_set.files {
+        files
+            .entry(file.path())
+            .or_default()
+            .extend(original_hunks(file));
+    }
+    files
+}
+
+fn original_hunks(file: &FilePatch) -> impl Iterator<Item = &Hunk> {
+    file.hunks
+        .iter()
+        .filter(|hunk| hunk.hunk_type == HunkType::Normal)
+}
+
+/// Every hunk of a file only one patch touches
+fn whole(hunks: &[&Hunk], change: PatchChange) -> Vec<HunkDiff> {
+    hunks
+        .iter()
+        .map(|&hunk| {
+            let hunk = Some(hunk.clone());
+            match change {
+                PatchChange::Added => HunkDiff {
+                    change,
+                    old: None,
+                    new: hunk,
+                },
+                _ => HunkDiff {
+                    change,
+                    old: hunk,
+                    new: None,
+                },
+            }
+        })
+        .collect()
+}
+
+fn compare_hunks(old: &[&Hunk], new: &[&Hunk]) -> Vec<HunkDiff> {
+    let mut new_used = vec![false; new.len()];
+    let mut old_left = Vec::new();
+    for &old_hunk in old {
+        let same = (0..new.len()).find(|&i| !new_used[i] && same_content(old_hunk, new[i]));
+        match same {
+            Some(i) => new_used[i] = true,
+            None => old_left.push(old_hunk),
+        }
+    }
+
+    let mut diffs = Vec::new();
+    for old_hunk in old_left {
+        let reworked = (0..new.len()).find(|&i| !new_used[i] && overlaps(old_hunk, new[i]));
+        let new_hunk = reworked.map(|i| {
+            new_used[i] = true;
+            new[i].clone()
+        });
+        diffs.push(HunkDiff {
+            change: if new_hunk.is_some() {
+                PatchChange::Modified
+            } else {
+                PatchChange::Removed
+            },
+            old: Some(old_hunk.clone()),
+            new: new_hunk,
+        });
+    }
+    for (_, &new_hunk) in new.iter().enumerate().filter(|(i, _)| !new_used[*i]) {
+        diffs.push(HunkDiff {
+            change: PatchChange::Added,
+            old: None,
+            new: Some(new_hunk.clone()),
+        });
+    }
+
+    diffs.sort_by_key(|diff| {
+        diff.old
+            .as_ref()
+            .or(diff.new.as_ref())
+            .map_or(0, |hunk| hunk.source_start)
+    });
+    diffs
+}
+
+/// Whether two hunks make the same change with the same context
+fn same_content(a: &Hunk, b: &Hunk) -> bool {
+    a.differences.len() == b.differences.len()
+        && a.differences.iter().zip(&b.differences).all(|(x, y)| {
+            x.diff_type == y.diff_type
+                && x.source_lines == y.source_lines
+                && x.dest_lines == y.dest_lines
+        })
+}
+
+/// Whether two hunks touch overlapping lines of the source file
+fn overlaps(a: &Hunk, b: &Hunk) -> bool {
+    let range = |hunk: &Hunk| hunk.source_start..hunk.source_start + hunk.source_count.max(1);
+    let (a, b) = (range(a), range(b));
+    a.start < b.end && b.start < a.end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OLD: &str = "\
+--- a/file1.txt
++++ b/file1.txt
+@@ -1,3 +1,3 @@
+ alpha
+-beta
++BETA
+ gamma
+@@ -10,3 +10,4 @@
+ kappa
++lambda
+ mu
+ nu
+--- a/file2.txt
++++ b/file2.txt
+@@ -1,2 +1,2 @@
+ one
+-two
++TWO
+";
+
+    #[test]
+    fn test_only_the_reworked_hunk_is_reported() {
+        // Files swapped and the second hunk of file1 reworked
+        let new = "\
+--- a/file2.txt
++++ b/file2.txt
+@@ -1,2 +1,2 @@
+ one
+-two
++TWO
+--- a/file1.txt
++++ b/file1.txt
+@@ -1,3 +1,3 @@
+ alpha
+-beta
++BETA
+ gamma
+@@ -10,3 +10,4 @@
+ kappa
++lambda!
+ mu
+ nu
+";
+        let diff = compare_patches(OLD, new).unwrap();
+        assert_eq!(diff.files.len(), 1);
+        let file = &diff.files[0];
+        assert_eq!(file.path, "file1.txt");
+        assert_eq!(file.change, PatchChange::Modified);
+        assert_eq!(file.hunks.len(), 1);
+        let hunk = &file.hunks[0];
+        assert_eq!(hunk.change, PatchChange::Modified);
+        assert_eq!(hunk.old.as_ref().unwrap().source_start, 10);
+        let adds = |hunk: &Option<Hunk>, line: &str| {
+            hunk.as_ref()
+                .unwrap()
+                .differences
+                .iter()
+                .flat_map(|d| &d.dest_lines)
+                .any(|l| l.trim_end() == line)
+        };
+        assert!(adds(&hunk.old, "lambda"));
+        assert!(adds(&hunk.new, "lambda!"));
+    }
+
+    #[test]
+    fn test_shifted_hunks_and_identical_patches_are_equal() {
+        assert!(compare_patches(OLD, OLD).unwrap().is_empty());
+
+        let shifted = OLD.replace("@@ -10,3 +10,4 @@", "@@ -12,3 +12,4 @@ fn main");
+        assert!(compare_patches(OLD, &shifted).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_added_and_removed_files_and_hunks() {
+        let new = "\
+--- a/file1.txt
++++ b/file1.txt
+@@ -1,3 +1,3 @@
+ alpha
+-beta
++BETA
+ gamma
+@@ -30,2 +30,1 @@
+ omega
+-end
+--- /dev/null
++++ b/file3.txt
+@@ -0,0 +1 @@
++new
+";
+        let diff = compare_patches(OLD, new).unwrap();
+        let changes: Vec<(&str, PatchChange)> = diff
+            .files
+            .iter()
+            .map(|f| (f.path.as_str(), f.change))
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                ("file1.txt", PatchChange::Modified),
+                ("file2.txt", PatchChange::Removed),
+                ("file3.txt", PatchChange::Added),
+            ]
+        );
+
+        let hunks: Vec<PatchChange> = diff.files[0].hunks.iter().map(|h| h.change).collect();
+        assert_eq!(hunks, [PatchChange::Removed, PatchChange::Added]);
+        assert!(diff.files[1].hunks.iter().all(|h| h.new.is_none()));
+        assert!(diff.files[2].hunks.iter().all(|h| h.old.is_none()));
+    }
+}
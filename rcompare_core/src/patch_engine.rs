@@ -1,6 +1,30 @@
 use rcompare_common::{
-    DifferenceType, FilePatch, Hunk, HunkType, PatchDifference, RCompareError,
+    DifferenceType, FilePatch, Hunk, HunkType, PatchDifference, RCompareError, Vfs,
 };
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+/// How [`PatchEngine::apply_difference_to`] treats a difference whose
+/// result is already present in the destination
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ApplyMode {
+    /// Mark it applied without counting it as a change, so re-runs are safe
+    #[default]
+    Lenient,
+    /// Fail with [`RCompareError::PatchApply`]
+    Strict,
+}
+
+/// Where a difference stands against the current destination content
+#[derive(Debug, PartialEq, Eq)]
+enum ContentState {
+    /// The destination holds the lines the difference replaces
+    Pending,
+    /// The destination already holds the result of applying it
+    AlreadyApplied,
+    /// The destination holds neither
+    Conflict,
+}
 
 /// Engine for applying/unapplying individual differences and blending
 /// original file content into a parsed patch model.
@@ -18,24 +42,62 @@ impl PatchEngine {
             )));
         }
         let (hi, di) = indices[flat_idx];
-        let diff = &patch.hunks[hi].differences[di];
-        if diff.applied {
+        if patch.hunks[hi].differences[di].applied {
             return Ok(());
         }
 
-        let delta = diff.dest_line_count() as isize - diff.source_line_count() as isize;
-        let diff_dest_line = diff.dest_line_no;
-
-        // Apply
-        patch.hunks[hi].differences[di].applied = true;
-        patch.hunks[hi].differences[di].unsaved =
-            !patch.hunks[hi].differences[di].unsaved;
-        patch.applied_count += 1;
+        Self::mark_applied(patch, hi, di, true);
+        Ok(())
+    }
 
-        // Adjust tracking line numbers for all subsequent diffs
-        Self::adjust_tracking(patch, diff_dest_line, delta);
+    /// Apply a single difference after checking it against `current`, the
+    /// destination content as it is now.
+    ///
+    /// Applying puts a difference's source lines in place of its destination
+    /// lines at its tracking line. If `current` already holds the source
+    /// lines there, e.g. because the patch was applied before, the
+    /// difference is already applied: in [`ApplyMode::Lenient`] it is marked
+    /// applied without being flagged unsaved and `Ok(false)` is returned, in
+    /// [`ApplyMode::Strict`] it is an error. Content matching neither side is
+    /// flagged as a conflict and is always an error. Returns `Ok(true)` when
+    /// the difference was applied as a change.
+    pub fn apply_difference_to(
+        patch: &mut FilePatch,
+        flat_idx: usize,
+        current: &str,
+        mode: ApplyMode,
+    ) -> Result<bool, RCompareError> {
+        let indices = patch.difference_indices();
+        if flat_idx >= indices.len() {
+            return Err(RCompareError::PatchParse(format!(
+                "Difference index {flat_idx} out of range (count: {})",
+                indices.len()
+            )));
+        }
+        let (hi, di) = indices[flat_idx];
+        Self::apply_checked(patch, hi, di, &split_lines(current), mode)
+    }
 
-        Ok(())
+    /// Apply every difference after checking it against `current`, see
+    /// [`apply_difference_to`](Self::apply_difference_to).
+    ///
+    /// Stops at the first error. Returns the number of differences applied as
+    /// changes, so applying the same patch twice returns 0 the second time.
+    pub fn apply_all_to(
+        patch: &mut FilePatch,
+        current: &str,
+        mode: ApplyMode,
+    ) -> Result<usize, RCompareError> {
+        let current = split_lines(current);
+        let mut changed = 0;
+        // Bottom-up, so applying one difference never moves the tracking
+        // line of one that is still to be checked against `current`
+        for (hi, di) in patch.difference_indices().into_iter().rev() {
+            if Self::apply_checked(patch, hi, di, &current, mode)? {
+                changed += 1;
+            }
+        }
+        Ok(changed)
     }
 
     /// Unapply a single difference (by flat index among non-Unchanged diffs).
@@ -89,6 +151,78 @@ impl PatchEngine {
         Ok(())
     }
 
+    fn apply_checked(
+        patch: &mut FilePatch,
+        hi: usize,
+        di: usize,
+        current: &[&str],
+        mode: ApplyMode,
+    ) -> Result<bool, RCompareError> {
+        let diff = &mut patch.hunks[hi].differences[di];
+        if diff.applied {
+            return Ok(false);
+        }
+
+        let line = diff.tracking_dest_line_no;
+        match Self::content_state(diff, current) {
+            ContentState::Pending => {
+                Self::mark_applied(patch, hi, di, true);
+                Ok(true)
+            }
+            ContentState::AlreadyApplied if mode == ApplyMode::Lenient => {
+                Self::mark_applied(patch, hi, di, false);
+                Ok(false)
+            }
+            ContentState::AlreadyApplied => Err(RCompareError::PatchApply(format!(
+                "Difference at line {line} is already applied"
+            ))),
+            ContentState::Conflict => {
+                diff.conflict = true;
+                Err(RCompareError::PatchApply(format!(
+                    "Difference at line {line} does not match the current content"
+                )))
+            }
+        }
+    }
+
+    /// Compare a difference with `current` at its tracking line
+    fn content_state(diff: &PatchDifference, current: &[&str]) -> ContentState {
+        let start = diff.tracking_dest_line_no.saturating_sub(1);
+        let holds = |lines: &[String]| {
+            current
+                .get(start..start + lines.len())
+                .is_some_and(|found| found.iter().zip(lines).all(|(a, b)| *a == b))
+        };
+
+        // A side without lines matches anywhere, so check the other first
+        let (before, after) = (&diff.dest_lines, &diff.source_lines);
+        if !before.is_empty() && holds(before) {
+            ContentState::Pending
+        } else if holds(after) {
+            ContentState::AlreadyApplied
+        } else if before.is_empty() {
+            ContentState::Pending
+        } else {
+            ContentState::Conflict
+        }
+    }
+
+    /// Flag a difference applied and shift the tracking lines after it
+    fn mark_applied(patch: &mut FilePatch, hi: usize, di: usize, toggle_unsaved: bool) {
+        let diff = &mut patch.hunks[hi].differences[di];
+        let delta = diff.dest_line_count() as isize - diff.source_line_count() as isize;
+        let diff_dest_line = diff.dest_line_no;
+
+        diff.applied = true;
+        if toggle_unsaved {
+            diff.unsaved = !diff.unsaved;
+        }
+        patch.applied_count += 1;
+
+        // Adjust tracking line numbers for all subsequent diffs
+        Self::adjust_tracking(patch, diff_dest_line, delta);
+    }
+
     /// Unapply all differences at once.
     pub fn unapply_all(patch: &mut FilePatch) -> Result<(), RCompareError> {
         for hunk in &mut patch.hunks {
@@ -112,10 +246,49 @@ impl PatchEngine {
         patch: &mut FilePatch,
         original_content: &str,
     ) -> Result<(), RCompareError> {
-        let file_lines: Vec<&str> = split_lines(original_content);
+        let mut lines = split_lines(original_content).into_iter();
+        Self::blend_lines(patch, || Ok(lines.next().map(str::to_string)))
+    }
+
+    /// Blend original content read line by line from `reader`.
+    ///
+    /// Same result as [`Self::blend_file`], without holding the whole file
+    /// in memory first. Fails if the content is not valid UTF-8.
+    pub fn blend_from_reader(
+        patch: &mut FilePatch,
+        mut reader: impl BufRead,
+    ) -> Result<(), RCompareError> {
+        Self::blend_lines(patch, || {
+            let mut line = String::new();
+            match reader.read_line(&mut line)? {
+                0 => Ok(None),
+                _ => Ok(Some(line)),
+            }
+        })
+    }
+
+    /// Blend the original content of `path` on `vfs`, so patches can be
+    /// blended against files inside archives or on remote storage.
+    pub fn blend_from_vfs(
+        patch: &mut FilePatch,
+        vfs: &dyn Vfs,
+        path: &Path,
+    ) -> Result<(), RCompareError> {
+        let reader = vfs
+            .open_file(path)
+            .map_err(|e| RCompareError::vfs(path.display().to_string(), e))?;
+        Self::blend_from_reader(patch, BufReader::new(reader))
+    }
+
+    /// Shared blend loop; `next_line` yields the original lines, line
+    /// endings included, and `None` once the file is exhausted.
+    fn blend_lines(
+        patch: &mut FilePatch,
+        mut next_line: impl FnMut() -> Result<Option<String>, RCompareError>,
+    ) -> Result<(), RCompareError> {
         let mut src_line_no: usize = 1;
         let mut dst_line_no: usize = 1;
-        let mut line_idx: usize = 0;
+        let mut exhausted = false;
         let mut new_hunks: Vec<Hunk> = Vec::new();
 
         for hunk in &patch.hunks {
@@ -130,13 +303,15 @@ impl PatchEngine {
                     dst_line_no,
                 );
 
-                while src_line_no < hunk.source_start && line_idx < file_lines.len() {
-                    let line_content = file_lines[line_idx].to_string();
+                while src_line_no < hunk.source_start && !exhausted {
+                    let Some(line_content) = next_line()? else {
+                        exhausted = true;
+                        break;
+                    };
                     diff.source_lines.push(line_content.clone());
                     diff.dest_lines.push(line_content);
                     src_line_no += 1;
                     dst_line_no += 1;
-                    line_idx += 1;
                 }
 
                 blend_hunk.differences.push(diff);
@@ -145,17 +320,26 @@ impl PatchEngine {
             }
 
             // Skip over the lines covered by this hunk
-            let size = hunk.source_count;
-            let advance = size.min(file_lines.len().saturating_sub(line_idx));
-            line_idx += advance;
-            src_line_no += size;
+            for _ in 0..hunk.source_count {
+                if exhausted || next_line()?.is_none() {
+                    exhausted = true;
+                    break;
+                }
+            }
+            src_line_no += hunk.source_count;
             dst_line_no += hunk.dest_count;
 
             new_hunks.push(hunk.clone());
         }
 
         // If there are remaining lines after the last hunk
-        if line_idx < file_lines.len() {
+        let mut trailing = Vec::new();
+        if !exhausted {
+            while let Some(line_content) = next_line()? {
+                trailing.push(line_content);
+            }
+        }
+        if !trailing.is_empty() {
             let mut blend_hunk = Hunk::new(src_line_no, dst_line_no);
             blend_hunk.hunk_type = HunkType::AddedByBlend;
 
@@ -164,13 +348,8 @@ impl PatchEngine {
                 src_line_no,
                 dst_line_no,
             );
-
-            while line_idx < file_lines.len() {
-                let line_content = file_lines[line_idx].to_string();
-                diff.source_lines.push(line_content.clone());
-                diff.dest_lines.push(line_content);
-                line_idx += 1;
-            }
+            diff.dest_lines = trailing.clone();
+            diff.source_lines = trailing;
 
             blend_hunk.differences.push(diff);
             blend_hunk.recompute_counts();
@@ -329,6 +508,74 @@ mod tests {
         assert_eq!(fp.hunks[0].hunk_type, HunkType::AddedByBlend);
     }
 
+    const BLEND_DIFF: &str = "\
+--- a/file.txt\t2024-01-01
++++ b/file.txt\t2024-01-02
+@@ -3,3 +3,3 @@
+ line3
+-old4
++new4
+ line5";
+    const BLEND_ORIGINAL: &str = "line1\nline2\nline3\nold4\nline5\nline6";
+
+    /// Hunk layout of a blended patch plus the content it reconstructs
+    fn blend_shape(fp: &FilePatch) -> (Vec<(HunkType, usize, usize, usize)>, String) {
+        let hunks = fp
+            .hunks
+            .iter()
+            .map(|h| (h.hunk_type, h.source_start, h.source_count, h.dest_count))
+            .collect();
+        (hunks, PatchEngine::reconstruct_destination(fp).unwrap())
+    }
+
+    fn blended_from_string() -> FilePatch {
+        let mut ps = PatchParser::new().parse_string(BLEND_DIFF).unwrap();
+        PatchEngine::blend_file(&mut ps.files[0], BLEND_ORIGINAL).unwrap();
+        ps.files.remove(0)
+    }
+
+    #[test]
+    fn test_blend_from_reader_matches_string() {
+        let mut ps = PatchParser::new().parse_string(BLEND_DIFF).unwrap();
+        let fp = &mut ps.files[0];
+        PatchEngine::blend_from_reader(fp, BLEND_ORIGINAL.as_bytes()).unwrap();
+
+        assert!(fp.blended);
+        assert_eq!(blend_shape(fp), blend_shape(&blended_from_string()));
+        assert_eq!(
+            PatchEngine::reconstruct_destination(fp).unwrap(),
+            "line1\nline2\nline3\nnew4\nline5\nline6"
+        );
+    }
+
+    #[test]
+    fn test_blend_from_reader_rejects_invalid_utf8() {
+        let mut ps = PatchParser::new().parse_string(BLEND_DIFF).unwrap();
+        let content: &[u8] = b"line1\n\xff\xfe\n";
+        let result = PatchEngine::blend_from_reader(&mut ps.files[0], content);
+        assert!(matches!(result, Err(RCompareError::Io(_))));
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn test_blend_from_vfs_matches_string() {
+        use crate::vfs::MemVfs;
+
+        let vfs = MemVfs::new("blend").with_file("src/file.txt", BLEND_ORIGINAL);
+        let mut ps = PatchParser::new().parse_string(BLEND_DIFF).unwrap();
+        let fp = &mut ps.files[0];
+        PatchEngine::blend_from_vfs(fp, &vfs, Path::new("src/file.txt")).unwrap();
+
+        assert_eq!(blend_shape(fp), blend_shape(&blended_from_string()));
+
+        let missing = PatchEngine::blend_from_vfs(
+            &mut PatchParser::new().parse_string(BLEND_DIFF).unwrap().files[0],
+            &vfs,
+            Path::new("src/missing.txt"),
+        );
+        assert!(matches!(missing, Err(RCompareError::Vfs { .. })));
+    }
+
     #[test]
     fn test_reconstruct_destination() {
         let parser = PatchParser::new();
@@ -370,4 +617,77 @@ mod tests {
         let result = PatchEngine::apply_difference(&mut fp, 999);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_apply_difference_to_twice_is_noop_when_lenient() {
+        let mut fp = make_simple_patch();
+        // Destination as patched: new2/new4 in place
+        let current = "line1\nnew2\nline3\nnew4\nline5";
+
+        let changed =
+            PatchEngine::apply_difference_to(&mut fp, 0, current, ApplyMode::Lenient).unwrap();
+        assert!(changed);
+        let applied = PatchEngine::reconstruct_destination(&fp).unwrap();
+        assert!(applied.contains("old2"));
+
+        // The same difference checked against the already-updated content
+        let mut rerun = make_simple_patch();
+        let changed =
+            PatchEngine::apply_difference_to(&mut rerun, 0, &applied, ApplyMode::Lenient).unwrap();
+        assert!(!changed);
+        let (hi, di) = rerun.difference_indices()[0];
+        assert!(rerun.hunks[hi].differences[di].applied);
+        assert!(!rerun.hunks[hi].differences[di].unsaved);
+        assert_eq!(rerun.applied_count, 1);
+
+        // Applying it again on the same model stays a no-op
+        PatchEngine::apply_difference_to(&mut rerun, 0, &applied, ApplyMode::Strict).unwrap();
+        assert_eq!(rerun.applied_count, 1);
+    }
+
+    #[test]
+    fn test_apply_difference_to_twice_errors_when_strict() {
+        let current = "line1\nnew2\nline3\nnew4\nline5";
+        let mut fp = make_simple_patch();
+        PatchEngine::apply_difference_to(&mut fp, 0, current, ApplyMode::Strict).unwrap();
+        let applied = PatchEngine::reconstruct_destination(&fp).unwrap();
+
+        let mut rerun = make_simple_patch();
+        let result = PatchEngine::apply_difference_to(&mut rerun, 0, &applied, ApplyMode::Strict);
+        assert!(matches!(result, Err(RCompareError::PatchApply(_))));
+        assert_eq!(rerun.applied_count, 0);
+
+        let mut conflicting = make_simple_patch();
+        let result = PatchEngine::apply_difference_to(
+            &mut conflicting,
+            0,
+            "line1\nother\nline3\nnew4\nline5",
+            ApplyMode::Lenient,
+        );
+        assert!(matches!(result, Err(RCompareError::PatchApply(_))));
+        let (hi, di) = conflicting.difference_indices()[0];
+        assert!(conflicting.hunks[hi].differences[di].conflict);
+    }
+
+    #[test]
+    fn test_apply_all_to_rerun_changes_nothing() {
+        let current = "line1\nnew2\nline3\nnew4\nline5";
+        let mut fp = make_simple_patch();
+        assert_eq!(
+            PatchEngine::apply_all_to(&mut fp, current, ApplyMode::Lenient).unwrap(),
+            2
+        );
+        let applied = PatchEngine::reconstruct_destination(&fp).unwrap();
+
+        let mut rerun = make_simple_patch();
+        assert_eq!(
+            PatchEngine::apply_all_to(&mut rerun, &applied, ApplyMode::Lenient).unwrap(),
+            0
+        );
+        assert_eq!(rerun.applied_count, 2);
+        assert_eq!(
+            PatchEngine::reconstruct_destination(&rerun).unwrap(),
+            applied
+        );
+    }
 }
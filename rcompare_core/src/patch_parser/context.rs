@@ -4,6 +4,8 @@ use rcompare_common::{
 use regex::Regex;
 use std::sync::LazyLock;
 
+use super::{malformed, parse_number, strip_cr};
+
 // Context diff file headers: *** source_file\ttimestamp and --- dest_file\ttimestamp
 static FILE_HEADER_SRC: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r"^\*\*\* ([^\t]+)(?:\t(.*))?$").unwrap()
@@ -28,20 +30,29 @@ static DST_RANGE: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r"^--- (\d+)(?:,(\d+))? ----?$").unwrap()
 });
 
+const TRUNCATED_HEADER: &str = "truncated file header: expected '---' after '***'";
+
 /// Parse context diff format into a list of FilePatches.
-pub fn parse_context(lines: &[&str]) -> Result<Vec<FilePatch>, RCompareError> {
+///
+/// In strict mode a `***` file header without a following `---` header or
+/// an overflowing line number is an error; lenient mode skips over them.
+pub fn parse_context(lines: &[&str], lenient: bool) -> Result<Vec<FilePatch>, RCompareError> {
     let mut file_patches = Vec::new();
     let mut i = 0;
 
     while i < lines.len() {
         // Look for *** source file header
-        if let Some(src_cap) = FILE_HEADER_SRC.captures(lines[i]) {
+        if let Some(src_cap) = FILE_HEADER_SRC.captures(strip_cr(lines[i])) {
             if i + 1 >= lines.len() {
+                if !lenient {
+                    return Err(malformed(i, TRUNCATED_HEADER));
+                }
                 i += 1;
                 continue;
             }
-            if let Some(dst_cap) = FILE_HEADER_DST.captures(lines[i + 1]) {
+            if let Some(dst_cap) = FILE_HEADER_DST.captures(strip_cr(lines[i + 1])) {
                 let mut fp = FilePatch::new();
+                fp.crlf_headers = lines[i].ends_with('\r');
                 fp.source = src_cap.get(1).map_or("", |m| m.as_str()).to_string();
                 fp.source_timestamp = src_cap.get(2).map_or("", |m| m.as_str()).to_string();
                 fp.destination = dst_cap.get(1).map_or("", |m| m.as_str()).to_string();
@@ -51,7 +62,8 @@ pub fn parse_context(lines: &[&str]) -> Result<Vec<FilePatch>, RCompareError> {
 
                 // Parse hunks (each starts with ***************)
                 while i < lines.len() {
-                    if let Some(sep_cap) = HUNK_SEPARATOR.captures(lines[i]) {
+                    if let Some(sep_cap) = HUNK_SEPARATOR.captures(strip_cr(lines[i])) {
+                        let hunk_start = i;
                         let func = sep_cap
                             .get(1)
                             .map(|m| m.as_str().trim())
@@ -62,13 +74,15 @@ pub fn parse_context(lines: &[&str]) -> Result<Vec<FilePatch>, RCompareError> {
 
                         // Parse source section: *** start,end ***
                         let (src_start, src_end, src_lines) = if i < lines.len() {
-                            if let Some(src_range) = SRC_RANGE.captures(lines[i]) {
-                                let start: usize = src_range[1].parse().unwrap_or(0);
-                                let end: usize = src_range
-                                    .get(2)
-                                    .map_or(start, |m| m.as_str().parse().unwrap_or(start));
+                            if let Some(src_range) = SRC_RANGE.captures(strip_cr(lines[i])) {
+                                let start = parse_number(&src_range[1], i, lenient, 0)?;
+                                let end = match src_range.get(2) {
+                                    Some(m) => parse_number(m.as_str(), i, lenient, start)?,
+                                    None => start,
+                                };
                                 i += 1;
-                                let src_body = collect_context_body(lines, &mut i);
+                                let src_body =
+                                    collect_context_body(lines, &mut i, range_len(start, end));
                                 (start, end, src_body)
                             } else {
                                 (0, 0, Vec::new())
@@ -79,13 +93,15 @@ pub fn parse_context(lines: &[&str]) -> Result<Vec<FilePatch>, RCompareError> {
 
                         // Parse dest section: --- start,end ----
                         let (dst_start, dst_end, dst_lines) = if i < lines.len() {
-                            if let Some(dst_range) = DST_RANGE.captures(lines[i]) {
-                                let start: usize = dst_range[1].parse().unwrap_or(0);
-                                let end: usize = dst_range
-                                    .get(2)
-                                    .map_or(start, |m| m.as_str().parse().unwrap_or(start));
+                            if let Some(dst_range) = DST_RANGE.captures(strip_cr(lines[i])) {
+                                let start = parse_number(&dst_range[1], i, lenient, 0)?;
+                                let end = match dst_range.get(2) {
+                                    Some(m) => parse_number(m.as_str(), i, lenient, start)?,
+                                    None => start,
+                                };
                                 i += 1;
-                                let dst_body = collect_context_body(lines, &mut i);
+                                let dst_body =
+                                    collect_context_body(lines, &mut i, range_len(start, end));
                                 (start, end, dst_body)
                             } else {
                                 (0, 0, Vec::new())
@@ -94,15 +110,16 @@ pub fn parse_context(lines: &[&str]) -> Result<Vec<FilePatch>, RCompareError> {
                             (0, 0, Vec::new())
                         };
 
-                        let src_count = if src_end >= src_start {
-                            src_end - src_start + 1
+                        // A single-number range covers one line or none, so
+                        // count from the bodies when there are any
+                        let (src_count, dst_count) = if src_lines.is_empty() && dst_lines.is_empty()
+                        {
+                            (range_len(src_start, src_end), range_len(dst_start, dst_end))
                         } else {
-                            0
-                        };
-                        let dst_count = if dst_end >= dst_start {
-                            dst_end - dst_start + 1
-                        } else {
-                            0
+                            (
+                                side_len(&src_lines, &dst_lines),
+                                side_len(&dst_lines, &src_lines),
+                            )
                         };
 
                         let mut hunk = Hunk::new(src_start, dst_start);
@@ -118,9 +135,10 @@ pub fn parse_context(lines: &[&str]) -> Result<Vec<FilePatch>, RCompareError> {
                             src_start,
                             dst_start,
                         );
+                        attach_raw(&mut hunk, &lines[hunk_start..i]);
 
                         fp.hunks.push(hunk);
-                    } else if FILE_HEADER_SRC.is_match(lines[i]) {
+                    } else if FILE_HEADER_SRC.is_match(strip_cr(lines[i])) {
                         // Next file starts
                         break;
                     } else {
@@ -129,6 +147,8 @@ pub fn parse_context(lines: &[&str]) -> Result<Vec<FilePatch>, RCompareError> {
                 }
 
                 file_patches.push(fp);
+            } else if !lenient {
+                return Err(malformed(i, TRUNCATED_HEADER));
             } else {
                 i += 1;
             }
@@ -153,37 +173,89 @@ enum ContextLine {
     Changed(String),
 }
 
-fn collect_context_body(lines: &[&str], i: &mut usize) -> Vec<ContextLine> {
+impl ContextLine {
+    fn text(&self) -> &str {
+        match self {
+            ContextLine::Context(text)
+            | ContextLine::Added(text)
+            | ContextLine::Removed(text)
+            | ContextLine::Changed(text) => text,
+        }
+    }
+}
+
+/// Number of lines in a `start,end` range, zero when `end < start`
+fn range_len(start: usize, end: usize) -> usize {
+    end.checked_sub(start).map_or(0, |d| d.saturating_add(1))
+}
+
+/// Lines on one side of a hunk; an omitted section has no changes, so it
+/// consists of the other section's context lines
+fn side_len(own: &[ContextLine], other: &[ContextLine]) -> usize {
+    if own.is_empty() {
+        other
+            .iter()
+            .filter(|line| matches!(line, ContextLine::Context(_)))
+            .count()
+    } else {
+        own.len()
+    }
+}
+
+/// Collect the body lines of one section.
+///
+/// Some tools strip the trailing spaces of an empty context line, leaving a
+/// blank line; those count as context while the range expects more lines.
+fn collect_context_body(lines: &[&str], i: &mut usize, expected: usize) -> Vec<ContextLine> {
     let mut body = Vec::new();
     while *i < lines.len() {
         let line = lines[*i];
-        if line.len() >= 2 {
-            match &line[..2] {
-                "  " => {
-                    body.push(ContextLine::Context(line[2..].to_string()));
-                    *i += 1;
-                }
-                "- " => {
-                    body.push(ContextLine::Removed(line[2..].to_string()));
-                    *i += 1;
-                }
-                "+ " => {
-                    body.push(ContextLine::Added(line[2..].to_string()));
-                    *i += 1;
-                }
-                "! " => {
-                    body.push(ContextLine::Changed(line[2..].to_string()));
-                    *i += 1;
-                }
-                _ => break,
+        let entry = match line.get(..2) {
+            Some("  ") => ContextLine::Context(line[2..].to_string()),
+            Some("- ") => ContextLine::Removed(line[2..].to_string()),
+            Some("+ ") => ContextLine::Added(line[2..].to_string()),
+            Some("! ") => ContextLine::Changed(line[2..].to_string()),
+            _ if strip_cr(line).is_empty() && body.len() < expected => {
+                ContextLine::Context(String::new())
             }
-        } else {
-            break;
-        }
+            _ => break,
+        };
+        body.push(entry);
+        *i += 1;
     }
     body
 }
 
+/// Keep the original hunk text so the serializer can reproduce it verbatim.
+///
+/// Context hunks list the source and destination sides separately, so the
+/// lines can't be split per difference: the first difference gets them all.
+fn attach_raw(hunk: &mut Hunk, raw: &[&str]) {
+    for (idx, diff) in hunk.differences.iter_mut().enumerate() {
+        diff.raw = Some(if idx == 0 {
+            raw.iter().map(|l| l.to_string()).collect()
+        } else {
+            Vec::new()
+        });
+    }
+}
+
+/// Take the run of lines at `idx` matching `kind`, advancing past it
+fn take_run(lines: &[ContextLine], idx: &mut usize, kind: fn(&ContextLine) -> bool) -> Vec<String> {
+    let mut run = Vec::new();
+    while let Some(line) = lines.get(*idx).filter(|line| kind(line)) {
+        run.push(format!("{}\n", line.text()));
+        *idx += 1;
+    }
+    run
+}
+
+/// Pair up the source and destination sections of a hunk.
+///
+/// Both sections are walked in step, synchronised on their context lines.
+/// Deletions come before insertions at the same position, and a run of `!`
+/// lines on either side pairs with the run at the same place on the other.
+/// A section may be omitted entirely when it holds no changes.
 fn merge_context_diffs(
     hunk: &mut Hunk,
     src_lines: &[ContextLine],
@@ -191,121 +263,60 @@ fn merge_context_diffs(
     src_start: usize,
     dst_start: usize,
 ) {
+    let is_context = |line: &ContextLine| matches!(line, ContextLine::Context(_));
     let mut si = 0;
     let mut di = 0;
     let mut src_line_no = src_start;
     let mut dst_line_no = dst_start;
 
     while si < src_lines.len() || di < dst_lines.len() {
-        // Context lines from source side
-        if si < src_lines.len() {
-            if let ContextLine::Context(ref text) = src_lines[si] {
-                let mut diff =
-                    PatchDifference::new(DifferenceType::Unchanged, src_line_no, dst_line_no);
-                diff.source_lines.push(format!("{text}\n"));
-                diff.dest_lines.push(format!("{text}\n"));
-                hunk.differences.push(diff);
-                si += 1;
-                // Advance dest past matching context
-                if di < dst_lines.len() {
-                    if let ContextLine::Context(_) = dst_lines[di] {
-                        di += 1;
-                    }
-                }
-                src_line_no += 1;
-                dst_line_no += 1;
-                continue;
-            }
-        }
-
-        // Removed lines (source only)
-        if si < src_lines.len() {
-            if let ContextLine::Removed(ref _text) = src_lines[si] {
-                let mut diff =
-                    PatchDifference::new(DifferenceType::Delete, src_line_no, dst_line_no);
-                while si < src_lines.len() {
-                    if let ContextLine::Removed(ref text) = src_lines[si] {
-                        diff.source_lines.push(format!("{text}\n"));
-                        si += 1;
-                        src_line_no += 1;
-                    } else {
-                        break;
-                    }
+        let src = src_lines.get(si);
+        let dst = dst_lines.get(di);
+
+        let (diff_type, source_lines, dest_lines) = match (src, dst) {
+            (Some(ContextLine::Removed(_)), _) => (
+                DifferenceType::Delete,
+                take_run(src_lines, &mut si, |l| matches!(l, ContextLine::Removed(_))),
+                Vec::new(),
+            ),
+            (_, Some(ContextLine::Added(_))) => (
+                DifferenceType::Insert,
+                Vec::new(),
+                take_run(dst_lines, &mut di, |l| matches!(l, ContextLine::Added(_))),
+            ),
+            (Some(ContextLine::Changed(_)), _) | (_, Some(ContextLine::Changed(_))) => (
+                DifferenceType::Change,
+                take_run(src_lines, &mut si, |l| matches!(l, ContextLine::Changed(_))),
+                take_run(dst_lines, &mut di, |l| matches!(l, ContextLine::Changed(_))),
+            ),
+            (Some(ContextLine::Context(text)), _) | (None, Some(ContextLine::Context(text))) => {
+                let line = vec![format!("{text}\n")];
+                if src.is_some_and(is_context) {
+                    si += 1;
                 }
-                hunk.differences.push(diff);
-                continue;
-            }
-        }
-
-        // Added lines (dest only)
-        if di < dst_lines.len() {
-            if let ContextLine::Added(ref _text) = dst_lines[di] {
-                let mut diff =
-                    PatchDifference::new(DifferenceType::Insert, src_line_no, dst_line_no);
-                while di < dst_lines.len() {
-                    if let ContextLine::Added(ref text) = dst_lines[di] {
-                        diff.dest_lines.push(format!("{text}\n"));
-                        di += 1;
-                        dst_line_no += 1;
-                    } else {
-                        break;
-                    }
+                if dst.is_some_and(is_context) {
+                    di += 1;
                 }
-                hunk.differences.push(diff);
-                continue;
+                (DifferenceType::Unchanged, line.clone(), line)
             }
-        }
-
-        // Changed lines (! in both source and dest)
-        if si < src_lines.len() {
-            if let ContextLine::Changed(ref _text) = src_lines[si] {
-                let mut diff =
-                    PatchDifference::new(DifferenceType::Change, src_line_no, dst_line_no);
-                while si < src_lines.len() {
-                    if let ContextLine::Changed(ref text) = src_lines[si] {
-                        diff.source_lines.push(format!("{text}\n"));
-                        si += 1;
-                        src_line_no += 1;
-                    } else {
-                        break;
-                    }
+            _ => {
+                // "+ " in the source section or "- " in the destination
+                // section; skip the stray line
+                if src.is_some() {
+                    si += 1;
+                } else {
+                    di += 1;
                 }
-                while di < dst_lines.len() {
-                    if let ContextLine::Changed(ref text) = dst_lines[di] {
-                        diff.dest_lines.push(format!("{text}\n"));
-                        di += 1;
-                        dst_line_no += 1;
-                    } else {
-                        break;
-                    }
-                }
-                hunk.differences.push(diff);
                 continue;
             }
-        }
-
-        // Skip context on dest side if source is exhausted
-        if di < dst_lines.len() {
-            if let ContextLine::Context(ref text) = dst_lines[di] {
-                let mut diff =
-                    PatchDifference::new(DifferenceType::Unchanged, src_line_no, dst_line_no);
-                diff.source_lines.push(format!("{text}\n"));
-                diff.dest_lines.push(format!("{text}\n"));
-                hunk.differences.push(diff);
-                di += 1;
-                src_line_no += 1;
-                dst_line_no += 1;
-                continue;
-            }
-        }
-
-        // Safety: advance to avoid infinite loop
-        if si < src_lines.len() {
-            si += 1;
-        }
-        if di < dst_lines.len() {
-            di += 1;
-        }
+        };
+
+        let mut diff = PatchDifference::new(diff_type, src_line_no, dst_line_no);
+        src_line_no = src_line_no.saturating_add(source_lines.len());
+        dst_line_no = dst_line_no.saturating_add(dest_lines.len());
+        diff.source_lines = source_lines;
+        diff.dest_lines = dest_lines;
+        hunk.differences.push(diff);
     }
 }
 
@@ -328,7 +339,7 @@ mod tests {
 ! new_line
   line3";
         let lines: Vec<&str> = input.lines().collect();
-        let result = parse_context(&lines).unwrap();
+        let result = parse_context(&lines, false).unwrap();
 
         assert_eq!(result.len(), 1);
         let fp = &result[0];
@@ -363,7 +374,7 @@ mod tests {
   line1
   line3";
         let lines: Vec<&str> = input.lines().collect();
-        let result = parse_context(&lines).unwrap();
+        let result = parse_context(&lines, false).unwrap();
         let hunk = &result[0].hunks[0];
         let delete = hunk
             .differences
@@ -386,7 +397,7 @@ mod tests {
 + added
   line2";
         let lines: Vec<&str> = input.lines().collect();
-        let result = parse_context(&lines).unwrap();
+        let result = parse_context(&lines, false).unwrap();
         let hunk = &result[0].hunks[0];
         let insert = hunk
             .differences
@@ -394,4 +405,142 @@ mod tests {
             .find(|d| d.diff_type == DifferenceType::Insert);
         assert!(insert.is_some());
     }
+
+    /// One line per difference: type, line numbers and `source>dest` text
+    fn summary(hunk: &Hunk) -> Vec<String> {
+        let join = |lines: &[String]| {
+            lines
+                .iter()
+                .map(|l| l.trim_end_matches('\n'))
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        hunk.differences
+            .iter()
+            .map(|d| {
+                let tag = match d.diff_type {
+                    DifferenceType::Unchanged => '=',
+                    DifferenceType::Insert => '+',
+                    DifferenceType::Delete => '-',
+                    DifferenceType::Change => '!',
+                };
+                format!(
+                    "{tag} {}:{} {}>{}",
+                    d.source_line_no,
+                    d.dest_line_no,
+                    join(&d.source_lines),
+                    join(&d.dest_lines)
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_context_pure_addition_omits_source_section() {
+        let input = "\
+*** a/file.txt\t2024-01-01
+--- b/file.txt\t2024-01-02
+***************
+*** 1,2 ****
+--- 1,4 ----
+  line1
++ added1
++ added2
+  line2";
+        let lines_in: Vec<&str> = input.lines().collect();
+        let hunk = &parse_context(&lines_in, false).unwrap()[0].hunks[0];
+
+        assert_eq!((hunk.source_count, hunk.dest_count), (2, 4));
+        assert_eq!(
+            summary(hunk),
+            [
+                "= 1:1 line1>line1",
+                "+ 2:2 >added1,added2",
+                "= 2:4 line2>line2",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_context_pure_deletion_omits_dest_section() {
+        let input = "\
+*** a/file.txt\t2024-01-01
+--- b/file.txt\t2024-01-02
+***************
+*** 1,4 ****
+  line1
+- gone1
+- gone2
+  line2
+--- 1,2 ----";
+        let lines_in: Vec<&str> = input.lines().collect();
+        let hunk = &parse_context(&lines_in, false).unwrap()[0].hunks[0];
+
+        assert_eq!((hunk.source_count, hunk.dest_count), (4, 2));
+        assert_eq!(
+            summary(hunk),
+            [
+                "= 1:1 line1>line1",
+                "- 2:2 gone1,gone2>",
+                "= 4:2 line2>line2",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_context_mixed_changes_stay_in_step() {
+        // The last line is empty; its stripped context line in the source
+        // section is a bare newline
+        let input = "\
+*** a/file.txt\t2024-01-01
+--- b/file.txt\t2024-01-02
+***************
+*** 1,6 ****
+! a
+  b
+- c
+  d
+! e
+
+--- 1,6 ----
+! A
++ new
+  b
+  d
+! E
+  ";
+        let lines_in: Vec<&str> = input.lines().collect();
+        let hunk = &parse_context(&lines_in, false).unwrap()[0].hunks[0];
+
+        assert_eq!((hunk.source_count, hunk.dest_count), (6, 6));
+        assert_eq!(
+            summary(hunk),
+            [
+                "! 1:1 a>A",
+                "+ 2:2 >new",
+                "= 2:3 b>b",
+                "- 3:4 c>",
+                "= 4:4 d>d",
+                "! 5:5 e>E",
+                "= 6:6 >",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_context_empty_source_range() {
+        let input = "\
+*** /dev/null\t2024-01-01
+--- b/new.txt\t2024-01-02
+***************
+*** 0 ****
+--- 1,2 ----
++ first
++ second";
+        let lines_in: Vec<&str> = input.lines().collect();
+        let hunk = &parse_context(&lines_in, false).unwrap()[0].hunks[0];
+
+        assert_eq!((hunk.source_count, hunk.dest_count), (0, 2));
+        assert_eq!(summary(hunk), ["+ 0:1 >first,second"]);
+    }
 }
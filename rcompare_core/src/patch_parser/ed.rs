@@ -4,6 +4,8 @@ use rcompare_common::{
 use regex::Regex;
 use std::sync::LazyLock;
 
+use super::{malformed, parse_number, range_len, strip_cr};
+
 // Ed command: N[,M]a or N[,M]c or N[,M]d
 static ED_COMMAND: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r"^(\d+)(?:,(\d+))?([acd])$").unwrap()
@@ -16,41 +18,52 @@ static ED_COMMAND: LazyLock<Regex> = LazyLock::new(|| {
 /// - `N,Md` — delete lines N through M
 /// - `N,Mc` — change lines N through M
 ///
-/// Content lines follow the command, terminated by a lone `.`
-pub fn parse_ed(lines: &[&str]) -> Result<Vec<FilePatch>, RCompareError> {
+/// Content lines follow the `a` and `c` commands, terminated by a lone `.`
+///
+/// In strict mode an overflowing line number, a range that ends before it
+/// starts or content missing its terminating `.` is an error.
+pub fn parse_ed(lines: &[&str], lenient: bool) -> Result<Vec<FilePatch>, RCompareError> {
     let mut fp = FilePatch::new();
     let mut i = 0;
 
     while i < lines.len() {
-        if let Some(cap) = ED_COMMAND.captures(lines[i]) {
-            let start: usize = cap[1].parse().unwrap_or(0);
-            let end: usize = cap
-                .get(2)
-                .map_or(start, |m| m.as_str().parse().unwrap_or(start));
+        if let Some(cap) = ED_COMMAND.captures(strip_cr(lines[i])) {
+            let cmd_index = i;
+            let start = parse_number(&cap[1], i, lenient, 0)?;
+            let end = match cap.get(2) {
+                Some(m) => parse_number(m.as_str(), i, lenient, start)?,
+                None => start,
+            };
             let cmd = &cap[3];
+            let line_count = range_len(start, end, i, lenient)?;
 
             i += 1;
 
-            // Collect content lines until "."
+            // Collect content lines until "." (delete has no content)
             let mut content_lines = Vec::new();
-            while i < lines.len() && lines[i] != "." {
-                content_lines.push(format!("{}\n", lines[i]));
-                i += 1;
-            }
-            // Skip the terminating "."
-            if i < lines.len() && lines[i] == "." {
-                i += 1;
+            if cmd != "d" {
+                while i < lines.len() && strip_cr(lines[i]) != "." {
+                    content_lines.push(format!("{}\n", lines[i]));
+                    i += 1;
+                }
+                // Skip the terminating "."
+                if i < lines.len() {
+                    i += 1;
+                } else if !lenient {
+                    return Err(malformed(
+                        cmd_index,
+                        "unterminated ed command: missing '.' line",
+                    ));
+                }
             }
 
-            let line_count = end - start + 1;
-
             match cmd {
                 "a" => {
                     // Append after line `start`
                     let mut diff =
-                        PatchDifference::new(DifferenceType::Insert, start, start + 1);
+                        PatchDifference::new(DifferenceType::Insert, start, start.saturating_add(1));
                     diff.dest_lines = content_lines;
-                    let mut hunk = Hunk::new(start, start + 1);
+                    let mut hunk = Hunk::new(start, start.saturating_add(1));
                     hunk.source_count = 0;
                     hunk.dest_count = diff.dest_lines.len();
                     hunk.differences.push(diff);
@@ -110,7 +123,7 @@ added line 1
 added line 2
 .";
         let lines: Vec<&str> = input.lines().collect();
-        let result = parse_ed(&lines).unwrap();
+        let result = parse_ed(&lines, false).unwrap();
         assert_eq!(result.len(), 1);
         let hunk = &result[0].hunks[0];
         assert_eq!(hunk.differences[0].diff_type, DifferenceType::Insert);
@@ -122,7 +135,7 @@ added line 2
         let input = "\
 3,5d";
         let lines: Vec<&str> = input.lines().collect();
-        let result = parse_ed(&lines).unwrap();
+        let result = parse_ed(&lines, false).unwrap();
         assert_eq!(result.len(), 1);
         let hunk = &result[0].hunks[0];
         assert_eq!(hunk.differences[0].diff_type, DifferenceType::Delete);
@@ -138,7 +151,7 @@ new line 2
 new line 3
 .";
         let lines: Vec<&str> = input.lines().collect();
-        let result = parse_ed(&lines).unwrap();
+        let result = parse_ed(&lines, false).unwrap();
         let hunk = &result[0].hunks[0];
         assert_eq!(hunk.differences[0].diff_type, DifferenceType::Change);
         assert_eq!(hunk.differences[0].source_lines.len(), 2); // old lines 1-2
@@ -155,7 +168,7 @@ replacement
 appended
 .";
         let lines: Vec<&str> = input.lines().collect();
-        let result = parse_ed(&lines).unwrap();
+        let result = parse_ed(&lines, false).unwrap();
         assert_eq!(result[0].hunks.len(), 2);
     }
 }
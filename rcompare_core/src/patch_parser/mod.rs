@@ -25,19 +25,41 @@ static PAT_ED: LazyLock<Regex> =
 
 /// Parser for diff/patch output in multiple formats.
 ///
-/// Automatically detects the generator (CVS, Perforce, plain diff) and
+/// Automatically detects the generator (CVS, Perforce, git, plain diff) and
 /// the format (unified, context, normal, ed, RCS), then delegates to
 /// the appropriate sub-parser.
-pub struct PatchParser;
+///
+/// By default parsing is strict: truncated file headers, invalid hunk
+/// headers, hunks whose body does not match the declared line counts and
+/// line numbers that overflow are reported as
+/// [`RCompareError::MalformedPatch`] with the 1-based input line. Lenient
+/// mode ([`PatchParser::with_lenient`]) skips what it cannot parse and
+/// recomputes hunk counts from the lines actually present.
+pub struct PatchParser {
+    lenient: bool,
+}
 
 impl PatchParser {
     pub fn new() -> Self {
-        Self
+        Self { lenient: false }
+    }
+
+    /// Enable best-effort parsing of malformed input
+    pub fn with_lenient(mut self, enabled: bool) -> Self {
+        self.lenient = enabled;
+        self
     }
 
     /// Parse diff text from a string.
+    ///
+    /// Lines are split on `\n` only, so a `\r` from a CRLF diff stays on the
+    /// line and ends up in the hunk content. This keeps CRLF (and mixed)
+    /// line endings intact through parsing, serialization and application.
     pub fn parse_string(&self, input: &str) -> Result<PatchSet, RCompareError> {
-        let mut lines_owned: Vec<String> = input.lines().map(|l| l.to_string()).collect();
+        let mut lines_owned: Vec<String> = input
+            .split_inclusive('\n')
+            .map(|l| l.strip_suffix('\n').unwrap_or(l).to_string())
+            .collect();
 
         // Clean up "\ No newline at end of file" markers
         Self::clean_no_newline(&mut lines_owned);
@@ -47,19 +69,20 @@ impl PatchParser {
         let generator = Self::detect_generator(&lines);
         let format = Self::detect_format(&lines);
 
+        let lenient = self.lenient;
         let file_patches = match format {
-            DiffFormat::Unified => unified::parse_unified(&lines)?,
-            DiffFormat::Context => context::parse_context(&lines)?,
-            DiffFormat::Normal => normal::parse_normal(&lines)?,
-            DiffFormat::Ed => ed::parse_ed(&lines)?,
-            DiffFormat::Rcs => rcs::parse_rcs(&lines)?,
+            DiffFormat::Unified => unified::parse_unified(&lines, lenient)?,
+            DiffFormat::Context => context::parse_context(&lines, lenient)?,
+            DiffFormat::Normal => normal::parse_normal(&lines, lenient)?,
+            DiffFormat::Ed => ed::parse_ed(&lines, lenient)?,
+            DiffFormat::Rcs => rcs::parse_rcs(&lines, lenient)?,
             DiffFormat::Unknown => {
                 // Try unified first (most common), fall back to normal
-                let result = unified::parse_unified(&lines)?;
+                let result = unified::parse_unified(&lines, lenient)?;
                 if !result.is_empty() {
                     result
                 } else {
-                    normal::parse_normal(&lines)?
+                    normal::parse_normal(&lines, lenient)?
                 }
             }
         };
@@ -81,6 +104,7 @@ impl PatchParser {
     ///
     /// - "Index: " prefix → CVSDiff
     /// - "==== " prefix → Perforce
+    /// - "diff --git " prefix → Git
     /// - Otherwise → Diff (plain diff)
     pub fn detect_generator(lines: &[&str]) -> DiffGenerator {
         for line in lines {
@@ -90,6 +114,9 @@ impl PatchParser {
             if line.starts_with("==== ") {
                 return DiffGenerator::Perforce;
             }
+            if line.starts_with("diff --git ") {
+                return DiffGenerator::Git;
+            }
         }
         DiffGenerator::Diff
     }
@@ -97,6 +124,7 @@ impl PatchParser {
     /// Detect the diff output format by scanning for characteristic patterns.
     pub fn detect_format(lines: &[&str]) -> DiffFormat {
         for line in lines {
+            let line = strip_cr(line);
             if PAT_UNIFIED.is_match(line) {
                 return DiffFormat::Unified;
             }
@@ -157,6 +185,59 @@ impl PatchParser {
     }
 }
 
+/// Strip the carriage return left on a line split from a CRLF diff.
+///
+/// Sub-parsers match headers and commands against the stripped line while
+/// taking hunk content from the raw line, so the original terminator is kept.
+pub(crate) fn strip_cr(line: &str) -> &str {
+    line.strip_suffix('\r').unwrap_or(line)
+}
+
+/// Build a [`RCompareError::MalformedPatch`] for a 0-based line index.
+pub(crate) fn malformed(index: usize, reason: impl Into<String>) -> RCompareError {
+    RCompareError::MalformedPatch {
+        line: index + 1,
+        reason: reason.into(),
+    }
+}
+
+/// Parse a line number or count taken from a header or command.
+///
+/// Values that overflow `usize` are an error in strict mode and fall back
+/// to `fallback` in lenient mode.
+pub(crate) fn parse_number(
+    text: &str,
+    index: usize,
+    lenient: bool,
+    fallback: usize,
+) -> Result<usize, RCompareError> {
+    match text.parse() {
+        Ok(n) => Ok(n),
+        Err(_) if lenient => Ok(fallback),
+        Err(_) => Err(malformed(index, format!("line number out of range: {text}"))),
+    }
+}
+
+/// Number of lines in the inclusive range `start..=end`.
+///
+/// A range that ends before it starts is an error in strict mode and is
+/// treated as empty in lenient mode.
+pub(crate) fn range_len(
+    start: usize,
+    end: usize,
+    index: usize,
+    lenient: bool,
+) -> Result<usize, RCompareError> {
+    match end.checked_sub(start).and_then(|d| d.checked_add(1)) {
+        Some(len) => Ok(len),
+        None if lenient => Ok(0),
+        None => Err(malformed(
+            index,
+            format!("invalid line range {start},{end}"),
+        )),
+    }
+}
+
 impl Default for PatchParser {
     fn default() -> Self {
         Self::new()
@@ -179,6 +260,12 @@ mod tests {
         assert_eq!(PatchParser::detect_generator(&lines), DiffGenerator::Perforce);
     }
 
+    #[test]
+    fn test_detect_generator_git() {
+        let lines = vec!["diff --git a/file.txt b/file.txt", "index 83db48f..bf269f4 100644"];
+        assert_eq!(PatchParser::detect_generator(&lines), DiffGenerator::Git);
+    }
+
     #[test]
     fn test_detect_generator_diff() {
         let lines = vec!["--- a/file.txt", "+++ b/file.txt"];
@@ -272,6 +359,161 @@ mod tests {
         assert_eq!(result.files.len(), 1);
     }
 
+    #[test]
+    fn test_parse_string_crlf_keeps_carriage_returns() {
+        let input = "--- a/f\r\n+++ b/f\r\n@@ -1,2 +1,2 @@\r\n ctx\r\n-old\r\n+new\n";
+        let parser = PatchParser::new();
+        let result = parser.parse_string(input).unwrap();
+        assert_eq!(result.format, DiffFormat::Unified);
+
+        let fp = &result.files[0];
+        assert_eq!(fp.source, "a/f");
+        assert_eq!(fp.destination, "b/f");
+        assert!(fp.crlf_headers);
+        let diffs = &fp.hunks[0].differences;
+        assert_eq!(diffs[0].source_lines, vec!["ctx\r\n"]);
+        assert_eq!(diffs[1].source_lines, vec!["old\r\n"]);
+        assert_eq!(diffs[1].dest_lines, vec!["new\n"]);
+    }
+
+    fn malformed_line(result: Result<PatchSet, RCompareError>) -> (usize, String) {
+        match result {
+            Err(RCompareError::MalformedPatch { line, reason }) => (line, reason),
+            other => panic!("expected MalformedPatch, got {:?}", other.map(|ps| ps.files.len())),
+        }
+    }
+
+    #[test]
+    fn test_malformed_truncated_header() {
+        let input = "--- a/file.txt\n";
+        let (line, reason) = malformed_line(PatchParser::new().parse_string(input));
+        assert_eq!(line, 1);
+        assert!(reason.contains("truncated file header"));
+
+        let lenient = PatchParser::new().with_lenient(true);
+        assert!(lenient.parse_string(input).unwrap().files.is_empty());
+    }
+
+    #[test]
+    fn test_malformed_hunk_count_mismatch() {
+        let input = "\
+--- a/file.txt
++++ b/file.txt
+@@ -1,5 +1,5 @@
+ line1
+-old
++new
+";
+        let (line, reason) = malformed_line(PatchParser::new().parse_string(input));
+        assert_eq!(line, 3);
+        assert!(reason.contains("declares 5 source"));
+
+        // Lenient mode keeps the hunk but trusts the body over the header
+        let lenient = PatchParser::new().with_lenient(true);
+        let ps = lenient.parse_string(input).unwrap();
+        let hunk = &ps.files[0].hunks[0];
+        assert_eq!(hunk.source_count, 2);
+        assert_eq!(hunk.dest_count, 2);
+    }
+
+    #[test]
+    fn test_malformed_overflowing_line_number() {
+        let input = "\
+--- a/file.txt
++++ b/file.txt
+@@ -99999999999999999999999,1 +1,1 @@
+-old
++new
+";
+        let (line, reason) = malformed_line(PatchParser::new().parse_string(input));
+        assert_eq!(line, 3);
+        assert!(reason.contains("out of range"));
+
+        let lenient = PatchParser::new().with_lenient(true);
+        let ps = lenient.parse_string(input).unwrap();
+        assert_eq!(ps.files[0].hunks[0].source_start, 0);
+    }
+
+    #[test]
+    fn test_malformed_negative_line_number() {
+        let input = "\
+--- a/file.txt
++++ b/file.txt
+@@ -1,1 +-1,1 @@
+-old
++new
+";
+        let (line, reason) = malformed_line(PatchParser::new().parse_string(input));
+        assert_eq!(line, 3);
+        assert!(reason.contains("invalid hunk header"));
+
+        let lenient = PatchParser::new().with_lenient(true);
+        let ps = lenient.parse_string(input).unwrap();
+        assert!(ps.files[0].hunks.is_empty());
+    }
+
+    #[test]
+    fn test_malformed_normal_range() {
+        let (line, reason) = malformed_line(PatchParser::new().parse_string("5,3d2\n< gone\n"));
+        assert_eq!(line, 1);
+        assert!(reason.contains("invalid line range"));
+
+        let (line, reason) =
+            malformed_line(PatchParser::new().parse_string("1,2c1,2\n< a\n---\n> b\n"));
+        assert_eq!(line, 1);
+        assert!(reason.contains("declares 2 '<' lines but 1 follow"));
+    }
+
+    #[test]
+    fn test_malformed_ed_and_rcs_truncation() {
+        let (line, reason) = malformed_line(PatchParser::new().parse_string("2a\nadded\n"));
+        assert_eq!(line, 1);
+        assert!(reason.contains("missing '.'"));
+
+        let (line, reason) = malformed_line(PatchParser::new().parse_string("a3 2\nonly one\n"));
+        assert_eq!(line, 1);
+        assert!(reason.contains("declares 2 lines but only 1 follow"));
+    }
+
+    #[test]
+    fn test_parse_never_panics_on_mutated_input() {
+        let samples = [
+            "--- a/f\t2024\n+++ b/f\t2024\n@@ -1,3 +1,3 @@ fn\n ctx\n-old\n+new\n ctx\n",
+            "*** a/f\n--- b/f\n***************\n*** 1,2 ****\n  ctx\n! old\n--- 1,2 ----\n  ctx\n! new\n",
+            "diff a b\n1,2c1\n< x\n< y\n---\n> z\n3a4\n> w\n",
+            "3,5c\nnew\n.\n7d\n1a\nadded\n.\n",
+            "d1 2\na5 1\nnew line\n",
+        ];
+        let alphabet = b" +-@*!<>.,0123456789acd\n\r\t";
+        let strict = PatchParser::new();
+        let lenient = PatchParser::new().with_lenient(true);
+
+        // Deterministic LCG so failures are reproducible
+        let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = move || {
+            seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+            (seed >> 33) as usize
+        };
+
+        for sample in samples {
+            for cut in 0..=sample.len() {
+                let truncated = &sample[..cut];
+                let _ = strict.parse_string(truncated);
+                assert!(lenient.parse_string(truncated).is_ok());
+            }
+            for _ in 0..200 {
+                let mut bytes = sample.as_bytes().to_vec();
+                for _ in 0..3 {
+                    let pos = next() % bytes.len();
+                    bytes[pos] = alphabet[next() % alphabet.len()];
+                }
+                let mutated = String::from_utf8(bytes).unwrap();
+                let _ = strict.parse_string(&mutated);
+                assert!(lenient.parse_string(&mutated).is_ok());
+            }
+        }
+    }
+
     #[test]
     fn test_parse_empty() {
         let parser = PatchParser::new();
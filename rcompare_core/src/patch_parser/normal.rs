@@ -4,6 +4,8 @@ use rcompare_common::{
 use regex::Regex;
 use std::sync::LazyLock;
 
+use super::{malformed, parse_number, range_len, strip_cr};
+
 // Normal diff header: diff [-options] source destination
 static DIFF_HEADER: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r#"^diff\s+(?:(?:-|--)[a-zA-Z0-9="\ ]+\s+)*(?:--\s+)?(.+)\s+(.+)$"#).unwrap()
@@ -25,18 +27,23 @@ static HUNK_CHANGED: LazyLock<Regex> = LazyLock::new(|| {
 });
 
 /// Parse normal diff format into a list of FilePatches.
-pub fn parse_normal(lines: &[&str]) -> Result<Vec<FilePatch>, RCompareError> {
+///
+/// In strict mode an overflowing line number, a range that ends before it
+/// starts or a command followed by the wrong number of `<`/`>` lines is an
+/// error. Lenient mode keeps what it could read and recomputes hunk counts.
+pub fn parse_normal(lines: &[&str], lenient: bool) -> Result<Vec<FilePatch>, RCompareError> {
     let mut file_patches = Vec::new();
     let mut i = 0;
     let mut current_fp: Option<FilePatch> = None;
 
     while i < lines.len() {
         // Check for diff header
-        if let Some(cap) = DIFF_HEADER.captures(lines[i]) {
+        if let Some(cap) = DIFF_HEADER.captures(strip_cr(lines[i])) {
             if let Some(fp) = current_fp.take() {
                 file_patches.push(fp);
             }
             let mut fp = FilePatch::new();
+            fp.crlf_headers = lines[i].ends_with('\r');
             fp.source = cap[1].to_string();
             fp.destination = cap[2].to_string();
             current_fp = Some(fp);
@@ -45,12 +52,15 @@ pub fn parse_normal(lines: &[&str]) -> Result<Vec<FilePatch>, RCompareError> {
         }
 
         // Check for hunk commands
-        if let Some(cap) = HUNK_ADDED.captures(lines[i]) {
-            let src_line: usize = cap[1].parse().unwrap_or(0);
-            let dst_start: usize = cap[2].parse().unwrap_or(0);
-            let dst_end: usize = cap
-                .get(3)
-                .map_or(dst_start, |m| m.as_str().parse().unwrap_or(dst_start));
+        if let Some(cap) = HUNK_ADDED.captures(strip_cr(lines[i])) {
+            let cmd_index = i;
+            let src_line = parse_number(&cap[1], i, lenient, 0)?;
+            let dst_start = parse_number(&cap[2], i, lenient, 0)?;
+            let dst_end = match cap.get(3) {
+                Some(m) => parse_number(m.as_str(), i, lenient, dst_start)?,
+                None => dst_start,
+            };
+            let dst_len = range_len(dst_start, dst_end, i, lenient)?;
 
             i += 1;
 
@@ -61,9 +71,11 @@ pub fn parse_normal(lines: &[&str]) -> Result<Vec<FilePatch>, RCompareError> {
                 i += 1;
             }
 
+            check_count(diff.dest_lines.len(), dst_len, "'>'", cmd_index, lenient)?;
+
             let mut hunk = Hunk::new(src_line, dst_start);
             hunk.source_count = 0;
-            hunk.dest_count = dst_end - dst_start + 1;
+            hunk.dest_count = diff.dest_lines.len();
             hunk.differences.push(diff);
 
             if let Some(ref mut fp) = current_fp {
@@ -76,12 +88,15 @@ pub fn parse_normal(lines: &[&str]) -> Result<Vec<FilePatch>, RCompareError> {
             continue;
         }
 
-        if let Some(cap) = HUNK_REMOVED.captures(lines[i]) {
-            let src_start: usize = cap[1].parse().unwrap_or(0);
-            let src_end: usize = cap
-                .get(2)
-                .map_or(src_start, |m| m.as_str().parse().unwrap_or(src_start));
-            let dst_line: usize = cap[3].parse().unwrap_or(0);
+        if let Some(cap) = HUNK_REMOVED.captures(strip_cr(lines[i])) {
+            let cmd_index = i;
+            let src_start = parse_number(&cap[1], i, lenient, 0)?;
+            let src_end = match cap.get(2) {
+                Some(m) => parse_number(m.as_str(), i, lenient, src_start)?,
+                None => src_start,
+            };
+            let dst_line = parse_number(&cap[3], i, lenient, 0)?;
+            let src_len = range_len(src_start, src_end, i, lenient)?;
 
             i += 1;
 
@@ -92,8 +107,10 @@ pub fn parse_normal(lines: &[&str]) -> Result<Vec<FilePatch>, RCompareError> {
                 i += 1;
             }
 
+            check_count(diff.source_lines.len(), src_len, "'<'", cmd_index, lenient)?;
+
             let mut hunk = Hunk::new(src_start, dst_line);
-            hunk.source_count = src_end - src_start + 1;
+            hunk.source_count = diff.source_lines.len();
             hunk.dest_count = 0;
             hunk.differences.push(diff);
 
@@ -107,15 +124,20 @@ pub fn parse_normal(lines: &[&str]) -> Result<Vec<FilePatch>, RCompareError> {
             continue;
         }
 
-        if let Some(cap) = HUNK_CHANGED.captures(lines[i]) {
-            let src_start: usize = cap[1].parse().unwrap_or(0);
-            let src_end: usize = cap
-                .get(2)
-                .map_or(src_start, |m| m.as_str().parse().unwrap_or(src_start));
-            let dst_start: usize = cap[3].parse().unwrap_or(0);
-            let dst_end: usize = cap
-                .get(4)
-                .map_or(dst_start, |m| m.as_str().parse().unwrap_or(dst_start));
+        if let Some(cap) = HUNK_CHANGED.captures(strip_cr(lines[i])) {
+            let cmd_index = i;
+            let src_start = parse_number(&cap[1], i, lenient, 0)?;
+            let src_end = match cap.get(2) {
+                Some(m) => parse_number(m.as_str(), i, lenient, src_start)?,
+                None => src_start,
+            };
+            let dst_start = parse_number(&cap[3], i, lenient, 0)?;
+            let dst_end = match cap.get(4) {
+                Some(m) => parse_number(m.as_str(), i, lenient, dst_start)?,
+                None => dst_start,
+            };
+            let src_len = range_len(src_start, src_end, i, lenient)?;
+            let dst_len = range_len(dst_start, dst_end, i, lenient)?;
 
             i += 1;
 
@@ -128,7 +150,7 @@ pub fn parse_normal(lines: &[&str]) -> Result<Vec<FilePatch>, RCompareError> {
                 i += 1;
             }
             // Skip divider (---)
-            if i < lines.len() && lines[i] == "---" {
+            if i < lines.len() && strip_cr(lines[i]) == "---" {
                 i += 1;
             }
             // Collect dest lines (> ...)
@@ -137,9 +159,12 @@ pub fn parse_normal(lines: &[&str]) -> Result<Vec<FilePatch>, RCompareError> {
                 i += 1;
             }
 
+            check_count(diff.source_lines.len(), src_len, "'<'", cmd_index, lenient)?;
+            check_count(diff.dest_lines.len(), dst_len, "'>'", cmd_index, lenient)?;
+
             let mut hunk = Hunk::new(src_start, dst_start);
-            hunk.source_count = src_end - src_start + 1;
-            hunk.dest_count = dst_end - dst_start + 1;
+            hunk.source_count = diff.source_lines.len();
+            hunk.dest_count = diff.dest_lines.len();
             hunk.differences.push(diff);
 
             if let Some(ref mut fp) = current_fp {
@@ -162,6 +187,23 @@ pub fn parse_normal(lines: &[&str]) -> Result<Vec<FilePatch>, RCompareError> {
     Ok(file_patches)
 }
 
+/// Check that a command was followed by as many lines as its range declares.
+fn check_count(
+    found: usize,
+    expected: usize,
+    kind: &str,
+    index: usize,
+    lenient: bool,
+) -> Result<(), RCompareError> {
+    if found != expected && !lenient {
+        return Err(malformed(
+            index,
+            format!("command declares {expected} {kind} lines but {found} follow"),
+        ));
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -176,7 +218,7 @@ mod tests {
 > new1
 > new2";
         let lines: Vec<&str> = input.lines().collect();
-        let result = parse_normal(&lines).unwrap();
+        let result = parse_normal(&lines, false).unwrap();
         assert_eq!(result.len(), 1);
         let hunk = &result[0].hunks[0];
         assert_eq!(hunk.differences[0].diff_type, DifferenceType::Change);
@@ -191,7 +233,7 @@ mod tests {
 > added1
 > added2";
         let lines: Vec<&str> = input.lines().collect();
-        let result = parse_normal(&lines).unwrap();
+        let result = parse_normal(&lines, false).unwrap();
         let hunk = &result[0].hunks[0];
         assert_eq!(hunk.differences[0].diff_type, DifferenceType::Insert);
         assert_eq!(hunk.differences[0].dest_lines.len(), 2);
@@ -205,7 +247,7 @@ mod tests {
 < removed2
 < removed3";
         let lines: Vec<&str> = input.lines().collect();
-        let result = parse_normal(&lines).unwrap();
+        let result = parse_normal(&lines, false).unwrap();
         let hunk = &result[0].hunks[0];
         assert_eq!(hunk.differences[0].diff_type, DifferenceType::Delete);
         assert_eq!(hunk.differences[0].source_lines.len(), 3);
@@ -220,7 +262,7 @@ diff source.txt destination.txt
 ---
 > new";
         let lines: Vec<&str> = input.lines().collect();
-        let result = parse_normal(&lines).unwrap();
+        let result = parse_normal(&lines, false).unwrap();
         assert_eq!(result.len(), 1);
         assert_eq!(result[0].source, "source.txt");
         assert_eq!(result[0].destination, "destination.txt");
@@ -4,6 +4,8 @@ use rcompare_common::{
 use regex::Regex;
 use std::sync::LazyLock;
 
+use super::{malformed, parse_number, strip_cr};
+
 // RCS add command: aN M — add M lines after line N
 static RCS_ADD: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r"^a(\d+)\s+(\d+)$").unwrap()
@@ -19,21 +21,33 @@ static RCS_DELETE: LazyLock<Regex> = LazyLock::new(|| {
 /// RCS format uses two commands:
 /// - `aN M` — add M lines after line N (followed by M content lines)
 /// - `dN M` — delete M lines starting at line N (no content lines follow)
-pub fn parse_rcs(lines: &[&str]) -> Result<Vec<FilePatch>, RCompareError> {
+///
+/// In strict mode an overflowing number or an `a` command followed by fewer
+/// than M lines is an error.
+pub fn parse_rcs(lines: &[&str], lenient: bool) -> Result<Vec<FilePatch>, RCompareError> {
     let mut fp = FilePatch::new();
     let mut i = 0;
 
     while i < lines.len() {
-        if let Some(cap) = RCS_ADD.captures(lines[i]) {
-            let after_line: usize = cap[1].parse().unwrap_or(0);
-            let count: usize = cap[2].parse().unwrap_or(0);
+        if let Some(cap) = RCS_ADD.captures(strip_cr(lines[i])) {
+            let after_line = parse_number(&cap[1], i, lenient, 0)?;
+            let count = parse_number(&cap[2], i, lenient, 0)?;
+            if !lenient && count > lines.len() - i - 1 {
+                return Err(malformed(
+                    i,
+                    format!(
+                        "add command declares {count} lines but only {} follow",
+                        lines.len() - i - 1
+                    ),
+                ));
+            }
 
             i += 1;
 
             let mut diff = PatchDifference::new(
                 DifferenceType::Insert,
                 after_line,
-                after_line + 1,
+                after_line.saturating_add(1),
             );
             for _ in 0..count {
                 if i < lines.len() {
@@ -42,14 +56,14 @@ pub fn parse_rcs(lines: &[&str]) -> Result<Vec<FilePatch>, RCompareError> {
                 }
             }
 
-            let mut hunk = Hunk::new(after_line, after_line + 1);
+            let mut hunk = Hunk::new(after_line, after_line.saturating_add(1));
             hunk.source_count = 0;
-            hunk.dest_count = count;
+            hunk.dest_count = diff.dest_lines.len();
             hunk.differences.push(diff);
             fp.hunks.push(hunk);
-        } else if let Some(cap) = RCS_DELETE.captures(lines[i]) {
-            let start_line: usize = cap[1].parse().unwrap_or(0);
-            let count: usize = cap[2].parse().unwrap_or(0);
+        } else if let Some(cap) = RCS_DELETE.captures(strip_cr(lines[i])) {
+            let start_line = parse_number(&cap[1], i, lenient, 0)?;
+            let count = parse_number(&cap[2], i, lenient, 0)?;
 
             i += 1;
 
@@ -88,7 +102,7 @@ a3 2
 added line 1
 added line 2";
         let lines: Vec<&str> = input.lines().collect();
-        let result = parse_rcs(&lines).unwrap();
+        let result = parse_rcs(&lines, false).unwrap();
         assert_eq!(result.len(), 1);
         let hunk = &result[0].hunks[0];
         assert_eq!(hunk.differences[0].diff_type, DifferenceType::Insert);
@@ -101,7 +115,7 @@ added line 2";
         let input = "\
 d5 3";
         let lines: Vec<&str> = input.lines().collect();
-        let result = parse_rcs(&lines).unwrap();
+        let result = parse_rcs(&lines, false).unwrap();
         assert_eq!(result.len(), 1);
         let hunk = &result[0].hunks[0];
         assert_eq!(hunk.differences[0].diff_type, DifferenceType::Delete);
@@ -115,7 +129,7 @@ d1 2
 a5 1
 new line";
         let lines: Vec<&str> = input.lines().collect();
-        let result = parse_rcs(&lines).unwrap();
+        let result = parse_rcs(&lines, false).unwrap();
         assert_eq!(result[0].hunks.len(), 2);
         assert_eq!(result[0].hunks[0].differences[0].diff_type, DifferenceType::Delete);
         assert_eq!(result[0].hunks[1].differences[0].diff_type, DifferenceType::Insert);
@@ -125,7 +139,7 @@ new line";
     fn test_parse_rcs_empty() {
         let input = "";
         let lines: Vec<&str> = input.lines().collect();
-        let result = parse_rcs(&lines).unwrap();
+        let result = parse_rcs(&lines, false).unwrap();
         assert!(result.is_empty());
     }
 }
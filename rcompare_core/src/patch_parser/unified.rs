@@ -1,9 +1,11 @@
 use rcompare_common::{
-    DifferenceType, FilePatch, Hunk, PatchDifference, RCompareError,
+    DifferenceType, FilePatch, GitHeader, Hunk, PatchDifference, RCompareError,
 };
 use regex::Regex;
 use std::sync::LazyLock;
 
+use super::{malformed, parse_number, strip_cr};
+
 static HEADER1: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r"^--- ([^\t]+)(?:\t([^\t]+)(?:\t(.*))?)?$").unwrap()
 });
@@ -18,178 +20,263 @@ static HUNK_HEADER: LazyLock<Regex> = LazyLock::new(|| {
 
 /// Parse unified diff format into a list of FilePatches.
 /// Returns one FilePatch per file pair found in the input.
-pub fn parse_unified(lines: &[&str]) -> Result<Vec<FilePatch>, RCompareError> {
+///
+/// Lines may keep a trailing `\r`; it is ignored for headers and preserved
+/// in hunk content. In strict mode a `---` header without a following `+++`
+/// header, an unparsable `@@` line, an overflowing line range or a hunk body
+/// that does not match its declared counts is an error. In lenient mode
+/// those are skipped and hunk counts are recomputed from the body.
+///
+/// A `diff --git` line and its extended header are kept in
+/// [`FilePatch::git`]. A git header not followed by `---`/`+++` (a pure
+/// rename, a mode change, an empty new file) becomes a file patch without
+/// hunks.
+pub fn parse_unified(lines: &[&str], lenient: bool) -> Result<Vec<FilePatch>, RCompareError> {
     let mut file_patches = Vec::new();
     let mut i = 0;
 
     while i < lines.len() {
-        // Look for --- header
-        if let Some(cap1) = HEADER1.captures(lines[i]) {
-            // Next line should be +++ header
-            if i + 1 >= lines.len() {
-                i += 1;
+        let mut git = None;
+        if let Some(paths) = strip_cr(lines[i]).strip_prefix("diff --git ") {
+            let crlf = lines[i].ends_with('\r');
+            let (header, next) = parse_git_header(lines, i + 1, paths);
+            i = next;
+            if !lines.get(i).is_some_and(|l| HEADER1.is_match(strip_cr(l))) {
+                let mut fp = FilePatch::new();
+                fp.crlf_headers = crlf;
+                fp.source = header.old_path.clone();
+                fp.destination = header.new_path.clone();
+                fp.git = Some(header);
+                file_patches.push(fp);
                 continue;
             }
-            if let Some(cap2) = HEADER2.captures(lines[i + 1]) {
-                let mut fp = FilePatch::new();
-                fp.source = cap1.get(1).map_or("", |m| m.as_str()).to_string();
-                fp.source_timestamp = cap1.get(2).map_or("", |m| m.as_str()).to_string();
-                fp.source_revision = cap1.get(3).map_or("", |m| m.as_str()).to_string();
-                fp.destination = cap2.get(1).map_or("", |m| m.as_str()).to_string();
-                fp.dest_timestamp = cap2.get(2).map_or("", |m| m.as_str()).to_string();
-                fp.dest_revision = cap2.get(3).map_or("", |m| m.as_str()).to_string();
-
-                i += 2;
-
-                // Parse hunks
-                while i < lines.len() {
-                    if let Some(hunk_cap) = HUNK_HEADER.captures(lines[i]) {
-                        let src_start: usize = hunk_cap[1].parse().unwrap_or(0);
-                        let src_count: usize = hunk_cap
-                            .get(2)
-                            .map_or(1, |m| m.as_str().parse().unwrap_or(1));
-                        let dst_start: usize = hunk_cap[3].parse().unwrap_or(0);
-                        let dst_count: usize = hunk_cap
-                            .get(4)
-                            .map_or(1, |m| m.as_str().parse().unwrap_or(1));
-                        let func_name = hunk_cap
-                            .get(5)
-                            .map(|m| m.as_str().trim())
-                            .filter(|s| !s.is_empty())
-                            .map(|s| s.to_string());
-
-                        let mut hunk = Hunk::new(src_start, dst_start);
-                        hunk.source_count = src_count;
-                        hunk.dest_count = dst_count;
-                        hunk.function_name = func_name;
-
-                        i += 1;
-
-                        // Parse hunk body
-                        let mut src_line = src_start;
-                        let mut dst_line = dst_start;
-                        // Accumulate consecutive same-type lines into one PatchDifference
-                        let mut current_diff: Option<PatchDifference> = None;
-                        let mut current_type: Option<char> = None;
-
-                        while i < lines.len() {
-                            let line = lines[i];
-                            if line.is_empty() {
-                                // Treat empty line as context (space prefix)
-                                // but only if we're still within expected counts
+            git = Some(header);
+        }
+
+        // Look for --- header
+        if let Some(cap1) = HEADER1.captures(strip_cr(lines[i])) {
+            // Next line should be +++ header
+            let cap2 = match lines.get(i + 1).and_then(|l| HEADER2.captures(strip_cr(l))) {
+                Some(cap2) => cap2,
+                None if lenient => {
+                    i += 1;
+                    continue;
+                }
+                None => {
+                    return Err(malformed(
+                        i,
+                        "truncated file header: expected '+++' after '---'",
+                    ))
+                }
+            };
+
+            let mut fp = FilePatch::new();
+            fp.crlf_headers = lines[i].ends_with('\r');
+            fp.source = cap1.get(1).map_or("", |m| m.as_str()).to_string();
+            fp.source_timestamp = cap1.get(2).map_or("", |m| m.as_str()).to_string();
+            fp.source_revision = cap1.get(3).map_or("", |m| m.as_str()).to_string();
+            fp.destination = cap2.get(1).map_or("", |m| m.as_str()).to_string();
+            fp.dest_timestamp = cap2.get(2).map_or("", |m| m.as_str()).to_string();
+            fp.dest_revision = cap2.get(3).map_or("", |m| m.as_str()).to_string();
+            fp.git = git;
+
+            i += 2;
+
+            // Parse hunks
+            while i < lines.len() {
+                let header = strip_cr(lines[i]);
+                if let Some(hunk_cap) = HUNK_HEADER.captures(header) {
+                    let header_index = i;
+                    let src_start = parse_number(&hunk_cap[1], i, lenient, 0)?;
+                    let src_count = match hunk_cap.get(2) {
+                        Some(m) => parse_number(m.as_str(), i, lenient, 1)?,
+                        None => 1,
+                    };
+                    let dst_start = parse_number(&hunk_cap[3], i, lenient, 0)?;
+                    let dst_count = match hunk_cap.get(4) {
+                        Some(m) => parse_number(m.as_str(), i, lenient, 1)?,
+                        None => 1,
+                    };
+                    let func_name = hunk_cap
+                        .get(5)
+                        .map(|m| m.as_str().trim())
+                        .filter(|s| !s.is_empty())
+                        .map(|s| s.to_string());
+
+                    if !lenient
+                        && (src_start.checked_add(src_count).is_none()
+                            || dst_start.checked_add(dst_count).is_none())
+                    {
+                        return Err(malformed(i, "hunk line range overflows"));
+                    }
+
+                    let mut hunk = Hunk::new(src_start, dst_start);
+                    hunk.source_count = src_count;
+                    hunk.dest_count = dst_count;
+                    hunk.function_name = func_name;
+
+                    i += 1;
+
+                    // Parse hunk body
+                    let mut src_line = src_start;
+                    let mut dst_line = dst_start;
+                    let mut src_seen = 0usize;
+                    let mut dst_seen = 0usize;
+                    // Accumulate consecutive same-type lines into one PatchDifference
+                    let mut current_diff: Option<PatchDifference> = None;
+                    let mut current_type: Option<char> = None;
+
+                    while i < lines.len() {
+                        let line = lines[i];
+                        let src_left = src_count.saturating_sub(src_seen);
+                        let dst_left = dst_count.saturating_sub(dst_seen);
+                        let (first, content) = if strip_cr(line).is_empty() {
+                            // Some tools drop the space prefix of empty context
+                            // lines; accept those only while the hunk expects more
+                            if src_left == 0 || dst_left == 0 {
                                 break;
                             }
-                            // Check if this line starts a new file header or hunk
-                            if HEADER1.is_match(line) || HUNK_HEADER.is_match(line) {
+                            (' ', line)
+                        } else {
+                            // A removed line may itself start with "-- ", so only
+                            // treat "--- " as a new file once the hunk is complete
+                            // (or, when lenient, when a "+++ " header follows)
+                            if HUNK_HEADER.is_match(strip_cr(line))
+                                || (HEADER1.is_match(strip_cr(line))
+                                    && (src_left == 0
+                                        || (lenient && next_is_header2(lines, i))))
+                            {
                                 break;
                             }
-                            let first = line.as_bytes()[0];
-                            match first {
-                                b' ' => {
-                                    // Context line - flush any pending diff
+                            let mut chars = line.chars();
+                            let first = chars.next().unwrap_or(' ');
+                            (first, chars.as_str())
+                        };
+                        match first {
+                            ' ' => {
+                                // Context line - flush any pending diff
+                                if let Some(diff) = current_diff.take() {
+                                    hunk.differences.push(diff);
+                                    current_type = None;
+                                }
+                                // Start or continue Unchanged block
+                                if current_type != Some(' ') {
+                                    current_diff = Some(PatchDifference::new(
+                                        DifferenceType::Unchanged,
+                                        src_line,
+                                        dst_line,
+                                    ));
+                                    current_type = Some(' ');
+                                }
+                                if let Some(ref mut d) = current_diff {
+                                    d.source_lines.push(format!("{content}\n"));
+                                    d.dest_lines.push(format!("{content}\n"));
+                                }
+                                src_line = src_line.saturating_add(1);
+                                dst_line = dst_line.saturating_add(1);
+                                src_seen += 1;
+                                dst_seen += 1;
+                                i += 1;
+                            }
+                            '-' => {
+                                // Removed line
+                                // If we were accumulating '+' lines, this is a Change block
+                                // If we were accumulating '-' lines, continue
+                                // Otherwise start new delete block
+                                if current_type == Some(' ') || current_type == Some('+') {
                                     if let Some(diff) = current_diff.take() {
                                         hunk.differences.push(diff);
-                                        current_type = None;
                                     }
-                                    // Start or continue Unchanged block
-                                    if current_type != Some(' ') {
-                                        current_diff = Some(PatchDifference::new(
-                                            DifferenceType::Unchanged,
-                                            src_line,
-                                            dst_line,
-                                        ));
-                                        current_type = Some(' ');
-                                    }
-                                    let content = &line[1..];
-                                    if let Some(ref mut d) = current_diff {
-                                        d.source_lines.push(format!("{content}\n"));
-                                        d.dest_lines.push(format!("{content}\n"));
-                                    }
-                                    src_line += 1;
-                                    dst_line += 1;
-                                    i += 1;
+                                    current_type = None;
                                 }
-                                b'-' => {
-                                    // Removed line
-                                    // If we were accumulating '+' lines, this is a Change block
-                                    // If we were accumulating '-' lines, continue
-                                    // Otherwise start new delete block
-                                    if current_type == Some(' ') || current_type == Some('+') {
-                                        if let Some(diff) = current_diff.take() {
-                                            hunk.differences.push(diff);
-                                        }
-                                        current_type = None;
-                                    }
-                                    if current_type != Some('-') {
-                                        current_diff = Some(PatchDifference::new(
-                                            DifferenceType::Delete,
-                                            src_line,
-                                            dst_line,
-                                        ));
-                                        current_type = Some('-');
-                                    }
-                                    let content = &line[1..];
-                                    if let Some(ref mut d) = current_diff {
-                                        d.source_lines.push(format!("{content}\n"));
-                                    }
-                                    src_line += 1;
-                                    i += 1;
+                                if current_type != Some('-') {
+                                    current_diff = Some(PatchDifference::new(
+                                        DifferenceType::Delete,
+                                        src_line,
+                                        dst_line,
+                                    ));
+                                    current_type = Some('-');
                                 }
-                                b'+' => {
-                                    // Added line
-                                    if current_type == Some(' ') {
-                                        if let Some(diff) = current_diff.take() {
-                                            hunk.differences.push(diff);
-                                        }
-                                        current_type = None;
-                                    }
-                                    if current_type == Some('-') {
-                                        // Switch from Delete to Change
-                                        if let Some(ref mut d) = current_diff {
-                                            d.diff_type = DifferenceType::Change;
-                                        }
-                                        current_type = Some('+');
-                                    } else if current_type != Some('+') {
-                                        current_diff = Some(PatchDifference::new(
-                                            DifferenceType::Insert,
-                                            src_line,
-                                            dst_line,
-                                        ));
-                                        current_type = Some('+');
+                                if let Some(ref mut d) = current_diff {
+                                    d.source_lines.push(format!("{content}\n"));
+                                }
+                                src_line = src_line.saturating_add(1);
+                                src_seen += 1;
+                                i += 1;
+                            }
+                            '+' => {
+                                // Added line
+                                if current_type == Some(' ') {
+                                    if let Some(diff) = current_diff.take() {
+                                        hunk.differences.push(diff);
                                     }
-                                    let content = &line[1..];
+                                    current_type = None;
+                                }
+                                if current_type == Some('-') {
+                                    // Switch from Delete to Change
                                     if let Some(ref mut d) = current_diff {
-                                        d.dest_lines.push(format!("{content}\n"));
+                                        d.diff_type = DifferenceType::Change;
                                     }
-                                    dst_line += 1;
-                                    i += 1;
+                                    current_type = Some('+');
+                                } else if current_type != Some('+') {
+                                    current_diff = Some(PatchDifference::new(
+                                        DifferenceType::Insert,
+                                        src_line,
+                                        dst_line,
+                                    ));
+                                    current_type = Some('+');
                                 }
-                                _ => {
-                                    // Not a hunk body line, stop
-                                    break;
+                                if let Some(ref mut d) = current_diff {
+                                    d.dest_lines.push(format!("{content}\n"));
                                 }
+                                dst_line = dst_line.saturating_add(1);
+                                dst_seen += 1;
+                                i += 1;
+                            }
+                            _ if !lenient && (src_left > 0 || dst_left > 0) => {
+                                return Err(malformed(
+                                    i,
+                                    format!("invalid hunk line prefix {first:?}: {line}"),
+                                ));
+                            }
+                            _ => {
+                                // Not a hunk body line, stop
+                                break;
                             }
                         }
+                    }
 
-                        // Flush remaining diff
-                        if let Some(diff) = current_diff.take() {
-                            hunk.differences.push(diff);
-                        }
+                    // Flush remaining diff
+                    if let Some(diff) = current_diff.take() {
+                        hunk.differences.push(diff);
+                    }
 
-                        fp.hunks.push(hunk);
-                    } else if HEADER1.is_match(lines[i]) {
-                        // Next file pair starts
-                        break;
-                    } else {
-                        // Skip non-hunk lines (e.g., "diff --git" headers)
-                        i += 1;
+                    if src_seen != src_count || dst_seen != dst_count {
+                        if !lenient {
+                            return Err(malformed(
+                                header_index,
+                                format!(
+                                    "hunk declares {src_count} source and {dst_count} destination \
+                                     lines but its body has {src_seen} and {dst_seen}"
+                                ),
+                            ));
+                        }
+                        hunk.recompute_counts();
                     }
-                }
 
-                file_patches.push(fp);
-            } else {
-                i += 1;
+                    fp.hunks.push(hunk);
+                } else if HEADER1.is_match(header) || header.starts_with("diff --git ") {
+                    // Next file pair starts
+                    break;
+                } else if header.starts_with("@@") && !lenient {
+                    return Err(malformed(i, format!("invalid hunk header: {header}")));
+                } else {
+                    // Skip non-hunk lines (e.g., "Binary files ... differ")
+                    i += 1;
+                }
             }
+
+            file_patches.push(fp);
         } else {
             i += 1;
         }
@@ -198,6 +285,55 @@ pub fn parse_unified(lines: &[&str]) -> Result<Vec<FilePatch>, RCompareError> {
     Ok(file_patches)
 }
 
+/// Parse the extended header lines following `diff --git <paths>`
+///
+/// Returns the header and the index of the first line after it.
+fn parse_git_header(lines: &[&str], mut i: usize, paths: &str) -> (GitHeader, usize) {
+    // Unquoted paths are ambiguous when they contain spaces; split at the
+    // destination prefix, which is right for the usual `a/`/`b/` prefixes
+    let (old_path, new_path) = match paths.split_once(" b/") {
+        Some((old, new)) => (old.to_string(), format!("b/{new}")),
+        None => match paths.split_once(' ') {
+            Some((old, new)) => (old.to_string(), new.to_string()),
+            None => (paths.to_string(), paths.to_string()),
+        },
+    };
+    let mut header = GitHeader {
+        old_path,
+        new_path,
+        ..GitHeader::default()
+    };
+
+    while let Some(line) = lines.get(i).map(|l| strip_cr(l)) {
+        let fields = [
+            ("old mode ", &mut header.old_mode),
+            ("new mode ", &mut header.new_mode),
+            ("new file mode ", &mut header.new_file_mode),
+            ("deleted file mode ", &mut header.deleted_file_mode),
+            ("similarity index ", &mut header.similarity_index),
+            ("rename from ", &mut header.rename_from),
+            ("rename to ", &mut header.rename_to),
+            ("index ", &mut header.index),
+        ];
+        let matched = fields
+            .into_iter()
+            .find_map(|(prefix, field)| Some((line.strip_prefix(prefix)?, field)));
+        match matched {
+            Some((value, field)) => *field = Some(value.to_string()),
+            None => break,
+        }
+        i += 1;
+    }
+
+    (header, i)
+}
+
+fn next_is_header2(lines: &[&str], i: usize) -> bool {
+    lines
+        .get(i + 1)
+        .is_some_and(|l| HEADER2.is_match(strip_cr(l)))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -213,7 +349,7 @@ mod tests {
 +line2_modified
  line3";
         let lines: Vec<&str> = input.lines().collect();
-        let result = parse_unified(&lines).unwrap();
+        let result = parse_unified(&lines, false).unwrap();
 
         assert_eq!(result.len(), 1);
         let fp = &result[0];
@@ -247,7 +383,7 @@ mod tests {
 +added2
  line2";
         let lines: Vec<&str> = input.lines().collect();
-        let result = parse_unified(&lines).unwrap();
+        let result = parse_unified(&lines, false).unwrap();
         let hunk = &result[0].hunks[0];
         // Unchanged(line1), Insert(added1, added2), Unchanged(line2)
         assert_eq!(hunk.differences.len(), 3);
@@ -255,6 +391,29 @@ mod tests {
         assert_eq!(hunk.differences[1].dest_lines.len(), 2);
     }
 
+    #[test]
+    fn test_parse_non_ascii_line_prefix() {
+        let input = "\
+--- a/file.txt
++++ b/file.txt
+@@ -1,2 +1,2 @@
+ über
+übel";
+        let lines: Vec<&str> = input.lines().collect();
+        match parse_unified(&lines, false) {
+            Err(RCompareError::MalformedPatch { line, reason }) => {
+                assert_eq!(line, 5);
+                assert!(reason.contains("'ü'"), "{reason}");
+            }
+            other => panic!("expected MalformedPatch, got {:?}", other.map(|f| f.len())),
+        }
+
+        // Lenient parsing ends the hunk at the bad line instead
+        let result = parse_unified(&lines, true).unwrap();
+        let hunk = &result[0].hunks[0];
+        assert_eq!(hunk.differences[0].source_lines, ["über\n"]);
+    }
+
     #[test]
     fn test_parse_delete_only() {
         let input = "\
@@ -266,7 +425,7 @@ mod tests {
 -removed2
  line2";
         let lines: Vec<&str> = input.lines().collect();
-        let result = parse_unified(&lines).unwrap();
+        let result = parse_unified(&lines, false).unwrap();
         let hunk = &result[0].hunks[0];
         assert_eq!(hunk.differences.len(), 3);
         assert_eq!(hunk.differences[1].diff_type, DifferenceType::Delete);
@@ -289,7 +448,7 @@ mod tests {
 +new10
  line12";
         let lines: Vec<&str> = input.lines().collect();
-        let result = parse_unified(&lines).unwrap();
+        let result = parse_unified(&lines, false).unwrap();
         assert_eq!(result[0].hunks.len(), 2);
         assert_eq!(result[0].hunks[0].source_start, 1);
         assert_eq!(result[0].hunks[1].source_start, 10);
@@ -311,7 +470,7 @@ mod tests {
 -old2
 +new2";
         let lines: Vec<&str> = input.lines().collect();
-        let result = parse_unified(&lines).unwrap();
+        let result = parse_unified(&lines, false).unwrap();
         assert_eq!(result.len(), 2);
         assert_eq!(result[0].source, "a/first.txt");
         assert_eq!(result[1].source, "a/second.txt");
@@ -328,7 +487,7 @@ mod tests {
 +new
  line12";
         let lines: Vec<&str> = input.lines().collect();
-        let result = parse_unified(&lines).unwrap();
+        let result = parse_unified(&lines, false).unwrap();
         assert_eq!(
             result[0].hunks[0].function_name.as_deref(),
             Some("int main()")
@@ -344,8 +503,53 @@ mod tests {
 -old
 +new";
         let lines: Vec<&str> = input.lines().collect();
-        let result = parse_unified(&lines).unwrap();
+        let result = parse_unified(&lines, false).unwrap();
         assert_eq!(result[0].source_revision, "1.1");
         assert_eq!(result[0].dest_revision, "1.2");
     }
+
+    #[test]
+    fn test_parse_git_headers() {
+        let input = "\
+diff --git a/run.sh b/run.sh
+old mode 100644
+new mode 100755
+diff --git a/old.txt b/new.txt
+similarity index 80%
+rename from old.txt
+rename to new.txt
+index 83db48f..bf269f4 100644
+--- a/old.txt
++++ b/new.txt
+@@ -1,2 +1,2 @@
+ keep
+-old
++new
+diff --git a/added.txt b/added.txt
+new file mode 100644
+index 0000000..e69de29";
+        let lines: Vec<&str> = input.lines().collect();
+        let result = parse_unified(&lines, false).unwrap();
+        assert_eq!(result.len(), 3);
+
+        let mode_change = result[0].git.as_ref().unwrap();
+        assert_eq!(result[0].source, "a/run.sh");
+        assert!(result[0].hunks.is_empty());
+        assert_eq!(mode_change.old_mode.as_deref(), Some("100644"));
+        assert_eq!(mode_change.new_mode.as_deref(), Some("100755"));
+
+        let rename = result[1].git.as_ref().unwrap();
+        assert_eq!(rename.old_path, "a/old.txt");
+        assert_eq!(rename.new_path, "b/new.txt");
+        assert_eq!(rename.similarity_index.as_deref(), Some("80%"));
+        assert_eq!(rename.rename_from.as_deref(), Some("old.txt"));
+        assert_eq!(rename.rename_to.as_deref(), Some("new.txt"));
+        assert_eq!(rename.index.as_deref(), Some("83db48f..bf269f4 100644"));
+        assert_eq!(result[1].destination, "b/new.txt");
+        assert_eq!(result[1].hunks.len(), 1);
+
+        let added = result[2].git.as_ref().unwrap();
+        assert_eq!(added.new_file_mode.as_deref(), Some("100644"));
+        assert!(result[2].hunks.is_empty());
+    }
 }
@@ -1,5 +1,7 @@
+use crate::patch_parser::context;
 use rcompare_common::{
-    DifferenceType, FilePatch, Hunk, HunkType, PatchDifference, PatchSet,
+    DiffGenerator, DifferenceType, FilePatch, GitHeader, Hunk, HunkType, PatchDifference,
+    PatchSet,
 };
 
 /// Serializer that recreates unified diff text from a PatchSet model.
@@ -7,57 +9,146 @@ use rcompare_common::{
 /// This is the inverse of parsing: given a PatchSet, produce the unified diff
 /// text that would parse back to the same model. `AddedByBlend` hunks are
 /// skipped since they represent original file context, not diff content.
+///
+/// Serialization is a pure function of the `PatchSet`: files are emitted in
+/// the order of `PatchSet::files` and header timestamps are taken from the
+/// model (see [`TimestampPolicy`]), never from the clock or the filesystem.
+///
+/// A file whose hunks all carry [`PatchDifference::raw`] text is written
+/// verbatim from it instead of being rebuilt. This is how context diffs
+/// round-trip: such a file also gets `***`/`---` file headers. Raw text that
+/// no longer matches its hunk, because the hunk was edited after parsing,
+/// is dropped and the whole file is rebuilt as a unified diff.
+///
+/// A `PatchSet` parsed from a git diff ([`DiffGenerator::Git`]) is written
+/// back with each file's `diff --git` line and extended header (modes,
+/// renames, `index`), so the output stays appliable by `git apply`. File
+/// patches without hunks, such as pure renames, then get no `---`/`+++`
+/// lines, as git writes them.
 pub struct PatchSerializer;
 
+/// How `---`/`+++` header timestamps are written
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum TimestampPolicy {
+    /// Reproduce the captured `source_timestamp`/`dest_timestamp` verbatim
+    #[default]
+    Preserve,
+    /// Write this string in place of every non-empty timestamp
+    Fixed(String),
+    /// Drop timestamps from the headers
+    Omit,
+}
+
+impl TimestampPolicy {
+    fn apply<'a>(&'a self, captured: &'a str) -> &'a str {
+        match self {
+            TimestampPolicy::Preserve => captured,
+            TimestampPolicy::Fixed(_) if captured.is_empty() => "",
+            TimestampPolicy::Fixed(fixed) => fixed,
+            TimestampPolicy::Omit => "",
+        }
+    }
+}
+
 impl PatchSerializer {
     /// Serialize an entire PatchSet to unified diff text.
     pub fn serialize(patch_set: &PatchSet) -> String {
+        Self::serialize_with_timestamps(patch_set, &TimestampPolicy::Preserve)
+    }
+
+    /// Serialize an entire PatchSet, writing header timestamps per `policy`.
+    pub fn serialize_with_timestamps(patch_set: &PatchSet, policy: &TimestampPolicy) -> String {
+        let git = patch_set.generator == DiffGenerator::Git;
         let mut output = String::new();
         for fp in &patch_set.files {
-            output.push_str(&Self::serialize_file_patch(fp));
+            output.push_str(&Self::write_file_patch(fp, policy, git));
         }
         output
     }
 
     /// Serialize a single FilePatch to unified diff text.
+    ///
+    /// Header lines use CRLF when the patch was parsed from a CRLF diff; hunk
+    /// content lines keep whatever terminator they were parsed with.
     pub fn serialize_file_patch(fp: &FilePatch) -> String {
+        Self::serialize_file_patch_with_timestamps(fp, &TimestampPolicy::Preserve)
+    }
+
+    /// Serialize a single FilePatch, writing header timestamps per `policy`.
+    ///
+    /// This always writes plain unified headers; git headers are only
+    /// written when serializing a whole git `PatchSet`.
+    pub fn serialize_file_patch_with_timestamps(
+        fp: &FilePatch,
+        policy: &TimestampPolicy,
+    ) -> String {
+        Self::write_file_patch(fp, policy, false)
+    }
+
+    fn write_file_patch(fp: &FilePatch, policy: &TimestampPolicy, git: bool) -> String {
         let mut output = String::new();
+        let eol = if fp.crlf_headers { "\r\n" } else { "\n" };
+        if let Some(header) = fp.git.as_ref().filter(|_| git) {
+            write_git_header(&mut output, header, eol);
+            if fp
+                .hunks
+                .iter()
+                .all(|h| h.hunk_type == HunkType::AddedByBlend)
+            {
+                return output;
+            }
+        }
+        let source_timestamp = policy.apply(&fp.source_timestamp);
+        let dest_timestamp = policy.apply(&fp.dest_timestamp);
+        let verbatim = verbatim_context_hunks(fp);
+        let (source_marker, dest_marker) = if verbatim.is_some() {
+            ("***", "---")
+        } else {
+            ("---", "+++")
+        };
 
         // File headers
-        output.push_str(&format!("--- {}", escape_path(&fp.source)));
-        if !fp.source_timestamp.is_empty() {
+        output.push_str(&format!("{} {}", source_marker, escape_path(&fp.source)));
+        if !source_timestamp.is_empty() {
             output.push('\t');
-            output.push_str(&fp.source_timestamp);
+            output.push_str(source_timestamp);
         }
         if !fp.source_revision.is_empty() {
             output.push('\t');
             output.push_str(&fp.source_revision);
         }
-        output.push('\n');
+        output.push_str(eol);
 
-        output.push_str(&format!("+++ {}", escape_path(&fp.destination)));
-        if !fp.dest_timestamp.is_empty() {
+        output.push_str(&format!("{} {}", dest_marker, escape_path(&fp.destination)));
+        if !dest_timestamp.is_empty() {
             output.push('\t');
-            output.push_str(&fp.dest_timestamp);
+            output.push_str(dest_timestamp);
         }
         if !fp.dest_revision.is_empty() {
             output.push('\t');
             output.push_str(&fp.dest_revision);
         }
-        output.push('\n');
+        output.push_str(eol);
+
+        if let Some(hunks) = verbatim {
+            for line in hunks.into_iter().flatten() {
+                push_raw_line(&mut output, line, eol);
+            }
+            return output;
+        }
 
         // Hunks (skip AddedByBlend)
         for hunk in &fp.hunks {
             if hunk.hunk_type == HunkType::AddedByBlend {
                 continue;
             }
-            output.push_str(&Self::serialize_hunk(hunk));
+            output.push_str(&Self::serialize_hunk(hunk, eol));
         }
 
         output
     }
 
-    fn serialize_hunk(hunk: &Hunk) -> String {
+    fn serialize_hunk(hunk: &Hunk, eol: &str) -> String {
         let mut body = String::new();
         let mut src_count = 0usize;
         let mut dst_count = 0usize;
@@ -92,7 +183,7 @@ impl PatchSerializer {
             header.push(' ');
             header.push_str(func);
         }
-        header.push('\n');
+        header.push_str(eol);
 
         format!("{header}{body}")
     }
@@ -103,35 +194,25 @@ impl PatchSerializer {
         match diff.diff_type {
             DifferenceType::Unchanged => {
                 for line in &diff.source_lines {
-                    output.push(' ');
-                    output.push_str(strip_trailing_newline(line));
-                    output.push('\n');
+                    push_line(&mut output, ' ', line);
                 }
             }
             DifferenceType::Change => {
                 for line in &diff.source_lines {
-                    output.push('-');
-                    output.push_str(strip_trailing_newline(line));
-                    output.push('\n');
+                    push_line(&mut output, '-', line);
                 }
                 for line in &diff.dest_lines {
-                    output.push('+');
-                    output.push_str(strip_trailing_newline(line));
-                    output.push('\n');
+                    push_line(&mut output, '+', line);
                 }
             }
             DifferenceType::Delete => {
                 for line in &diff.source_lines {
-                    output.push('-');
-                    output.push_str(strip_trailing_newline(line));
-                    output.push('\n');
+                    push_line(&mut output, '-', line);
                 }
             }
             DifferenceType::Insert => {
                 for line in &diff.dest_lines {
-                    output.push('+');
-                    output.push_str(strip_trailing_newline(line));
-                    output.push('\n');
+                    push_line(&mut output, '+', line);
                 }
             }
         }
@@ -140,6 +221,103 @@ impl PatchSerializer {
     }
 }
 
+/// Write the `diff --git` line and extended header lines in git's order
+fn write_git_header(output: &mut String, header: &GitHeader, eol: &str) {
+    output.push_str(&format!(
+        "diff --git {} {}{eol}",
+        header.old_path, header.new_path
+    ));
+    let lines = [
+        ("old mode", &header.old_mode),
+        ("new mode", &header.new_mode),
+        ("new file mode", &header.new_file_mode),
+        ("deleted file mode", &header.deleted_file_mode),
+        ("similarity index", &header.similarity_index),
+        ("rename from", &header.rename_from),
+        ("rename to", &header.rename_to),
+        ("index", &header.index),
+    ];
+    for (name, value) in lines {
+        if let Some(value) = value {
+            output.push_str(&format!("{name} {value}{eol}"));
+        }
+    }
+}
+
+/// The original context-format lines of every emitted hunk, if all of them
+/// kept their raw text and it still describes them
+fn verbatim_context_hunks(fp: &FilePatch) -> Option<Vec<Vec<&str>>> {
+    let hunks = fp
+        .hunks
+        .iter()
+        .filter(|h| h.hunk_type != HunkType::AddedByBlend)
+        .map(raw_lines)
+        .collect::<Option<Vec<_>>>()?;
+    let context = !hunks.is_empty()
+        && hunks.iter().all(|lines| {
+            lines
+                .first()
+                .is_some_and(|l| l.starts_with("***************"))
+        });
+    context.then_some(hunks)
+}
+
+/// The original lines of a hunk, if every difference in it kept them and
+/// they parse back to the hunk as it is now
+fn raw_lines(hunk: &Hunk) -> Option<Vec<&str>> {
+    if hunk.differences.is_empty() || hunk.differences.iter().any(|d| d.raw.is_none()) {
+        return None;
+    }
+    let raw: Vec<&str> = hunk
+        .differences
+        .iter()
+        .flat_map(|d| d.raw.iter().flatten())
+        .map(String::as_str)
+        .collect();
+
+    let lines: Vec<&str> = ["*** a", "--- b"]
+        .into_iter()
+        .chain(raw.iter().copied())
+        .collect();
+    let files = context::parse_context(&lines, true).ok()?;
+    let [file] = files.as_slice() else {
+        return None;
+    };
+    let [parsed] = file.hunks.as_slice() else {
+        return None;
+    };
+    let current = parsed.source_start == hunk.source_start
+        && parsed.dest_start == hunk.dest_start
+        && parsed.function_name == hunk.function_name
+        && parsed.differences.len() == hunk.differences.len()
+        && parsed
+            .differences
+            .iter()
+            .zip(&hunk.differences)
+            .all(|(parsed, diff)| {
+                parsed.diff_type == diff.diff_type
+                    && parsed.source_line_no == diff.source_line_no
+                    && parsed.dest_line_no == diff.dest_line_no
+                    && parsed.source_lines == diff.source_lines
+                    && parsed.dest_lines == diff.dest_lines
+            });
+    current.then_some(raw)
+}
+
+/// Write one raw context-format line
+///
+/// Separator and range lines end in the header line ending; content lines
+/// keep the terminator they were parsed with.
+fn push_raw_line(output: &mut String, line: &str, eol: &str) {
+    if line.starts_with("***") || line.starts_with("---") {
+        output.push_str(line.strip_suffix('\r').unwrap_or(line));
+        output.push_str(eol);
+    } else {
+        output.push_str(line);
+        output.push('\n');
+    }
+}
+
 fn escape_path(path: &str) -> String {
     if path.contains(' ') {
         format!("\"{}\"", path.replace('\\', "\\\\").replace('"', "\\\""))
@@ -148,8 +326,17 @@ fn escape_path(path: &str) -> String {
     }
 }
 
-fn strip_trailing_newline(s: &str) -> &str {
-    s.strip_suffix('\n').unwrap_or(s)
+/// Write one hunk line behind `prefix`
+///
+/// A line without its newline is the last line of a file that doesn't end
+/// in one; like diff, follow it with a `\ No newline at end of file` marker.
+fn push_line(output: &mut String, prefix: char, line: &str) {
+    output.push(prefix);
+    output.push_str(line.strip_suffix('\n').unwrap_or(line));
+    output.push('\n');
+    if !line.is_empty() && !line.ends_with('\n') {
+        output.push_str("\\ No newline at end of file\n");
+    }
 }
 
 #[cfg(test)]
@@ -249,6 +436,229 @@ mod tests {
         assert!(serialized.contains("@@ -10,3 +10,3 @@ int main()"));
     }
 
+    #[test]
+    fn test_round_trip_crlf_is_byte_identical() {
+        // CRLF throughout, plus one LF-only line to cover mixed endings
+        let input = concat!(
+            "--- a/file.txt\t2024-01-01\r\n",
+            "+++ b/file.txt\t2024-01-02\r\n",
+            "@@ -1,4 +1,4 @@\r\n",
+            " line1\r\n",
+            "-old2\r\n",
+            "+new2\r\n",
+            " line3\n",
+            " line4\r\n",
+        );
+        let parser = PatchParser::new();
+        let ps = parser.parse_string(input).unwrap();
+        let serialized = PatchSerializer::serialize(&ps);
+        assert_eq!(serialized.as_bytes(), input.as_bytes());
+    }
+
+    const TWO_FILE_DIFF: &str = "\
+--- a/zeta.txt\t2024-01-01 10:00:00.000000000 +0100
++++ b/zeta.txt\t2024-01-02 11:30:00.123456789 +0100
+@@ -1,2 +1,2 @@
+ keep
+-old
++new
+--- a/alpha.txt\t2023-12-31 23:59:59.999999999 -0500
++++ b/alpha.txt\t2024-01-01 00:00:00.000000000 -0500
+@@ -1,1 +1,1 @@
+-before
++after
+";
+
+    #[test]
+    fn test_serialize_is_deterministic() {
+        let ps = PatchParser::new().parse_string(TWO_FILE_DIFF).unwrap();
+        let first = PatchSerializer::serialize(&ps);
+        let second = PatchSerializer::serialize(&ps.clone());
+        assert_eq!(first.as_bytes(), second.as_bytes());
+        assert_eq!(first, TWO_FILE_DIFF);
+    }
+
+    #[test]
+    fn test_serialize_preserves_file_order() {
+        let ps = PatchParser::new().parse_string(TWO_FILE_DIFF).unwrap();
+        let serialized = PatchSerializer::serialize(&ps);
+        let zeta = serialized.find("--- a/zeta.txt").unwrap();
+        let alpha = serialized.find("--- a/alpha.txt").unwrap();
+        assert!(zeta < alpha);
+
+        let reparsed = PatchParser::new().parse_string(&serialized).unwrap();
+        let order: Vec<&str> = reparsed.files.iter().map(|f| f.source.as_str()).collect();
+        assert_eq!(order, ["a/zeta.txt", "a/alpha.txt"]);
+    }
+
+    #[test]
+    fn test_serialize_timestamp_policies() {
+        let ps = PatchParser::new().parse_string(TWO_FILE_DIFF).unwrap();
+
+        let fixed = TimestampPolicy::Fixed("1970-01-01 00:00:00 +0000".to_string());
+        let serialized = PatchSerializer::serialize_with_timestamps(&ps, &fixed);
+        assert!(serialized.contains("--- a/alpha.txt\t1970-01-01 00:00:00 +0000\n"));
+        assert!(!serialized.contains("2024"));
+
+        let serialized = PatchSerializer::serialize_with_timestamps(&ps, &TimestampPolicy::Omit);
+        assert!(serialized.contains("--- a/zeta.txt\n+++ b/zeta.txt\n"));
+
+        // Headers without a captured timestamp stay bare under a fixed policy
+        let bare = PatchParser::new()
+            .parse_string("--- a/f\n+++ b/f\n@@ -1 +1 @@\n-x\n+y\n")
+            .unwrap();
+        let serialized = PatchSerializer::serialize_with_timestamps(&bare, &fixed);
+        assert!(serialized.starts_with("--- a/f\n+++ b/f\n"));
+    }
+
+    #[test]
+    fn test_round_trip_context_via_raw_is_byte_identical() {
+        let input = "\
+*** a/file.txt\t2024-01-01 10:00:00
+--- b/file.txt\t2024-01-02 10:00:00
+*************** fn main
+*** 1,3 ****
+  line1
+! old_line
+  line3
+--- 1,4 ----
+  line1
+! new_line
++ added
+  line3
+***************
+*** 8,9 ****
+- removed
+  line9
+--- 9 ----
+  line9
+";
+        let ps = PatchParser::new().parse_string(input).unwrap();
+        assert_eq!(PatchSerializer::serialize(&ps), input);
+
+        // Without the raw text the hunks are rebuilt as unified diff
+        let mut rebuilt = ps.clone();
+        for diff in rebuilt.files[0].hunks.iter_mut().flat_map(|h| &mut h.differences) {
+            diff.raw = None;
+        }
+        let serialized = PatchSerializer::serialize(&rebuilt);
+        assert!(serialized.starts_with("--- a/file.txt\t2024-01-01 10:00:00\n+++ b/file.txt"));
+        assert!(serialized.contains("\n@@ -1,"));
+        assert!(serialized.contains("\n-old_line\n+new_line\n"));
+    }
+
+    const CONTEXT_DIFF: &str = "\
+*** a/file.txt
+--- b/file.txt
+***************
+*** 1,2 ****
+! old_first
+  line2
+--- 1,2 ----
+! new_first
+  line2
+***************
+*** 8,9 ****
+- removed
+  line9
+--- 8 ----
+  line9
+";
+
+    #[test]
+    fn test_edited_context_hunk_is_rebuilt_as_unified() {
+        let mut ps = PatchParser::new().parse_string(CONTEXT_DIFF).unwrap();
+        assert_eq!(PatchSerializer::serialize(&ps), CONTEXT_DIFF);
+
+        // Editing the second hunk makes its raw text stale; the file is then
+        // rebuilt as a whole rather than mixing formats
+        let edited = &mut ps.files[0].hunks[1].differences[0];
+        edited.source_lines = vec!["removed, edited\n".to_string()];
+        let serialized = PatchSerializer::serialize(&ps);
+        assert!(!serialized.contains("***"));
+        assert!(serialized.starts_with("--- a/file.txt\n+++ b/file.txt\n@@ -1,2 +1,2 @@\n"));
+        assert!(serialized.contains("\n-old_first\n+new_first\n"));
+        assert!(serialized.contains("\n-removed, edited\n line9\n"));
+    }
+
+    #[test]
+    fn test_context_hunks_follow_header_line_ending() {
+        let mut ps = PatchParser::new().parse_string(CONTEXT_DIFF).unwrap();
+        ps.files[0].crlf_headers = true;
+        let serialized = PatchSerializer::serialize(&ps);
+        assert!(serialized.starts_with("*** a/file.txt\r\n--- b/file.txt\r\n***************\r\n"));
+        assert!(serialized.contains("*** 1,2 ****\r\n! old_first\n"));
+        assert!(serialized.contains("--- 8 ----\r\n  line9\n"));
+
+        // A CRLF diff round-trips byte for byte
+        let crlf = CONTEXT_DIFF.replace('\n', "\r\n");
+        let ps = PatchParser::new().parse_string(&crlf).unwrap();
+        assert_eq!(PatchSerializer::serialize(&ps), crlf);
+    }
+
+    const GIT_DIFF: &str = "\
+diff --git a/run.sh b/run.sh
+old mode 100644
+new mode 100755
+diff --git a/old.txt b/new.txt
+similarity index 71%
+rename from old.txt
+rename to new.txt
+index 83db48f..bf269f4 100644
+--- a/old.txt
++++ b/new.txt
+@@ -1,3 +1,3 @@
+ first
+-second
++second, edited
+ third
+diff --git a/gone.txt b/gone.txt
+deleted file mode 100644
+index 3be11c6..0000000
+--- a/gone.txt
++++ /dev/null
+@@ -1,2 +0,0 @@
+-bye
+-now
+";
+
+    #[test]
+    fn test_round_trip_git_diff() {
+        let parser = PatchParser::new();
+        let ps = parser.parse_string(GIT_DIFF).unwrap();
+        assert_eq!(ps.generator, DiffGenerator::Git);
+
+        let serialized = PatchSerializer::serialize(&ps);
+        assert_eq!(serialized, GIT_DIFF);
+        assert!(serialized.starts_with("diff --git a/run.sh b/run.sh\n"));
+        assert!(serialized.contains("diff --git a/old.txt b/new.txt\n"));
+
+        // The re-parsed rename applies to the original content
+        let mut reparsed = parser.parse_string(&serialized).unwrap();
+        assert_eq!(reparsed.files.len(), 3);
+        let rename = &mut reparsed.files[1];
+        assert_eq!(
+            rename.git.as_ref().unwrap().rename_to.as_deref(),
+            Some("new.txt")
+        );
+        crate::patch_engine::PatchEngine::blend_file(rename, "first\nsecond\nthird\n").unwrap();
+        assert_eq!(
+            crate::patch_engine::PatchEngine::reconstruct_destination(rename).unwrap(),
+            "first\nsecond, edited\nthird\n"
+        );
+    }
+
+    #[test]
+    fn test_git_headers_need_git_generator() {
+        let mut ps = PatchParser::new().parse_string(GIT_DIFF).unwrap();
+        assert!(PatchSerializer::serialize_file_patch(&ps.files[1]).starts_with("--- a/old.txt\n"));
+
+        ps.generator = DiffGenerator::Diff;
+        let serialized = PatchSerializer::serialize(&ps);
+        assert!(!serialized.contains("diff --git"));
+        assert!(serialized.starts_with("--- a/run.sh\n+++ b/run.sh\n--- a/old.txt\n"));
+    }
+
     #[test]
     fn test_serialize_empty_patchset() {
         let ps = PatchSet::new();
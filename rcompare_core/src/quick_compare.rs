@@ -0,0 +1,524 @@
+//! One-call directory comparison API.
+//!
+//! [`compare_directories`] wires together a [`FolderScanner`], a [`HashCache`]
+//! and a [`ComparisonEngine`] with sensible defaults, for embedders that just
+//! want a list of [`DiffNode`]s for two local directories. The lower-level
+//! building blocks remain available when finer control is needed.
+//!
+//! [`compare_directory_pairs`] runs several such comparisons with one shared
+//! hash cache, e.g. sibling subfolders matched up by [`expand_glob_pairs`].
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use rcompare_core::{compare_directories, CompareOptions};
+//! use std::path::Path;
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let options = CompareOptions::new()
+//!     .ignore_pattern("target/")
+//!     .hash_verification(true)
+//!     .threads(4);
+//!
+//! let diffs = compare_directories(Path::new("/left"), Path::new("/right"), &options)?;
+//! for diff in &diffs {
+//!     println!("{:?}: {}", diff.status, diff.relative_path.display());
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::comparison::ComparisonEngine;
+use crate::hash_cache::HashCache;
+use crate::scanner::FolderScanner;
+use rcompare_common::{AppConfig, DiffNode, DiffStatus, RCompareError};
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+use tracing::debug;
+
+/// Options for [`compare_directories`].
+///
+/// Built with chained setters starting from [`CompareOptions::new`]. The
+/// defaults match the CLI: no extra ignore patterns, `.gitignore` files are
+/// honoured, hashes are not verified and the global rayon pool is used.
+#[derive(Debug, Clone)]
+pub struct CompareOptions {
+    /// Gitignore-style patterns to exclude from both sides
+    pub ignore_patterns: Vec<String>,
+    /// Whether to follow symbolic links while scanning
+    pub follow_symlinks: bool,
+    /// Whether to include dotfiles and other hidden entries
+    pub include_hidden: bool,
+    /// Whether to hash same-sized files instead of trusting timestamps
+    pub verify_hashes: bool,
+    /// Whether to read every same-sized pair in full, bypassing the hash
+    /// cache (see [`ComparisonEngine::with_force_content_check`])
+    pub force_content_check: bool,
+    /// Largest modification time difference still treated as equal
+    pub mtime_tolerance: Duration,
+    /// Whether to load `.gitignore` files found under each root
+    pub use_gitignore: bool,
+    /// Number of worker threads (`None` uses the global rayon pool)
+    pub threads: Option<usize>,
+    /// Directory for the persistent hash cache (`None` uses the default cache dir)
+    pub cache_dir: Option<PathBuf>,
+    /// Whether to read and persist the hash cache; when off, hashes are
+    /// kept in memory for the run only
+    pub use_cache: bool,
+}
+
+impl CompareOptions {
+    pub fn new() -> Self {
+        Self {
+            ignore_patterns: Vec::new(),
+            follow_symlinks: false,
+            include_hidden: true,
+            verify_hashes: false,
+            force_content_check: false,
+            mtime_tolerance: Duration::ZERO,
+            use_gitignore: true,
+            threads: None,
+            cache_dir: None,
+            use_cache: true,
+        }
+    }
+
+    /// Add a single ignore pattern
+    pub fn ignore_pattern(mut self, pattern: impl Into<String>) -> Self {
+        self.ignore_patterns.push(pattern.into());
+        self
+    }
+
+    /// Add several ignore patterns
+    pub fn ignore_patterns<I, S>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.ignore_patterns
+            .extend(patterns.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn follow_symlinks(mut self, enabled: bool) -> Self {
+        self.follow_symlinks = enabled;
+        self
+    }
+
+    pub fn include_hidden(mut self, enabled: bool) -> Self {
+        self.include_hidden = enabled;
+        self
+    }
+
+    pub fn hash_verification(mut self, enabled: bool) -> Self {
+        self.verify_hashes = enabled;
+        self
+    }
+
+    /// See [`ComparisonEngine::with_force_content_check`]
+    pub fn force_content_check(mut self, enabled: bool) -> Self {
+        self.force_content_check = enabled;
+        self
+    }
+
+    /// See [`ComparisonEngine::with_mtime_tolerance`]
+    pub fn mtime_tolerance(mut self, tolerance: Duration) -> Self {
+        self.mtime_tolerance = tolerance;
+        self
+    }
+
+    pub fn use_gitignore(mut self, enabled: bool) -> Self {
+        self.use_gitignore = enabled;
+        self
+    }
+
+    /// Limit scanning and hashing to `threads` worker threads
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = Some(threads);
+        self
+    }
+
+    pub fn cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.cache_dir = Some(dir.into());
+        self
+    }
+
+    pub fn use_cache(mut self, enabled: bool) -> Self {
+        self.use_cache = enabled;
+        self
+    }
+
+    fn app_config(&self) -> AppConfig {
+        AppConfig {
+            ignore_patterns: self.ignore_patterns.clone(),
+            follow_symlinks: self.follow_symlinks,
+            include_hidden: self.include_hidden,
+            use_hash_verification: self.verify_hashes,
+            cache_dir: self.cache_dir.clone(),
+            ..Default::default()
+        }
+    }
+
+    fn resolve_cache_dir(&self) -> PathBuf {
+        if let Some(ref dir) = self.cache_dir {
+            return dir.clone();
+        }
+        // The config path is only consulted in portable mode
+        rcompare_common::default_cache_dir(false, Path::new(""))
+            .unwrap_or_else(|_| std::env::temp_dir().join("rcompare_cache"))
+    }
+}
+
+impl Default for CompareOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Compare two local directories in a single call.
+///
+/// Scans both roots, compares the resulting entries and persists the hash
+/// cache. Results are sorted by relative path, exactly as returned by
+/// [`ComparisonEngine::compare`].
+pub fn compare_directories(
+    left: &Path,
+    right: &Path,
+    options: &CompareOptions,
+) -> Result<Vec<DiffNode>, RCompareError> {
+    let pair = [(left.to_path_buf(), right.to_path_buf())];
+    let mut results = compare_directory_pairs(&pair, options)?;
+    Ok(results.remove(0).diffs)
+}
+
+/// Result of one pair in [`compare_directory_pairs`]
+#[derive(Debug, Clone)]
+pub struct PairComparison {
+    pub left: PathBuf,
+    pub right: PathBuf,
+    /// Diff nodes for this pair, sorted by relative path
+    pub diffs: Vec<DiffNode>,
+}
+
+impl PairComparison {
+    /// Number of nodes in this pair with the given status
+    pub fn count(&self, status: DiffStatus) -> usize {
+        self.diffs.iter().filter(|d| d.status == status).count()
+    }
+}
+
+/// Compare several directory pairs in one run.
+///
+/// Every root is validated before anything is scanned. The pairs share one
+/// hash cache, which is persisted once at the end, and results come back
+/// in the order of `pairs`.
+pub fn compare_directory_pairs(
+    pairs: &[(PathBuf, PathBuf)],
+    options: &CompareOptions,
+) -> Result<Vec<PairComparison>, RCompareError> {
+    for (left, right) in pairs {
+        for (side, root) in [("Left", left), ("Right", right)] {
+            if !root.is_dir() {
+                return Err(RCompareError::Path(format!(
+                    "{} path is not a directory: {}",
+                    side,
+                    root.display()
+                )));
+            }
+        }
+    }
+
+    match options.threads {
+        Some(threads) => {
+            let pool = rayon::ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .map_err(|e| {
+                    RCompareError::Config(format!("Failed to build thread pool: {}", e))
+                })?;
+            pool.install(|| run_comparisons(pairs, options))
+        }
+        None => run_comparisons(pairs, options),
+    }
+}
+
+/// Expand two glob patterns into directory pairs.
+///
+/// Matches on each side are keyed by their path below the pattern's literal
+/// prefix (the components before the first one containing `*`, `?` or `[`),
+/// and equal keys are paired: `a/*` and `b/*` pair `a/x` with `b/x`. Only
+/// directories count as matches. A key found on one side only is an error,
+/// so a missing counterpart folder is never silently skipped. Pairs are
+/// sorted by key.
+pub fn expand_glob_pairs(
+    left_pattern: &str,
+    right_pattern: &str,
+) -> Result<Vec<(PathBuf, PathBuf)>, RCompareError> {
+    let left = glob_dirs(left_pattern)?;
+    let mut right = glob_dirs(right_pattern)?;
+
+    let mut pairs = Vec::new();
+    for (key, left_dir) in left {
+        let Some(right_dir) = right.remove(&key) else {
+            return Err(RCompareError::Path(format!(
+                "{} has no counterpart matching {}",
+                left_dir.display(),
+                right_pattern
+            )));
+        };
+        pairs.push((left_dir, right_dir));
+    }
+    if let Some(right_dir) = right.into_values().next() {
+        return Err(RCompareError::Path(format!(
+            "{} has no counterpart matching {}",
+            right_dir.display(),
+            left_pattern
+        )));
+    }
+    if pairs.is_empty() {
+        return Err(RCompareError::Path(format!(
+            "No directories match {}",
+            left_pattern
+        )));
+    }
+
+    Ok(pairs)
+}
+
+/// Directories matching `pattern`, keyed by their path below its literal prefix
+fn glob_dirs(pattern: &str) -> Result<BTreeMap<PathBuf, PathBuf>, RCompareError> {
+    let prefix: PathBuf = Path::new(pattern)
+        .components()
+        .take_while(|c| match c {
+            Component::Normal(part) => !part.to_string_lossy().contains(['*', '?', '[']),
+            _ => true,
+        })
+        .collect();
+
+    let paths = glob::glob(pattern)
+        .map_err(|e| RCompareError::Config(format!("Invalid glob pattern {}: {}", pattern, e)))?;
+    let mut dirs = BTreeMap::new();
+    for path in paths {
+        let path = path.map_err(|e| RCompareError::Io(e.into_error()))?;
+        if !path.is_dir() {
+            continue;
+        }
+        let key = path.strip_prefix(&prefix).unwrap_or(&path).to_path_buf();
+        dirs.insert(key, path);
+    }
+    Ok(dirs)
+}
+
+fn run_comparisons(
+    pairs: &[(PathBuf, PathBuf)],
+    options: &CompareOptions,
+) -> Result<Vec<PairComparison>, RCompareError> {
+    let cache = if options.use_cache {
+        let cache_dir = options.resolve_cache_dir();
+        debug!("Using cache directory: {}", cache_dir.display());
+        HashCache::open_or_in_memory(cache_dir)
+    } else {
+        HashCache::in_memory()
+    };
+
+    let engine = ComparisonEngine::new(cache)
+        .with_hash_verification(options.verify_hashes)
+        .with_force_content_check(options.force_content_check)
+        .with_mtime_tolerance(options.mtime_tolerance);
+
+    let mut results = Vec::with_capacity(pairs.len());
+    for (left, right) in pairs {
+        let diffs = run_comparison(&engine, left, right, options)?;
+        results.push(PairComparison {
+            left: left.clone(),
+            right: right.clone(),
+            diffs,
+        });
+    }
+    engine.persist_cache()?;
+
+    Ok(results)
+}
+
+fn run_comparison(
+    engine: &ComparisonEngine,
+    left: &Path,
+    right: &Path,
+    options: &CompareOptions,
+) -> Result<Vec<DiffNode>, RCompareError> {
+    let config = options.app_config();
+
+    let mut left_scanner = FolderScanner::new(config.clone());
+    let mut right_scanner = FolderScanner::new(config);
+    if options.use_gitignore {
+        left_scanner.load_gitignore(left)?;
+        right_scanner.load_gitignore(right)?;
+    }
+
+    let left_entries = left_scanner.scan(left)?;
+    let right_entries = right_scanner.scan(right)?;
+
+    engine.compare(left, right, left_entries, right_entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn status_of(diffs: &[DiffNode], path: &str) -> DiffStatus {
+        diffs
+            .iter()
+            .find(|d| d.relative_path == Path::new(path))
+            .map(|d| d.status)
+            .unwrap_or_else(|| panic!("missing diff node for {}", path))
+    }
+
+    #[test]
+    fn test_compare_directories_basic() {
+        let temp = TempDir::new().unwrap();
+        let left = temp.path().join("left");
+        let right = temp.path().join("right");
+        fs::create_dir_all(&left).unwrap();
+        fs::create_dir_all(&right).unwrap();
+
+        fs::write(left.join("same.txt"), b"identical").unwrap();
+        fs::write(right.join("same.txt"), b"identical").unwrap();
+        fs::write(left.join("changed.txt"), b"left version").unwrap();
+        fs::write(right.join("changed.txt"), b"right version!").unwrap();
+        fs::write(left.join("left_only.txt"), b"left").unwrap();
+        fs::write(right.join("right_only.txt"), b"right").unwrap();
+
+        let options = CompareOptions::new()
+            .hash_verification(true)
+            .threads(2)
+            .cache_dir(temp.path().join("cache"));
+        let diffs = compare_directories(&left, &right, &options).unwrap();
+
+        assert_eq!(diffs.len(), 4);
+        assert_eq!(status_of(&diffs, "same.txt"), DiffStatus::Same);
+        assert_eq!(status_of(&diffs, "changed.txt"), DiffStatus::Different);
+        assert_eq!(status_of(&diffs, "left_only.txt"), DiffStatus::OrphanLeft);
+        assert_eq!(status_of(&diffs, "right_only.txt"), DiffStatus::OrphanRight);
+    }
+
+    #[test]
+    fn test_compare_directories_without_writable_cache() {
+        let temp = TempDir::new().unwrap();
+        let left = temp.path().join("left");
+        let right = temp.path().join("right");
+        fs::create_dir_all(&left).unwrap();
+        fs::create_dir_all(&right).unwrap();
+
+        // Same size, different content: only hashing can tell them apart
+        fs::write(left.join("same.txt"), b"identical").unwrap();
+        fs::write(right.join("same.txt"), b"identical").unwrap();
+        fs::write(left.join("changed.txt"), b"version 1").unwrap();
+        fs::write(right.join("changed.txt"), b"version 2").unwrap();
+        let blocker = temp.path().join("blocker");
+        fs::write(&blocker, b"").unwrap();
+
+        let options = CompareOptions::new()
+            .hash_verification(true)
+            .cache_dir(blocker.join("cache"));
+        let diffs = compare_directories(&left, &right, &options).unwrap();
+
+        assert_eq!(status_of(&diffs, "same.txt"), DiffStatus::Same);
+        assert_eq!(status_of(&diffs, "changed.txt"), DiffStatus::Different);
+        assert!(!blocker.join("cache").exists());
+    }
+
+    #[test]
+    fn test_compare_directories_without_cache() {
+        let temp = TempDir::new().unwrap();
+        let left = temp.path().join("left");
+        let right = temp.path().join("right");
+        fs::create_dir_all(&left).unwrap();
+        fs::create_dir_all(&right).unwrap();
+        fs::write(left.join("changed.txt"), b"version 1").unwrap();
+        fs::write(right.join("changed.txt"), b"version 2").unwrap();
+
+        let options = CompareOptions::new()
+            .hash_verification(true)
+            .cache_dir(temp.path().join("cache"))
+            .use_cache(false);
+        let diffs = compare_directories(&left, &right, &options).unwrap();
+
+        assert_eq!(status_of(&diffs, "changed.txt"), DiffStatus::Different);
+        assert!(!temp.path().join("cache").exists());
+    }
+
+    #[test]
+    fn test_compare_directories_ignore_patterns() {
+        let temp = TempDir::new().unwrap();
+        let left = temp.path().join("left");
+        let right = temp.path().join("right");
+        fs::create_dir_all(&left).unwrap();
+        fs::create_dir_all(&right).unwrap();
+
+        fs::write(left.join("keep.txt"), b"keep").unwrap();
+        fs::write(right.join("keep.txt"), b"keep").unwrap();
+        fs::write(left.join("build.o"), b"object").unwrap();
+
+        let options = CompareOptions::new()
+            .ignore_pattern("*.o")
+            .hash_verification(true)
+            .cache_dir(temp.path().join("cache"));
+        let diffs = compare_directories(&left, &right, &options).unwrap();
+
+        assert_eq!(diffs.len(), 1);
+        assert_eq!(status_of(&diffs, "keep.txt"), DiffStatus::Same);
+    }
+
+    #[test]
+    fn test_compare_directories_rejects_missing_root() {
+        let temp = TempDir::new().unwrap();
+        let options = CompareOptions::new().cache_dir(temp.path().join("cache"));
+        let result = compare_directories(&temp.path().join("missing"), temp.path(), &options);
+        assert!(matches!(result, Err(RCompareError::Path(_))));
+    }
+
+    #[test]
+    fn test_compare_directory_pairs_reports_each_pair() {
+        let temp = TempDir::new().unwrap();
+        for (side, module, files) in [
+            ("projectA", "api", &[("a.txt", "one"), ("b.txt", "two")][..]),
+            ("projectB", "api", &[("a.txt", "one"), ("b.txt", "TWO")][..]),
+            ("projectA", "web", &[("index.html", "<p>")][..]),
+            (
+                "projectB",
+                "web",
+                &[("index.html", "<p>"), ("new.css", "p{}")][..],
+            ),
+        ] {
+            let dir = temp.path().join(side).join(module);
+            fs::create_dir_all(&dir).unwrap();
+            for (name, content) in files {
+                fs::write(dir.join(name), content).unwrap();
+            }
+        }
+
+        let pattern = |side: &str| format!("{}/*", temp.path().join(side).display());
+        let pairs = expand_glob_pairs(&pattern("projectA"), &pattern("projectB")).unwrap();
+        assert_eq!(pairs.len(), 2);
+        assert!(pairs[0].0.ends_with("projectA/api") && pairs[0].1.ends_with("projectB/api"));
+
+        let options = CompareOptions::new()
+            .hash_verification(true)
+            .cache_dir(temp.path().join("cache"));
+        let results = compare_directory_pairs(&pairs, &options).unwrap();
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].left, pairs[0].0);
+        assert_eq!(results[0].count(DiffStatus::Same), 1);
+        assert_eq!(results[0].count(DiffStatus::Different), 1);
+        assert_eq!(results[1].right, pairs[1].1);
+        assert_eq!(results[1].count(DiffStatus::Same), 1);
+        assert_eq!(results[1].count(DiffStatus::OrphanRight), 1);
+
+        fs::create_dir(temp.path().join("projectA/docs")).unwrap();
+        let result = expand_glob_pairs(&pattern("projectA"), &pattern("projectB"));
+        assert!(matches!(result, Err(RCompareError::Path(_))));
+    }
+}
@@ -0,0 +1,596 @@
+//! Grouping, sorting and filtering helpers for comparison results.
+//!
+//! These are pure functions over `&[DiffNode]` that return indices into the
+//! input slice, so callers can build several views of one result without
+//! cloning nodes. Ties always keep the original (path) order.
+//!
+//! The navigation helpers work on a plain list of statuses in display order,
+//! so a front-end can step through whatever flattened view it shows.
+//!
+//! [`three_way_dir_statuses`] rolls three-way results up to their folders so
+//! a three-way view can colour directories by which side changed beneath them.
+//!
+//! [`summarize`] and [`summarize_three_way`] are the one place results are
+//! tallied for summaries and exit codes.
+
+use rcompare_common::{
+    DiffNode, DiffReason, DiffStatus, FileEntry, ThreeWayDiffNode, ThreeWayDiffStatus,
+};
+use std::collections::BTreeMap;
+use std::path::{Component, PathBuf};
+
+/// Group node indices by the first component of their relative path
+///
+/// Entries at the top level of the tree are grouped under the empty path;
+/// a top-level directory is grouped with its own contents.
+pub fn group_by_top_dir(nodes: &[DiffNode]) -> BTreeMap<PathBuf, Vec<usize>> {
+    let mut groups: BTreeMap<PathBuf, Vec<usize>> = BTreeMap::new();
+    for (idx, node) in nodes.iter().enumerate() {
+        let mut components = node.relative_path.components();
+        let key = match (components.next(), components.next()) {
+            (Some(Component::Normal(first)), Some(_)) => PathBuf::from(first),
+            (Some(Component::Normal(first)), None) if is_dir(node) => PathBuf::from(first),
+            _ => PathBuf::new(),
+        };
+        groups.entry(key).or_default().push(idx);
+    }
+    groups
+}
+
+/// Absolute size difference between the two sides of a node
+///
+/// An orphan counts its full size; directories count as zero.
+pub fn size_delta(node: &DiffNode) -> u64 {
+    let size = |entry: Option<&FileEntry>| entry.filter(|e| !e.is_dir).map_or(0, |e| e.size);
+    size(node.left.as_ref()).abs_diff(size(node.right.as_ref()))
+}
+
+/// Node indices ordered by [`size_delta`], largest first
+pub fn sort_by_size_delta(nodes: &[DiffNode]) -> Vec<usize> {
+    let mut indices: Vec<usize> = (0..nodes.len()).collect();
+    indices.sort_by_key(|&idx| std::cmp::Reverse(size_delta(&nodes[idx])));
+    indices
+}
+
+/// Indices of nodes whose status is one of `statuses`
+pub fn filter_by_status(nodes: &[DiffNode], statuses: &[DiffStatus]) -> Vec<usize> {
+    nodes
+        .iter()
+        .enumerate()
+        .filter(|(_, node)| statuses.contains(&node.status))
+        .map(|(idx, _)| idx)
+        .collect()
+}
+
+/// Number of entries per [`DiffStatus`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StatusCounts {
+    pub same: usize,
+    pub different: usize,
+    pub orphan_left: usize,
+    pub orphan_right: usize,
+    pub unchecked: usize,
+    pub type_conflict: usize,
+    pub ignored: usize,
+}
+
+impl StatusCounts {
+    /// Tally a sequence of statuses
+    pub fn from_statuses<I: IntoIterator<Item = DiffStatus>>(statuses: I) -> Self {
+        let mut counts = Self::default();
+        for status in statuses {
+            *counts.slot(status) += 1;
+        }
+        counts
+    }
+
+    /// Count for a single status
+    pub fn get(&self, status: DiffStatus) -> usize {
+        match status {
+            DiffStatus::Same => self.same,
+            DiffStatus::Different => self.different,
+            DiffStatus::OrphanLeft => self.orphan_left,
+            DiffStatus::OrphanRight => self.orphan_right,
+            DiffStatus::Unchecked => self.unchecked,
+            DiffStatus::TypeConflict => self.type_conflict,
+            DiffStatus::Ignored => self.ignored,
+        }
+    }
+
+    fn slot(&mut self, status: DiffStatus) -> &mut usize {
+        match status {
+            DiffStatus::Same => &mut self.same,
+            DiffStatus::Different => &mut self.different,
+            DiffStatus::OrphanLeft => &mut self.orphan_left,
+            DiffStatus::OrphanRight => &mut self.orphan_right,
+            DiffStatus::Unchecked => &mut self.unchecked,
+            DiffStatus::TypeConflict => &mut self.type_conflict,
+            DiffStatus::Ignored => &mut self.ignored,
+        }
+    }
+}
+
+/// Totals of a two-way comparison result, from [`summarize`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ComparisonSummary {
+    /// Number of nodes
+    pub total: usize,
+    /// Nodes per status
+    pub statuses: StatusCounts,
+    /// Left orphans paired with a near-duplicate right orphan, i.e. likely
+    /// renames; one per pair
+    pub renamed: usize,
+    /// Nodes flagged [`DiffReason::EmptiedFile`]
+    pub emptied: usize,
+    /// Nodes flagged [`DiffReason::ChangedDuringScan`]
+    pub changed_during_scan: usize,
+}
+
+impl ComparisonSummary {
+    /// Paths present on both sides that don't match, in content or in kind
+    pub fn changed(&self) -> usize {
+        self.statuses.different + self.statuses.type_conflict
+    }
+
+    /// Whether any node is neither `Same` nor `Ignored`
+    pub fn has_differences(&self) -> bool {
+        self.statuses.same + self.statuses.ignored < self.total
+    }
+}
+
+/// Tally a two-way comparison result
+pub fn summarize(nodes: &[DiffNode]) -> ComparisonSummary {
+    let mut summary = ComparisonSummary {
+        total: nodes.len(),
+        ..ComparisonSummary::default()
+    };
+    for node in nodes {
+        *summary.statuses.slot(node.status) += 1;
+        if node.status == DiffStatus::OrphanLeft && node.similar_to.is_some() {
+            summary.renamed += 1;
+        }
+        match node.reason {
+            Some(DiffReason::EmptiedFile) => summary.emptied += 1,
+            Some(DiffReason::ChangedDuringScan) => summary.changed_during_scan += 1,
+            Some(DiffReason::DecompressedMatch | DiffReason::Renamed) | None => {}
+        }
+    }
+    summary
+}
+
+/// Totals of a three-way comparison result, from [`summarize_three_way`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ThreeWaySummary {
+    pub total: usize,
+    pub all_same: usize,
+    pub left_changed: usize,
+    pub right_changed: usize,
+    pub both_changed: usize,
+    pub base_only: usize,
+    pub left_only: usize,
+    pub right_only: usize,
+    pub both_added: usize,
+    pub base_and_left: usize,
+    pub base_and_right: usize,
+}
+
+impl ThreeWaySummary {
+    /// Count for a single status
+    pub fn get(&self, status: ThreeWayDiffStatus) -> usize {
+        match status {
+            ThreeWayDiffStatus::AllSame => self.all_same,
+            ThreeWayDiffStatus::LeftChanged => self.left_changed,
+            ThreeWayDiffStatus::RightChanged => self.right_changed,
+            ThreeWayDiffStatus::BothChanged => self.both_changed,
+            ThreeWayDiffStatus::BaseOnly => self.base_only,
+            ThreeWayDiffStatus::LeftOnly => self.left_only,
+            ThreeWayDiffStatus::RightOnly => self.right_only,
+            ThreeWayDiffStatus::BothAdded => self.both_added,
+            ThreeWayDiffStatus::BaseAndLeft => self.base_and_left,
+            ThreeWayDiffStatus::BaseAndRight => self.base_and_right,
+        }
+    }
+
+    fn slot(&mut self, status: ThreeWayDiffStatus) -> &mut usize {
+        match status {
+            ThreeWayDiffStatus::AllSame => &mut self.all_same,
+            ThreeWayDiffStatus::LeftChanged => &mut self.left_changed,
+            ThreeWayDiffStatus::RightChanged => &mut self.right_changed,
+            ThreeWayDiffStatus::BothChanged => &mut self.both_changed,
+            ThreeWayDiffStatus::BaseOnly => &mut self.base_only,
+            ThreeWayDiffStatus::LeftOnly => &mut self.left_only,
+            ThreeWayDiffStatus::RightOnly => &mut self.right_only,
+            ThreeWayDiffStatus::BothAdded => &mut self.both_added,
+            ThreeWayDiffStatus::BaseAndLeft => &mut self.base_and_left,
+            ThreeWayDiffStatus::BaseAndRight => &mut self.base_and_right,
+        }
+    }
+}
+
+/// Tally a three-way comparison result
+pub fn summarize_three_way(nodes: &[ThreeWayDiffNode]) -> ThreeWaySummary {
+    let mut summary = ThreeWaySummary {
+        total: nodes.len(),
+        ..ThreeWaySummary::default()
+    };
+    for node in nodes {
+        *summary.slot(node.status) += 1;
+    }
+    summary
+}
+
+/// Index of the first entry after `current` whose status is one of `targets`
+///
+/// The search wraps past the end of the list. With no current entry it
+/// starts from the top; if `current` is the only match it is returned again.
+pub fn next_with_status(
+    statuses: &[DiffStatus],
+    current: Option<usize>,
+    targets: &[DiffStatus],
+) -> Option<usize> {
+    let len = statuses.len();
+    let start = current.map_or(0, |idx| idx + 1);
+    (0..len)
+        .map(|offset| (start + offset) % len)
+        .find(|&idx| targets.contains(&statuses[idx]))
+}
+
+/// Index of the last entry before `current` whose status is one of `targets`
+///
+/// Mirror image of [`next_with_status`]: wraps past the start of the list and
+/// starts from the bottom when there is no current entry.
+pub fn previous_with_status(
+    statuses: &[DiffStatus],
+    current: Option<usize>,
+    targets: &[DiffStatus],
+) -> Option<usize> {
+    let len = statuses.len();
+    let start = current.unwrap_or(len);
+    (1..=len)
+        .map(|offset| (start + len - offset) % len)
+        .find(|&idx| targets.contains(&statuses[idx]))
+}
+
+/// Rolled-up status of every directory in a three-way result
+///
+/// A directory reports [`ThreeWayDiffStatus::LeftChanged`] when only left-side
+/// changes exist beneath it, [`ThreeWayDiffStatus::RightChanged`] for right-side
+/// only, [`ThreeWayDiffStatus::BothChanged`] when both sides changed (whether
+/// the same or different descendants) and [`ThreeWayDiffStatus::AllSame`]
+/// otherwise. Additions and deletions count for the side that made them; a
+/// path deleted on both sides or added on both sides counts for both.
+///
+/// A directory whose own status is not `AllSame` (e.g. a folder that only
+/// exists on the left) keeps that status, like the two-way tree rollup keeps
+/// orphan folders. Every ancestor of every node gets an entry, including the
+/// root under the empty path, even when the directory itself is not a node.
+pub fn three_way_dir_statuses(nodes: &[ThreeWayDiffNode]) -> BTreeMap<PathBuf, ThreeWayDiffStatus> {
+    let mut sides: BTreeMap<PathBuf, (bool, bool)> = BTreeMap::new();
+    for node in nodes {
+        let (left, right) = changed_sides(node.status);
+        let mut ancestor = node.relative_path.parent();
+        while let Some(dir) = ancestor {
+            let entry = sides.entry(dir.to_path_buf()).or_default();
+            entry.0 |= left;
+            entry.1 |= right;
+            ancestor = dir.parent();
+        }
+    }
+
+    let mut statuses: BTreeMap<PathBuf, ThreeWayDiffStatus> = sides
+        .into_iter()
+        .map(|(dir, sides)| {
+            let status = match sides {
+                (false, false) => ThreeWayDiffStatus::AllSame,
+                (true, false) => ThreeWayDiffStatus::LeftChanged,
+                (false, true) => ThreeWayDiffStatus::RightChanged,
+                (true, true) => ThreeWayDiffStatus::BothChanged,
+            };
+            (dir, status)
+        })
+        .collect();
+
+    for node in nodes.iter().filter(|node| is_three_way_dir(node)) {
+        if node.status != ThreeWayDiffStatus::AllSame {
+            statuses.insert(node.relative_path.clone(), node.status);
+        } else {
+            statuses
+                .entry(node.relative_path.clone())
+                .or_insert(ThreeWayDiffStatus::AllSame);
+        }
+    }
+    statuses
+}
+
+/// Which sides (left, right) moved away from base for a single path
+fn changed_sides(status: ThreeWayDiffStatus) -> (bool, bool) {
+    match status {
+        ThreeWayDiffStatus::AllSame => (false, false),
+        ThreeWayDiffStatus::LeftChanged
+        | ThreeWayDiffStatus::LeftOnly
+        | ThreeWayDiffStatus::BaseAndRight => (true, false),
+        ThreeWayDiffStatus::RightChanged
+        | ThreeWayDiffStatus::RightOnly
+        | ThreeWayDiffStatus::BaseAndLeft => (false, true),
+        ThreeWayDiffStatus::BothChanged
+        | ThreeWayDiffStatus::BothAdded
+        | ThreeWayDiffStatus::BaseOnly => (true, true),
+    }
+}
+
+fn is_three_way_dir(node: &ThreeWayDiffNode) -> bool {
+    [&node.base, &node.left, &node.right]
+        .into_iter()
+        .flatten()
+        .any(|e| e.is_dir)
+}
+
+fn is_dir(node: &DiffNode) -> bool {
+    node.left
+        .as_ref()
+        .or(node.right.as_ref())
+        .is_some_and(|e| e.is_dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rcompare_common::FileType;
+    use std::path::Path;
+    use std::time::SystemTime;
+
+    fn side(path: &str, size: Option<u64>) -> Option<FileEntry> {
+        size.map(|size| FileEntry {
+            path: PathBuf::from(path),
+            size,
+            modified: SystemTime::UNIX_EPOCH,
+            is_dir: false,
+            file_type: FileType::Regular,
+        })
+    }
+
+    fn node(path: &str, left: Option<u64>, right: Option<u64>, status: DiffStatus) -> DiffNode {
+        DiffNode {
+            relative_path: PathBuf::from(path),
+            left: side(path, left),
+            right: side(path, right),
+            status,
+            similar_to: None,
+            reason: None,
+            ignored_by: None,
+        }
+    }
+
+    fn fixture() -> Vec<DiffNode> {
+        let mut src = node("src", Some(0), Some(0), DiffStatus::Same);
+        src.left.as_mut().unwrap().is_dir = true;
+        src.right.as_mut().unwrap().is_dir = true;
+        vec![
+            node("README.md", Some(100), Some(100), DiffStatus::Same),
+            src,
+            node("src/lib.rs", Some(500), Some(800), DiffStatus::Different),
+            node("src/old.rs", Some(50), None, DiffStatus::OrphanLeft),
+            node("tests/a/it.rs", None, Some(1000), DiffStatus::OrphanRight),
+            node("tests/b.rs", Some(10), Some(5), DiffStatus::Unchecked),
+        ]
+    }
+
+    #[test]
+    fn test_group_by_top_dir() {
+        let groups = group_by_top_dir(&fixture());
+        let keys: Vec<&Path> = groups.keys().map(PathBuf::as_path).collect();
+        assert_eq!(keys, [Path::new(""), Path::new("src"), Path::new("tests")]);
+        assert_eq!(groups[Path::new("")], [0]);
+        assert_eq!(groups[Path::new("src")], [1, 2, 3]);
+        assert_eq!(groups[Path::new("tests")], [4, 5]);
+    }
+
+    #[test]
+    fn test_sort_by_size_delta_largest_first() {
+        let nodes = fixture();
+        assert_eq!(sort_by_size_delta(&nodes), [4, 2, 3, 5, 0, 1]);
+        assert_eq!(size_delta(&nodes[2]), 300);
+        assert_eq!(size_delta(&nodes[5]), 5);
+    }
+
+    #[test]
+    fn test_filter_by_status() {
+        let nodes = fixture();
+        assert_eq!(
+            filter_by_status(&nodes, &[DiffStatus::Different, DiffStatus::Unchecked]),
+            [2, 5]
+        );
+        assert_eq!(
+            filter_by_status(&nodes, &[DiffStatus::OrphanLeft, DiffStatus::OrphanRight]),
+            [3, 4]
+        );
+        assert!(filter_by_status(&nodes, &[]).is_empty());
+    }
+
+    fn statuses() -> Vec<DiffStatus> {
+        fixture().iter().map(|node| node.status).collect()
+    }
+
+    #[test]
+    fn test_status_counts() {
+        let counts = StatusCounts::from_statuses(statuses());
+        assert_eq!(counts.same, 2);
+        assert_eq!(counts.get(DiffStatus::Different), 1);
+        assert_eq!(counts.get(DiffStatus::OrphanRight), 1);
+        assert_eq!(counts.get(DiffStatus::TypeConflict), 0);
+    }
+
+    #[test]
+    fn test_next_with_status_skips_and_wraps() {
+        use DiffStatus::*;
+        let list = [Same, Different, OrphanLeft, Same, Different, Unchecked];
+        let targets = [Different];
+        assert_eq!(next_with_status(&list, None, &targets), Some(1));
+        assert_eq!(next_with_status(&list, Some(1), &targets), Some(4));
+        assert_eq!(next_with_status(&list, Some(2), &targets), Some(4));
+        assert_eq!(next_with_status(&list, Some(4), &targets), Some(1));
+        assert_eq!(next_with_status(&list, Some(5), &targets), Some(1));
+        assert_eq!(
+            next_with_status(&[Same, Different], Some(1), &targets),
+            Some(1)
+        );
+        assert_eq!(next_with_status(&list, None, &[TypeConflict]), None);
+        assert_eq!(next_with_status(&[], None, &targets), None);
+    }
+
+    #[test]
+    fn test_previous_with_status_skips_and_wraps() {
+        use DiffStatus::*;
+        let list = [Same, Different, OrphanLeft, Same, Different, Unchecked];
+        let targets = [Different];
+        assert_eq!(previous_with_status(&list, None, &targets), Some(4));
+        assert_eq!(previous_with_status(&list, Some(4), &targets), Some(1));
+        assert_eq!(previous_with_status(&list, Some(3), &targets), Some(1));
+        assert_eq!(previous_with_status(&list, Some(1), &targets), Some(4));
+        assert_eq!(previous_with_status(&list, Some(0), &targets), Some(4));
+        assert_eq!(previous_with_status(&list, None, &[TypeConflict]), None);
+        assert_eq!(previous_with_status(&[], None, &targets), None);
+    }
+
+    fn three_way(path: &str, is_dir: bool, status: ThreeWayDiffStatus) -> ThreeWayDiffNode {
+        let entry = Some(FileEntry {
+            path: PathBuf::from(path),
+            size: 0,
+            modified: SystemTime::UNIX_EPOCH,
+            is_dir,
+            file_type: FileType::from_is_dir(is_dir),
+        });
+        ThreeWayDiffNode {
+            relative_path: PathBuf::from(path),
+            base: entry.clone(),
+            left: entry.clone(),
+            right: entry,
+            status,
+        }
+    }
+
+    #[test]
+    fn test_three_way_dir_rollup() {
+        use ThreeWayDiffStatus::*;
+        let mut new_dir = three_way("new", true, LeftOnly);
+        new_dir.base = None;
+        new_dir.right = None;
+        let nodes = vec![
+            three_way("docs", true, AllSame),
+            three_way("docs/a.md", false, AllSame),
+            three_way("src", true, AllSame),
+            three_way("src/core", true, AllSame),
+            three_way("src/core/a.rs", false, LeftChanged),
+            three_way("src/core/b.rs", false, BaseAndRight),
+            three_way("src/ui", true, AllSame),
+            three_way("src/ui/main.rs", false, RightChanged),
+            three_way("src/ui/gone.rs", false, BaseAndLeft),
+            new_dir,
+            three_way("new/x.rs", false, LeftOnly),
+            three_way("tests/added/it.rs", false, BothAdded),
+        ];
+
+        let dirs = three_way_dir_statuses(&nodes);
+        assert_eq!(dirs[Path::new("docs")], AllSame);
+        assert_eq!(dirs[Path::new("src/core")], LeftChanged);
+        assert_eq!(dirs[Path::new("src/ui")], RightChanged);
+        // Left and right changed different descendants
+        assert_eq!(dirs[Path::new("src")], BothChanged);
+        // A folder added on one side keeps its own status
+        assert_eq!(dirs[Path::new("new")], LeftOnly);
+        // Ancestors that were never scanned as nodes still roll up
+        assert_eq!(dirs[Path::new("tests")], BothChanged);
+        assert_eq!(dirs[Path::new("tests/added")], BothChanged);
+        assert_eq!(dirs[Path::new("")], BothChanged);
+        assert!(!dirs.contains_key(Path::new("docs/a.md")));
+    }
+
+    #[test]
+    fn test_summarize_counts_statuses_and_reasons() {
+        let mut nodes = fixture();
+        let mut conflict = node("bin", Some(4), Some(0), DiffStatus::TypeConflict);
+        conflict.right.as_mut().unwrap().is_dir = true;
+        nodes.push(conflict);
+        let mut emptied = node("log.txt", Some(9), Some(0), DiffStatus::Different);
+        emptied.reason = Some(DiffReason::EmptiedFile);
+        nodes.push(emptied);
+        let mut renamed_from = node("old_name.rs", Some(70), None, DiffStatus::OrphanLeft);
+        renamed_from.similar_to = Some(rcompare_common::SimilarTo {
+            path: PathBuf::from("new_name.rs"),
+            similarity: 95.0,
+        });
+        let mut renamed_to = node("new_name.rs", None, Some(71), DiffStatus::OrphanRight);
+        renamed_to.similar_to = Some(rcompare_common::SimilarTo {
+            path: PathBuf::from("old_name.rs"),
+            similarity: 95.0,
+        });
+        nodes.extend([renamed_from, renamed_to]);
+        let volatile = nodes
+            .iter_mut()
+            .find(|n| n.status == DiffStatus::Unchecked)
+            .unwrap();
+        volatile.reason = Some(DiffReason::ChangedDuringScan);
+
+        let summary = summarize(&nodes);
+        assert_eq!(summary.total, 10);
+        assert_eq!(
+            summary.statuses,
+            StatusCounts {
+                same: 2,
+                different: 2,
+                orphan_left: 2,
+                orphan_right: 2,
+                unchecked: 1,
+                type_conflict: 1,
+                ignored: 0,
+            }
+        );
+        assert_eq!(summary.changed(), 3);
+        assert_eq!(summary.renamed, 1);
+        assert_eq!(summary.emptied, 1);
+        assert_eq!(summary.changed_during_scan, 1);
+        assert!(summary.has_differences());
+
+        let same = [
+            node("a", Some(1), Some(1), DiffStatus::Same),
+            node("b.log", Some(1), None, DiffStatus::Ignored),
+        ];
+        assert!(!summarize(&same).has_differences());
+        assert_eq!(summarize(&[]), ComparisonSummary::default());
+    }
+
+    #[test]
+    fn test_summarize_three_way() {
+        use ThreeWayDiffStatus::*;
+        let statuses = [
+            AllSame,
+            AllSame,
+            LeftChanged,
+            RightChanged,
+            RightChanged,
+            BothChanged,
+            BaseOnly,
+            LeftOnly,
+            RightOnly,
+            BothAdded,
+            BaseAndLeft,
+            BaseAndRight,
+        ];
+        let nodes: Vec<ThreeWayDiffNode> = statuses
+            .iter()
+            .enumerate()
+            .map(|(idx, &status)| three_way(&format!("f{idx}"), false, status))
+            .collect();
+
+        let summary = summarize_three_way(&nodes);
+        assert_eq!(summary.total, 12);
+        assert_eq!(summary.all_same, 2);
+        assert_eq!(summary.right_changed, 2);
+        for status in statuses
+            .into_iter()
+            .filter(|s| !matches!(s, AllSame | RightChanged))
+        {
+            assert_eq!(summary.get(status), 1, "{status:?}");
+        }
+    }
+}
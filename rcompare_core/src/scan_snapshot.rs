@@ -0,0 +1,228 @@
+//! Directory listings kept between scans for incremental rescanning.
+//!
+//! [`FolderScanner::scan_incremental`](crate::FolderScanner::scan_incremental)
+//! records, for every directory it walks, the directory's modification time
+//! and the entries it kept from it. The next incremental scan of the same
+//! root reuses the listing of every directory whose modification time is
+//! unchanged instead of reading it again.
+//!
+//! # On-Disk Format
+//!
+//! Like the hash cache, a saved snapshot starts with magic bytes (`RCSS`)
+//! and a little-endian `u32` format version, followed by the bincode
+//! payload. A snapshot that is missing, unreadable or of another version
+//! loads as `None`, so the worst outcome is a full rescan.
+
+use rcompare_common::{FileEntry, RCompareError};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tracing::{debug, warn};
+
+const SNAPSHOT_MAGIC: &[u8; 4] = b"RCSS";
+
+/// Current on-disk snapshot format version
+pub const SNAPSHOT_FORMAT_VERSION: u32 = 2;
+
+/// Listings of directories modified this close to the start of the scan
+/// that recorded them are not reused
+///
+/// A change made in the same timestamp tick as the listing would leave the
+/// directory mtime unchanged. Two seconds also covers FAT's granularity.
+const RACY_WINDOW: Duration = Duration::from_secs(2);
+
+/// Listing of one directory as of a previous scan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct DirListing {
+    pub(crate) modified: SystemTime,
+    /// Entries kept from the directory after hidden and ignore filtering
+    pub(crate) children: Vec<ListedChild>,
+}
+
+/// One entry of a [`DirListing`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct ListedChild {
+    pub(crate) entry: FileEntry,
+    /// Whether the entry is a real directory to walk (not a symlink to one)
+    pub(crate) descend: bool,
+}
+
+/// Directory listings of one scanned tree
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScanSnapshot {
+    root: PathBuf,
+    /// Fingerprint of the scanner settings the listings were filtered with
+    settings: String,
+    /// When the scan that recorded the listings started
+    taken: SystemTime,
+    /// Keyed by path relative to the root; the root itself is the empty path
+    dirs: HashMap<PathBuf, DirListing>,
+}
+
+impl ScanSnapshot {
+    pub(crate) fn new(root: &Path, settings: String, taken: SystemTime) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            settings,
+            taken,
+            dirs: HashMap::new(),
+        }
+    }
+
+    /// Root the snapshot was taken of
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Number of directory listings held
+    pub fn len(&self) -> usize {
+        self.dirs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.dirs.is_empty()
+    }
+
+    pub(crate) fn insert(&mut self, dir: PathBuf, listing: DirListing) {
+        self.dirs.insert(dir, listing);
+    }
+
+    /// Whether listings may be reused for a scan of `root` with `settings`
+    pub(crate) fn matches(&self, root: &Path, settings: &str) -> bool {
+        self.root == root && self.settings == settings
+    }
+
+    /// Listing of `dir` if its modification time is still `modified`
+    pub(crate) fn unchanged_listing(
+        &self,
+        dir: &Path,
+        modified: SystemTime,
+    ) -> Option<&DirListing> {
+        let listing = self.dirs.get(dir)?;
+        let settled = listing
+            .modified
+            .checked_add(RACY_WINDOW)
+            .is_some_and(|t| t <= self.taken);
+        (listing.modified == modified && settled).then_some(listing)
+    }
+
+    /// Where the snapshot of `root` is kept inside `cache_dir`
+    ///
+    /// The file name is derived from the canonical form of `root`, so every
+    /// scanned tree gets its own snapshot.
+    pub fn path_in(cache_dir: &Path, root: &Path) -> PathBuf {
+        let root = fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf());
+        let name = blake3::hash(root.to_string_lossy().as_bytes()).to_hex();
+        cache_dir
+            .join("scan_snapshots")
+            .join(format!("{}.bin", &name[..32]))
+    }
+
+    /// Load a snapshot saved with [`save`](Self::save)
+    ///
+    /// Returns `None` when the file is missing, unreadable or written by
+    /// another format version.
+    pub fn load(path: &Path) -> Option<Self> {
+        let data = fs::read(path).ok()?;
+        let Some(rest) = data.strip_prefix(SNAPSHOT_MAGIC) else {
+            warn!("Ignoring unrecognized scan snapshot {}", path.display());
+            return None;
+        };
+        let version = u32::from_le_bytes(rest.get(..4)?.try_into().ok()?);
+        if version != SNAPSHOT_FORMAT_VERSION {
+            debug!(
+                "Ignoring scan snapshot format v{} (this build uses v{})",
+                version, SNAPSHOT_FORMAT_VERSION
+            );
+            return None;
+        }
+        match bincode::deserialize(&rest[4..]) {
+            Ok(snapshot) => Some(snapshot),
+            Err(e) => {
+                warn!("Discarding unreadable scan snapshot: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Write the snapshot to `path` atomically, creating parent directories
+    pub fn save(&self, path: &Path) -> Result<(), RCompareError> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut data = SNAPSHOT_MAGIC.to_vec();
+        data.extend_from_slice(&SNAPSHOT_FORMAT_VERSION.to_le_bytes());
+        bincode::serialize_into(&mut data, self)
+            .map_err(|e| RCompareError::Serialization(e.to_string()))?;
+
+        let temp = path.with_extension("tmp");
+        fs::write(&temp, data)?;
+        fs::rename(&temp, path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rcompare_common::FileType;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_save_and_load_round_trip() {
+        let temp = TempDir::new().unwrap();
+        let taken = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(500);
+        let mut snapshot = ScanSnapshot::new(Path::new("/data"), "settings".to_string(), taken);
+        snapshot.insert(
+            PathBuf::new(),
+            DirListing {
+                modified,
+                children: vec![ListedChild {
+                    entry: FileEntry {
+                        path: PathBuf::from("a.txt"),
+                        size: 3,
+                        modified,
+                        is_dir: false,
+                        file_type: FileType::Regular,
+                    },
+                    descend: false,
+                }],
+            },
+        );
+
+        let path = temp.path().join("snapshots/data.bin");
+        snapshot.save(&path).unwrap();
+        let loaded = ScanSnapshot::load(&path).unwrap();
+        assert!(loaded.matches(Path::new("/data"), "settings"));
+        assert!(!loaded.matches(Path::new("/data"), "other"));
+        assert_eq!(loaded.len(), 1);
+        assert!(loaded.unchanged_listing(Path::new(""), modified).is_some());
+        assert!(loaded
+            .unchanged_listing(Path::new(""), modified + Duration::from_secs(1))
+            .is_none());
+
+        fs::write(&path, b"garbage").unwrap();
+        assert!(ScanSnapshot::load(&path).is_none());
+        assert!(ScanSnapshot::load(&temp.path().join("missing.bin")).is_none());
+    }
+
+    #[test]
+    fn test_recently_modified_listing_is_not_reused() {
+        let taken = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+        let modified = taken - Duration::from_secs(1);
+        let mut snapshot = ScanSnapshot::new(Path::new("/data"), String::new(), taken);
+        snapshot.insert(
+            PathBuf::new(),
+            DirListing {
+                modified,
+                children: Vec::new(),
+            },
+        );
+        assert!(snapshot
+            .unchanged_listing(Path::new(""), modified)
+            .is_none());
+    }
+}
@@ -11,6 +11,9 @@
 //! - **VFS abstraction**: Can scan both filesystem and virtual file systems (archives, cloud storage)
 //! - **Cancellation**: Supports cancelling long-running scans
 //! - **Symlink handling**: Configurable symlink following behavior
+//! - **Directory signatures**: Per-directory metadata hashes for skipping identical subtrees
+//! - **Incremental rescans**: Reuse listings of directories unchanged since a previous scan
+//! - **Ignored entries**: Optionally report what the filters left out, and which rule did
 //!
 //! # Examples
 //!
@@ -42,12 +45,75 @@
 //! let entries = scanner.scan(Path::new("/project")).unwrap();
 //! ```
 
-use ignore::gitignore::{Gitignore, GitignoreBuilder};
-use jwalk::WalkDir;
-use rcompare_common::{AppConfig, FileEntry, RCompareError, Vfs};
-use std::path::Path;
+use crate::scan_snapshot::{DirListing, ListedChild, ScanSnapshot};
+use ignore::gitignore::{Gitignore, GitignoreBuilder, Glob};
+use ignore::Match;
+use jwalk::{Parallelism, WalkDir};
+use rcompare_common::{
+    compile_ignore_patterns, AppConfig, Blake3Hash, ErrorKind, FileEntry, FileType, RCompareError,
+    Vfs,
+};
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
-use tracing::debug;
+use std::time::SystemTime;
+use tracing::{debug, instrument, warn, Span};
+
+/// Whether adding, removing or renaming an entry updates its directory's
+/// modification time, which incremental scans rely on
+///
+/// True for Unix filesystems and NTFS; elsewhere every incremental scan
+/// reads the whole tree.
+const DIR_MTIME_TRACKS_CHILDREN: bool = cfg!(any(unix, windows));
+
+/// Result of [`FolderScanner::scan_incremental`]
+#[derive(Debug)]
+pub struct IncrementalScan {
+    /// The same entries [`FolderScanner::scan`] would return
+    pub entries: Vec<FileEntry>,
+    /// Listings to pass to the next incremental scan of the same root
+    pub snapshot: ScanSnapshot,
+    /// Directories (relative to the root, which is the empty path) whose
+    /// listing was read from disk rather than reused
+    pub rescanned_dirs: Vec<PathBuf>,
+    /// Number of metadata lookups made
+    pub stat_calls: usize,
+}
+
+/// Result of [`FolderScanner::scan_with_ignored`] and
+/// [`FolderScanner::scan_vfs_with_ignored`]
+#[derive(Debug, Default)]
+pub struct FilteredScan {
+    /// The same entries [`FolderScanner::scan`] would return
+    pub entries: Vec<FileEntry>,
+    /// Entries left out by a hidden or ignore rule; inside an ignored
+    /// directory only the directory itself is listed
+    pub ignored: Vec<IgnoredEntry>,
+}
+
+/// An entry a scan left out, with the rule that excluded it
+#[derive(Debug, Clone)]
+pub struct IgnoredEntry {
+    pub entry: FileEntry,
+    /// `hidden`, an ignore pattern such as `*.log`, or a gitignore line
+    /// prefixed by its file, such as `.gitignore: target/`
+    pub rule: String,
+}
+
+/// Entries a walk left out, with the ignored directories indexed so entries
+/// below them are skipped without scanning the list
+#[derive(Default)]
+struct IgnoredCollector {
+    entries: Vec<IgnoredEntry>,
+    dirs: HashSet<PathBuf>,
+}
+
+/// Why an entry was left out of a scan
+enum Exclusion<'a> {
+    Hidden,
+    Pattern(&'a Glob),
+    Gitignore(&'a Glob),
+}
 
 /// Parallel folder scanner using jwalk with gitignore and custom pattern support.
 ///
@@ -75,15 +141,65 @@ pub struct FolderScanner {
     config: AppConfig,
     gitignore: Option<Gitignore>,
     custom_ignore: Option<Gitignore>,
+    skip_unreadable: bool,
+    /// Walker threads, from [`AppConfig::threads`] unless overridden;
+    /// `None` walks on the global rayon pool
+    threads: Option<usize>,
 }
 
 impl FolderScanner {
+    /// Create a scanner, skipping (with a warning) any invalid ignore pattern
+    ///
+    /// Use [`try_new`](Self::try_new) to reject invalid patterns instead.
     pub fn new(config: AppConfig) -> Self {
         let custom_ignore = Self::build_custom_ignore(&config);
         Self {
+            threads: config.threads.map(|threads| threads.max(1)),
+            config,
+            gitignore: None,
+            custom_ignore,
+            skip_unreadable: true,
+        }
+    }
+
+    /// Create a scanner, failing if an ignore pattern is not a valid glob
+    ///
+    /// The patterns are compiled once here and the matcher is reused for
+    /// every entry of every scan.
+    pub fn try_new(config: AppConfig) -> Result<Self, RCompareError> {
+        let custom_ignore = compile_ignore_patterns(&config.ignore_patterns)?;
+        Ok(Self {
+            threads: config.threads.map(|threads| threads.max(1)),
             config,
             gitignore: None,
             custom_ignore,
+            skip_unreadable: true,
+        })
+    }
+
+    /// Skip subdirectories that can't be read for lack of permission (on by
+    /// default)
+    ///
+    /// Each skipped directory is logged as a warning and left out of the
+    /// scan together with its contents. With `false`, such a directory fails
+    /// the scan with an [`ErrorKind::PermissionDenied`] error instead. An
+    /// unreadable root always fails the scan.
+    pub fn with_skip_unreadable(mut self, skip: bool) -> Self {
+        self.skip_unreadable = skip;
+        self
+    }
+
+    /// Walk directories with `threads` threads instead of the global rayon
+    /// pool; 1 walks serially on the calling thread
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = Some(threads.max(1));
+        self
+    }
+
+    fn parallelism(&self) -> Option<Parallelism> {
+        match self.threads? {
+            1 => Some(Parallelism::Serial),
+            threads => Some(Parallelism::RayonNewPool(threads)),
         }
     }
 
@@ -96,7 +212,7 @@ impl FolderScanner {
         let mut builder = GitignoreBuilder::new("");
         for pattern in &config.ignore_patterns {
             if let Err(err) = builder.add_line(None, pattern) {
-                debug!("Failed to add ignore pattern '{}': {}", pattern, err);
+                warn!("Skipping invalid ignore pattern '{}': {}", pattern, err);
             } else {
                 debug!("Added custom ignore pattern: {}", pattern);
             }
@@ -118,12 +234,16 @@ impl FolderScanner {
     }
 
     /// Load .gitignore patterns from a directory (including nested .gitignore files)
+    ///
+    /// The `.gitignore` files are found even when hidden entries are excluded
+    /// from the scan itself.
     pub fn load_gitignore(&mut self, root: &Path) -> Result<(), RCompareError> {
         let mut builder = GitignoreBuilder::new(root);
         let mut found_any = false;
 
         // Recursively find all .gitignore files in the directory tree
-        for entry in WalkDir::new(root).into_iter().flatten() {
+        // (jwalk skips dotfiles by default, which would hide .gitignore itself)
+        for entry in WalkDir::new(root).skip_hidden(false).into_iter().flatten() {
             let path = entry.path();
             if path.file_name() == Some(std::ffi::OsStr::new(".gitignore")) {
                 if let Some(e) = builder.add(&path) {
@@ -152,25 +272,60 @@ impl FolderScanner {
     }
 
     /// Scan a directory and return all files and subdirectories, with cancellation
+    #[instrument(
+        name = "scan",
+        skip_all,
+        fields(root = %root.display(), entries = tracing::field::Empty)
+    )]
     pub fn scan_with_cancel(
         &self,
         root: &Path,
         cancel: Option<&AtomicBool>,
+    ) -> Result<Vec<FileEntry>, RCompareError> {
+        self.walk(root, cancel, None)
+    }
+
+    /// Scan a directory, also returning the entries the filters left out
+    ///
+    /// Each ignored entry carries the rule that matched it. Recording them
+    /// costs a string per excluded subtree, so [`scan`](Self::scan) drops
+    /// them instead.
+    pub fn scan_with_ignored(
+        &self,
+        root: &Path,
+        cancel: Option<&AtomicBool>,
+    ) -> Result<FilteredScan, RCompareError> {
+        let mut ignored = IgnoredCollector::default();
+        let entries = self.walk(root, cancel, Some(&mut ignored))?;
+        Ok(FilteredScan {
+            entries,
+            ignored: ignored.entries,
+        })
+    }
+
+    fn walk(
+        &self,
+        root: &Path,
+        cancel: Option<&AtomicBool>,
+        mut ignored: Option<&mut IgnoredCollector>,
     ) -> Result<Vec<FileEntry>, RCompareError> {
         let mut entries = Vec::new();
+        // Directories excluded by the platform hidden attribute
+        let mut hidden_dirs: Vec<PathBuf> = Vec::new();
 
-        let walker = WalkDir::new(root)
+        let mut walker = WalkDir::new(root)
             .follow_links(self.config.follow_symlinks)
             .skip_hidden(false);
+        if let Some(parallelism) = self.parallelism() {
+            walker = walker.parallelism(parallelism);
+        }
 
         for entry in walker {
             if cancel.is_some_and(|flag| flag.load(Ordering::Relaxed)) {
                 return Err(RCompareError::Comparison("Scan cancelled".to_string()));
             }
 
-            let entry = entry.map_err(|e| {
-                RCompareError::Io(std::io::Error::other(format!("Walk error: {}", e)))
-            })?;
+            let entry = entry.map_err(|e| walk_error("Walk error", &e))?;
 
             let path = entry.path();
             let relative_path = path
@@ -180,12 +335,15 @@ impl FolderScanner {
 
             // Skip the synthetic root entry (empty path)
             if relative_path.as_os_str().is_empty() {
+                if let Some(e) = &entry.read_children_error {
+                    return Err(walk_error("Read error", e));
+                }
                 continue;
             }
 
-            let metadata = entry.metadata().map_err(|e| {
-                RCompareError::Io(std::io::Error::other(format!("Metadata error: {}", e)))
-            })?;
+            let metadata = entry
+                .metadata()
+                .map_err(|e| walk_error("Metadata error", &e))?;
 
             // For symlinks, follow them to determine if they point to a directory
             // (jwalk's metadata returns false for is_dir on symlinks when follow_links is false)
@@ -196,38 +354,262 @@ impl FolderScanner {
                 metadata.is_dir()
             };
 
-            // Skip if matches ignore patterns (check full path and all parent directories)
-            if self.should_ignore_with_parents(&relative_path, is_dir) {
-                continue;
-            }
-
-            // Skip if matches gitignore (check full path and all parent directories)
-            if let Some(ref gitignore) = self.gitignore {
-                if self.gitignore_matches_with_parents(gitignore, &relative_path, is_dir) {
-                    continue;
+            let exclusion = if self.config.include_hidden {
+                None
+            } else if is_dot_hidden(&relative_path)
+                || hidden_dirs.iter().any(|dir| relative_path.starts_with(dir))
+            {
+                Some(Exclusion::Hidden)
+            } else if has_hidden_attribute(&metadata) {
+                if is_dir {
+                    hidden_dirs.push(relative_path.clone());
                 }
-            }
+                Some(Exclusion::Hidden)
+            } else {
+                None
+            };
+
+            // Ignore patterns and gitignore (check full path and all parent directories)
+            let exclusion = exclusion.or_else(|| self.rule_exclusion(&relative_path, is_dir));
 
-            entries.push(FileEntry {
+            let file_entry = FileEntry {
                 path: relative_path,
                 size: metadata.len(),
                 modified: metadata
                     .modified()
                     .unwrap_or(std::time::SystemTime::UNIX_EPOCH),
                 is_dir,
-            });
+                file_type: FileType::from_std(metadata.file_type()),
+            };
+
+            if let Some(exclusion) = exclusion {
+                if let Some(ignored) = ignored.as_deref_mut() {
+                    self.record_ignored(ignored, file_entry, exclusion);
+                }
+                continue;
+            }
+
+            entries.push(file_entry);
+
+            // jwalk keeps a failure to list a directory on its entry
+            if let Some(e) = &entry.read_children_error {
+                let error = walk_error("Read error", e);
+                if !self.skips(&error) {
+                    return Err(error);
+                }
+                warn!("Skipping unreadable {:?}: {}", entry.path(), e);
+            }
         }
 
         debug!("Scanned {} entries from {:?}", entries.len(), root);
+        Span::current().record("entries", entries.len());
         Ok(entries)
     }
 
+    /// Scan a directory, reusing listings from a previous scan where possible
+    ///
+    /// Every directory is still stat-ed, but one whose modification time is
+    /// unchanged since `previous` was taken keeps its previous listing, so
+    /// it is not read or filtered again. Only directories where entries
+    /// were added, removed or renamed are read from disk.
+    ///
+    /// A file rewritten in place does not change its directory's
+    /// modification time, so the files of a reused listing are stat-ed
+    /// again for their current size and timestamp. `previous` is ignored if
+    /// it was taken of another root or with other ignore settings. With
+    /// `follow_symlinks` set, or on platforms where directory times don't
+    /// track their entries, this is a full scan and the returned snapshot
+    /// is empty.
+    #[instrument(
+        name = "scan_incremental",
+        skip_all,
+        fields(
+            root = %root.display(),
+            entries = tracing::field::Empty,
+            rescanned = tracing::field::Empty
+        )
+    )]
+    pub fn scan_incremental(
+        &self,
+        root: &Path,
+        previous: Option<&ScanSnapshot>,
+    ) -> Result<IncrementalScan, RCompareError> {
+        let settings = self.settings_fingerprint();
+        let mut scan = IncrementalScan {
+            entries: Vec::new(),
+            snapshot: ScanSnapshot::new(root, settings.clone(), SystemTime::now()),
+            rescanned_dirs: Vec::new(),
+            stat_calls: 0,
+        };
+
+        if !DIR_MTIME_TRACKS_CHILDREN || self.config.follow_symlinks {
+            debug!("Incremental scan unavailable, scanning {:?} in full", root);
+            scan.entries = self.scan(root)?;
+            scan.stat_calls = scan.entries.len();
+            scan.rescanned_dirs = std::iter::once(PathBuf::new())
+                .chain(
+                    scan.entries
+                        .iter()
+                        .filter(|e| e.is_dir)
+                        .map(|e| e.path.clone()),
+                )
+                .collect();
+        } else {
+            let previous = previous.filter(|p| p.matches(root, &settings));
+            self.walk_incremental(root, PathBuf::new(), previous, &mut scan)?;
+        }
+
+        debug!(
+            "Incremental scan of {:?}: {} entries, {} of {} directories read",
+            root,
+            scan.entries.len(),
+            scan.rescanned_dirs.len(),
+            scan.snapshot.len()
+        );
+        Span::current().record("entries", scan.entries.len());
+        Span::current().record("rescanned", scan.rescanned_dirs.len());
+        Ok(scan)
+    }
+
+    /// Walk `dir` for an incremental scan, returning its modification time
+    fn walk_incremental(
+        &self,
+        root: &Path,
+        dir: PathBuf,
+        previous: Option<&ScanSnapshot>,
+        scan: &mut IncrementalScan,
+    ) -> Result<Option<SystemTime>, RCompareError> {
+        scan.stat_calls += 1;
+        let modified = std::fs::metadata(root.join(&dir))?.modified().ok();
+
+        let reused = previous
+            .zip(modified)
+            .and_then(|(previous, modified)| previous.unchanged_listing(&dir, modified))
+            .and_then(|listing| restat_files(root, &listing.children, &mut scan.stat_calls));
+        let children = match reused {
+            Some(children) => children,
+            None => {
+                scan.rescanned_dirs.push(dir.clone());
+                match self.list_dir(root, &dir, &mut scan.stat_calls) {
+                    Ok(children) => children,
+                    // Not recorded, so the directory is tried again next time
+                    Err(e) if !dir.as_os_str().is_empty() && self.skips(&e) => {
+                        warn!("Skipping unreadable {:?}: {}", root.join(&dir), e);
+                        return Ok(modified);
+                    }
+                    Err(e) => return Err(e),
+                }
+            }
+        };
+        if let Some(modified) = modified {
+            scan.snapshot.insert(
+                dir,
+                DirListing {
+                    modified,
+                    children: children.clone(),
+                },
+            );
+        }
+
+        for child in children {
+            let index = scan.entries.len();
+            let path = child.entry.path.clone();
+            scan.entries.push(child.entry);
+            if child.descend {
+                // A reused listing holds the subdirectory's old timestamp
+                if let Some(modified) = self.walk_incremental(root, path, previous, scan)? {
+                    scan.entries[index].modified = modified;
+                }
+            }
+        }
+        Ok(modified)
+    }
+
+    /// Read the entries of one directory that pass hidden and ignore filtering
+    fn list_dir(
+        &self,
+        root: &Path,
+        dir: &Path,
+        stat_calls: &mut usize,
+    ) -> Result<Vec<ListedChild>, RCompareError> {
+        let mut dir_entries = std::fs::read_dir(root.join(dir))?.collect::<Result<Vec<_>, _>>()?;
+        dir_entries.sort_by_key(|entry| entry.file_name());
+
+        let mut children = Vec::new();
+        for dir_entry in dir_entries {
+            let relative_path = dir.join(dir_entry.file_name());
+            *stat_calls += 1;
+            let metadata = dir_entry.metadata()?;
+            // Symlinks to directories are reported as directories, as in
+            // `scan`, but not descended into
+            let descend = metadata.is_dir();
+            let is_dir = if metadata.file_type().is_symlink() {
+                *stat_calls += 1;
+                std::fs::metadata(dir_entry.path())
+                    .map(|m| m.is_dir())
+                    .unwrap_or(false)
+            } else {
+                descend
+            };
+
+            // Skipping a directory skips its subtree: hidden and ignore
+            // rules also match every path below an excluded directory
+            if !self.config.include_hidden
+                && (is_dot_hidden(&relative_path) || has_hidden_attribute(&metadata))
+            {
+                continue;
+            }
+            if self.rule_exclusion(&relative_path, is_dir).is_some() {
+                continue;
+            }
+
+            children.push(ListedChild {
+                entry: FileEntry {
+                    path: relative_path,
+                    size: metadata.len(),
+                    modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                    is_dir,
+                    file_type: FileType::from_std(metadata.file_type()),
+                },
+                descend,
+            });
+        }
+        Ok(children)
+    }
+
+    /// Whether `error` leaves a directory out of the scan instead of failing it
+    fn skips(&self, error: &RCompareError) -> bool {
+        self.skip_unreadable && error.kind() == ErrorKind::PermissionDenied
+    }
+
+    /// Fingerprint of the settings that decide which entries a scan keeps
+    fn settings_fingerprint(&self) -> String {
+        let gitignore = self
+            .gitignore
+            .as_ref()
+            .map(|g| (g.path().to_path_buf(), g.num_ignores(), g.num_whitelists()));
+        let key = format!(
+            "{:?}",
+            (
+                &self.config.ignore_patterns,
+                self.config.include_hidden,
+                gitignore
+            )
+        );
+        blake3::hash(key.as_bytes()).to_hex().to_string()
+    }
+
     /// Scan a VFS and return all files and subdirectories
     pub fn scan_vfs(&self, vfs: &dyn Vfs, root: &Path) -> Result<Vec<FileEntry>, RCompareError> {
         self.scan_vfs_with_cancel(vfs, root, None)
     }
 
     /// Scan a VFS and return all files and subdirectories, with cancellation
+    #[instrument(
+        name = "scan_vfs",
+        skip_all,
+        fields(root = %root.display(), entries = tracing::field::Empty)
+    )]
     pub fn scan_vfs_with_cancel(
         &self,
         vfs: &dyn Vfs,
@@ -235,16 +617,108 @@ impl FolderScanner {
         cancel: Option<&AtomicBool>,
     ) -> Result<Vec<FileEntry>, RCompareError> {
         let mut entries = Vec::new();
-        self.scan_vfs_recursive(vfs, root, root, &mut entries, cancel)?;
+        self.scan_vfs_recursive(vfs, root, root, &mut entries, None, cancel)?;
+        Span::current().record("entries", entries.len());
         Ok(entries)
     }
 
+    /// Scan a VFS, also returning the entries the filters left out
+    ///
+    /// See [`scan_with_ignored`](Self::scan_with_ignored).
+    pub fn scan_vfs_with_ignored(
+        &self,
+        vfs: &dyn Vfs,
+        root: &Path,
+        cancel: Option<&AtomicBool>,
+    ) -> Result<FilteredScan, RCompareError> {
+        let mut entries = Vec::new();
+        let mut ignored = IgnoredCollector::default();
+        self.scan_vfs_recursive(vfs, root, root, &mut entries, Some(&mut ignored), cancel)?;
+        Ok(FilteredScan {
+            entries,
+            ignored: ignored.entries,
+        })
+    }
+
+    /// Compute a content signature for every directory in a scan result
+    ///
+    /// A directory's signature hashes the sorted `(name, size, child-hash)`
+    /// tuples of its direct children, where a file contributes its size and
+    /// modification time and a subdirectory contributes its own signature.
+    /// Two directories with equal signatures therefore hold the same names,
+    /// sizes and timestamps all the way down. The scan root is keyed by the
+    /// empty path; directories only implied by a child's path are included.
+    pub fn directory_signatures(entries: &[FileEntry]) -> HashMap<PathBuf, Blake3Hash> {
+        // Non-root nodes: path -> file metadata (None for directories)
+        let mut nodes: HashMap<&Path, Option<(u64, u128)>> = HashMap::new();
+        for entry in entries {
+            let meta = (!entry.is_dir).then(|| {
+                let nanos = entry
+                    .modified
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|d| d.as_nanos())
+                    .unwrap_or(0);
+                (entry.size, nanos)
+            });
+            nodes.insert(entry.path.as_path(), meta);
+
+            let mut ancestor = entry.path.parent();
+            while let Some(dir) = ancestor.filter(|p| !p.as_os_str().is_empty()) {
+                nodes.entry(dir).or_insert(None);
+                ancestor = dir.parent();
+            }
+        }
+
+        let mut children: HashMap<&Path, Vec<&Path>> = HashMap::new();
+        children.insert(Path::new(""), Vec::new());
+        for (&path, meta) in &nodes {
+            if meta.is_none() {
+                children.entry(path).or_default();
+            }
+            if let Some(parent) = path.parent() {
+                children.entry(parent).or_default().push(path);
+            }
+        }
+
+        // Deepest directories first so child signatures are always ready
+        let mut dirs: Vec<&Path> = children.keys().copied().collect();
+        dirs.sort_by_key(|dir| std::cmp::Reverse(dir.components().count()));
+
+        let mut signatures: HashMap<PathBuf, Blake3Hash> = HashMap::new();
+        for dir in dirs {
+            let mut kids = children.remove(dir).unwrap_or_default();
+            kids.sort_by(|a, b| a.file_name().cmp(&b.file_name()));
+
+            let mut hasher = blake3::Hasher::new();
+            for kid in kids {
+                let name = kid.file_name().map_or(&[][..], |n| n.as_encoded_bytes());
+                hasher.update(&(name.len() as u64).to_le_bytes());
+                hasher.update(name);
+                match nodes[kid] {
+                    Some((size, modified)) => {
+                        hasher.update(b"f");
+                        hasher.update(&size.to_le_bytes());
+                        hasher.update(&modified.to_le_bytes());
+                    }
+                    None => {
+                        hasher.update(b"d");
+                        hasher.update(signatures[kid].as_bytes());
+                    }
+                }
+            }
+            signatures.insert(dir.to_path_buf(), hasher.finalize().into());
+        }
+
+        signatures
+    }
+
     fn scan_vfs_recursive(
         &self,
         vfs: &dyn Vfs,
         root: &Path,
         current: &Path,
         entries: &mut Vec<FileEntry>,
+        mut ignored: Option<&mut IgnoredCollector>,
         cancel: Option<&AtomicBool>,
     ) -> Result<(), RCompareError> {
         if cancel.is_some_and(|flag| flag.load(Ordering::Relaxed)) {
@@ -253,7 +727,7 @@ impl FolderScanner {
 
         let dir_entries = vfs
             .read_dir(current)
-            .map_err(|e| RCompareError::Vfs(e.to_string()))?;
+            .map_err(|e| RCompareError::vfs(current.display().to_string(), e))?;
 
         for entry in dir_entries {
             if cancel.is_some_and(|flag| flag.load(Ordering::Relaxed)) {
@@ -271,74 +745,158 @@ impl FolderScanner {
                 continue;
             }
 
-            if self.should_ignore_with_parents(&relative_path, entry.is_dir) {
-                continue;
-            }
-
-            if let Some(ref gitignore) = self.gitignore {
-                if self.gitignore_matches_with_parents(gitignore, &relative_path, entry.is_dir) {
-                    continue;
-                }
-            }
+            // VFS backends have no hidden attribute; only the dot rule applies
+            let exclusion = if !self.config.include_hidden && is_dot_hidden(&relative_path) {
+                Some(Exclusion::Hidden)
+            } else {
+                self.rule_exclusion(&relative_path, entry.is_dir)
+            };
 
-            entries.push(FileEntry {
+            let file_entry = FileEntry {
                 path: relative_path,
                 size: entry.size,
                 modified: entry.modified,
                 is_dir: entry.is_dir,
-            });
+                file_type: entry.file_type,
+            };
+
+            if let Some(exclusion) = exclusion {
+                if let Some(ignored) = ignored.as_deref_mut() {
+                    self.record_ignored(ignored, file_entry, exclusion);
+                }
+                continue;
+            }
+
+            entries.push(file_entry);
 
             if entry.is_dir {
-                self.scan_vfs_recursive(vfs, root, &vfs_path, entries, cancel)?;
+                self.scan_vfs_recursive(
+                    vfs,
+                    root,
+                    &vfs_path,
+                    entries,
+                    ignored.as_deref_mut(),
+                    cancel,
+                )?;
             }
         }
 
         Ok(())
     }
 
-    /// Check if a path or any of its parent directories should be ignored
-    fn should_ignore_with_parents(&self, path: &Path, is_dir: bool) -> bool {
-        if let Some(ref custom_ignore) = self.custom_ignore {
-            // Check the path itself
-            if custom_ignore.matched(path, is_dir).is_ignore() {
-                return true;
-            }
-
-            // Check all parent directories
-            let mut current = path;
-            while let Some(parent) = current.parent() {
-                if !parent.as_os_str().is_empty() && custom_ignore.matched(parent, true).is_ignore()
-                {
-                    return true;
-                }
-                current = parent;
-            }
+    /// The ignore pattern or gitignore line excluding a path, checking the
+    /// path itself and all its parent directories
+    fn rule_exclusion(&self, path: &Path, is_dir: bool) -> Option<Exclusion<'_>> {
+        if let Some(glob) = self
+            .custom_ignore
+            .as_ref()
+            .and_then(|ignore| matched_with_parents(ignore, path, is_dir))
+        {
+            return Some(Exclusion::Pattern(glob));
         }
-        false
+        self.gitignore
+            .as_ref()
+            .and_then(|ignore| matched_with_parents(ignore, path, is_dir))
+            .map(Exclusion::Gitignore)
     }
 
-    /// Check if a path or any of its parent directories match gitignore
-    fn gitignore_matches_with_parents(
+    /// Record an excluded entry unless a directory above it already was
+    fn record_ignored(
         &self,
-        gitignore: &Gitignore,
-        path: &Path,
-        is_dir: bool,
-    ) -> bool {
-        // Check the path itself
-        if gitignore.matched(path, is_dir).is_ignore() {
-            return true;
+        ignored: &mut IgnoredCollector,
+        entry: FileEntry,
+        exclusion: Exclusion<'_>,
+    ) {
+        if entry
+            .path
+            .ancestors()
+            .skip(1)
+            .any(|dir| ignored.dirs.contains(dir))
+        {
+            return;
+        }
+        if entry.is_dir {
+            ignored.dirs.insert(entry.path.clone());
         }
+        let rule = match exclusion {
+            Exclusion::Hidden => "hidden".to_string(),
+            Exclusion::Pattern(glob) => glob.original().to_string(),
+            Exclusion::Gitignore(glob) => match glob.from() {
+                Some(from) => {
+                    let root = self.gitignore.as_ref().map_or(Path::new(""), |g| g.path());
+                    let from = from.strip_prefix(root).unwrap_or(from);
+                    format!("{}: {}", from.display(), glob.original())
+                }
+                None => glob.original().to_string(),
+            },
+        };
+        ignored.entries.push(IgnoredEntry { entry, rule });
+    }
+}
+
+/// The ignoring glob matching a path or any of its parent directories
+fn matched_with_parents<'a>(ignore: &'a Gitignore, path: &Path, is_dir: bool) -> Option<&'a Glob> {
+    // Check the path itself
+    if let Match::Ignore(glob) = ignore.matched(path, is_dir) {
+        return Some(glob);
+    }
 
-        // Check all parent directories
-        let mut current = path;
-        while let Some(parent) = current.parent() {
-            if !parent.as_os_str().is_empty() && gitignore.matched(parent, true).is_ignore() {
-                return true;
+    // Check all parent directories
+    let mut current = path;
+    while let Some(parent) = current.parent() {
+        if !parent.as_os_str().is_empty() {
+            if let Match::Ignore(glob) = ignore.matched(parent, true) {
+                return Some(glob);
             }
-            current = parent;
         }
-        false
+        current = parent;
     }
+    None
+}
+
+/// A reused listing with the current size and modification time of each
+/// file, or `None` if one of them is gone and the directory must be read
+fn restat_files(
+    root: &Path,
+    children: &[ListedChild],
+    stat_calls: &mut usize,
+) -> Option<Vec<ListedChild>> {
+    let mut children = children.to_vec();
+    // Subdirectories get their time from their own walk
+    for child in children.iter_mut().filter(|child| !child.descend) {
+        *stat_calls += 1;
+        let metadata = std::fs::symlink_metadata(root.join(&child.entry.path)).ok()?;
+        child.entry.size = metadata.len();
+        child.entry.modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+    }
+    Some(children)
+}
+
+/// Convert a walk error, keeping the kind of the underlying I/O error
+fn walk_error(context: &str, error: &jwalk::Error) -> RCompareError {
+    let kind = error
+        .io_error()
+        .map_or(std::io::ErrorKind::Other, |e| e.kind());
+    RCompareError::Io(std::io::Error::new(kind, format!("{}: {}", context, error)))
+}
+
+/// Whether any component of a relative path starts with a dot
+fn is_dot_hidden(relative_path: &Path) -> bool {
+    relative_path.components().any(|component| {
+        matches!(component, Component::Normal(name) if name.to_string_lossy().starts_with('.'))
+    })
+}
+
+#[cfg(windows)]
+fn has_hidden_attribute(metadata: &std::fs::Metadata) -> bool {
+    use std::os::windows::fs::MetadataExt;
+    const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+    metadata.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0
+}
+
+#[cfg(not(windows))]
+fn has_hidden_attribute(_metadata: &std::fs::Metadata) -> bool {
+    false
 }
 
 #[cfg(test)]
@@ -377,6 +935,54 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_scanner_thread_counts_give_same_entries() {
+        let temp = TempDir::new().unwrap();
+        for dir in ["a", "a/b", "c"] {
+            fs::create_dir(temp.path().join(dir)).unwrap();
+            for i in 0..5 {
+                fs::write(temp.path().join(dir).join(format!("f{i}.txt")), b"x").unwrap();
+            }
+        }
+
+        let scan = |scanner: FolderScanner| {
+            let mut paths: Vec<PathBuf> = scanner
+                .scan(temp.path())
+                .unwrap()
+                .into_iter()
+                .map(|e| e.path)
+                .collect();
+            paths.sort();
+            paths
+        };
+
+        let default = FolderScanner::new(AppConfig::default());
+        assert!(default.parallelism().is_none());
+        let expected = scan(default);
+        assert_eq!(expected.len(), 18);
+
+        let serial = FolderScanner::new(AppConfig::default()).with_threads(0);
+        assert!(matches!(serial.parallelism(), Some(Parallelism::Serial)));
+        assert_eq!(scan(serial), expected);
+
+        let pooled = FolderScanner::new(AppConfig::default()).with_threads(3);
+        assert!(matches!(
+            pooled.parallelism(),
+            Some(Parallelism::RayonNewPool(3))
+        ));
+        assert_eq!(scan(pooled), expected);
+
+        let configured = FolderScanner::new(AppConfig {
+            threads: Some(2),
+            ..Default::default()
+        });
+        assert!(matches!(
+            configured.parallelism(),
+            Some(Parallelism::RayonNewPool(2))
+        ));
+        assert_eq!(scan(configured), expected);
+    }
+
     #[test]
     fn test_scanner_ignore_patterns() {
         let temp = TempDir::new().unwrap();
@@ -396,6 +1002,25 @@ mod tests {
             .all(|e| !e.path.to_string_lossy().ends_with(".o")));
     }
 
+    #[test]
+    fn test_try_new_rejects_invalid_pattern() {
+        let config = AppConfig {
+            ignore_patterns: vec!["*.o".to_string(), "{src,lib".to_string()],
+            ..Default::default()
+        };
+
+        let err = FolderScanner::try_new(config.clone()).err().unwrap();
+        assert!(matches!(err, RCompareError::Config(ref msg) if msg.contains("'{src,lib'")));
+
+        // The lenient constructor keeps the valid patterns
+        let temp = TempDir::new().unwrap();
+        fs::write(temp.path().join("keep.txt"), b"test").unwrap();
+        fs::write(temp.path().join("drop.o"), b"test").unwrap();
+        let entries = FolderScanner::new(config).scan(temp.path()).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].path, PathBuf::from("keep.txt"));
+    }
+
     #[test]
     fn test_scanner_gitignore_style_patterns() {
         let temp = TempDir::new().unwrap();
@@ -552,4 +1177,349 @@ mod tests {
             entries.len()
         );
     }
+
+    #[test]
+    fn test_scanner_include_hidden_toggle() {
+        let temp = TempDir::new().unwrap();
+        fs::write(temp.path().join(".env"), b"SECRET=1").unwrap();
+        fs::create_dir(temp.path().join(".git")).unwrap();
+        fs::write(temp.path().join(".git/HEAD"), b"ref").unwrap();
+        fs::create_dir(temp.path().join("src")).unwrap();
+        fs::write(temp.path().join("src/main.rs"), b"fn main() {}").unwrap();
+
+        let sorted_paths = |entries: Vec<FileEntry>| {
+            let mut paths: Vec<PathBuf> = entries.into_iter().map(|e| e.path).collect();
+            paths.sort();
+            paths
+        };
+
+        let all = FolderScanner::new(AppConfig::default())
+            .scan(temp.path())
+            .unwrap();
+        assert_eq!(
+            sorted_paths(all),
+            [".env", ".git", ".git/HEAD", "src", "src/main.rs"].map(PathBuf::from)
+        );
+
+        let scanner = FolderScanner::new(AppConfig {
+            include_hidden: false,
+            ..Default::default()
+        });
+        let visible = [PathBuf::from("src"), PathBuf::from("src/main.rs")];
+        assert_eq!(sorted_paths(scanner.scan(temp.path()).unwrap()), visible);
+
+        // VFS scans apply the same rule
+        let vfs = crate::vfs::LocalVfs::new(temp.path().to_path_buf());
+        let vfs_entries = scanner.scan_vfs(&vfs, Path::new("")).unwrap();
+        assert_eq!(sorted_paths(vfs_entries), visible);
+    }
+
+    #[test]
+    fn test_scan_with_ignored_records_matching_rule() {
+        let temp = TempDir::new().unwrap();
+        fs::write(temp.path().join("keep.txt"), b"keep").unwrap();
+        fs::write(temp.path().join("debug.log"), b"log").unwrap();
+        fs::write(temp.path().join(".env"), b"SECRET=1").unwrap();
+        fs::create_dir_all(temp.path().join("target/release")).unwrap();
+        fs::write(temp.path().join("target/release/app"), b"bin").unwrap();
+        fs::create_dir(temp.path().join("build")).unwrap();
+        fs::write(temp.path().join("build/out.o"), b"obj").unwrap();
+        fs::write(temp.path().join(".gitignore"), b"build/\n").unwrap();
+
+        let mut scanner = FolderScanner::new(AppConfig {
+            ignore_patterns: vec!["*.log".to_string(), "target/".to_string()],
+            include_hidden: false,
+            ..Default::default()
+        });
+        scanner.load_gitignore(temp.path()).unwrap();
+
+        let rules = |scan: FilteredScan| {
+            let mut ignored: Vec<(PathBuf, String)> = scan
+                .ignored
+                .into_iter()
+                .map(|i| (i.entry.path, i.rule))
+                .collect();
+            ignored.sort();
+            (sorted_paths(&scan.entries), ignored)
+        };
+        // Only the topmost excluded entry of a subtree is listed
+        let expected = (
+            vec![PathBuf::from("keep.txt")],
+            vec![
+                (PathBuf::from(".env"), "hidden".to_string()),
+                (PathBuf::from(".gitignore"), "hidden".to_string()),
+                (PathBuf::from("build"), ".gitignore: build/".to_string()),
+                (PathBuf::from("debug.log"), "*.log".to_string()),
+                (PathBuf::from("target"), "target/".to_string()),
+            ],
+        );
+
+        let scan = scanner.scan_with_ignored(temp.path(), None).unwrap();
+        assert_eq!(
+            sorted_paths(&scan.entries),
+            sorted_paths(&scanner.scan(temp.path()).unwrap())
+        );
+        assert_eq!(rules(scan), expected);
+
+        let vfs = crate::vfs::LocalVfs::new(temp.path().to_path_buf());
+        let scan = scanner
+            .scan_vfs_with_ignored(&vfs, Path::new(""), None)
+            .unwrap();
+        assert_eq!(rules(scan), expected);
+    }
+
+    #[test]
+    fn test_load_gitignore_reads_root_and_nested_files() {
+        let temp = TempDir::new().unwrap();
+        fs::create_dir_all(temp.path().join("sub/local")).unwrap();
+        fs::write(temp.path().join(".gitignore"), b"*.tmp\n").unwrap();
+        fs::write(temp.path().join("sub/.gitignore"), b"local/\n").unwrap();
+        fs::write(temp.path().join("keep.txt"), b"keep").unwrap();
+        fs::write(temp.path().join("scratch.tmp"), b"tmp").unwrap();
+        fs::write(temp.path().join("sub/kept.rs"), b"rs").unwrap();
+        fs::write(temp.path().join("sub/local/cache.bin"), b"bin").unwrap();
+
+        // Hidden entries stay excluded from the scan, but the .gitignore
+        // files themselves must still be found
+        let mut scanner = FolderScanner::new(AppConfig {
+            include_hidden: false,
+            ..Default::default()
+        });
+        scanner.load_gitignore(temp.path()).unwrap();
+
+        let entries = scanner.scan(temp.path()).unwrap();
+        assert_eq!(
+            sorted_paths(&entries),
+            vec![
+                PathBuf::from("keep.txt"),
+                PathBuf::from("sub"),
+                PathBuf::from("sub/kept.rs"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_directory_signatures_are_deterministic() {
+        let t0 = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_000);
+        let entry = |path: &str, size: u64, is_dir: bool| FileEntry {
+            path: PathBuf::from(path),
+            size,
+            modified: t0,
+            is_dir,
+            file_type: FileType::from_is_dir(is_dir),
+        };
+
+        let entries = vec![
+            entry("src", 0, true),
+            entry("src/a.rs", 10, false),
+            entry("src/b.rs", 20, false),
+            entry("empty", 0, true),
+            entry("implied/c.txt", 5, false),
+        ];
+        let mut shuffled = entries.clone();
+        shuffled.reverse();
+
+        let signatures = FolderScanner::directory_signatures(&entries);
+        assert_eq!(signatures, FolderScanner::directory_signatures(&shuffled));
+        for dir in ["", "src", "empty", "implied"] {
+            assert!(signatures.contains_key(Path::new(dir)), "missing {dir:?}");
+        }
+
+        // Empty directories hash alike; a size change propagates to the root
+        let other = FolderScanner::directory_signatures(&[entry("x", 0, true)]);
+        assert_eq!(signatures[Path::new("empty")], other[Path::new("x")]);
+
+        let mut changed = entries.clone();
+        changed[2].size = 21;
+        let changed = FolderScanner::directory_signatures(&changed);
+        assert_ne!(signatures[Path::new("src")], changed[Path::new("src")]);
+        assert_ne!(signatures[Path::new("")], changed[Path::new("")]);
+        assert_eq!(
+            signatures[Path::new("implied")],
+            changed[Path::new("implied")]
+        );
+    }
+
+    /// Build `dirs` directories of `files` files each, with every directory
+    /// dated an hour back so their listings count as settled
+    fn write_settled_tree(root: &Path, dirs: usize, files: usize) {
+        for d in 0..dirs {
+            let dir = root.join(format!("dir{d}/sub"));
+            fs::create_dir_all(&dir).unwrap();
+            for f in 0..files {
+                fs::write(dir.join(format!("file{f}.txt")), b"content").unwrap();
+            }
+        }
+        settle_dirs(root);
+    }
+
+    fn settle_dirs(root: &Path) {
+        let past = filetime::FileTime::from_system_time(
+            std::time::SystemTime::now() - std::time::Duration::from_secs(3600),
+        );
+        for entry in WalkDir::new(root) {
+            let entry = entry.unwrap();
+            if entry.file_type().is_dir() {
+                filetime::set_file_mtime(entry.path(), past).unwrap();
+            }
+        }
+    }
+
+    fn sorted_paths(entries: &[FileEntry]) -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = entries.iter().map(|e| e.path.clone()).collect();
+        paths.sort();
+        paths
+    }
+
+    #[test]
+    fn test_incremental_scan_rescans_only_changed_directory() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        write_settled_tree(root, 20, 10);
+        let scanner = FolderScanner::new(AppConfig::default());
+
+        let first = scanner.scan_incremental(root, None).unwrap();
+        assert_eq!(
+            sorted_paths(&first.entries),
+            sorted_paths(&scanner.scan(root).unwrap())
+        );
+        // Root plus dirN and dirN/sub for each of the 20 trees
+        assert_eq!(first.rescanned_dirs.len(), 41);
+
+        // Nothing changed: every directory and file is stat-ed once, and
+        // no directory is read
+        let unchanged = scanner
+            .scan_incremental(root, Some(&first.snapshot))
+            .unwrap();
+        assert!(unchanged.rescanned_dirs.is_empty());
+        assert_eq!(unchanged.stat_calls, 241);
+        assert!(unchanged.stat_calls < first.stat_calls);
+        assert_eq!(
+            sorted_paths(&unchanged.entries),
+            sorted_paths(&first.entries)
+        );
+
+        fs::write(root.join("dir7/sub/new.txt"), b"new").unwrap();
+        let touched = scanner
+            .scan_incremental(root, Some(&unchanged.snapshot))
+            .unwrap();
+        assert_eq!(touched.rescanned_dirs, vec![PathBuf::from("dir7/sub")]);
+        // 41 directories, 190 reused files and the 11 entries now in dir7/sub
+        assert_eq!(touched.stat_calls, 242);
+        assert!(touched.stat_calls < first.stat_calls);
+        assert_eq!(
+            sorted_paths(&touched.entries),
+            sorted_paths(&scanner.scan(root).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_incremental_scan_sees_files_rewritten_in_place() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        write_settled_tree(root, 1, 2);
+        let scanner = FolderScanner::new(AppConfig::default());
+        let first = scanner.scan_incremental(root, None).unwrap();
+
+        let file = root.join("dir0/sub/file0.txt");
+        fs::write(&file, b"rewritten content").unwrap();
+        let later = SystemTime::now() + std::time::Duration::from_secs(60);
+        filetime::set_file_mtime(&file, filetime::FileTime::from_system_time(later)).unwrap();
+
+        let second = scanner
+            .scan_incremental(root, Some(&first.snapshot))
+            .unwrap();
+        assert!(second.rescanned_dirs.is_empty());
+        let entry = second
+            .entries
+            .iter()
+            .find(|e| e.path == Path::new("dir0/sub/file0.txt"))
+            .unwrap();
+        assert_eq!(entry.size, 17);
+        assert_eq!(
+            entry.modified,
+            fs::metadata(&file).unwrap().modified().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_incremental_scan_ignores_snapshot_of_other_settings() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        write_settled_tree(root, 2, 2);
+        fs::write(root.join("dir0/skip.log"), b"log").unwrap();
+        settle_dirs(root);
+
+        let first = FolderScanner::new(AppConfig::default())
+            .scan_incremental(root, None)
+            .unwrap();
+        let ignoring = FolderScanner::new(AppConfig {
+            ignore_patterns: vec!["*.log".to_string()],
+            ..Default::default()
+        });
+        let second = ignoring
+            .scan_incremental(root, Some(&first.snapshot))
+            .unwrap();
+        assert_eq!(second.rescanned_dirs.len(), first.rescanned_dirs.len());
+        assert!(second
+            .entries
+            .iter()
+            .all(|e| e.path != Path::new("dir0/skip.log")));
+    }
+
+    #[test]
+    fn test_missing_root_is_not_found() {
+        let temp = TempDir::new().unwrap();
+        let scanner = FolderScanner::new(AppConfig::default());
+        let missing = temp.path().join("missing");
+
+        let error = scanner.scan(&missing).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::NotFound);
+        let error = scanner.scan_incremental(&missing, None).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::NotFound);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_unreadable_directory_is_permission_denied() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp = TempDir::new().unwrap();
+        fs::write(temp.path().join("a.txt"), b"a").unwrap();
+        let locked = temp.path().join("locked");
+        fs::create_dir(&locked).unwrap();
+        fs::write(locked.join("b.txt"), b"b").unwrap();
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o000)).unwrap();
+        if fs::read_dir(&locked).is_ok() {
+            // Running with privileges that bypass permission checks
+            fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+            return;
+        }
+
+        let scanner = FolderScanner::new(AppConfig::default()).with_skip_unreadable(false);
+        let strict = scanner.scan(temp.path()).map(|_| ());
+        let strict_incremental = scanner.scan_incremental(temp.path(), None).map(|_| ());
+        // Skipping is the default
+        let scanner = FolderScanner::new(AppConfig::default());
+        let skipped = scanner.scan(temp.path());
+        let skipped_incremental = scanner.scan_incremental(temp.path(), None);
+        fs::set_permissions(&locked, fs::Permissions::from_mode(0o755)).unwrap();
+
+        assert_eq!(strict.unwrap_err().kind(), ErrorKind::PermissionDenied);
+        assert_eq!(
+            strict_incremental.unwrap_err().kind(),
+            ErrorKind::PermissionDenied
+        );
+        let mut paths: Vec<_> = skipped.unwrap().into_iter().map(|e| e.path).collect();
+        paths.sort();
+        assert_eq!(paths, vec![PathBuf::from("a.txt"), PathBuf::from("locked")]);
+        let mut paths: Vec<_> = skipped_incremental
+            .unwrap()
+            .entries
+            .into_iter()
+            .map(|e| e.path)
+            .collect();
+        paths.sort();
+        assert_eq!(paths, vec![PathBuf::from("a.txt"), PathBuf::from("locked")]);
+    }
 }
@@ -0,0 +1,176 @@
+//! Content-defined chunk fingerprints for near-duplicate detection.
+//!
+//! A [`ChunkFingerprint`] splits data at positions chosen by a gear rolling
+//! hash, so an edit only changes the chunks around it and the boundaries
+//! elsewhere line up again. Comparing the chunk sets of two files gives a
+//! similarity estimate that survives insertions and deletions, unlike a
+//! fixed-block comparison.
+
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+/// Chunks never end before this many bytes
+const MIN_CHUNK: usize = 16;
+/// Chunks are cut here even without a hash boundary
+const MAX_CHUNK: usize = 1024;
+/// A boundary is declared when the top `BOUNDARY_BITS` bits of the rolling
+/// hash are zero, giving an average of `MIN_CHUNK + 2^BOUNDARY_BITS` bytes
+const BOUNDARY_BITS: u32 = 6;
+
+/// Pseudo-random byte table for the gear hash (splitmix64 sequence)
+const GEAR: [u64; 256] = {
+    let mut table = [0u64; 256];
+    let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+    let mut i = 0;
+    while i < 256 {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        table[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    table
+};
+
+/// Multiset of content-defined chunks of a file
+#[derive(Debug, Clone, Default)]
+pub struct ChunkFingerprint {
+    /// Chunk hash -> (occurrences, chunk length)
+    chunks: HashMap<u64, (u32, u32)>,
+    total_len: u64,
+}
+
+impl ChunkFingerprint {
+    pub fn from_bytes(data: &[u8]) -> Self {
+        let mut fingerprint = Self {
+            chunks: HashMap::new(),
+            total_len: data.len() as u64,
+        };
+
+        let mut start = 0;
+        let mut hash: u64 = 0;
+        for (i, &byte) in data.iter().enumerate() {
+            hash = (hash << 1).wrapping_add(GEAR[byte as usize]);
+            let len = i + 1 - start;
+            let boundary = len >= MIN_CHUNK && hash >> (64 - BOUNDARY_BITS) == 0;
+            if boundary || len >= MAX_CHUNK {
+                fingerprint.add_chunk(&data[start..=i]);
+                start = i + 1;
+                hash = 0;
+            }
+        }
+        if start < data.len() {
+            fingerprint.add_chunk(&data[start..]);
+        }
+
+        fingerprint
+    }
+
+    fn add_chunk(&mut self, chunk: &[u8]) {
+        let mut hasher = DefaultHasher::new();
+        chunk.hash(&mut hasher);
+        let entry = self
+            .chunks
+            .entry(hasher.finish())
+            .or_insert((0, chunk.len() as u32));
+        entry.0 += 1;
+    }
+
+    /// Number of bytes the fingerprint was built from
+    pub fn len(&self) -> u64 {
+        self.total_len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.total_len == 0
+    }
+
+    /// Estimated similarity in percent (0.0 - 100.0)
+    ///
+    /// This is the share of bytes covered by chunks found in both inputs,
+    /// relative to the combined size.
+    pub fn similarity(&self, other: &ChunkFingerprint) -> f64 {
+        let combined = self.total_len + other.total_len;
+        if combined == 0 {
+            return 100.0;
+        }
+
+        let shared: u64 = self
+            .chunks
+            .iter()
+            .filter_map(|(hash, &(count, len))| {
+                let (other_count, _) = other.chunks.get(hash)?;
+                Some(count.min(*other_count) as u64 * len as u64)
+            })
+            .sum();
+
+        (2 * shared) as f64 / combined as f64 * 100.0
+    }
+}
+
+/// Highest similarity two inputs of these sizes can reach, used to skip
+/// candidate pairs without reading them
+pub fn similarity_upper_bound(left_len: u64, right_len: u64) -> f64 {
+    let combined = left_len + right_len;
+    if combined == 0 {
+        return 100.0;
+    }
+    (2 * left_len.min(right_len)) as f64 / combined as f64 * 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_text(lines: usize) -> String {
+        (0..lines)
+            .map(|i| {
+                format!(
+                    "line {:03}: the quick brown fox jumps over {} dogs\n",
+                    i,
+                    i * 7
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_identical_and_empty_inputs() {
+        let text = sample_text(50);
+        let a = ChunkFingerprint::from_bytes(text.as_bytes());
+        let b = ChunkFingerprint::from_bytes(text.as_bytes());
+        assert_eq!(a.similarity(&b), 100.0);
+
+        let empty = ChunkFingerprint::from_bytes(b"");
+        assert_eq!(empty.similarity(&empty), 100.0);
+        assert_eq!(a.similarity(&empty), 0.0);
+    }
+
+    #[test]
+    fn test_insertion_keeps_later_chunks_aligned() {
+        let text = sample_text(200);
+        let edited = format!("a new first line\n{}", text);
+
+        let a = ChunkFingerprint::from_bytes(text.as_bytes());
+        let b = ChunkFingerprint::from_bytes(edited.as_bytes());
+        assert!(a.similarity(&b) > 95.0, "{}", a.similarity(&b));
+    }
+
+    #[test]
+    fn test_unrelated_inputs_score_low() {
+        let a = ChunkFingerprint::from_bytes(sample_text(100).as_bytes());
+        let other: String = (0..100)
+            .map(|i| format!("{} bottles of beer on the wall, take one down\n", i))
+            .collect();
+        let b = ChunkFingerprint::from_bytes(other.as_bytes());
+        assert!(a.similarity(&b) < 20.0, "{}", a.similarity(&b));
+    }
+
+    #[test]
+    fn test_upper_bound() {
+        assert_eq!(similarity_upper_bound(100, 100), 100.0);
+        assert_eq!(similarity_upper_bound(100, 300), 50.0);
+        assert_eq!(similarity_upper_bound(0, 0), 100.0);
+    }
+}
@@ -0,0 +1,925 @@
+//! Folder synchronization driven by comparison results.
+//!
+//! Synchronization happens in two steps. A [`SyncPlanner`] walks the
+//! [`DiffNode`]s produced by a [`ComparisonEngine`] and turns them into a list
+//! of [`SyncAction`]s: the copies (and, for [`SyncMode::Mirror`], deletions)
+//! needed to bring the two roots in line, plus the conflicts it will not
+//! resolve. [`execute_plan`] then performs the actions through
+//! [`FileOperations`], so that dry runs and trash handling behave exactly as
+//! for single-file operations. [`execute_sync`] does both in one call.
+//!
+//! When either side is an archive or a remote store, [`execute_plan_with_vfs`]
+//! performs the same plan through the [`Vfs`] backends instead.
+//! [`sync_archives`] plans and performs it in one call for two archives
+//! compared from their roots, e.g. to patch a writable copy of one ZIP with
+//! the changes found in another and repack it.
+//!
+//! [`ComparisonEngine`]: crate::ComparisonEngine
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use rcompare_core::sync::{execute_plan, SyncMode, SyncPlanner};
+//! use rcompare_core::{compare_directories, CompareOptions, FileOperations};
+//! use std::path::Path;
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let (left, right) = (Path::new("/left"), Path::new("/right"));
+//! let diffs = compare_directories(left, right, &CompareOptions::new())?;
+//!
+//! let plan = SyncPlanner::new(left, right).plan(&diffs, SyncMode::Mirror);
+//! for action in &plan {
+//!     println!("{} {}", action.kind.label(), action.relative_path.display());
+//! }
+//!
+//! let summary = execute_plan(&plan, &FileOperations::new(false, true));
+//! println!("{} copied, {} deleted", summary.copied, summary.deleted);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::comparison::mtimes_match;
+use crate::file_operations::FileOperations;
+use rcompare_common::{
+    DiffNode, DiffStatus, FileEntry, RCompareError, Vfs, VfsError, VfsOperation,
+};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{debug, warn};
+
+/// Direction and policy of a synchronization run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncMode {
+    /// Copy left-only and differing files to the right
+    LeftToRight,
+    /// Copy right-only and differing files to the left
+    RightToLeft,
+    /// Make the right side an exact copy of the left, deleting right-only entries
+    Mirror,
+    /// Copy orphans both ways; for differing files the newer side wins
+    UpdateNewer,
+}
+
+impl SyncMode {
+    /// Human-readable label used in status messages
+    pub fn label(&self) -> &'static str {
+        match self {
+            SyncMode::LeftToRight => "Left → Right",
+            SyncMode::RightToLeft => "Right → Left",
+            SyncMode::Mirror => "Mirror Left → Right",
+            SyncMode::UpdateNewer => "Bidirectional",
+        }
+    }
+}
+
+/// What a [`SyncAction`] does
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncActionKind {
+    /// Copy a file from the left root to the right root
+    CopyLeftToRight,
+    /// Copy a file from the right root to the left root
+    CopyRightToLeft,
+    /// Delete a file or a whole directory
+    Delete,
+    /// Entries differ but the mode doesn't say which side wins; left alone
+    Conflict,
+}
+
+impl SyncActionKind {
+    /// Short label used when listing a plan
+    pub fn label(&self) -> &'static str {
+        match self {
+            SyncActionKind::CopyLeftToRight => "copy L→R",
+            SyncActionKind::CopyRightToLeft => "copy R→L",
+            SyncActionKind::Delete => "delete",
+            SyncActionKind::Conflict => "conflict",
+        }
+    }
+}
+
+/// One step of a sync plan
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncAction {
+    pub kind: SyncActionKind,
+    /// Path relative to both roots
+    pub relative_path: PathBuf,
+    /// File read by a copy, or the left entry of a conflict; `None` for deletions
+    pub source: Option<PathBuf>,
+    /// File written by a copy, entry removed by a deletion, or the right
+    /// entry of a conflict
+    pub dest: PathBuf,
+    /// Size in bytes of the copied or deleted entry (0 for directories and
+    /// conflicts)
+    pub size: u64,
+    /// Whether the deleted entry is a directory
+    pub is_dir: bool,
+}
+
+/// Outcome of [`execute_sync`] or [`execute_plan`]
+///
+/// In dry-run mode the counts describe what would have been done.
+#[derive(Debug, Clone, Default)]
+pub struct SyncSummary {
+    /// Files copied between the two sides
+    pub copied: usize,
+    /// Files and directories deleted (mirror mode only)
+    pub deleted: usize,
+    /// One message per failed operation
+    pub errors: Vec<String>,
+}
+
+/// Turns comparison results into a list of [`SyncAction`]s without touching
+/// the filesystem
+#[derive(Debug, Clone)]
+pub struct SyncPlanner {
+    left_root: PathBuf,
+    right_root: PathBuf,
+    mtime_tolerance: Duration,
+}
+
+impl SyncPlanner {
+    pub fn new(left_root: impl Into<PathBuf>, right_root: impl Into<PathBuf>) -> Self {
+        Self {
+            left_root: left_root.into(),
+            right_root: right_root.into(),
+            mtime_tolerance: Duration::ZERO,
+        }
+    }
+
+    /// In [`SyncMode::UpdateNewer`], treat modification times at most
+    /// `tolerance` apart as equal; such files are reported as conflicts
+    ///
+    /// Use the same tolerance as [`ComparisonEngine::with_mtime_tolerance`] so a
+    /// file copied to a coarse-grained filesystem doesn't flip-flop between runs.
+    ///
+    /// [`ComparisonEngine::with_mtime_tolerance`]: crate::ComparisonEngine::with_mtime_tolerance
+    pub fn with_mtime_tolerance(mut self, tolerance: Duration) -> Self {
+        self.mtime_tolerance = tolerance;
+        self
+    }
+
+    /// Plan the actions that bring the roots in line according to `mode`
+    ///
+    /// `diffs` is expected in the order returned by the comparison engine
+    /// (sorted by relative path), so that a deleted directory is seen before its
+    /// contents, which are then left out of the plan. Files whose content was
+    /// not verified (`Unchecked`) are treated like differing files. Directories
+    /// are never copied themselves: their files are copied individually and
+    /// [`FileOperations::copy_file`] creates missing parents.
+    pub fn plan(&self, diffs: &[DiffNode], mode: SyncMode) -> Vec<SyncAction> {
+        let mut plan = Vec::new();
+        let mut deleted_dirs: Vec<PathBuf> = Vec::new();
+
+        for node in diffs {
+            if deleted_dirs
+                .iter()
+                .any(|dir| node.relative_path.starts_with(dir))
+            {
+                continue;
+            }
+
+            match (mode, node.status) {
+                (
+                    SyncMode::LeftToRight | SyncMode::Mirror,
+                    DiffStatus::OrphanLeft | DiffStatus::Different | DiffStatus::Unchecked,
+                ) => plan.extend(self.copy(node, SyncActionKind::CopyLeftToRight)),
+                (
+                    SyncMode::RightToLeft,
+                    DiffStatus::OrphanRight | DiffStatus::Different | DiffStatus::Unchecked,
+                ) => plan.extend(self.copy(node, SyncActionKind::CopyRightToLeft)),
+                (SyncMode::Mirror, DiffStatus::OrphanRight) => {
+                    plan.extend(self.delete_right(node, &mut deleted_dirs))
+                }
+                (SyncMode::Mirror, DiffStatus::TypeConflict) => {
+                    // Replace whatever is on the right with the left entry; the
+                    // contents of a left directory follow as left orphans
+                    plan.extend(self.delete_right(node, &mut deleted_dirs));
+                    plan.extend(self.copy(node, SyncActionKind::CopyLeftToRight));
+                }
+                (SyncMode::UpdateNewer, DiffStatus::OrphanLeft) => {
+                    plan.extend(self.copy(node, SyncActionKind::CopyLeftToRight))
+                }
+                (SyncMode::UpdateNewer, DiffStatus::OrphanRight) => {
+                    plan.extend(self.copy(node, SyncActionKind::CopyRightToLeft))
+                }
+                (SyncMode::UpdateNewer, DiffStatus::Different | DiffStatus::Unchecked) => {
+                    if let (Some(left), Some(right)) = (&node.left, &node.right) {
+                        if mtimes_match(left.modified, right.modified, self.mtime_tolerance) {
+                            debug!(
+                                "Not syncing {}: timestamps within tolerance",
+                                node.relative_path.display()
+                            );
+                            plan.push(self.conflict(node));
+                        } else if left.modified > right.modified {
+                            plan.extend(self.copy(node, SyncActionKind::CopyLeftToRight));
+                        } else {
+                            plan.extend(self.copy(node, SyncActionKind::CopyRightToLeft));
+                        }
+                    }
+                }
+                (_, DiffStatus::TypeConflict) => {
+                    debug!(
+                        "Not syncing type conflict at {}",
+                        node.relative_path.display()
+                    );
+                    plan.push(self.conflict(node));
+                }
+                _ => {}
+            }
+        }
+
+        plan
+    }
+
+    fn copy(&self, node: &DiffNode, kind: SyncActionKind) -> Option<SyncAction> {
+        let (entry, src_root, dest_root) = match kind {
+            SyncActionKind::CopyRightToLeft => (&node.right, &self.right_root, &self.left_root),
+            _ => (&node.left, &self.left_root, &self.right_root),
+        };
+        let entry = entry.as_ref().filter(|entry| !entry.is_dir)?;
+        Some(SyncAction {
+            kind,
+            relative_path: node.relative_path.clone(),
+            source: Some(src_root.join(&entry.path)),
+            dest: dest_root.join(&entry.path),
+            size: entry.size,
+            is_dir: false,
+        })
+    }
+
+    /// Delete the right entry, remembering deleted directories so their
+    /// contents can be skipped
+    fn delete_right(&self, node: &DiffNode, deleted_dirs: &mut Vec<PathBuf>) -> Option<SyncAction> {
+        let entry: &FileEntry = node.right.as_ref()?;
+        if entry.is_dir {
+            deleted_dirs.push(entry.path.clone());
+        }
+        Some(SyncAction {
+            kind: SyncActionKind::Delete,
+            relative_path: node.relative_path.clone(),
+            source: None,
+            dest: self.right_root.join(&entry.path),
+            size: if entry.is_dir { 0 } else { entry.size },
+            is_dir: entry.is_dir,
+        })
+    }
+
+    fn conflict(&self, node: &DiffNode) -> SyncAction {
+        SyncAction {
+            kind: SyncActionKind::Conflict,
+            relative_path: node.relative_path.clone(),
+            source: Some(self.left_root.join(&node.relative_path)),
+            dest: self.right_root.join(&node.relative_path),
+            size: 0,
+            is_dir: false,
+        }
+    }
+}
+
+/// Synchronize `left_root` and `right_root` according to `diffs` and `mode`
+///
+/// Plans with [`SyncPlanner::plan`] and runs the plan with [`execute_plan`].
+/// Failures are collected in the summary rather than aborting the run.
+pub fn execute_sync(
+    left_root: &Path,
+    right_root: &Path,
+    diffs: &[DiffNode],
+    mode: SyncMode,
+    ops: &FileOperations,
+) -> SyncSummary {
+    execute_sync_with_mtime_tolerance(left_root, right_root, diffs, mode, ops, Duration::ZERO)
+}
+
+/// [`execute_sync`] where, in [`SyncMode::UpdateNewer`], modification times
+/// at most `tolerance` apart count as equal and neither side is copied
+///
+/// See [`SyncPlanner::with_mtime_tolerance`].
+pub fn execute_sync_with_mtime_tolerance(
+    left_root: &Path,
+    right_root: &Path,
+    diffs: &[DiffNode],
+    mode: SyncMode,
+    ops: &FileOperations,
+    tolerance: Duration,
+) -> SyncSummary {
+    let plan = SyncPlanner::new(left_root, right_root)
+        .with_mtime_tolerance(tolerance)
+        .plan(diffs, mode);
+    execute_plan(&plan, ops)
+}
+
+/// Perform the copies and deletions of `plan` in order; conflicts are skipped
+///
+/// Failures are collected in the summary rather than aborting the run.
+pub fn execute_plan(plan: &[SyncAction], ops: &FileOperations) -> SyncSummary {
+    let mut summary = SyncSummary::default();
+
+    for action in plan {
+        match action.kind {
+            SyncActionKind::CopyLeftToRight | SyncActionKind::CopyRightToLeft => {
+                let Some(src) = &action.source else {
+                    continue;
+                };
+                match ops.copy_file(src, &action.dest) {
+                    Ok(result) if result.success => summary.copied += 1,
+                    Ok(result) => record_error(
+                        &mut summary,
+                        src,
+                        result.error.unwrap_or_else(|| "copy failed".to_string()),
+                    ),
+                    Err(e) => record_error(&mut summary, src, e.to_string()),
+                }
+            }
+            SyncActionKind::Delete => match ops.delete(&action.dest) {
+                Ok(result) if result.success => summary.deleted += 1,
+                Ok(result) => record_error(
+                    &mut summary,
+                    &action.dest,
+                    result.error.unwrap_or_else(|| "delete failed".to_string()),
+                ),
+                Err(e) => record_error(&mut summary, &action.dest, e.to_string()),
+            },
+            SyncActionKind::Conflict => {}
+        }
+    }
+
+    summary
+}
+
+/// Perform `plan` through VFS backends instead of the local filesystem
+///
+/// Plan paths are paths inside `left` and `right`, so plan with the roots the
+/// sides were scanned with (an empty path for archives and remote stores).
+/// Copies stream the file from one backend into [`Vfs::create_file`] on the
+/// other; deleting a directory removes the files in it. Every side the plan
+/// writes to is checked up front, so a read-only target fails before anything
+/// is copied, and flushed at the end so archives are rebuilt and uploads
+/// complete. With `dry_run` only the checks run and the summary counts what
+/// would be done.
+pub fn execute_plan_with_vfs(
+    plan: &[SyncAction],
+    left: &dyn Vfs,
+    right: &dyn Vfs,
+    dry_run: bool,
+) -> Result<SyncSummary, RCompareError> {
+    for action in plan {
+        let (target, op) = match action.kind {
+            SyncActionKind::CopyLeftToRight => (right, VfsOperation::Write),
+            SyncActionKind::CopyRightToLeft => (left, VfsOperation::Write),
+            SyncActionKind::Delete => (right, VfsOperation::Delete),
+            SyncActionKind::Conflict => continue,
+        };
+        target
+            .ensure_supported(op)
+            .map_err(|e| RCompareError::vfs(target.instance_id(), e))?;
+    }
+
+    let mut summary = SyncSummary::default();
+    for action in plan {
+        let result = match action.kind {
+            SyncActionKind::CopyLeftToRight | SyncActionKind::CopyRightToLeft => {
+                let (from, to) = if action.kind == SyncActionKind::CopyLeftToRight {
+                    (left, right)
+                } else {
+                    (right, left)
+                };
+                let Some(src) = &action.source else {
+                    continue;
+                };
+                if dry_run {
+                    Ok(())
+                } else {
+                    copy_between(from, src, to, &action.dest)
+                }
+                .map(|()| summary.copied += 1)
+            }
+            SyncActionKind::Delete => if dry_run {
+                Ok(())
+            } else if action.is_dir {
+                remove_tree(right, &action.dest)
+            } else {
+                right.remove_file(&action.dest)
+            }
+            .map(|()| summary.deleted += 1),
+            SyncActionKind::Conflict => continue,
+        };
+        if let Err(e) = result {
+            record_error(&mut summary, &action.dest, e.to_string());
+        }
+    }
+
+    if !dry_run {
+        for side in [left, right] {
+            side.flush()
+                .map_err(|e| RCompareError::vfs(side.instance_id(), e))?;
+        }
+    }
+    Ok(summary)
+}
+
+/// Sync two archives compared from their roots and repack the written one
+///
+/// `diffs` is the result of comparing `left` and `right` scanned from their
+/// roots (an empty path), as [`ComparisonEngine::compare_with_vfs`] does for
+/// archives. The plan for `mode` is performed with [`execute_plan_with_vfs`]:
+/// with [`SyncMode::RightToLeft`], right-only and differing files are copied
+/// from `right` into `left`. The written side is then flushed, which rebuilds
+/// a [`WritableZipVfs`] or [`WritableTarVfs`] archive on disk. To keep the
+/// original, open the writable VFS on a copy of the archive file.
+///
+/// [`ComparisonEngine::compare_with_vfs`]: crate::ComparisonEngine::compare_with_vfs
+/// [`WritableZipVfs`]: crate::vfs::WritableZipVfs
+/// [`WritableTarVfs`]: crate::vfs::WritableTarVfs
+pub fn sync_archives(
+    diffs: &[DiffNode],
+    mode: SyncMode,
+    left: &dyn Vfs,
+    right: &dyn Vfs,
+    dry_run: bool,
+) -> Result<SyncSummary, RCompareError> {
+    let plan = SyncPlanner::new("", "").plan(diffs, mode);
+    execute_plan_with_vfs(&plan, left, right, dry_run)
+}
+
+fn copy_between(from: &dyn Vfs, src: &Path, to: &dyn Vfs, dest: &Path) -> Result<(), VfsError> {
+    // Object stores have no directories to create
+    if let Some(parent) = dest.parent().filter(|p| !p.as_os_str().is_empty()) {
+        if to.capabilities().create_dir {
+            to.create_dir_all(parent)?;
+        }
+    }
+    let mut reader = from.open_file(src)?;
+    let mut writer = to.create_file(dest)?;
+    std::io::copy(&mut reader, &mut writer)?;
+    // Remote writers upload here; flushing explicitly surfaces their errors
+    writer.flush()?;
+    Ok(())
+}
+
+/// Remove every file below `dir`; the backend trait has no directory removal
+fn remove_tree(vfs: &dyn Vfs, dir: &Path) -> Result<(), VfsError> {
+    for entry in vfs.read_dir(dir)? {
+        if entry.is_dir {
+            remove_tree(vfs, &entry.path)?;
+        } else {
+            vfs.remove_file(&entry.path)?;
+        }
+    }
+    Ok(())
+}
+
+fn record_error(summary: &mut SyncSummary, path: &Path, message: String) {
+    warn!("Sync failed for {}: {}", path.display(), message);
+    summary
+        .errors
+        .push(format!("{}: {}", path.display(), message));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quick_compare::{compare_directories, CompareOptions};
+    #[cfg(feature = "archives")]
+    use crate::vfs::{LocalVfs, WritableZipVfs, ZipVfs};
+    #[cfg(feature = "archives")]
+    use crate::{ComparisonEngine, FolderScanner, HashCache};
+    use filetime::{set_file_mtime, FileTime};
+    #[cfg(feature = "archives")]
+    use rcompare_common::AppConfig;
+    use std::fs;
+    #[cfg(feature = "archives")]
+    use std::io::Read;
+    use tempfile::TempDir;
+
+    struct Fixture {
+        temp: TempDir,
+        left: PathBuf,
+        right: PathBuf,
+    }
+
+    impl Fixture {
+        /// Left and right trees with one file of every interesting status
+        fn new() -> Self {
+            let temp = TempDir::new().unwrap();
+            let left = temp.path().join("left");
+            let right = temp.path().join("right");
+            fs::create_dir_all(left.join("sub")).unwrap();
+            fs::create_dir_all(right.join("extra")).unwrap();
+
+            fs::write(left.join("same.txt"), b"same").unwrap();
+            fs::write(right.join("same.txt"), b"same").unwrap();
+            fs::write(left.join("left_only.txt"), b"left only").unwrap();
+            fs::write(left.join("sub/nested.txt"), b"nested").unwrap();
+            fs::write(right.join("right_only.txt"), b"right only").unwrap();
+            fs::write(right.join("extra/deep.txt"), b"deep").unwrap();
+
+            // Left is older, right is newer
+            fs::write(left.join("changed.txt"), b"left version").unwrap();
+            fs::write(right.join("changed.txt"), b"right version!").unwrap();
+            set_file_mtime(left.join("changed.txt"), FileTime::from_unix_time(1_000, 0)).unwrap();
+            set_file_mtime(
+                right.join("changed.txt"),
+                FileTime::from_unix_time(2_000, 0),
+            )
+            .unwrap();
+
+            Self { temp, left, right }
+        }
+
+        fn sync(&self, mode: SyncMode, dry_run: bool) -> SyncSummary {
+            let options = CompareOptions::new()
+                .hash_verification(true)
+                .cache_dir(self.temp.path().join("cache"));
+            let diffs = compare_directories(&self.left, &self.right, &options).unwrap();
+            let ops = FileOperations::new(dry_run, false);
+            execute_sync(&self.left, &self.right, &diffs, mode, &ops)
+        }
+
+        fn plan(&self, mode: SyncMode) -> Vec<SyncAction> {
+            let options = CompareOptions::new()
+                .hash_verification(true)
+                .cache_dir(self.temp.path().join("cache"));
+            let diffs = compare_directories(&self.left, &self.right, &options).unwrap();
+            SyncPlanner::new(&self.left, &self.right).plan(&diffs, mode)
+        }
+
+        fn left_text(&self, path: &str) -> Option<String> {
+            fs::read_to_string(self.left.join(path)).ok()
+        }
+
+        fn right_text(&self, path: &str) -> Option<String> {
+            fs::read_to_string(self.right.join(path)).ok()
+        }
+    }
+
+    #[test]
+    fn test_sync_left_to_right() {
+        let fx = Fixture::new();
+        let summary = fx.sync(SyncMode::LeftToRight, false);
+
+        assert_eq!(summary.copied, 3);
+        assert_eq!(summary.deleted, 0);
+        assert!(summary.errors.is_empty(), "{:?}", summary.errors);
+        assert_eq!(fx.right_text("left_only.txt").as_deref(), Some("left only"));
+        assert_eq!(fx.right_text("sub/nested.txt").as_deref(), Some("nested"));
+        assert_eq!(
+            fx.right_text("changed.txt").as_deref(),
+            Some("left version")
+        );
+        // Right-only files are kept and nothing flows back to the left
+        assert!(fx.right_text("right_only.txt").is_some());
+        assert!(fx.left_text("right_only.txt").is_none());
+    }
+
+    #[test]
+    fn test_sync_right_to_left() {
+        let fx = Fixture::new();
+        let summary = fx.sync(SyncMode::RightToLeft, false);
+
+        assert_eq!(summary.copied, 3);
+        assert_eq!(summary.deleted, 0);
+        assert_eq!(
+            fx.left_text("right_only.txt").as_deref(),
+            Some("right only")
+        );
+        assert_eq!(fx.left_text("extra/deep.txt").as_deref(), Some("deep"));
+        assert_eq!(
+            fx.left_text("changed.txt").as_deref(),
+            Some("right version!")
+        );
+        assert!(fx.left_text("left_only.txt").is_some());
+        assert!(fx.right_text("left_only.txt").is_none());
+    }
+
+    #[test]
+    fn test_sync_mirror_deletes_right_only_entries() {
+        let fx = Fixture::new();
+        let summary = fx.sync(SyncMode::Mirror, false);
+
+        assert_eq!(summary.copied, 3);
+        // right_only.txt and the whole extra/ directory
+        assert_eq!(summary.deleted, 2);
+        assert!(summary.errors.is_empty(), "{:?}", summary.errors);
+        assert!(!fx.right.join("right_only.txt").exists());
+        assert!(!fx.right.join("extra").exists());
+        assert_eq!(
+            fx.right_text("changed.txt").as_deref(),
+            Some("left version")
+        );
+
+        let options = CompareOptions::new()
+            .hash_verification(true)
+            .cache_dir(fx.temp.path().join("cache"));
+        let diffs = compare_directories(&fx.left, &fx.right, &options).unwrap();
+        assert!(
+            diffs.iter().all(|d| d.status == DiffStatus::Same),
+            "{:?}",
+            diffs
+        );
+    }
+
+    #[test]
+    fn test_sync_mirror_replaces_type_conflicts() {
+        let fx = Fixture::new();
+        fs::write(fx.left.join("conflict"), b"a file").unwrap();
+        fs::create_dir_all(fx.right.join("conflict")).unwrap();
+        fs::write(fx.right.join("conflict/inner.txt"), b"inner").unwrap();
+
+        let summary = fx.sync(SyncMode::Mirror, false);
+
+        assert!(summary.errors.is_empty(), "{:?}", summary.errors);
+        assert_eq!(fx.right_text("conflict").as_deref(), Some("a file"));
+    }
+
+    #[test]
+    fn test_sync_update_newer() {
+        let fx = Fixture::new();
+        let summary = fx.sync(SyncMode::UpdateNewer, false);
+
+        assert_eq!(summary.copied, 5);
+        assert_eq!(summary.deleted, 0);
+        assert_eq!(fx.right_text("left_only.txt").as_deref(), Some("left only"));
+        assert_eq!(
+            fx.left_text("right_only.txt").as_deref(),
+            Some("right only")
+        );
+        // The right copy is newer, so it wins
+        assert_eq!(
+            fx.left_text("changed.txt").as_deref(),
+            Some("right version!")
+        );
+        assert_eq!(
+            fx.right_text("changed.txt").as_deref(),
+            Some("right version!")
+        );
+    }
+
+    #[test]
+    fn test_sync_update_newer_mtime_tolerance() {
+        let fx = Fixture::new();
+        set_file_mtime(
+            fx.right.join("changed.txt"),
+            FileTime::from_unix_time(1_001, 0),
+        )
+        .unwrap();
+        let options = CompareOptions::new()
+            .hash_verification(true)
+            .cache_dir(fx.temp.path().join("cache"));
+        let diffs = compare_directories(&fx.left, &fx.right, &options).unwrap();
+        let ops = FileOperations::new(true, false);
+        let sync = |tolerance: u64| {
+            execute_sync_with_mtime_tolerance(
+                &fx.left,
+                &fx.right,
+                &diffs,
+                SyncMode::UpdateNewer,
+                &ops,
+                Duration::from_secs(tolerance),
+            )
+        };
+
+        // One second apart: equal at 2s tolerance, the right side wins at 0s
+        assert_eq!(sync(2).copied, 4);
+        assert_eq!(sync(0).copied, 5);
+    }
+
+    #[test]
+    fn test_sync_mirror_dry_run_changes_nothing() {
+        let fx = Fixture::new();
+        let summary = fx.sync(SyncMode::Mirror, true);
+
+        assert_eq!(summary.copied, 3);
+        assert_eq!(summary.deleted, 2);
+        assert!(fx.right.join("right_only.txt").exists());
+        assert!(fx.right.join("extra/deep.txt").exists());
+        assert!(fx.right_text("left_only.txt").is_none());
+        assert_eq!(
+            fx.right_text("changed.txt").as_deref(),
+            Some("right version!")
+        );
+    }
+
+    fn steps(plan: &[SyncAction]) -> Vec<(SyncActionKind, &str)> {
+        plan.iter()
+            .map(|action| (action.kind, action.relative_path.to_str().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn test_plan_for_each_mode() {
+        use SyncActionKind::*;
+        let fx = Fixture::new();
+
+        assert_eq!(
+            steps(&fx.plan(SyncMode::LeftToRight)),
+            [
+                (CopyLeftToRight, "changed.txt"),
+                (CopyLeftToRight, "left_only.txt"),
+                (CopyLeftToRight, "sub/nested.txt"),
+            ]
+        );
+        assert_eq!(
+            steps(&fx.plan(SyncMode::RightToLeft)),
+            [
+                (CopyRightToLeft, "changed.txt"),
+                (CopyRightToLeft, "extra/deep.txt"),
+                (CopyRightToLeft, "right_only.txt"),
+            ]
+        );
+        // The deleted extra/ directory takes its contents with it
+        assert_eq!(
+            steps(&fx.plan(SyncMode::Mirror)),
+            [
+                (CopyLeftToRight, "changed.txt"),
+                (Delete, "extra"),
+                (CopyLeftToRight, "left_only.txt"),
+                (Delete, "right_only.txt"),
+                (CopyLeftToRight, "sub/nested.txt"),
+            ]
+        );
+        assert_eq!(
+            steps(&fx.plan(SyncMode::UpdateNewer)),
+            [
+                (CopyRightToLeft, "changed.txt"),
+                (CopyRightToLeft, "extra/deep.txt"),
+                (CopyLeftToRight, "left_only.txt"),
+                (CopyRightToLeft, "right_only.txt"),
+                (CopyLeftToRight, "sub/nested.txt"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plan_paths_and_sizes() {
+        let fx = Fixture::new();
+        let plan = fx.plan(SyncMode::Mirror);
+
+        let copy = &plan[0];
+        assert_eq!(
+            copy.source.as_deref(),
+            Some(fx.left.join("changed.txt").as_path())
+        );
+        assert_eq!(copy.dest, fx.right.join("changed.txt"));
+        assert_eq!(copy.size, "left version".len() as u64);
+
+        let delete_dir = &plan[1];
+        assert_eq!(delete_dir.source, None);
+        assert_eq!(delete_dir.dest, fx.right.join("extra"));
+        assert!(delete_dir.is_dir);
+
+        let delete_file = &plan[3];
+        assert_eq!(delete_file.dest, fx.right.join("right_only.txt"));
+        assert_eq!(delete_file.size, "right only".len() as u64);
+        assert!(!delete_file.is_dir);
+    }
+
+    #[test]
+    fn test_plan_reports_conflicts() {
+        let fx = Fixture::new();
+        fs::write(fx.left.join("conflict"), b"a file").unwrap();
+        fs::create_dir_all(fx.right.join("conflict")).unwrap();
+
+        let plan = fx.plan(SyncMode::LeftToRight);
+        let conflict = plan
+            .iter()
+            .find(|action| action.kind == SyncActionKind::Conflict)
+            .unwrap();
+        assert_eq!(conflict.relative_path, PathBuf::from("conflict"));
+
+        // Mirror resolves it by replacing the right directory
+        let mirror = fx.plan(SyncMode::Mirror);
+        assert!(steps(&mirror).contains(&(SyncActionKind::Delete, "conflict")));
+        assert!(steps(&mirror).contains(&(SyncActionKind::CopyLeftToRight, "conflict")));
+
+        // Equal timestamps leave UpdateNewer without a winner
+        set_file_mtime(
+            fx.right.join("changed.txt"),
+            FileTime::from_unix_time(1_000, 0),
+        )
+        .unwrap();
+        let update = fx.plan(SyncMode::UpdateNewer);
+        assert!(steps(&update).contains(&(SyncActionKind::Conflict, "changed.txt")));
+    }
+
+    #[cfg(feature = "archives")]
+    fn read_zip(archive: &Path, path: &str) -> String {
+        let zip = ZipVfs::new(archive.to_path_buf()).unwrap();
+        let mut text = String::new();
+        zip.open_file(Path::new(path))
+            .unwrap()
+            .read_to_string(&mut text)
+            .unwrap();
+        text
+    }
+
+    #[cfg(feature = "archives")]
+    #[test]
+    fn test_sync_into_writable_zip() {
+        let fx = Fixture::new();
+        let empty = fx.temp.path().join("empty");
+        fs::create_dir_all(&empty).unwrap();
+        let options = CompareOptions::new().cache_dir(fx.temp.path().join("cache"));
+        let diffs = compare_directories(&fx.left, &empty, &options).unwrap();
+
+        // Archive paths start at the archive root
+        let plan = SyncPlanner::new("", "").plan(&diffs, SyncMode::LeftToRight);
+        let archive = fx.temp.path().join("backup.zip");
+        let left = LocalVfs::new(fx.left.clone());
+        let right = WritableZipVfs::create(archive.clone()).unwrap();
+
+        let preview = execute_plan_with_vfs(&plan, &left, &right, true).unwrap();
+        assert_eq!(preview.copied, 4);
+        assert!(!archive.exists());
+
+        let summary = execute_plan_with_vfs(&plan, &left, &right, false).unwrap();
+        assert_eq!(summary.copied, 4);
+        assert!(summary.errors.is_empty(), "{:?}", summary.errors);
+        assert_eq!(read_zip(&archive, "left_only.txt"), "left only");
+        assert_eq!(read_zip(&archive, "sub/nested.txt"), "nested");
+        assert_eq!(read_zip(&archive, "changed.txt"), "left version");
+    }
+
+    #[cfg(feature = "archives")]
+    #[test]
+    fn test_sync_archives_patches_writable_copy() {
+        let temp = TempDir::new().unwrap();
+        let zip = |name: &str, files: &[(&str, &str)]| {
+            let path = temp.path().join(name);
+            let vfs = WritableZipVfs::create(path.clone()).unwrap();
+            for (file, content) in files {
+                vfs.write_file(Path::new(file), content.as_bytes()).unwrap();
+            }
+            vfs.flush().unwrap();
+            path
+        };
+        let left = zip(
+            "left.zip",
+            &[
+                ("same.txt", "same"),
+                ("changed.txt", "left version"),
+                ("left_only.txt", "left only"),
+            ],
+        );
+        let right = zip(
+            "right.zip",
+            &[
+                ("same.txt", "same"),
+                ("changed.txt", "right version"),
+                ("docs/right_only.txt", "right only"),
+            ],
+        );
+        let patched = temp.path().join("patched.zip");
+        fs::copy(&left, &patched).unwrap();
+
+        let target = WritableZipVfs::new(patched.clone()).unwrap();
+        let source = ZipVfs::new(right.clone()).unwrap();
+        let scanner = FolderScanner::new(AppConfig::default());
+        let engine = ComparisonEngine::new(HashCache::in_memory()).with_hash_verification(true);
+        let diffs = engine
+            .compare_with_vfs(
+                Path::new(""),
+                Path::new(""),
+                scanner.scan_vfs(&target, Path::new("")).unwrap(),
+                scanner.scan_vfs(&source, Path::new("")).unwrap(),
+                Some(&target),
+                Some(&source),
+            )
+            .unwrap();
+
+        let summary =
+            sync_archives(&diffs, SyncMode::RightToLeft, &target, &source, false).unwrap();
+        assert_eq!(summary.copied, 2);
+        assert!(summary.errors.is_empty(), "{:?}", summary.errors);
+
+        for file in ["same.txt", "changed.txt", "docs/right_only.txt"] {
+            assert_eq!(read_zip(&patched, file), read_zip(&right, file), "{file}");
+        }
+        // Right-to-left never deletes, and the original left is untouched
+        assert_eq!(read_zip(&patched, "left_only.txt"), "left only");
+        assert_eq!(read_zip(&left, "changed.txt"), "left version");
+    }
+
+    #[cfg(feature = "archives")]
+    #[test]
+    fn test_sync_into_read_only_vfs_fails_up_front() {
+        let fx = Fixture::new();
+        let archive = fx.temp.path().join("readonly.zip");
+        let writable = WritableZipVfs::create(archive.clone()).unwrap();
+        writable.write_file(Path::new("keep.txt"), b"keep").unwrap();
+        writable.flush().unwrap();
+
+        let options = CompareOptions::new().cache_dir(fx.temp.path().join("cache"));
+        let diffs = compare_directories(&fx.left, &fx.right, &options).unwrap();
+        let plan = SyncPlanner::new("", "").plan(&diffs, SyncMode::LeftToRight);
+        let left = LocalVfs::new(fx.left.clone());
+        let right = ZipVfs::new(archive.clone()).unwrap();
+
+        let err = execute_plan_with_vfs(&plan, &left, &right, false).unwrap_err();
+        assert!(
+            err.to_string().contains("does not support writing files"),
+            "{err}"
+        );
+        assert_eq!(read_zip(&archive, "keep.txt"), "keep");
+    }
+}
@@ -1,22 +1,37 @@
-use rcompare_common::RCompareError;
+use crate::content_kind::{decode_text, read_text_file};
+use rcompare_common::{DiffNode, DiffStatus, RCompareError};
 use regex::Regex;
 use serde::Serialize;
-use similar::{ChangeTag, TextDiff};
-use std::fs;
-use std::path::Path;
+use similar::algorithms::{myers, Capture, Compact, DiffHook, NoFinishHook, Replace};
+use similar::{ChangeTag, DiffOp, TextDiff};
+use std::collections::HashMap;
+use std::io::Read;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 use syntect::easy::HighlightLines;
 use syntect::highlighting::ThemeSet;
 use syntect::parsing::SyntaxSet;
 use syntect::util::LinesWithEndings;
+use tracing::warn;
 
 /// Represents a line in a text diff
 #[derive(Debug, Clone, Serialize)]
 pub struct DiffLine {
     pub line_number_left: Option<usize>,
     pub line_number_right: Option<usize>,
+    /// The line, including its line ending unless it is the last line of a
+    /// file that does not end in one
     pub content: String,
     pub change_type: DiffChangeType,
     pub highlighted_segments: Vec<HighlightedSegment>,
+    /// Whitespace that differs between the original lines of an `Equal`
+    /// line, filled only when [`TextDiffConfig::show_whitespace_changes`] is set
+    pub whitespace_markers: Vec<WhitespaceMarker>,
+    /// For an `Insert` or `Delete` that is an edited version of a line on the
+    /// other side, that line's number on the other side (see
+    /// [`TextDiffConfig::line_pairing_threshold`])
+    pub paired_line: Option<usize>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
@@ -26,6 +41,13 @@ pub enum DiffChangeType {
     Delete,
 }
 
+/// One side of a two-way diff
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum DiffSide {
+    Left,
+    Right,
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct HighlightedSegment {
     pub text: String,
@@ -40,6 +62,110 @@ pub struct HighlightStyle {
     pub italic: bool,
 }
 
+/// Kind of whitespace difference on a line that compares equal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum WhitespaceMarkerKind {
+    /// Trailing whitespace was added, removed or changed
+    Trailing,
+    /// Tabs on one side where the other side has spaces
+    TabSpace,
+    /// Whitespace was added, removed or resized elsewhere in the line
+    Spacing,
+}
+
+/// Presentational marker for whitespace that changed but did not count as
+/// a content change, so a viewer can render it (e.g. as `·` or `→`).
+///
+/// Ranges are byte offsets into the original left and right lines, without
+/// the line ending. One side may be empty when whitespace was only added.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WhitespaceMarker {
+    pub kind: WhitespaceMarkerKind,
+    pub left: Range<usize>,
+    pub right: Range<usize>,
+}
+
+/// One line of an [`AlignedRow`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AlignedLine {
+    /// 1-based line number in its file
+    pub number: usize,
+    /// Line text without the line ending
+    pub text: String,
+}
+
+/// One row of a side-by-side view of a text diff
+///
+/// A row holds a line from each side, or a line from one side opposite a
+/// filler (`None`) where the other side has no counterpart. Rows built by
+/// [`align_rows`] keep both sides in file order.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AlignedRow {
+    pub left: Option<AlignedLine>,
+    pub right: Option<AlignedLine>,
+    /// Whether the row is part of a change rather than an equal line
+    pub changed: bool,
+}
+
+/// Lines inserted and deleted in one file's text diff, as counted by
+/// `git diff --stat`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TextDiffStats {
+    pub path: PathBuf,
+    pub insertions: usize,
+    pub deletions: usize,
+}
+
+impl TextDiffStats {
+    /// Count the `Insert` and `Delete` lines of a diff
+    pub fn from_lines(path: impl Into<PathBuf>, lines: &[DiffLine]) -> Self {
+        let count = |change_type| {
+            lines
+                .iter()
+                .filter(|line| line.change_type == change_type)
+                .count()
+        };
+        Self {
+            path: path.into(),
+            insertions: count(DiffChangeType::Insert),
+            deletions: count(DiffChangeType::Delete),
+        }
+    }
+
+    /// Insertions plus deletions
+    pub fn changes(&self) -> usize {
+        self.insertions + self.deletions
+    }
+}
+
+/// Which input a [`Diff3Region`] stands out in, using diff3's terms
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Diff3Kind {
+    /// The same lines in base, left and right
+    Shared,
+    /// Only the left changed these base lines
+    LeftOnly,
+    /// Only the right changed these base lines
+    RightOnly,
+    /// Left and right made the same change, so only the base differs
+    BaseOnly,
+    /// Left and right changed these base lines differently
+    Conflict,
+}
+
+/// One stretch of a three-way diff, for a three-pane view
+///
+/// Each side holds its lines of the stretch in file order; a side is empty
+/// where it has no lines there (e.g. the base of a stretch both sides
+/// inserted). The sides of a `Shared` region have equal text.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diff3Region {
+    pub kind: Diff3Kind,
+    pub base: Vec<AlignedLine>,
+    pub left: Vec<AlignedLine>,
+    pub right: Vec<AlignedLine>,
+}
+
 /// Whitespace handling options for text comparison
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum WhitespaceMode {
@@ -54,9 +180,54 @@ pub enum WhitespaceMode {
     IgnoreTrailing,
     /// Ignore changes in amount of whitespace
     IgnoreChanges,
+    /// Expand tabs to the next multiple of `tab_width` columns, so
+    /// whitespace only matches when it lines up at the same columns
+    ExpandTabs,
+}
+
+impl std::str::FromStr for WhitespaceMode {
+    type Err = RCompareError;
+
+    /// Parse a whitespace option as given on the command line: `all`,
+    /// `leading`, `trailing`, `changes` or `tabs`, in any case
+    fn from_str(mode: &str) -> Result<Self, Self::Err> {
+        match mode.to_lowercase().as_str() {
+            "all" => Ok(WhitespaceMode::IgnoreAll),
+            "leading" => Ok(WhitespaceMode::IgnoreLeading),
+            "trailing" => Ok(WhitespaceMode::IgnoreTrailing),
+            "changes" => Ok(WhitespaceMode::IgnoreChanges),
+            "tabs" => Ok(WhitespaceMode::ExpandTabs),
+            _ => Err(RCompareError::Config(format!(
+                "Invalid whitespace mode '{}'. Valid options: all, leading, trailing, changes, tabs",
+                mode
+            ))),
+        }
+    }
+}
+
+/// Line diff algorithm used for text comparison
+///
+/// - `Myers` finds a minimal edit script and is the fastest option. It suits
+///   prose, but on code it can align unrelated braces or blank lines.
+/// - `Patience` anchors on lines that occur exactly once on both sides, which
+///   keeps function boundaries aligned in source code. It falls back to Myers
+///   between anchors.
+/// - `Histogram` anchors on the least frequent common lines even when none is
+///   unique, which usually gives the cleanest hunks on code with many repeated
+///   lines, at some extra cost on large inputs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DiffAlgorithm {
+    Myers,
+    #[default]
+    Patience,
+    Histogram,
 }
 
 /// Regular expression rule for filtering or transforming lines before comparison
+///
+/// The replacement follows [`Regex::replace_all`] syntax: `$1` or `${1}`
+/// refers to a numbered group, `${name}` to a named one and `$$` is a
+/// literal `$`. Write `${1}x` rather than `$1x`, which would name group `1x`.
 #[derive(Debug, Clone)]
 pub struct RegexRule {
     pub pattern: Regex,
@@ -64,6 +235,55 @@ pub struct RegexRule {
     pub description: String,
 }
 
+impl RegexRule {
+    pub fn new(
+        pattern: &str,
+        replacement: impl Into<String>,
+        description: impl Into<String>,
+    ) -> Result<Self, RCompareError> {
+        let pattern = Regex::new(pattern)
+            .map_err(|e| RCompareError::Config(format!("Invalid regex '{}': {}", pattern, e)))?;
+        Ok(Self {
+            pattern,
+            replacement: replacement.into(),
+            description: description.into(),
+        })
+    }
+
+    /// Parse a `pattern:replacement[:description]` rule
+    ///
+    /// A colon that belongs to the pattern or replacement is written `\:`.
+    /// Everything after the second unescaped colon is the description.
+    pub fn parse(spec: &str) -> Result<Self, RCompareError> {
+        let mut fields = vec![String::new()];
+        let mut chars = spec.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' if chars.peek() == Some(&':') => {
+                    chars.next();
+                    fields.last_mut().unwrap().push(':');
+                }
+                ':' if fields.len() < 3 => fields.push(String::new()),
+                _ => fields.last_mut().unwrap().push(c),
+            }
+        }
+
+        if fields.len() < 2 {
+            return Err(RCompareError::Config(format!(
+                "Invalid regex rule format '{}'. Expected 'pattern:replacement:description'",
+                spec
+            )));
+        }
+        let description = fields.get(2).cloned().unwrap_or_default();
+        Self::new(&fields[0], fields[1].clone(), description)
+    }
+
+    /// Replace every match of the pattern in `text`
+    pub fn apply<'a>(&self, text: &'a str) -> std::borrow::Cow<'a, str> {
+        self.pattern.replace_all(text, self.replacement.as_str())
+    }
+}
+
 /// Configuration for text comparison
 #[derive(Debug, Clone, Default)]
 pub struct TextDiffConfig {
@@ -71,15 +291,46 @@ pub struct TextDiffConfig {
     pub ignore_case: bool,
     /// Whitespace handling mode
     pub whitespace_mode: WhitespaceMode,
-    /// Regular expression rules to apply before comparison
+    /// Regular expression rules to apply before comparison, in order; each
+    /// rule sees the output of the one before it
     pub regex_rules: Vec<RegexRule>,
     /// Normalize line endings (CRLF vs LF)
     pub normalize_line_endings: bool,
-    /// Tab width for expanding tabs to spaces
+    /// Tab width for expanding tabs to spaces (used by
+    /// [`WhitespaceMode::ExpandTabs`]; zero leaves tabs as they are)
     pub tab_width: usize,
+    /// Line diff algorithm
+    pub algorithm: DiffAlgorithm,
+    /// Mark whitespace differences on lines that compare equal
+    pub show_whitespace_changes: bool,
+    /// Like `diff -I`: a changed line whose old and new versions both match
+    /// one of these patterns is reported as `Equal`. Patterns are matched
+    /// against the preprocessed line without its line ending.
+    pub ignore_matching_lines: Vec<Regex>,
+    /// Minimum token similarity (0.0–1.0) for a deleted and an inserted line
+    /// of the same hunk to be paired as old and new versions of one line;
+    /// `None` disables pairing
+    pub line_pairing_threshold: Option<f64>,
+    /// Lines longer than this many bytes (minified JS or CSS, say) are only
+    /// compared as whole lines: they skip syntax highlighting, line pairing,
+    /// intra-line and whitespace diffing. `None` applies no limit.
+    pub max_line_length: Option<usize>,
+    /// Time budget for the line diff; once spent, the regions still being
+    /// searched are reported as plain deletions and insertions. `None` (the
+    /// default) applies no limit. A budget makes the result depend on
+    /// machine load, so it suits interactive views rather than reports or
+    /// patches.
+    pub diff_timeout: Option<Duration>,
 }
 
 impl TextDiffConfig {
+    /// Default [`TextDiffConfig::max_line_length`]: 10KB
+    pub const DEFAULT_MAX_LINE_LENGTH: usize = 10 * 1024;
+
+    /// Suggested [`TextDiffConfig::diff_timeout`] for interactive views: 2
+    /// seconds
+    pub const DEFAULT_DIFF_TIMEOUT: Duration = Duration::from_secs(2);
+
     pub fn new() -> Self {
         Self {
             ignore_case: false,
@@ -87,6 +338,12 @@ impl TextDiffConfig {
             regex_rules: Vec::new(),
             normalize_line_endings: true,
             tab_width: 4,
+            algorithm: DiffAlgorithm::Patience,
+            show_whitespace_changes: false,
+            ignore_matching_lines: Vec::new(),
+            line_pairing_threshold: Some(0.5),
+            max_line_length: Some(Self::DEFAULT_MAX_LINE_LENGTH),
+            diff_timeout: None,
         }
     }
 
@@ -103,6 +360,137 @@ impl TextDiffConfig {
             ..Default::default()
         }
     }
+
+    /// Start a [`TextDiffConfigBuilder`] from the [`new`](Self::new) defaults
+    pub fn builder() -> TextDiffConfigBuilder {
+        TextDiffConfigBuilder {
+            config: Self::new(),
+            error: None,
+        }
+    }
+
+    /// Build a configuration from the text options of the command line
+    ///
+    /// `ignore_whitespace` is parsed as a [`WhitespaceMode`], each regex rule
+    /// with [`RegexRule::parse`] and each ignore-matching-lines entry as a
+    /// regular expression. The first invalid value is reported.
+    pub fn from_cli_args(
+        ignore_whitespace: Option<&str>,
+        ignore_case: bool,
+        regex_rules: &[String],
+        ignore_matching_lines: &[String],
+    ) -> Result<Self, RCompareError> {
+        let mut builder = Self::builder().ignore_case(ignore_case);
+        if let Some(mode) = ignore_whitespace {
+            builder = builder.whitespace(mode.parse()?);
+        }
+        for rule in regex_rules {
+            builder = builder.regex_rule(rule);
+        }
+        for pattern in ignore_matching_lines {
+            builder = builder.ignore_matching_line(pattern);
+        }
+        builder.build()
+    }
+}
+
+/// Fluent builder for [`TextDiffConfig`], from [`TextDiffConfig::builder`]
+///
+/// Methods taking text parse it right away; the first value that fails is
+/// returned by [`build`](Self::build).
+#[derive(Debug)]
+pub struct TextDiffConfigBuilder {
+    config: TextDiffConfig,
+    error: Option<RCompareError>,
+}
+
+impl TextDiffConfigBuilder {
+    pub fn whitespace(mut self, mode: WhitespaceMode) -> Self {
+        self.config.whitespace_mode = mode;
+        self
+    }
+
+    pub fn ignore_case(mut self, enabled: bool) -> Self {
+        self.config.ignore_case = enabled;
+        self
+    }
+
+    /// Append a `pattern:replacement[:description]` rule (see
+    /// [`RegexRule::parse`]); rules apply in the order added
+    pub fn regex_rule(mut self, spec: &str) -> Self {
+        match RegexRule::parse(spec) {
+            Ok(rule) => self.config.regex_rules.push(rule),
+            Err(e) => self.fail(e),
+        }
+        self
+    }
+
+    /// Add a pattern for [`TextDiffConfig::ignore_matching_lines`]
+    pub fn ignore_matching_line(mut self, pattern: &str) -> Self {
+        match Regex::new(pattern) {
+            Ok(regex) => self.config.ignore_matching_lines.push(regex),
+            Err(e) => self.fail(RCompareError::Config(format!(
+                "Invalid ignore-matching-lines pattern '{}': {}",
+                pattern, e
+            ))),
+        }
+        self
+    }
+
+    pub fn normalize_line_endings(mut self, enabled: bool) -> Self {
+        self.config.normalize_line_endings = enabled;
+        self
+    }
+
+    pub fn tab_width(mut self, width: usize) -> Self {
+        self.config.tab_width = width;
+        self
+    }
+
+    pub fn algorithm(mut self, algorithm: DiffAlgorithm) -> Self {
+        self.config.algorithm = algorithm;
+        self
+    }
+
+    pub fn show_whitespace_changes(mut self, enabled: bool) -> Self {
+        self.config.show_whitespace_changes = enabled;
+        self
+    }
+
+    /// Set [`TextDiffConfig::line_pairing_threshold`], which must lie in
+    /// 0.0 - 1.0
+    pub fn line_pairing_threshold(mut self, threshold: Option<f64>) -> Self {
+        match threshold {
+            Some(t) if !(0.0..=1.0).contains(&t) => self.fail(RCompareError::Config(format!(
+                "Invalid line pairing threshold {}. Expected a value from 0.0 to 1.0",
+                t
+            ))),
+            _ => self.config.line_pairing_threshold = threshold,
+        }
+        self
+    }
+
+    pub fn max_line_length(mut self, max: Option<usize>) -> Self {
+        self.config.max_line_length = max;
+        self
+    }
+
+    pub fn diff_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.config.diff_timeout = timeout;
+        self
+    }
+
+    /// The configuration, or the error of the first invalid value
+    pub fn build(self) -> Result<TextDiffConfig, RCompareError> {
+        match self.error {
+            Some(e) => Err(e),
+            None => Ok(self.config),
+        }
+    }
+
+    fn fail(&mut self, error: RCompareError) {
+        self.error.get_or_insert(error);
+    }
 }
 
 /// Text diff engine with syntax highlighting support
@@ -137,6 +525,13 @@ impl TextDiffEngine {
         &self.config
     }
 
+    /// Whether `line` exceeds [`TextDiffConfig::max_line_length`]
+    fn is_long_line(&self, line: &str) -> bool {
+        self.config
+            .max_line_length
+            .is_some_and(|max| line.len() > max)
+    }
+
     /// Preprocess text according to configuration options
     fn preprocess_text(&self, text: &str) -> String {
         let mut result = text.to_string();
@@ -151,12 +546,9 @@ impl TextDiffEngine {
             result = result.to_lowercase();
         }
 
-        // Apply regex rules
+        // Apply regex rules in order, each to the previous rule's output
         for rule in &self.config.regex_rules {
-            result = rule
-                .pattern
-                .replace_all(&result, &rule.replacement)
-                .to_string();
+            result = rule.apply(&result).into_owned();
         }
 
         // Apply whitespace handling
@@ -186,6 +578,11 @@ impl TextDiffEngine {
                 .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
                 .collect::<Vec<_>>()
                 .join("\n"),
+            WhitespaceMode::ExpandTabs => result
+                .lines()
+                .map(|line| expand_tabs(line, self.config.tab_width))
+                .collect::<Vec<_>>()
+                .join("\n"),
         }
     }
 
@@ -195,13 +592,34 @@ impl TextDiffEngine {
         left_path: &Path,
         right_path: &Path,
     ) -> Result<Vec<DiffLine>, RCompareError> {
-        let left_content = fs::read_to_string(left_path)?;
-        let right_content = fs::read_to_string(right_path)?;
+        let left_content = read_text_file(left_path)?;
+        let right_content = read_text_file(right_path)?;
 
         self.compare_text(&left_content, &right_content, left_path)
     }
 
-    /// Compare two text strings with Myers algorithm
+    /// Compare text read from two readers, such as stdin and an open file
+    ///
+    /// Each side is read to the end and decoded like [`read_text_file`]
+    /// does, with `file_path` as the name hint for encoding detection and
+    /// syntax highlighting, since a stream has no name of its own.
+    pub fn compare_readers(
+        &self,
+        mut left: impl Read,
+        mut right: impl Read,
+        file_path: &Path,
+    ) -> Result<Vec<DiffLine>, RCompareError> {
+        let mut left_bytes = Vec::new();
+        left.read_to_end(&mut left_bytes)?;
+        let mut right_bytes = Vec::new();
+        right.read_to_end(&mut right_bytes)?;
+
+        let left_content = decode_text(&left_bytes, Some(file_path))?;
+        let right_content = decode_text(&right_bytes, Some(file_path))?;
+        self.compare_text(&left_content, &right_content, file_path)
+    }
+
+    /// Compare two text strings using the configured [`DiffAlgorithm`]
     pub fn compare_text(
         &self,
         left: &str,
@@ -212,60 +630,160 @@ impl TextDiffEngine {
         let left_processed = self.preprocess_text(left);
         let right_processed = self.preprocess_text(right);
 
-        let diff = TextDiff::from_lines(&left_processed, &right_processed);
-        let mut result = Vec::new();
+        let mut lines = self.diff_lines(
+            &left_processed,
+            &right_processed,
+            file_path,
+            self.config.algorithm,
+        );
+        if !self.config.ignore_matching_lines.is_empty() {
+            lines = self.suppress_ignored_changes(lines);
+        }
+        if let Some(threshold) = self.config.line_pairing_threshold {
+            pair_edited_lines(&mut lines, threshold, self.config.max_line_length);
+        }
+        if self.config.show_whitespace_changes {
+            self.mark_whitespace_changes(&mut lines, left, right);
+        }
+        Ok(lines)
+    }
 
-        let mut left_line_num = 1;
-        let mut right_line_num = 1;
+    /// Diff `left` and `right` against their common ancestor `base` for
+    /// review, without merging
+    ///
+    /// Each side is diffed against `base` with the configured algorithm
+    /// after preprocessing. Base lines kept by both sides anchor the
+    /// result; they form [`Diff3Kind::Shared`] regions, and every stretch
+    /// between anchors is tagged by which inputs it differs in. Lines are
+    /// reported as preprocessed, like [`compare_text`](Self::compare_text).
+    /// To resolve the changes instead, see [`crate::MergeEngine`].
+    pub fn diff3(&self, base: &str, left: &str, right: &str) -> Vec<Diff3Region> {
+        let base = self.preprocess_text(base);
+        let left = self.preprocess_text(left);
+        let right = self.preprocess_text(right);
+        let base: Vec<&str> = base.split_inclusive('\n').collect();
+        let left: Vec<&str> = left.split_inclusive('\n').collect();
+        let right: Vec<&str> = right.split_inclusive('\n').collect();
+        let chunks = diff3_chunks(
+            &base,
+            &left,
+            &right,
+            &self.line_ops(&base, &left, self.config.algorithm),
+            &self.line_ops(&base, &right, self.config.algorithm),
+        );
 
-        // Detect syntax for highlighting
-        let syntax = self
-            .syntax_set
-            .find_syntax_for_file(file_path)
-            .ok()
-            .flatten()
-            .or_else(|| Some(self.syntax_set.find_syntax_plain_text()));
+        let aligned = |lines: &[&str], range: Range<usize>| -> Vec<AlignedLine> {
+            lines[range.clone()]
+                .iter()
+                .zip(range.start + 1..)
+                .map(|(line, number)| AlignedLine {
+                    number,
+                    text: line.trim_end_matches(['\n', '\r']).to_string(),
+                })
+                .collect()
+        };
+        chunks
+            .into_iter()
+            .map(|chunk| Diff3Region {
+                kind: chunk.kind,
+                base: aligned(&base, chunk.base),
+                left: aligned(&left, chunk.left),
+                right: aligned(&right, chunk.right),
+            })
+            .collect()
+    }
 
-        for change in diff.iter_all_changes() {
-            let change_type = match change.tag() {
-                ChangeTag::Equal => DiffChangeType::Equal,
-                ChangeTag::Insert => DiffChangeType::Insert,
-                ChangeTag::Delete => DiffChangeType::Delete,
-            };
+    /// Turn changed lines matching [`TextDiffConfig::ignore_matching_lines`]
+    /// into `Equal` lines
+    ///
+    /// Within each run of changes the n-th deleted line is paired with the
+    /// n-th inserted line. A pair where both lines match is merged into one
+    /// `Equal` line; the rest of the run keeps its deletes before its inserts.
+    fn suppress_ignored_changes(&self, lines: Vec<DiffLine>) -> Vec<DiffLine> {
+        let ignored = |line: &DiffLine| {
+            let text = line.content.trim_end_matches(['\n', '\r']);
+            self.config
+                .ignore_matching_lines
+                .iter()
+                .any(|re| re.is_match(text))
+        };
 
-            let (line_left, line_right) = match change_type {
-                DiffChangeType::Equal => {
-                    let l = Some(left_line_num);
-                    let r = Some(right_line_num);
-                    left_line_num += 1;
-                    right_line_num += 1;
-                    (l, r)
-                }
-                DiffChangeType::Insert => {
-                    let r = Some(right_line_num);
-                    right_line_num += 1;
-                    (None, r)
+        let mut result = Vec::with_capacity(lines.len());
+        let mut lines = lines.into_iter().peekable();
+        while let Some(line) = lines.next() {
+            if line.change_type == DiffChangeType::Equal {
+                result.push(line);
+                continue;
+            }
+
+            let mut deleted = Vec::new();
+            let mut inserted = Vec::new();
+            let mut next = Some(line);
+            while let Some(line) = next {
+                match line.change_type {
+                    DiffChangeType::Delete => deleted.push(line),
+                    DiffChangeType::Insert => inserted.push(line),
+                    DiffChangeType::Equal => unreachable!("runs stop at equal lines"),
                 }
-                DiffChangeType::Delete => {
-                    let l = Some(left_line_num);
-                    left_line_num += 1;
-                    (l, None)
+                next = lines.next_if(|l| l.change_type != DiffChangeType::Equal);
+            }
+
+            let mut pending_deleted = Vec::new();
+            let mut pending_inserted = Vec::new();
+            let mut inserted = inserted.into_iter();
+            for old in deleted {
+                let Some(new) = inserted.next() else {
+                    pending_deleted.push(old);
+                    continue;
+                };
+                if ignored(&old) && ignored(&new) {
+                    result.append(&mut pending_deleted);
+                    result.append(&mut pending_inserted);
+                    result.push(DiffLine {
+                        line_number_right: new.line_number_right,
+                        change_type: DiffChangeType::Equal,
+                        ..old
+                    });
+                } else {
+                    pending_deleted.push(old);
+                    pending_inserted.push(new);
                 }
-            };
+            }
+            result.append(&mut pending_deleted);
+            result.append(&mut pending_inserted);
+            result.extend(inserted);
+        }
+        result
+    }
 
-            let content = change.to_string();
-            let highlighted = self.highlight_line(&content, syntax);
+    /// Attach whitespace markers to equal lines whose originals differ
+    fn mark_whitespace_changes(&self, lines: &mut [DiffLine], left: &str, right: &str) {
+        let left_lines: Vec<&str> = left.lines().collect();
+        let right_lines: Vec<&str> = right.lines().collect();
 
-            result.push(DiffLine {
-                line_number_left: line_left,
-                line_number_right: line_right,
-                content,
-                change_type,
-                highlighted_segments: highlighted,
-            });
+        for line in lines
+            .iter_mut()
+            .filter(|l| l.change_type == DiffChangeType::Equal)
+        {
+            let (Some(l), Some(r)) = (
+                line.line_number_left
+                    .and_then(|n| left_lines.get(n - 1))
+                    .map(|l| l.trim_end_matches('\r')),
+                line.line_number_right
+                    .and_then(|n| right_lines.get(n - 1))
+                    .map(|r| r.trim_end_matches('\r')),
+            ) else {
+                continue;
+            };
+            if self.is_long_line(l) || self.is_long_line(r) {
+                continue;
+            }
+            // Regex rules may join or split lines, so only trust the pairing
+            // when the originals really compare equal on their own
+            if l != r && self.preprocess_text(l) == self.preprocess_text(r) {
+                line.whitespace_markers = whitespace_markers(l, r);
+            }
         }
-
-        Ok(result)
     }
 
     /// Compare with Patience algorithm (better for code)
@@ -275,15 +793,30 @@ impl TextDiffEngine {
         right: &str,
         file_path: &Path,
     ) -> Result<Vec<DiffLine>, RCompareError> {
-        // Use patience algorithm from similar crate
-        let diff = TextDiff::configure()
-            .algorithm(similar::Algorithm::Patience)
-            .diff_lines(left, right);
+        let mut lines = self.diff_lines(left, right, file_path, DiffAlgorithm::Patience);
+        if let Some(threshold) = self.config.line_pairing_threshold {
+            pair_edited_lines(&mut lines, threshold, self.config.max_line_length);
+        }
+        Ok(lines)
+    }
+
+    fn diff_lines(
+        &self,
+        left: &str,
+        right: &str,
+        file_path: &Path,
+        algorithm: DiffAlgorithm,
+    ) -> Vec<DiffLine> {
+        let left_lines: Vec<&str> = left.split_inclusive('\n').collect();
+        let right_lines: Vec<&str> = right.split_inclusive('\n').collect();
+
+        let ops = self.line_ops(&left_lines, &right_lines, algorithm);
 
         let mut result = Vec::new();
         let mut left_line_num = 1;
         let mut right_line_num = 1;
 
+        // Detect syntax for highlighting
         let syntax = self
             .syntax_set
             .find_syntax_for_file(file_path)
@@ -291,7 +824,10 @@ impl TextDiffEngine {
             .flatten()
             .or_else(|| Some(self.syntax_set.find_syntax_plain_text()));
 
-        for change in diff.iter_all_changes() {
+        for change in ops
+            .iter()
+            .flat_map(|op| op.iter_changes(&left_lines, &right_lines))
+        {
             let change_type = match change.tag() {
                 ChangeTag::Equal => DiffChangeType::Equal,
                 ChangeTag::Insert => DiffChangeType::Insert,
@@ -318,8 +854,14 @@ impl TextDiffEngine {
                 }
             };
 
-            let content = change.to_string();
-            let highlighted = self.highlight_line(&content, syntax);
+            // Not `to_string()`, which adds a newline to a last line without one
+            let content = change.value().to_string();
+            // Highlighting a huge line can take longer than the diff itself
+            let highlighted = if self.is_long_line(&content) {
+                vec![plain_segment(&content)]
+            } else {
+                self.highlight_line(&content, syntax)
+            };
 
             result.push(DiffLine {
                 line_number_left: line_left,
@@ -327,14 +869,101 @@ impl TextDiffEngine {
                 content,
                 change_type,
                 highlighted_segments: highlighted,
+                whitespace_markers: Vec::new(),
+                paired_line: None,
             });
         }
 
-        Ok(result)
+        result
+    }
+
+    /// Diff two line lists with `algorithm`, within the configured timeout
+    fn line_ops(&self, left: &[&str], right: &[&str], algorithm: DiffAlgorithm) -> Vec<DiffOp> {
+        let deadline = self.config.diff_timeout.map(|t| Instant::now() + t);
+        match algorithm {
+            DiffAlgorithm::Myers => similar::capture_diff_slices_deadline(
+                similar::Algorithm::Myers,
+                left,
+                right,
+                deadline,
+            ),
+            DiffAlgorithm::Patience => similar::capture_diff_slices_deadline(
+                similar::Algorithm::Patience,
+                left,
+                right,
+                deadline,
+            ),
+            DiffAlgorithm::Histogram => histogram_diff(left, right, deadline),
+        }
+    }
+
+    /// Rebuild one side of a diff from its lines
+    ///
+    /// The left side is the `Equal` and `Delete` lines, the right side the
+    /// `Equal` and `Insert` lines, in order. Each returned line keeps its
+    /// line ending, so joining them gives back the compared text. That is
+    /// the original text only when the configuration leaves lines untouched
+    /// ([`WhitespaceMode::Exact`], no case folding, regex rules or line
+    /// ending normalization), since lines are stored after preprocessing and
+    /// `Equal` lines carry the left side's version.
+    pub fn reconstruct(side: DiffSide, lines: &[DiffLine]) -> Vec<String> {
+        let skipped = match side {
+            DiffSide::Left => DiffChangeType::Insert,
+            DiffSide::Right => DiffChangeType::Delete,
+        };
+        lines
+            .iter()
+            .filter(|line| line.change_type != skipped)
+            .map(|line| line.content.clone())
+            .collect()
+    }
+
+    /// Diff every text file that differs in a comparison and count its
+    /// changed lines
+    ///
+    /// Only nodes present on both sides with a `Different` or `Unchecked`
+    /// status are considered, and of those only the ones `is_text` accepts
+    /// given the node and its left and right file paths. Files that cannot
+    /// be read or compared are logged and left out.
+    pub fn diff_stats(
+        &self,
+        left_root: &Path,
+        right_root: &Path,
+        nodes: &[DiffNode],
+        is_text: impl Fn(&DiffNode, &Path, &Path) -> bool,
+    ) -> Vec<TextDiffStats> {
+        nodes
+            .iter()
+            .filter(|node| matches!(node.status, DiffStatus::Different | DiffStatus::Unchecked))
+            .filter_map(|node| {
+                let left_path = left_root.join(&node.left.as_ref()?.path);
+                let right_path = right_root.join(&node.right.as_ref()?.path);
+                if !is_text(node, &left_path, &right_path) {
+                    return None;
+                }
+                match self.compare_files(&left_path, &right_path) {
+                    Ok(lines) => Some(TextDiffStats::from_lines(&node.relative_path, &lines)),
+                    Err(e) => {
+                        warn!("Skipping {}: {}", node.relative_path.display(), e);
+                        None
+                    }
+                }
+            })
+            .collect()
     }
 
     /// Perform intra-line character diff
+    ///
+    /// When either line exceeds [`TextDiffConfig::max_line_length`], both
+    /// are reported as changed in full rather than diffed character by
+    /// character.
     pub fn intra_line_diff(&self, left_line: &str, right_line: &str) -> Vec<(String, bool)> {
+        if self.is_long_line(left_line) || self.is_long_line(right_line) {
+            return vec![
+                (left_line.to_string(), true),
+                (right_line.to_string(), true),
+            ];
+        }
         let diff = TextDiff::from_chars(left_line, right_line);
         let mut result = Vec::new();
 
@@ -353,17 +982,7 @@ impl TextDiffEngine {
     ) -> Vec<HighlightedSegment> {
         let syntax = match syntax {
             Some(s) => s,
-            None => {
-                return vec![HighlightedSegment {
-                    text: line.to_string(),
-                    style: HighlightStyle {
-                        foreground: (200, 200, 200),
-                        background: None,
-                        bold: false,
-                        italic: false,
-                    },
-                }]
-            }
+            None => return vec![plain_segment(line)],
         };
 
         let theme = &self.theme_set.themes["base16-ocean.dark"];
@@ -395,27 +1014,604 @@ impl TextDiffEngine {
         }
 
         if segments.is_empty() {
-            segments.push(HighlightedSegment {
-                text: line.to_string(),
-                style: HighlightStyle {
-                    foreground: (200, 200, 200),
-                    background: None,
-                    bold: false,
-                    italic: false,
-                },
-            });
+            segments.push(plain_segment(line));
         }
 
         segments
     }
 }
 
+/// `text` as a single unhighlighted segment
+fn plain_segment(text: &str) -> HighlightedSegment {
+    HighlightedSegment {
+        text: text.to_string(),
+        style: HighlightStyle {
+            foreground: (200, 200, 200),
+            background: None,
+            bold: false,
+            italic: false,
+        },
+    }
+}
+
 impl Default for TextDiffEngine {
     fn default() -> Self {
         Self::new()
     }
 }
 
+/// For each of the `len` old lines, its index on the new side if `ops`
+/// keep it
+fn kept_lines(ops: &[DiffOp], len: usize) -> Vec<Option<usize>> {
+    let mut kept = vec![None; len];
+    for op in ops {
+        if let DiffOp::Equal {
+            old_index,
+            new_index,
+            len,
+        } = *op
+        {
+            for i in 0..len {
+                kept[old_index + i] = Some(new_index + i);
+            }
+        }
+    }
+    kept
+}
+
+/// One stretch of a three-way line comparison, as ranges of base, left and
+/// right lines
+pub(crate) struct Diff3Chunk {
+    pub(crate) kind: Diff3Kind,
+    pub(crate) base: Range<usize>,
+    pub(crate) left: Range<usize>,
+    pub(crate) right: Range<usize>,
+}
+
+/// Split three versions of a text into chunks, given the line diffs of
+/// `left` and `right` against `base`
+///
+/// Runs of base lines both sides kept in step are [`Diff3Kind::Shared`];
+/// every stretch between them is tagged by which inputs it differs in.
+/// [`TextDiffEngine::diff3`] shows the chunks and
+/// [`crate::MergeEngine::merge_regions`] resolves them, so both see the same
+/// chunks.
+pub(crate) fn diff3_chunks(
+    base: &[&str],
+    left: &[&str],
+    right: &[&str],
+    left_ops: &[DiffOp],
+    right_ops: &[DiffOp],
+) -> Vec<Diff3Chunk> {
+    let in_left = kept_lines(left_ops, base.len());
+    let in_right = kept_lines(right_ops, base.len());
+
+    let mut chunks = Vec::new();
+    let (mut b, mut l, mut r) = (0, 0, 0);
+    while b < base.len() || l < left.len() || r < right.len() {
+        // Shared run of base lines both sides kept in step
+        let mut end = 0;
+        while b + end < base.len()
+            && in_left[b + end] == Some(l + end)
+            && in_right[b + end] == Some(r + end)
+        {
+            end += 1;
+        }
+        if end > 0 {
+            chunks.push(Diff3Chunk {
+                kind: Diff3Kind::Shared,
+                base: b..b + end,
+                left: l..l + end,
+                right: r..r + end,
+            });
+            (b, l, r) = (b + end, l + end, r + end);
+            continue;
+        }
+
+        // Unstable chunk up to the next base line both sides kept
+        let (next_b, next_l, next_r) = (b..base.len())
+            .find_map(|i| Some((i, in_left[i]?, in_right[i]?)))
+            .unwrap_or((base.len(), left.len(), right.len()));
+        let (base_chunk, left_chunk, right_chunk) =
+            (&base[b..next_b], &left[l..next_l], &right[r..next_r]);
+        let kind = if left_chunk == base_chunk && right_chunk == base_chunk {
+            Diff3Kind::Shared
+        } else if left_chunk == base_chunk {
+            Diff3Kind::RightOnly
+        } else if right_chunk == base_chunk {
+            Diff3Kind::LeftOnly
+        } else if left_chunk == right_chunk {
+            Diff3Kind::BaseOnly
+        } else {
+            Diff3Kind::Conflict
+        };
+        chunks.push(Diff3Chunk {
+            kind,
+            base: b..next_b,
+            left: l..next_l,
+            right: r..next_r,
+        });
+        (b, l, r) = (next_b, next_l, next_r);
+    }
+    chunks
+}
+
+/// Arrange diff lines into rows for a side-by-side view
+///
+/// Equal lines fill both sides of a row. Within a run of changes, lines
+/// linked by [`DiffLine::paired_line`] share a row and the other lines get
+/// a filler opposite them; when no line of the run is linked, the n-th
+/// deleted line is shown next to the n-th inserted line.
+pub fn align_rows(lines: &[DiffLine]) -> Vec<AlignedRow> {
+    let aligned = |line: &DiffLine, number: Option<usize>| {
+        number.map(|number| AlignedLine {
+            number,
+            text: line.content.trim_end_matches(['\n', '\r']).to_string(),
+        })
+    };
+    let left = |line: &DiffLine| aligned(line, line.line_number_left);
+    let right = |line: &DiffLine| aligned(line, line.line_number_right);
+
+    let mut rows = Vec::with_capacity(lines.len());
+    let mut start = 0;
+    while start < lines.len() {
+        if lines[start].change_type == DiffChangeType::Equal {
+            rows.push(AlignedRow {
+                left: left(&lines[start]),
+                right: right(&lines[start]),
+                changed: false,
+            });
+            start += 1;
+            continue;
+        }
+        let end = lines[start..]
+            .iter()
+            .position(|l| l.change_type == DiffChangeType::Equal)
+            .map_or(lines.len(), |n| start + n);
+        let run = &lines[start..end];
+        start = end;
+
+        let row = |old: Option<&DiffLine>, new: Option<&DiffLine>| AlignedRow {
+            left: old.and_then(left),
+            right: new.and_then(right),
+            changed: true,
+        };
+        let deleted: Vec<_> = run
+            .iter()
+            .filter(|l| l.change_type == DiffChangeType::Delete)
+            .collect();
+        let mut inserted = run
+            .iter()
+            .filter(|l| l.change_type == DiffChangeType::Insert)
+            .peekable();
+
+        if run.iter().any(|l| l.paired_line.is_some()) {
+            for old in deleted {
+                let Some(partner) = old.paired_line else {
+                    rows.push(row(Some(old), None));
+                    continue;
+                };
+                while let Some(new) =
+                    inserted.next_if(|l| l.line_number_right.is_some_and(|n| n < partner))
+                {
+                    rows.push(row(None, Some(new)));
+                }
+                let new = inserted.next_if(|l| l.line_number_right == Some(partner));
+                rows.push(row(Some(old), new));
+            }
+        } else {
+            for old in deleted {
+                rows.push(row(Some(old), inserted.next()));
+            }
+        }
+        rows.extend(inserted.map(|new| row(None, Some(new))));
+    }
+    rows
+}
+
+/// Hunks with more delete × insert combinations than this are left unpaired
+const MAX_PAIRING_CELLS: usize = 40_000;
+
+/// Link each edited line in a run of changes to its counterpart on the other
+/// side
+///
+/// Deleted and inserted lines of a run are matched in order, choosing the
+/// pairs that maximise the total similarity among those scoring at least
+/// `threshold`, so word-level highlighting compares the right old and new
+/// lines. Lines left without a partner stay plain deletions or insertions,
+/// as do lines longer than `max_line_length`.
+fn pair_edited_lines(lines: &mut [DiffLine], threshold: f64, max_line_length: Option<usize>) {
+    let mut start = 0;
+    while start < lines.len() {
+        if lines[start].change_type == DiffChangeType::Equal {
+            start += 1;
+            continue;
+        }
+        let end = lines[start..]
+            .iter()
+            .position(|l| l.change_type == DiffChangeType::Equal)
+            .map_or(lines.len(), |n| start + n);
+        let run = &mut lines[start..end];
+        start = end;
+
+        let of_type = |change_type| {
+            run.iter()
+                .enumerate()
+                .filter(|(_, l)| l.change_type == change_type)
+                .filter(|(_, l)| max_line_length.is_none_or(|max| l.content.len() <= max))
+                .map(|(i, _)| i)
+                .collect::<Vec<_>>()
+        };
+        let deleted = of_type(DiffChangeType::Delete);
+        let inserted = of_type(DiffChangeType::Insert);
+        if deleted.is_empty()
+            || inserted.is_empty()
+            || deleted.len() * inserted.len() > MAX_PAIRING_CELLS
+        {
+            continue;
+        }
+
+        let tokens = |indices: &[usize]| {
+            indices
+                .iter()
+                .map(|&i| line_tokens(&run[i].content))
+                .collect::<Vec<_>>()
+        };
+        let pairs = pair_by_similarity(&tokens(&deleted), &tokens(&inserted), threshold);
+        for (d, i) in pairs {
+            let (old, new) = (deleted[d], inserted[i]);
+            run[old].paired_line = run[new].line_number_right;
+            run[new].paired_line = run[old].line_number_left;
+        }
+    }
+}
+
+/// Order-preserving matching of `old` to `new` lines with the highest total
+/// similarity, considering only pairs at or above `threshold`
+fn pair_by_similarity(old: &[Vec<&str>], new: &[Vec<&str>], threshold: f64) -> Vec<(usize, usize)> {
+    let (n, m) = (old.len(), new.len());
+    let score = |i: usize, j: usize| {
+        let similarity = token_similarity(&old[i], &new[j]);
+        (similarity > 0.0 && similarity >= threshold).then_some(similarity)
+    };
+
+    // best[i][j]: best total for old[i..] against new[j..]
+    let mut best = vec![vec![0.0f64; m + 1]; n + 1];
+    let mut pair_score = vec![vec![None; m]; n];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            pair_score[i][j] = score(i, j);
+            let skip = best[i + 1][j].max(best[i][j + 1]);
+            best[i][j] = match pair_score[i][j] {
+                Some(s) => skip.max(s + best[i + 1][j + 1]),
+                None => skip,
+            };
+        }
+    }
+
+    let mut pairs = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        match pair_score[i][j] {
+            Some(s) if best[i][j] == s + best[i + 1][j + 1] => {
+                pairs.push((i, j));
+                i += 1;
+                j += 1;
+            }
+            _ if best[i][j] == best[i + 1][j] => i += 1,
+            _ => j += 1,
+        }
+    }
+    pairs
+}
+
+/// Words (runs of alphanumerics and `_`) and individual punctuation
+/// characters of a line; whitespace only separates tokens
+fn line_tokens(line: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut word_start = None;
+    for (i, c) in line.char_indices() {
+        if c.is_alphanumeric() || c == '_' {
+            word_start.get_or_insert(i);
+            continue;
+        }
+        if let Some(start) = word_start.take() {
+            tokens.push(&line[start..i]);
+        }
+        if !c.is_whitespace() {
+            tokens.push(&line[i..i + c.len_utf8()]);
+        }
+    }
+    if let Some(start) = word_start {
+        tokens.push(&line[start..]);
+    }
+    tokens
+}
+
+/// Dice coefficient of two token multisets: twice the shared tokens over the
+/// total, 0.0 when both are empty
+fn token_similarity(left: &[&str], right: &[&str]) -> f64 {
+    let total = left.len() + right.len();
+    if total == 0 {
+        return 0.0;
+    }
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for token in left {
+        *counts.entry(token).or_default() += 1;
+    }
+    let mut shared = 0;
+    for token in right {
+        if let Some(count) = counts.get_mut(token).filter(|c| **c > 0) {
+            *count -= 1;
+            shared += 1;
+        }
+    }
+    (2 * shared) as f64 / total as f64
+}
+
+/// Replace each tab with spaces up to the next multiple of `tab_width`
+///
+/// Columns are counted in characters from the start of the line, so a tab
+/// after mixed indentation fills only the rest of its tab stop.
+fn expand_tabs(line: &str, tab_width: usize) -> String {
+    if tab_width == 0 || !line.contains('\t') {
+        return line.to_string();
+    }
+
+    let mut expanded = String::with_capacity(line.len());
+    let mut column = 0;
+    for c in line.chars() {
+        if c == '\t' {
+            let pad = tab_width - column % tab_width;
+            expanded.push_str(&" ".repeat(pad));
+            column += pad;
+        } else {
+            expanded.push(c);
+            column += 1;
+        }
+    }
+    expanded
+}
+
+/// Whitespace differences between two lines, trailing whitespace first
+fn whitespace_markers(left: &str, right: &str) -> Vec<WhitespaceMarker> {
+    let left_body = left.trim_end();
+    let right_body = right.trim_end();
+    let mut markers = Vec::new();
+
+    if left[left_body.len()..] != right[right_body.len()..] {
+        markers.push(WhitespaceMarker {
+            kind: WhitespaceMarkerKind::Trailing,
+            left: left_body.len()..left.len(),
+            right: right_body.len()..right.len(),
+        });
+    }
+
+    let diff = TextDiff::from_chars(left_body, right_body);
+    // Byte offset of each char slice, plus the end of the line
+    let offsets = |slices: &[&str]| -> Vec<usize> {
+        std::iter::once(0)
+            .chain(slices.iter().scan(0, |pos, s| {
+                *pos += s.len();
+                Some(*pos)
+            }))
+            .collect()
+    };
+    let left_offsets = offsets(diff.old_slices());
+    let right_offsets = offsets(diff.new_slices());
+
+    for op in diff.ops() {
+        if matches!(op, DiffOp::Equal { .. }) {
+            continue;
+        }
+        let left_range = left_offsets[op.old_range().start]..left_offsets[op.old_range().end];
+        let right_range = right_offsets[op.new_range().start]..right_offsets[op.new_range().end];
+        let (left_text, right_text) = (
+            &left_body[left_range.clone()],
+            &right_body[right_range.clone()],
+        );
+        if !left_text
+            .chars()
+            .chain(right_text.chars())
+            .all(char::is_whitespace)
+        {
+            continue;
+        }
+        let kind = if (left_text.contains('\t') && right_text.contains(' '))
+            || (left_text.contains(' ') && right_text.contains('\t'))
+        {
+            WhitespaceMarkerKind::TabSpace
+        } else {
+            WhitespaceMarkerKind::Spacing
+        };
+        markers.push(WhitespaceMarker {
+            kind,
+            left: left_range,
+            right: right_range,
+        });
+    }
+
+    markers
+}
+
+/// Lines occurring more often than this in a region are never used as anchors
+const HISTOGRAM_MAX_OCCURRENCES: usize = 64;
+
+/// Histogram diff over two line slices.
+///
+/// Anchors on the longest run of common lines whose rarest line occurs least
+/// often on the left, then diffs the regions before and after it the same
+/// way. Regions without a usable anchor are diffed with Myers. Past
+/// `deadline`, regions left are reported as deleted and inserted without
+/// searching them. The result goes through the same replace/compact passes as
+/// the `similar` algorithms.
+fn histogram_diff(old: &[&str], new: &[&str], deadline: Option<Instant>) -> Vec<DiffOp> {
+    let mut hook = Compact::new(Replace::new(Capture::new()), old, new);
+    let result = histogram_regions(&mut hook, old, new, deadline).and_then(|()| hook.finish());
+    if let Err(never) = result {
+        match never {}
+    }
+    hook.into_inner().into_inner().into_ops()
+}
+
+/// Work left for [`histogram_regions`], taken last in, first out
+enum HistogramStep {
+    /// Old and new line ranges still to diff
+    Region(Range<usize>, Range<usize>),
+    /// A run of `len` equal lines at old and new starts to report
+    Equal(usize, usize, usize),
+}
+
+/// Diff `old` against `new` anchor by anchor
+///
+/// The regions around each anchor go on an explicit stack rather than
+/// recursing, so inputs that split into many nested regions can't overflow
+/// the call stack.
+fn histogram_regions<D: DiffHook>(
+    hook: &mut D,
+    old: &[&str],
+    new: &[&str],
+    deadline: Option<Instant>,
+) -> Result<(), D::Error> {
+    let mut steps = vec![HistogramStep::Region(0..old.len(), 0..new.len())];
+    while let Some(step) = steps.pop() {
+        let (mut old_range, mut new_range) = match step {
+            HistogramStep::Region(old_range, new_range) => (old_range, new_range),
+            HistogramStep::Equal(old_start, new_start, len) => {
+                hook.equal(old_start, new_start, len)?;
+                continue;
+            }
+        };
+
+        // Common prefix and suffix never need an anchor
+        let prefix = old[old_range.clone()]
+            .iter()
+            .zip(&new[new_range.clone()])
+            .take_while(|(a, b)| a == b)
+            .count();
+        if prefix > 0 {
+            hook.equal(old_range.start, new_range.start, prefix)?;
+            old_range.start += prefix;
+            new_range.start += prefix;
+        }
+        let suffix = old[old_range.clone()]
+            .iter()
+            .rev()
+            .zip(new[new_range.clone()].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        old_range.end -= suffix;
+        new_range.end -= suffix;
+        if suffix > 0 {
+            // Pushed first, so it comes after everything inside the region
+            steps.push(HistogramStep::Equal(old_range.end, new_range.end, suffix));
+        }
+
+        if old_range.is_empty() && !new_range.is_empty() {
+            hook.insert(old_range.start, new_range.start, new_range.len())?;
+        } else if new_range.is_empty() && !old_range.is_empty() {
+            hook.delete(old_range.start, old_range.len(), new_range.start)?;
+        } else if deadline.is_some_and(|d| Instant::now() >= d) {
+            if !old_range.is_empty() {
+                hook.delete(old_range.start, old_range.len(), new_range.start)?;
+                hook.insert(old_range.end, new_range.start, new_range.len())?;
+            }
+        } else if !old_range.is_empty() {
+            match find_histogram_anchor(old, old_range.clone(), new, new_range.clone()) {
+                Some((os, ns, len)) => {
+                    steps.push(HistogramStep::Region(
+                        os + len..old_range.end,
+                        ns + len..new_range.end,
+                    ));
+                    steps.push(HistogramStep::Equal(os, ns, len));
+                    steps.push(HistogramStep::Region(
+                        old_range.start..os,
+                        new_range.start..ns,
+                    ));
+                }
+                None => {
+                    myers::diff_deadline(
+                        &mut NoFinishHook::new(&mut *hook),
+                        old,
+                        old_range,
+                        new,
+                        new_range,
+                        deadline,
+                    )?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Find the common run whose rarest line occurs least often in `old_range`,
+/// preferring longer runs on ties. Returns `(old_start, new_start, len)`.
+///
+/// The positions of each old line are indexed once for the region. Lines
+/// occurring more than [`HISTOGRAM_MAX_OCCURRENCES`] times are never
+/// anchors, so at most that many positions are kept per line and each new
+/// line tries at most that many candidates.
+fn find_histogram_anchor(
+    old: &[&str],
+    old_range: Range<usize>,
+    new: &[&str],
+    new_range: Range<usize>,
+) -> Option<(usize, usize, usize)> {
+    // Positions of each line, one past the cap marking a line as too common
+    let mut positions: HashMap<&str, Vec<usize>> = HashMap::new();
+    for i in old_range.clone() {
+        let at = positions.entry(old[i]).or_default();
+        if at.len() <= HISTOGRAM_MAX_OCCURRENCES {
+            at.push(i);
+        }
+    }
+
+    // (old_start, new_start, len, rarity)
+    let mut best: Option<(usize, usize, usize, usize)> = None;
+    let mut j = new_range.start;
+    while j < new_range.end {
+        let mut next_j = j + 1;
+        let candidates = positions
+            .get(new[j])
+            .filter(|at| at.len() <= HISTOGRAM_MAX_OCCURRENCES);
+        for &i in candidates.into_iter().flatten() {
+            let (mut os, mut ns) = (i, j);
+            while os > old_range.start && ns > new_range.start && old[os - 1] == new[ns - 1] {
+                os -= 1;
+                ns -= 1;
+            }
+            let (mut oe, mut ne) = (i + 1, j + 1);
+            while oe < old_range.end && ne < new_range.end && old[oe] == new[ne] {
+                oe += 1;
+                ne += 1;
+            }
+
+            // Capped counts only exceed the candidate's own, so the minimum
+            // is exact
+            let rarity = old[os..oe]
+                .iter()
+                .map(|line| positions[line].len())
+                .min()
+                .unwrap_or(usize::MAX);
+            let len = oe - os;
+            let better = match best {
+                None => true,
+                Some((_, _, best_len, best_rarity)) => {
+                    rarity < best_rarity || (rarity == best_rarity && len > best_len)
+                }
+            };
+            if better {
+                best = Some((os, ns, len, rarity));
+            }
+            next_j = next_j.max(ne);
+        }
+        j = next_j;
+    }
+
+    best.map(|(os, ns, len, _)| (os, ns, len))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -432,6 +1628,59 @@ mod tests {
         assert!(!diff.is_empty());
     }
 
+    #[test]
+    fn test_diff_stats_counts_changed_lines() {
+        let left = tempfile::TempDir::new().unwrap();
+        let right = tempfile::TempDir::new().unwrap();
+        std::fs::write(left.path().join("a.txt"), "one\ntwo\nthree\n").unwrap();
+        std::fs::write(right.path().join("a.txt"), "one\n2\nthree\nfour\n").unwrap();
+        std::fs::write(left.path().join("skip.txt"), "x\n").unwrap();
+        std::fs::write(right.path().join("skip.txt"), "y\n").unwrap();
+
+        let node = |name: &str, status| DiffNode {
+            relative_path: PathBuf::from(name),
+            left: Some(rcompare_common::FileEntry {
+                path: PathBuf::from(name),
+                size: 0,
+                modified: std::time::SystemTime::UNIX_EPOCH,
+                is_dir: false,
+                file_type: rcompare_common::FileType::Regular,
+            }),
+            right: Some(rcompare_common::FileEntry {
+                path: PathBuf::from(name),
+                size: 0,
+                modified: std::time::SystemTime::UNIX_EPOCH,
+                is_dir: false,
+                file_type: rcompare_common::FileType::Regular,
+            }),
+            status,
+            similar_to: None,
+            reason: None,
+            ignored_by: None,
+        };
+        let nodes = vec![
+            node("a.txt", DiffStatus::Different),
+            node("skip.txt", DiffStatus::Different),
+            node("same.txt", DiffStatus::Same),
+        ];
+
+        let stats = TextDiffEngine::new().diff_stats(
+            left.path(),
+            right.path(),
+            &nodes,
+            |_, left_path, _| !left_path.ends_with("skip.txt"),
+        );
+        assert_eq!(
+            stats,
+            vec![TextDiffStats {
+                path: PathBuf::from("a.txt"),
+                insertions: 2,
+                deletions: 1,
+            }]
+        );
+        assert_eq!(stats[0].changes(), 3);
+    }
+
     #[test]
     fn test_intra_line_diff() {
         let engine = TextDiffEngine::new();
@@ -442,6 +1691,557 @@ mod tests {
         assert!(diff.iter().any(|(_, changed)| *changed));
     }
 
+    #[test]
+    fn test_compare_reader_against_file() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let right_path = temp.path().join("b.txt");
+        std::fs::write(&right_path, "one\ntwo\nthree\n").unwrap();
+        let stdin = std::io::Cursor::new(b"one\n2\nthree\n".to_vec());
+
+        let engine = TextDiffEngine::new();
+        let diff = engine
+            .compare_readers(
+                stdin,
+                std::fs::File::open(&right_path).unwrap(),
+                &right_path,
+            )
+            .unwrap();
+        assert_eq!(render(&diff), vec![" one", "-2", "+two", " three"]);
+    }
+
+    #[test]
+    fn test_minified_lines_are_compared_whole() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let left_path = temp.path().join("left.min.js");
+        let right_path = temp.path().join("right.min.js");
+        let left = "var a=1;".repeat(128 * 1024);
+        let right = left.replacen("var a=1;", "var b=2;", 1);
+        assert_eq!(left.len(), 1024 * 1024);
+        std::fs::write(&left_path, &left).unwrap();
+        std::fs::write(&right_path, &right).unwrap();
+
+        let engine = TextDiffEngine::new();
+        let start = Instant::now();
+        let diff = engine.compare_files(&left_path, &right_path).unwrap();
+        let intra = engine.intra_line_diff(&left, &right);
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        assert_eq!(diff.len(), 2);
+        assert_eq!(diff[0].change_type, DiffChangeType::Delete);
+        assert_eq!(diff[1].change_type, DiffChangeType::Insert);
+        assert!(diff.iter().all(|l| l.paired_line.is_none()));
+        assert!(diff.iter().all(|l| l.highlighted_segments.len() == 1));
+        assert_eq!(intra, vec![(left, true), (right, true)]);
+    }
+
+    #[test]
+    fn test_expired_diff_timeout_still_rebuilds_both_sides() {
+        let left = "a\nb\nc\nd\n";
+        let right = "a\nx\nc\ny\n";
+        for algorithm in [
+            DiffAlgorithm::Myers,
+            DiffAlgorithm::Patience,
+            DiffAlgorithm::Histogram,
+        ] {
+            let engine = TextDiffEngine::with_config(TextDiffConfig {
+                algorithm,
+                diff_timeout: Some(Duration::ZERO),
+                ..TextDiffConfig::new()
+            });
+            let diff = engine
+                .compare_text(left, right, Path::new("t.txt"))
+                .unwrap();
+            assert_eq!(
+                TextDiffEngine::reconstruct(DiffSide::Left, &diff).concat(),
+                left
+            );
+            assert_eq!(
+                TextDiffEngine::reconstruct(DiffSide::Right, &diff).concat(),
+                right
+            );
+        }
+    }
+
+    #[test]
+    fn test_reconstruct_returns_both_originals() {
+        let pieces = ["a\n", "b\n", "c\n", "\n", "  a\n", "a\r\n", "b", "é\n"];
+
+        // Deterministic LCG so failures are reproducible
+        let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = move || {
+            seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1);
+            (seed >> 33) as usize
+        };
+        let mut random_text = || {
+            let lines = next() % 12;
+            (0..lines)
+                .map(|_| pieces[next() % pieces.len()])
+                .collect::<String>()
+        };
+
+        let engines = [
+            DiffAlgorithm::Myers,
+            DiffAlgorithm::Patience,
+            DiffAlgorithm::Histogram,
+        ]
+        .map(|algorithm| {
+            TextDiffEngine::with_config(TextDiffConfig {
+                algorithm,
+                normalize_line_endings: false,
+                ..Default::default()
+            })
+        });
+        for _ in 0..300 {
+            let left = random_text();
+            let right = random_text();
+            for engine in &engines {
+                let diff = engine
+                    .compare_text(&left, &right, Path::new("random.txt"))
+                    .unwrap();
+                let rebuilt_left = TextDiffEngine::reconstruct(DiffSide::Left, &diff).concat();
+                let rebuilt_right = TextDiffEngine::reconstruct(DiffSide::Right, &diff).concat();
+                assert_eq!(rebuilt_left, left, "left of {:?} vs {:?}", left, right);
+                assert_eq!(rebuilt_right, right, "right of {:?} vs {:?}", left, right);
+            }
+        }
+    }
+
+    fn render(diff: &[DiffLine]) -> Vec<String> {
+        diff.iter()
+            .map(|line| {
+                let tag = match line.change_type {
+                    DiffChangeType::Equal => ' ',
+                    DiffChangeType::Insert => '+',
+                    DiffChangeType::Delete => '-',
+                };
+                format!("{}{}", tag, line.content.trim_end_matches('\n'))
+            })
+            .collect()
+    }
+
+    fn diff_with(algorithm: DiffAlgorithm, left: &str, right: &str) -> Vec<String> {
+        let config = TextDiffConfig {
+            algorithm,
+            ..TextDiffConfig::new()
+        };
+        let engine = TextDiffEngine::with_config(config);
+        render(
+            &engine
+                .compare_text(left, right, Path::new("test.c"))
+                .unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_trailing_whitespace_is_equal_but_marked() {
+        let config = TextDiffConfig {
+            whitespace_mode: WhitespaceMode::IgnoreTrailing,
+            show_whitespace_changes: true,
+            ..TextDiffConfig::new()
+        };
+        let engine = TextDiffEngine::with_config(config);
+        let diff = engine
+            .compare_text("a\nb  \nc\n", "a\nb\nc\t\n", Path::new("test.txt"))
+            .unwrap();
+
+        assert!(diff.iter().all(|l| l.change_type == DiffChangeType::Equal));
+        assert!(diff[0].whitespace_markers.is_empty());
+        assert_eq!(
+            diff[1].whitespace_markers,
+            [WhitespaceMarker {
+                kind: WhitespaceMarkerKind::Trailing,
+                left: 1..3,
+                right: 1..1,
+            }]
+        );
+        assert_eq!(
+            diff[2].whitespace_markers,
+            [WhitespaceMarker {
+                kind: WhitespaceMarkerKind::Trailing,
+                left: 1..1,
+                right: 1..2,
+            }]
+        );
+
+        // Markers are opt-in
+        let engine = TextDiffEngine::with_config(TextDiffConfig {
+            show_whitespace_changes: false,
+            ..engine.config().clone()
+        });
+        let diff = engine
+            .compare_text("b  \n", "b\n", Path::new("test.txt"))
+            .unwrap();
+        assert_eq!(diff[0].change_type, DiffChangeType::Equal);
+        assert!(diff[0].whitespace_markers.is_empty());
+    }
+
+    #[test]
+    fn test_tab_space_swap_is_marked() {
+        let config = TextDiffConfig {
+            whitespace_mode: WhitespaceMode::IgnoreChanges,
+            show_whitespace_changes: true,
+            ..TextDiffConfig::new()
+        };
+        let engine = TextDiffEngine::with_config(config);
+        let diff = engine
+            .compare_text("\tx = 1;\n", "    x  = 1;\n", Path::new("test.c"))
+            .unwrap();
+
+        assert_eq!(diff.len(), 1);
+        assert_eq!(diff[0].change_type, DiffChangeType::Equal);
+        let kinds: Vec<_> = diff[0].whitespace_markers.iter().map(|m| m.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                WhitespaceMarkerKind::TabSpace,
+                WhitespaceMarkerKind::Spacing
+            ]
+        );
+        assert_eq!(diff[0].whitespace_markers[0].left, 0..1);
+        assert_eq!(diff[0].whitespace_markers[0].right, 0..4);
+    }
+
+    #[test]
+    fn test_regex_rules_chain_with_capture_groups() {
+        let rules = vec![
+            // 2024-01-31 -> 31/01/2024
+            RegexRule::parse(r"(\d{4})-(\d{2})-(\d{2}):${3}/${2}/${1}:Reorder dates").unwrap(),
+            // Second rule sees the first rule's output
+            RegexRule::parse(r"(?P<day>\d{2})/\d{2}/(?P<year>\d{4}):${year}-W${day}").unwrap(),
+        ];
+        assert_eq!(rules[0].description, "Reorder dates");
+        assert!(rules[1].description.is_empty());
+
+        let engine = TextDiffEngine::with_config(TextDiffConfig {
+            regex_rules: rules,
+            ..TextDiffConfig::new()
+        });
+        assert_eq!(
+            engine.preprocess_text("start 2024-01-31 end $1\n"),
+            "start 2024-W31 end $1\n"
+        );
+    }
+
+    #[test]
+    fn test_ignore_matching_lines() {
+        let config = TextDiffConfig {
+            ignore_matching_lines: vec![Regex::new("^#").unwrap()],
+            ..TextDiffConfig::new()
+        };
+        let engine = TextDiffEngine::with_config(config);
+        let left = "# generated 2024-01-01\nkeep\n# rev 1\nvalue = 1\n";
+        let right = "# generated 2025-06-30\nkeep\n# rev 2\nvalue = 2\n";
+        let diff = engine
+            .compare_text(left, right, Path::new("test.conf"))
+            .unwrap();
+
+        assert_eq!(
+            render(&diff),
+            [
+                " # generated 2024-01-01",
+                " keep",
+                " # rev 1",
+                "-value = 1",
+                "+value = 2",
+            ]
+        );
+        let merged = &diff[2];
+        assert_eq!(
+            (merged.line_number_left, merged.line_number_right),
+            (Some(3), Some(3))
+        );
+
+        // Only one side matching is still a change
+        let diff = engine
+            .compare_text("# note\n", "note\n", Path::new("test.conf"))
+            .unwrap();
+        assert_eq!(render(&diff), ["-# note", "+note"]);
+    }
+
+    fn changed_line<'a>(
+        diff: &'a [DiffLine],
+        change_type: DiffChangeType,
+        text: &str,
+    ) -> &'a DiffLine {
+        diff.iter()
+            .find(|l| l.change_type == change_type && l.content.trim() == text)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_reworded_line_is_paired_with_its_new_version() {
+        let engine = TextDiffEngine::new();
+        let left = "fn total() {\n    let sum = price * quantity;\n}\n";
+        let right =
+            "fn total() {\n    println!(\"debug\");\n    let sum = price * quantity * tax;\n}\n";
+        let diff = engine
+            .compare_text(left, right, Path::new("test.rs"))
+            .unwrap();
+
+        // Positional pairing would match the old line with the println
+        let old = changed_line(&diff, DiffChangeType::Delete, "let sum = price * quantity;");
+        let new = changed_line(
+            &diff,
+            DiffChangeType::Insert,
+            "let sum = price * quantity * tax;",
+        );
+        let inserted = changed_line(&diff, DiffChangeType::Insert, "println!(\"debug\");");
+        assert_eq!(old.paired_line, Some(3));
+        assert_eq!(new.paired_line, Some(2));
+        assert_eq!(inserted.paired_line, None);
+    }
+
+    #[test]
+    fn test_unrelated_lines_stay_unpaired() {
+        let engine = TextDiffEngine::new();
+        let diff = engine
+            .compare_text(
+                "x = 1\nold words here\n",
+                "x = 1\nsomething entirely different\n",
+                Path::new("test.txt"),
+            )
+            .unwrap();
+
+        assert!(
+            diff.iter().all(|line| line.paired_line.is_none()),
+            "{diff:?}"
+        );
+    }
+
+    #[test]
+    fn test_line_pairing_can_be_disabled() {
+        let config = TextDiffConfig {
+            line_pairing_threshold: None,
+            ..TextDiffConfig::new()
+        };
+        let engine = TextDiffEngine::with_config(config);
+        let diff = engine
+            .compare_text("let a = 1;\n", "let a = 2;\n", Path::new("test.rs"))
+            .unwrap();
+
+        assert!(diff.iter().all(|line| line.paired_line.is_none()));
+        let enabled = TextDiffEngine::new()
+            .compare_text("let a = 1;\n", "let a = 2;\n", Path::new("test.rs"))
+            .unwrap();
+        assert!(enabled.iter().all(|line| line.paired_line == Some(1)));
+    }
+
+    #[test]
+    fn test_align_rows_follows_line_pairing() {
+        let engine = TextDiffEngine::new();
+        let left = "fn total() {\n    let sum = price * quantity;\n}\n";
+        let right =
+            "fn total() {\n    println!(\"debug\");\n    let sum = price * quantity * tax;\n}\n";
+        let diff = engine
+            .compare_text(left, right, Path::new("test.rs"))
+            .unwrap();
+
+        let rows: Vec<_> = align_rows(&diff)
+            .into_iter()
+            .map(|row| {
+                (
+                    row.left.map(|l| l.number),
+                    row.right.map(|r| r.number),
+                    row.changed,
+                )
+            })
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                (Some(1), Some(1), false),
+                (None, Some(2), true),
+                (Some(2), Some(3), true),
+                (Some(3), Some(4), false),
+            ]
+        );
+
+        let unpaired = TextDiffEngine::with_config(TextDiffConfig {
+            line_pairing_threshold: None,
+            ..TextDiffConfig::new()
+        })
+        .compare_text("a\nb\nc\n", "x\n", Path::new("test.txt"))
+        .unwrap();
+        let rows = align_rows(&unpaired);
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].right.as_ref().unwrap().text, "x");
+        assert!(rows[1..]
+            .iter()
+            .all(|row| row.right.is_none() && row.changed));
+    }
+
+    #[test]
+    fn test_regex_rule_parse_escaped_colons() {
+        let rule =
+            RegexRule::parse(r"(\d{2})\:(\d{2})\:\d{2}:$1\:$2:Drop seconds: keep rest").unwrap();
+        assert_eq!(rule.pattern.as_str(), r"(\d{2}):(\d{2}):\d{2}");
+        assert_eq!(rule.replacement, "$1:$2");
+        assert_eq!(rule.description, "Drop seconds: keep rest");
+        assert_eq!(rule.apply("at 12:34:56 UTC"), "at 12:34 UTC");
+
+        assert!(matches!(
+            RegexRule::parse("no-separator"),
+            Err(RCompareError::Config(_))
+        ));
+        assert!(RegexRule::parse("(unclosed:x").is_err());
+    }
+
+    fn expand_tabs_equal(tab_width: usize, left: &str, right: &str) -> bool {
+        let engine = TextDiffEngine::with_config(TextDiffConfig {
+            whitespace_mode: WhitespaceMode::ExpandTabs,
+            tab_width,
+            ..TextDiffConfig::new()
+        });
+        engine
+            .compare_text(left, right, Path::new("test.c"))
+            .unwrap()
+            .iter()
+            .all(|l| l.change_type == DiffChangeType::Equal)
+    }
+
+    #[test]
+    fn test_expand_tabs_matches_by_column() {
+        assert!(expand_tabs_equal(4, "\tx = 1;\n", "    x = 1;\n"));
+        assert!(!expand_tabs_equal(4, "\tx = 1;\n", "   x = 1;\n"));
+        assert!(!expand_tabs_equal(8, "\tx = 1;\n", "    x = 1;\n"));
+        // Spaces inside a line are still significant
+        assert!(!expand_tabs_equal(4, "x = 1;\n", "x  = 1;\n"));
+    }
+
+    #[test]
+    fn test_expand_tabs_mixed_indentation() {
+        // Two spaces then a tab reach the same tab stop as a lone tab
+        assert!(expand_tabs_equal(4, "  \tx\n", "\tx\n"));
+        assert!(expand_tabs_equal(4, "\t\tx\n", "    \tx\n"));
+        assert!(expand_tabs_equal(4, "ab\tc\n", "ab  c\n"));
+        assert_eq!(expand_tabs("  \t \tx", 4), "        x");
+        assert_eq!(expand_tabs("\tx", 0), "\tx");
+    }
+
+    #[test]
+    fn test_default_algorithm_is_patience() {
+        assert_eq!(TextDiffConfig::new().algorithm, DiffAlgorithm::Patience);
+        assert_eq!(TextDiffConfig::default().algorithm, DiffAlgorithm::Patience);
+    }
+
+    #[test]
+    fn test_patience_keeps_moved_function_intact() {
+        // Swapping two functions: Myers finds a minimal script that splits a
+        // function at its closing brace, patience anchors on the unique
+        // signatures and moves whole functions
+        let left = "void f() {\n  x;\n}\nvoid g() {\n  y;\n}\n";
+        let right = "void g() {\n  y;\n}\nvoid f() {\n  x;\n}\n";
+
+        let myers = diff_with(DiffAlgorithm::Myers, left, right);
+        assert_eq!(
+            myers,
+            [
+                "+void g() {",
+                "+  y;",
+                "+}",
+                " void f() {",
+                "   x;",
+                "-}",
+                "-void g() {",
+                "-  y;",
+                " }",
+            ]
+        );
+
+        let patience = diff_with(DiffAlgorithm::Patience, left, right);
+        assert_eq!(
+            patience,
+            [
+                "+void g() {",
+                "+  y;",
+                "+}",
+                " void f() {",
+                "   x;",
+                " }",
+                "-void g() {",
+                "-  y;",
+                "-}",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_histogram_anchors_on_longest_rare_run() {
+        // Patience only sees the unique lines and keeps the two body lines,
+        // histogram also uses the repeated braces and keeps the longer run
+        let left = "}\n}\nvoid render() {\n  draw();\n  flush();\n";
+        let right = "  draw();\n  flush();\n}\n}\nvoid render() {\n";
+
+        let patience = diff_with(DiffAlgorithm::Patience, left, right);
+        assert_eq!(
+            patience,
+            [
+                "-}",
+                "-}",
+                "-void render() {",
+                "   draw();",
+                "   flush();",
+                "+}",
+                "+}",
+                "+void render() {",
+            ]
+        );
+
+        let histogram = diff_with(DiffAlgorithm::Histogram, left, right);
+        assert_eq!(
+            histogram,
+            [
+                "+  draw();",
+                "+  flush();",
+                " }",
+                " }",
+                " void render() {",
+                "-  draw();",
+                "-  flush();",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_histogram_handles_disjoint_input() {
+        let histogram = diff_with(DiffAlgorithm::Histogram, "a\nb\n", "c\nd\n");
+        assert_eq!(histogram, ["-a", "-b", "+c", "+d"]);
+    }
+
+    #[test]
+    fn test_histogram_handles_many_nested_anchors() {
+        // Every other line changes and the rest repeat a few dozen times, so
+        // the diff splits into thousands of regions around short anchors
+        let side = |prefix: &str| -> Vec<String> {
+            (0..4_000)
+                .map(|i| match i % 2 {
+                    0 => format!("anchor {}", i % 200),
+                    _ => format!("{} {}", prefix, i),
+                })
+                .collect()
+        };
+        let (old, new) = (side("old"), side("new"));
+        let old: Vec<&str> = old.iter().map(String::as_str).collect();
+        let new: Vec<&str> = new.iter().map(String::as_str).collect();
+
+        let ops = histogram_diff(&old, &new, None);
+        let kept: usize = ops
+            .iter()
+            .map(|op| match *op {
+                DiffOp::Equal { len, .. } => len,
+                _ => 0,
+            })
+            .sum();
+        assert_eq!(kept, 2_000);
+        let (mut old_end, mut new_end) = (0, 0);
+        for op in &ops {
+            assert_eq!(op.old_range().start, old_end);
+            assert_eq!(op.new_range().start, new_end);
+            (old_end, new_end) = (op.old_range().end, op.new_range().end);
+        }
+        assert_eq!((old_end, new_end), (old.len(), new.len()));
+    }
+
     #[test]
     fn test_patience_algorithm() {
         let engine = TextDiffEngine::new();
@@ -453,4 +2253,193 @@ mod tests {
             .unwrap();
         assert!(!diff.is_empty());
     }
+
+    /// Kind and line texts of base, left and right for each region
+    fn diff3_summary(regions: &[Diff3Region]) -> Vec<(Diff3Kind, Vec<&str>, Vec<&str>, Vec<&str>)> {
+        fn texts(lines: &[AlignedLine]) -> Vec<&str> {
+            lines.iter().map(|line| line.text.as_str()).collect()
+        }
+        regions
+            .iter()
+            .map(|region| {
+                (
+                    region.kind,
+                    texts(&region.base),
+                    texts(&region.left),
+                    texts(&region.right),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_diff3_tags_change_on_one_side_only() {
+        let engine = TextDiffEngine::new();
+        let base = "a\nb\nc\nd\n";
+        let left = "a\nB\nc\nd\n";
+
+        let regions = engine.diff3(base, left, base);
+        assert_eq!(
+            diff3_summary(&regions),
+            vec![
+                (Diff3Kind::Shared, vec!["a"], vec!["a"], vec!["a"]),
+                (Diff3Kind::LeftOnly, vec!["b"], vec!["B"], vec!["b"]),
+                (
+                    Diff3Kind::Shared,
+                    vec!["c", "d"],
+                    vec!["c", "d"],
+                    vec!["c", "d"]
+                ),
+            ]
+        );
+        let changed = &regions[1];
+        assert_eq!(changed.base[0].number, 2);
+        assert_eq!(changed.left[0].number, 2);
+        assert_eq!(regions[2].right[1].number, 4);
+
+        let swapped = engine.diff3(base, base, left);
+        assert_eq!(swapped[1].kind, Diff3Kind::RightOnly);
+    }
+
+    #[test]
+    fn test_diff3_tags_change_on_both_sides() {
+        let engine = TextDiffEngine::new();
+        let base = "a\nb\nc\n";
+
+        let regions = engine.diff3(base, "a\nleft\nc\n", "a\nright\nc\n");
+        assert_eq!(
+            diff3_summary(&regions)[1],
+            (Diff3Kind::Conflict, vec!["b"], vec!["left"], vec!["right"])
+        );
+
+        let regions = engine.diff3(base, "a\nboth\nc\n", "a\nboth\nc\n");
+        assert_eq!(
+            diff3_summary(&regions)[1],
+            (Diff3Kind::BaseOnly, vec!["b"], vec!["both"], vec!["both"])
+        );
+        assert_eq!(regions.len(), 3);
+
+        // An insertion has no base lines
+        let regions = engine.diff3(base, "a\nb\nnew\nc\n", base);
+        assert_eq!(
+            diff3_summary(&regions)[1],
+            (Diff3Kind::LeftOnly, vec![], vec!["new"], vec![])
+        );
+        assert_eq!(regions[1].left[0].number, 3);
+    }
+
+    #[test]
+    fn test_config_builder_sets_options_in_order() {
+        let config = TextDiffConfig::builder()
+            .whitespace(WhitespaceMode::IgnoreTrailing)
+            .ignore_case(true)
+            .regex_rule(r"\d+:N")
+            .regex_rule(r"N-N:range")
+            .ignore_matching_line("^#")
+            .algorithm(DiffAlgorithm::Myers)
+            .line_pairing_threshold(None)
+            .build()
+            .unwrap();
+
+        assert_eq!(config.whitespace_mode, WhitespaceMode::IgnoreTrailing);
+        assert!(config.ignore_case);
+        assert_eq!(config.algorithm, DiffAlgorithm::Myers);
+        assert_eq!(config.line_pairing_threshold, None);
+        assert_eq!(config.ignore_matching_lines[0].as_str(), "^#");
+        let rules: Vec<&str> = config
+            .regex_rules
+            .iter()
+            .map(|r| r.pattern.as_str())
+            .collect();
+        assert_eq!(rules, [r"\d+", "N-N"]);
+        // Untouched options keep the `new` defaults
+        assert!(config.normalize_line_endings);
+        assert_eq!(
+            config.max_line_length,
+            Some(TextDiffConfig::DEFAULT_MAX_LINE_LENGTH)
+        );
+
+        let engine = TextDiffEngine::with_config(config);
+        let diff = engine
+            .compare_text("Lines 3-7  \n", "lines 10-12\n", Path::new("a.txt"))
+            .unwrap();
+        assert!(diff.iter().all(|l| l.change_type == DiffChangeType::Equal));
+    }
+
+    #[test]
+    fn test_config_builder_reports_first_invalid_value() {
+        let err = TextDiffConfig::builder()
+            .regex_rule("no separator")
+            .ignore_matching_line("(")
+            .build()
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Invalid regex rule format 'no separator'"),
+            "{}",
+            err
+        );
+
+        let err = TextDiffConfig::builder()
+            .ignore_matching_line("(")
+            .build()
+            .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("Invalid ignore-matching-lines pattern '('"),
+            "{}",
+            err
+        );
+
+        let err = TextDiffConfig::builder()
+            .line_pairing_threshold(Some(1.5))
+            .build()
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("line pairing threshold 1.5"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn test_from_cli_args_matches_cli_parsing() {
+        let strings = |items: &[&str]| items.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let config = TextDiffConfig::from_cli_args(
+            Some("Changes"),
+            true,
+            &strings(&[r"v\d+:vN:versions"]),
+            &strings(&["^//"]),
+        )
+        .unwrap();
+        assert_eq!(config.whitespace_mode, WhitespaceMode::IgnoreChanges);
+        assert!(config.ignore_case);
+        assert_eq!(config.regex_rules[0].description, "versions");
+        assert_eq!(config.ignore_matching_lines.len(), 1);
+
+        let defaults = TextDiffConfig::from_cli_args(None, false, &[], &[]).unwrap();
+        let new = TextDiffConfig::new();
+        assert_eq!(defaults.whitespace_mode, new.whitespace_mode);
+        assert_eq!(defaults.line_pairing_threshold, new.line_pairing_threshold);
+        assert_eq!(defaults.diff_timeout, new.diff_timeout);
+        // CLI output must not depend on machine load
+        assert_eq!(defaults.diff_timeout, None);
+
+        for (name, mode) in [
+            ("all", WhitespaceMode::IgnoreAll),
+            ("leading", WhitespaceMode::IgnoreLeading),
+            ("trailing", WhitespaceMode::IgnoreTrailing),
+            ("tabs", WhitespaceMode::ExpandTabs),
+        ] {
+            assert_eq!(name.parse::<WhitespaceMode>().unwrap(), mode);
+        }
+        let err = TextDiffConfig::from_cli_args(Some("some"), false, &[], &[]).unwrap_err();
+        assert!(
+            err.to_string().contains(
+                "Invalid whitespace mode 'some'. Valid options: all, leading, trailing, changes, tabs"
+            ),
+            "{}",
+            err
+        );
+    }
 }
@@ -1,15 +1,19 @@
 use super::local::LocalVfs;
 use bzip2::read::BzDecoder;
 use bzip2::write::BzEncoder;
-use flate2::read::GzDecoder;
+use flate2::read::MultiGzDecoder;
 use flate2::write::GzEncoder;
 use flate2::Compression;
-use rcompare_common::{FileEntry, FileMetadata, Vfs, VfsCapabilities, VfsError};
-use sevenz_rust::decompress_file;
+use rcompare_common::{
+    CompressionInfo, FileEntry, FileMetadata, FileType, HashAlgorithm, Vfs, VfsCapabilities,
+    VfsError,
+};
+use sevenz_rust::{decompress_file, SevenZMethod};
+use std::collections::{BTreeMap, HashMap};
 use std::fs::File;
 use std::io::{Cursor, Read, Write};
-use std::path::{Path, PathBuf};
-use std::sync::{Arc, Mutex};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock};
 use std::time::SystemTime;
 use unrar::Archive;
 use xz2::read::XzDecoder;
@@ -21,6 +25,9 @@ use zip::{ZipArchive, ZipWriter};
 pub struct ZipVfs {
     instance_id: String,
     archive_path: PathBuf,
+    /// Compression of every stored entry (`None` for directories), read from
+    /// the central directory on first use
+    compression: OnceLock<HashMap<PathBuf, Option<CompressionInfo>>>,
 }
 
 /// Writable ZIP archive VFS implementation
@@ -43,9 +50,29 @@ impl ZipVfs {
         Ok(Self {
             instance_id,
             archive_path,
+            compression: OnceLock::new(),
         })
     }
 
+    fn compression(&self) -> Result<&HashMap<PathBuf, Option<CompressionInfo>>, VfsError> {
+        if let Some(compression) = self.compression.get() {
+            return Ok(compression);
+        }
+        let mut archive = self.open_archive()?;
+        let mut compression = HashMap::with_capacity(archive.len());
+        for i in 0..archive.len() {
+            let file = archive
+                .by_index_raw(i)
+                .map_err(|e| VfsError::Io(std::io::Error::other(e)))?;
+            let info = (!file.is_dir()).then(|| CompressionInfo {
+                compressed_size: file.compressed_size(),
+                method: file.compression().to_string().to_lowercase(),
+            });
+            compression.insert(PathBuf::from(file.name()), info);
+        }
+        Ok(self.compression.get_or_init(|| compression))
+    }
+
     fn open_archive(&self) -> Result<ZipArchive<File>, VfsError> {
         let file = File::open(&self.archive_path)?;
         ZipArchive::new(file)
@@ -90,12 +117,12 @@ impl Vfs for ZipVfs {
 
     fn read_dir(&self, path: &Path) -> Result<Vec<FileEntry>, VfsError> {
         let mut archive = self.open_archive()?;
-        let mut entries = Vec::new();
+        let mut entries: BTreeMap<String, FileEntry> = BTreeMap::new();
         let path_str = path.to_string_lossy();
         let prefix = if path_str.is_empty() {
             String::new()
         } else {
-            format!("{}/", path_str)
+            format!("{}/", path_str.trim_end_matches('/'))
         };
 
         for i in 0..archive.len() {
@@ -103,31 +130,53 @@ impl Vfs for ZipVfs {
                 .by_index(i)
                 .map_err(|e| VfsError::Io(std::io::Error::other(e)))?;
 
-            let name = file.name();
-            if name.starts_with(&prefix) {
-                let relative = &name[prefix.len()..];
+            let Some(relative) = file.name().strip_prefix(&prefix) else {
+                continue;
+            };
+            let relative = relative.trim_end_matches('/');
+            if relative.is_empty() {
+                continue;
+            }
 
-                // Only include direct children
-                if !relative.is_empty() && !relative.contains('/') {
-                    entries.push(FileEntry {
-                        path: PathBuf::from(name),
-                        size: file.size(),
-                        modified: file
-                            .last_modified()
-                            .to_time()
-                            .map(|dt| {
-                                let timestamp = dt.unix_timestamp();
-                                SystemTime::UNIX_EPOCH
-                                    + std::time::Duration::from_secs(timestamp as u64)
-                            })
-                            .unwrap_or(SystemTime::UNIX_EPOCH),
-                        is_dir: file.is_dir(),
-                    });
+            match relative.split_once('/') {
+                // Direct child, including explicit "dir/" entries
+                None => {
+                    entries.insert(
+                        relative.to_string(),
+                        FileEntry {
+                            path: PathBuf::from(format!("{}{}", prefix, relative)),
+                            size: file.size(),
+                            modified: file
+                                .last_modified()
+                                .to_time()
+                                .map(|dt| {
+                                    let timestamp = dt.unix_timestamp();
+                                    SystemTime::UNIX_EPOCH
+                                        + std::time::Duration::from_secs(timestamp as u64)
+                                })
+                                .unwrap_or(SystemTime::UNIX_EPOCH),
+                            is_dir: file.is_dir(),
+                            file_type: FileType::from_is_dir(file.is_dir()),
+                        },
+                    );
+                }
+                // Deeper entry: its first component is a directory, which
+                // archives don't always store as an entry of its own
+                Some((child, _)) => {
+                    entries
+                        .entry(child.to_string())
+                        .or_insert_with(|| FileEntry {
+                            path: PathBuf::from(format!("{}{}", prefix, child)),
+                            size: 0,
+                            modified: SystemTime::UNIX_EPOCH,
+                            is_dir: true,
+                            file_type: FileType::Directory,
+                        });
                 }
             }
         }
 
-        Ok(entries)
+        Ok(entries.into_values().collect())
     }
 
     fn open_file(&self, path: &Path) -> Result<Box<dyn Read + Send>, VfsError> {
@@ -163,6 +212,47 @@ impl Vfs for ZipVfs {
     fn capabilities(&self) -> VfsCapabilities {
         VfsCapabilities::read_only()
     }
+
+    /// CRC32 and uncompressed size from the central directory, so entries
+    /// can be compared without decompressing them
+    fn content_fingerprint(&self, path: &Path) -> Result<Option<String>, VfsError> {
+        let mut archive = self.open_archive()?;
+        let path_str = path.to_string_lossy();
+
+        let file = archive
+            .by_name(&path_str)
+            .map_err(|_| VfsError::NotFound(path.display().to_string()))?;
+
+        if file.is_dir() {
+            return Ok(None);
+        }
+
+        Ok(Some(format!("zip:{:08x}:{}", file.crc32(), file.size())))
+    }
+
+    /// Hashes the entry while decompressing it, where `open_file` buffers
+    /// the whole entry first
+    fn hash(&self, path: &Path, algorithm: HashAlgorithm) -> Result<Option<Vec<u8>>, VfsError> {
+        let mut archive = self.open_archive()?;
+        let path_str = path.to_string_lossy();
+
+        let file = archive
+            .by_name(&path_str)
+            .map_err(|_| VfsError::NotFound(path.display().to_string()))?;
+
+        if file.is_dir() {
+            return Err(VfsError::NotAFile(path.display().to_string()));
+        }
+
+        Ok(Some(super::stream_hash(file, algorithm)?))
+    }
+
+    fn compression_info(&self, path: &Path) -> Result<Option<CompressionInfo>, VfsError> {
+        self.compression()?
+            .get(path)
+            .cloned()
+            .ok_or_else(|| VfsError::NotFound(path.display().to_string()))
+    }
 }
 
 impl WritableZipVfs {
@@ -309,6 +399,7 @@ impl Vfs for WritableZipVfs {
             rename: true,
             create_dir: true,
             set_mtime: true,
+            expensive_reads: false,
         }
     }
 
@@ -388,12 +479,35 @@ impl TarVfs {
 
     fn open_archive(&self) -> Result<tar::Archive<Box<dyn Read>>, VfsError> {
         let file = File::open(&self.archive_path)?;
-        if is_gzip_archive(&self.archive_path) {
-            let decoder = GzDecoder::new(file);
-            Ok(tar::Archive::new(Box::new(decoder)))
-        } else {
-            Ok(tar::Archive::new(Box::new(file)))
+        let reader: Box<dyn Read> = match tar_compression(&self.archive_path) {
+            Some(compression) => compression.decoder(file)?,
+            None => Box::new(file),
+        };
+        Ok(tar::Archive::new(reader))
+    }
+
+    /// Metadata of the entry at `path` itself, with its target if it is a
+    /// symlink
+    fn entry_info(&self, path: &Path) -> Result<(FileMetadata, Option<PathBuf>), VfsError> {
+        let mut archive = self.open_archive()?;
+        for entry in archive.entries()? {
+            let entry = entry?;
+            if entry.path()? != path {
+                continue;
+            }
+
+            let header = entry.header();
+            let target = symlink_target(&entry)?;
+            let metadata = FileMetadata {
+                size: header.size()?,
+                modified: SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(header.mtime()?),
+                is_dir: header.entry_type().is_dir(),
+                is_symlink: target.is_some(),
+            };
+            return Ok((metadata, target));
         }
+
+        Err(VfsError::NotFound(path.display().to_string()))
     }
 }
 
@@ -403,24 +517,22 @@ impl Vfs for TarVfs {
     }
 
     fn metadata(&self, path: &Path) -> Result<FileMetadata, VfsError> {
-        let mut archive = self.open_archive()?;
-        for entry in archive.entries()? {
-            let entry = entry?;
-            let entry_path = entry.path()?;
-
-            if entry_path == path {
-                let header = entry.header();
-                return Ok(FileMetadata {
-                    size: header.size()?,
-                    modified: SystemTime::UNIX_EPOCH
-                        + std::time::Duration::from_secs(header.mtime()?),
-                    is_dir: header.entry_type().is_dir(),
-                    is_symlink: header.entry_type() == tar::EntryType::Symlink,
-                });
+        let mut path = path.to_path_buf();
+        for _ in 0..MAX_SYMLINK_HOPS {
+            match self.entry_info(&path)? {
+                (_, Some(target)) => path = resolve_link(&path, &target)?,
+                (metadata, None) => return Ok(metadata),
             }
         }
+        Err(too_many_links(&path))
+    }
 
-        Err(VfsError::NotFound(path.display().to_string()))
+    fn symlink_metadata(&self, path: &Path) -> Result<FileMetadata, VfsError> {
+        self.entry_info(path).map(|(metadata, _)| metadata)
+    }
+
+    fn read_link(&self, path: &Path) -> Result<PathBuf, VfsError> {
+        self.entry_info(path)?.1.ok_or_else(|| not_a_link(path))
     }
 
     fn read_dir(&self, path: &Path) -> Result<Vec<FileEntry>, VfsError> {
@@ -439,6 +551,7 @@ impl Vfs for TarVfs {
                         modified: SystemTime::UNIX_EPOCH
                             + std::time::Duration::from_secs(header.mtime()?),
                         is_dir: header.entry_type().is_dir(),
+                        file_type: FileType::from_is_dir(header.entry_type().is_dir()),
                     });
                 }
             }
@@ -448,20 +561,28 @@ impl Vfs for TarVfs {
     }
 
     fn open_file(&self, path: &Path) -> Result<Box<dyn Read + Send>, VfsError> {
-        let mut archive = self.open_archive()?;
-
-        for entry in archive.entries()? {
-            let mut entry = entry?;
-            let entry_path = entry.path()?;
+        let mut path = path.to_path_buf();
+        'follow: for _ in 0..MAX_SYMLINK_HOPS {
+            let mut archive = self.open_archive()?;
+
+            for entry in archive.entries()? {
+                let mut entry = entry?;
+                if entry.path()? != path {
+                    continue;
+                }
+                if let Some(target) = symlink_target(&entry)? {
+                    path = resolve_link(&path, &target)?;
+                    continue 'follow;
+                }
 
-            if entry_path == path {
                 let mut contents = Vec::new();
                 entry.read_to_end(&mut contents)?;
                 return Ok(Box::new(Cursor::new(contents)));
             }
-        }
 
-        Err(VfsError::NotFound(path.display().to_string()))
+            return Err(VfsError::NotFound(path.display().to_string()));
+        }
+        Err(too_many_links(&path))
     }
 
     fn remove_file(&self, _path: &Path) -> Result<(), VfsError> {
@@ -492,7 +613,7 @@ impl WritableTarVfs {
         if archive_path.exists() {
             let file = File::open(&archive_path)?;
             let mut archive: tar::Archive<Box<dyn Read>> = if compress_gzip {
-                let decoder = GzDecoder::new(file);
+                let decoder = MultiGzDecoder::new(file);
                 tar::Archive::new(Box::new(decoder))
             } else {
                 tar::Archive::new(Box::new(file))
@@ -571,6 +692,14 @@ impl Vfs for WritableTarVfs {
         self.local_vfs.metadata(path)
     }
 
+    fn symlink_metadata(&self, path: &Path) -> Result<FileMetadata, VfsError> {
+        self.local_vfs.symlink_metadata(path)
+    }
+
+    fn read_link(&self, path: &Path) -> Result<PathBuf, VfsError> {
+        self.local_vfs.read_link(path)
+    }
+
     fn read_dir(&self, path: &Path) -> Result<Vec<FileEntry>, VfsError> {
         self.local_vfs.read_dir(path)
     }
@@ -601,6 +730,7 @@ impl Vfs for WritableTarVfs {
             rename: true,
             create_dir: true,
             set_mtime: true,
+            expensive_reads: false,
         }
     }
 
@@ -647,11 +777,25 @@ impl Vfs for WritableTarVfs {
     }
 }
 
-/// 7Z archive VFS implementation (read-only, extracted to temp dir)
+/// 7Z archive VFS implementation (read-only)
+///
+/// Listing and metadata come from the archive header alone; `open_file`
+/// decompresses only the block holding the requested entry, into memory.
+/// The entries of the solid block decoded last are kept, so reading a
+/// solid archive in order decodes each block once.
 pub struct SevenZVfs {
     instance_id: String,
-    _temp_dir: tempfile::TempDir,
-    local_vfs: LocalVfs,
+    archive_path: PathBuf,
+    archive: sevenz_rust::Archive,
+    /// Index into `archive.files` by path; `None` for directories that are
+    /// only implied by the paths below them
+    entries: HashMap<PathBuf, Option<usize>>,
+    /// Direct children of every directory, sorted
+    children: HashMap<PathBuf, Vec<PathBuf>>,
+    compression: HashMap<PathBuf, CompressionInfo>,
+    /// Block index and contents, by index into `archive.files`, of the
+    /// solid block decoded last
+    decoded_block: Mutex<Option<(usize, HashMap<usize, Arc<[u8]>>)>>,
 }
 
 /// Writable 7Z archive VFS implementation
@@ -678,25 +822,164 @@ impl SevenZVfs {
             return Err(VfsError::NotFound(archive_path.display().to_string()));
         }
 
-        let temp_dir =
-            tempfile::TempDir::new().map_err(|e| VfsError::Io(std::io::Error::other(e)))?;
+        let archive = sevenz_rust::Archive::open(&archive_path).map_err(sevenz_error)?;
+
+        let mut entries = HashMap::new();
+        for (idx, file) in archive.files.iter().enumerate() {
+            if file.is_anti_item() {
+                continue;
+            }
+            let path = PathBuf::from(file.name().trim_end_matches('/'));
+            if path.as_os_str().is_empty() {
+                continue;
+            }
+            for ancestor in path.ancestors().skip(1) {
+                if !ancestor.as_os_str().is_empty() {
+                    entries.entry(ancestor.to_path_buf()).or_insert(None);
+                }
+            }
+            entries.insert(path, Some(idx));
+        }
 
-        decompress_file(&archive_path, temp_dir.path()).map_err(|e| {
-            VfsError::Io(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
-                e.to_string(),
-            ))
-        })?;
+        let mut children: HashMap<PathBuf, Vec<PathBuf>> = HashMap::new();
+        for path in entries.keys() {
+            let parent = path.parent().unwrap_or(Path::new(""));
+            children
+                .entry(parent.to_path_buf())
+                .or_default()
+                .push(path.clone());
+        }
+        for list in children.values_mut() {
+            list.sort();
+        }
 
+        let compression = read_7z_compression(&archive);
         let instance_id = format!("7z:{}", archive_path.display());
-        let local_vfs = LocalVfs::new(temp_dir.path().to_path_buf());
 
         Ok(Self {
             instance_id,
-            _temp_dir: temp_dir,
-            local_vfs,
+            archive_path,
+            archive,
+            entries,
+            children,
+            compression,
+            decoded_block: Mutex::new(None),
         })
     }
+
+    fn entry_metadata(&self, entry: Option<usize>) -> FileMetadata {
+        match entry.map(|idx| &self.archive.files[idx]) {
+            Some(file) => FileMetadata {
+                size: file.size(),
+                modified: sevenz_modified(file),
+                is_dir: file.is_directory(),
+                is_symlink: false,
+            },
+            None => FileMetadata {
+                size: 0,
+                modified: SystemTime::UNIX_EPOCH,
+                is_dir: true,
+                is_symlink: false,
+            },
+        }
+    }
+
+    /// Decompress one entry into memory
+    ///
+    /// Only the block holding the entry is decoded. A solid block is decoded
+    /// whole and kept until an entry of another block is read.
+    fn read_entry(&self, idx: usize) -> Result<Arc<[u8]>, VfsError> {
+        let Some(folder) = self.archive.stream_map.file_folder_index[idx] else {
+            // Empty files have no stream
+            return Ok(Arc::from([]));
+        };
+        if self.archive.folders[folder].num_unpack_sub_streams <= 1 {
+            return Ok(self.decode_block(folder)?.remove(&idx).unwrap_or_default());
+        }
+
+        let mut decoded = self
+            .decoded_block
+            .lock()
+            .map_err(|_| VfsError::Io(std::io::Error::other("Failed to lock decoded block")))?;
+        if !matches!(&*decoded, Some((cached, _)) if *cached == folder) {
+            *decoded = Some((folder, self.decode_block(folder)?));
+        }
+        Ok(decoded
+            .as_ref()
+            .and_then(|(_, entries)| entries.get(&idx).cloned())
+            .unwrap_or_default())
+    }
+
+    /// Decompress every entry of block `folder`, keyed by index into
+    /// `archive.files`
+    fn decode_block(&self, folder: usize) -> Result<HashMap<usize, Arc<[u8]>>, VfsError> {
+        let first = self.archive.stream_map.folder_first_file_index[folder];
+        let mut source = File::open(&self.archive_path)?;
+        let mut entries = HashMap::new();
+        sevenz_rust::BlockDecoder::new(folder, &self.archive, &[], &mut source)
+            .for_each_entries(&mut |_, reader| {
+                let mut data = Vec::new();
+                reader.read_to_end(&mut data)?;
+                entries.insert(first + entries.len(), Arc::from(data));
+                Ok(true)
+            })
+            .map_err(sevenz_error)?;
+        Ok(entries)
+    }
+}
+
+fn sevenz_error(e: sevenz_rust::Error) -> VfsError {
+    VfsError::Io(std::io::Error::new(
+        std::io::ErrorKind::InvalidData,
+        e.to_string(),
+    ))
+}
+
+fn sevenz_modified(file: &sevenz_rust::SevenZArchiveEntry) -> SystemTime {
+    if !file.has_last_modified_date {
+        return SystemTime::UNIX_EPOCH;
+    }
+    let nanos = file.last_modified_date().to_unix_time_nanos();
+    u64::try_from(nanos)
+        .map(|nanos| SystemTime::UNIX_EPOCH + std::time::Duration::from_nanos(nanos))
+        .unwrap_or(SystemTime::UNIX_EPOCH)
+}
+
+/// Packed size and coder chain of every entry stored alone in its block
+///
+/// Entries in a solid block share one packed stream, so they have no size
+/// of their own and are left out.
+fn read_7z_compression(archive: &sevenz_rust::Archive) -> HashMap<PathBuf, CompressionInfo> {
+    let mut compression = HashMap::new();
+    for (idx, entry) in archive.files.iter().enumerate() {
+        let Some(folder) = archive.stream_map.file_folder_index[idx]
+            .and_then(|folder_idx| archive.folders.get(folder_idx))
+        else {
+            continue;
+        };
+        if !entry.has_stream || folder.num_unpack_sub_streams != 1 {
+            continue;
+        }
+
+        let method = folder
+            .coders
+            .iter()
+            .map(|coder| {
+                SevenZMethod::by_id(coder.decompression_method_id())
+                    .map_or("unknown", |m| m.name())
+                    .to_lowercase()
+            })
+            .collect::<Vec<_>>()
+            .join("+");
+        compression.insert(
+            PathBuf::from(entry.name()),
+            CompressionInfo {
+                compressed_size: entry.compressed_size,
+                method,
+            },
+        );
+    }
+    compression
 }
 
 impl Vfs for SevenZVfs {
@@ -705,15 +988,46 @@ impl Vfs for SevenZVfs {
     }
 
     fn metadata(&self, path: &Path) -> Result<FileMetadata, VfsError> {
-        self.local_vfs.metadata(path)
+        if path.as_os_str().is_empty() {
+            return Ok(self.entry_metadata(None));
+        }
+        match self.entries.get(path) {
+            Some(&entry) => Ok(self.entry_metadata(entry)),
+            None => Err(VfsError::NotFound(path.display().to_string())),
+        }
     }
 
     fn read_dir(&self, path: &Path) -> Result<Vec<FileEntry>, VfsError> {
-        self.local_vfs.read_dir(path)
+        if !self.metadata(path)?.is_dir {
+            return Err(VfsError::NotADirectory(path.display().to_string()));
+        }
+
+        Ok(self
+            .children
+            .get(path)
+            .into_iter()
+            .flatten()
+            .map(|child| {
+                let meta = self.entry_metadata(self.entries[child]);
+                FileEntry {
+                    path: child.clone(),
+                    size: meta.size,
+                    modified: meta.modified,
+                    is_dir: meta.is_dir,
+                    file_type: FileType::from_is_dir(meta.is_dir),
+                }
+            })
+            .collect())
     }
 
     fn open_file(&self, path: &Path) -> Result<Box<dyn Read + Send>, VfsError> {
-        self.local_vfs.open_file(path)
+        match self.entries.get(path) {
+            Some(&Some(idx)) if !self.archive.files[idx].is_directory() => {
+                Ok(Box::new(Cursor::new(self.read_entry(idx)?)))
+            }
+            Some(_) => Err(VfsError::NotAFile(path.display().to_string())),
+            None => Err(VfsError::NotFound(path.display().to_string())),
+        }
     }
 
     fn remove_file(&self, _path: &Path) -> Result<(), VfsError> {
@@ -731,6 +1045,13 @@ impl Vfs for SevenZVfs {
     fn capabilities(&self) -> VfsCapabilities {
         VfsCapabilities::read_only()
     }
+
+    fn compression_info(&self, path: &Path) -> Result<Option<CompressionInfo>, VfsError> {
+        if self.metadata(path)?.is_dir {
+            return Ok(None);
+        }
+        Ok(self.compression.get(path).cloned())
+    }
 }
 
 impl Writable7zVfs {
@@ -837,6 +1158,7 @@ impl Vfs for Writable7zVfs {
             rename: true,
             create_dir: true,
             set_mtime: true,
+            expensive_reads: false,
         }
     }
 
@@ -971,21 +1293,89 @@ impl Vfs for RarVfs {
     }
 }
 
+/// Symlinks followed before giving up, as with `ELOOP` on Linux
+const MAX_SYMLINK_HOPS: usize = 40;
+
+/// Target of a TAR symlink entry, `None` for any other entry
+fn symlink_target<R: Read>(entry: &tar::Entry<R>) -> Result<Option<PathBuf>, VfsError> {
+    if entry.header().entry_type() != tar::EntryType::Symlink {
+        return Ok(None);
+    }
+    Ok(entry.link_name()?.map(|target| target.into_owned()))
+}
+
+/// Archive path a symlink at `link` pointing to `target` refers to
+///
+/// Targets are resolved against the link's directory. Absolute targets and
+/// targets climbing above the archive root point outside the archive.
+fn resolve_link(link: &Path, target: &Path) -> Result<PathBuf, VfsError> {
+    let mut resolved = link.parent().map(Path::to_path_buf).unwrap_or_default();
+    for component in target.components() {
+        match component {
+            Component::Normal(name) => resolved.push(name),
+            Component::CurDir => {}
+            Component::ParentDir if resolved.pop() => {}
+            _ => {
+                return Err(VfsError::NotFound(format!(
+                    "{} (symlink to {} leaves the archive)",
+                    link.display(),
+                    target.display()
+                )))
+            }
+        }
+    }
+    Ok(resolved)
+}
+
+fn too_many_links(path: &Path) -> VfsError {
+    VfsError::Io(std::io::Error::other(format!(
+        "Too many levels of symbolic links: {}",
+        path.display()
+    )))
+}
+
+fn not_a_link(path: &Path) -> VfsError {
+    VfsError::Io(std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        format!("Not a symlink: {}", path.display()),
+    ))
+}
+
 fn is_gzip_archive(path: &Path) -> bool {
-    let name = match path.file_name() {
-        Some(name) => name.to_string_lossy().to_lowercase(),
-        None => return false,
-    };
+    tar_compression(path) == Some(CompressionType::Gzip)
+}
 
-    name.ends_with(".tar.gz") || name.ends_with(".tgz")
+/// Compression wrapped around a TAR archive, from its double or short
+/// extension (`.tar.gz`/`.tgz`, `.tar.bz2`/`.tbz2`, `.tar.xz`/`.txz`,
+/// `.tar.zst`/`.tzst`)
+pub(crate) fn tar_compression(path: &Path) -> Option<CompressionType> {
+    let name = path.file_name()?.to_string_lossy().to_lowercase();
+    [
+        (".tar.gz", CompressionType::Gzip),
+        (".tgz", CompressionType::Gzip),
+        (".tar.bz2", CompressionType::Bzip2),
+        (".tbz2", CompressionType::Bzip2),
+        (".tar.xz", CompressionType::Xz),
+        (".txz", CompressionType::Xz),
+        (".tar.zst", CompressionType::Zstd),
+        (".tzst", CompressionType::Zstd),
+    ]
+    .into_iter()
+    .find(|(suffix, _)| name.ends_with(suffix))
+    .map(|(_, compression)| compression)
 }
 
 /// Compression type for single-file compressed formats
+///
+/// More formats may be added, so matches outside this crate need a
+/// wildcard arm.
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
 pub enum CompressionType {
     Gzip,
     Bzip2,
     Xz,
+    Zstd,
 }
 
 impl CompressionType {
@@ -998,22 +1388,53 @@ impl CompressionType {
             Some(CompressionType::Bzip2)
         } else if name.ends_with(".xz") && !name.ends_with(".tar.xz") {
             Some(CompressionType::Xz)
+        } else if name.ends_with(".zst") && !name.ends_with(".tar.zst") {
+            Some(CompressionType::Zstd)
+        } else {
+            None
+        }
+    }
+
+    /// Detect compression type from the leading magic bytes of a file
+    pub fn from_magic(header: &[u8]) -> Option<Self> {
+        if header.starts_with(&[0x1f, 0x8b]) {
+            Some(CompressionType::Gzip)
+        } else if header.starts_with(b"BZh") {
+            Some(CompressionType::Bzip2)
+        } else if header.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Some(CompressionType::Xz)
+        } else if header.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Some(CompressionType::Zstd)
         } else {
             None
         }
     }
 
+    /// Wrap `reader` so that reading from it yields the decompressed bytes
+    pub fn decoder<'a>(
+        &self,
+        reader: impl Read + Send + 'a,
+    ) -> std::io::Result<Box<dyn Read + Send + 'a>> {
+        Ok(match self {
+            CompressionType::Gzip => Box::new(MultiGzDecoder::new(reader)),
+            CompressionType::Bzip2 => Box::new(BzDecoder::new(reader)),
+            CompressionType::Xz => Box::new(XzDecoder::new(reader)),
+            CompressionType::Zstd => Box::new(zstd::stream::read::Decoder::new(reader)?),
+        })
+    }
+
     /// Get the extension for this compression type
     pub fn extension(&self) -> &'static str {
         match self {
             CompressionType::Gzip => ".gz",
             CompressionType::Bzip2 => ".bz2",
             CompressionType::Xz => ".xz",
+            CompressionType::Zstd => ".zst",
         }
     }
 }
 
-/// VFS for single-file compressed formats (.gz, .bz2, .xz)
+/// VFS for single-file compressed formats (.gz, .bz2, .xz, .zst)
 /// Exposes the decompressed content as a virtual file
 pub struct CompressedFileVfs {
     instance_id: String,
@@ -1055,21 +1476,9 @@ impl CompressedFileVfs {
     fn decompress(&self) -> Result<Vec<u8>, VfsError> {
         let file = File::open(&self.archive_path)?;
         let mut contents = Vec::new();
-
-        match self.compression_type {
-            CompressionType::Gzip => {
-                let mut decoder = GzDecoder::new(file);
-                decoder.read_to_end(&mut contents)?;
-            }
-            CompressionType::Bzip2 => {
-                let mut decoder = BzDecoder::new(file);
-                decoder.read_to_end(&mut contents)?;
-            }
-            CompressionType::Xz => {
-                let mut decoder = XzDecoder::new(file);
-                decoder.read_to_end(&mut contents)?;
-            }
-        }
+        self.compression_type
+            .decoder(file)?
+            .read_to_end(&mut contents)?;
 
         Ok(contents)
     }
@@ -1123,6 +1532,7 @@ impl Vfs for CompressedFileVfs {
             size: contents.len() as u64,
             modified: file_meta.modified().unwrap_or(SystemTime::UNIX_EPOCH),
             is_dir: false,
+            file_type: FileType::Regular,
         }])
     }
 
@@ -1183,21 +1593,7 @@ impl WritableCompressedFileVfs {
         let content = if archive_path.exists() {
             let file = File::open(&archive_path)?;
             let mut contents = Vec::new();
-
-            match compression_type {
-                CompressionType::Gzip => {
-                    let mut decoder = GzDecoder::new(file);
-                    decoder.read_to_end(&mut contents)?;
-                }
-                CompressionType::Bzip2 => {
-                    let mut decoder = BzDecoder::new(file);
-                    decoder.read_to_end(&mut contents)?;
-                }
-                CompressionType::Xz => {
-                    let mut decoder = XzDecoder::new(file);
-                    decoder.read_to_end(&mut contents)?;
-                }
-            }
+            compression_type.decoder(file)?.read_to_end(&mut contents)?;
             contents
         } else {
             Vec::new()
@@ -1271,6 +1667,11 @@ impl WritableCompressedFileVfs {
                 encoder.write_all(&content)?;
                 encoder.finish()?;
             }
+            CompressionType::Zstd => {
+                let mut encoder = zstd::stream::write::Encoder::new(file, 0)?;
+                encoder.write_all(&content)?;
+                encoder.finish()?;
+            }
         }
 
         Ok(())
@@ -1326,6 +1727,7 @@ impl Vfs for WritableCompressedFileVfs {
             size: content.len() as u64,
             modified: SystemTime::now(),
             is_dir: false,
+            file_type: FileType::Regular,
         }])
     }
 
@@ -1367,6 +1769,7 @@ impl Vfs for WritableCompressedFileVfs {
             rename: false,
             create_dir: false,
             set_mtime: false,
+            expensive_reads: false,
         }
     }
 
@@ -1464,6 +1867,209 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_zip_content_fingerprint_is_crc_and_size() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let mut fingerprints = Vec::new();
+        for (name, content) in [("a.zip", "same"), ("b.zip", "same"), ("c.zip", "diff")] {
+            let path = temp.path().join(name);
+            let writer = WritableZipVfs::create(path.clone()).unwrap();
+            writer
+                .write_file(Path::new("file.txt"), content.as_bytes())
+                .unwrap();
+            writer.flush().unwrap();
+            let vfs = ZipVfs::new(path).unwrap();
+            fingerprints.push(vfs.content_fingerprint(Path::new("file.txt")).unwrap());
+        }
+
+        // CRC32 of "same"
+        assert_eq!(fingerprints[0].as_deref(), Some("zip:fc50f144:4"));
+        assert_eq!(fingerprints[0], fingerprints[1]);
+        assert_ne!(fingerprints[0], fingerprints[2]);
+    }
+
+    #[test]
+    fn test_sevenz_compression_info() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let path = temp.path().join("a.7z");
+        let writer = Writable7zVfs::create(path.clone()).unwrap();
+        let content = "compressible ".repeat(500);
+        writer
+            .write_file(Path::new("file.txt"), content.as_bytes())
+            .unwrap();
+        writer.flush().unwrap();
+
+        let vfs = SevenZVfs::new(path).unwrap();
+        let info = vfs
+            .compression_info(Path::new("file.txt"))
+            .unwrap()
+            .expect("entry alone in its block has a packed size");
+        assert!(info.compressed_size > 0);
+        assert!(info.compressed_size < content.len() as u64);
+        assert!(info.method.contains("lzma"), "{}", info.method);
+    }
+
+    fn write_7z(path: &Path, files: &[(&str, String)]) {
+        let writer = Writable7zVfs::create(path.to_path_buf()).unwrap();
+        for (name, content) in files {
+            if let Some(parent) = Path::new(name).parent() {
+                writer.create_dir_all(parent).unwrap();
+            }
+            writer
+                .write_file(Path::new(name), content.as_bytes())
+                .unwrap();
+        }
+        writer.flush().unwrap();
+    }
+
+    #[test]
+    fn test_sevenz_lists_from_header() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let path = temp.path().join("tree.7z");
+        write_7z(
+            &path,
+            &[
+                ("top.txt", "top".to_string()),
+                ("sub/inner.txt", "inner content".to_string()),
+                ("sub/deeper/leaf.txt", "leaf".to_string()),
+            ],
+        );
+
+        let vfs = SevenZVfs::new(path).unwrap();
+        let list = |dir: &str| -> Vec<(String, bool)> {
+            vfs.read_dir(Path::new(dir))
+                .unwrap()
+                .into_iter()
+                .map(|e| (e.path.to_string_lossy().replace('\\', "/"), e.is_dir))
+                .collect()
+        };
+        assert_eq!(
+            list(""),
+            vec![("sub".to_string(), true), ("top.txt".to_string(), false)]
+        );
+        assert_eq!(
+            list("sub"),
+            vec![
+                ("sub/deeper".to_string(), true),
+                ("sub/inner.txt".to_string(), false)
+            ]
+        );
+
+        let meta = vfs.metadata(Path::new("sub/inner.txt")).unwrap();
+        assert_eq!(meta.size, 13);
+        assert!(!meta.is_dir);
+        assert!(vfs.metadata(Path::new("sub/deeper")).unwrap().is_dir);
+        assert!(matches!(
+            vfs.metadata(Path::new("missing.txt")),
+            Err(VfsError::NotFound(_))
+        ));
+        assert!(matches!(
+            vfs.read_dir(Path::new("top.txt")),
+            Err(VfsError::NotADirectory(_))
+        ));
+
+        let mut content = String::new();
+        vfs.open_file(Path::new("sub/deeper/leaf.txt"))
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "leaf");
+    }
+
+    #[test]
+    fn test_sevenz_reads_single_entry_without_full_extraction() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let path = temp.path().join("partial.7z");
+        write_7z(
+            &path,
+            &[
+                ("keep.txt", "keep this ".repeat(200)),
+                ("broken.txt", "break that ".repeat(200)),
+            ],
+        );
+
+        // Overwrite the packed stream of broken.txt. Extracting the whole
+        // archive would now fail, so every successful call below proves
+        // that only the requested entry was decoded.
+        let archive = sevenz_rust::Archive::open(&path).unwrap();
+        let idx = archive
+            .files
+            .iter()
+            .position(|f| f.name() == "broken.txt")
+            .unwrap();
+        let folder = archive.stream_map.file_folder_index[idx].unwrap();
+        let pack = archive.stream_map.folder_first_pack_stream_index[folder];
+        let start = 32 + archive.pack_pos + archive.stream_map.pack_stream_offsets[pack];
+        let end = start + archive.pack_sizes[pack];
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[start as usize..end as usize].fill(0xA5);
+        std::fs::write(&path, bytes).unwrap();
+        assert!(decompress_file(&path, temp.path().join("out")).is_err());
+
+        let vfs = SevenZVfs::new(path).unwrap();
+        assert_eq!(vfs.read_dir(Path::new("")).unwrap().len(), 2);
+        assert_eq!(
+            vfs.metadata(Path::new("broken.txt")).unwrap().size,
+            "break that ".len() as u64 * 200
+        );
+
+        let mut content = String::new();
+        vfs.open_file(Path::new("keep.txt"))
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "keep this ".repeat(200));
+        assert!(vfs.open_file(Path::new("broken.txt")).is_err());
+    }
+
+    #[test]
+    fn test_sevenz_decodes_a_solid_block_once() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let names = ["a.txt", "b.txt", "c.txt"];
+        let mut entries = Vec::new();
+        let mut readers = Vec::new();
+        for name in names {
+            let source = temp.path().join(name);
+            std::fs::write(&source, name.repeat(100)).unwrap();
+            entries.push(sevenz_rust::SevenZArchiveEntry::from_path(
+                &source,
+                name.to_string(),
+            ));
+            readers.push(sevenz_rust::SourceReader::new(File::open(&source).unwrap()));
+        }
+        let path = temp.path().join("solid.7z");
+        let mut writer = sevenz_rust::SevenZWriter::create(&path).unwrap();
+        writer
+            .push_archive_entries(entries, readers.into())
+            .unwrap();
+        writer.finish().unwrap();
+
+        let read = |vfs: &SevenZVfs, name: &str| -> Result<String, VfsError> {
+            let mut content = String::new();
+            vfs.open_file(Path::new(name))?
+                .read_to_string(&mut content)?;
+            Ok(content)
+        };
+        let vfs = SevenZVfs::new(path.clone()).unwrap();
+        assert_eq!(read(&vfs, "c.txt").unwrap(), "c.txt".repeat(100));
+
+        // Corrupt the shared packed stream: every read below is served from
+        // the block decoded for c.txt
+        let archive = sevenz_rust::Archive::open(&path).unwrap();
+        assert_eq!(archive.folders.len(), 1);
+        let start = 32 + archive.pack_pos;
+        let end = start + archive.pack_sizes[0];
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[start as usize..end as usize].fill(0xA5);
+        std::fs::write(&path, bytes).unwrap();
+
+        for name in names {
+            assert_eq!(read(&vfs, name).unwrap(), name.repeat(100));
+        }
+        let fresh = SevenZVfs::new(path).unwrap();
+        assert!(read(&fresh, "a.txt").is_err());
+    }
+
     #[test]
     fn test_tar_vfs_creation() {
         let result = TarVfs::new(PathBuf::from("/nonexistent.tar"));
@@ -0,0 +1,549 @@
+//! Construct the right VFS backend from a path or URL.
+//!
+//! [`open`] is the single place that maps user input onto a backend, so
+//! frontends don't each carry their own archive detection and constructor
+//! dispatch. Supported forms:
+//!
+//! - a local directory: [`LocalVfs`]
+//! - a local archive, by extension: `.zip`, `.tar` (also compressed, as
+//!   `.tar.gz`/`.tgz`, `.tar.bz2`, `.tar.xz` or `.tar.zst`), `.7z`, `.rar`,
+//!   or a single compressed file (`.gz`, `.bz2`, `.xz`, `.zst`)
+//! - `sftp://[user[:password]@]host[:port]/path`
+//! - `s3://bucket/prefix`
+//! - `webdav://` and `webdavs://` URLs (plain and TLS), or `http://`/`https://`
+
+#[cfg(feature = "archives")]
+use super::archive::tar_compression;
+use super::LocalVfs;
+#[cfg(feature = "archives")]
+use super::{
+    CompressedFileVfs, CompressionType, RarVfs, SevenZVfs, TarVfs, Writable7zVfs,
+    WritableCompressedFileVfs, WritableTarVfs, WritableZipVfs, ZipVfs,
+};
+#[cfg(feature = "cloud")]
+use super::{
+    HostKeyVerification, S3Auth, S3Config, S3Vfs, SftpAuth, SftpConfig, SftpVfs, WebDavAuth,
+    WebDavConfig, WebDavVfs,
+};
+use rcompare_common::{Vfs, VfsError};
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+#[cfg(feature = "cloud")]
+use url::Url;
+
+/// Archive formats recognized by file name
+#[cfg(feature = "archives")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveKind {
+    Zip,
+    Tar,
+    SevenZ,
+    Rar,
+    /// A single gzip, bzip2 or xz compressed file
+    Compressed,
+}
+
+/// Credentials and connection options for remote backends
+///
+/// Values embedded in the URL (user name, password) take precedence.
+/// Unset fields fall back to each backend's default: the SSH agent for
+/// SFTP, the default AWS credential chain for S3, and no authentication
+/// for WebDAV.
+#[derive(Debug, Clone, Default)]
+pub struct VfsCredentials {
+    /// User name for SFTP or WebDAV basic authentication
+    pub username: Option<String>,
+    /// Password for SFTP or WebDAV basic authentication
+    pub password: Option<String>,
+    /// Private key file for SFTP
+    pub private_key: Option<PathBuf>,
+    /// Passphrase for `private_key`
+    pub passphrase: Option<String>,
+    /// Bearer token for WebDAV
+    pub token: Option<String>,
+    /// S3 access key ID (requires `secret_access_key`)
+    pub access_key_id: Option<String>,
+    /// S3 secret access key
+    pub secret_access_key: Option<String>,
+    /// S3 region (default `us-east-1`)
+    pub region: Option<String>,
+    /// Endpoint for S3-compatible services
+    pub endpoint: Option<String>,
+    /// SFTP host key policy
+    #[cfg(feature = "cloud")]
+    pub host_key: HostKeyVerification,
+}
+
+/// Open a local directory, archive or remote URL as a VFS
+///
+/// Input is a URL only when it starts with a `scheme://` and no local path
+/// of that name exists; anything else, including paths that are not valid
+/// UTF-8, is opened as a local path. Remote backends connect (or build their
+/// client) immediately, so connection and authentication errors surface
+/// here.
+pub fn open(
+    uri_or_path: impl AsRef<OsStr>,
+    creds: &VfsCredentials,
+) -> Result<Box<dyn Vfs>, VfsError> {
+    let path = Path::new(uri_or_path.as_ref());
+    if let Some((uri, scheme)) = url_scheme(path) {
+        return open_url(uri, scheme, creds);
+    }
+
+    if path.is_dir() {
+        return Ok(Box::new(LocalVfs::new(path.to_path_buf())));
+    }
+    if !path.exists() {
+        return Err(VfsError::NotFound(path.display().to_string()));
+    }
+    open_archive(path.to_path_buf())
+}
+
+/// The input and its scheme, if `path` is a `scheme://` URL rather than a
+/// local path
+///
+/// Windows drive paths like `C:\` never contain "://", and a scheme must
+/// start with a letter followed only by letters, digits, `+`, `-` or `.`,
+/// so `./dir://x` stays a path.
+fn url_scheme(path: &Path) -> Option<(&str, &str)> {
+    let uri = path.to_str()?;
+    let (scheme, _) = uri.split_once("://")?;
+    let mut chars = scheme.chars();
+    let valid = chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    (valid && !path.exists()).then_some((uri, scheme))
+}
+
+/// Like [`open`], but open local archives through their writable backend
+///
+/// Writes to an archive are staged and only land in the file on
+/// [`Vfs::flush`]; a missing ZIP, TAR or 7Z archive is created then. RAR archives can't be written and are rejected here;
+/// directories and remote URLs open exactly as with [`open`], so check
+/// [`Vfs::capabilities`] before writing to them.
+pub fn open_writable(
+    uri_or_path: impl AsRef<OsStr>,
+    creds: &VfsCredentials,
+) -> Result<Box<dyn Vfs>, VfsError> {
+    let path = Path::new(uri_or_path.as_ref());
+    if url_scheme(path).is_some() || path.is_dir() {
+        return open(path, creds);
+    }
+
+    #[cfg(feature = "archives")]
+    {
+        let path = path.to_path_buf();
+        match detect_archive_kind(&path) {
+            Some(ArchiveKind::Zip) => Ok(Box::new(WritableZipVfs::new(path)?)),
+            Some(ArchiveKind::Tar)
+                if !matches!(tar_compression(&path), None | Some(CompressionType::Gzip)) =>
+            {
+                Err(VfsError::Unsupported(format!(
+                    "Only .tar, .tar.gz and .tgz archives can be written: {}",
+                    path.display()
+                )))
+            }
+            Some(ArchiveKind::Tar) => Ok(Box::new(WritableTarVfs::new(path)?)),
+            Some(ArchiveKind::SevenZ) => Ok(Box::new(Writable7zVfs::new(path)?)),
+            Some(ArchiveKind::Compressed) => Ok(Box::new(WritableCompressedFileVfs::new(path)?)),
+            Some(ArchiveKind::Rar) => Err(VfsError::Unsupported(format!(
+                "RAR archives are read-only: {}",
+                path.display()
+            ))),
+            None => open(path, creds),
+        }
+    }
+    #[cfg(not(feature = "archives"))]
+    open(path, creds)
+}
+
+#[cfg(feature = "archives")]
+fn open_archive(path: PathBuf) -> Result<Box<dyn Vfs>, VfsError> {
+    match detect_archive_kind(&path) {
+        Some(ArchiveKind::Zip) => Ok(Box::new(ZipVfs::new(path)?)),
+        Some(ArchiveKind::Tar) => Ok(Box::new(TarVfs::new(path)?)),
+        Some(ArchiveKind::SevenZ) => Ok(Box::new(SevenZVfs::new(path)?)),
+        Some(ArchiveKind::Rar) => Ok(Box::new(RarVfs::new(path)?)),
+        Some(ArchiveKind::Compressed) => Ok(Box::new(CompressedFileVfs::new(path)?)),
+        None => Err(VfsError::Unsupported(format!(
+            "Not a directory or supported archive \
+             (.zip, .tar, .tar.gz, .tar.bz2, .tar.xz, .tar.zst, .7z, .rar, \
+             .gz, .bz2, .xz, .zst): {}",
+            path.display()
+        ))),
+    }
+}
+
+#[cfg(not(feature = "archives"))]
+fn open_archive(path: PathBuf) -> Result<Box<dyn Vfs>, VfsError> {
+    Err(VfsError::Unsupported(format!(
+        "Not a directory (archive support requires the `archives` feature): {}",
+        path.display()
+    )))
+}
+
+#[cfg(feature = "cloud")]
+fn open_url(uri: &str, scheme: &str, creds: &VfsCredentials) -> Result<Box<dyn Vfs>, VfsError> {
+    let url = Url::parse(uri).map_err(|e| invalid_url(uri, e))?;
+    match scheme.to_ascii_lowercase().as_str() {
+        "sftp" | "ssh" => open_sftp(&url, creds),
+        "s3" => open_s3(&url, creds),
+        "webdav" | "webdavs" | "http" | "https" => open_webdav(&url, creds),
+        other => Err(VfsError::Unsupported(format!(
+            "Unsupported URL scheme '{}': {}",
+            other, uri
+        ))),
+    }
+}
+
+#[cfg(not(feature = "cloud"))]
+fn open_url(uri: &str, _scheme: &str, _creds: &VfsCredentials) -> Result<Box<dyn Vfs>, VfsError> {
+    Err(VfsError::Unsupported(format!(
+        "Remote URLs require the `cloud` feature: {}",
+        uri
+    )))
+}
+
+/// Detect an archive format from a file name (case-insensitive)
+///
+/// Compressed TAR names such as `.tar.xz` are checked before single
+/// compressed files, so they open as archives rather than as one file.
+#[cfg(feature = "archives")]
+pub fn detect_archive_kind(path: &Path) -> Option<ArchiveKind> {
+    let name = path.file_name()?.to_string_lossy().to_lowercase();
+    if name.ends_with(".tar") || tar_compression(path).is_some() {
+        Some(ArchiveKind::Tar)
+    } else if name.ends_with(".zip") {
+        Some(ArchiveKind::Zip)
+    } else if name.ends_with(".7z") {
+        Some(ArchiveKind::SevenZ)
+    } else if name.ends_with(".rar") {
+        Some(ArchiveKind::Rar)
+    } else if CompressionType::from_path(path).is_some() {
+        Some(ArchiveKind::Compressed)
+    } else {
+        None
+    }
+}
+
+#[cfg(feature = "cloud")]
+fn open_sftp(url: &Url, creds: &VfsCredentials) -> Result<Box<dyn Vfs>, VfsError> {
+    let host = url
+        .host_str()
+        .ok_or_else(|| VfsError::Unsupported(format!("SFTP URL has no host: {}", url)))?;
+    let username = Some(url.username())
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .or_else(|| creds.username.clone())
+        .or_else(|| std::env::var("USER").ok())
+        .unwrap_or_default();
+    let password = url
+        .password()
+        .map(str::to_string)
+        .or_else(|| creds.password.clone());
+    let auth = match (&creds.private_key, password) {
+        (Some(private_key), _) => SftpAuth::KeyFile {
+            private_key: private_key.clone(),
+            passphrase: creds.passphrase.clone(),
+        },
+        (None, Some(password)) => SftpAuth::Password(password),
+        (None, None) => SftpAuth::Agent,
+    };
+
+    Ok(Box::new(SftpVfs::new(SftpConfig {
+        host: host.to_string(),
+        port: url.port().unwrap_or(22),
+        username,
+        auth,
+        root_path: PathBuf::from(url.path()),
+        host_key: creds.host_key.clone(),
+    })?))
+}
+
+#[cfg(feature = "cloud")]
+fn open_s3(url: &Url, creds: &VfsCredentials) -> Result<Box<dyn Vfs>, VfsError> {
+    let bucket = url
+        .host_str()
+        .ok_or_else(|| VfsError::Unsupported(format!("S3 URL has no bucket: {}", url)))?;
+    let auth = match (&creds.access_key_id, &creds.secret_access_key) {
+        (Some(access_key_id), Some(secret_access_key)) => S3Auth::AccessKey {
+            access_key_id: access_key_id.clone(),
+            secret_access_key: secret_access_key.clone(),
+            session_token: None,
+        },
+        _ => S3Auth::Default,
+    };
+
+    Ok(Box::new(S3Vfs::new(S3Config {
+        bucket: bucket.to_string(),
+        region: creds
+            .region
+            .clone()
+            .unwrap_or_else(|| "us-east-1".to_string()),
+        prefix: PathBuf::from(url.path().trim_start_matches('/')),
+        auth,
+        endpoint: creds.endpoint.clone(),
+    })?))
+}
+
+#[cfg(feature = "cloud")]
+fn open_webdav(url: &Url, creds: &VfsCredentials) -> Result<Box<dyn Vfs>, VfsError> {
+    let http_scheme = match url.scheme() {
+        "webdavs" | "https" => "https",
+        _ => "http",
+    };
+    let host = url
+        .host_str()
+        .ok_or_else(|| VfsError::Unsupported(format!("WebDAV URL has no host: {}", url)))?;
+    let base = match url.port() {
+        Some(port) => format!("{}://{}:{}", http_scheme, host, port),
+        None => format!("{}://{}", http_scheme, host),
+    };
+
+    let username = Some(url.username())
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .or_else(|| creds.username.clone());
+    let password = url
+        .password()
+        .map(str::to_string)
+        .or_else(|| creds.password.clone());
+    let auth = match (&creds.token, username) {
+        (Some(token), _) => WebDavAuth::Bearer {
+            token: token.clone(),
+        },
+        (None, Some(username)) => WebDavAuth::Basic {
+            username,
+            password: password.unwrap_or_default(),
+        },
+        (None, None) => WebDavAuth::None,
+    };
+
+    Ok(Box::new(WebDavVfs::new(WebDavConfig {
+        url: base,
+        auth,
+        root_path: PathBuf::from(url.path()),
+    })?))
+}
+
+#[cfg(feature = "cloud")]
+fn invalid_url(input: &str, err: url::ParseError) -> VfsError {
+    VfsError::Io(std::io::Error::new(
+        std::io::ErrorKind::InvalidInput,
+        format!("Invalid URL '{}': {}", input, err),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[cfg(any(feature = "archives", feature = "cloud"))]
+    fn open_id(uri: impl AsRef<OsStr>) -> String {
+        open(uri, &VfsCredentials::default())
+            .unwrap()
+            .instance_id()
+            .to_string()
+    }
+
+    #[test]
+    #[cfg(feature = "archives")]
+    fn test_detect_archive_kind() {
+        let kind = |name: &str| detect_archive_kind(Path::new(name));
+        assert_eq!(kind("/path/to/FILE.ZIP"), Some(ArchiveKind::Zip));
+        assert_eq!(kind("file.tar"), Some(ArchiveKind::Tar));
+        assert_eq!(kind("FILE.TAR.GZ"), Some(ArchiveKind::Tar));
+        assert_eq!(kind("file.tgz"), Some(ArchiveKind::Tar));
+        assert_eq!(kind("file.tar.bz2"), Some(ArchiveKind::Tar));
+        assert_eq!(kind("FILE.TAR.XZ"), Some(ArchiveKind::Tar));
+        assert_eq!(kind("file.tar.zst"), Some(ArchiveKind::Tar));
+        assert_eq!(kind("file.txz"), Some(ArchiveKind::Tar));
+        assert_eq!(kind("file.7z"), Some(ArchiveKind::SevenZ));
+        assert_eq!(kind("file.rar"), Some(ArchiveKind::Rar));
+        assert_eq!(kind("notes.txt.gz"), Some(ArchiveKind::Compressed));
+        assert_eq!(kind("notes.txt.xz"), Some(ArchiveKind::Compressed));
+        assert_eq!(kind("notes.txt.zst"), Some(ArchiveKind::Compressed));
+        assert_eq!(kind("file.txt"), None);
+        assert_eq!(kind(""), None);
+    }
+
+    #[test]
+    #[cfg(feature = "archives")]
+    fn test_open_dispatches_local_paths() {
+        use std::io::{Read, Write};
+
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+
+        let zip_path = root.join("data.zip");
+        let mut zip = zip::ZipWriter::new(fs::File::create(&zip_path).unwrap());
+        zip.start_file("a.txt", zip::write::FileOptions::default())
+            .unwrap();
+        zip.write_all(b"zip").unwrap();
+        zip.finish().unwrap();
+
+        let tar_path = root.join("data.tar.gz");
+        let encoder = flate2::write::GzEncoder::new(
+            fs::File::create(&tar_path).unwrap(),
+            flate2::Compression::default(),
+        );
+        let mut tar = tar::Builder::new(encoder);
+        let mut header = tar::Header::new_gnu();
+        header.set_size(3);
+        header.set_cksum();
+        tar.append_data(&mut header, "a.txt", &b"tar"[..]).unwrap();
+        tar.into_inner().unwrap().finish().unwrap();
+
+        let sevenz_dir = root.join("sevenz");
+        fs::create_dir(&sevenz_dir).unwrap();
+        fs::write(sevenz_dir.join("a.txt"), "7z").unwrap();
+        let sevenz_path = root.join("data.7z");
+        sevenz_rust::compress_to_path(&sevenz_dir, &sevenz_path).unwrap();
+
+        let gz_path = root.join("notes.txt.gz");
+        let mut gz = flate2::write::GzEncoder::new(
+            fs::File::create(&gz_path).unwrap(),
+            flate2::Compression::default(),
+        );
+        gz.write_all(b"gz").unwrap();
+        gz.finish().unwrap();
+
+        let xz_tar_path = root.join("data.tar.xz");
+        let mut tar = tar::Builder::new(xz2::write::XzEncoder::new(
+            fs::File::create(&xz_tar_path).unwrap(),
+            6,
+        ));
+        let mut header = tar::Header::new_gnu();
+        header.set_size(2);
+        header.set_cksum();
+        tar.append_data(&mut header, "a.txt", &b"xz"[..]).unwrap();
+        tar.into_inner().unwrap().finish().unwrap();
+
+        assert!(open_id(root).starts_with("local:"));
+        assert!(open_id(&zip_path).starts_with("zip:"));
+        assert!(open_id(&tar_path).starts_with("tar:"));
+        assert!(open_id(&sevenz_path).starts_with("7z:"));
+        assert!(open_id(&gz_path).starts_with("compressed:"));
+        let xz_tar = open(&xz_tar_path, &VfsCredentials::default()).unwrap();
+        assert!(xz_tar.instance_id().starts_with("tar:"));
+        let mut contents = String::new();
+        xz_tar
+            .open_file(Path::new("a.txt"))
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        assert_eq!(contents, "xz");
+        assert!(matches!(
+            open_writable(&xz_tar_path, &VfsCredentials::default()),
+            Err(VfsError::Unsupported(_))
+        ));
+
+        let plain = root.join("plain.txt");
+        fs::write(&plain, "text").unwrap();
+        let creds = VfsCredentials::default();
+        assert!(matches!(
+            open(&plain, &creds),
+            Err(VfsError::Unsupported(_))
+        ));
+        assert!(matches!(
+            open(root.join("missing.zip"), &creds),
+            Err(VfsError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_open_keeps_local_paths_that_look_like_urls() {
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path().join("s3:").join("bucket");
+        fs::create_dir_all(&dir).unwrap();
+        let creds = VfsCredentials::default();
+
+        let spelled = format!("{}/s3://bucket", temp.path().display());
+        assert!(open(&spelled, &creds)
+            .unwrap()
+            .instance_id()
+            .starts_with("local:"));
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::ffi::OsStrExt;
+            let non_utf8 = temp.path().join(OsStr::from_bytes(b"caf\xe9"));
+            fs::create_dir(&non_utf8).unwrap();
+            let vfs = open(&non_utf8, &creds).unwrap();
+            assert!(vfs.instance_id().starts_with("local:"));
+            assert!(vfs.read_dir(Path::new("")).unwrap().is_empty());
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "archives")]
+    fn test_open_writable_picks_writable_archive_backends() {
+        let temp = TempDir::new().unwrap();
+        let creds = VfsCredentials::default();
+
+        let zip = open_writable(temp.path().join("new.zip"), &creds).unwrap();
+        assert!(zip.instance_id().starts_with("zip-rw:"));
+        assert!(zip.capabilities().write);
+
+        let dir = open_writable(temp.path(), &creds).unwrap();
+        assert!(dir.instance_id().starts_with("local:"));
+
+        let rar = temp.path().join("data.rar");
+        fs::write(&rar, "not really a rar").unwrap();
+        assert!(matches!(
+            open_writable(&rar, &creds),
+            Err(VfsError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    #[cfg(feature = "cloud")]
+    fn test_open_dispatches_urls() {
+        let creds = VfsCredentials {
+            access_key_id: Some("AKIDEXAMPLE".to_string()),
+            secret_access_key: Some("secret".to_string()),
+            region: Some("eu-west-1".to_string()),
+            ..Default::default()
+        };
+        let s3 = open("s3://bucket/some/prefix", &creds).unwrap();
+        assert_eq!(s3.instance_id(), "s3://bucket/some/prefix");
+
+        assert!(open_id("webdav://dav.example.com/remote.php").starts_with("webdav://http://"));
+        assert!(open_id("webdavs://dav.example.com:8443/files")
+            .starts_with("webdav://https://dav.example.com:8443/files"));
+        assert!(open_id("https://dav.example.com/files").starts_with("webdav://https://"));
+
+        // SFTP connects immediately; a closed port shows it was dispatched
+        let err = open("sftp://user@127.0.0.1:1/data", &VfsCredentials::default())
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("Failed to connect to 127.0.0.1:1"));
+
+        assert!(matches!(
+            open("ftp://example.com/data", &VfsCredentials::default()),
+            Err(VfsError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    #[cfg(not(feature = "archives"))]
+    fn test_open_rejects_archives_without_archives_feature() {
+        let temp = TempDir::new().unwrap();
+        let zip_path = temp.path().join("data.zip");
+        fs::write(&zip_path, "not opened").unwrap();
+        assert!(matches!(
+            open(zip_path.to_str().unwrap(), &VfsCredentials::default()),
+            Err(VfsError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    #[cfg(not(feature = "cloud"))]
+    fn test_open_rejects_urls_without_cloud_feature() {
+        assert!(matches!(
+            open("s3://bucket/prefix", &VfsCredentials::default()),
+            Err(VfsError::Unsupported(_))
+        ));
+    }
+}
@@ -1,4 +1,6 @@
-use rcompare_common::{FileEntry, FileMetadata, Vfs, VfsCapabilities, VfsError};
+use rcompare_common::{
+    FileEntry, FileMetadata, FileType, HashAlgorithm, Vfs, VfsCapabilities, VfsError,
+};
 use std::fs;
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
@@ -34,6 +36,22 @@ impl Vfs for LocalVfs {
         })
     }
 
+    fn symlink_metadata(&self, path: &Path) -> Result<FileMetadata, VfsError> {
+        let full_path = self.root.join(path);
+        let meta = fs::symlink_metadata(&full_path)?;
+
+        Ok(FileMetadata {
+            size: meta.len(),
+            modified: meta.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+            is_dir: meta.is_dir(),
+            is_symlink: meta.is_symlink(),
+        })
+    }
+
+    fn read_link(&self, path: &Path) -> Result<PathBuf, VfsError> {
+        Ok(fs::read_link(self.root.join(path))?)
+    }
+
     fn read_dir(&self, path: &Path) -> Result<Vec<FileEntry>, VfsError> {
         let full_path = self.root.join(path);
 
@@ -52,6 +70,7 @@ impl Vfs for LocalVfs {
                     size: meta.len(),
                     modified: meta.modified().unwrap_or(SystemTime::UNIX_EPOCH),
                     is_dir: meta.is_dir(),
+                    file_type: FileType::from_std(meta.file_type()),
                 })
             })
             .collect();
@@ -70,6 +89,17 @@ impl Vfs for LocalVfs {
         Ok(Box::new(file))
     }
 
+    fn hash(&self, path: &Path, algorithm: HashAlgorithm) -> Result<Option<Vec<u8>>, VfsError> {
+        let full_path = self.root.join(path);
+
+        if !full_path.is_file() {
+            return Err(VfsError::NotAFile(full_path.display().to_string()));
+        }
+
+        let file = fs::File::open(&full_path)?;
+        Ok(Some(super::stream_hash(file, algorithm)?))
+    }
+
     fn remove_file(&self, path: &Path) -> Result<(), VfsError> {
         let full_path = self.root.join(path);
         fs::remove_file(&full_path)?;
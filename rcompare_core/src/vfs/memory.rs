@@ -0,0 +1,412 @@
+//! In-memory VFS for trees built in code
+//!
+//! `MemVfs` holds a whole directory tree in memory. It is meant as a test
+//! double and for embedders that generate trees programmatically and want
+//! to scan, compare or sync them like any other source.
+
+use rcompare_common::{FileEntry, FileMetadata, FileType, Vfs, VfsCapabilities, VfsError};
+use std::collections::BTreeMap;
+use std::io::{Read, Write};
+use std::ops::Bound;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::SystemTime;
+
+type Nodes = BTreeMap<PathBuf, MemNode>;
+
+#[derive(Clone)]
+enum MemNode {
+    Dir {
+        modified: SystemTime,
+    },
+    File {
+        content: Vec<u8>,
+        modified: SystemTime,
+    },
+}
+
+impl MemNode {
+    fn modified(&self) -> SystemTime {
+        match self {
+            Self::Dir { modified } | Self::File { modified, .. } => *modified,
+        }
+    }
+
+    fn size(&self) -> u64 {
+        match self {
+            Self::Dir { .. } => 0,
+            Self::File { content, .. } => content.len() as u64,
+        }
+    }
+
+    fn is_dir(&self) -> bool {
+        matches!(self, Self::Dir { .. })
+    }
+}
+
+/// Read-write VFS backed by an in-memory tree
+///
+/// Paths are relative to the tree root; leading `/` and `.` components are
+/// ignored, so `"/a/b.txt"`, `"./a/b.txt"` and `"a/b.txt"` name the same
+/// file. Writes create missing parent directories and stamp entries with
+/// the current time unless [`set_mtime`](Vfs::set_mtime) pins it. Clones
+/// share the same tree.
+#[derive(Clone)]
+pub struct MemVfs {
+    instance_id: String,
+    nodes: Arc<RwLock<Nodes>>,
+}
+
+impl MemVfs {
+    /// Create an empty tree identified as `mem:<name>`
+    pub fn new(name: &str) -> Self {
+        let mut nodes = Nodes::new();
+        nodes.insert(
+            PathBuf::new(),
+            MemNode::Dir {
+                modified: SystemTime::now(),
+            },
+        );
+        Self {
+            instance_id: format!("mem:{}", name),
+            nodes: Arc::new(RwLock::new(nodes)),
+        }
+    }
+
+    /// Add a file, creating its parent directories
+    ///
+    /// # Panics
+    ///
+    /// Panics if `path` or one of its parents is already used by an entry
+    /// of the other kind.
+    pub fn with_file(self, path: impl AsRef<Path>, content: impl Into<Vec<u8>>) -> Self {
+        let path = path.as_ref();
+        if let Err(e) = self.write_file(path, &content.into()) {
+            panic!(
+                "cannot add {} to {}: {}",
+                path.display(),
+                self.instance_id,
+                e
+            );
+        }
+        self
+    }
+
+    /// Add a directory and its parents
+    ///
+    /// # Panics
+    ///
+    /// Panics if `path` or one of its parents is already a file.
+    pub fn with_dir(self, path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref();
+        if let Err(e) = self.create_dir_all(path) {
+            panic!(
+                "cannot add {} to {}: {}",
+                path.display(),
+                self.instance_id,
+                e
+            );
+        }
+        self
+    }
+
+    fn read(&self) -> Result<RwLockReadGuard<'_, Nodes>, VfsError> {
+        self.nodes
+            .read()
+            .map_err(|_| VfsError::Io(std::io::Error::other("Failed to lock memory tree")))
+    }
+
+    fn write(&self) -> Result<RwLockWriteGuard<'_, Nodes>, VfsError> {
+        self.nodes
+            .write()
+            .map_err(|_| VfsError::Io(std::io::Error::other("Failed to lock memory tree")))
+    }
+}
+
+impl Default for MemVfs {
+    fn default() -> Self {
+        Self::new("default")
+    }
+}
+
+/// Key of `path` in the tree: its normal components only
+fn normalize(path: &Path) -> PathBuf {
+    path.components()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(name),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Create the missing ancestors of `path` as directories
+fn insert_parents(nodes: &mut Nodes, path: &Path, modified: SystemTime) -> Result<(), VfsError> {
+    let mut ancestors: Vec<&Path> = path
+        .ancestors()
+        .skip(1)
+        .filter(|p| !p.as_os_str().is_empty())
+        .collect();
+    ancestors.reverse();
+
+    for ancestor in ancestors {
+        match nodes.get(ancestor) {
+            Some(MemNode::Dir { .. }) => {}
+            Some(MemNode::File { .. }) => {
+                return Err(VfsError::NotADirectory(ancestor.display().to_string()))
+            }
+            None => {
+                nodes.insert(ancestor.to_path_buf(), MemNode::Dir { modified });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Store `content` as the file at `path`, creating its parents
+fn insert_file(nodes: &mut Nodes, path: PathBuf, content: Vec<u8>) -> Result<(), VfsError> {
+    if nodes.get(&path).is_some_and(MemNode::is_dir) {
+        return Err(VfsError::NotAFile(path.display().to_string()));
+    }
+    let modified = SystemTime::now();
+    insert_parents(nodes, &path, modified)?;
+    nodes.insert(path, MemNode::File { content, modified });
+    Ok(())
+}
+
+/// `path` and every entry below it, in tree order
+fn subtree(nodes: &Nodes, path: &Path) -> Vec<PathBuf> {
+    nodes
+        .range::<Path, _>((Bound::Included(path), Bound::Unbounded))
+        .take_while(|(key, _)| key.starts_with(path))
+        .map(|(key, _)| key.clone())
+        .collect()
+}
+
+/// Writer returned by [`MemVfs::create_file`]; stores its buffer on flush
+/// and when dropped
+///
+/// Only writes since the last flush update the file, and a file removed or
+/// replaced by a directory in the meantime is left alone rather than
+/// recreated.
+struct MemFileWriter {
+    nodes: Arc<RwLock<Nodes>>,
+    path: PathBuf,
+    buffer: Vec<u8>,
+    dirty: bool,
+}
+
+impl Write for MemFileWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        self.dirty = true;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        let mut nodes = self
+            .nodes
+            .write()
+            .map_err(|_| std::io::Error::other("Failed to lock memory tree"))?;
+        if let Some(MemNode::File { content, modified }) = nodes.get_mut(&self.path) {
+            content.clone_from(&self.buffer);
+            *modified = SystemTime::now();
+        }
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+impl Drop for MemFileWriter {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+impl Vfs for MemVfs {
+    fn instance_id(&self) -> &str {
+        &self.instance_id
+    }
+
+    fn metadata(&self, path: &Path) -> Result<FileMetadata, VfsError> {
+        let nodes = self.read()?;
+        let node = nodes
+            .get(&normalize(path))
+            .ok_or_else(|| VfsError::NotFound(path.display().to_string()))?;
+
+        Ok(FileMetadata {
+            size: node.size(),
+            modified: node.modified(),
+            is_dir: node.is_dir(),
+            is_symlink: false,
+        })
+    }
+
+    fn read_dir(&self, path: &Path) -> Result<Vec<FileEntry>, VfsError> {
+        let dir = normalize(path);
+        let nodes = self.read()?;
+        match nodes.get(&dir) {
+            Some(MemNode::Dir { .. }) => {}
+            Some(MemNode::File { .. }) => {
+                return Err(VfsError::NotADirectory(path.display().to_string()))
+            }
+            None => return Err(VfsError::NotFound(path.display().to_string())),
+        }
+
+        let entries = nodes
+            .range::<Path, _>((Bound::Excluded(dir.as_path()), Bound::Unbounded))
+            .take_while(|(key, _)| key.starts_with(&dir))
+            .filter(|(key, _)| key.parent() == Some(dir.as_path()))
+            .map(|(key, node)| FileEntry {
+                path: key.clone(),
+                size: node.size(),
+                modified: node.modified(),
+                is_dir: node.is_dir(),
+                file_type: FileType::from_is_dir(node.is_dir()),
+            })
+            .collect();
+
+        Ok(entries)
+    }
+
+    fn open_file(&self, path: &Path) -> Result<Box<dyn Read + Send>, VfsError> {
+        let nodes = self.read()?;
+        match nodes.get(&normalize(path)) {
+            Some(MemNode::File { content, .. }) => {
+                Ok(Box::new(std::io::Cursor::new(content.clone())))
+            }
+            Some(MemNode::Dir { .. }) => Err(VfsError::NotAFile(path.display().to_string())),
+            None => Err(VfsError::NotFound(path.display().to_string())),
+        }
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<(), VfsError> {
+        let key = normalize(path);
+        let mut nodes = self.write()?;
+        match nodes.get(&key) {
+            Some(MemNode::File { .. }) => {
+                nodes.remove(&key);
+                Ok(())
+            }
+            Some(MemNode::Dir { .. }) => Err(VfsError::NotAFile(path.display().to_string())),
+            None => Err(VfsError::NotFound(path.display().to_string())),
+        }
+    }
+
+    fn copy_file(&self, src: &Path, dest: &Path) -> Result<(), VfsError> {
+        let mut nodes = self.write()?;
+        let content = match nodes.get(&normalize(src)) {
+            Some(MemNode::File { content, .. }) => content.clone(),
+            Some(MemNode::Dir { .. }) => return Err(VfsError::NotAFile(src.display().to_string())),
+            None => return Err(VfsError::NotFound(src.display().to_string())),
+        };
+        insert_file(&mut nodes, normalize(dest), content)
+    }
+
+    fn is_writable(&self) -> bool {
+        true
+    }
+
+    fn capabilities(&self) -> VfsCapabilities {
+        VfsCapabilities::full()
+    }
+
+    fn create_file(&self, path: &Path) -> Result<Box<dyn Write + Send>, VfsError> {
+        let key = normalize(path);
+        insert_file(&mut *self.write()?, key.clone(), Vec::new())?;
+        Ok(Box::new(MemFileWriter {
+            nodes: Arc::clone(&self.nodes),
+            path: key,
+            buffer: Vec::new(),
+            dirty: false,
+        }))
+    }
+
+    fn create_dir(&self, path: &Path) -> Result<(), VfsError> {
+        let key = normalize(path);
+        let mut nodes = self.write()?;
+        if nodes.contains_key(&key) {
+            return Err(VfsError::Io(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                path.display().to_string(),
+            )));
+        }
+        match key.parent().map(|parent| nodes.get(parent)) {
+            Some(Some(MemNode::Dir { .. })) | None => {}
+            Some(Some(MemNode::File { .. })) => {
+                return Err(VfsError::NotADirectory(path.display().to_string()))
+            }
+            Some(None) => return Err(VfsError::NotFound(path.display().to_string())),
+        }
+        nodes.insert(
+            key,
+            MemNode::Dir {
+                modified: SystemTime::now(),
+            },
+        );
+        Ok(())
+    }
+
+    fn create_dir_all(&self, path: &Path) -> Result<(), VfsError> {
+        let key = normalize(path);
+        let mut nodes = self.write()?;
+        let modified = SystemTime::now();
+        match nodes.get(&key) {
+            Some(MemNode::Dir { .. }) => Ok(()),
+            Some(MemNode::File { .. }) => Err(VfsError::NotADirectory(path.display().to_string())),
+            None => {
+                insert_parents(&mut nodes, &key, modified)?;
+                nodes.insert(key, MemNode::Dir { modified });
+                Ok(())
+            }
+        }
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<(), VfsError> {
+        let from_key = normalize(from);
+        let to_key = normalize(to);
+        if from_key.as_os_str().is_empty() || to_key.starts_with(&from_key) {
+            return Err(VfsError::Io(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("cannot move {} to {}", from.display(), to.display()),
+            )));
+        }
+
+        let mut nodes = self.write()?;
+        if !nodes.contains_key(&from_key) {
+            return Err(VfsError::NotFound(from.display().to_string()));
+        }
+        if nodes.get(&to_key).is_some_and(MemNode::is_dir) {
+            return Err(VfsError::NotAFile(to.display().to_string()));
+        }
+        insert_parents(&mut nodes, &to_key, SystemTime::now())?;
+
+        for old in subtree(&nodes, &from_key) {
+            if let Some(node) = nodes.remove(&old) {
+                let new = match old.strip_prefix(&from_key) {
+                    Ok(suffix) if !suffix.as_os_str().is_empty() => to_key.join(suffix),
+                    _ => to_key.clone(),
+                };
+                nodes.insert(new, node);
+            }
+        }
+        Ok(())
+    }
+
+    fn set_mtime(&self, path: &Path, mtime: SystemTime) -> Result<(), VfsError> {
+        let mut nodes = self.write()?;
+        match nodes.get_mut(&normalize(path)) {
+            Some(MemNode::Dir { modified } | MemNode::File { modified, .. }) => {
+                *modified = mtime;
+                Ok(())
+            }
+            None => Err(VfsError::NotFound(path.display().to_string())),
+        }
+    }
+
+    fn write_file(&self, path: &Path, content: &[u8]) -> Result<(), VfsError> {
+        insert_file(&mut *self.write()?, normalize(path), content.to_vec())
+    }
+}
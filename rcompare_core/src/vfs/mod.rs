@@ -1,28 +1,72 @@
+#[cfg(feature = "archives")]
 pub mod archive;
+pub mod factory;
 pub mod local;
+pub mod memory;
+#[cfg(feature = "cloud")]
 pub mod s3;
+#[cfg(feature = "cloud")]
 pub mod sftp;
 pub mod virtual_vfs;
+#[cfg(feature = "cloud")]
 pub mod webdav;
 
-#[cfg(test)]
+#[cfg(all(test, feature = "cloud"))]
 mod tests_cloud;
 
 #[cfg(test)]
 mod tests_local;
 
-#[cfg(test)]
+#[cfg(all(test, feature = "archives"))]
 mod tests_archive;
 
+#[cfg(test)]
+mod tests_memory;
+
 #[cfg(test)]
 mod tests_virtual;
 
+#[cfg(feature = "archives")]
 pub use archive::{
     CompressedFileVfs, CompressionType, RarVfs, SevenZVfs, TarVfs, Writable7zVfs,
     WritableCompressedFileVfs, WritableTarVfs, WritableZipVfs, ZipVfs,
 };
+#[cfg(feature = "archives")]
+pub use factory::{detect_archive_kind, ArchiveKind};
+pub use factory::{open, open_writable, VfsCredentials};
 pub use local::LocalVfs;
+pub use memory::MemVfs;
+#[cfg(feature = "cloud")]
 pub use s3::{S3Auth, S3Config, S3Vfs};
-pub use sftp::{SftpAuth, SftpConfig, SftpVfs};
-pub use virtual_vfs::{FilteredVfs, UnionVfs};
+#[cfg(feature = "cloud")]
+pub use sftp::{HostKeyVerification, SftpAuth, SftpConfig, SftpVfs};
+pub use virtual_vfs::{FilteredVfs, RetryVfs, TempExtractVfs, UnionVfs};
+#[cfg(feature = "cloud")]
 pub use webdav::{WebDavAuth, WebDavConfig, WebDavVfs};
+
+use rcompare_common::HashAlgorithm;
+use sha2::{Digest, Sha256};
+use std::io::Read;
+
+/// Stream `reader` through the `algorithm` hasher and return the raw digest
+///
+/// Backs the [`Vfs::hash`](rcompare_common::Vfs::hash) implementations that
+/// can read straight from the source instead of buffering it for
+/// `open_file`.
+pub(crate) fn stream_hash(
+    mut reader: impl Read,
+    algorithm: HashAlgorithm,
+) -> std::io::Result<Vec<u8>> {
+    match algorithm {
+        HashAlgorithm::Blake3 => {
+            let mut hasher = blake3::Hasher::new();
+            std::io::copy(&mut reader, &mut hasher)?;
+            Ok(hasher.finalize().as_bytes().to_vec())
+        }
+        HashAlgorithm::Sha256 => {
+            let mut hasher = Sha256::new();
+            std::io::copy(&mut reader, &mut hasher)?;
+            Ok(hasher.finalize().to_vec())
+        }
+    }
+}
@@ -1,7 +1,10 @@
 use aws_config::BehaviorVersion;
 use aws_credential_types::Credentials;
+use aws_sdk_s3::error::SdkError;
+use aws_sdk_s3::operation::head_object::HeadObjectOutput;
+use aws_sdk_s3::types::ServerSideEncryption;
 use aws_sdk_s3::Client;
-use rcompare_common::{FileEntry, FileMetadata, Vfs, VfsCapabilities, VfsError};
+use rcompare_common::{FileEntry, FileMetadata, FileType, Vfs, VfsCapabilities, VfsError};
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
@@ -154,6 +157,36 @@ impl S3Vfs {
     }
 }
 
+/// Whether an SDK call failed for a reason that may go away on retry: a
+/// timeout, a dropped connection, throttling or a server error
+fn is_transient_sdk_error<E>(error: &SdkError<E>) -> bool {
+    match error {
+        SdkError::TimeoutError(_) => true,
+        SdkError::DispatchFailure(failure) => failure.is_io() || failure.is_timeout(),
+        _ => error.raw_response().is_some_and(|response| {
+            let status = response.status();
+            status.is_server_error() || status.as_u16() == 429
+        }),
+    }
+}
+
+/// Content fingerprint from an object's ETag, when the ETag is the MD5 of
+/// its content
+///
+/// That holds for single-part uploads stored plain or with SSE-S3. Multipart
+/// ETags depend on the part size and SSE-KMS or SSE-C ETags are opaque, so
+/// equal content can carry different ETags; those objects get no fingerprint
+/// and are compared by content.
+pub(crate) fn etag_fingerprint(head: &HeadObjectOutput) -> Option<String> {
+    let etag = head.e_tag()?.trim_matches('"');
+    let is_md5 = etag.len() == 32 && etag.bytes().all(|b| b.is_ascii_hexdigit());
+    let plain_encryption = matches!(
+        head.server_side_encryption(),
+        None | Some(ServerSideEncryption::Aes256)
+    ) && head.sse_customer_algorithm().is_none();
+    (is_md5 && plain_encryption).then(|| format!("s3:md5:{}", etag.to_ascii_lowercase()))
+}
+
 impl Vfs for S3Vfs {
     fn instance_id(&self) -> &str {
         &self.instance_id
@@ -192,6 +225,10 @@ impl Vfs for S3Vfs {
                         is_symlink: false,
                     })
                 }
+                Err(e) if is_transient_sdk_error(&e) => Err(VfsError::Transient(format!(
+                    "Failed to get S3 object metadata: {}",
+                    e
+                ))),
                 Err(_) => {
                     // Object not found, might be a directory
                     // Try listing with the key as a prefix
@@ -243,10 +280,12 @@ impl Vfs for S3Vfs {
                 }
 
                 let output = list_request.send().await.map_err(|e| {
-                    VfsError::Io(std::io::Error::other(format!(
-                        "Failed to list S3 objects: {}",
-                        e
-                    )))
+                    let message = format!("Failed to list S3 objects: {}", e);
+                    if is_transient_sdk_error(&e) {
+                        VfsError::Transient(message)
+                    } else {
+                        VfsError::Io(std::io::Error::other(message))
+                    }
                 })?;
 
                 // Add files (objects)
@@ -272,6 +311,7 @@ impl Vfs for S3Vfs {
                             size,
                             modified,
                             is_dir: Self::is_directory_key(key),
+                            file_type: FileType::from_is_dir(Self::is_directory_key(key)),
                         });
                     }
                 }
@@ -285,6 +325,7 @@ impl Vfs for S3Vfs {
                             size: 0,
                             modified: SystemTime::now(),
                             is_dir: true,
+                            file_type: FileType::Directory,
                         });
                     }
                 }
@@ -313,10 +354,12 @@ impl Vfs for S3Vfs {
                 .send()
                 .await
                 .map_err(|e| {
-                    VfsError::Io(std::io::Error::new(
-                        std::io::ErrorKind::NotFound,
-                        format!("Failed to get S3 object: {}", e),
-                    ))
+                    let message = format!("Failed to get S3 object: {}", e);
+                    if is_transient_sdk_error(&e) {
+                        VfsError::Transient(message)
+                    } else {
+                        VfsError::Io(std::io::Error::new(std::io::ErrorKind::NotFound, message))
+                    }
                 })?;
 
             // Read the entire body into memory
@@ -390,9 +433,32 @@ impl Vfs for S3Vfs {
             rename: true,
             create_dir: true,
             set_mtime: false, // S3 doesn't support setting modification time
+            expensive_reads: true,
         }
     }
 
+    fn content_fingerprint(&self, path: &Path) -> Result<Option<String>, VfsError> {
+        let key = self.to_s3_key(path);
+
+        self.runtime.block_on(async {
+            let output = self
+                .client
+                .head_object()
+                .bucket(&self.config.bucket)
+                .key(&key)
+                .send()
+                .await
+                .map_err(|e| {
+                    VfsError::Io(std::io::Error::other(format!(
+                        "Failed to get S3 object metadata: {}",
+                        e
+                    )))
+                })?;
+
+            Ok(etag_fingerprint(&output))
+        })
+    }
+
     fn create_file(&self, path: &Path) -> Result<Box<dyn std::io::Write + Send>, VfsError> {
         // Return a writer that buffers data and uploads on drop
         Ok(Box::new(S3Writer::new(
@@ -461,13 +527,16 @@ impl Vfs for S3Vfs {
     }
 }
 
-/// A writer that buffers data and uploads to S3 when dropped
+/// A writer that buffers data and uploads to S3 when flushed or dropped
 struct S3Writer {
     client: Arc<Client>,
     runtime: Arc<Runtime>,
     bucket: String,
     key: String,
     buffer: Vec<u8>,
+    /// Data written since the last successful upload (an empty file still
+    /// needs one)
+    pending: bool,
 }
 
 impl S3Writer {
@@ -478,6 +547,7 @@ impl S3Writer {
             bucket,
             key,
             buffer: Vec::new(),
+            pending: true,
         }
     }
 }
@@ -485,10 +555,15 @@ impl S3Writer {
 impl std::io::Write for S3Writer {
     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
         self.buffer.extend_from_slice(buf);
+        self.pending = true;
         Ok(buf.len())
     }
 
     fn flush(&mut self) -> std::io::Result<()> {
+        if !self.pending {
+            return Ok(());
+        }
+
         // Upload the buffer to S3
         let client = self.client.clone();
         let bucket = self.bucket.clone();
@@ -505,8 +580,10 @@ impl std::io::Write for S3Writer {
                 .await
                 .map_err(|e| std::io::Error::other(format!("Failed to upload to S3: {}", e)))?;
 
-            Ok(())
-        })
+            Ok::<_, std::io::Error>(())
+        })?;
+        self.pending = false;
+        Ok(())
     }
 }
 
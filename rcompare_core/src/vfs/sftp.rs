@@ -1,5 +1,5 @@
-use rcompare_common::{FileEntry, FileMetadata, Vfs, VfsError};
-use ssh2::{Session, Sftp};
+use rcompare_common::{FileEntry, FileMetadata, FileType, Vfs, VfsCapabilities, VfsError};
+use ssh2::{CheckResult, HashType, KnownHostFileKind, KnownHosts, Session, Sftp};
 use std::io::{Cursor, Read};
 use std::net::TcpStream;
 use std::path::{Path, PathBuf};
@@ -14,6 +14,46 @@ pub struct SftpConfig {
     pub username: String,
     pub auth: SftpAuth,
     pub root_path: PathBuf,
+    pub host_key: HostKeyVerification,
+}
+
+/// How the server's host key is verified before authenticating
+///
+/// By default the key must already be listed in `~/.ssh/known_hosts`;
+/// unknown hosts are rejected unless `trust_on_first_use` is enabled.
+#[derive(Debug, Clone, Default)]
+pub struct HostKeyVerification {
+    /// known_hosts file to check (default: `~/.ssh/known_hosts`)
+    pub known_hosts_path: Option<PathBuf>,
+    /// Expected SHA-256 fingerprint in the `SHA256:<base64>` form printed by
+    /// `ssh-keygen -l`; when set, known_hosts is not consulted
+    pub pinned_fingerprint: Option<String>,
+    /// Accept an unknown host and record its key in the known_hosts file
+    pub trust_on_first_use: bool,
+}
+
+impl HostKeyVerification {
+    fn known_hosts_file(&self) -> Option<PathBuf> {
+        self.known_hosts_path.clone().or_else(|| {
+            directories::UserDirs::new().map(|dirs| dirs.home_dir().join(".ssh/known_hosts"))
+        })
+    }
+}
+
+/// Outcome of looking a host key up in known_hosts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum KnownHostStatus {
+    Match,
+    Mismatch,
+    NotFound,
+}
+
+/// What to do with a host key that passed verification
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum HostKeyDecision {
+    Trusted,
+    /// Unknown host accepted on first use; its key should be recorded
+    Record,
 }
 
 /// Authentication method for SFTP
@@ -35,6 +75,7 @@ impl Default for SftpConfig {
             username: String::new(),
             auth: SftpAuth::Agent,
             root_path: PathBuf::from("/"),
+            host_key: HostKeyVerification::default(),
         }
     }
 }
@@ -95,6 +136,8 @@ impl SftpVfs {
             ))
         })?;
 
+        Self::verify_host_key(&session, config)?;
+
         // Authenticate
         match &config.auth {
             SftpAuth::Password(password) => {
@@ -174,6 +217,67 @@ impl SftpVfs {
         Ok(session)
     }
 
+    /// Check the server's host key against the pinned fingerprint or the
+    /// known_hosts file, recording it there on trust-on-first-use
+    fn verify_host_key(session: &Session, config: &SftpConfig) -> Result<(), VfsError> {
+        let (key, key_type) = session.host_key().ok_or_else(|| {
+            VfsError::Io(std::io::Error::other("Server did not provide a host key"))
+        })?;
+        let fingerprint = session
+            .host_key_hash(HashType::Sha256)
+            .map(format_fingerprint)
+            .ok_or_else(|| VfsError::Io(std::io::Error::other("Failed to hash server host key")))?;
+
+        let known_hosts_file = config.host_key.known_hosts_file();
+        let mut known_hosts = session.known_hosts().map_err(|e| {
+            VfsError::Io(std::io::Error::other(format!(
+                "Failed to initialize known hosts: {}",
+                e
+            )))
+        })?;
+        let status = match &known_hosts_file {
+            Some(path) if config.host_key.pinned_fingerprint.is_none() && path.exists() => {
+                known_hosts
+                    .read_file(path, KnownHostFileKind::OpenSSH)
+                    .map_err(|e| {
+                        VfsError::Io(std::io::Error::other(format!(
+                            "Failed to read known hosts file {}: {}",
+                            path.display(),
+                            e
+                        )))
+                    })?;
+                known_host_status(&known_hosts, &config.host, config.port, key)?
+            }
+            _ => KnownHostStatus::NotFound,
+        };
+
+        let decision = decide_host_key(&config.host_key, &config.host, &fingerprint, status)?;
+        if decision == HostKeyDecision::Record {
+            let path = known_hosts_file.ok_or_else(|| {
+                VfsError::Io(std::io::Error::other(
+                    "Cannot record host key: no known hosts file location",
+                ))
+            })?;
+            known_hosts
+                .add(
+                    &known_hosts_entry(&config.host, config.port),
+                    key,
+                    "added by rcompare",
+                    key_type.into(),
+                )
+                .and_then(|()| known_hosts.write_file(&path, KnownHostFileKind::OpenSSH))
+                .map_err(|e| {
+                    VfsError::Io(std::io::Error::other(format!(
+                        "Failed to record host key in {}: {}",
+                        path.display(),
+                        e
+                    )))
+                })?;
+        }
+
+        Ok(())
+    }
+
     fn get_sftp(&self) -> Result<Sftp, VfsError> {
         let session = self
             .session
@@ -193,6 +297,89 @@ impl SftpVfs {
     }
 }
 
+/// Look up a raw host key in a loaded known_hosts database
+pub(crate) fn known_host_status(
+    known_hosts: &KnownHosts,
+    host: &str,
+    port: u16,
+    key: &[u8],
+) -> Result<KnownHostStatus, VfsError> {
+    match known_hosts.check_port(host, port, key) {
+        CheckResult::Match => Ok(KnownHostStatus::Match),
+        CheckResult::Mismatch => Ok(KnownHostStatus::Mismatch),
+        CheckResult::NotFound => Ok(KnownHostStatus::NotFound),
+        CheckResult::Failure => Err(VfsError::Io(std::io::Error::other(format!(
+            "Known hosts check failed for {}",
+            host
+        )))),
+    }
+}
+
+/// Decide whether a server presenting `fingerprint` may be trusted
+///
+/// A pinned fingerprint is authoritative. Otherwise the known_hosts status
+/// decides, and an unknown host is only accepted with trust-on-first-use.
+pub(crate) fn decide_host_key(
+    policy: &HostKeyVerification,
+    host: &str,
+    fingerprint: &str,
+    status: KnownHostStatus,
+) -> Result<HostKeyDecision, VfsError> {
+    if let Some(pinned) = &policy.pinned_fingerprint {
+        let pinned = pinned.trim();
+        let pinned = pinned.strip_prefix("SHA256:").unwrap_or(pinned);
+        let actual = fingerprint.strip_prefix("SHA256:").unwrap_or(fingerprint);
+        return if pinned.trim_end_matches('=') == actual {
+            Ok(HostKeyDecision::Trusted)
+        } else {
+            Err(VfsError::HostKeyMismatch {
+                host: host.to_string(),
+                fingerprint: fingerprint.to_string(),
+            })
+        };
+    }
+
+    match status {
+        KnownHostStatus::Match => Ok(HostKeyDecision::Trusted),
+        KnownHostStatus::Mismatch => Err(VfsError::HostKeyMismatch {
+            host: host.to_string(),
+            fingerprint: fingerprint.to_string(),
+        }),
+        KnownHostStatus::NotFound if policy.trust_on_first_use => Ok(HostKeyDecision::Record),
+        KnownHostStatus::NotFound => Err(VfsError::UnknownHostKey {
+            host: host.to_string(),
+            fingerprint: fingerprint.to_string(),
+        }),
+    }
+}
+
+/// Host pattern as OpenSSH writes it, bracketed for non-default ports
+fn known_hosts_entry(host: &str, port: u16) -> String {
+    if port == 22 {
+        host.to_string()
+    } else {
+        format!("[{}]:{}", host, port)
+    }
+}
+
+/// Format a SHA-256 digest like `ssh-keygen -l` (unpadded base64)
+pub(crate) fn format_fingerprint(digest: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::from("SHA256:");
+    for chunk in digest.chunks(3) {
+        let bytes = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..=chunk.len() {
+            out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+        }
+    }
+    out
+}
+
 impl Vfs for SftpVfs {
     fn instance_id(&self) -> &str {
         &self.instance_id
@@ -258,6 +445,7 @@ impl Vfs for SftpVfs {
                     size: stat.size.unwrap_or(0),
                     modified,
                     is_dir: stat.is_dir(),
+                    file_type: FileType::from_is_dir(stat.is_dir()),
                 })
             })
             .collect();
@@ -346,6 +534,18 @@ impl Vfs for SftpVfs {
 
         Ok(())
     }
+
+    fn capabilities(&self) -> VfsCapabilities {
+        VfsCapabilities {
+            read: true,
+            write: false,
+            delete: true,
+            rename: false,
+            create_dir: false,
+            set_mtime: false,
+            expensive_reads: true,
+        }
+    }
 }
 
 // Note: We can't easily test SFTP without a real server,
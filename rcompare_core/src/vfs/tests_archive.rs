@@ -1,9 +1,10 @@
 #[cfg(test)]
 mod tests {
     use crate::vfs::{
-        CompressedFileVfs, TarVfs, WritableCompressedFileVfs, WritableZipVfs, ZipVfs,
+        CompressedFileVfs, CompressionType, TarVfs, WritableCompressedFileVfs, WritableZipVfs,
+        ZipVfs,
     };
-    use rcompare_common::Vfs;
+    use rcompare_common::{ErrorKind, Vfs};
     use std::fs;
     use std::io::{Read, Write};
     use std::path::PathBuf;
@@ -130,6 +131,94 @@ mod tests {
         assert!(!meta.is_dir);
     }
 
+    #[test]
+    fn test_zip_vfs_compression_info() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let zip_path = temp_dir.path().join("test.zip");
+
+        let file = fs::File::create(&zip_path).expect("Failed to create file");
+        let mut zip = zip::ZipWriter::new(file);
+        let deflated =
+            zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+        let stored =
+            zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+
+        let content = "the same line over and over\n".repeat(200);
+        zip.start_file("text.txt", deflated)
+            .expect("Failed to start file");
+        zip.write_all(content.as_bytes()).expect("Failed to write");
+        zip.start_file("raw.bin", stored)
+            .expect("Failed to start file");
+        zip.write_all(b"raw").expect("Failed to write");
+        zip.add_directory("dir/", stored)
+            .expect("Failed to add directory");
+        zip.finish().expect("Failed to finish ZIP");
+
+        let vfs = ZipVfs::new(zip_path).expect("Failed to create ZipVfs");
+
+        let info = vfs
+            .compression_info(&PathBuf::from("text.txt"))
+            .expect("Failed to get compression info")
+            .expect("ZIP entries report compression");
+        assert_eq!(info.method, "deflated");
+        assert!(info.compressed_size > 0);
+        assert!(info.compressed_size < content.len() as u64);
+        assert!(info.ratio(content.len() as u64) < 0.5);
+
+        let info = vfs
+            .compression_info(&PathBuf::from("raw.bin"))
+            .expect("Failed to get compression info")
+            .expect("ZIP entries report compression");
+        assert_eq!(info.method, "stored");
+        assert_eq!(info.compressed_size, 3);
+
+        assert!(vfs
+            .compression_info(&PathBuf::from("dir/"))
+            .expect("Failed to get compression info")
+            .is_none());
+        assert!(vfs.compression_info(&PathBuf::from("missing.txt")).is_err());
+    }
+
+    #[test]
+    fn test_zip_vfs_hash_streams_entry() {
+        use rcompare_common::HashAlgorithm;
+        use sha2::{Digest, Sha256};
+
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let zip_path = temp_dir.path().join("test.zip");
+
+        let file = fs::File::create(&zip_path).expect("Failed to create file");
+        let mut zip = zip::ZipWriter::new(file);
+        let content = "the same line over and over\n".repeat(200);
+        zip.start_file("text.txt", zip::write::FileOptions::default())
+            .expect("Failed to start file");
+        zip.write_all(content.as_bytes()).expect("Failed to write");
+        zip.add_directory("dir/", zip::write::FileOptions::default())
+            .expect("Failed to add directory");
+        zip.finish().expect("Failed to finish ZIP");
+
+        let vfs = ZipVfs::new(zip_path).expect("Failed to create ZipVfs");
+        let path = PathBuf::from("text.txt");
+
+        let blake3 = vfs
+            .hash(&path, HashAlgorithm::Blake3)
+            .expect("Failed to hash entry")
+            .expect("ZIP entries are hashed by the backend");
+        assert_eq!(blake3, blake3::hash(content.as_bytes()).as_bytes());
+        let sha256 = vfs
+            .hash(&path, HashAlgorithm::Sha256)
+            .expect("Failed to hash entry")
+            .expect("ZIP entries are hashed by the backend");
+        assert_eq!(sha256, Sha256::digest(content.as_bytes()).to_vec());
+
+        assert!(vfs
+            .hash(&PathBuf::from("dir/"), HashAlgorithm::Blake3)
+            .is_err());
+        assert!(vfs
+            .hash(&PathBuf::from("missing.txt"), HashAlgorithm::Blake3)
+            .is_err());
+    }
+
     #[test]
     fn test_zip_vfs_capabilities() {
         let temp_dir = TempDir::new().expect("Failed to create temp dir");
@@ -285,6 +374,66 @@ mod tests {
         assert_eq!(buffer, "TAR content");
     }
 
+    #[test]
+    fn test_tar_vfs_symlink_entries() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let tar_path = temp_dir.path().join("links.tar");
+
+        let file = fs::File::create(&tar_path).expect("Failed to create file");
+        let mut tar = tar::Builder::new(file);
+        let data = b"real content";
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_cksum();
+        tar.append_data(&mut header, "data/real.txt", &data[..])
+            .expect("Failed to append");
+        for (link, target) in [
+            ("link.txt", "data/real.txt"),
+            ("data/chained.txt", "../link.txt"),
+            ("escape.txt", "../outside.txt"),
+        ] {
+            let mut header = tar::Header::new_gnu();
+            header.set_entry_type(tar::EntryType::Symlink);
+            header.set_size(0);
+            tar.append_link(&mut header, link, target)
+                .expect("Failed to append link");
+        }
+        tar.finish().expect("Failed to finish TAR");
+
+        let vfs = TarVfs::new(tar_path).expect("Failed to create TarVfs");
+        let link = PathBuf::from("link.txt");
+        assert_eq!(
+            vfs.read_link(&link).expect("Failed to read link"),
+            PathBuf::from("data/real.txt")
+        );
+        let link_meta = vfs
+            .symlink_metadata(&link)
+            .expect("Failed to get link metadata");
+        assert!(link_meta.is_symlink);
+        assert_eq!(link_meta.size, 0);
+
+        // metadata and open_file follow links, also through other links
+        for path in ["link.txt", "data/chained.txt"] {
+            let meta = vfs.metadata(&PathBuf::from(path)).expect(path);
+            assert!(!meta.is_symlink);
+            assert_eq!(meta.size, data.len() as u64);
+            let mut buffer = String::new();
+            vfs.open_file(&PathBuf::from(path))
+                .expect(path)
+                .read_to_string(&mut buffer)
+                .expect("Failed to read");
+            assert_eq!(buffer, "real content");
+        }
+
+        let escape = PathBuf::from("escape.txt");
+        assert!(vfs.symlink_metadata(&escape).unwrap().is_symlink);
+        let err = vfs.metadata(&escape).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::NotFound);
+
+        let err = vfs.read_link(&PathBuf::from("data/real.txt")).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+
     #[test]
     fn test_tar_vfs_capabilities() {
         let temp_dir = TempDir::new().expect("Failed to create temp dir");
@@ -334,6 +483,63 @@ mod tests {
         assert_eq!(buffer, "Compressed content");
     }
 
+    /// Concatenated gzip members, as written by `cat a.gz b.gz` or pigz
+    fn write_two_member_gzip(path: &std::path::Path, first: &[u8], second: &[u8]) {
+        use flate2::write::GzEncoder;
+        use flate2::Compression;
+
+        let mut bytes = Vec::new();
+        for member in [first, second] {
+            let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(member).expect("Failed to write");
+            bytes.extend(encoder.finish().expect("Failed to finish"));
+        }
+        fs::write(path, bytes).expect("Failed to write file");
+    }
+
+    #[test]
+    fn test_compressed_file_vfs_gzip_reads_every_member() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let gz_path = temp_dir.path().join("log.txt.gz");
+        write_two_member_gzip(&gz_path, b"first member\n", b"second member\n");
+
+        let vfs = CompressedFileVfs::new(gz_path).expect("Failed to create CompressedFileVfs");
+        let mut buffer = String::new();
+        vfs.open_file(&PathBuf::from("log.txt"))
+            .expect("Failed to open")
+            .read_to_string(&mut buffer)
+            .expect("Failed to read");
+
+        assert_eq!(buffer, "first member\nsecond member\n");
+    }
+
+    #[test]
+    fn test_tar_gz_vfs_reads_every_member() {
+        let data = b"split across gzip members";
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_cksum();
+        let mut tar = tar::Builder::new(Vec::new());
+        tar.append_data(&mut header, "data.txt", &data[..])
+            .expect("Failed to append");
+        let tar_bytes = tar.into_inner().expect("Failed to finish TAR");
+
+        // Split inside the entry so the second member holds the rest of it
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let tar_path = temp_dir.path().join("data.tar.gz");
+        let (first, second) = tar_bytes.split_at(520);
+        write_two_member_gzip(&tar_path, first, second);
+
+        let vfs = TarVfs::new(tar_path).expect("Failed to create TarVfs");
+        let mut buffer = String::new();
+        vfs.open_file(&PathBuf::from("data.txt"))
+            .expect("Failed to open file")
+            .read_to_string(&mut buffer)
+            .expect("Failed to read");
+
+        assert_eq!(buffer, "split across gzip members");
+    }
+
     #[test]
     fn test_compressed_file_vfs_bzip2() {
         use bzip2::write::BzEncoder;
@@ -387,6 +593,33 @@ mod tests {
         assert_eq!(buffer, "XZ content");
     }
 
+    #[test]
+    fn test_compressed_file_vfs_zstd_round_trip() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let zst_path = temp_dir.path().join("test.txt.zst");
+
+        let vfs = WritableCompressedFileVfs::new(zst_path.clone())
+            .expect("Failed to create WritableCompressedFileVfs");
+        vfs.write_file(&PathBuf::from("test.txt"), b"Zstd content")
+            .expect("Failed to write");
+        vfs.flush().expect("Failed to flush");
+
+        let header = fs::read(&zst_path).expect("Failed to read");
+        assert_eq!(
+            CompressionType::from_magic(&header),
+            Some(CompressionType::Zstd)
+        );
+
+        let vfs = CompressedFileVfs::new(zst_path).expect("Failed to create CompressedFileVfs");
+        let mut reader = vfs
+            .open_file(&PathBuf::from("test.txt"))
+            .expect("Failed to open");
+        let mut buffer = String::new();
+        reader.read_to_string(&mut buffer).expect("Failed to read");
+
+        assert_eq!(buffer, "Zstd content");
+    }
+
     #[test]
     fn test_compressed_file_vfs_capabilities() {
         use flate2::write::GzEncoder;
@@ -506,6 +739,61 @@ mod tests {
         assert_eq!(buffer, "Nested file");
     }
 
+    #[test]
+    fn test_zip_vfs_read_dir_lists_subdirectories() {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let zip_path = temp_dir.path().join("dirs.zip");
+
+        // "explicit/" has its own entry, "implicit" only appears as a prefix
+        let file = fs::File::create(&zip_path).expect("Failed to create file");
+        let mut zip = zip::ZipWriter::new(file);
+        let options = zip::write::FileOptions::default();
+
+        zip.add_directory("explicit/", options)
+            .expect("Failed to add dir");
+        zip.start_file("explicit/a.txt", options)
+            .expect("Failed to start file");
+        zip.write_all(b"A").expect("Failed to write");
+        zip.start_file("implicit/deeper/b.txt", options)
+            .expect("Failed to start file");
+        zip.write_all(b"B").expect("Failed to write");
+        zip.start_file("top.txt", options)
+            .expect("Failed to start file");
+        zip.write_all(b"Top").expect("Failed to write");
+
+        let _ = zip.finish().expect("Failed to finish ZIP");
+
+        let vfs = ZipVfs::new(zip_path).expect("Failed to create ZipVfs");
+        let list = |dir: &str| -> Vec<(String, bool)> {
+            vfs.read_dir(&PathBuf::from(dir))
+                .expect("Failed to read dir")
+                .into_iter()
+                .map(|e| (e.path.to_string_lossy().to_string(), e.is_dir))
+                .collect()
+        };
+
+        assert_eq!(
+            list(""),
+            vec![
+                ("explicit".to_string(), true),
+                ("implicit".to_string(), true),
+                ("top.txt".to_string(), false),
+            ]
+        );
+        assert_eq!(
+            list("explicit"),
+            vec![("explicit/a.txt".to_string(), false)]
+        );
+        assert_eq!(
+            list("implicit"),
+            vec![("implicit/deeper".to_string(), true)]
+        );
+        assert_eq!(
+            list("implicit/deeper"),
+            vec![("implicit/deeper/b.txt".to_string(), false)]
+        );
+    }
+
     #[test]
     fn test_zip_vfs_file_not_found() {
         let temp_dir = TempDir::new().expect("Failed to create temp dir");
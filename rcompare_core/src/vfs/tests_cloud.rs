@@ -1,9 +1,13 @@
 #[cfg(test)]
 mod tests {
+    use crate::vfs::sftp::{
+        decide_host_key, format_fingerprint, known_host_status, HostKeyDecision, KnownHostStatus,
+    };
     use crate::vfs::{
-        S3Auth, S3Config, S3Vfs, SftpAuth, SftpConfig, SftpVfs, WebDavAuth, WebDavConfig, WebDavVfs,
+        HostKeyVerification, S3Auth, S3Config, S3Vfs, SftpAuth, SftpConfig, SftpVfs, WebDavAuth,
+        WebDavConfig, WebDavVfs,
     };
-    use rcompare_common::Vfs;
+    use rcompare_common::{Vfs, VfsError};
     use std::path::PathBuf;
 
     // Note: These tests require actual S3, WebDAV, and SFTP services to be available
@@ -317,6 +321,31 @@ mod tests {
         let _ = vfs.remove_file(&new_path);
     }
 
+    #[test]
+    fn test_s3_etag_fingerprint_needs_single_part_md5() {
+        use crate::vfs::s3::etag_fingerprint;
+        use aws_sdk_s3::operation::head_object::HeadObjectOutput;
+        use aws_sdk_s3::types::ServerSideEncryption;
+
+        let md5 = "\"9E107D9D372BB6826BD81D3542A419D6\"";
+        let head = |etag: &str| HeadObjectOutput::builder().e_tag(etag);
+
+        assert_eq!(
+            etag_fingerprint(&head(md5).build()).as_deref(),
+            Some("s3:md5:9e107d9d372bb6826bd81d3542a419d6")
+        );
+        let sse_s3 = head(md5).server_side_encryption(ServerSideEncryption::Aes256);
+        assert!(etag_fingerprint(&sse_s3.build()).is_some());
+
+        let multipart = head("\"d41d8cd98f00b204e9800998ecf8427e-3\"");
+        assert_eq!(etag_fingerprint(&multipart.build()), None);
+        let kms = head(md5).server_side_encryption(ServerSideEncryption::AwsKms);
+        assert_eq!(etag_fingerprint(&kms.build()), None);
+        let sse_c = head(md5).sse_customer_algorithm("AES256");
+        assert_eq!(etag_fingerprint(&sse_c.build()), None);
+        assert_eq!(etag_fingerprint(&HeadObjectOutput::builder().build()), None);
+    }
+
     #[test]
     fn test_s3_config_default() {
         let config = S3Config::default();
@@ -1371,6 +1400,128 @@ mod tests {
         let _auth = SftpAuth::Agent;
     }
 
+    #[test]
+    fn test_sftp_host_key_verification_defaults_to_strict() {
+        let config = SftpConfig::default();
+        assert!(config.host_key.pinned_fingerprint.is_none());
+        assert!(!config.host_key.trust_on_first_use);
+    }
+
+    #[test]
+    fn test_sftp_format_fingerprint() {
+        assert_eq!(format_fingerprint(b"foobar"), "SHA256:Zm9vYmFy");
+        assert_eq!(format_fingerprint(b"fo"), "SHA256:Zm8");
+        assert_eq!(
+            format_fingerprint(&[0u8; 32]),
+            format!("SHA256:{}", "A".repeat(43))
+        );
+    }
+
+    /// Raw ssh-ed25519 public key blob filled with `byte`
+    fn ed25519_blob(byte: u8) -> Vec<u8> {
+        let mut blob = Vec::new();
+        blob.extend_from_slice(&11u32.to_be_bytes());
+        blob.extend_from_slice(b"ssh-ed25519");
+        blob.extend_from_slice(&32u32.to_be_bytes());
+        blob.extend_from_slice(&[byte; 32]);
+        blob
+    }
+
+    fn mock_known_hosts(session: &ssh2::Session) -> ssh2::KnownHosts {
+        let base64 = |blob: &[u8]| format_fingerprint(blob)["SHA256:".len()..].to_string();
+        let mut known_hosts = session.known_hosts().unwrap();
+        let lines = format!(
+            "prod.example.com ssh-ed25519 {}\n[backup.example.com]:2222 ssh-ed25519 {}\n",
+            base64(&ed25519_blob(1)),
+            base64(&ed25519_blob(2)),
+        );
+        for line in lines.lines() {
+            known_hosts
+                .read_str(line, ssh2::KnownHostFileKind::OpenSSH)
+                .unwrap();
+        }
+        known_hosts
+    }
+
+    #[test]
+    fn test_sftp_known_hosts_match_mismatch_and_unknown() {
+        let session = ssh2::Session::new().unwrap();
+        let known_hosts = mock_known_hosts(&session);
+        let status = |host: &str, port: u16, byte: u8| {
+            known_host_status(&known_hosts, host, port, &ed25519_blob(byte)).unwrap()
+        };
+
+        assert_eq!(status("prod.example.com", 22, 1), KnownHostStatus::Match);
+        assert_eq!(
+            status("backup.example.com", 2222, 2),
+            KnownHostStatus::Match
+        );
+        assert_eq!(status("prod.example.com", 22, 9), KnownHostStatus::Mismatch);
+        assert_eq!(status("new.example.com", 22, 1), KnownHostStatus::NotFound);
+
+        let strict = HostKeyVerification::default();
+        let decide = |policy: &HostKeyVerification, status| {
+            decide_host_key(policy, "prod.example.com", "SHA256:abc", status)
+        };
+        assert_eq!(
+            decide(&strict, KnownHostStatus::Match).unwrap(),
+            HostKeyDecision::Trusted
+        );
+        assert!(matches!(
+            decide(&strict, KnownHostStatus::Mismatch),
+            Err(VfsError::HostKeyMismatch { .. })
+        ));
+        assert!(matches!(
+            decide(&strict, KnownHostStatus::NotFound),
+            Err(VfsError::UnknownHostKey { .. })
+        ));
+
+        // Trust-on-first-use only relaxes unknown hosts, never mismatches
+        let tofu = HostKeyVerification {
+            trust_on_first_use: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            decide(&tofu, KnownHostStatus::NotFound).unwrap(),
+            HostKeyDecision::Record
+        );
+        assert!(matches!(
+            decide(&tofu, KnownHostStatus::Mismatch),
+            Err(VfsError::HostKeyMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_sftp_pinned_fingerprint() {
+        let fingerprint = format_fingerprint(&[7u8; 32]);
+        let pinned = |pin: &str| HostKeyVerification {
+            pinned_fingerprint: Some(pin.to_string()),
+            ..Default::default()
+        };
+
+        // The pin decides regardless of known_hosts; prefix and padding are optional
+        let bare = fingerprint.strip_prefix("SHA256:").unwrap();
+        for pin in [fingerprint.clone(), bare.to_string(), format!("{}=", bare)] {
+            let decision =
+                decide_host_key(&pinned(&pin), "h", &fingerprint, KnownHostStatus::NotFound);
+            assert_eq!(decision.unwrap(), HostKeyDecision::Trusted);
+        }
+
+        let other = format_fingerprint(&[8u8; 32]);
+        let err = decide_host_key(&pinned(&other), "h", &fingerprint, KnownHostStatus::Match)
+            .unwrap_err();
+        match err {
+            VfsError::HostKeyMismatch {
+                host,
+                fingerprint: seen,
+            } => {
+                assert_eq!(host, "h");
+                assert_eq!(seen, fingerprint);
+            }
+            other => panic!("unexpected error: {other:?}"),
+        }
+    }
+
     #[test]
     fn test_sftp_config_clone() {
         let config1 = SftpConfig {
@@ -1379,6 +1530,7 @@ mod tests {
             username: "testuser".to_string(),
             auth: SftpAuth::Password("pass123".to_string()),
             root_path: PathBuf::from("/data"),
+            host_key: HostKeyVerification::default(),
         };
 
         let config2 = config1.clone();
@@ -1396,6 +1548,7 @@ mod tests {
             username: "user".to_string(),
             auth: SftpAuth::Agent,
             root_path: PathBuf::from("/"),
+            host_key: HostKeyVerification::default(),
         };
 
         assert_eq!(config.port, 2222);
@@ -1410,6 +1563,7 @@ mod tests {
             username: "user".to_string(),
             auth: SftpAuth::Agent,
             root_path: PathBuf::from("/home/user/files"),
+            host_key: HostKeyVerification::default(),
         };
 
         assert_eq!(config.root_path, PathBuf::from("/home/user/files"));
@@ -1424,6 +1578,7 @@ mod tests {
             username: "testuser".to_string(),
             auth: SftpAuth::Password("testpass".to_string()),
             root_path: PathBuf::from("/"),
+            host_key: HostKeyVerification::default(),
         };
 
         let result = SftpVfs::new(config);
@@ -1449,6 +1604,7 @@ mod tests {
             username: "testuser".to_string(),
             auth: SftpAuth::Password("testpass".to_string()),
             root_path: PathBuf::from("/tmp"),
+            host_key: HostKeyVerification::default(),
         };
 
         let vfs = SftpVfs::new(config).expect("Failed to create SFTP VFS");
@@ -1483,6 +1639,7 @@ mod tests {
             username: "testuser".to_string(),
             auth: SftpAuth::Password("testpass".to_string()),
             root_path: PathBuf::from("/tmp/rcompare-test"),
+            host_key: HostKeyVerification::default(),
         };
 
         let vfs = SftpVfs::new(config).expect("Failed to create SFTP VFS");
@@ -1515,6 +1672,7 @@ mod tests {
             username: "testuser".to_string(),
             auth: SftpAuth::Password("testpass".to_string()),
             root_path: PathBuf::from("/tmp/rcompare-test"),
+            host_key: HostKeyVerification::default(),
         };
 
         let vfs = SftpVfs::new(config).expect("Failed to create SFTP VFS");
@@ -1544,6 +1702,7 @@ mod tests {
             username: "testuser".to_string(),
             auth: SftpAuth::Password("testpass".to_string()),
             root_path: PathBuf::from("/tmp/rcompare-test"),
+            host_key: HostKeyVerification::default(),
         };
 
         let vfs = SftpVfs::new(config).expect("Failed to create SFTP VFS");
@@ -1575,6 +1734,7 @@ mod tests {
             username: "testuser".to_string(),
             auth: SftpAuth::Password("testpass".to_string()),
             root_path: PathBuf::from("/tmp/rcompare-test"),
+            host_key: HostKeyVerification::default(),
         };
 
         let vfs = SftpVfs::new(config).expect("Failed to create SFTP VFS");
@@ -1608,6 +1768,7 @@ mod tests {
             username: "testuser".to_string(),
             auth: SftpAuth::Password("testpass".to_string()),
             root_path: PathBuf::from("/tmp/rcompare-test"),
+            host_key: HostKeyVerification::default(),
         };
 
         let vfs = SftpVfs::new(config).expect("Failed to create SFTP VFS");
@@ -1637,6 +1798,7 @@ mod tests {
             username: "testuser".to_string(),
             auth: SftpAuth::Password("testpass".to_string()),
             root_path: PathBuf::from("/tmp"),
+            host_key: HostKeyVerification::default(),
         };
 
         let vfs = SftpVfs::new(config).expect("Failed to create SFTP VFS");
@@ -1654,6 +1816,7 @@ mod tests {
             username: "testuser".to_string(),
             auth: SftpAuth::Password("testpass".to_string()),
             root_path: PathBuf::from("/tmp"),
+            host_key: HostKeyVerification::default(),
         };
 
         let vfs = SftpVfs::new(config).expect("Failed to create SFTP VFS");
@@ -1674,6 +1837,7 @@ mod tests {
                 passphrase: None,
             },
             root_path: PathBuf::from("/tmp"),
+            host_key: HostKeyVerification::default(),
         };
 
         let result = SftpVfs::new(config);
@@ -1702,6 +1866,7 @@ mod tests {
             username: "testuser".to_string(),
             auth: SftpAuth::Agent,
             root_path: PathBuf::from("/tmp"),
+            host_key: HostKeyVerification::default(),
         };
 
         let result = SftpVfs::new(config);
@@ -1729,6 +1894,7 @@ mod tests {
             username: "testuser".to_string(),
             auth: SftpAuth::Password("testpass".to_string()),
             root_path: PathBuf::from("/tmp/rcompare-test"),
+            host_key: HostKeyVerification::default(),
         };
 
         let vfs = SftpVfs::new(config).expect("Failed to create SFTP VFS");
@@ -1758,6 +1924,7 @@ mod tests {
             username: "testuser".to_string(),
             auth: SftpAuth::Password("testpass".to_string()),
             root_path: PathBuf::from("/tmp/rcompare-test"),
+            host_key: HostKeyVerification::default(),
         };
 
         let vfs = SftpVfs::new(config).expect("Failed to create SFTP VFS");
@@ -537,6 +537,17 @@ mod tests {
                 .read_to_string(&mut buffer)
                 .expect("Failed to read through symlink");
             assert_eq!(buffer, "content");
+
+            let link_meta = vfs
+                .symlink_metadata(&link_path)
+                .expect("Failed to get symlink metadata");
+            assert!(link_meta.is_symlink);
+            assert!(!meta.is_symlink);
+            assert_eq!(
+                vfs.read_link(&link_path).expect("Failed to read link"),
+                full_file_path
+            );
+            assert!(vfs.read_link(&file_path).is_err());
         }
     }
 }
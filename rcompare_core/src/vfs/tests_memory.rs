@@ -0,0 +1,182 @@
+#[cfg(test)]
+mod tests {
+    use crate::vfs::MemVfs;
+    use crate::{ComparisonEngine, FolderScanner, HashCache};
+    use rcompare_common::{AppConfig, DiffStatus, Vfs, VfsError};
+    use std::io::{Read, Write};
+    use std::path::{Path, PathBuf};
+    use std::time::{Duration, SystemTime};
+
+    fn read_to_string(vfs: &dyn Vfs, path: &str) -> String {
+        let mut content = String::new();
+        vfs.open_file(Path::new(path))
+            .expect("Failed to open")
+            .read_to_string(&mut content)
+            .expect("Failed to read");
+        content
+    }
+
+    #[test]
+    fn test_mem_vfs_file_operations() {
+        let vfs = MemVfs::new("ops")
+            .with_file("docs/readme.md", "hello")
+            .with_dir("empty");
+        assert_eq!(vfs.instance_id(), "mem:ops");
+
+        // Parents are created and paths are normalized
+        assert!(vfs.metadata(Path::new("docs")).unwrap().is_dir);
+        assert_eq!(
+            vfs.metadata(Path::new("/docs/./readme.md")).unwrap().size,
+            5
+        );
+        assert_eq!(read_to_string(&vfs, "docs/readme.md"), "hello");
+
+        let mut writer = vfs.create_file(Path::new("docs/new.txt")).unwrap();
+        writer.write_all(b"written").unwrap();
+        drop(writer);
+        assert_eq!(read_to_string(&vfs, "docs/new.txt"), "written");
+
+        let mtime = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+        vfs.set_mtime(Path::new("docs/new.txt"), mtime).unwrap();
+        assert_eq!(
+            vfs.metadata(Path::new("docs/new.txt")).unwrap().modified,
+            mtime
+        );
+
+        vfs.rename(Path::new("docs"), Path::new("moved/docs"))
+            .unwrap();
+        assert!(!vfs.exists(Path::new("docs")));
+        assert_eq!(read_to_string(&vfs, "moved/docs/new.txt"), "written");
+
+        vfs.copy_file(Path::new("moved/docs/readme.md"), Path::new("copy.md"))
+            .unwrap();
+        vfs.remove_file(Path::new("moved/docs/readme.md")).unwrap();
+        assert_eq!(read_to_string(&vfs, "copy.md"), "hello");
+
+        let mut root: Vec<PathBuf> = vfs
+            .read_dir(Path::new(""))
+            .unwrap()
+            .into_iter()
+            .map(|e| e.path)
+            .collect();
+        root.sort();
+        assert_eq!(
+            root,
+            vec![
+                PathBuf::from("copy.md"),
+                PathBuf::from("empty"),
+                PathBuf::from("moved")
+            ]
+        );
+
+        assert!(matches!(
+            vfs.read_dir(Path::new("copy.md")),
+            Err(VfsError::NotADirectory(_))
+        ));
+        assert!(matches!(
+            vfs.remove_file(Path::new("empty")),
+            Err(VfsError::NotAFile(_))
+        ));
+        assert!(matches!(
+            vfs.write_file(Path::new("copy.md/inner.txt"), b"x"),
+            Err(VfsError::NotADirectory(_))
+        ));
+    }
+
+    #[test]
+    fn test_mem_vfs_writer_leaves_removed_and_stamped_files_alone() {
+        let vfs = MemVfs::new("writer");
+        let mut writer = vfs.create_file(Path::new("gone.txt")).unwrap();
+        writer.write_all(b"data").unwrap();
+        vfs.remove_file(Path::new("gone.txt")).unwrap();
+        drop(writer);
+        assert!(!vfs.exists(Path::new("gone.txt")));
+
+        let mut writer = vfs.create_file(Path::new("kept.txt")).unwrap();
+        writer.write_all(b"data").unwrap();
+        writer.flush().unwrap();
+        let mtime = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000);
+        vfs.set_mtime(Path::new("kept.txt"), mtime).unwrap();
+        drop(writer);
+        assert_eq!(read_to_string(&vfs, "kept.txt"), "data");
+        assert_eq!(vfs.metadata(Path::new("kept.txt")).unwrap().modified, mtime);
+    }
+
+    #[test]
+    fn test_scan_mem_vfs() {
+        let vfs = MemVfs::new("scan")
+            .with_file("a.txt", "a")
+            .with_file("src/lib.rs", "pub fn f() {}")
+            .with_file("src/nested/deep.rs", "")
+            .with_file("target/out.bin", "ignored")
+            .with_file(".hidden", "hidden")
+            .with_dir("empty");
+
+        let config = AppConfig {
+            ignore_patterns: vec!["target/".to_string()],
+            include_hidden: false,
+            ..AppConfig::default()
+        };
+        let scanner = FolderScanner::new(config);
+        let entries = scanner.scan_vfs(&vfs, Path::new("")).unwrap();
+
+        let mut paths: Vec<(PathBuf, bool, u64)> = entries
+            .into_iter()
+            .map(|e| (e.path, e.is_dir, e.size))
+            .collect();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![
+                (PathBuf::from("a.txt"), false, 1),
+                (PathBuf::from("empty"), true, 0),
+                (PathBuf::from("src"), true, 0),
+                (PathBuf::from("src/lib.rs"), false, 13),
+                (PathBuf::from("src/nested"), true, 0),
+                (PathBuf::from("src/nested/deep.rs"), false, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compare_two_mem_vfs() {
+        let left = MemVfs::new("left")
+            .with_file("same.txt", "identical")
+            .with_file("changed.txt", "left!")
+            .with_file("only_left/file.txt", "x");
+        let right = MemVfs::new("right")
+            .with_file("same.txt", "identical")
+            .with_file("changed.txt", "right")
+            .with_file("only_right.txt", "y");
+
+        let scanner = FolderScanner::new(AppConfig::default());
+        let root = Path::new("");
+        let left_entries = scanner.scan_vfs(&left, root).unwrap();
+        let right_entries = scanner.scan_vfs(&right, root).unwrap();
+
+        let engine = ComparisonEngine::new(HashCache::in_memory());
+        let nodes = engine
+            .compare_with_vfs(
+                root,
+                root,
+                left_entries,
+                right_entries,
+                Some(&left),
+                Some(&right),
+            )
+            .unwrap();
+
+        let status = |path: &str| {
+            nodes
+                .iter()
+                .find(|n| n.relative_path == Path::new(path))
+                .map(|n| n.status)
+                .unwrap_or_else(|| panic!("missing {path}"))
+        };
+        assert_eq!(status("same.txt"), DiffStatus::Same);
+        // Same size, different content: only a content check tells them apart
+        assert_eq!(status("changed.txt"), DiffStatus::Different);
+        assert_eq!(status("only_left/file.txt"), DiffStatus::OrphanLeft);
+        assert_eq!(status("only_right.txt"), DiffStatus::OrphanRight);
+    }
+}
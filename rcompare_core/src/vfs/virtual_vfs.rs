@@ -3,11 +3,19 @@
 //! These VFS implementations wrap other VFS instances to provide additional functionality:
 //! - `FilteredVfs`: Filter entries based on patterns or predicates
 //! - `UnionVfs`: Combine multiple VFS sources into a single view
-
-use rcompare_common::{FileEntry, FileMetadata, Vfs, VfsCapabilities, VfsError};
-use std::io::Read;
-use std::path::Path;
-use std::sync::Arc;
+//! - `TempExtractVfs`: Serve repeated reads from temp copies of extracted files
+//! - `RetryVfs`: Retry reads that fail with transient errors, with backoff
+
+use rcompare_common::{
+    CompressionInfo, FileEntry, FileMetadata, HashAlgorithm, Vfs, VfsCapabilities, VfsError,
+};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tracing::warn;
 
 /// A VFS wrapper that filters entries based on include/exclude patterns
 ///
@@ -114,6 +122,20 @@ impl Vfs for FilteredVfs {
         self.inner.metadata(path)
     }
 
+    fn symlink_metadata(&self, path: &Path) -> Result<FileMetadata, VfsError> {
+        if !self.is_visible(path) {
+            return Err(VfsError::NotFound(path.display().to_string()));
+        }
+        self.inner.symlink_metadata(path)
+    }
+
+    fn read_link(&self, path: &Path) -> Result<PathBuf, VfsError> {
+        if !self.is_visible(path) {
+            return Err(VfsError::NotFound(path.display().to_string()));
+        }
+        self.inner.read_link(path)
+    }
+
     fn read_dir(&self, path: &Path) -> Result<Vec<FileEntry>, VfsError> {
         let entries = self.inner.read_dir(path)?;
         Ok(entries
@@ -150,6 +172,27 @@ impl Vfs for FilteredVfs {
     fn capabilities(&self) -> VfsCapabilities {
         self.inner.capabilities()
     }
+
+    fn content_fingerprint(&self, path: &Path) -> Result<Option<String>, VfsError> {
+        if !self.is_visible(path) {
+            return Err(VfsError::NotFound(path.display().to_string()));
+        }
+        self.inner.content_fingerprint(path)
+    }
+
+    fn hash(&self, path: &Path, algorithm: HashAlgorithm) -> Result<Option<Vec<u8>>, VfsError> {
+        if !self.is_visible(path) {
+            return Err(VfsError::NotFound(path.display().to_string()));
+        }
+        self.inner.hash(path, algorithm)
+    }
+
+    fn compression_info(&self, path: &Path) -> Result<Option<CompressionInfo>, VfsError> {
+        if !self.is_visible(path) {
+            return Err(VfsError::NotFound(path.display().to_string()));
+        }
+        self.inner.compression_info(path)
+    }
 }
 
 /// A VFS that combines multiple VFS sources into a single unified view
@@ -209,6 +252,18 @@ impl Vfs for UnionVfs {
             .metadata(path)
     }
 
+    fn symlink_metadata(&self, path: &Path) -> Result<FileMetadata, VfsError> {
+        self.find_layer(path)
+            .ok_or_else(|| VfsError::NotFound(path.display().to_string()))?
+            .symlink_metadata(path)
+    }
+
+    fn read_link(&self, path: &Path) -> Result<PathBuf, VfsError> {
+        self.find_layer(path)
+            .ok_or_else(|| VfsError::NotFound(path.display().to_string()))?
+            .read_link(path)
+    }
+
     fn read_dir(&self, path: &Path) -> Result<Vec<FileEntry>, VfsError> {
         let mut all_entries: std::collections::HashMap<std::path::PathBuf, FileEntry> =
             std::collections::HashMap::new();
@@ -243,6 +298,12 @@ impl Vfs for UnionVfs {
             .open_file(path)
     }
 
+    fn hash(&self, path: &Path, algorithm: HashAlgorithm) -> Result<Option<Vec<u8>>, VfsError> {
+        self.find_layer(path)
+            .ok_or_else(|| VfsError::NotFound(path.display().to_string()))?
+            .hash(path, algorithm)
+    }
+
     fn remove_file(&self, path: &Path) -> Result<(), VfsError> {
         // Find writable layer containing this file
         for layer in self.layers.iter().rev() {
@@ -295,15 +356,309 @@ impl Vfs for UnionVfs {
             caps.rename = caps.rename || layer_caps.rename;
             caps.create_dir = caps.create_dir || layer_caps.create_dir;
             caps.set_mtime = caps.set_mtime || layer_caps.set_mtime;
+            caps.expensive_reads = caps.expensive_reads || layer_caps.expensive_reads;
         }
         caps
     }
 }
 
+/// A read-through VFS that extracts each file to a temp file on first access
+///
+/// Reading an archive entry decompresses it from the start every time, which
+/// is slow for engines that reopen or seek around a file (Excel, Parquet).
+/// TempExtractVfs copies an entry to a private temp directory the first time
+/// it is opened and serves every later read from that copy. Engines that take
+/// a file path can use [`TempExtractVfs::extracted_path`] directly. The temp
+/// directory is removed when the VFS is dropped.
+///
+/// Writes made to the inner VFS behind this wrapper's back are not seen by
+/// files that were already extracted.
+pub struct TempExtractVfs {
+    instance_id: String,
+    inner: Arc<dyn Vfs>,
+    temp_dir: tempfile::TempDir,
+    /// Extracted copies by VFS path
+    extracted: Mutex<HashMap<PathBuf, PathBuf>>,
+    /// Name of the next per-file slot directory; never reused
+    next_slot: AtomicUsize,
+}
+
+impl TempExtractVfs {
+    /// Create a new TempExtractVfs wrapping another VFS
+    pub fn new(inner: Arc<dyn Vfs>) -> Result<Self, VfsError> {
+        let instance_id = format!("extract:{}", inner.instance_id());
+        Ok(Self {
+            instance_id,
+            inner,
+            temp_dir: tempfile::TempDir::new()?,
+            extracted: Mutex::new(HashMap::new()),
+            next_slot: AtomicUsize::new(0),
+        })
+    }
+
+    /// Local path of the extracted copy of `path`, extracting it on first use
+    ///
+    /// The copy keeps the original file name, so extension-based detection
+    /// still works on it.
+    pub fn extracted_path(&self, path: &Path) -> Result<PathBuf, VfsError> {
+        // Held across extraction so concurrent readers never extract twice
+        let mut extracted = self.extracted.lock().unwrap();
+        if let Some(local) = extracted.get(path) {
+            return Ok(local.clone());
+        }
+
+        let mut reader = self.inner.open_file(path)?;
+        let slot_id = self.next_slot.fetch_add(1, Ordering::Relaxed);
+        let slot = self.temp_dir.path().join(slot_id.to_string());
+        std::fs::create_dir(&slot)?;
+        let local = slot.join(path.file_name().unwrap_or("file".as_ref()));
+        let copied = std::fs::File::create(&local)
+            .and_then(|mut file| std::io::copy(&mut reader, &mut file));
+        if let Err(err) = copied {
+            let _ = std::fs::remove_dir_all(&slot);
+            return Err(err.into());
+        }
+
+        extracted.insert(path.to_path_buf(), local.clone());
+        Ok(local)
+    }
+
+    /// Drop the extracted copy of `path`, if any
+    fn invalidate(&self, path: &Path) {
+        if let Some(local) = self.extracted.lock().unwrap().remove(path) {
+            if let Some(slot) = local.parent() {
+                let _ = std::fs::remove_dir_all(slot);
+            }
+        }
+    }
+}
+
+impl Vfs for TempExtractVfs {
+    fn instance_id(&self) -> &str {
+        &self.instance_id
+    }
+
+    fn metadata(&self, path: &Path) -> Result<FileMetadata, VfsError> {
+        self.inner.metadata(path)
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> Result<FileMetadata, VfsError> {
+        self.inner.symlink_metadata(path)
+    }
+
+    fn read_link(&self, path: &Path) -> Result<PathBuf, VfsError> {
+        self.inner.read_link(path)
+    }
+
+    fn read_dir(&self, path: &Path) -> Result<Vec<FileEntry>, VfsError> {
+        self.inner.read_dir(path)
+    }
+
+    fn open_file(&self, path: &Path) -> Result<Box<dyn Read + Send>, VfsError> {
+        let local = self.extracted_path(path)?;
+        Ok(Box::new(std::fs::File::open(local)?))
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<(), VfsError> {
+        self.inner.remove_file(path)?;
+        self.invalidate(path);
+        Ok(())
+    }
+
+    fn copy_file(&self, src: &Path, dest: &Path) -> Result<(), VfsError> {
+        self.inner.copy_file(src, dest)?;
+        self.invalidate(dest);
+        Ok(())
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.inner.exists(path)
+    }
+
+    fn is_writable(&self) -> bool {
+        self.inner.is_writable()
+    }
+
+    fn capabilities(&self) -> VfsCapabilities {
+        self.inner.capabilities()
+    }
+
+    fn content_fingerprint(&self, path: &Path) -> Result<Option<String>, VfsError> {
+        self.inner.content_fingerprint(path)
+    }
+
+    fn hash(&self, path: &Path, algorithm: HashAlgorithm) -> Result<Option<Vec<u8>>, VfsError> {
+        self.inner.hash(path, algorithm)
+    }
+
+    fn compression_info(&self, path: &Path) -> Result<Option<CompressionInfo>, VfsError> {
+        self.inner.compression_info(path)
+    }
+}
+
+/// A VFS wrapper that retries reads failing with transient errors
+///
+/// `metadata`, `symlink_metadata`, `read_dir` and `open_file` are retried
+/// when the inner VFS fails with an error for which
+/// [`VfsError::is_transient`] holds, waiting twice as long before each
+/// further attempt up to a cap. Permanent errors, and the last transient
+/// one once the attempts are used up, are returned as is. Only opening a
+/// file is retried, not reading from it. Writes are passed through without
+/// retrying, since repeating a partly applied write is not always safe.
+pub struct RetryVfs {
+    instance_id: String,
+    inner: Arc<dyn Vfs>,
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl RetryVfs {
+    /// Create a RetryVfs making up to 4 attempts, waiting 200ms, 400ms and
+    /// 800ms between them
+    pub fn new(inner: Arc<dyn Vfs>) -> Self {
+        let instance_id = format!("retry:{}", inner.instance_id());
+        Self {
+            instance_id,
+            inner,
+            max_attempts: 4,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(10),
+        }
+    }
+
+    /// Set the number of attempts per operation, including the first (at least 1)
+    pub fn with_max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = attempts.max(1);
+        self
+    }
+
+    /// Set the wait before the first retry and the cap on later waits
+    pub fn with_backoff(mut self, initial: Duration, max: Duration) -> Self {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+        self
+    }
+
+    fn retry<T>(
+        &self,
+        operation: &str,
+        path: &Path,
+        f: impl Fn() -> Result<T, VfsError>,
+    ) -> Result<T, VfsError> {
+        let mut backoff = self.initial_backoff;
+        let mut attempt = 1;
+        loop {
+            match f() {
+                Err(e) if e.is_transient() && attempt < self.max_attempts => {
+                    warn!(
+                        "{} {} on {} failed (attempt {}/{}), retrying in {:?}: {}",
+                        operation,
+                        path.display(),
+                        self.inner.instance_id(),
+                        attempt,
+                        self.max_attempts,
+                        backoff,
+                        e
+                    );
+                    std::thread::sleep(backoff);
+                    backoff = (backoff * 2).min(self.max_backoff);
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+impl Vfs for RetryVfs {
+    fn instance_id(&self) -> &str {
+        &self.instance_id
+    }
+
+    fn metadata(&self, path: &Path) -> Result<FileMetadata, VfsError> {
+        self.retry("metadata", path, || self.inner.metadata(path))
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> Result<FileMetadata, VfsError> {
+        self.retry("symlink_metadata", path, || {
+            self.inner.symlink_metadata(path)
+        })
+    }
+
+    fn read_link(&self, path: &Path) -> Result<PathBuf, VfsError> {
+        self.inner.read_link(path)
+    }
+
+    fn read_dir(&self, path: &Path) -> Result<Vec<FileEntry>, VfsError> {
+        self.retry("read_dir", path, || self.inner.read_dir(path))
+    }
+
+    fn open_file(&self, path: &Path) -> Result<Box<dyn Read + Send>, VfsError> {
+        self.retry("open_file", path, || self.inner.open_file(path))
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<(), VfsError> {
+        self.inner.remove_file(path)
+    }
+
+    fn copy_file(&self, src: &Path, dest: &Path) -> Result<(), VfsError> {
+        self.inner.copy_file(src, dest)
+    }
+
+    fn is_writable(&self) -> bool {
+        self.inner.is_writable()
+    }
+
+    fn capabilities(&self) -> VfsCapabilities {
+        self.inner.capabilities()
+    }
+
+    fn content_fingerprint(&self, path: &Path) -> Result<Option<String>, VfsError> {
+        self.inner.content_fingerprint(path)
+    }
+
+    fn hash(&self, path: &Path, algorithm: HashAlgorithm) -> Result<Option<Vec<u8>>, VfsError> {
+        self.retry("hash", path, || self.inner.hash(path, algorithm))
+    }
+
+    fn compression_info(&self, path: &Path) -> Result<Option<CompressionInfo>, VfsError> {
+        self.inner.compression_info(path)
+    }
+
+    fn create_file(&self, path: &Path) -> Result<Box<dyn Write + Send>, VfsError> {
+        self.inner.create_file(path)
+    }
+
+    fn create_dir(&self, path: &Path) -> Result<(), VfsError> {
+        self.inner.create_dir(path)
+    }
+
+    fn create_dir_all(&self, path: &Path) -> Result<(), VfsError> {
+        self.inner.create_dir_all(path)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<(), VfsError> {
+        self.inner.rename(from, to)
+    }
+
+    fn set_mtime(&self, path: &Path, mtime: SystemTime) -> Result<(), VfsError> {
+        self.inner.set_mtime(path, mtime)
+    }
+
+    fn write_file(&self, path: &Path, content: &[u8]) -> Result<(), VfsError> {
+        self.inner.write_file(path, content)
+    }
+
+    fn flush(&self) -> Result<(), VfsError> {
+        self.inner.flush()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::vfs::LocalVfs;
+    use crate::vfs::{LocalVfs, MemVfs};
     use std::fs;
     use tempfile::TempDir;
 
@@ -391,4 +746,221 @@ mod tests {
         reader.read_to_string(&mut content).unwrap();
         assert_eq!(content, "from layer 2");
     }
+
+    /// Counts how often files are opened on the wrapped VFS
+    #[cfg(feature = "archives")]
+    struct CountingVfs {
+        inner: Arc<dyn Vfs>,
+        opens: std::sync::atomic::AtomicUsize,
+    }
+
+    #[cfg(feature = "archives")]
+    impl Vfs for CountingVfs {
+        fn instance_id(&self) -> &str {
+            self.inner.instance_id()
+        }
+
+        fn metadata(&self, path: &Path) -> Result<FileMetadata, VfsError> {
+            self.inner.metadata(path)
+        }
+
+        fn read_dir(&self, path: &Path) -> Result<Vec<FileEntry>, VfsError> {
+            self.inner.read_dir(path)
+        }
+
+        fn open_file(&self, path: &Path) -> Result<Box<dyn Read + Send>, VfsError> {
+            self.opens.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            self.inner.open_file(path)
+        }
+
+        fn remove_file(&self, path: &Path) -> Result<(), VfsError> {
+            self.inner.remove_file(path)
+        }
+
+        fn copy_file(&self, src: &Path, dest: &Path) -> Result<(), VfsError> {
+            self.inner.copy_file(src, dest)
+        }
+    }
+
+    #[cfg(feature = "archives")]
+    #[test]
+    fn test_temp_extract_vfs_extracts_once() {
+        use crate::vfs::{WritableZipVfs, ZipVfs};
+
+        let temp = TempDir::new().unwrap();
+        let zip_path = temp.path().join("data.zip");
+        let writer = WritableZipVfs::create(zip_path.clone()).unwrap();
+        writer
+            .write_file(Path::new("report.xlsx"), b"workbook bytes")
+            .unwrap();
+        writer.flush().unwrap();
+
+        let counting = Arc::new(CountingVfs {
+            inner: Arc::new(ZipVfs::new(zip_path).unwrap()),
+            opens: std::sync::atomic::AtomicUsize::new(0),
+        });
+        let vfs = TempExtractVfs::new(counting.clone()).unwrap();
+        assert!(vfs.instance_id().starts_with("extract:"));
+
+        for _ in 0..2 {
+            let mut content = Vec::new();
+            vfs.open_file(Path::new("report.xlsx"))
+                .unwrap()
+                .read_to_end(&mut content)
+                .unwrap();
+            assert_eq!(content, b"workbook bytes");
+        }
+        let local = vfs.extracted_path(Path::new("report.xlsx")).unwrap();
+        assert_eq!(counting.opens.load(std::sync::atomic::Ordering::SeqCst), 1);
+        assert_eq!(local.file_name().unwrap(), "report.xlsx");
+        assert_eq!(fs::read(&local).unwrap(), b"workbook bytes");
+
+        drop(vfs);
+        assert!(!local.exists());
+    }
+
+    #[test]
+    fn test_temp_extract_vfs_reextracts_after_invalidate() {
+        let inner = MemVfs::new("extract")
+            .with_file("a.txt", "a")
+            .with_file("b.txt", "b")
+            .with_file("c.txt", "c");
+        let vfs = TempExtractVfs::new(Arc::new(inner)).unwrap();
+
+        let first_a = vfs.extracted_path(Path::new("a.txt")).unwrap();
+        vfs.extracted_path(Path::new("b.txt")).unwrap();
+        vfs.remove_file(Path::new("a.txt")).unwrap();
+        assert!(!first_a.exists());
+
+        // Extracting after an invalidation must not collide with a live slot
+        let c = vfs.extracted_path(Path::new("c.txt")).unwrap();
+        assert_eq!(fs::read(&c).unwrap(), b"c");
+        vfs.copy_file(Path::new("c.txt"), Path::new("a.txt"))
+            .unwrap();
+        let a = vfs.extracted_path(Path::new("a.txt")).unwrap();
+        assert_eq!(fs::read(&a).unwrap(), b"c");
+        assert_eq!(
+            fs::read(vfs.extracted_path(Path::new("b.txt")).unwrap()).unwrap(),
+            b"b"
+        );
+    }
+
+    /// Fails the first `failures` reads with `error`, then reads from `inner`
+    struct FlakyVfs {
+        inner: Arc<dyn Vfs>,
+        failures: usize,
+        error: fn() -> VfsError,
+        calls: std::sync::atomic::AtomicUsize,
+    }
+
+    impl FlakyVfs {
+        fn new(inner: Arc<dyn Vfs>, failures: usize, error: fn() -> VfsError) -> Self {
+            Self {
+                inner,
+                failures,
+                error,
+                calls: std::sync::atomic::AtomicUsize::new(0),
+            }
+        }
+
+        fn calls(&self) -> usize {
+            self.calls.load(std::sync::atomic::Ordering::SeqCst)
+        }
+
+        fn check(&self) -> Result<(), VfsError> {
+            let call = self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            if call < self.failures {
+                Err((self.error)())
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    impl Vfs for FlakyVfs {
+        fn instance_id(&self) -> &str {
+            "flaky"
+        }
+
+        fn metadata(&self, path: &Path) -> Result<FileMetadata, VfsError> {
+            self.check()?;
+            self.inner.metadata(path)
+        }
+
+        fn read_dir(&self, path: &Path) -> Result<Vec<FileEntry>, VfsError> {
+            self.check()?;
+            self.inner.read_dir(path)
+        }
+
+        fn open_file(&self, path: &Path) -> Result<Box<dyn Read + Send>, VfsError> {
+            self.check()?;
+            self.inner.open_file(path)
+        }
+
+        fn remove_file(&self, path: &Path) -> Result<(), VfsError> {
+            self.inner.remove_file(path)
+        }
+
+        fn copy_file(&self, src: &Path, dest: &Path) -> Result<(), VfsError> {
+            self.inner.copy_file(src, dest)
+        }
+    }
+
+    fn timeout() -> VfsError {
+        VfsError::Io(std::io::Error::from(std::io::ErrorKind::TimedOut))
+    }
+
+    fn no_backoff(inner: Arc<dyn Vfs>) -> RetryVfs {
+        RetryVfs::new(inner).with_backoff(Duration::ZERO, Duration::ZERO)
+    }
+
+    #[test]
+    fn test_retry_vfs_recovers_from_transient_errors() {
+        let temp = TempDir::new().unwrap();
+        fs::write(temp.path().join("file.txt"), b"content").unwrap();
+        let local: Arc<dyn Vfs> = Arc::new(LocalVfs::new(temp.path().to_path_buf()));
+
+        let flaky = Arc::new(FlakyVfs::new(local.clone(), 2, timeout));
+        let vfs = no_backoff(flaky.clone());
+        assert_eq!(vfs.instance_id(), "retry:flaky");
+        let mut content = String::new();
+        vfs.open_file(Path::new("file.txt"))
+            .unwrap()
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, "content");
+        assert_eq!(flaky.calls(), 3);
+
+        let flaky = Arc::new(FlakyVfs::new(local, 2, || {
+            VfsError::Transient("503 Service Unavailable".to_string())
+        }));
+        let entries = no_backoff(flaky.clone()).read_dir(Path::new("")).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(flaky.calls(), 3);
+    }
+
+    #[test]
+    fn test_retry_vfs_gives_up() {
+        let temp = TempDir::new().unwrap();
+        let local: Arc<dyn Vfs> = Arc::new(LocalVfs::new(temp.path().to_path_buf()));
+
+        // Permanent errors are returned on the first attempt
+        let flaky = Arc::new(FlakyVfs::new(local.clone(), usize::MAX, || {
+            VfsError::PermissionDenied("bucket".to_string())
+        }));
+        let err = no_backoff(flaky.clone())
+            .metadata(Path::new("file.txt"))
+            .unwrap_err();
+        assert!(matches!(err, VfsError::PermissionDenied(_)));
+        assert_eq!(flaky.calls(), 1);
+
+        // Transient errors are retried until the attempts run out
+        let flaky = Arc::new(FlakyVfs::new(local, usize::MAX, timeout));
+        let err = no_backoff(flaky.clone())
+            .with_max_attempts(3)
+            .read_dir(Path::new(""))
+            .unwrap_err();
+        assert!(err.is_transient());
+        assert_eq!(flaky.calls(), 3);
+    }
 }
@@ -1,4 +1,4 @@
-use rcompare_common::{FileEntry, FileMetadata, Vfs, VfsCapabilities, VfsError};
+use rcompare_common::{FileEntry, FileMetadata, FileType, Vfs, VfsCapabilities, VfsError};
 use reqwest::{Client, Method, StatusCode};
 use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
@@ -155,6 +155,7 @@ impl WebDavVfs {
                         size: 0, // Will be updated from getcontentlength
                         modified: SystemTime::now(),
                         is_dir,
+                        file_type: FileType::from_is_dir(is_dir),
                     });
                 }
             }
@@ -172,6 +173,21 @@ impl WebDavVfs {
     }
 }
 
+/// Error for a request that got no response; timeouts and failed
+/// connections are transient
+fn request_error(message: String, error: &reqwest::Error) -> VfsError {
+    if error.is_timeout() || error.is_connect() {
+        VfsError::Transient(message)
+    } else {
+        VfsError::Io(std::io::Error::other(message))
+    }
+}
+
+/// Whether a response status reports a temporary condition on the server
+fn is_transient_status(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+}
+
 impl Vfs for WebDavVfs {
     fn instance_id(&self) -> &str {
         &self.instance_id
@@ -199,12 +215,10 @@ impl Vfs for WebDavVfs {
 
             let request = self.add_auth_header(request);
 
-            let response = request.send().await.map_err(|e| {
-                VfsError::Io(std::io::Error::other(format!(
-                    "WebDAV PROPFIND failed: {}",
-                    e
-                )))
-            })?;
+            let response = request
+                .send()
+                .await
+                .map_err(|e| request_error(format!("WebDAV PROPFIND failed: {}", e), &e))?;
 
             if !response.status().is_success() {
                 if response.status() == StatusCode::NOT_FOUND {
@@ -213,10 +227,11 @@ impl Vfs for WebDavVfs {
                         path.display()
                     )));
                 }
-                return Err(VfsError::Io(std::io::Error::other(format!(
-                    "WebDAV PROPFIND returned status: {}",
-                    response.status()
-                ))));
+                let message = format!("WebDAV PROPFIND returned status: {}", response.status());
+                if is_transient_status(response.status()) {
+                    return Err(VfsError::Transient(message));
+                }
+                return Err(VfsError::Io(std::io::Error::other(message)));
             }
 
             let xml = response.text().await.map_err(|e| {
@@ -282,18 +297,17 @@ impl Vfs for WebDavVfs {
 
             let request = self.add_auth_header(request);
 
-            let response = request.send().await.map_err(|e| {
-                VfsError::Io(std::io::Error::other(format!(
-                    "WebDAV PROPFIND failed: {}",
-                    e
-                )))
-            })?;
+            let response = request
+                .send()
+                .await
+                .map_err(|e| request_error(format!("WebDAV PROPFIND failed: {}", e), &e))?;
 
             if !response.status().is_success() {
-                return Err(VfsError::Io(std::io::Error::other(format!(
-                    "WebDAV PROPFIND returned status: {}",
-                    response.status()
-                ))));
+                let message = format!("WebDAV PROPFIND returned status: {}", response.status());
+                if is_transient_status(response.status()) {
+                    return Err(VfsError::Transient(message));
+                }
+                return Err(VfsError::Io(std::io::Error::other(message)));
             }
 
             let xml = response.text().await.map_err(|e| {
@@ -315,12 +329,19 @@ impl Vfs for WebDavVfs {
             let request = self.add_auth_header(request);
 
             let response = request.send().await.map_err(|e| {
-                VfsError::Io(std::io::Error::new(
-                    std::io::ErrorKind::NotFound,
-                    format!("Failed to GET WebDAV file: {}", e),
-                ))
+                let message = format!("Failed to GET WebDAV file: {}", e);
+                if e.is_timeout() || e.is_connect() {
+                    return VfsError::Transient(message);
+                }
+                VfsError::Io(std::io::Error::new(std::io::ErrorKind::NotFound, message))
             })?;
 
+            if is_transient_status(response.status()) {
+                return Err(VfsError::Transient(format!(
+                    "WebDAV GET returned status: {}",
+                    response.status()
+                )));
+            }
             if !response.status().is_success() {
                 return Err(VfsError::NotFound(format!(
                     "WebDAV file not found: {}",
@@ -403,6 +424,7 @@ impl Vfs for WebDavVfs {
             rename: true,
             create_dir: true,
             set_mtime: false, // WebDAV typically doesn't support setting mtime directly
+            expensive_reads: true,
         }
     }
 
@@ -528,13 +550,16 @@ impl Vfs for WebDavVfs {
     }
 }
 
-/// A writer that buffers data and uploads to WebDAV when dropped
+/// A writer that buffers data and uploads to WebDAV when flushed or dropped
 struct WebDavWriter {
     client: Arc<Client>,
     runtime: Arc<Runtime>,
     url: Url,
     auth: WebDavAuth,
     buffer: Vec<u8>,
+    /// Data written since the last successful upload (an empty file still
+    /// needs one)
+    pending: bool,
 }
 
 impl WebDavWriter {
@@ -545,6 +570,7 @@ impl WebDavWriter {
             url,
             auth,
             buffer: Vec::new(),
+            pending: true,
         }
     }
 
@@ -569,10 +595,15 @@ impl WebDavWriter {
 impl std::io::Write for WebDavWriter {
     fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
         self.buffer.extend_from_slice(buf);
+        self.pending = true;
         Ok(buf.len())
     }
 
     fn flush(&mut self) -> std::io::Result<()> {
+        if !self.pending {
+            return Ok(());
+        }
+
         // Upload the buffer to WebDAV
         let client = self.client.clone();
         let url = self.url.clone();
@@ -585,10 +616,13 @@ impl std::io::Write for WebDavWriter {
             request
                 .send()
                 .await
+                .and_then(|response| response.error_for_status())
                 .map_err(|e| std::io::Error::other(format!("Failed to upload to WebDAV: {}", e)))?;
 
-            Ok(())
-        })
+            Ok::<_, std::io::Error>(())
+        })?;
+        self.pending = false;
+        Ok(())
     }
 }
 
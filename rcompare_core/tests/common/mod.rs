@@ -0,0 +1,172 @@
+//! Fixture helpers for the end-to-end comparison tests.
+//!
+//! A [`Fixture`] owns a temp directory with a `left` and a `right` tree.
+//! Files are written with a fixed modification time so that identical
+//! content compares `Same` without hashing, and a tree can be packed into a
+//! ZIP archive to compare a directory against an archive.
+
+use filetime::{set_file_mtime, FileTime};
+use rcompare_common::{AppConfig, DiffNode, DiffStatus, Vfs};
+use rcompare_core::vfs::{WritableZipVfs, ZipVfs};
+use rcompare_core::{ComparisonEngine, FolderScanner, HashCache};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::TempDir;
+
+/// Modification time given to every fixture file
+pub const FIXED_MTIME: i64 = 1_700_000_000;
+
+#[derive(Debug, Clone, Copy)]
+pub enum Side {
+    Left,
+    Right,
+}
+
+pub struct Fixture {
+    temp: TempDir,
+    left: PathBuf,
+    right: PathBuf,
+}
+
+impl Fixture {
+    pub fn new() -> Self {
+        let temp = TempDir::new().expect("create temp dir");
+        let left = temp.path().join("left");
+        let right = temp.path().join("right");
+        fs::create_dir(&left).expect("create left tree");
+        fs::create_dir(&right).expect("create right tree");
+        Self { temp, left, right }
+    }
+
+    pub fn root(&self, side: Side) -> &Path {
+        match side {
+            Side::Left => &self.left,
+            Side::Right => &self.right,
+        }
+    }
+
+    /// Write a file (creating parent directories) with [`FIXED_MTIME`]
+    pub fn file(&self, side: Side, path: &str, content: &str) -> &Self {
+        let full = self.root(side).join(path);
+        if let Some(parent) = full.parent() {
+            fs::create_dir_all(parent).expect("create parent dirs");
+        }
+        fs::write(&full, content).expect("write fixture file");
+        set_file_mtime(&full, FileTime::from_unix_time(FIXED_MTIME, 0)).expect("set mtime");
+        self
+    }
+
+    /// Write the same file on both sides
+    pub fn both(&self, path: &str, content: &str) -> &Self {
+        self.file(Side::Left, path, content)
+            .file(Side::Right, path, content)
+    }
+
+    /// Create an empty directory
+    pub fn dir(&self, side: Side, path: &str) -> &Self {
+        fs::create_dir_all(self.root(side).join(path)).expect("create fixture dir");
+        self
+    }
+
+    /// Pack one side into `<name>.zip` next to the trees
+    pub fn zip(&self, side: Side, name: &str) -> PathBuf {
+        let path = self.temp.path().join(format!("{name}.zip"));
+        let archive = WritableZipVfs::create(path.clone()).expect("create zip");
+        let root = self.root(side);
+        for entry in self.scan(side) {
+            let target = Path::new(&entry.path);
+            if entry.is_dir {
+                archive.create_dir_all(target).expect("add zip dir");
+            } else {
+                let content = fs::read(root.join(&entry.path)).expect("read fixture file");
+                archive.write_file(target, &content).expect("add zip file");
+            }
+        }
+        archive.flush().expect("write zip");
+        path
+    }
+
+    /// An engine whose hash cache lives inside the fixture
+    pub fn engine(&self) -> ComparisonEngine {
+        let cache = HashCache::new(self.temp.path().join("cache")).expect("create hash cache");
+        ComparisonEngine::new(cache)
+    }
+
+    fn scan(&self, side: Side) -> Vec<rcompare_common::FileEntry> {
+        FolderScanner::new(AppConfig::default())
+            .scan(self.root(side))
+            .expect("scan fixture tree")
+    }
+
+    /// Scan both trees and compare them with the default engine
+    pub fn compare(&self) -> Vec<DiffNode> {
+        self.compare_with(&self.engine())
+    }
+
+    /// Scan both trees and compare them with `engine`
+    pub fn compare_with(&self, engine: &ComparisonEngine) -> Vec<DiffNode> {
+        engine
+            .compare(
+                &self.left,
+                &self.right,
+                self.scan(Side::Left),
+                self.scan(Side::Right),
+            )
+            .expect("compare trees")
+    }
+
+    /// Compare the left tree against a ZIP archive on the right
+    pub fn compare_left_with_zip(&self, zip: &Path) -> Vec<DiffNode> {
+        let vfs = ZipVfs::new(zip.to_path_buf()).expect("open zip");
+        let right_entries = FolderScanner::new(AppConfig::default())
+            .scan_vfs(&vfs, Path::new(""))
+            .expect("scan zip");
+        self.engine()
+            .compare_with_vfs(
+                &self.left,
+                Path::new(""),
+                self.scan(Side::Left),
+                right_entries,
+                None,
+                Some(&vfs as &dyn Vfs),
+            )
+            .expect("compare tree with zip")
+    }
+}
+
+/// Render nodes as `"<status> <path>"` lines, directories with a trailing
+/// `/`, using the CLI's status marks: `==`, `!=`, `<<`, `>>`, `??`, `<>`
+///
+/// Also checks that each node's sides agree with its status, so tests can
+/// assert the whole result with one comparison.
+pub fn render(nodes: &[DiffNode]) -> Vec<String> {
+    nodes
+        .iter()
+        .map(|node| {
+            let (mark, left, right) = match node.status {
+                DiffStatus::Same => ("==", true, true),
+                DiffStatus::Different => ("!=", true, true),
+                DiffStatus::OrphanLeft => ("<<", true, false),
+                DiffStatus::OrphanRight => (">>", false, true),
+                DiffStatus::Unchecked => ("??", true, true),
+                DiffStatus::TypeConflict => ("<>", true, true),
+                DiffStatus::Ignored => ("--", node.left.is_some(), node.right.is_some()),
+            };
+            assert_eq!(
+                (node.left.is_some(), node.right.is_some()),
+                (left, right),
+                "sides of {} don't match {:?}",
+                node.relative_path.display(),
+                node.status
+            );
+
+            let is_dir = node
+                .left
+                .as_ref()
+                .or(node.right.as_ref())
+                .is_some_and(|e| e.is_dir);
+            let path = node.relative_path.to_string_lossy().replace('\\', "/");
+            format!("{mark} {path}{}", if is_dir { "/" } else { "" })
+        })
+        .collect()
+}
@@ -0,0 +1,150 @@
+//! End-to-end comparisons of real trees built on disk: scan both sides with
+//! `FolderScanner`, compare with `ComparisonEngine`, assert every node.
+
+mod common;
+
+use common::{render, Fixture, Side};
+
+fn sample(fixture: &Fixture) -> &Fixture {
+    fixture
+        .both("README.md", "# project\n")
+        .both("src/lib.rs", "pub fn answer() -> u32 { 42 }\n")
+        .both("src/util/mod.rs", "pub mod strings;\n")
+        .both("src/util/strings.rs", "pub fn trim() {}\n")
+}
+
+#[test]
+fn identical_trees_are_all_same() {
+    let fixture = Fixture::new();
+    sample(&fixture);
+
+    assert_eq!(
+        render(&fixture.compare()),
+        [
+            "== README.md",
+            "== src/",
+            "== src/lib.rs",
+            "== src/util/",
+            "== src/util/mod.rs",
+            "== src/util/strings.rs",
+        ]
+    );
+}
+
+#[test]
+fn modified_file_is_different() {
+    let fixture = Fixture::new();
+    sample(&fixture).file(
+        Side::Right,
+        "src/lib.rs",
+        "pub fn answer() -> u32 { 43 }\n// longer\n",
+    );
+
+    let nodes = fixture.compare();
+    assert_eq!(
+        render(&nodes),
+        [
+            "== README.md",
+            "== src/",
+            "!= src/lib.rs",
+            "== src/util/",
+            "== src/util/mod.rs",
+            "== src/util/strings.rs",
+        ]
+    );
+    let lib = &nodes[2];
+    assert_ne!(
+        lib.left.as_ref().unwrap().size,
+        lib.right.as_ref().unwrap().size
+    );
+}
+
+#[test]
+fn same_size_edit_needs_hash_verification() {
+    let fixture = Fixture::new();
+    // Same size and the same (fixed) mtime on both sides
+    sample(&fixture).file(Side::Right, "src/lib.rs", "pub fn answer() -> u32 { 43 }\n");
+
+    // Size and mtime agree, so the default engine takes the files as equal
+    assert!(render(&fixture.compare()).contains(&"== src/lib.rs".to_string()));
+
+    let engine = fixture.engine().with_hash_verification(true);
+    assert_eq!(
+        render(&fixture.compare_with(&engine)),
+        [
+            "== README.md",
+            "== src/",
+            "!= src/lib.rs",
+            "== src/util/",
+            "== src/util/mod.rs",
+            "== src/util/strings.rs",
+        ]
+    );
+}
+
+#[test]
+fn orphans_on_each_side() {
+    let fixture = Fixture::new();
+    sample(&fixture)
+        .file(Side::Left, "src/old.rs", "// removed\n")
+        .file(Side::Left, "legacy/notes.txt", "old notes\n")
+        .file(Side::Right, "src/new.rs", "// added\n")
+        .dir(Side::Right, "empty");
+
+    assert_eq!(
+        render(&fixture.compare()),
+        [
+            "== README.md",
+            ">> empty/",
+            "<< legacy/",
+            "<< legacy/notes.txt",
+            "== src/",
+            "== src/lib.rs",
+            ">> src/new.rs",
+            "<< src/old.rs",
+            "== src/util/",
+            "== src/util/mod.rs",
+            "== src/util/strings.rs",
+        ]
+    );
+}
+
+#[test]
+fn file_against_directory_is_type_conflict() {
+    let fixture = Fixture::new();
+    fixture.file(Side::Left, "config", "key = 1\n").file(
+        Side::Right,
+        "config/settings.toml",
+        "key = 1\n",
+    );
+
+    assert_eq!(
+        render(&fixture.compare()),
+        ["<> config", ">> config/settings.toml"]
+    );
+}
+
+#[test]
+fn directory_against_zip_of_itself() {
+    let fixture = Fixture::new();
+    sample(&fixture);
+    // Pack the right tree, then diverge the left tree from it
+    let zip = fixture.zip(Side::Right, "right");
+    fixture
+        .file(Side::Left, "src/lib.rs", "pub fn answer() -> u32 { 43 }\n")
+        .file(Side::Left, "src/extra.rs", "// left only\n");
+
+    // Archive entries don't keep fixture mtimes, so content is hashed
+    assert_eq!(
+        render(&fixture.compare_left_with_zip(&zip)),
+        [
+            "== README.md",
+            "== src/",
+            "<< src/extra.rs",
+            "!= src/lib.rs",
+            "== src/util/",
+            "== src/util/mod.rs",
+            "== src/util/strings.rs",
+        ]
+    );
+}
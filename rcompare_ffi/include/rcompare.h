@@ -4,7 +4,8 @@
  *
  * This library provides libkomparediff2-compatible patch parsing, manipulation,
  * and serialization functionality. It supports unified diff, context diff, and
- * other common diff formats.
+ * other common diff formats. It also exposes directory comparison through
+ * rcompare_compare_dirs().
  *
  * @example
  * ```c
@@ -50,6 +51,14 @@ extern "C" {
  */
 typedef struct PatchSetHandle PatchSetHandle;
 
+/**
+ * @brief Opaque handle to the result of a directory comparison
+ *
+ * Created by rcompare_compare_dirs() and freed by rcompare_free_comparison().
+ * Do not attempt to dereference or manipulate directly.
+ */
+typedef struct ComparisonHandle ComparisonHandle;
+
 /* ========================================================================== */
 /*                              Enumerations                                  */
 /* ========================================================================== */
@@ -74,7 +83,8 @@ typedef enum {
     RCOMPARE_GENERATOR_DIFF = 1,       /**< Plain diff utility */
     RCOMPARE_GENERATOR_CVS = 2,        /**< CVS diff */
     RCOMPARE_GENERATOR_PERFORCE = 3,   /**< Perforce */
-    RCOMPARE_GENERATOR_SUBVERSION = 4  /**< Subversion */
+    RCOMPARE_GENERATOR_SUBVERSION = 4, /**< Subversion */
+    RCOMPARE_GENERATOR_GIT = 5         /**< git diff */
 } RCompareDiffGenerator;
 
 /**
@@ -95,6 +105,19 @@ typedef enum {
     RCOMPARE_HUNK_ADDED_BY_BLEND = 1 /**< Context added by blending */
 } RCompareHunkType;
 
+/**
+ * @brief Comparison status of a directory comparison node
+ */
+typedef enum {
+    RCOMPARE_STATUS_SAME = 0,          /**< Both sides are identical */
+    RCOMPARE_STATUS_DIFFERENT = 1,     /**< Both sides exist but differ */
+    RCOMPARE_STATUS_ORPHAN_LEFT = 2,   /**< Exists only on the left */
+    RCOMPARE_STATUS_ORPHAN_RIGHT = 3,  /**< Exists only on the right */
+    RCOMPARE_STATUS_UNCHECKED = 4,     /**< Same size, content not verified */
+    RCOMPARE_STATUS_TYPE_CONFLICT = 5, /**< File on one side, directory on the other */
+    RCOMPARE_STATUS_IGNORED = 6        /**< Left out by a hidden or ignore rule */
+} RCompareStatus;
+
 /* ========================================================================== */
 /*                           Lifecycle Functions                              */
 /* ========================================================================== */
@@ -248,6 +271,26 @@ size_t rcompare_filepatch_hunk_count(const PatchSetHandle* handle, size_t file_i
  */
 int rcompare_filepatch_is_blended(const PatchSetHandle* handle, size_t file_idx);
 
+/**
+ * @brief Count differences flagged as conflicting with the original file
+ *
+ * Conflicts are detected by rcompare_blend_file().
+ *
+ * @param handle PatchSet handle
+ * @param file_idx File index (0-based)
+ * @return Number of conflicting differences, or 0 if handle/index invalid
+ */
+size_t rcompare_filepatch_conflict_count(const PatchSetHandle* handle, size_t file_idx);
+
+/**
+ * @brief Count differences that are not applied yet
+ *
+ * @param handle PatchSet handle
+ * @param file_idx File index (0-based)
+ * @return Number of unapplied differences, or 0 if handle/index invalid
+ */
+size_t rcompare_filepatch_unapplied_count(const PatchSetHandle* handle, size_t file_idx);
+
 /* ========================================================================== */
 /*                            Hunk Accessors                                  */
 /* ========================================================================== */
@@ -512,6 +555,107 @@ int rcompare_unapply_all(PatchSetHandle* handle, size_t file_idx);
  */
 char* rcompare_serialize_diff(const PatchSetHandle* handle);
 
+/* ========================================================================== */
+/*                         Directory Comparison                               */
+/* ========================================================================== */
+
+/**
+ * @brief Options for rcompare_compare_dirs()
+ */
+typedef struct {
+    const char* const* ignore_patterns; /**< Gitignore-style patterns (may be NULL if count is 0) */
+    size_t ignore_pattern_count;        /**< Number of entries in ignore_patterns */
+    int verify_hashes;                  /**< Non-zero to hash same-sized files */
+    const char* cache_dir;              /**< Hash cache directory, or NULL for the default */
+} RCompareOptions;
+
+/**
+ * @brief Compare two local directories
+ *
+ * @param left Left directory path (UTF-8)
+ * @param right Right directory path (UTF-8)
+ * @param options Comparison options, or NULL for defaults
+ * @param out Output parameter for the ComparisonHandle
+ * @return 0 on success, -1 on error
+ *
+ * @note The caller must free the handle with rcompare_free_comparison()
+ * @note Nodes are sorted by relative path
+ */
+int rcompare_compare_dirs(const char* left, const char* right,
+                          const RCompareOptions* options, ComparisonHandle** out);
+
+/**
+ * @brief Free a comparison handle and all associated memory
+ *
+ * @param handle Handle to free (can be NULL)
+ *
+ * @note After calling, all string pointers from node accessors become invalid
+ */
+void rcompare_free_comparison(ComparisonHandle* handle);
+
+/**
+ * @brief Get the number of nodes in a comparison
+ *
+ * @param handle Comparison handle
+ * @return Number of nodes, or 0 if handle is NULL
+ */
+size_t rcompare_comparison_node_count(const ComparisonHandle* handle);
+
+/**
+ * @brief Get the relative path of a node, using '/' separators
+ *
+ * @param handle Comparison handle
+ * @param idx Node index (0-based)
+ * @return Path string, or NULL if index is invalid
+ *
+ * @note String valid until rcompare_free_comparison() is called
+ */
+const char* rcompare_comparison_node_path(ComparisonHandle* handle, size_t idx);
+
+/**
+ * @brief Get the comparison status of a node
+ *
+ * @param handle Comparison handle
+ * @param idx Node index (0-based)
+ * @return Status enum value
+ */
+uint32_t rcompare_comparison_node_status(const ComparisonHandle* handle, size_t idx);
+
+/**
+ * @brief Check whether either side of a node is a directory
+ *
+ * @return 1 if a directory, 0 otherwise
+ */
+int rcompare_comparison_node_is_dir(const ComparisonHandle* handle, size_t idx);
+
+/**
+ * @brief Check whether a node exists on the left side
+ *
+ * @return 1 if present, 0 otherwise
+ */
+int rcompare_comparison_node_has_left(const ComparisonHandle* handle, size_t idx);
+
+/**
+ * @brief Check whether a node exists on the right side
+ *
+ * @return 1 if present, 0 otherwise
+ */
+int rcompare_comparison_node_has_right(const ComparisonHandle* handle, size_t idx);
+
+/**
+ * @brief Get the size of the left entry in bytes
+ *
+ * @return Size, or 0 if the node has no left side
+ */
+uint64_t rcompare_comparison_node_left_size(const ComparisonHandle* handle, size_t idx);
+
+/**
+ * @brief Get the size of the right entry in bytes
+ *
+ * @return Size, or 0 if the node has no right side
+ */
+uint64_t rcompare_comparison_node_right_size(const ComparisonHandle* handle, size_t idx);
+
 #ifdef __cplusplus
 }
 #endif
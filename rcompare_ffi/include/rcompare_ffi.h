@@ -1,9 +1,10 @@
 /*
- * rcompare_ffi.h - C FFI for rcompare patch parsing, engine, and serialization.
+ * rcompare_ffi.h - C FFI for rcompare patch parsing, engine, and serialization,
+ * and for directory comparison.
  *
- * All functions use opaque RComparePatchSet handles.
- * String pointers returned by accessor functions are valid until
- * rcompare_free_patchset() is called on the owning handle.
+ * All functions use opaque RComparePatchSet or RCompareComparison handles.
+ * String pointers returned by accessor functions are valid until the owning
+ * handle is freed.
  */
 
 #ifndef RCOMPARE_FFI_H
@@ -16,8 +17,9 @@
 extern "C" {
 #endif
 
-/* Opaque handle */
+/* Opaque handles */
 typedef struct RComparePatchSet RComparePatchSet;
+typedef struct RCompareComparison RCompareComparison;
 
 /* --- Enums (match Rust repr(u32)) --- */
 
@@ -35,6 +37,7 @@ typedef struct RComparePatchSet RComparePatchSet;
 #define RCOMPARE_GEN_CVSDIFF     2
 #define RCOMPARE_GEN_PERFORCE    3
 #define RCOMPARE_GEN_SUBVERSION  4
+#define RCOMPARE_GEN_GIT         5
 
 /* DifferenceType */
 #define RCOMPARE_DIFF_UNCHANGED  0
@@ -46,6 +49,15 @@ typedef struct RComparePatchSet RComparePatchSet;
 #define RCOMPARE_HUNK_NORMAL        0
 #define RCOMPARE_HUNK_ADDEDBYBLEND  1
 
+/* DiffStatus */
+#define RCOMPARE_STATUS_SAME           0
+#define RCOMPARE_STATUS_DIFFERENT      1
+#define RCOMPARE_STATUS_ORPHAN_LEFT    2
+#define RCOMPARE_STATUS_ORPHAN_RIGHT   3
+#define RCOMPARE_STATUS_UNCHECKED      4
+#define RCOMPARE_STATUS_TYPE_CONFLICT  5
+#define RCOMPARE_STATUS_IGNORED        6
+
 /* --- Lifecycle --- */
 
 /**
@@ -80,6 +92,8 @@ const char *rcompare_filepatch_source_revision(RComparePatchSet *h, size_t idx);
 const char *rcompare_filepatch_dest_revision(RComparePatchSet *h, size_t idx);
 size_t      rcompare_filepatch_hunk_count(const RComparePatchSet *h, size_t idx);
 int         rcompare_filepatch_is_blended(const RComparePatchSet *h, size_t idx);
+size_t      rcompare_filepatch_conflict_count(const RComparePatchSet *h, size_t idx);
+size_t      rcompare_filepatch_unapplied_count(const RComparePatchSet *h, size_t idx);
 
 /* --- Hunk accessors (file_idx fi, hunk_idx hi) --- */
 
@@ -126,6 +140,36 @@ int rcompare_unapply_all(RComparePatchSet *h, size_t fi);
  */
 char *rcompare_serialize_diff(const RComparePatchSet *h);
 
+/* --- Directory comparison --- */
+
+/* Options for rcompare_compare_dirs (pass NULL for defaults). */
+typedef struct {
+    const char *const *ignore_patterns;  /* gitignore-style patterns */
+    size_t ignore_pattern_count;
+    int verify_hashes;                   /* non-zero to hash same-sized files */
+    const char *cache_dir;               /* NULL for the default cache dir */
+} RCompareOptions;
+
+/**
+ * Compare two directories and create a comparison handle.
+ * @return 0 on success, -1 on error
+ */
+int rcompare_compare_dirs(const char *left, const char *right,
+                          const RCompareOptions *options,
+                          RCompareComparison **out);
+
+/** Free a comparison handle and all associated memory. */
+void rcompare_free_comparison(RCompareComparison *handle);
+
+size_t      rcompare_comparison_node_count(const RCompareComparison *h);
+const char *rcompare_comparison_node_path(RCompareComparison *h, size_t idx);
+uint32_t    rcompare_comparison_node_status(const RCompareComparison *h, size_t idx);
+int         rcompare_comparison_node_is_dir(const RCompareComparison *h, size_t idx);
+int         rcompare_comparison_node_has_left(const RCompareComparison *h, size_t idx);
+int         rcompare_comparison_node_has_right(const RCompareComparison *h, size_t idx);
+uint64_t    rcompare_comparison_node_left_size(const RCompareComparison *h, size_t idx);
+uint64_t    rcompare_comparison_node_right_size(const RCompareComparison *h, size_t idx);
+
 #ifdef __cplusplus
 }
 #endif
@@ -1,14 +1,18 @@
-//! C FFI layer for rcompare's patch parsing, engine, and serialization.
+//! C FFI layer for rcompare's patch parsing, engine, and serialization, and
+//! for directory comparison.
 //!
-//! Exposes opaque PatchSet handles and accessor functions for use from C/C++.
-//! All strings returned by accessor functions are owned by the PatchSet and
-//! valid until `rcompare_free_patchset` is called (arena pattern).
+//! Exposes opaque PatchSet and comparison handles and accessor functions for
+//! use from C/C++. All strings returned by accessor functions are owned by the
+//! handle and valid until it is freed (arena pattern).
 
 #![allow(private_interfaces)]
 
-use rcompare_common::{DiffFormat, DiffGenerator, HunkType, PatchSet};
-use rcompare_core::{PatchEngine, PatchParser, PatchSerializer};
-use std::ffi::{c_char, CString};
+use rcompare_common::{DiffFormat, DiffGenerator, DiffNode, DiffStatus, HunkType, PatchSet};
+use rcompare_core::{
+    compare_directories, CompareOptions, PatchEngine, PatchParser, PatchSerializer,
+};
+use std::ffi::{c_char, CStr, CString};
+use std::path::Path;
 use std::ptr;
 
 /// Opaque handle to a PatchSet, with pre-computed CString cache for arena allocation.
@@ -199,6 +203,40 @@ pub unsafe extern "C" fn rcompare_filepatch_is_blended(
     ps.files[idx].blended as i32
 }
 
+/// Number of differences in a file patch flagged as conflicting.
+///
+/// # Safety
+///
+/// `h` must be a valid handle from `rcompare_parse_diff`, or null.
+#[no_mangle]
+pub unsafe extern "C" fn rcompare_filepatch_conflict_count(
+    h: *const PatchSetHandle,
+    idx: usize,
+) -> usize {
+    if h.is_null() {
+        return 0;
+    }
+    let ps = &(*h).patch_set;
+    ps.files.get(idx).map_or(0, |fp| fp.conflicts().count())
+}
+
+/// Number of differences in a file patch that are not applied yet.
+///
+/// # Safety
+///
+/// `h` must be a valid handle from `rcompare_parse_diff`, or null.
+#[no_mangle]
+pub unsafe extern "C" fn rcompare_filepatch_unapplied_count(
+    h: *const PatchSetHandle,
+    idx: usize,
+) -> usize {
+    if h.is_null() {
+        return 0;
+    }
+    let ps = &(*h).patch_set;
+    ps.files.get(idx).map_or(0, |fp| fp.unapplied().count())
+}
+
 // ===== Hunk accessors =====
 
 /// # Safety
@@ -602,8 +640,254 @@ pub unsafe extern "C" fn rcompare_serialize_diff(
     }
 }
 
+// ===== Directory comparison =====
+
+/// Options for `rcompare_compare_dirs`.
+#[repr(C)]
+pub struct RCompareOptions {
+    /// Array of `ignore_pattern_count` gitignore-style patterns (may be null
+    /// when the count is 0)
+    pub ignore_patterns: *const *const c_char,
+    pub ignore_pattern_count: usize,
+    /// Non-zero to hash same-sized files instead of trusting timestamps
+    pub verify_hashes: i32,
+    /// Hash cache directory, or null for the default cache directory
+    pub cache_dir: *const c_char,
+}
+
+/// Opaque handle to the nodes of a directory comparison, with a CString
+/// cache for arena allocation.
+struct ComparisonHandle {
+    nodes: Vec<DiffNode>,
+    string_cache: Vec<CString>,
+}
+
+impl ComparisonHandle {
+    fn cache_str(&mut self, s: &str) -> *const c_char {
+        let cs = CString::new(s).unwrap_or_default();
+        let ptr = cs.as_ptr();
+        self.string_cache.push(cs);
+        ptr
+    }
+}
+
+/// Compare two local directories and create a comparison handle.
+/// Returns 0 on success, -1 on error.
+/// On success, `*out` is set to the handle (caller must free with
+/// `rcompare_free_comparison`). Nodes are sorted by relative path.
+///
+/// # Safety
+///
+/// - `left` and `right` must be valid NUL-terminated UTF-8 strings.
+/// - `options` must point to a valid `RCompareOptions` whose pattern array
+///   holds `ignore_pattern_count` valid strings, or be null for defaults.
+/// - `out` must point to a valid, writable `*mut ComparisonHandle` location.
+#[no_mangle]
+pub unsafe extern "C" fn rcompare_compare_dirs(
+    left: *const c_char,
+    right: *const c_char,
+    options: *const RCompareOptions,
+    out: *mut *mut ComparisonHandle,
+) -> i32 {
+    if left.is_null() || right.is_null() || out.is_null() {
+        return -1;
+    }
+    let (Ok(left), Ok(right)) = (
+        CStr::from_ptr(left).to_str(),
+        CStr::from_ptr(right).to_str(),
+    ) else {
+        return -1;
+    };
+
+    let mut compare_options = CompareOptions::new();
+    if !options.is_null() {
+        let options = &*options;
+        if options.ignore_pattern_count > 0 {
+            if options.ignore_patterns.is_null() {
+                return -1;
+            }
+            let patterns =
+                std::slice::from_raw_parts(options.ignore_patterns, options.ignore_pattern_count);
+            for &pattern in patterns {
+                if pattern.is_null() {
+                    return -1;
+                }
+                match CStr::from_ptr(pattern).to_str() {
+                    Ok(p) => compare_options = compare_options.ignore_pattern(p),
+                    Err(_) => return -1,
+                }
+            }
+        }
+        compare_options = compare_options.hash_verification(options.verify_hashes != 0);
+        if !options.cache_dir.is_null() {
+            match CStr::from_ptr(options.cache_dir).to_str() {
+                Ok(dir) => compare_options = compare_options.cache_dir(dir),
+                Err(_) => return -1,
+            }
+        }
+    }
+
+    match compare_directories(Path::new(left), Path::new(right), &compare_options) {
+        Ok(nodes) => {
+            let handle = Box::new(ComparisonHandle {
+                nodes,
+                string_cache: Vec::new(),
+            });
+            *out = Box::into_raw(handle);
+            0
+        }
+        Err(_) => -1,
+    }
+}
+
+/// Free a comparison handle and all cached strings.
+///
+/// # Safety
+///
+/// - `handle` must be a pointer returned by `rcompare_compare_dirs`, or null.
+/// - Must not be called more than once for the same handle.
+#[no_mangle]
+pub unsafe extern "C" fn rcompare_free_comparison(handle: *mut ComparisonHandle) {
+    if !handle.is_null() {
+        drop(Box::from_raw(handle));
+    }
+}
+
+/// # Safety
+///
+/// `h` must be a valid handle from `rcompare_compare_dirs`, or null.
+#[no_mangle]
+pub unsafe extern "C" fn rcompare_comparison_node_count(h: *const ComparisonHandle) -> usize {
+    if h.is_null() {
+        return 0;
+    }
+    let handle = &*h;
+    handle.nodes.len()
+}
+
+/// Relative path of a node, using `/` separators.
+///
+/// # Safety
+///
+/// `h` must be a valid handle from `rcompare_compare_dirs`, or null.
+#[no_mangle]
+pub unsafe extern "C" fn rcompare_comparison_node_path(
+    h: *mut ComparisonHandle,
+    idx: usize,
+) -> *const c_char {
+    if h.is_null() {
+        return ptr::null();
+    }
+    let handle = &mut *h;
+    let path = match handle.nodes.get(idx) {
+        Some(node) => node.relative_path.to_string_lossy().replace('\\', "/"),
+        None => return ptr::null(),
+    };
+    handle.cache_str(&path)
+}
+
+/// Status of a node (see `RCOMPARE_STATUS_*`); `RCOMPARE_STATUS_SAME` for an
+/// invalid index.
+///
+/// # Safety
+///
+/// `h` must be a valid handle from `rcompare_compare_dirs`, or null.
+#[no_mangle]
+pub unsafe extern "C" fn rcompare_comparison_node_status(
+    h: *const ComparisonHandle,
+    idx: usize,
+) -> u32 {
+    get_node(h, idx).map_or(0, |node| match node.status {
+        DiffStatus::Same => 0,
+        DiffStatus::Different => 1,
+        DiffStatus::OrphanLeft => 2,
+        DiffStatus::OrphanRight => 3,
+        DiffStatus::Unchecked => 4,
+        DiffStatus::TypeConflict => 5,
+        DiffStatus::Ignored => 6,
+    })
+}
+
+/// Whether either side of a node is a directory.
+///
+/// # Safety
+///
+/// `h` must be a valid handle from `rcompare_compare_dirs`, or null.
+#[no_mangle]
+pub unsafe extern "C" fn rcompare_comparison_node_is_dir(
+    h: *const ComparisonHandle,
+    idx: usize,
+) -> i32 {
+    get_node(h, idx).map_or(0, |node| {
+        [&node.left, &node.right]
+            .into_iter()
+            .flatten()
+            .any(|e| e.is_dir) as i32
+    })
+}
+
+/// # Safety
+///
+/// `h` must be a valid handle from `rcompare_compare_dirs`, or null.
+#[no_mangle]
+pub unsafe extern "C" fn rcompare_comparison_node_has_left(
+    h: *const ComparisonHandle,
+    idx: usize,
+) -> i32 {
+    get_node(h, idx).map_or(0, |node| node.left.is_some() as i32)
+}
+
+/// # Safety
+///
+/// `h` must be a valid handle from `rcompare_compare_dirs`, or null.
+#[no_mangle]
+pub unsafe extern "C" fn rcompare_comparison_node_has_right(
+    h: *const ComparisonHandle,
+    idx: usize,
+) -> i32 {
+    get_node(h, idx).map_or(0, |node| node.right.is_some() as i32)
+}
+
+/// Size in bytes of the left entry, or 0 if the node has no left side.
+///
+/// # Safety
+///
+/// `h` must be a valid handle from `rcompare_compare_dirs`, or null.
+#[no_mangle]
+pub unsafe extern "C" fn rcompare_comparison_node_left_size(
+    h: *const ComparisonHandle,
+    idx: usize,
+) -> u64 {
+    get_node(h, idx)
+        .and_then(|node| node.left.as_ref())
+        .map_or(0, |e| e.size)
+}
+
+/// Size in bytes of the right entry, or 0 if the node has no right side.
+///
+/// # Safety
+///
+/// `h` must be a valid handle from `rcompare_compare_dirs`, or null.
+#[no_mangle]
+pub unsafe extern "C" fn rcompare_comparison_node_right_size(
+    h: *const ComparisonHandle,
+    idx: usize,
+) -> u64 {
+    get_node(h, idx)
+        .and_then(|node| node.right.as_ref())
+        .map_or(0, |e| e.size)
+}
+
 // ===== Helpers =====
 
+unsafe fn get_node<'a>(h: *const ComparisonHandle, idx: usize) -> Option<&'a DiffNode> {
+    if h.is_null() {
+        return None;
+    }
+    let handle = &*h;
+    handle.nodes.get(idx)
+}
+
 unsafe fn get_hunk<'a>(
     h: *const PatchSetHandle,
     fi: usize,
@@ -612,7 +896,8 @@ unsafe fn get_hunk<'a>(
     if h.is_null() {
         return None;
     }
-    (&(*h).patch_set.files).get(fi)?.hunks.get(hi)
+    let ps = &(*h).patch_set;
+    ps.files.get(fi)?.hunks.get(hi)
 }
 
 unsafe fn get_diff<'a>(
@@ -708,8 +993,7 @@ mod tests {
     #[test]
     fn test_parse_malformed_diff() {
         unsafe {
-            // The parser is lenient - empty/random text just produces empty patchset
-            // Test with truly malformed diff structure (incomplete header)
+            // The parser is strict - an unparsable hunk header is rejected
             let malformed = "--- a/file.txt\n+++ b/file.txt\n@@ invalid hunk header";
             let mut handle: *mut PatchSetHandle = ptr::null_mut();
             let result = rcompare_parse_diff(
@@ -717,12 +1001,8 @@ mod tests {
                 malformed.len(),
                 &mut handle as *mut _,
             );
-            // Parser may be lenient and return empty patchset (success)
-            // or may fail - both are acceptable behaviors
-            if result == 0 {
-                assert!(!handle.is_null());
-                rcompare_free_patchset(handle);
-            }
+            assert_eq!(result, -1, "Should return error for malformed hunk header");
+            assert!(handle.is_null());
         }
     }
 
@@ -867,6 +1147,38 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_filepatch_conflict_and_unapplied_counts() {
+        unsafe {
+            let mut handle: *mut PatchSetHandle = ptr::null_mut();
+            rcompare_parse_diff(
+                SAMPLE_DIFF.as_ptr(),
+                SAMPLE_DIFF.len(),
+                &mut handle as *mut _,
+            );
+            assert_eq!(rcompare_filepatch_conflict_count(handle, 0), 0);
+            assert_eq!(rcompare_filepatch_unapplied_count(handle, 0), 1);
+
+            assert_eq!(rcompare_apply_difference(handle, 0, 0), 0);
+            assert_eq!(rcompare_filepatch_unapplied_count(handle, 0), 0);
+            assert_eq!(rcompare_unapply_difference(handle, 0, 0), 0);
+
+            // Conflicts are flagged by the core engine when applying against
+            // mismatching content
+            let ps = &mut (*handle).patch_set;
+            let fp = &mut ps.files[0];
+            let (hi, di) = fp.difference_indices()[0];
+            fp.hunks[hi].differences[di].conflict = true;
+            assert_eq!(rcompare_filepatch_conflict_count(handle, 0), 1);
+            assert_eq!(rcompare_filepatch_unapplied_count(handle, 0), 1);
+
+            assert_eq!(rcompare_filepatch_conflict_count(handle, 999), 0);
+            assert_eq!(rcompare_filepatch_unapplied_count(ptr::null(), 0), 0);
+
+            rcompare_free_patchset(handle);
+        }
+    }
+
     // ===== Hunk Accessor Tests =====
 
     #[test]
@@ -1360,4 +1672,91 @@ mod tests {
             rcompare_free_patchset(handle);
         }
     }
+
+    #[test]
+    fn test_compare_dirs_and_iterate_nodes() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let left = temp.path().join("left");
+        let right = temp.path().join("right");
+        std::fs::create_dir_all(left.join("sub")).unwrap();
+        std::fs::create_dir_all(right.join("sub")).unwrap();
+        std::fs::write(left.join("same.txt"), "same").unwrap();
+        std::fs::write(right.join("same.txt"), "same").unwrap();
+        std::fs::write(left.join("sub/changed.txt"), "left").unwrap();
+        std::fs::write(right.join("sub/changed.txt"), "right!").unwrap();
+        std::fs::write(left.join("only_left.txt"), "l").unwrap();
+        std::fs::write(right.join("only_right.txt"), "rr").unwrap();
+        std::fs::write(left.join("skip.log"), "x").unwrap();
+
+        let c = |p: &std::path::Path| CString::new(p.to_str().unwrap()).unwrap();
+        let (left_c, right_c) = (c(&left), c(&right));
+        let cache_c = c(&temp.path().join("cache"));
+        let pattern = CString::new("*.log").unwrap();
+        let patterns = [pattern.as_ptr()];
+        let options = RCompareOptions {
+            ignore_patterns: patterns.as_ptr(),
+            ignore_pattern_count: patterns.len(),
+            verify_hashes: 1,
+            cache_dir: cache_c.as_ptr(),
+        };
+
+        unsafe {
+            let mut handle: *mut ComparisonHandle = ptr::null_mut();
+            let rc = rcompare_compare_dirs(
+                left_c.as_ptr(),
+                right_c.as_ptr(),
+                &options,
+                &mut handle as *mut _,
+            );
+            assert_eq!(rc, 0);
+
+            let mut nodes = Vec::new();
+            for i in 0..rcompare_comparison_node_count(handle) {
+                let path = std::ffi::CStr::from_ptr(rcompare_comparison_node_path(handle, i))
+                    .to_str()
+                    .unwrap()
+                    .to_string();
+                nodes.push((
+                    path,
+                    rcompare_comparison_node_status(handle, i),
+                    rcompare_comparison_node_is_dir(handle, i),
+                    rcompare_comparison_node_has_left(handle, i),
+                    rcompare_comparison_node_has_right(handle, i),
+                    rcompare_comparison_node_left_size(handle, i),
+                    rcompare_comparison_node_right_size(handle, i),
+                ));
+            }
+            assert_eq!(
+                nodes,
+                [
+                    ("only_left.txt".to_string(), 2, 0, 1, 0, 1, 0),
+                    ("only_right.txt".to_string(), 3, 0, 0, 1, 0, 2),
+                    ("same.txt".to_string(), 0, 0, 1, 1, 4, 4),
+                    ("sub".to_string(), 0, 1, 1, 1, nodes[3].5, nodes[3].6),
+                    ("sub/changed.txt".to_string(), 1, 0, 1, 1, 4, 6),
+                ]
+            );
+
+            assert!(rcompare_comparison_node_path(handle, 99).is_null());
+            assert_eq!(rcompare_comparison_node_left_size(handle, 99), 0);
+            rcompare_free_comparison(handle);
+        }
+    }
+
+    #[test]
+    fn test_compare_dirs_missing_directory_fails() {
+        let missing = CString::new("/nonexistent/rcompare/left").unwrap();
+        unsafe {
+            let mut handle: *mut ComparisonHandle = ptr::null_mut();
+            let rc = rcompare_compare_dirs(
+                missing.as_ptr(),
+                missing.as_ptr(),
+                ptr::null(),
+                &mut handle as *mut _,
+            );
+            assert_eq!(rc, -1);
+            assert!(handle.is_null());
+            assert_eq!(rcompare_comparison_node_count(handle), 0);
+        }
+    }
 }
@@ -3,29 +3,28 @@
 slint::include_modules!();
 
 use rcompare_common::{
-    default_cache_dir, ensure_config, save_config, DiffNode, DiffStatus, FileEntry, SessionProfile,
-    ThreeWayDiffNode, ThreeWayDiffStatus, Vfs,
+    default_cache_dir, ensure_config, save_config, DiffNode, DiffStatus, FileEntry,
+    ProfileSettings, SessionProfile, ThreeWayDiffNode, ThreeWayDiffStatus, Vfs,
 };
 use rcompare_core::image_diff::{is_image_file, ImageDiffEngine};
-use rcompare_core::text_diff::{DiffChangeType, DiffLine, HighlightedSegment};
-use rcompare_core::vfs::{SevenZVfs, TarVfs, ZipVfs};
+use rcompare_core::text_diff::{DiffChangeType, DiffLine, HighlightedSegment, TextDiffConfig};
+use rcompare_core::vfs::{self, VfsCredentials};
 use rcompare_core::TextDiffEngine;
-use rcompare_core::{BinaryDiffEngine, ComparisonEngine, FileOperations, FolderScanner, HashCache};
+use rcompare_core::{
+    detect_file_content_kind, execute_plan, execute_plan_with_vfs, next_with_status,
+    previous_with_status, read_text_file, summarize, summarize_three_way, BinaryDiffEngine,
+    ComparisonEngine, FileOperations, FolderScanner, HashCache, StatusCounts, SyncActionKind,
+    SyncMode, SyncPlanner,
+};
 use std::collections::HashSet;
-use std::io::Read;
 use std::path::PathBuf;
 use std::rc::Rc;
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tracing::{error, info};
 use tracing_subscriber::EnvFilter;
 
-enum ArchiveKind {
-    Zip,
-    Tar,
-    SevenZ,
-}
-
 enum ScanSource {
     Local { root: PathBuf },
     Vfs { vfs: Box<dyn Vfs>, root: PathBuf },
@@ -43,6 +42,11 @@ struct CompareRoots {
     right_root: PathBuf,
     left_is_vfs: bool,
     right_is_vfs: bool,
+    /// Paths the sides were opened from, to reopen archives for writing
+    left_path: PathBuf,
+    right_path: PathBuf,
+    /// Modification time tolerance the comparison ran with
+    mtime_tolerance: Duration,
 }
 
 struct ComparisonResult {
@@ -89,6 +93,9 @@ struct TreeState {
 struct CompareState {
     generation: AtomicU64,
     cancel: Mutex<Option<Arc<AtomicBool>>>,
+    /// Settings of the loaded profile, applied on top of the saved config
+    /// for each comparison and never written back to it
+    profile: Mutex<Option<ProfileSettings>>,
 }
 
 enum TextSide {
@@ -119,10 +126,10 @@ impl FilterFlags {
     fn should_show(&self, status: DiffStatus, name: &str) -> bool {
         let status_match = match status {
             DiffStatus::Same => self.show_identical,
-            DiffStatus::Different => self.show_different,
+            DiffStatus::Different | DiffStatus::TypeConflict => self.show_different,
             DiffStatus::OrphanLeft => self.show_left_only,
             DiffStatus::OrphanRight => self.show_right_only,
-            DiffStatus::Unchecked => true,
+            DiffStatus::Unchecked | DiffStatus::Ignored => true,
         };
 
         let search_match =
@@ -166,6 +173,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let compare_state = Arc::new(CompareState {
         generation: AtomicU64::new(0),
         cancel: Mutex::new(None),
+        profile: Mutex::new(None),
     });
 
     // Set up callbacks
@@ -185,6 +193,10 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     }
 
                     ui.set_left_path(path.to_string_lossy().to_string().into());
+                    // Another folder is no longer the loaded profile
+                    if let Ok(mut active) = compare_state.profile.lock() {
+                        *active = None;
+                    }
 
                     // Auto-trigger comparison if both paths are set
                     let left_path = ui.get_left_path().to_string();
@@ -225,6 +237,10 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     }
 
                     ui.set_right_path(path.to_string_lossy().to_string().into());
+                    // Another folder is no longer the loaded profile
+                    if let Ok(mut active) = compare_state.profile.lock() {
+                        *active = None;
+                    }
 
                     // Auto-trigger comparison if both paths are set
                     let left_path = ui.get_left_path().to_string();
@@ -752,6 +768,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     ui.on_save_current_profile({
         let ui_weak = ui_weak.clone();
+        let compare_state = compare_state.clone();
         move |name| {
             if let Some(ui) = ui_weak.upgrade() {
                 let left_path = ui.get_left_path().to_string();
@@ -776,6 +793,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                             right_path: PathBuf::from(&right_path),
                             ignore_patterns: vec![],
                             last_used: now,
+                            settings: compare_state
+                                .profile
+                                .lock()
+                                .ok()
+                                .and_then(|active| active.clone())
+                                .unwrap_or_else(|| ProfileSettings::from_config(&loaded.config)),
                         };
 
                         loaded.config.profiles.push(profile);
@@ -809,6 +832,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     ui.on_load_profile({
         let ui_weak = ui_weak.clone();
+        let compare_state = compare_state.clone();
         move |index| {
             if let Some(ui) = ui_weak.upgrade() {
                 if index < 0 {
@@ -829,11 +853,16 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                             .unwrap_or(0);
                         loaded.config.profiles[idx].last_used = now;
 
-                        let profile = &loaded.config.profiles[idx];
+                        let profile = loaded.config.profiles[idx].clone();
                         let left_path = profile.left_path.to_string_lossy().to_string();
                         let right_path = profile.right_path.to_string_lossy().to_string();
                         let name = profile.name.clone();
 
+                        // The next comparisons run with the profile's settings
+                        if let Ok(mut active) = compare_state.profile.lock() {
+                            *active = Some(profile.settings);
+                        }
+
                         // Save updated last_used
                         let _ = save_config(&loaded.path, &loaded.config);
 
@@ -1039,6 +1068,34 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     });
 
+    ui.on_next_difference({
+        let ui_weak = ui_weak.clone();
+        let tree_state = tree_state.clone();
+        move || {
+            if let Some(ui) = ui_weak.upgrade() {
+                if let Ok(mut guard) = tree_state.lock() {
+                    if let Some(state) = guard.as_mut() {
+                        step_difference(&ui, state, true);
+                    }
+                }
+            }
+        }
+    });
+
+    ui.on_previous_difference({
+        let ui_weak = ui_weak.clone();
+        let tree_state = tree_state.clone();
+        move || {
+            if let Some(ui) = ui_weak.upgrade() {
+                if let Ok(mut guard) = tree_state.lock() {
+                    if let Some(state) = guard.as_mut() {
+                        step_difference(&ui, state, false);
+                    }
+                }
+            }
+        }
+    });
+
     ui.on_collapse_all({
         let ui_weak = ui_weak.clone();
         let tree_state = tree_state.clone();
@@ -1229,11 +1286,17 @@ fn spawn_comparison(
     generation: u64,
     cancel: Arc<AtomicBool>,
 ) {
+    let profile = compare_state
+        .profile
+        .lock()
+        .ok()
+        .and_then(|profile| profile.clone());
     std::thread::spawn(move || {
         let result = run_comparison(
             &left_path,
             &right_path,
             base_path.as_deref(),
+            profile.as_ref(),
             Some(cancel.as_ref()),
         );
 
@@ -1253,6 +1316,7 @@ fn spawn_comparison(
                             Rc::new(slint::VecModel::from(result.right_items)).into(),
                         );
                         ui.set_status_text(result.status.into());
+                        set_status_counts(&ui, &result.tree_state.root);
 
                         if let Ok(mut roots) = compare_roots.lock() {
                             *roots = Some(result.roots);
@@ -1472,6 +1536,7 @@ fn run_comparison(
     left: &str,
     right: &str,
     base: Option<&str>,
+    profile: Option<&ProfileSettings>,
     cancel: Option<&AtomicBool>,
 ) -> Result<ComparisonResult, AnyError> {
     let left_path = PathBuf::from(left);
@@ -1485,8 +1550,13 @@ fn run_comparison(
     }
 
     let loaded = ensure_config(false)?;
-    let config = loaded.config;
+    let mut config = loaded.config;
+    if let Some(settings) = profile {
+        settings.apply_to(&mut config);
+    }
     let verify_hashes = config.use_hash_verification;
+    let mtime_tolerance = Duration::from_secs(config.mtime_tolerance_secs);
+    let threads = config.threads;
 
     // Determine cache directory
     let cache_path = match config.cache_dir.clone() {
@@ -1494,7 +1564,7 @@ fn run_comparison(
         None => default_cache_dir(loaded.portable, &loaded.path)?,
     };
 
-    let hash_cache = HashCache::new(cache_path)?;
+    let hash_cache = HashCache::open_or_in_memory(cache_path);
     let mut scanner = FolderScanner::new(config);
 
     if left_path.is_dir() {
@@ -1512,7 +1582,12 @@ fn run_comparison(
     let right_entries = scan_source(&scanner, &right_source, cancel)?;
     info!("Found {} entries in right directory", right_entries.len());
 
-    let comparison_engine = ComparisonEngine::new(hash_cache).with_hash_verification(verify_hashes);
+    let mut comparison_engine = ComparisonEngine::new(hash_cache)
+        .with_hash_verification(verify_hashes)
+        .with_mtime_tolerance(mtime_tolerance);
+    if let Some(threads) = threads {
+        comparison_engine = comparison_engine.with_threads(threads);
+    }
 
     // Check if three-way comparison
     if let Some(base_str) = base {
@@ -1540,30 +1615,16 @@ fn run_comparison(
         )?;
         comparison_engine.persist_cache()?;
 
-        // Count statuses
-        let mut all_same = 0;
-        let mut left_changed = 0;
-        let mut right_changed = 0;
-        let mut both_changed = 0;
-
-        for node in &three_way_nodes {
-            match node.status {
-                ThreeWayDiffStatus::AllSame => all_same += 1,
-                ThreeWayDiffStatus::LeftChanged => left_changed += 1,
-                ThreeWayDiffStatus::RightChanged => right_changed += 1,
-                ThreeWayDiffStatus::BothChanged | ThreeWayDiffStatus::BothAdded => {
-                    both_changed += 1
-                }
-                _ => {}
-            }
-        }
-
+        let summary = summarize_three_way(&three_way_nodes);
         let tree_state = build_tree_state_from_three_way(three_way_nodes);
         let (left_items, right_items) = flatten_tree(&tree_state.root, &tree_state.expanded);
 
         let status = format!(
             "Three-way | Same: {} | Left changed: {} | Right changed: {} | Both changed: {}",
-            all_same, left_changed, right_changed, both_changed
+            summary.all_same,
+            summary.left_changed,
+            summary.right_changed,
+            summary.both_changed + summary.both_added
         );
 
         return Ok(ComparisonResult {
@@ -1575,6 +1636,9 @@ fn run_comparison(
                 right_root: right_source.root().to_path_buf(),
                 left_is_vfs: left_source.vfs().is_some(),
                 right_is_vfs: right_source.vfs().is_some(),
+                left_path: left_path.clone(),
+                right_path: right_path.clone(),
+                mtime_tolerance,
             },
             tree_state,
         });
@@ -1593,31 +1657,17 @@ fn run_comparison(
     )?;
     comparison_engine.persist_cache()?;
 
-    let mut same_count = 0;
-    let mut different_count = 0;
-    let mut orphan_left_count = 0;
-    let mut orphan_right_count = 0;
-
-    for node in &diff_nodes {
-        match node.status {
-            DiffStatus::Same => same_count += 1,
-            DiffStatus::Different => different_count += 1,
-            DiffStatus::OrphanLeft => orphan_left_count += 1,
-            DiffStatus::OrphanRight => orphan_right_count += 1,
-            DiffStatus::Unchecked => {}
-        }
-    }
-
+    let summary = summarize(&diff_nodes);
     let tree_state = build_tree_state(diff_nodes);
     let (left_items, right_items) = flatten_tree(&tree_state.root, &tree_state.expanded);
 
     let status = format!(
         "Total: {} | Same: {} | Different: {} | Left only: {} | Right only: {}",
         left_items.len().max(right_items.len()),
-        same_count,
-        different_count,
-        orphan_left_count,
-        orphan_right_count
+        summary.statuses.same,
+        summary.changed(),
+        summary.statuses.orphan_left,
+        summary.statuses.orphan_right
     );
 
     Ok(ComparisonResult {
@@ -1629,6 +1679,9 @@ fn run_comparison(
             right_root: right_source.root().to_path_buf(),
             left_is_vfs: left_source.vfs().is_some(),
             right_is_vfs: right_source.vfs().is_some(),
+            left_path,
+            right_path,
+            mtime_tolerance,
         },
         tree_state,
     })
@@ -1645,10 +1698,14 @@ fn run_text_diff(left: &str, right: &str) -> Result<Vec<RawTextDiffLine>, AnyErr
         return Err(format!("Right path is not a file: {}", right).into());
     }
 
-    let left_content = std::fs::read_to_string(&left_path)?;
-    let right_content = std::fs::read_to_string(&right_path)?;
+    let left_content = read_text_file(&left_path)?;
+    let right_content = read_text_file(&right_path)?;
 
-    let engine = TextDiffEngine::new();
+    // Keep the view responsive on pathological inputs
+    let config = TextDiffConfig::builder()
+        .diff_timeout(Some(TextDiffConfig::DEFAULT_DIFF_TIMEOUT))
+        .build()?;
+    let engine = TextDiffEngine::with_config(config);
     let diff_lines = engine.compare_text_patience(&left_content, &right_content, &left_path)?;
 
     Ok(build_raw_text_lines(diff_lines))
@@ -1856,6 +1913,9 @@ fn insert_diff_node(root: &mut TreeNode, diff: DiffNode) {
         left,
         right,
         status,
+        similar_to: _,
+        reason: _,
+        ignored_by: _,
     } = diff;
 
     let components: Vec<String> = relative_path
@@ -1921,19 +1981,26 @@ fn aggregate_status(node: &mut TreeNode) -> DiffStatus {
     for child in node.children.iter_mut() {
         let status = aggregate_status(child);
         match status {
-            DiffStatus::Different | DiffStatus::OrphanLeft | DiffStatus::OrphanRight => {
+            DiffStatus::Different
+            | DiffStatus::OrphanLeft
+            | DiffStatus::OrphanRight
+            | DiffStatus::TypeConflict => {
                 has_diff = true;
             }
             DiffStatus::Unchecked => {
                 has_unchecked = true;
             }
-            DiffStatus::Same => {}
+            DiffStatus::Same | DiffStatus::Ignored => {}
         }
     }
 
     if node.is_dir {
         node.status = match node.status {
-            DiffStatus::OrphanLeft | DiffStatus::OrphanRight | DiffStatus::Different => node.status,
+            DiffStatus::OrphanLeft
+            | DiffStatus::OrphanRight
+            | DiffStatus::Different
+            | DiffStatus::TypeConflict
+            | DiffStatus::Ignored => node.status,
             DiffStatus::Same | DiffStatus::Unchecked => {
                 if has_diff {
                     DiffStatus::Different
@@ -2075,6 +2142,91 @@ fn has_visible_children(node: &TreeNode, filters: &FilterFlags) -> bool {
     false
 }
 
+/// Files that pass the current filters, in the order the tree displays them
+///
+/// Collapsed folders are included so navigation can reach into them.
+fn navigable_files<'a>(root: &'a TreeNode, filters: &FilterFlags) -> Vec<&'a TreeNode> {
+    let mut files = Vec::new();
+    collect_navigable_files(root, filters, &mut files);
+    files
+}
+
+fn collect_navigable_files<'a>(
+    node: &'a TreeNode,
+    filters: &FilterFlags,
+    files: &mut Vec<&'a TreeNode>,
+) {
+    for child in &node.children {
+        if child.is_dir {
+            collect_navigable_files(child, filters, files);
+        } else if filters.should_show(child.status, &child.name) {
+            files.push(child);
+        }
+    }
+}
+
+/// Select the next (or previous) differing file, expanding its parent folders
+fn step_difference(ui: &MainWindow, state: &mut TreeState, forward: bool) {
+    const TARGETS: [DiffStatus; 2] = [DiffStatus::Different, DiffStatus::TypeConflict];
+
+    let filters = FilterFlags::from_ui(ui);
+    let files = navigable_files(&state.root, &filters);
+    let statuses: Vec<DiffStatus> = files.iter().map(|node| node.status).collect();
+    let selected = PathBuf::from(ui.get_selected_path().to_string());
+    let current = files.iter().position(|node| node.path == selected);
+
+    let found = if forward {
+        next_with_status(&statuses, current, &TARGETS)
+    } else {
+        previous_with_status(&statuses, current, &TARGETS)
+    };
+    let Some(idx) = found else {
+        ui.set_status_text("No differences to navigate".into());
+        return;
+    };
+
+    let target = files[idx].path.clone();
+    let position = statuses[..=idx]
+        .iter()
+        .filter(|status| TARGETS.contains(status))
+        .count();
+    let total = statuses
+        .iter()
+        .filter(|status| TARGETS.contains(status))
+        .count();
+
+    for ancestor in target.ancestors().skip(1) {
+        if !ancestor.as_os_str().is_empty() {
+            state.expanded.insert(ancestor.to_path_buf());
+        }
+    }
+
+    let path = target.to_string_lossy().to_string();
+    let (left_items, right_items) = flatten_tree_filtered(&state.root, &state.expanded, &filters);
+    ui.set_left_items(Rc::new(slint::VecModel::from(left_items)).into());
+    ui.set_right_items(Rc::new(slint::VecModel::from(right_items)).into());
+    ui.set_selected_path(path.clone().into());
+    ui.set_status_text(format!("Difference {} of {}: {}", position, total, path).into());
+}
+
+/// Update the per-status badges on the filter chips from the files in the tree
+fn set_status_counts(ui: &MainWindow, root: &TreeNode) {
+    let all = FilterFlags {
+        show_identical: true,
+        show_different: true,
+        show_left_only: true,
+        show_right_only: true,
+        search_text: String::new(),
+    };
+    let counts =
+        StatusCounts::from_statuses(navigable_files(root, &all).iter().map(|node| node.status));
+
+    ui.set_count_identical(counts.same as i32);
+    ui.set_count_different((counts.different + counts.type_conflict) as i32);
+    ui.set_count_left_only(counts.orphan_left as i32);
+    ui.set_count_right_only(counts.orphan_right as i32);
+}
+
 fn copy_directory_recursive(
     ops: &FileOperations,
     source: &std::path::Path,
@@ -2140,10 +2292,10 @@ fn build_file_item(
 fn status_code(status: DiffStatus) -> i32 {
     match status {
         DiffStatus::Same => 0,
-        DiffStatus::Different => 1,
+        DiffStatus::Different | DiffStatus::TypeConflict => 1,
         DiffStatus::OrphanLeft => 2,
         DiffStatus::OrphanRight => 3,
-        DiffStatus::Unchecked => 4,
+        DiffStatus::Unchecked | DiffStatus::Ignored => 4,
     }
 }
 
@@ -2154,37 +2306,23 @@ fn status_label(status: DiffStatus) -> &'static str {
         DiffStatus::OrphanLeft => "Left",
         DiffStatus::OrphanRight => "Right",
         DiffStatus::Unchecked => "Unk",
+        DiffStatus::TypeConflict => "Type",
+        DiffStatus::Ignored => "Ign",
     }
 }
 
 fn status_color(status: DiffStatus) -> slint::Color {
     match status {
         DiffStatus::Same => slint::Color::from_rgb_u8(191, 200, 211),
-        DiffStatus::Different => slint::Color::from_rgb_u8(224, 90, 90),
+        DiffStatus::Different | DiffStatus::TypeConflict => slint::Color::from_rgb_u8(224, 90, 90),
         DiffStatus::OrphanLeft => slint::Color::from_rgb_u8(240, 181, 77),
         DiffStatus::OrphanRight => slint::Color::from_rgb_u8(91, 133, 221),
-        DiffStatus::Unchecked => slint::Color::from_rgb_u8(152, 163, 175),
+        DiffStatus::Unchecked | DiffStatus::Ignored => slint::Color::from_rgb_u8(152, 163, 175),
     }
 }
 
 fn is_probably_text_file(path: &std::path::Path) -> bool {
-    let mut file = match std::fs::File::open(path) {
-        Ok(file) => file,
-        Err(_) => return false,
-    };
-
-    let mut buffer = [0u8; 8192];
-    let read = match file.read(&mut buffer) {
-        Ok(read) => read,
-        Err(_) => return false,
-    };
-
-    let slice = &buffer[..read];
-    if slice.contains(&0) {
-        return false;
-    }
-
-    std::str::from_utf8(slice).is_ok()
+    detect_file_content_kind(path).is_ok_and(|kind| kind.is_text())
 }
 
 fn build_raw_text_lines(lines: Vec<DiffLine>) -> Vec<RawTextDiffLine> {
@@ -2289,130 +2427,106 @@ fn execute_sync_operation(
     dry_run: bool,
     use_trash: bool,
 ) -> Result<String, String> {
-    use rcompare_common::DiffStatus;
-
-    let ops = FileOperations::new(dry_run, use_trash);
-    let mut copied = 0;
-    let mut errors = 0;
-
-    // Collect files to sync based on mode
-    fn collect_files(
-        node: &TreeNode,
-        left_root: &PathBuf,
-        right_root: &PathBuf,
-        sync_mode: i32,
-        ops: &FileOperations,
-        copied: &mut usize,
-        errors: &mut usize,
-    ) {
+    // Flatten the file nodes back into comparison order (parents before children)
+    fn collect_files(node: &TreeNode, diffs: &mut Vec<DiffNode>) {
         if !node.is_dir {
-            match (sync_mode, node.status) {
-                // Left to Right: copy left orphans and different files (prefer left)
-                (0, DiffStatus::OrphanLeft) | (0, DiffStatus::Different) => {
-                    if let Some(ref left) = node.left {
-                        let src = left_root.join(&left.path);
-                        let dest = right_root.join(&left.path);
-                        if ops.copy_file(&src, &dest).is_ok() {
-                            *copied += 1;
-                        } else {
-                            *errors += 1;
-                        }
-                    }
-                }
-                // Right to Left: copy right orphans and different files (prefer right)
-                (1, DiffStatus::OrphanRight) | (1, DiffStatus::Different) => {
-                    if let Some(ref right) = node.right {
-                        let src = right_root.join(&right.path);
-                        let dest = left_root.join(&right.path);
-                        if ops.copy_file(&src, &dest).is_ok() {
-                            *copied += 1;
-                        } else {
-                            *errors += 1;
-                        }
-                    }
-                }
-                // Bidirectional: newer wins
-                (2, DiffStatus::OrphanLeft) => {
-                    if let Some(ref left) = node.left {
-                        let src = left_root.join(&left.path);
-                        let dest = right_root.join(&left.path);
-                        if ops.copy_file(&src, &dest).is_ok() {
-                            *copied += 1;
-                        } else {
-                            *errors += 1;
-                        }
-                    }
-                }
-                (2, DiffStatus::OrphanRight) => {
-                    if let Some(ref right) = node.right {
-                        let src = right_root.join(&right.path);
-                        let dest = left_root.join(&right.path);
-                        if ops.copy_file(&src, &dest).is_ok() {
-                            *copied += 1;
-                        } else {
-                            *errors += 1;
-                        }
-                    }
-                }
-                (2, DiffStatus::Different) => {
-                    // Compare timestamps and copy newer
-                    if let (Some(ref left), Some(ref right)) = (&node.left, &node.right) {
-                        if left.modified > right.modified {
-                            let src = left_root.join(&left.path);
-                            let dest = right_root.join(&left.path);
-                            if ops.copy_file(&src, &dest).is_ok() {
-                                *copied += 1;
-                            } else {
-                                *errors += 1;
-                            }
-                        } else if right.modified > left.modified {
-                            let src = right_root.join(&right.path);
-                            let dest = left_root.join(&right.path);
-                            if ops.copy_file(&src, &dest).is_ok() {
-                                *copied += 1;
-                            } else {
-                                *errors += 1;
-                            }
-                        }
-                    }
-                }
-                _ => {}
-            }
+            diffs.push(DiffNode {
+                relative_path: node.path.clone(),
+                left: node.left.clone(),
+                right: node.right.clone(),
+                status: node.status,
+                similar_to: None,
+                reason: None,
+                ignored_by: None,
+            });
         }
-
-        // Recurse into children
         for child in &node.children {
-            collect_files(child, left_root, right_root, sync_mode, ops, copied, errors);
+            collect_files(child, diffs);
         }
     }
 
-    collect_files(
-        tree,
-        &roots.left_root,
-        &roots.right_root,
-        sync_mode,
-        &ops,
-        &mut copied,
-        &mut errors,
-    );
-
-    let mode_str = match sync_mode {
-        0 => "Left → Right",
-        1 => "Right → Left",
-        _ => "Bidirectional",
+    let mode = match sync_mode {
+        0 => SyncMode::LeftToRight,
+        1 => SyncMode::RightToLeft,
+        _ => SyncMode::UpdateNewer,
     };
 
-    if dry_run {
-        Ok(format!(
-            "Sync preview ({}): {} files would be copied, {} errors",
-            mode_str, copied, errors
+    let mut diffs = Vec::new();
+    collect_files(tree, &mut diffs);
+
+    // Archives are written through their VFS; local sides then become VFS
+    // roots too, so every plan path is relative
+    let vfs_sides = if roots.left_is_vfs || roots.right_is_vfs {
+        Some((
+            open_sync_side(&roots.left_path, roots.left_is_vfs)?,
+            open_sync_side(&roots.right_path, roots.right_is_vfs)?,
         ))
     } else {
-        Ok(format!(
-            "Sync complete ({}): {} files copied, {} errors",
-            mode_str, copied, errors
-        ))
+        None
+    };
+    let planner = match vfs_sides {
+        Some(_) => SyncPlanner::new("", ""),
+        None => SyncPlanner::new(&roots.left_root, &roots.right_root),
+    };
+    let plan = planner
+        .with_mtime_tolerance(roots.mtime_tolerance)
+        .plan(&diffs, mode);
+    let conflicts = plan
+        .iter()
+        .filter(|action| action.kind == SyncActionKind::Conflict)
+        .count();
+
+    if dry_run {
+        // Still reject read-only targets in a preview
+        if let Some((left, right)) = &vfs_sides {
+            execute_plan_with_vfs(&plan, left.as_ref(), right.as_ref(), true)
+                .map_err(|e| e.to_string())?;
+        }
+        let count = |kind: SyncActionKind| plan.iter().filter(|a| a.kind == kind).count();
+        let bytes: u64 = plan
+            .iter()
+            .filter(|a| {
+                matches!(
+                    a.kind,
+                    SyncActionKind::CopyLeftToRight | SyncActionKind::CopyRightToLeft
+                )
+            })
+            .map(|a| a.size)
+            .sum();
+        return Ok(format!(
+            "Sync preview ({}): {} files → right, {} files → left, {} to delete, {} conflicts ({} bytes to copy)",
+            mode.label(),
+            count(SyncActionKind::CopyLeftToRight),
+            count(SyncActionKind::CopyRightToLeft),
+            count(SyncActionKind::Delete),
+            conflicts,
+            bytes
+        ));
+    }
+
+    let summary = match &vfs_sides {
+        Some((left, right)) => execute_plan_with_vfs(&plan, left.as_ref(), right.as_ref(), false)
+            .map_err(|e| e.to_string())?,
+        None => execute_plan(&plan, &FileOperations::new(false, use_trash)),
+    };
+
+    Ok(format!(
+        "Sync complete ({}): {} files copied, {} conflicts skipped, {} errors",
+        mode.label(),
+        summary.copied,
+        conflicts,
+        summary.errors.len()
+    ))
+}
+
+/// Open one side of a sync: archives through their writable backend, local
+/// directories as-is
+fn open_sync_side(path: &std::path::Path, is_vfs: bool) -> Result<Box<dyn Vfs>, String> {
+    if !is_vfs {
+        return Ok(Box::new(vfs::LocalVfs::new(path.to_path_buf())));
     }
+    vfs::open_writable(path, &VfsCredentials::default())
+        .map_err(|e| format!("{}: {}", path.display(), e))
 }
 
 fn profiles_to_ui_items(profiles: &[SessionProfile]) -> Vec<ProfileItem> {
@@ -2484,7 +2598,7 @@ fn select_folder(last_dir: Option<&std::path::Path>) -> Option<std::path::PathBu
 
 fn select_archive() -> Option<std::path::PathBuf> {
     native_dialog::FileDialog::new()
-        .add_filter("Archives", &["zip", "tar", "tar.gz", "tgz", "7z"])
+        .add_filter("Archives", &["zip", "tar", "tar.gz", "tgz", "7z", "rar"])
         .show_open_single_file()
         .unwrap_or(None)
 }
@@ -2514,27 +2628,10 @@ fn build_scan_source(path: &std::path::Path) -> Result<ScanSource, AnyError> {
     }
 
     if path.is_file() {
-        return match detect_archive_kind(path) {
-            Some(ArchiveKind::Zip) => Ok(ScanSource::Vfs {
-                vfs: Box::new(ZipVfs::new(path.to_path_buf())?),
-                root: PathBuf::new(),
-            }),
-            Some(ArchiveKind::Tar) => Ok(ScanSource::Vfs {
-                vfs: Box::new(TarVfs::new(path.to_path_buf())?),
-                root: PathBuf::new(),
-            }),
-            Some(ArchiveKind::SevenZ) => Ok(ScanSource::Vfs {
-                vfs: Box::new(SevenZVfs::new(path.to_path_buf())?),
-                root: PathBuf::new(),
-            }),
-            None => Err(std::io::Error::new(
-                std::io::ErrorKind::InvalidInput,
-                format!(
-                    "Path is not a directory or supported archive (.zip, .tar, .tar.gz, .tgz, .7z): {}",
-                    path.display()
-                ),
-            ).into()),
-        };
+        return Ok(ScanSource::Vfs {
+            vfs: vfs::open(path, &VfsCredentials::default())?,
+            root: PathBuf::new(),
+        });
     }
 
     Err(std::io::Error::new(
@@ -2544,19 +2641,6 @@ fn build_scan_source(path: &std::path::Path) -> Result<ScanSource, AnyError> {
     .into())
 }
 
-fn detect_archive_kind(path: &std::path::Path) -> Option<ArchiveKind> {
-    let name = path.file_name()?.to_string_lossy().to_lowercase();
-    if name.ends_with(".zip") {
-        Some(ArchiveKind::Zip)
-    } else if name.ends_with(".tar") || name.ends_with(".tar.gz") || name.ends_with(".tgz") {
-        Some(ArchiveKind::Tar)
-    } else if name.ends_with(".7z") {
-        Some(ArchiveKind::SevenZ)
-    } else {
-        None
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;